@@ -12,6 +12,7 @@ use std::sync::{Arc, Mutex};
 use sim_core::analysis::AnalysisPlan;
 use sim_core::circuit::{AnalysisCmd, Circuit};
 use sim_core::engine::Engine;
+use sim_core::diagnostic::Diagnostic;
 use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist, parse_netlist_file};
 use sim_core::result_store::{ResultStore, RunId, RunResult};
 
@@ -51,6 +52,7 @@ struct RunTranRequest {
     tstop: Option<f64>,
     tstart: Option<f64>,
     tmax: Option<f64>,
+    uic: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -67,6 +69,20 @@ struct RunResponse {
     nodes: Vec<String>,
     solution: Vec<f64>,
     message: Option<String>,
+    /// `.csparam` 等输出参数: (名字, 取值)
+    output_params: Vec<(String, f64)>,
+    /// OP 下各 MOSFET / 二极管的工作点
+    device_op: Vec<DeviceOpResponse>,
+}
+
+#[derive(Debug, Serialize)]
+struct DeviceOpResponse {
+    name: String,
+    kind: String,
+    /// MOSFET 工作区: Cutoff / Linear / Saturation
+    region: Option<String>,
+    /// 数值量: ids/gm/gds/gmbs/vth 或 id/gd
+    values: std::collections::BTreeMap<String, f64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -85,6 +101,8 @@ struct RunsResponse {
 #[derive(Debug, Serialize)]
 struct NodesResponse {
     nodes: Vec<String>,
+    /// 层次名 (`x1.out`) -> 节点名
+    aliases: std::collections::BTreeMap<String, String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -231,8 +249,15 @@ async fn get_summary(State(state): State<ApiState>) -> Result<Json<Summary>, Api
 
 async fn get_nodes(State(state): State<ApiState>) -> Result<Json<NodesResponse>, ApiError> {
     let circuit = load_last_circuit(&state)?;
+    let aliases = circuit
+        .nodes
+        .aliases
+        .iter()
+        .map(|(alias, id)| (alias.clone(), circuit.nodes.id_to_name[id.0].clone()))
+        .collect();
     Ok(Json(NodesResponse {
         nodes: circuit.nodes.id_to_name,
+        aliases,
     }))
 }
 
@@ -245,6 +270,17 @@ fn run_to_response(run_id: RunId, run: RunResult) -> RunResponse {
         nodes: run.node_names,
         solution: run.solution,
         message: run.message,
+        output_params: run.output_params,
+        device_op: run
+            .device_op
+            .iter()
+            .map(|info| DeviceOpResponse {
+                name: info.name.clone(),
+                kind: info.kind_name().to_string(),
+                region: info.region().map(|region| format!("{:?}", region)),
+                values: info.values().into_iter().map(|(key, value)| (key.to_string(), value)).collect(),
+            })
+            .collect(),
     }
 }
 
@@ -402,6 +438,8 @@ fn select_tran_cmd(
             tstop,
             tstart,
             tmax,
+            uic: payload.uic.unwrap_or(false),
+            windows: Vec::new(),
         });
     }
 
@@ -429,21 +467,21 @@ fn load_netlist(input: NetlistInput) -> Result<sim_core::netlist::NetlistAst, Ap
         NetlistInput::Path(path) => parse_netlist_file(&path),
     };
     if !ast.errors.is_empty() {
-        let details = ast
-            .errors
-            .iter()
-            .map(|err| format!("line {}: {}", err.line, err.message))
-            .collect();
         return Err(api_error(
             StatusCode::BAD_REQUEST,
             "PARSE_ERROR",
             "netlist parse failed",
-            Some(details),
+            Some(diagnostic_details(&ast.errors)),
         ));
     }
     Ok(ast)
 }
 
+/// 每条诊断渲染为带源代码行与列标记的多行文本
+fn diagnostic_details(diagnostics: &[Diagnostic]) -> Vec<String> {
+    diagnostics.iter().map(Diagnostic::render).collect()
+}
+
 fn handle_run_op(state: &ApiState, payload: RunOpRequest) -> Result<RunResponse, ApiError> {
     let input = select_input(payload.netlist.clone(), payload.path.clone())?;
     let ast = load_netlist(input)?;
@@ -453,7 +491,7 @@ fn handle_run_op(state: &ApiState, payload: RunOpRequest) -> Result<RunResponse,
             StatusCode::BAD_REQUEST,
             "ELAB_ERROR",
             &format!("netlist elaboration failed: {}", elab.error_count),
-            None,
+            Some(diagnostic_details(&elab.errors)),
         ));
     }
 
@@ -471,7 +509,7 @@ fn handle_run_dc(state: &ApiState, payload: RunDcRequest) -> Result<RunResponse,
             StatusCode::BAD_REQUEST,
             "ELAB_ERROR",
             &format!("netlist elaboration failed: {}", elab.error_count),
-            None,
+            Some(diagnostic_details(&elab.errors)),
         ));
     }
 
@@ -490,7 +528,7 @@ fn handle_run_tran(state: &ApiState, payload: RunTranRequest) -> Result<RunRespo
             StatusCode::BAD_REQUEST,
             "ELAB_ERROR",
             &format!("netlist elaboration failed: {}", elab.error_count),
-            None,
+            Some(diagnostic_details(&elab.errors)),
         ));
     }
 
@@ -0,0 +1,395 @@
+//! ngspice 风格 `.control` ... `.endc` 脚本的解释器。
+//!
+//! 支持的命令: `run` (执行网表中的全部分析)、`op` / `dc` / `tran` / `ac` (直接执行一个分析)、
+//! `alter` (修改器件取值或参数)、`print` (打印最近一次分析的结果)、`write` (写 raw 文件)、
+//! `echo`、`reset` 与 `quit` / `exit`。其他命令给出警告后跳过。
+
+use std::path::Path;
+
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::{AnalysisCmd, Circuit};
+use sim_core::engine::Engine;
+use sim_core::netlist::{
+    build_circuit, elaborate_netlist, parse_netlist, parse_number_with_suffix, NetlistAst,
+};
+use sim_core::result_store::{AnalysisType, ResultStore, RunId, RunResult, RunStatus};
+
+/// 依次执行 deck 中的 `.control` 命令, 返回退出码 (0 成功, 1 有命令失败)
+pub fn run_script(ast: &NetlistAst, circuit: Circuit, precision: usize) -> i32 {
+    let mut interp = Interpreter {
+        engine: Engine::new_default(circuit.clone()),
+        original: circuit,
+        store: ResultStore::new(),
+        last_run: None,
+        precision,
+    };
+    let mut exit_code = 0;
+    for command in &ast.control_script {
+        match interp.execute(&command.text) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(message) => {
+                eprintln!("control line {}: {}: {}", command.line, command.text, message);
+                exit_code = 1;
+            }
+        }
+    }
+    exit_code
+}
+
+struct Interpreter {
+    engine: Engine,
+    /// `reset` 时恢复的电路
+    original: Circuit,
+    store: ResultStore,
+    last_run: Option<RunId>,
+    precision: usize,
+}
+
+/// `print` / `write` 中的一个输出向量: `v(a)`、`v(a,b)`、`vdb(a)`、`vp(a)` 或裸节点名
+struct Vector {
+    label: String,
+    func: String,
+    pos: String,
+    neg: Option<String>,
+}
+
+impl Interpreter {
+    /// 执行一条命令; 返回 Ok(false) 表示脚本结束
+    fn execute(&mut self, text: &str) -> Result<bool, String> {
+        let (command, rest) = match text.split_once(char::is_whitespace) {
+            Some((command, rest)) => (command.to_ascii_lowercase(), rest.trim()),
+            None => (text.to_ascii_lowercase(), ""),
+        };
+        match command.as_str() {
+            "run" => {
+                let mut analyses = self.engine.circuit.analysis.clone();
+                if analyses.is_empty() {
+                    analyses.push(AnalysisCmd::Op);
+                }
+                for cmd in analyses {
+                    self.run_analysis(cmd)?;
+                }
+            }
+            "op" | "dc" | "tran" | "ac" => {
+                let cmd = parse_analysis(text)?;
+                self.run_analysis(cmd)?;
+            }
+            "alter" => self.alter(rest)?,
+            "print" => self.print(rest)?,
+            "write" => self.write(rest)?,
+            "echo" => println!("{}", rest),
+            "reset" => {
+                self.engine = Engine::new_default(self.original.clone());
+                self.last_run = None;
+            }
+            "quit" | "exit" => return Ok(false),
+            _ => eprintln!("warning: unsupported control command '{}'", command),
+        }
+        Ok(true)
+    }
+
+    fn run_analysis(&mut self, cmd: AnalysisCmd) -> Result<(), String> {
+        let run_id = self.engine.run_with_store(&AnalysisPlan { cmd }, &mut self.store);
+        let run = &self.store.runs[run_id.0];
+        if !matches!(run.status, RunStatus::Converged) {
+            return Err(format!("run failed: status={:?} message={:?}", run.status, run.message));
+        }
+        println!("{:?} analysis done", run.analysis);
+        self.last_run = Some(run_id);
+        Ok(())
+    }
+
+    /// 层次名 (`x1.out`) 换成结果中的节点名 (`x1:out`)
+    fn canonicalize(&self, mut vectors: Vec<Vector>) -> Vec<Vector> {
+        let nodes = &self.engine.circuit.nodes;
+        let canonical = |name: &mut String| {
+            if let Some(id) = nodes.lookup(name) {
+                *name = nodes.id_to_name[id.0].clone();
+            }
+        };
+        for vector in &mut vectors {
+            canonical(&mut vector.pos);
+            if let Some(neg) = &mut vector.neg {
+                canonical(neg);
+            }
+        }
+        vectors
+    }
+
+    fn last_run(&self) -> Result<&RunResult, String> {
+        self.last_run
+            .map(|id| &self.store.runs[id.0])
+            .ok_or_else(|| "no analysis has been run".to_string())
+    }
+
+    /// `alter R1 2k`、`alter R1 = 2k`、`alter M1 w=2u` 或 `alter @M1[w]=2u`
+    fn alter(&mut self, rest: &str) -> Result<(), String> {
+        let (name, param, value) = if let Some(spec) = rest.strip_prefix('@') {
+            let (name, spec) = spec.split_once('[').ok_or("expected @device[param]")?;
+            let (param, value) = spec.split_once(']').ok_or("expected @device[param]")?;
+            (name.trim(), Some(param.trim()), value.trim().trim_start_matches('=').trim())
+        } else {
+            let (name, spec) = rest.split_once(char::is_whitespace).ok_or("expected a device and a value")?;
+            match spec.split_once('=') {
+                Some((param, value)) if !param.trim().is_empty() => (name, Some(param.trim()), value.trim()),
+                Some((_, value)) => (name, None, value.trim()),
+                None => (name, None, spec.trim()),
+            }
+        };
+        let number = parse_number_with_suffix(value).ok_or_else(|| format!("invalid value '{}'", value))?;
+        let inst = self
+            .engine
+            .circuit
+            .instances
+            .instances
+            .iter_mut()
+            .find(|inst| inst.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("device not found: {}", name))?;
+        match param.map(str::to_ascii_lowercase) {
+            None => inst.value = Some(number.to_string()),
+            Some(key) if matches!(key.as_str(), "resistance" | "capacitance" | "inductance" | "dc") => {
+                inst.value = Some(number.to_string())
+            }
+            Some(key) => {
+                inst.params.insert(key, number.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn print(&self, rest: &str) -> Result<(), String> {
+        let run = self.last_run()?;
+        let vectors = self.canonicalize(parse_vectors(rest, run)?);
+        let precision = self.precision;
+        match run.analysis {
+            AnalysisType::Ac => {
+                print!("{:>5} {:>16}", "index", "frequency");
+                print_labels(&vectors);
+                for (point, freq) in run.ac_frequencies.iter().enumerate() {
+                    print!("{:>5} {:>16.*e}", point, precision, freq);
+                    for vector in &vectors {
+                        let value = ac_value(run, point, vector)?;
+                        print!(" {:>16.*e}", precision, value);
+                    }
+                    println!();
+                }
+            }
+            AnalysisType::Dc | AnalysisType::Tran | AnalysisType::Pss => {
+                let (scale, solutions) = real_points(run);
+                let scale_name = match run.analysis {
+                    AnalysisType::Tran | AnalysisType::Pss => "time",
+                    _ => run.sweep_var.as_deref().unwrap_or("sweep"),
+                };
+                print!("{:>5} {:>16}", "index", scale_name);
+                print_labels(&vectors);
+                for (point, (x, solution)) in scale.iter().zip(solutions).enumerate() {
+                    print!("{:>5} {:>16.*e}", point, precision, x);
+                    for vector in &vectors {
+                        print!(" {:>16.*e}", precision, real_value(run, solution, vector)?);
+                    }
+                    println!();
+                }
+            }
+            _ => {
+                for vector in &vectors {
+                    println!("{} = {:.*e}", vector.label, precision, real_value(run, &run.solution, vector)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `write file.raw [vectors]`: 未指定文件时写 `rawspice.raw`
+    fn write(&self, rest: &str) -> Result<(), String> {
+        let run = self.last_run()?;
+        let (file, vectors) = match rest.split_once(char::is_whitespace) {
+            Some((file, vectors)) => (file, vectors),
+            None if rest.is_empty() => ("rawspice.raw", ""),
+            None => (rest, ""),
+        };
+        let vectors = self.canonicalize(parse_vectors(vectors, run)?);
+        let mut columns = Vec::new();
+        for vector in &vectors {
+            if vector.neg.is_some() || vector.func != "v" {
+                return Err(format!("write only supports node voltages, got '{}'", vector.label));
+            }
+            columns.push(node_index(run, &vector.pos).ok_or_else(|| format!("unknown node '{}'", vector.pos))?);
+        }
+        let selected = select_columns(run, &columns);
+        let path = Path::new(file);
+        let precision = self.precision;
+        let result = sim_core::raw::write_raw(&selected, path, precision);
+        result.map_err(|err| format!("failed to write output: {}", err))?;
+        println!("raw written: {}", path.display());
+        Ok(())
+    }
+}
+
+/// 控制块中的分析命令与网表语句同形, 借用网表解析得到 `AnalysisCmd`
+fn parse_analysis(text: &str) -> Result<AnalysisCmd, String> {
+    let ast = parse_netlist(&format!(".{}", text));
+    if let Some(err) = ast.errors.first() {
+        return Err(err.message.clone());
+    }
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    circuit
+        .analysis
+        .into_iter()
+        .next()
+        .ok_or_else(|| "invalid analysis command".to_string())
+}
+
+/// 按空白切分参数, 括号内的空白和逗号不切分; 空或 `all` 表示全部非地节点
+fn parse_vectors(text: &str, run: &RunResult) -> Result<Vec<Vector>, String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut depth = 0usize;
+    let mut current = String::new();
+    for ch in text.chars() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if ch.is_whitespace() && depth == 0 {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+        } else if !ch.is_whitespace() {
+            current.push(ch);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    if tokens.is_empty() || (tokens.len() == 1 && tokens[0].eq_ignore_ascii_case("all")) {
+        return Ok(run
+            .node_names
+            .iter()
+            .filter(|name| name.as_str() != "0")
+            .map(|name| Vector {
+                label: format!("v({})", name.replace(':', ".")),
+                func: "v".to_string(),
+                pos: name.clone(),
+                neg: None,
+            })
+            .collect());
+    }
+
+    tokens
+        .into_iter()
+        .map(|token| {
+            let Some((func, args)) = token.strip_suffix(')').and_then(|t| t.split_once('(')) else {
+                return Ok(Vector {
+                    label: format!("v({})", token),
+                    func: "v".to_string(),
+                    pos: token,
+                    neg: None,
+                });
+            };
+            let func = func.to_ascii_lowercase();
+            if !matches!(func.as_str(), "v" | "vm" | "vdb" | "vp") {
+                return Err(format!("unsupported vector '{}'", token));
+            }
+            let (pos, neg) = match args.split_once(',') {
+                Some((pos, neg)) => (pos.to_string(), Some(neg.to_string())),
+                None => (args.to_string(), None),
+            };
+            Ok(Vector {
+                label: token.to_ascii_lowercase(),
+                func,
+                pos,
+                neg,
+            })
+        })
+        .collect()
+}
+
+fn print_labels(vectors: &[Vector]) {
+    for vector in vectors {
+        print!(" {:>16}", vector.label);
+    }
+    println!();
+}
+
+fn node_index(run: &RunResult, name: &str) -> Option<usize> {
+    run.node_names.iter().position(|node| node.eq_ignore_ascii_case(name))
+}
+
+/// 节点电压; 地节点为 0
+fn node_voltage(run: &RunResult, solution: &[f64], name: &str) -> Result<f64, String> {
+    if name == "0" || name.eq_ignore_ascii_case("gnd") {
+        return Ok(0.0);
+    }
+    let idx = node_index(run, name).ok_or_else(|| format!("unknown node '{}'", name))?;
+    Ok(solution.get(idx).copied().unwrap_or(0.0))
+}
+
+fn real_value(run: &RunResult, solution: &[f64], vector: &Vector) -> Result<f64, String> {
+    let mut value = node_voltage(run, solution, &vector.pos)?;
+    if let Some(neg) = &vector.neg {
+        value -= node_voltage(run, solution, neg)?;
+    }
+    Ok(match vector.func.as_str() {
+        "vdb" => 20.0 * value.abs().log10(),
+        "vm" => value.abs(),
+        "vp" if value < 0.0 => 180.0,
+        "vp" => 0.0,
+        _ => value,
+    })
+}
+
+/// 取 AC 复数解计算差分, 再按函数取幅度、dB 或相位
+fn ac_value(run: &RunResult, point: usize, vector: &Vector) -> Result<f64, String> {
+    let phasor = |name: &str| -> Result<_, String> {
+        if name == "0" || name.eq_ignore_ascii_case("gnd") {
+            return Ok(Default::default());
+        }
+        let idx = node_index(run, name).ok_or_else(|| format!("unknown node '{}'", name))?;
+        Ok(run.ac_phasors[point].get(idx).copied().unwrap_or_default())
+    };
+    let mut value = phasor(&vector.pos)?;
+    if let Some(neg) = &vector.neg {
+        value -= phasor(neg)?;
+    }
+    Ok(match vector.func.as_str() {
+        "vdb" => 20.0 * value.norm().log10(),
+        "vp" => value.arg().to_degrees(),
+        _ => value.norm(),
+    })
+}
+
+/// DC 扫描与瞬态分析的 (扫描变量, 解向量) 序列
+fn real_points(run: &RunResult) -> (&[f64], &[Vec<f64>]) {
+    match run.analysis {
+        AnalysisType::Tran | AnalysisType::Pss => (&run.tran_times, &run.tran_solutions),
+        _ => (&run.sweep_values, &run.sweep_solutions),
+    }
+}
+
+/// 只保留选定节点列的结果副本; 未选择时保留全部
+fn select_columns(run: &RunResult, columns: &[usize]) -> RunResult {
+    let mut selected = run.clone();
+    if columns.is_empty() {
+        return selected;
+    }
+    let pick = |row: &Vec<f64>| columns.iter().map(|&idx| row.get(idx).copied().unwrap_or(0.0)).collect();
+    selected.node_names = columns.iter().map(|&idx| run.node_names[idx].clone()).collect();
+    // 只写选定的节点电压
+    selected.branch_names = Vec::new();
+    selected.solution = pick(&run.solution);
+    selected.sweep_solutions = run.sweep_solutions.iter().map(pick).collect();
+    selected.tran_solutions = run.tran_solutions.iter().map(pick).collect();
+    selected.ac_solutions = run
+        .ac_solutions
+        .iter()
+        .map(|row| columns.iter().map(|&idx| row.get(idx).copied().unwrap_or((0.0, 0.0))).collect())
+        .collect();
+    selected.ac_phasors = run
+        .ac_phasors
+        .iter()
+        .map(|row| columns.iter().map(|&idx| row.get(idx).copied().unwrap_or_default()).collect())
+        .collect();
+    selected
+}
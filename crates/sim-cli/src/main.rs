@@ -1,11 +1,20 @@
+mod control;
+
 use std::env;
 use std::path::{Path, PathBuf};
 
 use sim_core::analysis::AnalysisPlan;
-use sim_core::circuit::{AcSweepType, AnalysisCmd};
+use sim_core::check::check_netlist;
+use sim_core::circuit::{AcSweepType, AnalysisCmd, Corner, MonteCarloConfig, StepSpec};
+use sim_core::corner::run_corners;
 use sim_core::engine::Engine;
-use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist_file};
-use sim_core::result_store::{AnalysisType, ResultStore, RunStatus};
+use sim_core::netlist::{
+    build_circuit, elaborate_netlist, parse_netlist_file_decks, parse_netlist_file_decks_with_lib,
+    parse_number_with_suffix, NetlistAst,
+    NetlistFormat, Stmt,
+};
+use sim_core::newton::DcStrategy;
+use sim_core::result_store::{AnalysisType, ResultStore, RunResult, RunStatus};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -24,8 +33,9 @@ OPTIONS:
     -V, --version           Print version information
     -o, --output <PATH>     Write results to output file
     -f, --format <FORMAT>   Output format: psf, raw (default: psf)
-    -a, --analysis <TYPE>   Analysis type: op, dc, tran, ac (default: from netlist or op)
-    --dc-source <NAME>      DC sweep source name
+    -a, --analysis <TYPE>   Analysis type: op, dc, tran, ac (default: from netlist or op;
+                            .pac/.pnoise take precedence over the .pss they need)
+    --dc-source <NAME>      DC sweep target: device, @device[param] or .param name
     --dc-start <VALUE>      DC sweep start voltage
     --dc-stop <VALUE>       DC sweep stop voltage
     --dc-step <VALUE>       DC sweep step size
@@ -34,14 +44,30 @@ OPTIONS:
     --ac-fstart <FREQ>      AC start frequency in Hz (default: 1)
     --ac-fstop <FREQ>       AC stop frequency in Hz (default: 1e6)
     --precision <N>         Output precision (1-15 significant digits, default: 6)
+    --threads <N>           Worker threads for .step / Monte Carlo points and AC frequencies
+                            (default: 1; 0 = all cores)
+    --option <KEY=VALUE>    Set a .options value, overriding the netlist (repeatable;
+                            e.g. reltol=1e-3, abstol, vntol, itl1, itl4, gmin, trtol)
+    -I, --include-dir <DIR> Add a directory to the .include search path (repeatable;
+                            MYSPICE_INCLUDE_PATH is searched after these)
+    --check                 Only run netlist design-rule checks (floating nodes,
+                            zero-valued R/C, unused models/params, ...) and exit
+    --spectre               Parse the netlist as Spectre syntax (default for .scs files)
+    --save-op <PATH>        Write the converged operating point to a file
+    --load-op <PATH>        Start operating point solves from a saved operating point
 
 EXAMPLES:
     sim-cli circuit.cir                          # Run analysis from netlist
+    sim-cli circuit.cir --check                  # Lint the netlist without simulating
+    sim-cli amp.scs                              # Spectre netlist (by extension)
     sim-cli circuit.cir -o out.psf               # Export to PSF file
     sim-cli circuit.cir -o out.raw -f raw        # Export to ngspice raw format
     sim-cli circuit.cir -a dc --dc-source V1 \
         --dc-start 0 --dc-stop 5 --dc-step 0.1   # DC sweep
     sim-cli circuit.cir -a tran                  # Transient analysis
+    sim-cli circuit.cir --option reltol=1e-3     # Override a .options value
+    sim-cli circuit.cir --save-op bias.op        # Save the operating point ...
+    sim-cli circuit.cir --load-op bias.op        # ... and reuse it as initial guess
     sim-cli circuit.cir -a ac --ac-sweep dec \
         --ac-points 10 --ac-fstart 1 --ac-fstop 1e6  # AC analysis"#
     );
@@ -73,6 +99,13 @@ fn main() {
     let mut ac_fstart: Option<f64> = None;
     let mut ac_fstop: Option<f64> = None;
     let mut precision: usize = 6;
+    let mut threads: usize = 1;
+    let mut options: Vec<(String, String)> = Vec::new();
+    let mut include_dirs: Vec<PathBuf> = Vec::new();
+    let mut check_only = false;
+    let mut save_op = None;
+    let mut load_op = None;
+    let mut spectre = false;
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -153,6 +186,57 @@ fn main() {
                     }
                 };
             }
+            "--threads" => {
+                let Some(value) = args.next() else {
+                    eprintln!("missing value for {}", arg);
+                    std::process::exit(2);
+                };
+                threads = match value.parse::<usize>() {
+                    Ok(n) => n,
+                    Err(_) => {
+                        eprintln!("threads must be a non-negative integer");
+                        std::process::exit(2);
+                    }
+                };
+            }
+            "--option" => {
+                let Some(value) = args.next() else {
+                    eprintln!("missing value for {}", arg);
+                    std::process::exit(2);
+                };
+                let Some((key, value)) = value.split_once('=') else {
+                    eprintln!("option must be given as KEY=VALUE: {}", value);
+                    std::process::exit(2);
+                };
+                options.push((key.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+            "--check" => {
+                check_only = true;
+            }
+            "--spectre" => {
+                spectre = true;
+            }
+            "--save-op" | "--load-op" => {
+                let Some(value) = args.next() else {
+                    eprintln!("missing value for {}", arg);
+                    std::process::exit(2);
+                };
+                if arg == "--save-op" {
+                    save_op = Some(PathBuf::from(value));
+                } else {
+                    load_op = Some(PathBuf::from(value));
+                }
+            }
+            "--include-dir" | "-I" => {
+                let Some(value) = args.next() else {
+                    eprintln!("missing value for {}", arg);
+                    std::process::exit(2);
+                };
+                include_dirs.push(PathBuf::from(value));
+            }
+            dir if dir.starts_with("-I") && dir.len() > 2 => {
+                include_dirs.push(PathBuf::from(&dir[2..]));
+            }
             "--ac-sweep" => {
                 let Some(value) = args.next() else {
                     eprintln!("missing value for {}", arg);
@@ -206,164 +290,417 @@ fn main() {
         std::process::exit(2);
     }
 
-    let ast = parse_netlist_file(path);
+    let format = if spectre {
+        NetlistFormat::Spectre
+    } else {
+        NetlistFormat::from_path(path)
+    };
+    let opts = RunOptions {
+        analysis,
+        dc_source,
+        dc_start,
+        dc_stop,
+        dc_step,
+        ac_sweep,
+        ac_points,
+        ac_fstart,
+        ac_fstop,
+        output_format,
+        precision,
+        threads,
+        options,
+        check_only,
+        save_op,
+        load_op,
+    };
+
+    // 一个文件可以包含多个以 .end 分隔的网表, 依次独立运行
+    let decks = parse_netlist_file_decks(path, &include_dirs, format);
+    // 工艺角切换 `.lib` 段时重新读入同一个 deck
+    let reload = |idx: usize, lib: Option<&str>| {
+        parse_netlist_file_decks_with_lib(path, &include_dirs, format, lib).swap_remove(idx)
+    };
+    if decks.len() == 1 {
+        std::process::exit(run_deck(&decks[0], output_path.as_deref(), &opts, &|lib| reload(0, lib)));
+    }
+    let mut exit_code = 0;
+    for (idx, ast) in decks.iter().enumerate() {
+        println!("=== deck {}/{}: {} ===", idx + 1, decks.len(), deck_title(ast));
+        let deck_output = output_path.as_deref().map(|path| deck_output_path(path, idx + 1));
+        exit_code = exit_code.max(run_deck(ast, deck_output.as_deref(), &opts, &|lib| reload(idx, lib)));
+        println!();
+    }
+    std::process::exit(exit_code);
+}
+
+/// 命令行给出的分析与输出设置, 对每个 deck 相同
+struct RunOptions {
+    analysis: Option<String>,
+    dc_source: Option<String>,
+    dc_start: Option<f64>,
+    dc_stop: Option<f64>,
+    dc_step: Option<f64>,
+    ac_sweep: Option<String>,
+    ac_points: Option<usize>,
+    ac_fstart: Option<f64>,
+    ac_fstop: Option<f64>,
+    output_format: OutputFormat,
+    precision: usize,
+    threads: usize,
+    /// `--option KEY=VALUE`, 覆盖网表中的 `.options`
+    options: Vec<(String, String)>,
+    check_only: bool,
+    /// `--save-op`: 运行后写出收敛的工作点
+    save_op: Option<PathBuf>,
+    /// `--load-op`: 以保存的工作点作为工作点求解的初值
+    load_op: Option<PathBuf>,
+}
+
+/// `.title`, 否则取 deck 中的第一行注释
+fn deck_title(ast: &NetlistAst) -> String {
+    if let Some(title) = &ast.title {
+        return title.clone();
+    }
+    ast.statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Comment(text) => Some(text.trim_start_matches('*').trim().to_string()),
+            _ => None,
+        })
+        .unwrap_or_else(|| "untitled".to_string())
+}
+
+/// 多 deck 时每个 deck 单独输出: `out.psf` -> `out_deck2.psf`
+fn deck_output_path(path: &Path, deck: usize) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}_deck{}.{}", stem, deck, ext.to_string_lossy()),
+        None => format!("{}_deck{}", stem, deck),
+    };
+    path.with_file_name(name)
+}
+
+/// 电流探针 (`.probe I(R1)` 或输出参数中的 `i(R1)`) 在解向量中的电流
+fn probe_currents<'a>(run: &'a RunResult, solution: &'a [f64]) -> impl Iterator<Item = (&'a str, f64)> + 'a {
+    run.branch_names
+        .iter()
+        .filter_map(|branch| branch.strip_suffix("#i"))
+        .filter_map(move |name| Some((name, run.branch_current(name, solution)?)))
+}
+
+/// 运行一个 deck, 返回退出码 (0 成功, 1 仿真失败或检查有发现, 2 网表错误)。
+/// `reload(lib)` 以指定的 `.lib` 段重新读入该 deck
+fn run_deck(
+    ast: &NetlistAst,
+    output_path: Option<&Path>,
+    opts: &RunOptions,
+    reload: &dyn Fn(Option<&str>) -> NetlistAst,
+) -> i32 {
     if !ast.errors.is_empty() {
-        eprintln!("netlist parse errors:");
         for err in &ast.errors {
-            eprintln!("  line {}: {}", err.line, err.message);
+            eprintln!("{}\n", err.render());
         }
-        std::process::exit(2);
+        eprintln!("netlist parse failed: {} error(s)", ast.errors.len());
+        return 2;
     }
 
-    let elab = elaborate_netlist(&ast);
+    let elab = elaborate_netlist(ast);
     if elab.error_count > 0 {
-        eprintln!("netlist elaboration errors: {}", elab.error_count);
-        std::process::exit(2);
+        for err in &elab.errors {
+            eprintln!("{}\n", err.render());
+        }
+        eprintln!("netlist elaboration failed: {} error(s)", elab.error_count);
+        return 2;
     }
 
-    let circuit = build_circuit(&ast, &elab);
-    let (cmd, sweep) = select_analysis(
-        &analysis,
+    if opts.check_only {
+        let findings = check_netlist(ast, &elab);
+        for finding in &findings {
+            println!("{}\n", finding.render());
+        }
+        println!("check: {} finding(s)", findings.len());
+        return if findings.is_empty() { 0 } else { 1 };
+    }
+
+    let mut circuit = build_circuit(ast, &elab);
+    circuit.options.extend(opts.options.iter().cloned());
+    // 带 `.control` 块的 deck 由脚本决定运行哪些分析, 除非命令行指定了分析类型
+    if !ast.control_script.is_empty() && opts.analysis.is_none() {
+        return control::run_script(ast, circuit, opts.precision);
+    }
+    if !circuit.corners.is_empty() && opts.analysis.is_none() {
+        return run_corner_analysis(&circuit.corners, reload, opts.precision);
+    }
+    let cmd = select_analysis(
+        &opts.analysis,
         &circuit,
-        dc_source,
-        dc_start,
-        dc_stop,
-        dc_step,
-        ac_sweep,
-        ac_points,
-        ac_fstart,
-        ac_fstop,
+        opts.dc_source.clone(),
+        opts.dc_start,
+        opts.dc_stop,
+        opts.dc_step,
+        opts.ac_sweep.clone(),
+        opts.ac_points,
+        opts.ac_fstart,
+        opts.ac_fstop,
     );
+    let output_path = output_path.map(Path::to_path_buf);
+    let (output_format, precision) = (opts.output_format, opts.precision);
 
+    let monte_carlo = circuit.monte_carlo;
+    let steps = circuit.steps.clone();
     let mut engine = Engine::new_default(circuit);
+    engine.set_threads(opts.threads);
+    if let Some(path) = &opts.load_op {
+        match engine.load_op(path) {
+            Ok(matched) => println!("operating point loaded: {} ({} values)", path.display(), matched),
+            Err(err) => {
+                eprintln!("failed to load operating point {}: {}", path.display(), err);
+                return 2;
+            }
+        }
+    }
     let mut store = ResultStore::new();
 
-    if let Some(sweep) = sweep {
-        run_dc_sweep(&mut engine, &mut store, cmd, sweep.clone(), output_path.as_deref(), output_format, precision);
-    } else {
-        let plan = AnalysisPlan { cmd };
-        let run_id = engine.run_with_store(&plan, &mut store);
-        let run = &store.runs[run_id.0];
+    if let Some(config) = monte_carlo {
+        return run_monte_carlo(&mut engine, &mut store, cmd, &config, precision);
+    }
+    if !steps.is_empty() {
+        return run_step_sweep(&mut engine, &mut store, cmd, &steps, precision);
+    }
 
-        if !matches!(run.status, RunStatus::Converged) {
-            eprintln!("run failed: status={:?} message={:?}", run.status, run.message);
-            std::process::exit(1);
+    let plan = AnalysisPlan { cmd };
+    let run_id = engine.run_with_store(&plan, &mut store);
+    let run = &store.runs[run_id.0];
+
+    if !matches!(run.status, RunStatus::Converged) {
+        eprintln!("run failed: status={:?} message={:?}", run.status, run.message);
+        return 1;
+    }
+    if let Some(path) = &opts.save_op {
+        if let Err(err) = engine.save_op(path) {
+            eprintln!("failed to save operating point: {}", err);
+            return 1;
         }
+        println!("operating point written: {}", path.display());
+    }
 
-        // Print results based on analysis type
-        match run.analysis {
-            AnalysisType::Tran => {
-                println!("tran status: {:?} steps={}", run.status, run.iterations);
-                println!("Final values:");
-                for (idx, name) in run.node_names.iter().enumerate() {
-                    let value = run.solution.get(idx).copied().unwrap_or(0.0);
-                    println!("  V({}) = {:.*e}", name, precision, value);
-                }
+    // Print results based on analysis type
+    match run.analysis {
+        AnalysisType::Pss => {
+            println!(
+                "pss status: {:?} shooting_iterations={} points={}",
+                run.status,
+                run.iterations,
+                run.tran_times.len()
+            );
+            println!("Steady state at t={:.*e}:", precision, run.tran_times.first().copied().unwrap_or(0.0));
+            for (idx, name) in run.node_names.iter().enumerate() {
+                let value = run.solution.get(idx).copied().unwrap_or(0.0);
+                println!("  V({}) = {:.*e}", name, precision, value);
+            }
+        }
+        AnalysisType::Tran => {
+            println!("tran status: {:?} steps={}", run.status, run.iterations);
+            if let Some(message) = &run.message {
+                eprintln!("warning: {}", message);
+            }
+            println!("Final values:");
+            for (idx, name) in run.node_names.iter().enumerate() {
+                let value = run.solution.get(idx).copied().unwrap_or(0.0);
+                println!("  V({}) = {:.*e}", name, precision, value);
+            }
+            for (name, value) in probe_currents(run, &run.solution) {
+                println!("  I({}) = {:.*e}", name, precision, value);
+            }
+        }
+        AnalysisType::Ac => {
+            println!("ac status: {:?} frequency_points={}", run.status, run.ac_frequencies.len());
+            // Print DC operating point
+            println!("DC Operating Point:");
+            for (idx, name) in run.node_names.iter().enumerate() {
+                let value = run.solution.get(idx).copied().unwrap_or(0.0);
+                println!("  V({}) = {:.*e}", name, precision, value);
             }
-            AnalysisType::Ac => {
-                println!("ac status: {:?} frequency_points={}", run.status, run.ac_frequencies.len());
-                // Print DC operating point
-                println!("DC Operating Point:");
-                for (idx, name) in run.node_names.iter().enumerate() {
-                    let value = run.solution.get(idx).copied().unwrap_or(0.0);
-                    println!("  V({}) = {:.*e}", name, precision, value);
+            // Print first and last frequency results
+            if !run.ac_frequencies.is_empty() {
+                println!("\nAC Results at f = {:.*e} Hz:", precision, run.ac_frequencies[0]);
+                if let Some(sol) = run.ac_solutions.first() {
+                    for (idx, name) in run.node_names.iter().enumerate() {
+                        if let Some((mag_db, phase_deg)) = sol.get(idx) {
+                            println!("  V({}) = {:.*} dB, {:.*}°", name, precision, mag_db, precision, phase_deg);
+                        }
+                    }
                 }
-                // Print first and last frequency results
-                if !run.ac_frequencies.is_empty() {
-                    println!("\nAC Results at f = {:.*e} Hz:", precision, run.ac_frequencies[0]);
-                    if let Some(sol) = run.ac_solutions.first() {
+                if run.ac_frequencies.len() > 1 {
+                    let last_idx = run.ac_frequencies.len() - 1;
+                    println!("\nAC Results at f = {:.*e} Hz:", precision, run.ac_frequencies[last_idx]);
+                    if let Some(sol) = run.ac_solutions.last() {
                         for (idx, name) in run.node_names.iter().enumerate() {
                             if let Some((mag_db, phase_deg)) = sol.get(idx) {
                                 println!("  V({}) = {:.*} dB, {:.*}°", name, precision, mag_db, precision, phase_deg);
                             }
                         }
                     }
-                    if run.ac_frequencies.len() > 1 {
-                        let last_idx = run.ac_frequencies.len() - 1;
-                        println!("\nAC Results at f = {:.*e} Hz:", precision, run.ac_frequencies[last_idx]);
-                        if let Some(sol) = run.ac_solutions.last() {
-                            for (idx, name) in run.node_names.iter().enumerate() {
-                                if let Some((mag_db, phase_deg)) = sol.get(idx) {
-                                    println!("  V({}) = {:.*} dB, {:.*}°", name, precision, mag_db, precision, phase_deg);
-                                }
-                            }
+                }
+            }
+        }
+        AnalysisType::Sp => {
+            println!("sp status: {:?} frequency_points={}", run.status, run.ac_frequencies.len());
+            if let Some(sparams) = &run.sparams {
+                let n = sparams.ports;
+                for (freq, matrix) in sparams.frequencies.iter().zip(&sparams.s) {
+                    print!("f={:.*e}", precision, freq);
+                    for (idx, value) in matrix.iter().enumerate() {
+                        let mag_db = 20.0 * value.norm().max(1e-30).log10();
+                        let phase_deg = value.arg().to_degrees();
+                        print!(" S{}{}={:.*}dB,{:.*}°", idx / n + 1, idx % n + 1, precision, mag_db, precision, phase_deg);
+                    }
+                    println!();
+                }
+            }
+        }
+        AnalysisType::Pac => {
+            println!("pac status: {:?} frequency_points={}", run.status, run.ac_frequencies.len());
+            if let Some(pac) = &run.pac {
+                println!("PSS fundamental f0 = {:.*e} Hz", precision, pac.fundamental);
+                for (point, freq) in pac.frequencies.iter().enumerate() {
+                    println!("\nPAC Results at f = {:.*e} Hz:", precision, freq);
+                    for &sideband in &pac.sidebands {
+                        let sideband_freq = pac.sideband_frequency(point, sideband).unwrap_or(0.0);
+                        print!("  sideband {:+} (f={:.*e})", sideband, precision, sideband_freq);
+                        for (idx, name) in run.node_names.iter().enumerate().skip(1) {
+                            let value = pac.sideband_voltage(idx, point, sideband).unwrap_or_default();
+                            print!(" |V({})|={:.*e}", name, precision, value.norm());
                         }
+                        println!();
                     }
                 }
             }
-            _ => {
-                println!("run status: {:?} iterations={}", run.status, run.iterations);
-                for (idx, name) in run.node_names.iter().enumerate() {
-                    let value = run.solution.get(idx).copied().unwrap_or(0.0);
-                    println!("V({}) = {:.*e}", name, precision, value);
+        }
+        AnalysisType::Noise | AnalysisType::Pnoise => {
+            let name = if matches!(run.analysis, AnalysisType::Pnoise) { "pnoise" } else { "noise" };
+            println!("{} status: {:?} frequency_points={}", name, run.status, run.ac_frequencies.len());
+            if let Some(noise) = &run.noise {
+                println!("Total output noise {} = {:.*e} Vrms", noise.output, precision, noise.total_output_noise().sqrt());
+                println!("Equivalent input noise ({}) = {:.*e}", noise.source, precision, noise.total_input_noise().sqrt());
+                println!("Noise contributions:");
+                for line in noise.contribution_table(precision).lines() {
+                    println!("  {}", line);
                 }
             }
         }
-
-        if let Some(path) = output_path {
-            let write_result = match output_format {
-                OutputFormat::Psf => match run.analysis {
-                    AnalysisType::Ac => {
-                        sim_core::psf::write_psf_ac(
-                            &run.ac_frequencies,
-                            &run.node_names,
-                            &run.ac_solutions,
-                            &path,
-                            precision,
-                        )
+        AnalysisType::Dc => {
+            println!("dc status: {:?} points={}", run.status, run.sweep_values.len());
+            let sweep_var = run.sweep_var.as_deref().unwrap_or("sweep");
+            for (outer_value, range) in run.sweep_curves() {
+                if let Some(outer_var) = &run.sweep_outer_var {
+                    println!("{}={:.*e}", outer_var, precision, outer_value);
+                }
+                for point in range {
+                    print!("{}={:.*e}", sweep_var, precision, run.sweep_values[point]);
+                    for (idx, name) in run.node_names.iter().enumerate() {
+                        let v = run.sweep_solutions[point].get(idx).copied().unwrap_or(0.0);
+                        print!(" V({})={:.*e}", name, precision, v);
                     }
-                    AnalysisType::Tran => {
-                        sim_core::psf::write_psf_tran(
-                            &run.tran_times,
-                            &run.node_names,
-                            &run.tran_solutions,
-                            &path,
-                            precision,
-                        )
+                    for (name, value) in probe_currents(run, &run.sweep_solutions[point]) {
+                        print!(" I({})={:.*e}", name, precision, value);
                     }
-                    _ => store.write_psf_text(run_id, &path, precision),
-                },
-                OutputFormat::Raw => match run.analysis {
-                    AnalysisType::Ac => {
-                        sim_core::raw::write_raw_ac(
-                            &run.ac_frequencies,
-                            &run.node_names,
-                            &run.ac_solutions,
-                            &path,
-                            precision,
-                        )
+                    println!();
+                }
+            }
+        }
+        _ => {
+            println!("run status: {:?} iterations={}", run.status, run.iterations);
+            if let Some(strategy) = run.dc_strategy().filter(|s| *s != DcStrategy::Newton) {
+                eprintln!("note: operating point found by {:?}", strategy);
+            }
+            for (idx, name) in run.node_names.iter().enumerate() {
+                let value = run.solution.get(idx).copied().unwrap_or(0.0);
+                println!("V({}) = {:.*e}", name, precision, value);
+            }
+            for (name, value) in probe_currents(run, &run.solution) {
+                println!("I({}) = {:.*e}", name, precision, value);
+            }
+            if !run.device_op.is_empty() {
+                println!("Device operating points:");
+                for info in &run.device_op {
+                    print!("  {} {}", info.name, info.kind_name());
+                    if let Some(region) = info.region() {
+                        print!(" region={:?}", region);
                     }
-                    AnalysisType::Tran => {
-                        sim_core::raw::write_raw_tran(
-                            &run.tran_times,
-                            &run.node_names,
-                            &run.tran_solutions,
-                            &path,
-                            precision,
-                        )
+                    for (key, value) in info.values() {
+                        print!(" {}={:.*e}", key, precision, value);
                     }
-                    _ => sim_core::raw::write_raw_op(run, &path, precision),
-                },
-            };
-            if let Err(err) = write_result {
-                eprintln!("failed to write output: {}", err);
-                std::process::exit(1);
-            }
-            let format_name = match output_format {
-                OutputFormat::Psf => "psf",
-                OutputFormat::Raw => "raw",
-            };
-            println!("{} written: {}", format_name, path.display());
+                    println!();
+                }
+            }
+        }
+    }
+
+    if !run.output_params.is_empty() {
+        println!("Output parameters:");
+        for (name, value) in &run.output_params {
+            println!("  {} = {:.*e}", name, precision, value);
+        }
+    }
+
+    if !run.measurements.is_empty() {
+        println!("Measurements:");
+        for measurement in &run.measurements {
+            match &measurement.value {
+                Ok(value) => println!("  {} = {:.*e}", measurement.name, precision, value),
+                Err(reason) => println!("  {} = failed ({})", measurement.name, reason),
+            }
         }
     }
-}
 
-#[derive(Clone)]
-struct DcSweep {
-    source: String,
-    start: f64,
-    stop: f64,
-    step: f64,
+    if let Some(path) = output_path {
+        let write_result = match output_format {
+            // S 参数总是写成 Touchstone
+            _ if matches!(run.analysis, AnalysisType::Sp) => store.write_touchstone(run_id, &path, precision),
+            OutputFormat::Psf => match run.analysis {
+                AnalysisType::Ac => {
+                    sim_core::psf::write_psf_ac(
+                        &run.ac_frequencies,
+                        &run.node_names,
+                        &run.ac_solutions,
+                        &path,
+                        precision,
+                    )
+                }
+                AnalysisType::Tran | AnalysisType::Pss => {
+                    sim_core::psf::write_psf_tran(
+                        &run.tran_times,
+                        &run.node_names,
+                        &run.tran_solutions,
+                        &path,
+                        precision,
+                    )
+                }
+                AnalysisType::Dc => sim_core::psf::write_psf_sweep(
+                    run.sweep_var.as_deref().unwrap_or("sweep"),
+                    &run.sweep_values,
+                    &run.node_names,
+                    &run.sweep_solutions,
+                    &path,
+                    precision,
+                ),
+                _ => store.write_psf_text(run_id, &path, precision),
+            },
+            OutputFormat::Raw => sim_core::raw::write_raw(run, &path, precision),
+        };
+        if let Err(err) = write_result {
+            eprintln!("failed to write output: {}", err);
+            return 1;
+        }
+        let format_name = match output_format {
+            _ if matches!(run.analysis, AnalysisType::Sp) => "touchstone",
+            OutputFormat::Psf => "psf",
+            OutputFormat::Raw => "raw",
+        };
+        println!("{} written: {}", format_name, path.display());
+    }
+    0
 }
 
 fn select_analysis(
@@ -377,122 +714,61 @@ fn select_analysis(
     ac_points: Option<usize>,
     ac_fstart: Option<f64>,
     ac_fstop: Option<f64>,
-) -> (AnalysisCmd, Option<DcSweep>) {
+) -> AnalysisCmd {
     let from_netlist = circuit.analysis.first().cloned();
     let analysis = analysis.as_deref();
 
     match analysis {
-        Some("op") => (AnalysisCmd::Op, None),
+        Some("op") => AnalysisCmd::Op,
         Some("dc") => {
-            let sweep = build_dc_sweep(dc_source, dc_start, dc_stop, dc_step)
-                .or_else(|| extract_dc_sweep(from_netlist));
-            let Some(sweep) = sweep else {
-                eprintln!("dc analysis requires source/start/stop/step or .dc in netlist");
-                std::process::exit(2);
-            };
-            (
-                AnalysisCmd::Dc {
-                    source: sweep.source.clone(),
-                    start: sweep.start,
-                    stop: sweep.stop,
-                    step: sweep.step,
-                },
-                Some(sweep),
-            )
-        }
-        Some("tran") => {
-            let cmd = match from_netlist {
-                Some(AnalysisCmd::Tran {
-                    tstep,
-                    tstop,
-                    tstart,
-                    tmax,
-                }) => AnalysisCmd::Tran {
-                    tstep,
-                    tstop,
-                    tstart,
-                    tmax,
-                },
-                _ => AnalysisCmd::Tran {
-                    tstep: 1e-6,
-                    tstop: 1e-5,
-                    tstart: 0.0,
-                    tmax: 1e-5,
-                },
-            };
-            (cmd, None)
-        }
-        Some("ac") => {
-            let cmd = build_ac_cmd(ac_sweep, ac_points, ac_fstart, ac_fstop)
-                .or_else(|| extract_ac_cmd(from_netlist.clone()))
-                .unwrap_or_else(|| AnalysisCmd::Ac {
-                    sweep_type: AcSweepType::Dec,
-                    points: 10,
-                    fstart: 1.0,
-                    fstop: 1e6,
-                });
-            (cmd, None)
+            // 命令行给出的扫描优先, 否则取网表中的 .dc (含双层、温度、参数与 data 扫描)
+            if let (Some(source), Some(start), Some(stop), Some(step)) = (dc_source, dc_start, dc_stop, dc_step) {
+                if step <= 0.0 {
+                    eprintln!("dc step must be > 0");
+                    std::process::exit(2);
+                }
+                return AnalysisCmd::Dc { source, start, stop, step };
+            }
+            match from_netlist {
+                Some(
+                    cmd @ (AnalysisCmd::Dc { .. }
+                    | AnalysisCmd::DcNested { .. }
+                    | AnalysisCmd::DcParam { .. }
+                    | AnalysisCmd::DcData { .. }),
+                ) => cmd,
+                _ => {
+                    eprintln!("dc analysis requires source/start/stop/step or .dc in netlist");
+                    std::process::exit(2);
+                }
+            }
         }
-        _ => match from_netlist {
-            Some(AnalysisCmd::Dc {
-                source,
-                start,
-                stop,
-                step,
-            }) => {
-                let sweep = DcSweep {
-                    source: source.clone(),
-                    start,
-                    stop,
-                    step,
-                };
-                (
-                    AnalysisCmd::Dc {
-                        source,
-                        start,
-                        stop,
-                        step,
-                    },
-                    Some(sweep),
-                )
-            }
-            Some(cmd) => (cmd, None),
-            None => (AnalysisCmd::Op, None),
+        Some("tran") => match from_netlist {
+            Some(cmd @ AnalysisCmd::Tran { .. }) => cmd,
+            _ => AnalysisCmd::Tran {
+                tstep: 1e-6,
+                tstop: 1e-5,
+                tstart: 0.0,
+                tmax: 1e-5,
+                uic: false,
+                windows: Vec::new(),
+            },
         },
-    }
-}
-
-fn build_dc_sweep(
-    source: Option<String>,
-    start: Option<f64>,
-    stop: Option<f64>,
-    step: Option<f64>,
-) -> Option<DcSweep> {
-    match (source, start, stop, step) {
-        (Some(source), Some(start), Some(stop), Some(step)) => Some(DcSweep {
-            source,
-            start,
-            stop,
-            step,
-        }),
-        _ => None,
-    }
-}
-
-fn extract_dc_sweep(cmd: Option<AnalysisCmd>) -> Option<DcSweep> {
-    match cmd {
-        Some(AnalysisCmd::Dc {
-            source,
-            start,
-            stop,
-            step,
-        }) => Some(DcSweep {
-            source,
-            start,
-            stop,
-            step,
-        }),
-        _ => None,
+        Some("ac") => build_ac_cmd(ac_sweep, ac_points, ac_fstart, ac_fstop)
+            .or_else(|| extract_ac_cmd(from_netlist.clone()))
+            .unwrap_or_else(|| AnalysisCmd::Ac {
+                sweep_type: AcSweepType::Dec,
+                points: 10,
+                fstart: 1.0,
+                fstop: 1e6,
+            }),
+        // .pac / .pnoise 依赖的 .pss 通常写在前面, 有周期小信号分析时优先运行它
+        _ => circuit
+            .analysis
+            .iter()
+            .find(|cmd| matches!(cmd, AnalysisCmd::Pac { .. } | AnalysisCmd::Pnoise { .. }))
+            .cloned()
+            .or(from_netlist)
+            .unwrap_or(AnalysisCmd::Op),
     }
 }
 
@@ -524,138 +800,106 @@ fn extract_ac_cmd(cmd: Option<AnalysisCmd>) -> Option<AnalysisCmd> {
     }
 }
 
-fn parse_number_with_suffix(token: &str) -> Option<f64> {
-    let lower = token.to_ascii_lowercase();
-    let trimmed = lower.trim();
-    let (num_str, multiplier) = if trimmed.ends_with("meg") {
-        (&trimmed[..trimmed.len() - 3], 1e6)
-    } else {
-        let (value_part, suffix) = trimmed.split_at(trimmed.len().saturating_sub(1));
-        match suffix {
-            "f" => (value_part, 1e-15),
-            "p" => (value_part, 1e-12),
-            "n" => (value_part, 1e-9),
-            "u" => (value_part, 1e-6),
-            "m" => (value_part, 1e-3),
-            "k" => (value_part, 1e3),
-            "g" => (value_part, 1e9),
-            "t" => (value_part, 1e12),
-            _ => (trimmed, 1.0),
+/// `.corner`: 逐个角打印状态与输出参数, 最后按输出参数汇总各角的范围与失败的角
+fn run_corner_analysis(corners: &[Corner], reload: &dyn Fn(Option<&str>) -> NetlistAst, precision: usize) -> i32 {
+    let mut store = ResultStore::new();
+    let report = run_corners(corners, reload, &mut store);
+    for run in &report.runs {
+        println!("corner {}: {}", run.corner.name, if run.passed() { "pass" } else { "fail" });
+        for (name, value) in &run.measurements {
+            match value {
+                Some(value) => println!("  {} = {:.*e}", name, precision, value),
+                None => println!("  {} = n/a", name),
+            }
+        }
+        for check in &run.checks {
+            let verdict = if check.passed() { "pass" } else { "fail" };
+            match check.value {
+                Some(value) => println!("  {} = {:.*e} ({})", check.name, precision, value, verdict),
+                None => println!("  {} = n/a ({})", check.name, verdict),
+            }
+        }
+        for failure in &run.failures {
+            eprintln!("  {}", failure);
         }
-    };
-
-    if let Ok(num) = num_str.parse::<f64>() {
-        Some(num * multiplier)
-    } else {
-        None
     }
+    for summary in report.measurements() {
+        print!("{}:", summary.name);
+        if let (Some(min), Some(max)) = (summary.min, summary.max) {
+            print!(" min={:.*e} max={:.*e}", precision, min, precision, max);
+        }
+        if !summary.failed_corners.is_empty() {
+            print!(" failed in {}", summary.failed_corners.join(", "));
+        }
+        println!();
+    }
+    let failed = report.runs.iter().filter(|run| !run.passed()).count();
+    println!("corners: {} failed: {}", report.runs.len(), failed);
+    if report.passed() { 0 } else { 1 }
 }
 
-fn run_dc_sweep(
+/// `.mc`: 逐次打印抽样值与输出参数, 最后汇总失败次数
+fn run_monte_carlo(
     engine: &mut Engine,
     store: &mut ResultStore,
     cmd: AnalysisCmd,
-    sweep: DcSweep,
-    output_path: Option<&Path>,
-    output_format: OutputFormat,
+    config: &MonteCarloConfig,
     precision: usize,
-) {
-    if sweep.step <= 0.0 {
-        eprintln!("dc step must be > 0");
-        std::process::exit(2);
-    }
-    println!(
-        "dc sweep: {} from {} to {} step {}",
-        sweep.source, sweep.start, sweep.stop, sweep.step
-    );
-
-    let mut sweep_values: Vec<f64> = Vec::new();
-    let mut sweep_results: Vec<Vec<f64>> = Vec::new();
-    let mut node_names: Vec<String> = Vec::new();
-
-    let mut value = sweep.start;
-    let mut guard = 0usize;
-    while value <= sweep.stop + sweep.step * 0.5 {
-        apply_dc_source(engine, &sweep.source, value);
-        let plan = AnalysisPlan { cmd: cmd.clone() };
-        let run_id = engine.run_with_store(&plan, store);
+) -> i32 {
+    let run_ids = engine.run_monte_carlo(&AnalysisPlan { cmd }, config, store);
+    let mut failed = 0;
+    for (index, run_id) in run_ids.iter().enumerate() {
         let run = &store.runs[run_id.0];
-        if !matches!(run.status, RunStatus::Converged) {
-            eprintln!(
-                "dc sweep failed at {}={}: status={:?} message={:?}",
-                sweep.source, value, run.status, run.message
-            );
-            std::process::exit(1);
-        }
-
-        // Capture node names from first run
-        if node_names.is_empty() {
-            node_names = run.node_names.clone();
-        }
-
-        // Collect sweep data
-        sweep_values.push(value);
-        sweep_results.push(run.solution.clone());
-
-        // Print to stdout
-        print!("{}={:.*e}", sweep.source, precision, value);
-        for (idx, name) in run.node_names.iter().enumerate() {
-            let v = run.solution.get(idx).copied().unwrap_or(0.0);
-            print!(" V({})={:.*e}", name, precision, v);
+        print!("mc run {} status: {:?}", index + 1, run.status);
+        for (name, value) in &run.sampled_params {
+            print!(" {}={:.*e}", name, precision, value);
         }
         println!();
-
-        value += sweep.step;
-        guard += 1;
-        if guard > 1_000_000 {
-            eprintln!("dc sweep aborted: too many steps");
-            std::process::exit(2);
+        if !matches!(run.status, RunStatus::Converged) {
+            failed += 1;
+            eprintln!("  message: {:?}", run.message);
         }
-    }
-
-    // Write output if requested
-    if let Some(path) = output_path {
-        let write_result = match output_format {
-            OutputFormat::Psf => sim_core::psf::write_psf_sweep(
-                &sweep.source,
-                &sweep_values,
-                &node_names,
-                &sweep_results,
-                path,
-                precision,
-            ),
-            OutputFormat::Raw => sim_core::raw::write_raw_sweep(
-                &sweep.source,
-                &sweep_values,
-                &node_names,
-                &sweep_results,
-                path,
-                precision,
-            ),
-        };
-        if let Err(err) = write_result {
-            eprintln!("failed to write output: {}", err);
-            std::process::exit(1);
+        for (name, value) in &run.output_params {
+            println!("  {} = {:.*e}", name, precision, value);
         }
-        let format_name = match output_format {
-            OutputFormat::Psf => "psf",
-            OutputFormat::Raw => "raw",
-        };
-        println!("{} written: {}", format_name, path.display());
     }
+    println!("mc runs: {} failed: {} seed: {}", run_ids.len(), failed, config.seed);
+    if failed > 0 { 1 } else { 0 }
 }
 
-fn apply_dc_source(engine: &mut Engine, source: &str, value: f64) {
-    let mut found = false;
-    for inst in &mut engine.circuit.instances.instances {
-        if inst.name.eq_ignore_ascii_case(source) {
-            inst.value = Some(value.to_string());
-            found = true;
-            break;
+/// `.step`: 逐点打印各维取值、状态与输出参数, 最后汇总失败的点数
+fn run_step_sweep(
+    engine: &mut Engine,
+    store: &mut ResultStore,
+    cmd: AnalysisCmd,
+    steps: &[StepSpec],
+    precision: usize,
+) -> i32 {
+    let index = match engine.run_step_sweep(&AnalysisPlan { cmd }, steps, store) {
+        Ok(index) => index,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            return 1;
+        }
+    };
+    let sweep = &store.step_sweeps[index];
+    let mut failed = 0;
+    for (flat, run_id) in sweep.runs.iter().enumerate() {
+        let run = &store.runs[run_id.0];
+        print!("step");
+        for (name, value) in sweep.vars.iter().zip(sweep.point(flat)) {
+            print!(" {}={:.*e}", name, precision, value);
+        }
+        println!(" status: {:?}", run.status);
+        if !matches!(run.status, RunStatus::Converged) {
+            failed += 1;
+            eprintln!("  message: {:?}", run.message);
+        }
+        for (name, value) in &run.output_params {
+            println!("  {} = {:.*e}", name, precision, value);
         }
     }
-    if !found {
-        eprintln!("dc source not found: {}", source);
-        std::process::exit(2);
-    }
+    println!("step points: {} failed: {}", sweep.runs.len(), failed);
+    if failed > 0 { 1 } else { 0 }
 }
+
@@ -2,3 +2,102 @@
 fn cli_module_placeholder() {
     assert!(true);
 }
+
+#[test]
+fn cli_runs_control_block_commands() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_control_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let netlist = dir.join("ctl.cir");
+    std::fs::write(
+        &netlist,
+        "* control\nV1 in 0 2\nR1 in out 1k\nR2 out 0 1k\n.control\nrun\nprint v(out)\n\
+         alter R2 3k\nop\nprint v(in,out)\nwrite out.raw v(out)\n.endc\n.end\n",
+    )
+    .unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_sim-cli"))
+        .arg(&netlist)
+        .current_dir(&dir)
+        .output()
+        .expect("failed to run sim-cli");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {}\nstderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("v(out) = 1.000000e0"), "stdout: {}", stdout);
+    assert!(stdout.contains("v(in,out) = 5.000000e-1"), "stdout: {}", stdout);
+    let raw = std::fs::read_to_string(dir.join("out.raw")).unwrap();
+    assert!(raw.contains("v(out)"));
+    assert!(raw.contains("1.500000e0"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cli_threads_do_not_change_step_results() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_threads_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let netlist = dir.join("step.cir");
+    std::fs::write(
+        &netlist,
+        "* step\n.param rtop=1k\nV1 in 0 1\nR1 in out rtop\nR2 out 0 1k\n.param vout='v(out)'\n\
+         .step param rtop 1k 4k 1k\n.step V1 list 1 2\n.op\n.end\n",
+    )
+    .unwrap();
+
+    let run = |threads: &str| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_sim-cli"))
+            .arg(&netlist)
+            .args(["--threads", threads])
+            .output()
+            .expect("failed to run sim-cli");
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        assert!(output.status.success(), "stdout: {}\nstderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+        // 各工作线程的 Newton 调试输出先后不定, 只比较结果报告
+        stdout.lines().filter(|line| !line.starts_with("newton[")).collect::<Vec<_>>().join("\n")
+    };
+    let serial = run("1");
+    assert!(serial.contains("vout"), "stdout: {}", serial);
+    assert_eq!(run("4"), serial);
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_sim-cli"))
+        .arg(&netlist)
+        .args(["--threads", "many"])
+        .output()
+        .expect("failed to run sim-cli");
+    assert_eq!(output.status.code(), Some(2));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cli_option_overrides_netlist_options() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_option_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let netlist = dir.join("diode.cir");
+    std::fs::write(
+        &netlist,
+        "* diode\nV1 in 0 5\nR1 in a 1k\nD1 a 0 dmod\n.model dmod d is=1e-14\n\
+         .options gminsteps=0 srcsteps=0 ptransteps=0 itl1=2\n.op\n.end\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_sim-cli"))
+            .arg(&netlist)
+            .args(args)
+            .output()
+            .expect("failed to run sim-cli")
+    };
+    // 网表中的 itl1=2 不够收敛, 命令行改为 100 后收敛
+    assert_eq!(run(&[]).status.code(), Some(1));
+    let output = run(&["--option", "ITL1=100"]);
+    assert!(output.status.success(), "stdout: {}", String::from_utf8_lossy(&output.stdout));
+    assert_eq!(run(&["--option", "itl1"]).status.code(), Some(2));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use crate::circuit::AnalysisCmd;
+use crate::netlist::parse_number_with_suffix;
 
 #[derive(Debug, Clone)]
 pub struct ConvergenceConfig {
@@ -28,6 +31,21 @@ pub struct TimeStepConfig {
     pub rel_tol: f64,
 }
 
+impl TimeStepConfig {
+    /// 步长误差估计的 (abs_tol, rel_tol): 缺省 1e-9 / 1e-3 (SPICE 的 reltol 缺省值, 与 Newton 收敛判据的
+    /// 1e-6 无关), 由 `.options vntol / reltol` 覆盖, 再乘以 `trtol` (缺省 1)
+    pub fn tolerances(options: &HashMap<String, String>) -> (f64, f64) {
+        let get = |key: &str| {
+            options
+                .get(key)
+                .and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()))
+                .filter(|v: &f64| *v > 0.0)
+        };
+        let trtol = get("trtol").unwrap_or(1.0);
+        (get("vntol").unwrap_or(1e-9) * trtol, get("reltol").unwrap_or(1e-3) * trtol)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TimeStepState {
     pub time: f64,
@@ -93,3 +111,103 @@ pub fn estimate_error_weighted(
         accept: max_ratio <= 1.0,
     }
 }
+
+/// 瞬态稳态检测: 所有节点电压与一个检测窗口之前相差不超过 `tol`, 并连续保持 `windows` 个窗口时
+/// 提前结束瞬态。`period` 为检测窗口 (如开关电源的开关周期), 为 None 时窗口为一个时间步
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SteadyStateConfig {
+    pub windows: usize,
+    pub tol: f64,
+    pub period: Option<f64>,
+}
+
+impl SteadyStateConfig {
+    /// `.options ssdetect=<N> [sstol=<V>] [ssperiod=<T>]`; 没有 ssdetect 或 N < 1 时不检测, sstol 缺省 1 µV
+    pub fn from_options(options: &HashMap<String, String>) -> Option<Self> {
+        let get = |key: &str| options.get(key).and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()));
+        let windows = get("ssdetect").filter(|n| *n >= 1.0)? as usize;
+        Some(Self {
+            windows,
+            tol: get("sstol").filter(|tol| *tol > 0.0).unwrap_or(1e-6),
+            period: get("ssperiod").filter(|period| *period > 0.0),
+        })
+    }
+}
+
+/// 按接受的时间点逐个判断是否已到达稳态
+#[derive(Debug, Clone)]
+pub struct SteadyStateDetector {
+    config: SteadyStateConfig,
+    /// 最近一个检测窗口内 (及其前一个) 接受的时间点与节点电压
+    history: Vec<(f64, Vec<f64>)>,
+    /// 逐步检测: 连续满足条件的时间步数
+    settled_steps: usize,
+    /// 按周期检测: 从何时起一直满足条件
+    settled_since: Option<f64>,
+}
+
+impl SteadyStateDetector {
+    pub fn new(config: SteadyStateConfig) -> Self {
+        Self {
+            config,
+            history: Vec::new(),
+            settled_steps: 0,
+            settled_since: None,
+        }
+    }
+
+    /// 按周期检测时的检测窗口
+    pub fn period(&self) -> Option<f64> {
+        self.config.period
+    }
+
+    /// 丢弃记录的历史, 重新开始计数
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.settled_steps = 0;
+        self.settled_since = None;
+    }
+
+    /// 记录 `time` 处接受的节点电压, 返回是否已经稳定
+    pub fn accept(&mut self, time: f64, nodes: &[f64]) -> bool {
+        let within = |earlier: &[f64]| {
+            nodes.iter().zip(earlier).all(|(now, before)| (now - before).abs() <= self.config.tol)
+        };
+        let steady = match self.config.period {
+            None => {
+                let settled = self.history.last().is_some_and(|(_, last)| within(last));
+                self.settled_steps = if settled { self.settled_steps + 1 } else { 0 };
+                self.history.clear();
+                self.settled_steps >= self.config.windows
+            }
+            Some(period) => {
+                let settled = self.value_at(time - period).is_some_and(|earlier| within(&earlier));
+                if !settled {
+                    self.settled_since = None;
+                } else if self.settled_since.is_none() {
+                    self.settled_since = Some(time);
+                }
+                // 只保留一个窗口之前的最后一个点之后的历史
+                if let Some(keep) = self.history.iter().rposition(|(t, _)| *t <= time - period) {
+                    self.history.drain(..keep);
+                }
+                self.settled_since
+                    .is_some_and(|since| time - since >= self.config.windows as f64 * period)
+            }
+        };
+        self.history.push((time, nodes.to_vec()));
+        steady
+    }
+
+    /// 历史中 `time` 处的节点电压 (线性插值); 早于最早的点时为 None
+    fn value_at(&self, time: f64) -> Option<Vec<f64>> {
+        let after = self.history.iter().position(|(t, _)| *t >= time)?;
+        let (t1, x1) = &self.history[after];
+        if after == 0 {
+            return (*t1 == time).then(|| x1.clone());
+        }
+        let (t0, x0) = &self.history[after - 1];
+        let frac = (time - t0) / (t1 - t0);
+        Some(x0.iter().zip(x1).map(|(a, b)| a + frac * (b - a)).collect())
+    }
+}
@@ -0,0 +1,795 @@
+//! Behavioral source (B element) expressions.
+//!
+//! A B source is written as `B1 n+ n- V={expr}` or `B1 n+ n- I={expr}`, where
+//! the expression may reference node voltages `V(n)` / `V(n1,n2)` and branch
+//! currents `I(Vx)` of voltage-defined elements.
+//!
+//! Expressions are parsed into a small AST. Evaluation carries the partial
+//! derivatives with respect to every referenced circuit variable alongside
+//! the value (forward-mode differentiation), so the Newton linearization
+//! uses exact derivatives of the expression rather than finite differences.
+
+use crate::netlist::parse_number_with_suffix;
+
+/// Circuit quantity referenced by a behavioral expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprVar {
+    /// Node voltage `V(node)`
+    Node(String),
+    /// Branch current `I(name)` through an element with an auxiliary current variable
+    Branch(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Const(f64),
+    Var(usize),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Parsed behavioral expression together with the variables it references.
+#[derive(Debug, Clone)]
+pub struct BehavioralExpr {
+    root: Expr,
+    /// Referenced variables, in order of first appearance. Derivatives returned
+    /// by [`BehavioralExpr::eval`] use the same indexing.
+    pub vars: Vec<ExprVar>,
+}
+
+impl BehavioralExpr {
+    /// Parse an expression. Identifiers other than `V(...)`, `I(...)`,
+    /// built-in functions and constants are rejected; parameters must be
+    /// substituted during elaboration.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser::new(text)?;
+        let root = parser.parse_all()?;
+        if let Some(name) = parser.unresolved.first() {
+            return Err(format!("unknown identifier `{}`", name));
+        }
+        Ok(Self {
+            root,
+            vars: parser.vars,
+        })
+    }
+
+    /// Node names referenced through `V(...)`, in the order they appear in `vars`.
+    pub fn node_names(&self) -> Vec<&str> {
+        self.vars
+            .iter()
+            .filter_map(|var| match var {
+                ExprVar::Node(name) => Some(name.as_str()),
+                ExprVar::Branch(_) => None,
+            })
+            .collect()
+    }
+
+    /// Evaluate the expression. `values[k]` is the current value of `vars[k]`.
+    /// Returns the value and the partial derivative with respect to each variable.
+    pub fn eval(&self, values: &[f64]) -> (f64, Vec<f64>) {
+        let n = self.vars.len();
+        let result = eval_node(&self.root, values, n);
+        (result.value, result.grad)
+    }
+
+    /// Outcome of every comparison, condition and step-like function (`u`,
+    /// `sgn`) in the expression, in tree order, including those in untaken
+    /// branches. When two solutions give different outcomes the expression
+    /// jumped between them; the transient engine uses this to locate the
+    /// switching time.
+    pub fn conditions(&self, values: &[f64]) -> Vec<bool> {
+        let mut out = Vec::new();
+        collect_conditions(&self.root, values, self.vars.len(), &mut out);
+        out
+    }
+}
+
+fn collect_conditions(expr: &Expr, values: &[f64], n: usize, out: &mut Vec<bool>) {
+    let value = |expr: &Expr| eval_node(expr, values, n).value;
+    match expr {
+        Expr::Const(_) | Expr::Var(_) => {}
+        Expr::Neg(inner) | Expr::Not(inner) => collect_conditions(inner, values, n, out),
+        Expr::Binary(op, lhs, rhs) => {
+            if matches!(op, BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge | BinOp::Eq | BinOp::Ne) {
+                out.push(value(lhs) > value(rhs));
+                out.push(value(lhs) < value(rhs));
+            }
+            collect_conditions(lhs, values, n, out);
+            collect_conditions(rhs, values, n, out);
+        }
+        Expr::Ternary(cond, a, b) => {
+            out.push(value(cond) != 0.0);
+            for expr in [cond, a, b] {
+                collect_conditions(expr, values, n, out);
+            }
+        }
+        Expr::Call(name, args) => {
+            match (name.as_str(), args.first()) {
+                ("if", Some(cond)) => out.push(value(cond) != 0.0),
+                ("u" | "sgn" | "sign", Some(arg)) => {
+                    out.push(value(arg) > 0.0);
+                    out.push(value(arg) < 0.0);
+                }
+                _ => {}
+            }
+            for arg in args {
+                collect_conditions(arg, values, n, out);
+            }
+        }
+    }
+}
+
+/// Check expression syntax only; unknown identifiers are accepted because
+/// `.param` substitution has not happened yet at parse time.
+pub fn validate_syntax(text: &str) -> Result<(), String> {
+    let mut parser = Parser::new(text)?;
+    parser.parse_all().map(|_| ())
+}
+
+/// Rewrite the references inside an expression while keeping the rest of the
+/// text intact. `map_ident` may replace bare identifiers (parameters),
+/// `map_node` renames nodes inside `V(...)` and `map_branch` renames the
+/// element inside `I(...)`. Used by elaboration for parameter substitution
+/// and subcircuit scoping.
+pub fn rewrite_references(
+    text: &str,
+    map_ident: impl Fn(&str) -> Option<String>,
+    map_node: impl Fn(&str) -> String,
+    map_branch: impl Fn(&str) -> String,
+) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch.is_ascii_digit() || (ch == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())) {
+            let end = scan_number(&chars, i);
+            out.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        if is_ident_start(ch) {
+            let end = scan_ident(&chars, i);
+            let ident: String = chars[i..end].iter().collect();
+            let mut j = end;
+            while j < chars.len() && chars[j].is_whitespace() {
+                j += 1;
+            }
+            let is_call = chars.get(j) == Some(&'(');
+            let lower = ident.to_ascii_lowercase();
+            if is_call && (lower == "v" || lower == "i") {
+                if let Some(close) = chars[j..].iter().position(|c| *c == ')') {
+                    let inner: String = chars[j + 1..j + close].iter().collect();
+                    let mapped: Vec<String> = inner
+                        .split(',')
+                        .map(|arg| {
+                            let arg = arg.trim();
+                            if lower == "v" {
+                                map_node(arg)
+                            } else {
+                                map_branch(arg)
+                            }
+                        })
+                        .collect();
+                    out.push_str(&ident);
+                    out.push('(');
+                    out.push_str(&mapped.join(","));
+                    out.push(')');
+                    i = j + close + 1;
+                    continue;
+                }
+            }
+            if !is_call && !is_constant(&lower) {
+                if let Some(replacement) = map_ident(&ident) {
+                    out.push_str(&replacement);
+                    i = end;
+                    continue;
+                }
+            }
+            out.push_str(&ident);
+            i = end;
+            continue;
+        }
+        out.push(ch);
+        i += 1;
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    /// Raw argument list of `V(...)` / `I(...)`
+    Probe(char, Vec<String>),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{}", value),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Probe(kind, args) => write!(f, "{}({})", kind, args.join(",")),
+            Token::Op(op) => write!(f, "{}", op),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn is_ident_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_'
+}
+
+fn is_constant(lower: &str) -> bool {
+    matches!(lower, "pi")
+}
+
+fn scan_ident(chars: &[char], start: usize) -> usize {
+    let mut end = start;
+    while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_' || chars[end] == '.') {
+        end += 1;
+    }
+    end
+}
+
+fn scan_number(chars: &[char], start: usize) -> usize {
+    let mut end = start;
+    while end < chars.len() {
+        let ch = chars[end];
+        // 指数符号: 1e-3 / 2.5E+6
+        let exponent_sign = (ch == '+' || ch == '-')
+            && end > start
+            && matches!(chars[end - 1], 'e' | 'E')
+            && chars[start..end - 1].iter().all(|c| c.is_ascii_digit() || *c == '.');
+        if ch.is_ascii_alphanumeric() || ch == '.' || exponent_sign {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if ch.is_ascii_digit() || (ch == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())) {
+            let end = scan_number(&chars, i);
+            let literal: String = chars[i..end].iter().collect();
+            let value = parse_number_with_suffix(&literal)
+                .or_else(|| literal.parse().ok())
+                .ok_or_else(|| format!("cannot parse number `{}`", literal))?;
+            tokens.push(Token::Number(value));
+            i = end;
+            continue;
+        }
+        if is_ident_start(ch) {
+            let end = scan_ident(&chars, i);
+            let ident: String = chars[i..end].iter().collect();
+            let mut j = end;
+            while j < chars.len() && chars[j].is_whitespace() {
+                j += 1;
+            }
+            let lower = ident.to_ascii_lowercase();
+            if (lower == "v" || lower == "i") && chars.get(j) == Some(&'(') {
+                let close = chars[j..]
+                    .iter()
+                    .position(|c| *c == ')')
+                    .ok_or_else(|| format!("missing `)` after {}(", ident))?;
+                let inner: String = chars[j + 1..j + close].iter().collect();
+                let args: Vec<String> = inner.split(',').map(|a| a.trim().to_string()).collect();
+                if args.iter().any(|a| a.is_empty()) {
+                    return Err(format!("{}() needs an argument", ident));
+                }
+                let kind = if lower == "v" { 'v' } else { 'i' };
+                tokens.push(Token::Probe(kind, args));
+                i = j + close + 1;
+                continue;
+            }
+            tokens.push(Token::Ident(ident));
+            i = end;
+            continue;
+        }
+        let next = chars.get(i + 1).copied();
+        let (op, width): (&'static str, usize) = match (ch, next) {
+            ('*', Some('*')) => ("^", 2),
+            ('<', Some('=')) => ("<=", 2),
+            ('>', Some('=')) => (">=", 2),
+            ('=', Some('=')) => ("==", 2),
+            ('!', Some('=')) => ("!=", 2),
+            ('&', Some('&')) => ("&&", 2),
+            ('|', Some('|')) => ("||", 2),
+            ('+', _) => ("+", 1),
+            ('-', _) => ("-", 1),
+            ('*', _) => ("*", 1),
+            ('/', _) => ("/", 1),
+            ('^', _) => ("^", 1),
+            ('<', _) => ("<", 1),
+            ('>', _) => (">", 1),
+            ('!', _) => ("!", 1),
+            ('?', _) => ("?", 1),
+            (':', _) => (":", 1),
+            ('(', _) => {
+                tokens.push(Token::LParen);
+                i += 1;
+                continue;
+            }
+            (')', _) => {
+                tokens.push(Token::RParen);
+                i += 1;
+                continue;
+            }
+            (',', _) => {
+                tokens.push(Token::Comma);
+                i += 1;
+                continue;
+            }
+            _ => return Err(format!("unexpected character `{}`", ch)),
+        };
+        tokens.push(Token::Op(op));
+        i += width;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    vars: Vec<ExprVar>,
+    unresolved: Vec<String>,
+}
+
+impl Parser {
+    fn new(text: &str) -> Result<Self, String> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            return Err("empty expression".to_string());
+        }
+        Ok(Self {
+            tokens,
+            pos: 0,
+            vars: Vec::new(),
+            unresolved: Vec::new(),
+        })
+    }
+
+    fn parse_all(&mut self) -> Result<Expr, String> {
+        let expr = self.parse_ternary()?;
+        if self.pos < self.tokens.len() {
+            return Err(format!("unexpected `{}` after the expression", self.tokens[self.pos]));
+        }
+        Ok(expr)
+    }
+
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        if self.tokens.get(self.pos) == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected `{}`", token))
+        }
+    }
+
+    fn parse_ternary(&mut self) -> Result<Expr, String> {
+        let cond = self.parse_binary(0)?;
+        if self.peek_op() == Some("?") {
+            self.pos += 1;
+            let a = self.parse_ternary()?;
+            self.expect(Token::Op(":"))?;
+            let b = self.parse_ternary()?;
+            return Ok(Expr::Ternary(Box::new(cond), Box::new(a), Box::new(b)));
+        }
+        Ok(cond)
+    }
+
+    /// Left-associative binary operators by precedence level; `^` is handled in `parse_power`.
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, String> {
+        const LEVELS: &[&[(&str, BinOp)]] = &[
+            &[("||", BinOp::Or)],
+            &[("&&", BinOp::And)],
+            &[
+                ("==", BinOp::Eq),
+                ("!=", BinOp::Ne),
+                ("<", BinOp::Lt),
+                ("<=", BinOp::Le),
+                (">", BinOp::Gt),
+                (">=", BinOp::Ge),
+            ],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+            &[("*", BinOp::Mul), ("/", BinOp::Div)],
+        ];
+        if level == LEVELS.len() {
+            return self.parse_unary();
+        }
+        let mut lhs = self.parse_binary(level + 1)?;
+        while let Some(op) = self.peek_op() {
+            let Some((_, bin)) = LEVELS[level].iter().find(|(sym, _)| *sym == op) else {
+                break;
+            };
+            self.pos += 1;
+            let rhs = self.parse_binary(level + 1)?;
+            lhs = Expr::Binary(*bin, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        match self.peek_op() {
+            Some("-") => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.parse_unary()?)))
+            }
+            Some("+") => {
+                self.pos += 1;
+                self.parse_unary()
+            }
+            Some("!") => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.parse_unary()?)))
+            }
+            _ => self.parse_power(),
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, String> {
+        let base = self.parse_primary()?;
+        if self.peek_op() == Some("^") {
+            self.pos += 1;
+            let exp = self.parse_unary()?;
+            return Ok(Expr::Binary(BinOp::Pow, Box::new(base), Box::new(exp)));
+        }
+        Ok(base)
+    }
+
+    fn var_index(&mut self, var: ExprVar) -> usize {
+        if let Some(idx) = self.vars.iter().position(|v| *v == var) {
+            return idx;
+        }
+        self.vars.push(var);
+        self.vars.len() - 1
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "unexpected end of expression".to_string())?;
+        self.pos += 1;
+        match token {
+            Token::Number(value) => Ok(Expr::Const(value)),
+            Token::Probe('v', args) => match args.as_slice() {
+                [node] => Ok(Expr::Var(self.var_index(ExprVar::Node(node.clone())))),
+                [pos, neg] => {
+                    let a = self.var_index(ExprVar::Node(pos.clone()));
+                    let b = self.var_index(ExprVar::Node(neg.clone()));
+                    Ok(Expr::Binary(
+                        BinOp::Sub,
+                        Box::new(Expr::Var(a)),
+                        Box::new(Expr::Var(b)),
+                    ))
+                }
+                _ => Err("V(...) takes one or two nodes".to_string()),
+            },
+            Token::Probe(_, args) => match args.as_slice() {
+                [name] => Ok(Expr::Var(self.var_index(ExprVar::Branch(name.clone())))),
+                _ => Err("I(...) takes one element name".to_string()),
+            },
+            Token::Ident(name) => {
+                if self.tokens.get(self.pos) == Some(&Token::LParen) {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if self.tokens.get(self.pos) == Some(&Token::RParen) {
+                        self.pos += 1;
+                    } else {
+                        loop {
+                            args.push(self.parse_ternary()?);
+                            match self.tokens.get(self.pos) {
+                                Some(Token::Comma) => self.pos += 1,
+                                Some(Token::RParen) => {
+                                    self.pos += 1;
+                                    break;
+                                }
+                                _ => return Err(format!("missing `)` after {}(", name)),
+                            }
+                        }
+                    }
+                    let lower = name.to_ascii_lowercase();
+                    check_function_arity(&lower, args.len())?;
+                    return Ok(Expr::Call(lower, args));
+                }
+                let lower = name.to_ascii_lowercase();
+                if lower == "pi" {
+                    return Ok(Expr::Const(std::f64::consts::PI));
+                }
+                self.unresolved.push(name);
+                Ok(Expr::Const(0.0))
+            }
+            Token::LParen => {
+                let inner = self.parse_ternary()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            other => Err(format!("unexpected `{}`", other)),
+        }
+    }
+}
+
+fn check_function_arity(name: &str, argc: usize) -> Result<(), String> {
+    let expected: &[usize] = match name {
+        "sin" | "cos" | "tan" | "asin" | "acos" | "atan" | "sinh" | "cosh" | "tanh" | "exp"
+        | "ln" | "log" | "log10" | "sqrt" | "abs" | "sgn" | "sign" | "u" | "uramp" => &[1],
+        "min" | "max" | "pow" | "pwr" | "atan2" => &[2],
+        "if" | "limit" => &[3],
+        // table(x, x1, y1, x2, y2, ...)
+        "table" if argc >= 3 && !argc.is_multiple_of(2) => return Ok(()),
+        "table" => &[3],
+        _ => return Err(format!("unknown function `{}`", name)),
+    };
+    if expected.contains(&argc) {
+        Ok(())
+    } else {
+        Err(format!("{}() does not take {} arguments", name, argc))
+    }
+}
+
+/// Value with gradient with respect to the expression variables.
+struct Dual {
+    value: f64,
+    grad: Vec<f64>,
+}
+
+impl Dual {
+    fn constant(value: f64, n: usize) -> Self {
+        Self {
+            value,
+            grad: vec![0.0; n],
+        }
+    }
+
+    /// Apply a scalar function with known derivative `dfdx` at `self.value`.
+    fn chain(self, value: f64, dfdx: f64) -> Self {
+        Self {
+            value,
+            grad: self.grad.into_iter().map(|g| g * dfdx).collect(),
+        }
+    }
+
+    fn combine(a: Dual, b: Dual, value: f64, da: f64, db: f64) -> Self {
+        Self {
+            value,
+            grad: a
+                .grad
+                .iter()
+                .zip(b.grad.iter())
+                .map(|(ga, gb)| ga * da + gb * db)
+                .collect(),
+        }
+    }
+}
+
+fn bool_value(flag: bool) -> f64 {
+    if flag {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn eval_node(expr: &Expr, values: &[f64], n: usize) -> Dual {
+    match expr {
+        Expr::Const(value) => Dual::constant(*value, n),
+        Expr::Var(idx) => {
+            let mut d = Dual::constant(values.get(*idx).copied().unwrap_or(0.0), n);
+            d.grad[*idx] = 1.0;
+            d
+        }
+        Expr::Neg(inner) => {
+            let a = eval_node(inner, values, n);
+            let value = -a.value;
+            a.chain(value, -1.0)
+        }
+        Expr::Not(inner) => {
+            let a = eval_node(inner, values, n);
+            Dual::constant(bool_value(a.value == 0.0), n)
+        }
+        Expr::Ternary(cond, a, b) => {
+            if eval_node(cond, values, n).value != 0.0 {
+                eval_node(a, values, n)
+            } else {
+                eval_node(b, values, n)
+            }
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            let a = eval_node(lhs, values, n);
+            let b = eval_node(rhs, values, n);
+            let (av, bv) = (a.value, b.value);
+            match op {
+                BinOp::Add => Dual::combine(a, b, av + bv, 1.0, 1.0),
+                BinOp::Sub => Dual::combine(a, b, av - bv, 1.0, -1.0),
+                BinOp::Mul => Dual::combine(a, b, av * bv, bv, av),
+                BinOp::Div => Dual::combine(a, b, av / bv, 1.0 / bv, -av / (bv * bv)),
+                BinOp::Pow => {
+                    let value = av.powf(bv);
+                    let da = if av == 0.0 { 0.0 } else { bv * av.powf(bv - 1.0) };
+                    let db = if av > 0.0 { value * av.ln() } else { 0.0 };
+                    Dual::combine(a, b, value, da, db)
+                }
+                BinOp::Lt => Dual::constant(bool_value(av < bv), n),
+                BinOp::Le => Dual::constant(bool_value(av <= bv), n),
+                BinOp::Gt => Dual::constant(bool_value(av > bv), n),
+                BinOp::Ge => Dual::constant(bool_value(av >= bv), n),
+                BinOp::Eq => Dual::constant(bool_value(av == bv), n),
+                BinOp::Ne => Dual::constant(bool_value(av != bv), n),
+                BinOp::And => Dual::constant(bool_value(av != 0.0 && bv != 0.0), n),
+                BinOp::Or => Dual::constant(bool_value(av != 0.0 || bv != 0.0), n),
+            }
+        }
+        Expr::Call(name, args) => eval_call(name, args, values, n),
+    }
+}
+
+fn eval_call(name: &str, args: &[Expr], values: &[f64], n: usize) -> Dual {
+    if name == "if" {
+        let cond = eval_node(&args[0], values, n);
+        return if cond.value != 0.0 {
+            eval_node(&args[1], values, n)
+        } else {
+            eval_node(&args[2], values, n)
+        };
+    }
+
+    if name == "table" {
+        return eval_table(args, values, n);
+    }
+
+    let mut evaluated: Vec<Dual> = args.iter().map(|arg| eval_node(arg, values, n)).collect();
+    if evaluated.len() == 1 {
+        let a = evaluated.pop().unwrap();
+        let x = a.value;
+        let (value, dfdx) = match name {
+            "sin" => (x.sin(), x.cos()),
+            "cos" => (x.cos(), -x.sin()),
+            "tan" => (x.tan(), 1.0 / (x.cos() * x.cos())),
+            "asin" => (x.asin(), 1.0 / (1.0 - x * x).sqrt()),
+            "acos" => (x.acos(), -1.0 / (1.0 - x * x).sqrt()),
+            "atan" => (x.atan(), 1.0 / (1.0 + x * x)),
+            "sinh" => (x.sinh(), x.cosh()),
+            "cosh" => (x.cosh(), x.sinh()),
+            "tanh" => (x.tanh(), 1.0 - x.tanh() * x.tanh()),
+            "exp" => (x.exp(), x.exp()),
+            "ln" | "log" => (x.ln(), 1.0 / x),
+            "log10" => (x.log10(), 1.0 / (x * std::f64::consts::LN_10)),
+            "sqrt" => (x.sqrt(), if x > 0.0 { 0.5 / x.sqrt() } else { 0.0 }),
+            "abs" => (x.abs(), if x < 0.0 { -1.0 } else { 1.0 }),
+            "sgn" | "sign" => (if x > 0.0 { 1.0 } else if x < 0.0 { -1.0 } else { 0.0 }, 0.0),
+            "u" => (bool_value(x > 0.0), 0.0),
+            "uramp" => (x.max(0.0), bool_value(x > 0.0)),
+            _ => (f64::NAN, 0.0),
+        };
+        return a.chain(value, dfdx);
+    }
+
+    if name == "limit" {
+        let hi = evaluated.pop().unwrap();
+        let lo = evaluated.pop().unwrap();
+        let x = evaluated.pop().unwrap();
+        return if x.value < lo.value {
+            lo
+        } else if x.value > hi.value {
+            hi
+        } else {
+            x
+        };
+    }
+
+    let b = evaluated.pop().unwrap();
+    let a = evaluated.pop().unwrap();
+    let (av, bv) = (a.value, b.value);
+    match name {
+        "min" => {
+            if av <= bv {
+                a
+            } else {
+                b
+            }
+        }
+        "max" => {
+            if av >= bv {
+                a
+            } else {
+                b
+            }
+        }
+        "pow" => {
+            let value = av.powf(bv);
+            let da = if av == 0.0 { 0.0 } else { bv * av.powf(bv - 1.0) };
+            let db = if av > 0.0 { value * av.ln() } else { 0.0 };
+            Dual::combine(a, b, value, da, db)
+        }
+        "pwr" => {
+            // Sign-preserving power: sgn(a) * |a|^b
+            let sign = if av < 0.0 { -1.0 } else { 1.0 };
+            let mag = av.abs();
+            let value = sign * mag.powf(bv);
+            let da = if mag == 0.0 { 0.0 } else { bv * mag.powf(bv - 1.0) };
+            let db = if mag > 0.0 { value * mag.ln() } else { 0.0 };
+            Dual::combine(a, b, value, da, db)
+        }
+        "atan2" => {
+            let denom = av * av + bv * bv;
+            let (da, db) = if denom > 0.0 { (bv / denom, -av / denom) } else { (0.0, 0.0) };
+            Dual::combine(a, b, av.atan2(bv), da, db)
+        }
+        _ => Dual::constant(f64::NAN, n),
+    }
+}
+
+/// Piecewise-linear lookup `table(x, x1, y1, x2, y2, ...)`. Points are sorted
+/// by x; outside the table the end values are held. The derivative is the
+/// slope of the active segment.
+fn eval_table(args: &[Expr], values: &[f64], n: usize) -> Dual {
+    let x = eval_node(&args[0], values, n);
+    let mut points: Vec<(f64, f64)> = args[1..]
+        .chunks(2)
+        .map(|pair| (eval_node(&pair[0], values, n).value, eval_node(&pair[1], values, n).value))
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let xv = x.value;
+    let first = points[0];
+    let last = points[points.len() - 1];
+    if xv <= first.0 {
+        return Dual::constant(first.1, n);
+    }
+    if xv >= last.0 {
+        return Dual::constant(last.1, n);
+    }
+    let segment = points
+        .windows(2)
+        .find(|w| xv <= w[1].0)
+        .map(|w| (w[0], w[1]))
+        .unwrap_or((first, last));
+    let ((x0, y0), (x1, y1)) = segment;
+    let slope = if x1 > x0 { (y1 - y0) / (x1 - x0) } else { 0.0 };
+    x.chain(y0 + slope * (xv - x0), slope)
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use crate::circuit::{DeviceKind, Instance};
+use crate::mna::{StampContext, StampRecord};
+use crate::netlist::parse_number_with_suffix;
+
+/// 器件旁路: 非线性半导体器件的端电压自上一次计算以来变化不超过 `tol` 时,
+/// 不再重新计算模型, 直接重放上一次的 stamp
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BypassConfig {
+    pub tol: f64,
+}
+
+impl BypassConfig {
+    /// `.options bypass [bypasstol=<V>]`; bypass=0 或未给出时不旁路, bypasstol 缺省 1 µV
+    pub fn from_options(options: &HashMap<String, String>) -> Option<Self> {
+        let get = |key: &str| options.get(key).and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()));
+        get("bypass").filter(|on| *on != 0.0)?;
+        Some(Self {
+            tol: get("bypasstol").filter(|tol| *tol >= 0.0).unwrap_or(1e-6),
+        })
+    }
+}
+
+/// 旁路统计: 重新计算与直接重放的器件 stamp 次数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BypassStats {
+    pub evaluated: usize,
+    pub bypassed: usize,
+}
+
+/// 积分公式用到的历史点数 (Gear2 为两个); 端电压在这么多个接受点内都不变时, 伴随模型与历史无关
+const HISTORY_POINTS: usize = 2;
+
+#[derive(Debug, Clone)]
+struct CachedStamp {
+    voltages: Vec<f64>,
+    gmin: f64,
+    source_scale: f64,
+    dt: f64,
+    /// 记录时所在的时间步
+    step: usize,
+    /// 记录时器件的积分历史是否已不变
+    quiet: bool,
+    record: StampRecord,
+}
+
+/// 各器件最近一次的 stamp。同一次 Newton 求解内端电压不变即可重放;
+/// 瞬态跨时间步重放还要求步长相同, 且器件在最近几个接受点上一直空闲 (积分历史不变)
+#[derive(Debug, Clone)]
+pub struct BypassCache {
+    config: BypassConfig,
+    entries: HashMap<String, CachedStamp>,
+    /// 已接受的时间步数
+    step: usize,
+    /// 各器件在上一个接受点的端电压, 及此前连续空闲的接受步数
+    accepted: HashMap<String, (Vec<f64>, usize)>,
+    pub stats: BypassStats,
+}
+
+fn is_bypassable(inst: &Instance) -> bool {
+    matches!(inst.kind, DeviceKind::D | DeviceKind::Q | DeviceKind::M | DeviceKind::J | DeviceKind::Z)
+}
+
+fn terminal_voltages(inst: &Instance, x: &[f64]) -> Vec<f64> {
+    inst.nodes.iter().map(|node| x.get(node.0).copied().unwrap_or(0.0)).collect()
+}
+
+impl BypassCache {
+    pub fn new(config: BypassConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+            step: 0,
+            accepted: HashMap::new(),
+            stats: BypassStats::default(),
+        }
+    }
+
+    fn within_tol(&self, old: &[f64], new: &[f64]) -> bool {
+        old.iter().zip(new).all(|(old, new)| (old - new).abs() <= self.config.tol)
+    }
+
+    fn is_quiet(&self, inst: &Instance) -> bool {
+        self.accepted.get(&inst.name).is_some_and(|(_, quiet)| *quiet >= HISTORY_POINTS)
+    }
+
+    /// 瞬态接受一个时间步后调用, 更新各器件的空闲步数
+    pub fn accept(&mut self, instances: &[Instance], x: &[f64]) {
+        self.step += 1;
+        for inst in instances.iter().filter(|inst| is_bypassable(inst)) {
+            let voltages = terminal_voltages(inst, x);
+            let quiet = match self.accepted.get(&inst.name) {
+                Some((last, quiet)) if self.within_tol(last, &voltages) => quiet + 1,
+                _ => 0,
+            };
+            self.accepted.insert(inst.name.clone(), (voltages, quiet));
+        }
+    }
+
+    /// 用 `stamp` 写入 `inst`, 可旁路时改为重放缓存; `dt` 为瞬态步长, 直流为 0。
+    /// 发生结电压限幅的 stamp 不缓存, 以免旁路掩盖尚未收敛的限幅
+    pub fn stamp(
+        &mut self,
+        inst: &Instance,
+        ctx: &mut StampContext,
+        x: &[f64],
+        dt: f64,
+        stamp: impl FnOnce(&mut StampContext),
+    ) {
+        if !is_bypassable(inst) {
+            stamp(ctx);
+            return;
+        }
+        let voltages = terminal_voltages(inst, x);
+        let quiet = self.is_quiet(inst);
+        if let Some(cached) = self.entries.get(&inst.name) {
+            let settled = cached.gmin == ctx.gmin
+                && cached.source_scale == ctx.source_scale
+                && cached.dt == dt
+                && (cached.step == self.step || (cached.quiet && quiet))
+                && self.within_tol(&cached.voltages, &voltages);
+            if settled {
+                ctx.replay(&cached.record);
+                self.stats.bypassed += 1;
+                return;
+            }
+        }
+
+        let limited_before = ctx.junctions.as_deref_mut().is_some_and(|junctions| std::mem::take(&mut junctions.limited));
+        ctx.record = Some(StampRecord::default());
+        stamp(ctx);
+        let record = ctx.record.take().unwrap_or_default();
+        let limited = match ctx.junctions.as_deref_mut() {
+            Some(junctions) => {
+                let limited = junctions.limited;
+                junctions.limited |= limited_before;
+                limited
+            }
+            None => false,
+        };
+        self.stats.evaluated += 1;
+        if limited {
+            self.entries.remove(&inst.name);
+        } else {
+            self.entries.insert(
+                inst.name.clone(),
+                CachedStamp {
+                    voltages,
+                    gmin: ctx.gmin,
+                    source_scale: ctx.source_scale,
+                    dt,
+                    step: self.step,
+                    quiet,
+                    record,
+                },
+            );
+        }
+    }
+}
+
+/// 有旁路缓存时经由 [`BypassCache::stamp`] 写入, 否则直接调用 `stamp`
+pub fn stamp_with_bypass(
+    cache: Option<&mut BypassCache>,
+    inst: &Instance,
+    ctx: &mut StampContext,
+    x: &[f64],
+    dt: f64,
+    stamp: impl FnOnce(&mut StampContext),
+) {
+    match cache {
+        Some(cache) => cache.stamp(inst, ctx, x, dt, stamp),
+        None => stamp(ctx),
+    }
+}
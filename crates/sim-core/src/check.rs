@@ -0,0 +1,339 @@
+//! 网表设计规则检查 (lint): 在展开之后报告不影响解析、但多半是笔误的问题,
+//! 如悬空节点、零值电阻、未使用的模型与参数等。结果以 Warning 级别的
+//! [`Diagnostic`] 返回, 与解析错误共用定位和渲染。
+
+use std::collections::{HashMap, HashSet};
+
+use crate::circuit::NodeNormalization;
+use crate::diagnostic::{codes, Diagnostic};
+use crate::netlist::{
+    extract_subckts, model_bin_base, parse_number_with_suffix, ControlKind, DeviceKind,
+    DeviceStmt, ElaboratedNetlist, NetlistAst, Stmt,
+};
+
+/// 对展开后的网表做全部检查, 结果按行号排序
+pub fn check_netlist(ast: &NetlistAst, elab: &ElaboratedNetlist) -> Vec<Diagnostic> {
+    let mut findings = Vec::new();
+    check_duplicate_instances(elab, &mut findings);
+    check_duplicate_ports(elab, &mut findings);
+    check_zero_values(elab, &mut findings);
+    check_node_connectivity(elab, &mut findings);
+    check_subckt_ports(ast, &mut findings);
+    check_unused_models(ast, &mut findings);
+    check_unused_params(ast, &mut findings);
+
+    let mut findings: Vec<Diagnostic> = findings.into_iter().map(|diag| ast.locate(diag)).collect();
+    findings.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    findings
+}
+
+fn device_warning(code: &'static str, device: &DeviceStmt, message: String) -> Diagnostic {
+    Diagnostic::warning(code, device.line, message).with_source_line(device.raw.clone())
+}
+
+fn check_duplicate_instances(elab: &ElaboratedNetlist, findings: &mut Vec<Diagnostic>) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for device in &elab.instances {
+        let key = device.name.to_ascii_lowercase();
+        match seen.get(&key) {
+            Some(first_line) => findings.push(device_warning(
+                codes::DUPLICATE_INSTANCE,
+                device,
+                format!(
+                    "duplicate instance name '{}' (first defined on line {})",
+                    device.name, first_line
+                ),
+            )),
+            None => {
+                seen.insert(key, device.line);
+            }
+        }
+    }
+}
+
+fn check_duplicate_ports(elab: &ElaboratedNetlist, findings: &mut Vec<Diagnostic>) {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    for device in elab.instances.iter().filter(|device| matches!(device.kind, DeviceKind::P)) {
+        let Some(number) = device.params.iter().find(|param| param.key.eq_ignore_ascii_case("port")) else {
+            continue;
+        };
+        match seen.get(&number.value) {
+            Some(first) => findings.push(device_warning(
+                codes::DUPLICATE_PORT,
+                device,
+                format!("{} reuses port number {} of {}", device.name, number.value, first),
+            )),
+            None => {
+                seen.insert(number.value.clone(), &device.name);
+            }
+        }
+    }
+}
+
+fn check_zero_values(elab: &ElaboratedNetlist, findings: &mut Vec<Diagnostic>) {
+    for device in &elab.instances {
+        let what = match device.kind {
+            DeviceKind::R => "resistance",
+            DeviceKind::C => "capacitance",
+            _ => continue,
+        };
+        let value = device
+            .value
+            .as_deref()
+            .and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()));
+        if value == Some(0.0) {
+            findings.push(device_warning(
+                codes::ZERO_VALUE,
+                device,
+                format!("{} has zero {}", device.name, what),
+            ));
+        }
+    }
+}
+
+/// 器件的全部连接端口, 以及其中构成直流通路 (可以把端口电位钉住) 的端口组
+fn device_terminals(device: &DeviceStmt) -> (Vec<&str>, Vec<Vec<&str>>) {
+    let nodes: Vec<&str> = device.nodes.iter().map(String::as_str).collect();
+    let mut terminals = nodes.clone();
+    let dc_path = match device.kind {
+        // 端口经参考阻抗 Z0 连通两端
+        DeviceKind::R | DeviceKind::L | DeviceKind::V | DeviceKind::D | DeviceKind::P => nodes.clone(),
+        // 漏、源、衬底通过沟道和结连通, 栅极只有电容
+        DeviceKind::M => [0, 2, 3].iter().filter_map(|&idx| nodes.get(idx).copied()).collect(),
+        // 集电极、基极、发射极通过两个 PN 结连通, 衬底只有结电容
+        DeviceKind::Q => nodes.iter().take(3).copied().collect(),
+        // 漏源经沟道连通, 栅极经两个 PN 结连通
+        DeviceKind::J | DeviceKind::Z => nodes.clone(),
+        // S 参数块的直流特性取自最低频点, 按各端口互相连通处理
+        DeviceKind::SParam => nodes.clone(),
+        DeviceKind::E | DeviceKind::H | DeviceKind::S | DeviceKind::W => nodes.iter().take(2).copied().collect(),
+        // 多导体传输线的每根导体 (含参考导体) 两端各自直通, 在下面逐对加入
+        DeviceKind::WLine => Vec::new(),
+        // 传输线在直流下两端信号导体直通 (参考导体通常接地)
+        DeviceKind::T => [0, 2].iter().filter_map(|&idx| nodes.get(idx).copied()).collect(),
+        DeviceKind::B => {
+            if device.params.iter().any(|param| param.key == "v") {
+                nodes.clone()
+            } else {
+                Vec::new()
+            }
+        }
+        // 数字器件 / DAC 的输出 (最后一个节点) 被钉在相对地的电平上
+        DeviceKind::A => nodes.last().map(|out| vec![*out, "0"]).unwrap_or_default(),
+        // 理想变压器两侧只有磁耦合, 不钉住任何端口电位
+        DeviceKind::C | DeviceKind::I | DeviceKind::G | DeviceKind::F | DeviceKind::N => Vec::new(),
+        DeviceKind::X | DeviceKind::Unknown => Vec::new(),
+    };
+    let mut dc_paths = vec![dc_path];
+    if matches!(device.kind, DeviceKind::WLine) && nodes.len().is_multiple_of(2) {
+        let half = nodes.len() / 2;
+        dc_paths.extend((0..half).map(|j| vec![nodes[j], nodes[half + j]]));
+    }
+    // 光电器件的光节点写在参数中; LED 的光输出被钉在相对地的光功率上
+    for param in device.params.iter().filter(|param| crate::netlist::is_node_param(&device.kind, &param.key)) {
+        terminals.push(param.value.as_str());
+        if param.key.eq_ignore_ascii_case("lightout") {
+            dc_paths.push(vec![param.value.as_str(), "0"]);
+        }
+    }
+    // POLY 受控源的控制节点写在系数之前
+    if let (DeviceKind::E | DeviceKind::G, Some(poly)) = (&device.kind, &device.poly) {
+        terminals.extend(poly.coeffs.iter().take(poly.degree * 2).map(String::as_str));
+    }
+    (terminals, dc_paths)
+}
+
+fn check_node_connectivity(elab: &ElaboratedNetlist, findings: &mut Vec<Diagnostic>) {
+    let normalization = NodeNormalization::default();
+    let mut order: Vec<String> = Vec::new();
+    let mut first_device: HashMap<String, &DeviceStmt> = HashMap::new();
+    let mut connections: HashMap<String, usize> = HashMap::new();
+    let mut parent: HashMap<String, String> = HashMap::new();
+
+    fn find(parent: &mut HashMap<String, String>, node: &str) -> String {
+        let next = parent.get(node).cloned().unwrap_or_else(|| node.to_string());
+        if next == node {
+            return next;
+        }
+        let root = find(parent, &next);
+        parent.insert(node.to_string(), root.clone());
+        root
+    }
+
+    for device in &elab.instances {
+        let (terminals, dc_paths) = device_terminals(device);
+        for node in terminals {
+            let node = normalization.normalize(node);
+            if !first_device.contains_key(&node) {
+                order.push(node.clone());
+                first_device.insert(node.clone(), device);
+            }
+            *connections.entry(node).or_insert(0) += 1;
+        }
+        for dc_path in dc_paths {
+            let dc_path: Vec<String> = dc_path.iter().map(|node| normalization.normalize(node)).collect();
+            for window in dc_path.windows(2) {
+                let a = find(&mut parent, &window[0]);
+                let b = find(&mut parent, &window[1]);
+                if a != b {
+                    parent.insert(a, b);
+                }
+            }
+        }
+    }
+
+    let ground = find(&mut parent, "0");
+    for node in &order {
+        if node == "0" {
+            continue;
+        }
+        let device = first_device[node];
+        if connections[node] == 1 {
+            findings.push(device_warning(
+                codes::SINGLE_CONNECTION,
+                device,
+                format!("node '{}' has only one connection ({})", node, device.name),
+            ));
+        }
+        if find(&mut parent, node) != ground {
+            findings.push(device_warning(
+                codes::FLOATING_NODE,
+                device,
+                format!("node '{}' has no DC path to ground", node),
+            ));
+        }
+    }
+}
+
+fn check_subckt_ports(ast: &NetlistAst, findings: &mut Vec<Diagnostic>) {
+    let (_, subckts, _) = extract_subckts(&ast.statements);
+    for def in subckts {
+        let used: HashSet<String> = def
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Device(device) => Some(device),
+                _ => None,
+            })
+            .flat_map(|device| device_terminals(device).0)
+            .map(str::to_ascii_lowercase)
+            .collect();
+        let source = ast.statements.iter().find_map(|stmt| match stmt {
+            Stmt::Control(ctrl) if ctrl.line == def.line && matches!(ctrl.kind, ControlKind::Subckt) => {
+                Some(ctrl.raw.clone())
+            }
+            _ => None,
+        });
+        for port in &def.ports {
+            if used.contains(&port.to_ascii_lowercase()) {
+                continue;
+            }
+            let mut diag = Diagnostic::warning(
+                codes::UNCONNECTED_PORT,
+                def.line,
+                format!("port '{}' of subckt '{}' is not connected inside it", port, def.name),
+            );
+            if let Some(source) = &source {
+                diag = diag.with_source_line(source.clone());
+            }
+            findings.push(diag);
+        }
+    }
+}
+
+fn check_unused_models(ast: &NetlistAst, findings: &mut Vec<Diagnostic>) {
+    let referenced: HashSet<String> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Device(device) => device.model.as_deref(),
+            _ => None,
+        })
+        .map(str::to_ascii_lowercase)
+        .collect();
+    for stmt in &ast.statements {
+        let Stmt::Control(ctrl) = stmt else { continue };
+        let (ControlKind::Model, Some(name)) = (&ctrl.kind, &ctrl.model_name) else {
+            continue;
+        };
+        let name = name.to_ascii_lowercase();
+        // binning 模型 `nch.1` 通过基础名 `nch` 引用
+        let used = referenced.contains(&name)
+            || model_bin_base(&name).is_some_and(|base| referenced.contains(base));
+        if !used {
+            findings.push(
+                Diagnostic::warning(codes::UNUSED_MODEL, ctrl.line, format!("model '{}' is never used", name))
+                    .with_source_line(ctrl.raw.clone()),
+            );
+        }
+    }
+}
+
+fn check_unused_params(ast: &NetlistAst, findings: &mut Vec<Diagnostic>) {
+    // 所有可能引用参数的文本: 器件取值与参数、控制语句的参数与参数值
+    let mut texts: Vec<(&str, Option<usize>)> = Vec::new();
+    for stmt in &ast.statements {
+        match stmt {
+            Stmt::Device(device) => {
+                texts.extend(device.value.as_deref().map(|v| (v, None)));
+                texts.extend(device.params.iter().map(|param| (param.value.as_str(), None)));
+                texts.extend(device.extras.iter().map(|extra| (extra.as_str(), None)));
+            }
+            Stmt::Control(ctrl) => {
+                texts.extend(ctrl.args.iter().map(|arg| (arg.as_str(), None)));
+                // 参数不算引用自身
+                let owner = matches!(ctrl.kind, ControlKind::Param).then_some(ctrl.line);
+                texts.extend(ctrl.params.iter().map(|param| (param.value.as_str(), owner)));
+            }
+            _ => {}
+        }
+    }
+
+    for stmt in &ast.statements {
+        let Stmt::Control(ctrl) = stmt else { continue };
+        if !matches!(ctrl.kind, ControlKind::Param) {
+            continue;
+        }
+        for param in &ctrl.params {
+            let used = texts.iter().any(|(text, owner)| {
+                *owner != Some(ctrl.line) && mentions_identifier(text, &param.key)
+            });
+            if !used {
+                findings.push(
+                    Diagnostic::warning(
+                        codes::UNUSED_PARAM,
+                        ctrl.line,
+                        format!("param '{}' is never used", param.key),
+                    )
+                    .with_source_line(ctrl.raw.clone()),
+                );
+            }
+        }
+    }
+}
+
+/// 表达式文本中是否出现标识符 `name` (大小写不敏感; `1k` 这类数值后缀不算)
+fn mentions_identifier(text: &str, name: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let ch = chars[idx];
+        if ch.is_ascii_alphabetic() || ch == '_' {
+            let start = idx;
+            while idx < chars.len() && (chars[idx].is_ascii_alphanumeric() || chars[idx] == '_') {
+                idx += 1;
+            }
+            let ident: String = chars[start..idx].iter().collect();
+            if ident.eq_ignore_ascii_case(name) {
+                return true;
+            }
+        } else if ch.is_ascii_digit() || ch == '.' {
+            while idx < chars.len() && (chars[idx].is_ascii_alphanumeric() || chars[idx] == '.') {
+                idx += 1;
+            }
+        } else {
+            idx += 1;
+        }
+    }
+    false
+}
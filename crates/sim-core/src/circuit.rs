@@ -9,33 +9,116 @@ pub struct ModelId(pub usize);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct InstanceId(pub usize);
 
+/// 默认视为地 (节点 0) 的节点名
+pub const DEFAULT_GROUND_ALIASES: &[&str] = &["gnd", "gnd!", "vss!"];
+
+/// 节点名规范化规则
+#[derive(Debug, Clone)]
+pub struct NodeNormalization {
+    /// 节点名大小写不敏感 (统一转小写)
+    pub case_insensitive: bool,
+    /// 与节点 0 等价的节点名; 子电路内部节点 `X1:gnd` 同样按最后一段匹配
+    pub ground_aliases: Vec<String>,
+}
+
+impl Default for NodeNormalization {
+    fn default() -> Self {
+        Self {
+            case_insensitive: true,
+            ground_aliases: DEFAULT_GROUND_ALIASES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl NodeNormalization {
+    pub fn normalize(&self, name: &str) -> String {
+        let local = name.rsplit(':').next().unwrap_or(name);
+        if local == "0"
+            || self
+                .ground_aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(local))
+        {
+            return "0".to_string();
+        }
+        if self.case_insensitive {
+            name.to_ascii_lowercase()
+        } else {
+            name.to_string()
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NodeTable {
     pub name_to_id: HashMap<String, NodeId>,
     pub id_to_name: Vec<String>,
     pub gnd_id: NodeId,
+    pub normalization: NodeNormalization,
+    /// 层次名 (`x1.out`、`x1.x2.n3`) -> 节点; 子电路内部节点 `X1:out` 与端口都会登记
+    pub aliases: HashMap<String, NodeId>,
 }
 
 impl NodeTable {
     pub fn new() -> Self {
+        Self::with_normalization(NodeNormalization::default())
+    }
+
+    pub fn with_normalization(normalization: NodeNormalization) -> Self {
         let mut table = Self {
             name_to_id: HashMap::new(),
             id_to_name: Vec::new(),
             gnd_id: NodeId(0),
+            normalization,
+            aliases: HashMap::new(),
         };
         table.ensure_node("0");
         table
     }
 
     pub fn ensure_node(&mut self, name: &str) -> NodeId {
-        if let Some(id) = self.name_to_id.get(name) {
+        let name = self.normalization.normalize(name);
+        if let Some(id) = self.name_to_id.get(&name) {
             return *id;
         }
         let id = NodeId(self.id_to_name.len());
-        self.name_to_id.insert(name.to_string(), id);
-        self.id_to_name.push(name.to_string());
+        if name.contains(':') {
+            self.aliases.insert(hierarchical_node_name(&name), id);
+        }
+        self.name_to_id.insert(name.clone(), id);
+        self.id_to_name.push(name);
         id
     }
+
+    /// 登记节点的层次名, 按节点名的大小写规则规范化
+    pub fn add_alias(&mut self, alias: &str, id: NodeId) {
+        let alias = self.normalization.normalize(alias);
+        if !self.name_to_id.contains_key(&alias) {
+            self.aliases.insert(alias, id);
+        }
+    }
+
+    /// 按规范化后的名字查找节点; 也接受层次名 `x1.out`
+    pub fn lookup(&self, name: &str) -> Option<NodeId> {
+        let name = self.normalization.normalize(name);
+        self.name_to_id
+            .get(&name)
+            .or_else(|| self.aliases.get(&name))
+            .copied()
+    }
+
+    /// 节点的层次名: `X1.X2:n3` -> `x1.x2.n3`, 顶层节点不变
+    pub fn hierarchical_name(&self, id: NodeId) -> Option<String> {
+        self.id_to_name.get(id.0).map(|name| hierarchical_node_name(name))
+    }
+}
+
+/// 展开后的内部节点名 `X1.X2:n3` 写成层次名 `X1.X2.n3`
+fn hierarchical_node_name(name: &str) -> String {
+    name.replace(':', ".")
 }
 
 #[derive(Debug, Clone)]
@@ -76,11 +159,40 @@ pub enum DeviceKind {
     I,
     D,
     M,
+    /// 双极型晶体管; nodes 为 [c, b, e, s, c', b', e'], 后三个是 RC/RB/RE
+    /// 内侧的本征节点, 对应电阻为 0 时与外部端口相同
+    Q,
+    /// 结型场效应管; nodes 为 [d, g, s, d', s'], 后两个是 RD/RS 内侧的本征节点
+    J,
+    /// MESFET / HEMT; 节点布局与 J 相同
+    Z,
+    /// 电压控制开关; nodes 为 [n+, n-, nc+, nc-]
+    S,
+    /// 电流控制开关; nodes 为 [n+, n-], control 为控制电压源名
+    W,
+    /// 多导体有损传输线; nodes 为 N 个输入、输入参考、N 个输出、输出参考,
+    /// 之后是分段的内部节点; params 含 n、l (线长)、nseg 与模型的 RLGC 矩阵元
+    WLine,
+    /// S 参数块; nodes 为 N 个端口节点加参考节点, params 的 file 为 Touchstone 文件路径
+    SParam,
+    /// 理想变压器; nodes 为 [p+, p-, s+, s-], value 为匝数比 Np/Ns
+    N,
+    /// 无损传输线; nodes 为 [a+, a-, b+, b-], params 含 z0 与 td (或 f / nl)
+    T,
+    /// RF 端口; nodes 为 [n+, n-], params 含 port 与 z0, value 为 DC 源电压
+    P,
     E,
     G,
     F,
     H,
     X,
+    /// 行为源, 表达式保存在 params 的 "v" 或 "i" 中
+    B,
+    /// 数字器件 / 混合信号桥, 由模型类型区分; 数字输出是最后一个节点
+    /// (d_inverter [in, out], d_nand [in..., out], d_dff [d, clk, q],
+    /// adc_bridge [ain, dout], dac_bridge [din, aout]);
+    /// 模型类型已注册为插件时由 [`crate::device_model::DeviceModel`] 盖章
+    A,
 }
 
 #[derive(Debug, Clone)]
@@ -96,6 +208,8 @@ pub struct Instance {
     pub ac_mag: Option<f64>,
     /// AC analysis phase in degrees (for voltage/current sources)
     pub ac_phase: Option<f64>,
+    /// 瞬态状态槽位, 由 [`InstanceTable::insert`] 按插入顺序分配
+    pub state_slot: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -112,12 +226,42 @@ impl InstanceTable {
         }
     }
 
-    pub fn insert(&mut self, instance: Instance) -> InstanceId {
+    pub fn insert(&mut self, mut instance: Instance) -> InstanceId {
         let id = InstanceId(self.instances.len());
+        instance.state_slot = id.0;
         self.name_to_id.insert(instance.name.clone(), id);
         self.instances.push(instance);
         id
     }
+
+    /// 按实例名查找 (大小写不敏感); 子电路内的实例名为层次名 `X1.M1`
+    pub fn find(&self, name: &str) -> Option<InstanceId> {
+        self.name_to_id.get(name).copied().or_else(|| {
+            self.instances
+                .iter()
+                .position(|inst| inst.name.eq_ignore_ascii_case(name))
+                .map(InstanceId)
+        })
+    }
+}
+
+/// RF 端口 (P 元件)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Port {
+    pub instance: InstanceId,
+    /// 端口号, 从 1 开始
+    pub number: usize,
+    /// 参考阻抗 [Ohm]
+    pub z0: f64,
+}
+
+/// 按名字引用的仿真信号
+#[derive(Debug, Clone, PartialEq)]
+pub enum Signal {
+    /// 节点电压: `out`、`x1.out` 或展开后的 `X1:out`
+    Node(NodeId),
+    /// 器件量: `x1.m1:ids` 为实例 X1.M1 的 ids
+    Device { instance: InstanceId, quantity: String },
 }
 
 /// AC sweep type for frequency analysis
@@ -140,11 +284,30 @@ pub enum AnalysisCmd {
         stop: f64,
         step: f64,
     },
+    /// `.dc src1 start stop step src2 start stop step`: src1 为内层 (快速) 扫描,
+    /// src2 为外层, 结果是一族曲线
+    DcNested {
+        inner: DcSweepSpec,
+        outer: DcSweepSpec,
+    },
+    /// `.dc param <name> start stop step`: 扫描 `.param` 参数, 每点重新展开网表
+    DcParam {
+        param: String,
+        start: f64,
+        stop: f64,
+        step: f64,
+    },
+    /// `.dc data=<name>`: 逐行应用 `.data` 表中的取值
+    DcData { table: DataTable },
     Tran {
         tstep: f64,
         tstop: f64,
         tstart: f64,
         tmax: f64,
+        /// UIC: 跳过初始工作点, 直接从 `.ic` / IC= 给定的初值开始
+        uic: bool,
+        /// `.tran tstep1 tstop1 tstep2 tstop2 ...` 的各时间窗, 按 tstop 递增; 单一窗口时为空
+        windows: Vec<TranWindow>,
     },
     Ac {
         sweep_type: AcSweepType,
@@ -152,6 +315,164 @@ pub enum AnalysisCmd {
         fstart: f64,
         fstop: f64,
     },
+    /// `.noise V(out[,ref]) src dec|oct|lin points fstart fstop`
+    Noise {
+        output: String,
+        reference: Option<String>,
+        source: String,
+        sweep_type: AcSweepType,
+        points: usize,
+        fstart: f64,
+        fstop: f64,
+    },
+    /// `.tf V(out)|I(Vx) src`: 小信号传输函数、输入/输出电阻
+    Tf { output: String, source: String },
+    /// `.four freq V(n) ...`: 基于瞬态结果的傅里叶分解
+    Four { freq: f64, outputs: Vec<String> },
+    /// `.sens V(out)|I(Vx) ...`: DC 灵敏度
+    Sens { outputs: Vec<String> },
+    /// `.pz in+ in- out+ out- vol|cur pol|zer|pz`
+    Pz {
+        input_pos: String,
+        input_neg: String,
+        output_pos: String,
+        output_neg: String,
+        transfer: PzTransfer,
+        target: PzTarget,
+    },
+    /// `.pss gfreq tstab [oscnob] [psspoints] [harms] [sciter] [steady_coeff]`:
+    /// 用打靶法求受驱电路的周期稳态
+    Pss {
+        /// 基波频率, 周期为 1/freq
+        freq: f64,
+        /// 开始打靶之前先做普通瞬态的时长
+        tstab: f64,
+        /// 每个周期的时间步数
+        points: usize,
+        /// 打靶 Newton 的最大迭代次数
+        max_iters: usize,
+        /// 周期首末状态的相对容差
+        tol: f64,
+    },
+    /// `.sp dec|oct|lin points fstart fstop`: 以端口 (P 器件) 为参考面的 N 端口 S 参数
+    Sp {
+        sweep_type: AcSweepType,
+        points: usize,
+        fstart: f64,
+        fstop: f64,
+    },
+    /// `.pac dec|oct|lin points fstart fstop [sidebands=K]`: 在 `.pss` 周期稳态上的周期 AC,
+    /// 求各 AC 源激励在边带 f + m·f0 (|m| <= K) 上的响应
+    Pac {
+        sweep_type: AcSweepType,
+        points: usize,
+        fstart: f64,
+        fstop: f64,
+        sidebands: usize,
+    },
+    /// `.pnoise V(out[,ref]) src dec|oct|lin points fstart fstop [sidebands=K]`:
+    /// 在 `.pss` 周期稳态上的周期噪声, 计入噪声在边带间的搬移
+    Pnoise {
+        output: String,
+        reference: Option<String>,
+        source: String,
+        sweep_type: AcSweepType,
+        points: usize,
+        fstart: f64,
+        fstop: f64,
+        sidebands: usize,
+    },
+}
+
+/// `.mc <runs> [seed=<n>]`: Monte Carlo 的运行次数与随机种子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonteCarloConfig {
+    pub runs: usize,
+    pub seed: u64,
+}
+
+/// `.corner <name> [lib=<section>] [temp=<℃>] [<param>=<value> ...]`: 一个工艺角
+#[derive(Debug, Clone, PartialEq)]
+pub struct Corner {
+    pub name: String,
+    /// 替换网表中 `.lib <file> <section>` 的段名
+    pub lib: Option<String>,
+    pub temp: Option<f64>,
+    /// 覆盖的 `.param` 取值 (例如电源电压)
+    pub params: Vec<(String, f64)>,
+}
+
+/// 瞬态时间窗: 上一窗口的终点到 `tstop` 之间内部步长不超过 `tstep`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TranWindow {
+    pub tstep: f64,
+    pub tstop: f64,
+}
+
+/// DC 扫描变量的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DcSweepKind {
+    /// 独立源 (V/I) 的值
+    Source,
+    /// 电路温度 (摄氏度)
+    Temp,
+    /// `.param` 参数
+    Param,
+}
+
+/// 单层 DC 扫描的变量与范围
+#[derive(Debug, Clone, PartialEq)]
+pub struct DcSweepSpec {
+    pub kind: DcSweepKind,
+    pub source: String,
+    pub start: f64,
+    pub stop: f64,
+    pub step: f64,
+}
+
+/// `.step` 一维扫描的取值
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepValues {
+    /// `<start> <stop> <incr>`
+    Lin { start: f64, stop: f64, step: f64 },
+    /// `dec|oct <start> <stop> <points>`: 每十倍频 / 倍频程的点数
+    Log { sweep_type: AcSweepType, points: usize, start: f64, stop: f64 },
+    /// `list <v1> <v2> ...`
+    List(Vec<f64>),
+}
+
+/// `.step [lin|dec|oct] (param <name> | temp | <source>) ...`: 一维扫描变量
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepSpec {
+    pub kind: DcSweepKind,
+    /// 源名或参数名; 温度为 "temp"
+    pub name: String,
+    pub values: StepValues,
+}
+
+/// `.data` 表: 每列是一个源名、`temp` 或 `.param` 参数, 每行是一组同时生效的取值
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataTable {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<f64>>,
+}
+
+/// 零极点分析的传输函数类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PzTransfer {
+    /// 输出电压 / 输入电压
+    Voltage,
+    /// 输出电压 / 输入电流
+    Current,
+}
+
+/// 零极点分析求解的对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PzTarget {
+    Poles,
+    Zeros,
+    Both,
 }
 
 #[derive(Debug, Clone)]
@@ -160,16 +481,75 @@ pub struct Circuit {
     pub models: ModelTable,
     pub instances: InstanceTable,
     pub analysis: Vec<AnalysisCmd>,
+    /// `.ic V(node)=value` 给定的节点初始电压
+    pub initial_conditions: HashMap<NodeId, f64>,
+    /// 构建电路所用的网表, 参数扫描时据此重新展开
+    pub netlist: Option<crate::netlist::NetlistAst>,
+    /// `.options` 设置, 键为小写; 不带值的开关记为 "1"
+    pub options: HashMap<String, String>,
+    /// `.mc` 给出时, 分析按 Monte Carlo 重复运行
+    pub monte_carlo: Option<MonteCarloConfig>,
+    /// `.corner` 给出的工艺角, 按出现顺序
+    pub corners: Vec<Corner>,
+    /// `.measure` 语句, 按出现顺序
+    pub measures: Vec<crate::measure::MeasureCmd>,
+    /// `.step` 扫描变量, 按出现顺序 (第一个为最外层)
+    pub steps: Vec<StepSpec>,
 }
 
 impl Circuit {
     pub fn new() -> Self {
+        Self::with_node_normalization(NodeNormalization::default())
+    }
+
+    pub fn with_node_normalization(normalization: NodeNormalization) -> Self {
         Self {
-            nodes: NodeTable::new(),
+            nodes: NodeTable::with_normalization(normalization),
             models: ModelTable::new(),
             instances: InstanceTable::new(),
             analysis: Vec::new(),
+            initial_conditions: HashMap::new(),
+            netlist: None,
+            options: HashMap::new(),
+            monte_carlo: None,
+            corners: Vec::new(),
+            measures: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// 全部 RF 端口, 按端口号排序; S 参数与噪声系数分析据此确定端口顺序
+    pub fn ports(&self) -> Vec<Port> {
+        let mut ports: Vec<Port> = self
+            .instances
+            .instances
+            .iter()
+            .enumerate()
+            .filter(|(_, inst)| matches!(inst.kind, DeviceKind::P))
+            .map(|(idx, inst)| {
+                let params = sim_devices::source::build_port_params(&inst.params);
+                Port {
+                    instance: InstanceId(idx),
+                    number: params.number,
+                    z0: params.z0,
+                }
+            })
+            .collect();
+        ports.sort_by_key(|port| port.number);
+        ports
+    }
+
+    /// 解析层次信号名; 节点优先, `path:quantity` 的 path 是实例时视为器件量
+    pub fn resolve_signal(&self, name: &str) -> Option<Signal> {
+        if let Some(id) = self.nodes.lookup(name) {
+            return Some(Signal::Node(id));
         }
+        let (path, quantity) = name.rsplit_once(':')?;
+        let instance = self.instances.find(path)?;
+        Some(Signal::Device {
+            instance,
+            quantity: quantity.to_ascii_lowercase(),
+        })
     }
 }
 
@@ -104,11 +104,18 @@ pub struct ComplexStampContext<'a> {
     pub node_count: usize,
     /// Angular frequency ω = 2πf
     pub omega: f64,
+    /// Device multiplicity (m=); scales node (KCL) rows only
+    pub multiplier: f64,
 }
 
 impl<'a> ComplexStampContext<'a> {
     /// Add a complex admittance value to the matrix at position (i, j).
     pub fn add(&mut self, i: usize, j: usize, value: Complex64) {
+        let value = if i < self.node_count {
+            value * self.multiplier
+        } else {
+            value
+        };
         self.builder.insert(j, i, value);
     }
 
@@ -124,6 +131,11 @@ impl<'a> ComplexStampContext<'a> {
 
     /// Add a complex value to the right-hand side vector.
     pub fn add_rhs(&mut self, i: usize, value: Complex64) {
+        let value = if i < self.node_count {
+            value * self.multiplier
+        } else {
+            value
+        };
         if let Some(entry) = self.rhs.get_mut(i) {
             *entry += value;
         }
@@ -176,6 +188,7 @@ impl ComplexMnaBuilder {
             aux: &mut self.aux,
             node_count: self.node_count,
             omega,
+            multiplier: 1.0,
         }
     }
 }
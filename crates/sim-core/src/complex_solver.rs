@@ -5,6 +5,9 @@
 
 use num_complex::Complex64;
 
+use crate::ordering::SymbolicOrdering;
+use crate::solver::SparseLu;
+
 /// Trait for complex linear solvers.
 pub trait ComplexLinearSolver {
     /// Prepare the solver for a given matrix size.
@@ -138,7 +141,62 @@ impl ComplexLinearSolver for ComplexDenseSolver {
     }
 }
 
+/// Sparse LU solver for complex matrices.
+///
+/// The symbolic analysis (minimum-degree ordering of the matrix pattern) is
+/// computed once and kept across calls while the sparsity pattern is unchanged,
+/// which is the case for every frequency point of an AC sweep; each call only
+/// scatters the new values and refactors numerically.
+#[derive(Debug, Default)]
+pub struct ComplexSparseSolver {
+    pub ordering: SymbolicOrdering,
+    lu: SparseLu<Complex64>,
+}
+
+impl ComplexSparseSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of nonzeros in L and U (excluding the diagonal) of the last factorization.
+    pub fn factor_nnz(&self) -> usize {
+        self.lu.factor_nnz()
+    }
+}
+
+impl ComplexLinearSolver for ComplexSparseSolver {
+    fn prepare(&mut self, size: usize) {
+        self.lu.n = size;
+    }
+
+    fn solve(
+        &mut self,
+        ap: &[i64],
+        ai: &[i64],
+        ax: &[Complex64],
+        b: &[Complex64],
+        x: &mut [Complex64],
+    ) -> bool {
+        if self.lu.n == 0 {
+            return true;
+        }
+        if ap.len() != self.lu.n + 1 || b.len() != self.lu.n || self.ordering.update(ap, ai).is_err() {
+            return false;
+        }
+        let values = self.ordering.permute_values(ax);
+        if self.lu.factorize(&self.ordering.permuted_ap, &self.ordering.permuted_ai, &values).is_err() {
+            return false;
+        }
+        let mut permuted = self.ordering.permute_rhs(b);
+        if self.lu.solve_in_place(&mut permuted).is_err() {
+            return false;
+        }
+        self.ordering.restore(&permuted, x);
+        true
+    }
+}
+
 /// Create a complex linear solver.
 pub fn create_complex_solver() -> Box<dyn ComplexLinearSolver> {
-    Box::new(ComplexDenseSolver::new())
+    Box::new(ComplexSparseSolver::new())
 }
@@ -0,0 +1,230 @@
+//! 工艺角分析: 对 `.corner` 给出的每个角重新读入网表 (可切换 `.lib` 段),
+//! 覆盖温度与 `.param` 取值后运行网表中的全部分析, 再汇总各角输出参数的通过情况。
+//!
+//! ```text
+//! .lib 'models.lib' tt
+//! .param vdd=1.8
+//! .corner tt
+//! .corner ff lib=ff temp=-40 vdd=1.98
+//! .corner ss lib=ss temp=125 vdd=1.62
+//! .meas tran tpd TRIG v(in) VAL=0.9 RISE=1 TARG v(out) VAL=0.9 RISE=1 max=2n
+//! ```
+//!
+//! 一个角通过, 当且仅当它的每个分析都收敛、每个输出参数都求得有限值,
+//! 且每条 `.measure` 都求得有限值并落在 `min=`/`max=` 限值内。
+
+use crate::analysis::AnalysisPlan;
+use crate::circuit::{AnalysisCmd, Corner};
+use crate::engine::Engine;
+use crate::measure::MeasureCmd;
+use crate::netlist::{build_circuit, elaborate_netlist, output_params, override_param, NetlistAst};
+use crate::result_store::{ResultStore, RunId, RunStatus};
+
+/// 一个角的运行结果
+#[derive(Debug, Clone)]
+pub struct CornerRun {
+    pub corner: Corner,
+    /// 各分析在 `ResultStore` 中的结果, 按网表中的顺序
+    pub run_ids: Vec<RunId>,
+    /// 输出参数 (小写名) 及其取值; 多个分析都给出时取最后一个, 求不出值时为 None
+    pub measurements: Vec<(String, Option<f64>)>,
+    /// 各条 `.measure` (小写名) 的取值与限值, 按网表中的顺序
+    pub checks: Vec<MeasureCheck>,
+    /// 失败原因, 为空表示通过
+    pub failures: Vec<String>,
+}
+
+impl CornerRun {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// 一条 `.measure` 在一个角上的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasureCheck {
+    pub name: String,
+    /// 测得的值, 没有对应的分析或求不出值时为 None
+    pub value: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl MeasureCheck {
+    fn new(cmd: &MeasureCmd) -> Self {
+        Self {
+            name: cmd.spec.name.to_ascii_lowercase(),
+            value: None,
+            min: cmd.min,
+            max: cmd.max,
+        }
+    }
+
+    /// 求得有限值且落在限值内
+    pub fn passed(&self) -> bool {
+        self.value.is_some_and(|value| {
+            value.is_finite() && self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+        })
+    }
+}
+
+/// 一个输出参数在所有角上的汇总
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasurementSummary {
+    pub name: String,
+    /// 求不出有限值的角
+    pub failed_corners: Vec<String>,
+    /// 求得的最小值与最大值
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CornerReport {
+    pub runs: Vec<CornerRun>,
+}
+
+impl CornerReport {
+    pub fn passed(&self) -> bool {
+        self.runs.iter().all(CornerRun::passed)
+    }
+
+    /// 按输出参数与 `.measure` 汇总各角的结果, 按首次出现的顺序
+    pub fn measurements(&self) -> Vec<MeasurementSummary> {
+        let mut summaries: Vec<MeasurementSummary> = Vec::new();
+        for run in &self.runs {
+            let checks = run.checks.iter().map(|check| (&check.name, &check.value));
+            for (name, value) in run.measurements.iter().map(|(name, value)| (name, value)).chain(checks) {
+                let idx = match summaries.iter().position(|summary| &summary.name == name) {
+                    Some(idx) => idx,
+                    None => {
+                        summaries.push(MeasurementSummary {
+                            name: name.clone(),
+                            failed_corners: Vec::new(),
+                            min: None,
+                            max: None,
+                        });
+                        summaries.len() - 1
+                    }
+                };
+                let summary = &mut summaries[idx];
+                match value.filter(|value| value.is_finite()) {
+                    Some(value) => {
+                        summary.min = Some(summary.min.map_or(value, |min| min.min(value)));
+                        summary.max = Some(summary.max.map_or(value, |max| max.max(value)));
+                    }
+                    None => summary.failed_corners.push(run.corner.name.clone()),
+                }
+            }
+        }
+        summaries
+    }
+}
+
+/// 依次运行各个角。`load(lib)` 返回把网表中的 `.lib` 段换成 `lib` 后的网表 (`None` 为网表原样),
+/// 例如 [`crate::netlist::parse_netlist_file_decks_with_lib`]; 网表中没有分析语句时运行 `.op`
+pub fn run_corners(
+    corners: &[Corner],
+    mut load: impl FnMut(Option<&str>) -> NetlistAst,
+    store: &mut ResultStore,
+) -> CornerReport {
+    let mut report = CornerReport::default();
+    for corner in corners {
+        let ast = load(corner.lib.as_deref());
+        report.runs.push(run_corner(corner, ast, store));
+    }
+    report
+}
+
+fn run_corner(corner: &Corner, mut ast: NetlistAst, store: &mut ResultStore) -> CornerRun {
+    let mut run = CornerRun {
+        corner: corner.clone(),
+        run_ids: Vec::new(),
+        measurements: Vec::new(),
+        checks: Vec::new(),
+        failures: ast.errors.iter().map(|diag| diag.message.clone()).collect(),
+    };
+    for (name, value) in &corner.params {
+        override_param(&mut ast, name, &value.to_string());
+    }
+    let elab = elaborate_netlist(&ast);
+    run.failures.extend(elab.errors.iter().map(|diag| diag.message.clone()));
+
+    if run.failures.is_empty() {
+        let circuit = build_circuit(&ast, &elab);
+        let measures = circuit.measures.clone();
+        run.checks = measures.iter().map(MeasureCheck::new).collect();
+        let mut analyses = circuit.analysis.clone();
+        if analyses.is_empty() {
+            analyses.push(AnalysisCmd::Op);
+        }
+        let mut engine = Engine::new_default(circuit);
+        if let Some(temp) = corner.temp {
+            engine.set_temperature(temp);
+        }
+        for cmd in analyses {
+            let run_id = engine.run_with_store(&AnalysisPlan { cmd }, store);
+            let result = &store.runs[run_id.0];
+            if !matches!(result.status, RunStatus::Converged) {
+                run.failures.push(format!(
+                    "{:?} analysis {:?}: {}",
+                    result.analysis,
+                    result.status,
+                    result.message.as_deref().unwrap_or("")
+                ));
+            }
+            for (name, value) in &result.output_params {
+                match run.measurements.iter_mut().find(|(key, _)| key == name) {
+                    Some(entry) => entry.1 = Some(*value),
+                    None => run.measurements.push((name.clone(), Some(*value))),
+                }
+            }
+            // 测量名可能重复, 按顺序与对应分析的语句一一配对
+            let applicable = measures
+                .iter()
+                .zip(run.checks.iter_mut())
+                .filter(|(cmd, _)| cmd.analysis.applies_to(result.analysis));
+            for ((_, check), measurement) in applicable.zip(&result.measurements) {
+                check.value = measurement.value.as_ref().ok().copied();
+            }
+            run.run_ids.push(run_id);
+        }
+    }
+
+    // 没有运行或没有求出值的输出参数也列出, 便于按参数汇总失败的角
+    for param in output_params(&ast) {
+        let name = param.key.to_ascii_lowercase();
+        if !run.measurements.iter().any(|(key, _)| *key == name) {
+            run.measurements.push((name, None));
+        }
+    }
+    let unevaluated: Vec<String> = run
+        .measurements
+        .iter()
+        .filter(|(_, value)| !value.is_some_and(f64::is_finite))
+        .map(|(name, _)| format!("output parameter '{}' could not be evaluated", name))
+        .collect();
+    run.failures.extend(unevaluated);
+    let out_of_spec: Vec<String> = run
+        .checks
+        .iter()
+        .filter(|check| !check.passed())
+        .map(|check| match check.value {
+            Some(value) if value.is_finite() => {
+                format!("measurement '{}' = {:e} is outside {}", check.name, value, limits(check))
+            }
+            _ => format!("measurement '{}' could not be evaluated", check.name),
+        })
+        .collect();
+    run.failures.extend(out_of_spec);
+    run
+}
+
+fn limits(check: &MeasureCheck) -> String {
+    match (check.min, check.max) {
+        (Some(min), Some(max)) => format!("[{:e}, {:e}]", min, max),
+        (Some(min), None) => format!("min={:e}", min),
+        (None, Some(max)) => format!("max={:e}", max),
+        (None, None) => String::new(),
+    }
+}
@@ -0,0 +1,99 @@
+//! 器件插件: 外部 crate 通过 [`DeviceModel`] 增加新的器件类型,
+//! 无需修改 `DeviceKind` 与 stamp.rs 中的分派。
+//!
+//! 插件器件在网表中写成 A 元件, 按 `.model` 的类型名找到注册的实现:
+//!
+//! ```text
+//! A1 n1 n2 ... mymodel
+//! .model mymodel my_device k=1
+//! ```
+//!
+//! 实例参数 (已合并模型参数) 在 `Instance::params` 中。注册表是进程级的,
+//! 类型名不区分大小写; 与内置数字器件同名时插件优先。
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::circuit::{DeviceKind, Instance};
+use crate::complex_mna::ComplexStampContext;
+use crate::mna::StampContext;
+use crate::stamp::{StampError, TransientState};
+
+pub trait DeviceModel: Send + Sync {
+    /// `.model` 类型名
+    fn model_type(&self) -> &str;
+
+    /// 端口数是否合法; 不合法时盖章返回 InvalidNodes
+    fn accepts_terminals(&self, count: usize) -> bool {
+        count >= 2
+    }
+
+    /// 需要的辅助变量 (支路电流) 名称, 按顺序在盖章前分配;
+    /// 盖章时用同名的 `allocate_aux` 取得下标
+    fn aux_names(&self, _inst: &Instance) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// 直流 / 牛顿迭代盖章; `x` 为 None 时是初始猜测
+    fn stamp_dc(&self, inst: &Instance, ctx: &mut StampContext, x: Option<&[f64]>) -> Result<(), StampError>;
+
+    /// 瞬态盖章, 缺省与直流相同; 历史量放在 `state.device_state[StateKey::of(inst)]`
+    fn stamp_tran(
+        &self,
+        inst: &Instance,
+        ctx: &mut StampContext,
+        x: Option<&[f64]>,
+        _dt: f64,
+        _state: &mut TransientState,
+    ) -> Result<(), StampError> {
+        self.stamp_dc(inst, ctx, x)
+    }
+
+    /// 瞬态步被接受后记录历史量
+    fn accept_step(&self, _inst: &Instance, _x: &[f64], _state: &mut TransientState) {}
+
+    /// 在直流工作点 `dc_solution` 处线性化的小信号盖章
+    fn stamp_ac(&self, inst: &Instance, ctx: &mut ComplexStampContext, dc_solution: &[f64]) -> Result<(), StampError>;
+}
+
+type Registry = RwLock<HashMap<String, Arc<dyn DeviceModel>>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// 注册器件类型; 同名的旧实现被替换
+pub fn register_device_model(model: Arc<dyn DeviceModel>) {
+    let key = model.model_type().to_ascii_lowercase();
+    registry().write().unwrap_or_else(|err| err.into_inner()).insert(key, model);
+}
+
+/// 按类型名查找
+pub fn lookup_device_model(model_type: &str) -> Option<Arc<dyn DeviceModel>> {
+    let registry = registry().read().unwrap_or_else(|err| err.into_inner());
+    registry.get(&model_type.to_ascii_lowercase()).cloned()
+}
+
+/// A 实例对应的插件实现
+pub fn instance_device_model(inst: &Instance) -> Option<Arc<dyn DeviceModel>> {
+    if !matches!(inst.kind, DeviceKind::A) {
+        return None;
+    }
+    lookup_device_model(inst.params.get("type")?)
+}
+
+/// 插件盖章的公共部分: 检查端口数并按声明顺序分配辅助变量
+pub(crate) fn prepare_stamp(
+    model: &dyn DeviceModel,
+    inst: &Instance,
+    mut allocate_aux: impl FnMut(&str) -> usize,
+) -> Result<(), StampError> {
+    if !model.accepts_terminals(inst.nodes.len()) {
+        return Err(StampError::InvalidNodes);
+    }
+    for name in model.aux_names(inst) {
+        allocate_aux(&name);
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+//! 网表诊断信息: 文件/行/列位置、严重级别、错误码和英文消息,
+//! 以及 CLI 与 HTTP API 共用的文本渲染
+
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// 诊断错误码; 同一类问题使用同一个码, 便于脚本过滤
+pub mod codes {
+    /// `+` 续行之前没有语句
+    pub const ORPHAN_CONTINUATION: &str = "E0001";
+    /// `.data` 块没有 `.enddata`
+    pub const UNTERMINATED_DATA: &str = "E0002";
+    /// `.data` 表内容无效
+    pub const INVALID_DATA_TABLE: &str = "E0003";
+    /// `.model` 缺少名称或类型
+    pub const MODEL_MISSING_NAME: &str = "E0004";
+    /// `.subckt` 缺少名称
+    pub const SUBCKT_MISSING_NAME: &str = "E0005";
+    /// `.subckt` 没有对应的 `.ends`
+    pub const SUBCKT_MISSING_ENDS: &str = "E0006";
+    /// `.control` 块没有 `.endc`
+    pub const UNTERMINATED_CONTROL: &str = "E0007";
+    /// `.measure` 语句无法解析
+    pub const INVALID_MEASURE: &str = "E0008";
+    /// 器件名首字母不是已知器件类型
+    pub const UNKNOWN_DEVICE: &str = "E0101";
+    /// 节点个数不符合器件要求
+    pub const NODE_COUNT: &str = "E0102";
+    /// 缺少数值 / 增益
+    pub const MISSING_VALUE: &str = "E0103";
+    /// 缺少模型名 / 子电路名 / 控制源
+    pub const MISSING_REFERENCE: &str = "E0104";
+    /// 多余字段
+    pub const EXTRA_FIELDS: &str = "E0105";
+    /// 波形或 POLY 参数个数不足
+    pub const MISSING_ARGUMENTS: &str = "E0106";
+    /// 行为源表达式错误
+    pub const INVALID_EXPRESSION: &str = "E0107";
+    /// 表达式中的 `I(...)` 引用了不存在或没有支路电流的元件
+    pub const UNKNOWN_BRANCH: &str = "E0108";
+    /// 引用了未定义的子电路
+    pub const UNDEFINED_SUBCKT: &str = "E0201";
+    /// `.param` 之间循环依赖
+    pub const PARAM_CYCLE: &str = "E0202";
+    /// 文件无法读取
+    pub const FILE_UNREADABLE: &str = "E0301";
+    /// `.include` 缺少路径
+    pub const INCLUDE_MISSING_PATH: &str = "E0302";
+    /// `.include` 循环引用
+    pub const INCLUDE_CYCLE: &str = "E0303";
+    /// `.lib <file> <section>` 中的段不存在
+    pub const LIB_SECTION_NOT_FOUND: &str = "E0304";
+    /// Spectre 语句无法翻译
+    pub const SPECTRE_SYNTAX: &str = "E0401";
+
+    // 以下为 `check` (设计规则检查) 的警告
+    /// 节点没有到地的直流通路
+    pub const FLOATING_NODE: &str = "W0001";
+    /// 节点只连接了一个器件端口
+    pub const SINGLE_CONNECTION: &str = "W0002";
+    /// 电阻 / 电容取值为 0
+    pub const ZERO_VALUE: &str = "W0003";
+    /// 子电路端口在内部未连接
+    pub const UNCONNECTED_PORT: &str = "W0004";
+    /// 模型从未被引用
+    pub const UNUSED_MODEL: &str = "W0005";
+    /// 参数从未被引用
+    pub const UNUSED_PARAM: &str = "W0006";
+    /// 重复的实例名
+    pub const DUPLICATE_INSTANCE: &str = "W0007";
+    /// 多个 P 元件使用同一端口号
+    pub const DUPLICATE_PORT: &str = "W0008";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// 来源文件; 直接解析字符串时为 None
+    pub file: Option<PathBuf>,
+    /// 1 起始的行号, 0 表示与具体行无关 (如文件无法读取)
+    pub line: usize,
+    /// 1 起始、左闭右开的字符列范围
+    pub columns: Option<Range<usize>>,
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    /// 出错的源代码行, 用于渲染
+    pub source_line: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, line: usize, message: impl Into<String>) -> Self {
+        Self {
+            file: None,
+            line,
+            columns: None,
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            source_line: None,
+        }
+    }
+
+    pub fn warning(code: &'static str, line: usize, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, line, message)
+        }
+    }
+
+    pub fn with_columns(mut self, columns: Range<usize>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    pub fn with_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    pub fn with_source_line(mut self, source_line: impl Into<String>) -> Self {
+        self.source_line = Some(source_line.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// `file:line:col` 形式的位置, 缺失的部分省略
+    pub fn location(&self) -> String {
+        let mut location = match &self.file {
+            Some(file) => file.display().to_string(),
+            None => "<netlist>".to_string(),
+        };
+        if self.line > 0 {
+            location.push_str(&format!(":{}", self.line));
+            if let Some(columns) = &self.columns {
+                location.push_str(&format!(":{}", columns.start));
+            }
+        }
+        location
+    }
+
+    /// 多行文本渲染:
+    ///
+    /// ```text
+    /// error[E0101]: unknown device type 'Y1'
+    ///  --> top.cir:3:1
+    ///   |
+    /// 3 | Y1 c b e ymod
+    ///   | ^^
+    /// ```
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "{}[{}]: {}\n --> {}",
+            self.severity.as_str(),
+            self.code,
+            self.message,
+            self.location()
+        );
+        let Some(source) = self.source_line.as_deref().filter(|_| self.line > 0) else {
+            return out;
+        };
+        let gutter = self.line.to_string();
+        let pad = " ".repeat(gutter.len());
+        out.push_str(&format!("\n{} |\n{} | {}", pad, gutter, source));
+        let width = source.chars().count();
+        let columns = self.columns.clone().unwrap_or_else(|| {
+            let indent = source.chars().take_while(|ch| ch.is_whitespace()).count();
+            indent + 1..width + 1
+        });
+        let start = columns.start.max(1);
+        let end = columns.end.min(width + 1).max(start + 1);
+        out.push_str(&format!(
+            "\n{} | {}{}",
+            pad,
+            " ".repeat(start - 1),
+            "^".repeat(end - start)
+        ));
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}[{}]: {}",
+            self.location(),
+            self.severity.as_str(),
+            self.code,
+            self.message
+        )
+    }
+}
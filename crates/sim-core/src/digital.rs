@@ -0,0 +1,155 @@
+//! 数字器件 (A 元件) 的事件驱动求值。
+//!
+//! 数字线网也是电路节点, 由驱动它的器件把节点电压钉在逻辑值 0 / 1 上,
+//! 因此波形里直接可见。瞬态中数字线网只在事件时刻变化: 每个接受时间点
+//! 先执行到期的事件, 再按新的线网值与模拟输入重新求值各器件, 输出变化按
+//! RISE_DELAY / FALL_DELAY 排入事件队列。引擎把下一个事件时刻当作断点,
+//! 事件执行后的第一步不做误差比较 (数字跳变本来就不连续)。
+
+use std::collections::HashMap;
+
+use sim_devices::digital::{build_digital_params, DigitalKind, DigitalParams};
+
+use crate::circuit::{DeviceKind, Instance};
+
+/// 节点电压不低于该值视为逻辑 1
+pub const LOGIC_THRESHOLD: f64 = 0.5;
+
+/// 一次排定的线网变化
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DigitalEvent {
+    pub time: f64,
+    pub node: usize,
+    pub level: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DigitalState {
+    /// 线网 (节点编号) 的当前逻辑值
+    pub nets: HashMap<usize, bool>,
+    /// 尚未执行的事件, 按排定顺序
+    pub events: Vec<DigitalEvent>,
+    /// 触发器在上一个接受时间点看到的时钟值
+    pub clocks: HashMap<String, bool>,
+    /// 最近一次 advance 是否改变了线网
+    pub fired: bool,
+    /// 线网是否已从初始解建立
+    pub ready: bool,
+}
+
+/// A 实例的模型参数; 不是数字器件 (含插件器件) 或端口数不符时为 None
+pub fn digital_params(inst: &Instance) -> Option<DigitalParams> {
+    if !matches!(inst.kind, DeviceKind::A) || crate::device_model::instance_device_model(inst).is_some() {
+        return None;
+    }
+    build_digital_params(&inst.params).filter(|params| params.accepts_terminals(inst.nodes.len()))
+}
+
+/// 器件驱动的数字线网; DAC 的输出是模拟节点, 不算
+pub fn digital_output(inst: &Instance, params: &DigitalParams) -> Option<usize> {
+    match params.kind {
+        DigitalKind::DacBridge => None,
+        _ => inst.nodes.last().map(|node| node.0),
+    }
+}
+
+fn node_level(x: &[f64], node: usize) -> bool {
+    x.get(node).copied().unwrap_or(0.0) >= LOGIC_THRESHOLD
+}
+
+impl DigitalState {
+    /// 线网的逻辑值; 还没有记录时按节点电压判断
+    pub fn level(&self, node: usize, x: &[f64]) -> bool {
+        self.nets.get(&node).copied().unwrap_or_else(|| node_level(x, node))
+    }
+
+    /// 晚于 `time` 的最早事件时刻
+    pub fn next_event(&self, time: f64) -> Option<f64> {
+        self.events
+            .iter()
+            .map(|event| event.time)
+            .filter(|t| *t > time)
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// 取出并清除 fired 标志
+    pub fn take_fired(&mut self) -> bool {
+        std::mem::take(&mut self.fired)
+    }
+
+    /// 从初始解 (工作点或 UIC 初值) 建立线网值, 触发器取 IC, 然后求值一次
+    pub fn initialize(&mut self, instances: &[Instance], x: &[f64], time: f64) {
+        self.nets.clear();
+        self.events.clear();
+        self.clocks.clear();
+        for inst in instances {
+            let Some(params) = digital_params(inst) else { continue };
+            if let Some(out) = digital_output(inst, &params) {
+                let level = match params.kind {
+                    DigitalKind::Dff => params.ic,
+                    _ => node_level(x, out),
+                };
+                self.nets.insert(out, level);
+            }
+            if params.kind == DigitalKind::Dff {
+                self.clocks.insert(inst.name.clone(), node_level(x, inst.nodes[1].0));
+            }
+        }
+        self.evaluate(instances, x, time);
+        self.fired = false;
+        self.ready = true;
+    }
+
+    /// 推进到接受时间点 `time`: 执行到期事件, 再求值全部器件
+    pub fn advance(&mut self, instances: &[Instance], x: &[f64], time: f64) {
+        let (due, pending): (Vec<DigitalEvent>, Vec<DigitalEvent>) =
+            std::mem::take(&mut self.events).into_iter().partition(|event| event.time <= time);
+        self.events = pending;
+        for event in due {
+            if self.nets.insert(event.node, event.level) != Some(event.level) {
+                self.fired = true;
+            }
+        }
+        self.evaluate(instances, x, time);
+    }
+
+    /// 线网最终会到达的值: 最后一个排定事件, 没有则为当前值
+    fn projected(&self, node: usize, x: &[f64]) -> bool {
+        self.events
+            .iter()
+            .rev()
+            .find(|event| event.node == node)
+            .map_or_else(|| self.level(node, x), |event| event.level)
+    }
+
+    fn evaluate(&mut self, instances: &[Instance], x: &[f64], time: f64) {
+        for inst in instances {
+            let Some(params) = digital_params(inst) else { continue };
+            let Some(out) = digital_output(inst, &params) else { continue };
+            let nodes: Vec<usize> = inst.nodes.iter().map(|node| node.0).collect();
+            let target = match params.kind {
+                DigitalKind::Inverter | DigitalKind::Nand => {
+                    let inputs: Vec<bool> = nodes[..nodes.len() - 1].iter().map(|node| self.level(*node, x)).collect();
+                    params.gate_output(&inputs)
+                }
+                DigitalKind::Dff => {
+                    let clock = self.level(nodes[1], x);
+                    let previous = self.clocks.insert(inst.name.clone(), clock).unwrap_or(clock);
+                    if previous || !clock {
+                        continue;
+                    }
+                    self.level(nodes[0], x)
+                }
+                DigitalKind::AdcBridge => params.adc_level(x.get(nodes[0]).copied().unwrap_or(0.0), self.projected(out, x)),
+                DigitalKind::DacBridge => continue,
+            };
+            if target != self.projected(out, x) {
+                self.events.push(DigitalEvent {
+                    time: time + params.delay(target),
+                    node: out,
+                    level: target,
+                });
+            }
+        }
+    }
+}
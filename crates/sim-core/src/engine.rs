@@ -1,20 +1,81 @@
+use std::collections::HashMap;
+use std::path::Path;
+
 use crate::analysis::{
-    estimate_error_weighted, AnalysisPlan, ErrorEstimate, TimeStepConfig, TimeStepState,
+    estimate_error_weighted, AnalysisPlan, ErrorEstimate, SteadyStateConfig, SteadyStateDetector, TimeStepConfig,
+    TimeStepState,
+};
+use crate::circuit::{
+    AcSweepType, Circuit, DataTable, DcSweepKind, DcSweepSpec, DeviceKind, MonteCarloConfig, StepSpec, StepValues,
+    TranWindow,
 };
-use crate::circuit::{AcSweepType, Circuit};
+use crate::netlist::{
+    build_circuit_with_nodes, elaborate_netlist, has_param, override_param, param_number, parse_number_with_suffix,
+    sample_statistical_params, Distribution, NetlistAst,
+};
+use crate::bypass::{stamp_with_bypass, BypassCache, BypassConfig, BypassStats};
 use crate::complex_mna::ComplexMnaBuilder;
-use crate::complex_solver::create_complex_solver;
-use crate::mna::MnaBuilder;
-use crate::result_store::{AnalysisType, ResultStore, RunId, RunResult, RunStatus};
-use crate::solver::{create_solver, LinearSolver, SolverType};
-use crate::stamp::{update_transient_state, DeviceStamp, InstanceStamp, TransientState};
-use crate::newton::{debug_dump_newton_with_tag, run_newton_with_stepping, NewtonConfig};
+use crate::complex_solver::{create_complex_solver, ComplexLinearSolver};
+use crate::integrator::integrator_from_option;
+use crate::noise::{NoiseResult, NoiseSource};
+use crate::op_file::{read_op, write_op, SavedOp};
+use crate::measure::{evaluate_measures, sample_signal, MeasureSpec, Signal};
+use crate::periodic::{transpose_csc, PacResult, PeriodicSystem};
+use crate::sensitivity::{perturbation, TranSensitivity};
+use crate::mna::{AuxVarTable, JunctionState, MnaBuilder, MnaWorkspace, PatternStats, StampContext};
+use crate::ordering::OrderedSolver;
+use crate::result_store::{
+    AnalysisType, ConvergenceReport, DeviceOpInfo, ResultStore, RunId, RunResult, RunStatus, StepSweep,
+};
+use crate::solver::{create_solver, LinearSolver, SolverError, SolverType};
+use crate::topology::analyze_singular_matrix;
+use crate::stamp::{
+    apply_initial_conditions, compile_instances, defaulted_initial_states, noise_sources, switching_conditions,
+    update_transient_state, CompiledInstance, DeviceStamp, InstanceStamp, TransientState,
+};
+use crate::newton::{
+    debug_dump_newton_with_tag, residual, run_newton_with_limiting, MnaParts, NewtonConfig, NewtonDiagnostics,
+    NewtonResult,
+};
 use num_complex::Complex64;
+use rayon::prelude::*;
 
 pub struct Engine {
     pub circuit: Circuit,
+    /// 后端求解器外包一层减少填充的重排 ([`OrderedSolver`])
     solver: Box<dyn LinearSolver>,
     solver_type: SolverType,
+    /// 上一次收敛的工作点, 供 [`Engine::tune_op`] 热启动
+    last_op: Option<OpSnapshot>,
+    /// 各次求解累计的器件旁路统计
+    bypass_stats: BypassStats,
+    /// `.step` / Monte Carlo 各点与 AC 各频率点并行运行的线程数; 1 为串行, 0 为全部核
+    threads: usize,
+    /// 跨 Newton 迭代与时间步复用的 MNA 存储与矩阵结构
+    workspace: MnaWorkspace,
+    /// [`Engine::load_op`] 读入的工作点, 作为之后 OP 与瞬态初始工作点求解的初值
+    initial_op: Option<OpSnapshot>,
+    /// 与 `circuit.instances` 一一对应的预编译实例, 每次求解开始及扫描改动器件取值后重新编译
+    compiled: Vec<CompiledInstance>,
+}
+
+/// 收敛的工作点及其结电压, 连同最近一次冷启动求解所用的迭代次数
+#[derive(Debug, Clone)]
+struct OpSnapshot {
+    solution: Vec<f64>,
+    junctions: JunctionState,
+    cold_iterations: usize,
+}
+
+/// 热启动工作点求解的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmStartOp {
+    pub run_id: RunId,
+    /// 是否从上一次收敛的解出发; 没有可用的解时退回冷启动
+    pub warm: bool,
+    pub iterations: usize,
+    /// 相对最近一次冷启动求解少用的 Newton 迭代次数
+    pub iterations_saved: usize,
 }
 
 impl Engine {
@@ -23,20 +84,45 @@ impl Engine {
         let node_count = circuit.nodes.id_to_name.len();
         Self {
             circuit,
-            solver: create_solver(solver_type, node_count),
+            solver: Box::new(OrderedSolver::new(create_solver(solver_type, node_count))),
             solver_type,
+            last_op: None,
+            initial_op: None,
+            bypass_stats: BypassStats::default(),
+            threads: 1,
+            workspace: MnaWorkspace::new(node_count),
+            compiled: Vec::new(),
         }
     }
 
-    /// 使用默认求解器（Dense）创建 Engine
+    /// 使用默认求解器（Auto, 按矩阵规模选择稠密或稀疏 LU）创建 Engine
     pub fn new_default(circuit: Circuit) -> Self {
         Self::new(circuit, SolverType::default())
     }
 
+    /// 并行扫描工作线程的 Engine 副本构造器: 电路取 `circuit`, 沿用本引擎的求解器类型、
+    /// [`Engine::load_op`] 读入的工作点与热启动状态; 工作线程内串行运行
+    fn worker_clone(&self, circuit: Circuit) -> impl Fn() -> Engine + Sync {
+        let solver_type = self.solver_type;
+        let last_op = self.last_op.clone();
+        let initial_op = self.initial_op.clone();
+        move || {
+            let mut engine = Engine::new(circuit.clone(), solver_type);
+            engine.last_op = last_op.clone();
+            engine.initial_op = initial_op.clone();
+            engine
+        }
+    }
+
+    /// 按当前实例重新编译器件取值与模型参数
+    fn compile_instances(&mut self) {
+        self.compiled = compile_instances(&self.circuit.instances.instances);
+    }
+
     /// 当电路大小变化时，重新初始化 solver
     pub fn resize_solver(&mut self) {
         let node_count = self.circuit.nodes.id_to_name.len();
-        self.solver = create_solver(self.solver_type, node_count);
+        self.solver = Box::new(OrderedSolver::new(create_solver(self.solver_type, node_count)));
     }
 
     /// 切换求解器类型
@@ -45,59 +131,834 @@ impl Engine {
         self.resize_solver();
     }
 
+    /// `.step` 与 Monte Carlo 的各点及 AC 的各频率点在 `threads` 个工作线程上并行运行
+    /// (1 为串行, 0 为全部核); 结果按点的顺序入库, 与串行运行相同
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
     pub fn run(&mut self, plan: &AnalysisPlan) {
         println!("engine: run {:?}", plan.cmd);
         match plan.cmd {
-            crate::circuit::AnalysisCmd::Tran { .. } => self.run_tran(),
+            crate::circuit::AnalysisCmd::Tran { tstep, tstop, tstart, tmax, uic, ref windows } => {
+                let _ = self.run_tran_result_with_params(tstep, tstop, tstart, tmax, uic, windows);
+            }
             _ => self.run_dc(),
         }
     }
 
     pub fn run_with_store(&mut self, plan: &AnalysisPlan, store: &mut ResultStore) -> RunId {
+        let result = self.run_result(plan);
+        store.add_run(result)
+    }
+
+    /// 跑 `plan` 并补上支路名、器件工作点与输出参数
+    fn run_result(&mut self, plan: &AnalysisPlan) -> RunResult {
         let result = match &plan.cmd {
-            crate::circuit::AnalysisCmd::Tran { tstep, tstop, tstart, tmax } => {
-                self.run_tran_result_with_params(*tstep, *tstop, *tstart, *tmax)
+            crate::circuit::AnalysisCmd::Tran { tstep, tstop, tstart, tmax, uic, windows } => {
+                self.run_tran_result_with_params(*tstep, *tstop, *tstart, *tmax, *uic, windows)
             }
             crate::circuit::AnalysisCmd::Dc { source, start, stop, step } => {
                 self.run_dc_sweep_result(source, *start, *stop, *step)
             }
+            crate::circuit::AnalysisCmd::DcParam { param, start, stop, step } => {
+                let spec = DcSweepSpec {
+                    kind: DcSweepKind::Param,
+                    source: param.clone(),
+                    start: *start,
+                    stop: *stop,
+                    step: *step,
+                };
+                self.run_dc_family_result(&spec, None)
+            }
+            crate::circuit::AnalysisCmd::DcData { table } => self.run_dc_data_result(table),
+            crate::circuit::AnalysisCmd::DcNested { inner, outer } => {
+                self.run_dc_family_result(inner, Some(outer))
+            }
             crate::circuit::AnalysisCmd::Ac { sweep_type, points, fstart, fstop } => {
                 self.run_ac_result(*sweep_type, *points, *fstart, *fstop)
             }
+            crate::circuit::AnalysisCmd::Pss { freq, tstab, points, max_iters, tol } => {
+                self.run_pss_result(*freq, *tstab, *points, *max_iters, *tol)
+            }
+            crate::circuit::AnalysisCmd::Sp { sweep_type, points, fstart, fstop } => {
+                self.run_sp_result(*sweep_type, *points, *fstart, *fstop)
+            }
+            crate::circuit::AnalysisCmd::Noise { output, reference, source, sweep_type, points, fstart, fstop } => {
+                let frequencies = generate_frequency_sweep(*sweep_type, *points, *fstart, *fstop);
+                self.run_noise_result(output, reference.as_deref(), source, frequencies)
+            }
+            crate::circuit::AnalysisCmd::Pac { sweep_type, points, fstart, fstop, sidebands } => {
+                let frequencies = generate_frequency_sweep(*sweep_type, *points, *fstart, *fstop);
+                self.run_pac_result(frequencies, *sidebands)
+            }
+            crate::circuit::AnalysisCmd::Pnoise { output, reference, source, sweep_type, points, fstart, fstop, sidebands } => {
+                let frequencies = generate_frequency_sweep(*sweep_type, *points, *fstart, *fstop);
+                self.run_pnoise_result(output, reference.as_deref(), source, frequencies, *sidebands)
+            }
+            crate::circuit::AnalysisCmd::Tf { .. }
+            | crate::circuit::AnalysisCmd::Four { .. }
+            | crate::circuit::AnalysisCmd::Sens { .. }
+            | crate::circuit::AnalysisCmd::Pz { .. } => self.unsupported_result(&plan.cmd),
             _ => self.run_dc_result(AnalysisType::Op),
         };
-        store.add_run(result)
+        self.finish_result(result)
+    }
+
+    /// 补上支路名、器件工作点与输出参数
+    fn finish_result(&mut self, mut result: RunResult) -> RunResult {
+        if matches!(result.status, RunStatus::Converged) {
+            result.branch_names = self.branch_names(result.analysis);
+            if matches!(result.analysis, AnalysisType::Op) {
+                result.device_op = self.device_op(&result.solution);
+            }
+        }
+        self.evaluate_output_params(&mut result);
+        self.evaluate_measures(&mut result);
+        result
+    }
+
+    /// 按 [`Engine::set_threads`] 为 `points` 个独立点建的线程池; 串行 (1 线程或只有一点) 时为 None
+    fn thread_pool(&self, points: usize) -> Option<rayon::ThreadPool> {
+        (self.threads != 1 && points > 1)
+            .then(|| rayon::ThreadPoolBuilder::new().num_threads(self.threads).build().ok())
+            .flatten()
+    }
+
+    /// 对 `points` 的每一点从当前 (标称) 电路出发跑 `run`, 按点的顺序返回结果。
+    /// 多线程时每个工作线程持有一个 Engine 副本, 结果与串行运行相同; 结束后电路保持标称
+    fn run_points<P, F>(&mut self, points: &[P], run: F) -> Vec<RunResult>
+    where
+        P: Sync,
+        F: Fn(&mut Engine, &P) -> RunResult + Sync,
+    {
+        let pool = self.thread_pool(points.len());
+        let nominal = self.circuit.clone();
+        let Some(pool) = pool else {
+            let results = points
+                .iter()
+                .map(|point| {
+                    self.circuit.netlist = nominal.netlist.clone();
+                    self.circuit.instances.instances = nominal.instances.instances.clone();
+                    run(self, point)
+                })
+                .collect();
+            self.circuit = nominal;
+            return results;
+        };
+        let worker = self.worker_clone(nominal.clone());
+        pool.install(|| {
+            points
+                .par_iter()
+                .map_init(
+                    worker,
+                    |engine, point| {
+                        engine.circuit.netlist = nominal.netlist.clone();
+                        engine.circuit.instances.instances = nominal.instances.instances.clone();
+                        run(engine, point)
+                    },
+                )
+                .collect()
+        })
+    }
+
+    /// Monte Carlo: 每次运行按 `seed` 的随机序列为调用 gauss/agauss/unif/aunif 的 `.param` 抽样,
+    /// 重新展开网表后跑 `plan`; 每次运行单独入库并记录抽样值。结束后恢复标称电路
+    pub fn run_monte_carlo(
+        &mut self,
+        plan: &AnalysisPlan,
+        config: &MonteCarloConfig,
+        store: &mut ResultStore,
+    ) -> Vec<RunId> {
+        let Some(nominal) = self.circuit.netlist.clone() else {
+            let message = "Monte Carlo requires a circuit built from a netlist".to_string();
+            return vec![store.add_run(self.failed_result(message))];
+        };
+        // 抽样按运行顺序串行生成, 与是否并行无关
+        let mut rng = sim_devices::trnoise::Gaussian::new(config.seed);
+        let samples: Vec<Vec<(String, f64)>> = (0..config.runs)
+            .map(|_| {
+                sample_statistical_params(&nominal, |distribution| match distribution {
+                    Distribution::Gauss { mean, sigma } => mean + sigma * rng.normal(),
+                    Distribution::Uniform { low, high } => low + (high - low) * rng.uniform(),
+                })
+            })
+            .collect();
+        let results = self.run_points(&samples, |engine, samples| {
+            // 抽样值写进网表, 输出参数 (.param 引用节点电压) 也按本次抽样求值
+            let mut ast = nominal.clone();
+            for (name, value) in samples {
+                override_param(&mut ast, name, &value.to_string());
+            }
+            engine.circuit.netlist = Some(ast);
+            match engine.apply_param_overrides(&[]) {
+                Ok(()) => engine.run_result(plan),
+                Err(message) => engine.failed_result(message),
+            }
+        });
+        results
+            .into_iter()
+            .zip(samples)
+            .map(|(mut result, samples)| {
+                result.sampled_params = samples;
+                store.add_run(result)
+            })
+            .collect()
+    }
+
+    /// `.step` 多维扫描: 按 `steps` 的顺序嵌套 (第一个为最外层, 最多三维),
+    /// 对每个取值组合跑一次 `plan` 并登记为 [`StepSweep`], 返回其在 `store.step_sweeps` 中的下标。
+    /// 某一点取值无法应用时该点记为失败的运行; 结束后恢复原电路
+    pub fn run_step_sweep(
+        &mut self,
+        plan: &AnalysisPlan,
+        steps: &[StepSpec],
+        store: &mut ResultStore,
+    ) -> Result<usize, String> {
+        if steps.is_empty() || steps.len() > 3 {
+            return Err(format!(".step supports 1 to 3 sweep variables, got {}", steps.len()));
+        }
+        let mut targets = Vec::with_capacity(steps.len());
+        for step in steps {
+            let spec = DcSweepSpec {
+                kind: step.kind,
+                source: step.name.clone(),
+                start: 0.0,
+                stop: 0.0,
+                step: 0.0,
+            };
+            targets.push(self.resolve_sweep_target(&spec)?);
+        }
+        let values: Vec<Vec<f64>> = steps.iter().map(|step| step_values(&step.values)).collect();
+        if let Some(step) = steps.iter().zip(&values).find(|(_, values)| values.is_empty()) {
+            return Err(format!(".step of '{}' has no points", step.0.name));
+        }
+
+        let nominal = self.circuit.netlist.clone();
+        let mut sweep = StepSweep {
+            vars: steps.iter().map(|step| step.name.clone()).collect(),
+            values,
+            runs: Vec::new(),
+        };
+        let total: usize = sweep.shape().iter().product();
+        let points: Vec<Vec<f64>> = (0..total).map(|flat| sweep.point(flat)).collect();
+        let results = self.run_points(&points, |engine, point| {
+            match engine.apply_step_point(nominal.as_ref(), &targets, point) {
+                Ok(()) => engine.run_result(plan),
+                Err(message) => engine.failed_result(message),
+            }
+        });
+        sweep.runs = results.into_iter().map(|result| store.add_run(result)).collect();
+        Ok(store.add_step_sweep(sweep))
+    }
+
+    /// 应用 `.step` 的一个取值组合: 先把所有参数写进网表并重新展开, 再设源值与温度
+    fn apply_step_point(
+        &mut self,
+        nominal: Option<&NetlistAst>,
+        targets: &[SweepTarget],
+        point: &[f64],
+    ) -> Result<(), String> {
+        let params: Vec<(&str, f64)> = targets
+            .iter()
+            .zip(point)
+            .filter_map(|(target, value)| match target {
+                SweepTarget::Param(name) => Some((name.as_str(), *value)),
+                _ => None,
+            })
+            .collect();
+        if let (Some(nominal), false) = (nominal, params.is_empty()) {
+            // 改写后的网表留在电路上, 输出参数也按本点的参数求值
+            let mut ast = nominal.clone();
+            for (name, value) in &params {
+                override_param(&mut ast, name, &value.to_string());
+            }
+            self.circuit.netlist = Some(ast);
+            self.apply_param_overrides(&[])?;
+        }
+        for (target, value) in targets.iter().zip(point) {
+            if !matches!(target, SweepTarget::Param(_)) {
+                self.apply_sweep_value(target, *value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 解向量中节点电压之后的辅助变量名; 辅助变量按盖章顺序分配, 这里按分析类型重放一次盖章。
+    /// AC 结果的 `solution` 是直流工作点, 因此与直流相同
+    fn branch_names(&self, analysis: AnalysisType) -> Vec<String> {
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let x = vec![0.0; node_count];
+        if matches!(analysis, AnalysisType::Ac | AnalysisType::Pac) {
+            // AC 的辅助变量由复数盖章分配, 顺序与 `ac_phasors` 中的支路电流一致
+            let mut mna = ComplexMnaBuilder::new(node_count);
+            for inst in &self.circuit.instances.instances {
+                let mut ctx = mna.context(0.0);
+                let _ = inst.stamp_ac(&mut ctx, &x);
+            }
+            return mna.aux.id_to_name;
+        }
+        let mut state = TransientState::default();
+        let mut mna = MnaBuilder::new(node_count);
+        for inst in &self.circuit.instances.instances {
+            let mut ctx = mna.context();
+            let _ = match analysis {
+                AnalysisType::Tran | AnalysisType::Pss => inst.stamp_tran(&mut ctx, Some(&x), 1.0, &mut state),
+                _ => inst.stamp_dc(&mut ctx, Some(&x)),
+            };
+        }
+        mna.aux.id_to_name
+    }
+
+    /// 各 MOSFET / 二极管在工作点 `x` 的电流与小信号参数
+    fn device_op(&self, x: &[f64]) -> Vec<DeviceOpInfo> {
+        self.circuit
+            .instances
+            .instances
+            .iter()
+            .filter_map(|inst| {
+                Some(DeviceOpInfo {
+                    name: inst.name.clone(),
+                    kind: crate::stamp::device_op_info(inst, x)?,
+                })
+            })
+            .collect()
+    }
+
+    /// 用分析的最终解 (OP 的解、扫描与瞬态的最后一点) 计算输出参数; AC 与 S 参数结果不参与
+    fn evaluate_output_params(&self, result: &mut RunResult) {
+        let Some(ast) = &self.circuit.netlist else { return };
+        if !matches!(result.status, RunStatus::Converged) {
+            return;
+        }
+        let solution = match result.analysis {
+            AnalysisType::Tran | AnalysisType::Pss => result.tran_solutions.last(),
+            AnalysisType::Dc => result.sweep_solutions.last(),
+            AnalysisType::Ac | AnalysisType::Sp | AnalysisType::Noise | AnalysisType::Pac | AnalysisType::Pnoise => None,
+            _ => Some(&result.solution),
+        };
+        let Some(solution) = solution else { return };
+        let nodes = &self.circuit.nodes;
+        let params = crate::netlist::evaluate_output_params(
+            ast,
+            |name| {
+                let id = nodes.lookup(name)?;
+                Some(solution.get(id.0).copied().unwrap_or(0.0))
+            },
+            |device| result.branch_current(device, solution),
+        );
+        result.output_params = params;
+    }
+
+    /// 在收敛的结果上求网表中作用于该分析的 `.measure`
+    fn evaluate_measures(&self, result: &mut RunResult) {
+        if !matches!(result.status, RunStatus::Converged) {
+            return;
+        }
+        let specs: Vec<MeasureSpec> = self
+            .circuit
+            .measures
+            .iter()
+            .filter(|cmd| cmd.analysis.applies_to(result.analysis))
+            .map(|cmd| cmd.spec.clone())
+            .collect();
+        if !specs.is_empty() {
+            result.measurements = evaluate_measures(result, &specs);
+        }
+    }
+
+    /// Newton 不收敛时的诊断: 残差最大的节点、各节点最后的 |Δx|, 以及把每个器件单独在
+    /// 最后两次线性化点盖章、矩阵项与右端项变化最大的器件. `stamp` 给单个器件盖章 (直流或瞬态)
+    fn convergence_report(
+        &self,
+        diagnostics: &NewtonDiagnostics,
+        mut stamp: impl FnMut(&InstanceStamp, &mut StampContext, &[f64]),
+    ) -> ConvergenceReport {
+        let names = &self.circuit.nodes.id_to_name;
+        let gnd = self.circuit.nodes.gnd_id.0;
+        let node_values = |values: &[f64]| -> Vec<(String, f64)> {
+            values
+                .iter()
+                .zip(names)
+                .enumerate()
+                .filter(|(idx, _)| *idx != gnd)
+                .map(|(_, (value, name))| (name.clone(), value.abs()))
+                .collect()
+        };
+        let worst_residual = node_values(&diagnostics.residual)
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let stamps: Vec<InstanceStamp> = self
+            .circuit
+            .instances
+            .instances
+            .iter()
+            .map(|inst| InstanceStamp { instance: inst.clone() })
+            .collect();
+        // 先给全部器件盖一次章, 使辅助变量的编号与求解时一致
+        let mut full = MnaBuilder::new(names.len());
+        for device in &stamps {
+            stamp(device, &mut full.context(), &diagnostics.x);
+        }
+        let mut device_entries = |device: &InstanceStamp, x: &[f64]| {
+            let mut mna = builder_with_aux(names.len(), &full.aux);
+            stamp(device, &mut mna.context(), x);
+            stamp_entries(&mna)
+        };
+        let largest_stamp_change = stamps
+            .iter()
+            .map(|device| {
+                let now = device_entries(device, &diagnostics.x);
+                let before = device_entries(device, &diagnostics.previous_x);
+                let change = now
+                    .iter()
+                    .map(|(key, value)| (value - before.get(key).copied().unwrap_or(0.0)).abs())
+                    .chain(before.iter().filter(|(key, _)| !now.contains_key(*key)).map(|(_, value)| value.abs()))
+                    .fold(0.0, f64::max);
+                (device.instance.name.clone(), change)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let singular = if diagnostics.solver_error == Some(SolverError::FactorFailed) {
+            full.builder.insert(gnd, gnd, 1.0);
+            let (ap, ai, ax) = full.builder.finalize();
+            let branches: Vec<(String, usize, usize, usize)> = self
+                .circuit
+                .instances
+                .instances
+                .iter()
+                .filter(|inst| matches!(inst.kind, DeviceKind::V | DeviceKind::E | DeviceKind::H | DeviceKind::L))
+                .filter_map(|inst| {
+                    let current = full.aux.name_to_id.get(&inst.name)?;
+                    let (a, b) = (inst.nodes.first()?, inst.nodes.get(1)?);
+                    Some((inst.name.clone(), names.len() + current, a.0, b.0))
+                })
+                .collect();
+            Some(analyze_singular_matrix(&ap, &ai, &ax, names, gnd, &branches)).filter(|report| !report.is_empty())
+        } else {
+            None
+        };
+
+        ConvergenceReport {
+            worst_residual,
+            largest_stamp_change,
+            last_dx: node_values(&diagnostics.dx),
+            singular,
+        }
+    }
+
+    /// 直流 Newton 求解不收敛时的诊断
+    fn dc_convergence_report(&self, result: &NewtonResult) -> Option<ConvergenceReport> {
+        let diagnostics = result.diagnostics.as_ref()?;
+        Some(self.convergence_report(diagnostics, |device, ctx, x| {
+            let _ = device.stamp_dc(ctx, Some(x));
+        }))
+    }
+
+    /// 已解析但引擎尚未实现的分析: 返回 Failed 而不是悄悄改跑 OP
+    fn unsupported_result(&self, cmd: &crate::circuit::AnalysisCmd) -> RunResult {
+        self.failed_result(format!("analysis not supported yet: {:?}", cmd))
+    }
+
+    fn failed_result(&self, message: String) -> RunResult {
+        RunResult {
+            id: RunId(0),
+            analysis: AnalysisType::Op,
+            status: RunStatus::Failed,
+            iterations: 0,
+            node_names: self.circuit.nodes.id_to_name.clone(),
+            solution: Vec::new(),
+            message: Some(message),
+            sweep_var: None,
+            sweep_values: Vec::new(),
+            sweep_solutions: Vec::new(),
+            sweep_outer_var: None,
+            sweep_outer_values: Vec::new(),
+            tran_times: Vec::new(),
+            tran_solutions: Vec::new(),
+            ac_frequencies: Vec::new(),
+            ac_solutions: Vec::new(),
+            ac_phasors: Vec::new(),
+            output_params: Vec::new(),
+            measurements: Vec::new(),
+            branch_names: Vec::new(),
+            device_op: Vec::new(),
+            homotopy: Vec::new(),
+            convergence: None,
+            sparams: None,
+            noise: None,
+            pac: None,
+            sampled_params: Vec::new(),
+        }
     }
 
     pub fn run_dc(&mut self) {
         let _ = self.run_dc_result(AnalysisType::Op);
     }
 
+    /// 自创建以来各次求解 (工作点与瞬态时间步) 累计的器件旁路统计; 未开启 `.options bypass` 时为 0
+    pub fn bypass_stats(&self) -> BypassStats {
+        self.bypass_stats
+    }
+
+    /// 自创建以来实数 MNA 矩阵结构重新建立与复用的次数
+    pub fn pattern_stats(&self) -> PatternStats {
+        self.workspace.pattern.stats
+    }
+
+    /// 交互调参: 按 `changes` 修改取值后, 从上一次收敛的工作点出发重新求解 OP 并入库。
+    /// 名字与 DC 扫描相同, 可以是器件名、`@<器件>[<参数>]` 或 `.param` 参数名; 修改保留在电路中。
+    /// 求解器不重建, 矩阵结构不变时沿用已有的符号分解。
+    /// 任一名字无法解析时不做任何修改并返回 Err
+    pub fn tune_op(&mut self, changes: &[(&str, f64)], store: &mut ResultStore) -> Result<WarmStartOp, String> {
+        let mut targets = Vec::with_capacity(changes.len());
+        for (name, value) in changes {
+            let spec = DcSweepSpec {
+                kind: DcSweepKind::Source,
+                source: name.to_string(),
+                start: *value,
+                stop: *value,
+                step: 0.0,
+            };
+            targets.push((self.resolve_sweep_target(&spec)?, *value));
+        }
+        // 参数会重新展开网表并覆盖器件取值, 因此先于器件取值应用
+        targets.sort_by_key(|(target, _)| !matches!(target, SweepTarget::Param(_)));
+        for (target, value) in &targets {
+            self.apply_sweep_value(target, *value)?;
+        }
+
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let start = self.last_op.clone().filter(|op| op.solution.len() >= node_count);
+        let warm = start.is_some();
+        let cold_iterations = start.as_ref().map(|op| op.cold_iterations);
+        let result = self.solve_op(AnalysisType::Op, start);
+        let iterations = result.iterations;
+        let result = self.finish_result(result);
+        let run_id = store.add_run(result);
+        Ok(WarmStartOp {
+            run_id,
+            warm,
+            iterations,
+            iterations_saved: cold_iterations.map_or(0, |cold| cold.saturating_sub(iterations)),
+        })
+    }
+
+    /// 瞬态灵敏度 (直接法): 跑一次标称瞬态并入库, 沿它的每个时间步在收敛解处分解的 Jacobian 上
+    /// 解灵敏度方程, 得各输出对各参数的偏导数波形。参数名与 DC 扫描相同
+    /// (器件名、`@<器件>[<参数>]`、数值 `.param` 或 `temp`), 输出为 `v(n)` / `v(a,b)` / `i(dev)`;
+    /// `delta` 为对残差求 ∂F/∂p 时的相对扰动步长。结束后电路保持标称
+    pub fn run_tran_sensitivity(
+        &mut self,
+        plan: &AnalysisPlan,
+        outputs: &[&str],
+        params: &[&str],
+        delta: f64,
+        store: &mut ResultStore,
+    ) -> Result<TranSensitivity, String> {
+        let crate::circuit::AnalysisCmd::Tran { tstep, tstop, tstart, tmax, uic, ref windows } = plan.cmd else {
+            return Err("transient sensitivity needs a .tran analysis".to_string());
+        };
+        let signals = outputs
+            .iter()
+            .map(|output| Signal::parse(output).ok_or_else(|| format!("invalid sensitivity output '{}'", output)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut targets = Vec::with_capacity(params.len());
+        let mut nominal = Vec::with_capacity(params.len());
+        for name in params {
+            let spec = DcSweepSpec {
+                kind: if name.eq_ignore_ascii_case("temp") { DcSweepKind::Temp } else { DcSweepKind::Source },
+                source: name.to_string(),
+                start: 0.0,
+                stop: 0.0,
+                step: 0.0,
+            };
+            let target = self.resolve_sweep_target(&spec)?;
+            nominal.push(self.sweep_target_value(&target).ok_or_else(|| format!("'{}' has no numeric value", name))?);
+            targets.push(target);
+        }
+
+        // 灵敏度要沿全部已接受的时间步推进: 标称运行从 t=0 起保存, 入库前再去掉 tstart 之前的点
+        let mut result = self.run_tran_result_with_params(tstep, tstop, 0.0, tmax, uic, windows);
+        if !matches!(result.status, RunStatus::Converged) {
+            return Err(format!("nominal transient failed: {}", result.message.unwrap_or_default()));
+        }
+        let mut states = self.tran_state_sensitivities(&result, &targets, &nominal, delta, uic)?;
+        let skipped = result.tran_times.iter().take_while(|time| **time < tstart).count();
+        result.tran_times.drain(..skipped);
+        result.tran_solutions.drain(..skipped);
+        let result = self.finish_result(result);
+        let times = result.tran_times.clone();
+        for signal in &signals {
+            sample_signal(&result, signal, &times)?;
+        }
+
+        // 输出都是解向量分量的线性组合, 对解向量灵敏度取同样的分量即得输出灵敏度
+        let mut view = result.clone();
+        let mut values = vec![Vec::with_capacity(params.len()); signals.len()];
+        for per_param in &mut states {
+            view.tran_solutions = per_param.split_off(skipped);
+            for (signal, per_output) in signals.iter().zip(&mut values) {
+                per_output.push(sample_signal(&view, signal, &times)?);
+            }
+        }
+        store.add_run(result);
+        Ok(TranSensitivity {
+            times,
+            outputs: outputs.iter().map(|output| output.to_string()).collect(),
+            params: params.iter().map(|param| param.to_string()).collect(),
+            nominal,
+            values,
+        })
+    }
+
+    /// 沿标称瞬态 `result` (从 t=0 起保存全部时间点) 的每一步在收敛解 x 处建立 Jacobian J 并分解一次,
+    /// 对每个参数解 J·s = −dF/dp, 返回 `[参数][时间点]` 的解向量灵敏度 ∂x/∂p。
+    /// dF/dp 取参数为 p ± h 的电路在同一 x 处残差的中心差分, 两侧电路的积分历史分别沿 x ± h·s 推进,
+    /// 因此含历史项随参数的变化; 起点为直流工作点的灵敏度 (UIC 时为 0)
+    fn tran_state_sensitivities(
+        &mut self,
+        result: &RunResult,
+        targets: &[SweepTarget],
+        nominal: &[f64],
+        delta: f64,
+        uic: bool,
+    ) -> Result<Vec<Vec<Vec<f64>>>, String> {
+        let (times, solutions) = (&result.tran_times, &result.tran_solutions);
+        let Some(x0) = solutions.first() else {
+            return Ok(vec![Vec::new(); targets.len()]);
+        };
+        // 每个参数两侧各一个扰动电路
+        let mut sides = Vec::with_capacity(2 * targets.len());
+        for (target, value) in targets.iter().zip(nominal) {
+            let h = perturbation(*value, delta);
+            for side in [h, -h] {
+                let mut engine = Engine::new(self.circuit.clone(), self.solver_type);
+                engine.apply_sweep_value(target, value + side)?;
+                engine.compile_instances();
+                sides.push((engine, side));
+            }
+        }
+        self.compile_instances();
+
+        let mut sensitivities: Vec<Vec<Vec<f64>>> = vec![Vec::with_capacity(times.len()); targets.len()];
+        let initial = if uic {
+            vec![vec![0.0; x0.len()]; targets.len()]
+        } else {
+            let parts = self.linearize_at(x0, None);
+            let residuals = sides.iter_mut().map(|(engine, _)| engine.linearize_at(x0, None).1).collect::<Vec<_>>();
+            self.solve_sensitivities(&parts, &residuals, &sides)?
+        };
+        let mut state = self.replay_state(x0, uic)?;
+        let mut side_states = Vec::with_capacity(sides.len());
+        for ((engine, side), s) in sides.iter().zip(initial.iter().flat_map(|s| [s, s])) {
+            side_states.push(engine.replay_state(&shifted(x0, s, *side), uic)?);
+        }
+        for (per_param, s) in sensitivities.iter_mut().zip(initial) {
+            per_param.push(s);
+        }
+
+        for (k, x) in solutions.iter().enumerate().skip(1) {
+            let (time, dt) = (times[k], times[k] - times[k - 1]);
+            let parts = self.linearize_at(x, Some((&mut state, dt)));
+            let residuals = sides
+                .iter_mut()
+                .zip(&mut side_states)
+                .map(|((engine, _), side_state)| engine.linearize_at(x, Some((side_state, dt))).1)
+                .collect::<Vec<_>>();
+            let step = self
+                .solve_sensitivities(&parts, &residuals, &sides)
+                .map_err(|message| format!("{} at t={:e}", message, time))?;
+            let per_side = step.iter().flat_map(|s| [s, s]);
+            for (((engine, side), side_state), s) in sides.iter().zip(&mut side_states).zip(per_side) {
+                engine.accept_tran_step(&shifted(x, s, *side), side_state, time, dt);
+            }
+            self.accept_tran_step(x, &mut state, time, dt);
+            for (per_param, s) in sensitivities.iter_mut().zip(step) {
+                per_param.push(s);
+            }
+        }
+        Ok(sensitivities)
+    }
+
+    /// 分解 `parts` 的矩阵 (即 Jacobian), 对每个参数用两侧残差的中心差分作右端解出 ∂x/∂p
+    fn solve_sensitivities(
+        &mut self,
+        parts: &(MnaParts, Vec<f64>),
+        residuals: &[Vec<f64>],
+        sides: &[(Engine, f64)],
+    ) -> Result<Vec<Vec<f64>>, String> {
+        let ((ap, ai, ax, _, n), _) = parts;
+        self.solver.prepare(*n);
+        self.solver
+            .analyze(ap, ai)
+            .and_then(|_| self.solver.factor(ap, ai, ax))
+            .map_err(|err| format!("sensitivity Jacobian could not be factored: {:?}", err))?;
+        residuals
+            .chunks(2)
+            .zip(sides.chunks(2))
+            .map(|(pair, side)| {
+                let width = side[0].1 - side[1].1;
+                let mut rhs: Vec<f64> = pair[1].iter().zip(&pair[0]).map(|(down, up)| (down - up) / width).collect();
+                rhs.resize(*n, 0.0);
+                self.solver
+                    .solve(&mut rhs)
+                    .map_err(|err| format!("sensitivity solve failed: {:?}", err))?;
+                Ok(rhs)
+            })
+            .collect()
+    }
+
+    /// 在解 `x` 处建立一次 MNA (不做结电压限幅): `step` 为瞬态的状态与步长, None 为直流工作点。
+    /// 返回各部分与残差 A·x − b; 矩阵即 Newton 在 `x` 处的 Jacobian
+    fn linearize_at(&mut self, x: &[f64], step: Option<(&mut TransientState, f64)>) -> (MnaParts, Vec<f64>) {
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let gnd = self.circuit.nodes.gnd_id.0;
+        let gmin = match step {
+            Some(_) => self.transient_newton_config().gmin,
+            None => self.newton_config().gmin,
+        };
+        let mna = self.workspace.begin(node_count);
+        match step {
+            Some((state, dt)) => {
+                for (inst, device) in self.circuit.instances.instances.iter().zip(&self.compiled) {
+                    let _ = device.stamp_tran(inst, &mut mna.context_with(gmin, 1.0), Some(x), dt, state);
+                }
+            }
+            None => {
+                for (inst, device) in self.circuit.instances.instances.iter().zip(&self.compiled) {
+                    let _ = device.stamp_dc(inst, &mut mna.context_with(gmin, 1.0), Some(x));
+                }
+            }
+        }
+        mna.builder.insert(gnd, gnd, 1.0);
+        let parts = self.workspace.finish();
+        let residual = residual(&parts.0, &parts.1, &parts.2, &parts.3, x);
+        (parts, residual)
+    }
+
+    /// 瞬态起点 `x0` 处的积分状态, 与 [`Engine::run_tran_result_with_params`] 的初始化相同 (不带旁路)
+    fn replay_state(&self, x0: &[f64], uic: bool) -> Result<TransientState, String> {
+        let mut state = self.new_transient_state()?;
+        state.bypass = None;
+        state.time = 0.0;
+        let instances = &self.circuit.instances.instances;
+        if uic {
+            apply_initial_conditions(instances, x0, &mut state);
+        } else {
+            update_transient_state(instances, x0, &mut state);
+        }
+        state.start_history();
+        Ok(state)
+    }
+
+    /// 把上一次收敛的工作点 (节点电压、支路电流与结电压) 按名字写入 `path`,
+    /// 之后同一电路的运行可用 [`Engine::load_op`] 读入作为初值
+    pub fn save_op(&self, path: &Path) -> std::io::Result<()> {
+        let op = self.last_op.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no converged operating point to save")
+        })?;
+        let names = &self.circuit.nodes.id_to_name;
+        let gnd = self.circuit.nodes.gnd_id.0;
+        let nodes = names
+            .iter()
+            .zip(&op.solution)
+            .enumerate()
+            .filter(|(idx, _)| *idx != gnd)
+            .map(|(_, (name, value))| (name.clone(), *value))
+            .collect();
+        let branches = self
+            .branch_names(AnalysisType::Op)
+            .into_iter()
+            .zip(op.solution[names.len().min(op.solution.len())..].iter().copied())
+            .collect();
+        let mut junctions: Vec<(String, f64)> =
+            op.junctions.voltages.iter().map(|(key, value)| (key.clone(), *value)).collect();
+        junctions.sort_by(|a, b| a.0.cmp(&b.0));
+        write_op(&SavedOp { nodes, branches, junctions }, path)
+    }
+
+    /// 读入 [`Engine::save_op`] 写出的工作点, 作为之后 OP 与瞬态初始工作点求解的 Newton 初值;
+    /// 按名字对应到当前电路, 电路中没有的名字忽略。返回对上的取值个数
+    pub fn load_op(&mut self, path: &Path) -> std::io::Result<usize> {
+        let saved = read_op(path)?;
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let branches = self.branch_names(AnalysisType::Op);
+        let mut solution = vec![0.0; node_count + branches.len()];
+        let mut matched = 0;
+        for (name, value) in &saved.nodes {
+            if let Some(id) = self.circuit.nodes.lookup(name) {
+                solution[id.0] = *value;
+                matched += 1;
+            }
+        }
+        for (name, value) in &saved.branches {
+            if let Some(idx) = branches.iter().position(|branch| branch == name) {
+                solution[node_count + idx] = *value;
+                matched += 1;
+            }
+        }
+        solution[self.circuit.nodes.gnd_id.0] = 0.0;
+        let mut junctions = JunctionState::default();
+        for (key, value) in &saved.junctions {
+            let device = key.split(':').next().unwrap_or(key);
+            if self.circuit.instances.find(device).is_some() {
+                junctions.voltages.insert(key.clone(), *value);
+                matched += 1;
+            }
+        }
+        self.initial_op = Some(OpSnapshot {
+            solution,
+            junctions,
+            cold_iterations: 0,
+        });
+        Ok(matched)
+    }
+
     pub fn run_tran(&mut self) {
-        // Use default parameters for standalone run
-        let _ = self.run_tran_result_with_params(1e-6, 1e-5, 0.0, 1e-5);
+        // 按网表中的 .tran 运行, 没有时用缺省参数
+        let tran = self.circuit.analysis.iter().find_map(|cmd| match cmd {
+            crate::circuit::AnalysisCmd::Tran { tstep, tstop, tstart, tmax, uic, windows } => {
+                Some((*tstep, *tstop, *tstart, *tmax, *uic, windows.clone()))
+            }
+            _ => None,
+        });
+        let (tstep, tstop, tstart, tmax, uic, windows) = tran.unwrap_or((1e-6, 1e-5, 0.0, 1e-6, false, Vec::new()));
+        let _ = self.run_tran_result_with_params(tstep, tstop, tstart, tmax, uic, &windows);
     }
 
     fn run_dc_result(&mut self, analysis: AnalysisType) -> RunResult {
-        let config = NewtonConfig::default();
+        self.solve_op(analysis, self.initial_op.clone())
+    }
+
+    /// 求解直流工作点; `start` 为 None 时从零出发 (冷启动), 否则从给定的解与结电压出发。
+    /// 收敛的解记为下一次热启动的起点
+    fn solve_op(&mut self, analysis: AnalysisType, start: Option<OpSnapshot>) -> RunResult {
+        let config = self.newton_config();
         let node_count = self.circuit.nodes.id_to_name.len();
-        let mut x = vec![0.0; node_count];
+        let cold = start.is_none();
+        let (mut x, mut junctions, cold_iterations) = match start {
+            Some(op) => (op.solution, op.junctions, op.cold_iterations),
+            None => (vec![0.0; node_count], JunctionState::default(), 0),
+        };
+        self.compile_instances();
         self.solver.prepare(node_count);
         let gnd = self.circuit.nodes.gnd_id.0;
-        let result = run_newton_with_stepping(&config, &mut x, |x, gmin, source_scale| {
-            let mut mna = MnaBuilder::new(node_count);
-            for inst in &self.circuit.instances.instances {
-                let stamp = InstanceStamp {
-                    instance: inst.clone(),
-                };
-                let mut ctx = mna.context_with(gmin, source_scale);
-                let _ = stamp.stamp_dc(&mut ctx, Some(x));
+        let mut bypass = BypassConfig::from_options(&self.circuit.options).map(BypassCache::new);
+        let result = run_newton_with_limiting(&config, &mut x, &mut junctions, |x, gmin, source_scale, junctions| {
+            let mna = self.workspace.begin(node_count);
+            for (inst, device) in self.circuit.instances.instances.iter().zip(&self.compiled) {
+                let mut ctx = mna.context_with_junctions(gmin, source_scale, junctions);
+                stamp_with_bypass(bypass.as_mut(), inst, &mut ctx, x, 0.0, |ctx| {
+                    let _ = device.stamp_dc(inst, ctx, Some(x));
+                });
             }
             // 固定地节点，避免矩阵奇异
             mna.builder.insert(gnd, gnd, 1.0);
-            let (ap, ai, ax) = mna.builder.finalize();
-            (ap, ai, ax, mna.rhs, mna.builder.n)
+            self.workspace.finish()
         }, self.solver.as_mut());
+        self.record_bypass(bypass);
 
         debug_dump_newton_with_tag("dc", &result);
         let status = match result.reason {
@@ -105,6 +966,22 @@ impl Engine {
             crate::newton::NewtonExitReason::MaxIters => RunStatus::MaxIters,
             crate::newton::NewtonExitReason::SolverFailure => RunStatus::Failed,
         };
+        if matches!(status, RunStatus::Converged) {
+            self.last_op = Some(OpSnapshot {
+                solution: x.clone(),
+                junctions,
+                cold_iterations: if cold { result.iterations } else { cold_iterations },
+            });
+        }
+        let convergence = self.dc_convergence_report(&result);
+        let message = match &convergence {
+            Some(report) => Some(format!(
+                "{}: {}",
+                result.message.as_deref().unwrap_or("operating point failed to converge"),
+                report.summary()
+            )),
+            None => result.message,
+        };
         RunResult {
             id: RunId(0),
             analysis,
@@ -116,28 +993,45 @@ impl Engine {
             } else {
                 Vec::new()
             },
-            message: result.message,
+            message,
             sweep_var: None,
             sweep_values: Vec::new(),
             sweep_solutions: Vec::new(),
+            sweep_outer_var: None,
+            sweep_outer_values: Vec::new(),
             tran_times: Vec::new(),
             tran_solutions: Vec::new(),
             ac_frequencies: Vec::new(),
             ac_solutions: Vec::new(),
+            ac_phasors: Vec::new(),
+            output_params: Vec::new(),
+            measurements: Vec::new(),
+            branch_names: Vec::new(),
+            device_op: Vec::new(),
+            homotopy: result.homotopy,
+            convergence,
+            sparams: None,
+            noise: None,
+            pac: None,
+            sampled_params: Vec::new(),
         }
     }
 
     /// Run TRAN analysis with specified parameters and store waveform data
     ///
-    /// This function performs transient analysis from `tstart` to `tstop` using
+    /// This function performs transient analysis from 0 to `tstop` using
     /// adaptive time stepping. It stores the solution at each accepted time point
-    /// in `tran_times` and `tran_solutions`.
+    /// from `tstart` on in `tran_times` and `tran_solutions`; both `tstart` and
+    /// `tstop` are breakpoints, so the stored waveform starts and ends exactly there.
     ///
     /// # Arguments
-    /// * `tstep` - Suggested time step for output
+    /// * `tstep` - Suggested time step for output; also the first internal step
     /// * `tstop` - Stop time
-    /// * `tstart` - Start time (usually 0)
-    /// * `tmax` - Maximum internal time step
+    /// * `tstart` - Time from which results are stored (usually 0)
+    /// * `tmax` - Maximum internal time step (non-positive means `tstep`)
+    /// * `uic` - Skip the initial operating point and start from `.ic` / IC= values
+    /// * `windows` - Optional time windows; inside each the internal step is capped at
+    ///   the window's `tstep` and window ends are breakpoints
     ///
     /// # Returns
     /// RunResult containing:
@@ -150,30 +1044,47 @@ impl Engine {
         tstop: f64,
         tstart: f64,
         tmax: f64,
+        uic: bool,
+        windows: &[TranWindow],
     ) -> RunResult {
         let node_count = self.circuit.nodes.id_to_name.len();
         let mut x = vec![0.0; node_count];
-        let mut state = TransientState::default();
+        let mut junctions = JunctionState::default();
+        self.compile_instances();
         self.solver.prepare(node_count);
+        let mut state = match self.new_transient_state() {
+            Ok(state) => state,
+            Err(message) => return self.tran_failure(message),
+        };
 
+        if !(tstep > 0.0 && tstop > tstart && tstart >= 0.0) {
+            return self.tran_failure(format!(
+                "invalid .tran parameters: tstep={} tstop={} tstart={}",
+                tstep, tstop, tstart
+            ));
+        }
+        let tmax = if tmax > 0.0 { tmax } else { tstep };
+        let (abs_tol, rel_tol) = TimeStepConfig::tolerances(&self.circuit.options);
         let config = TimeStepConfig {
             tstep,
             tstop,
             tstart,
             tmax,
-            min_dt: tstep * 1e-6,  // Minimum step is 1e-6 of tstep
+            // Minimum step is 1e-6 of the smallest tstep
+            min_dt: windows.iter().fold(tstep, |min, window| min.min(window.tstep)) * 1e-6,
             max_dt: tmax,
-            abs_tol: 1e-9,
-            rel_tol: 1e-6,
+            abs_tol,
+            rel_tol,
         };
 
         let mut step_state = TimeStepState {
-            time: config.tstart,
+            time: 0.0,
             step: 0,
             dt: config.tstep.min(config.tmax),
             last_dt: config.tstep,
             accepted: true,
         };
+        state.time = 0.0;
 
         let mut final_status = RunStatus::Converged;
         let gnd = self.circuit.nodes.gnd_id.0;
@@ -182,107 +1093,191 @@ impl Engine {
         let mut tran_times: Vec<f64> = Vec::new();
         let mut tran_solutions: Vec<Vec<f64>> = Vec::new();
 
-        // Run initial DC operating point (t=tstart)
-        let dc_result = run_newton_with_stepping(&NewtonConfig::default(), &mut x, |x, gmin, source_scale| {
-            let mut mna = MnaBuilder::new(node_count);
-            for inst in &self.circuit.instances.instances {
-                let stamp = InstanceStamp {
-                    instance: inst.clone(),
-                };
-                let mut ctx = mna.context_with(gmin, source_scale);
-                let _ = stamp.stamp_dc(&mut ctx, Some(x));
+        // UIC 下没有给定初值的状态量, 随结果报告
+        let mut note = None;
+        let mut homotopy = Vec::new();
+        let mut convergence = None;
+        if uic {
+            // UIC: 不求初始工作点, 节点电压取 .ic 值 (未给定为 0)
+            for (node, value) in &self.circuit.initial_conditions {
+                if let Some(slot) = x.get_mut(node.0) {
+                    *slot = *value;
+                }
             }
-            mna.builder.insert(gnd, gnd, 1.0);
-            let (ap, ai, ax) = mna.builder.finalize();
-            (ap, ai, ax, mna.rhs, mna.builder.n)
-        }, self.solver.as_mut());
-
-        debug_dump_newton_with_tag("tran_dc_op", &dc_result);
+            x[gnd] = 0.0;
+            apply_initial_conditions(&self.circuit.instances.instances, &x, &mut state);
+            let defaulted = defaulted_initial_states(&self.circuit.instances.instances, &self.circuit.initial_conditions);
+            if !defaulted.is_empty() {
+                note = Some(format!("UIC: no initial condition for {}; started from 0", defaulted.join(", ")));
+            }
+        } else {
+            // Run initial DC operating point (t=0)
+            let dc_result = self.initial_operating_point(&mut x, &mut junctions);
+            debug_dump_newton_with_tag("tran_dc_op", &dc_result);
+
+            if !dc_result.converged {
+                let convergence = self.dc_convergence_report(&dc_result);
+                let summary = convergence.as_ref().map(ConvergenceReport::summary).unwrap_or_default();
+                let mut failure = self.tran_failure(format!("DC operating point failed to converge: {}", summary));
+                failure.homotopy = dc_result.homotopy;
+                failure.convergence = convergence;
+                return failure;
+            }
+            homotopy = dc_result.homotopy;
 
-        if !dc_result.converged {
-            return RunResult {
-                id: RunId(0),
-                analysis: AnalysisType::Tran,
-                status: RunStatus::Failed,
-                iterations: 0,
-                node_names: self.circuit.nodes.id_to_name.clone(),
-                solution: Vec::new(),
-                message: Some("DC operating point failed to converge".to_string()),
-                sweep_var: None,
-                sweep_values: Vec::new(),
-                sweep_solutions: Vec::new(),
-                tran_times: Vec::new(),
-                tran_solutions: Vec::new(),
-                ac_frequencies: Vec::new(),
-                ac_solutions: Vec::new(),
-            };
+            // Initialize transient state from DC solution
+            update_transient_state(&self.circuit.instances.instances, &x, &mut state);
         }
+        state.start_history();
 
-        // Store initial point (t=tstart)
-        tran_times.push(config.tstart);
-        tran_solutions.push(x.clone());
+        // .options ssdetect: 节点电压稳定后提前结束
+        let mut steady = SteadyStateConfig::from_options(&self.circuit.options).map(SteadyStateDetector::new);
 
-        // Initialize transient state from DC solution
-        update_transient_state(&self.circuit.instances.instances, &x, &mut state);
+        // Store initial point when saving starts at t=0
+        if config.tstart == 0.0 {
+            tran_times.push(0.0);
+            tran_solutions.push(x.clone());
+            if let Some(steady) = steady.as_mut() {
+                steady_after_sources(steady, &state, 0.0, &x[..node_count]);
+            }
+        }
 
         // Time stepping loop
+        // UIC 初始向量并非电路解, 首步不做误差比较
+        // 噪声源在 t=0 之后才加上第一个采样, 第一步同样不连续; 源波形的起点也按断点处理
+        let mut skip_error_check = uic || !state.trnoise.is_empty() || !state.waveforms.is_empty();
+        // 因器件切换被截短的步原本的终点
+        let mut resume_at: Option<f64> = None;
         while step_state.time < config.tstop {
+            // 数字事件、噪声采样时刻与源波形的转折点是断点: 步长截到最近的一个;
+            // 仅超出步长不到 min_dt 的断点同样落上去, 以免留下舍入误差大小的碎步
+            let noise_sample = state.next_noise_sample(step_state.time);
+            let breakpoint = state.next_breakpoint(step_state.time);
+            // TSTART (开始保存) 与 TSTOP 同样是断点
+            let window = [config.tstart, config.tstop].into_iter().find(|t| *t > step_state.time);
+            // 多窗口: 当前窗口内步长不超过该窗口的 tstep, 窗口终点也是断点
+            let tran_window = windows.iter().find(|window| window.tstop > step_state.time);
+            if let Some(tran_window) = tran_window {
+                step_state.dt = step_state.dt.min(tran_window.tstep);
+            }
+            let window_end = tran_window.map(|window| window.tstop);
+            let mut event = [
+                state.digital.next_event(step_state.time),
+                noise_sample,
+                breakpoint,
+                window,
+                window_end,
+                resume_at,
+            ]
+                .into_iter()
+                .flatten()
+                .filter(|event| *event < step_state.time + step_state.dt + config.min_dt)
+                .min_by(|a, b| a.total_cmp(b));
+            if let Some(event) = event {
+                step_state.dt = event - step_state.time;
+            }
             let mut x_iter = x.clone();
-            let result = run_newton_with_stepping(&NewtonConfig::default(), &mut x_iter, |x, gmin, source_scale| {
-                let mut mna = MnaBuilder::new(node_count);
-                for inst in &self.circuit.instances.instances {
-                    let stamp = InstanceStamp {
-                        instance: inst.clone(),
-                    };
-                    let mut ctx = mna.context_with(gmin, source_scale);
-                    let _ = stamp.stamp_tran(
-                        &mut ctx,
-                        Some(x),
-                        step_state.dt,
-                        &mut state,
-                    );
-                }
-                mna.builder.insert(gnd, gnd, 1.0);
-                let (ap, ai, ax) = mna.builder.finalize();
-                (ap, ai, ax, mna.rhs, mna.builder.n)
-            }, self.solver.as_mut());
-
+            let result = self.tran_newton(&mut x_iter, &mut state, &mut junctions, step_state.dt);
             debug_dump_newton_with_tag("tran", &result);
 
             if !result.converged {
                 // Reduce time step and retry
-                step_state.dt = (step_state.dt * 0.5).max(config.min_dt);
-                if step_state.dt <= config.min_dt {
+                let retry_dt = (step_state.dt * 0.5).max(config.min_dt);
+                if retry_dt <= config.min_dt {
+                    // 步长已缩到下限: 按最后尝试的这一步报告诊断
                     final_status = RunStatus::Failed;
+                    if let Some(diagnostics) = &result.diagnostics {
+                        let report = self.convergence_report(diagnostics, |device, ctx, x| {
+                            let _ = device.stamp_tran(ctx, Some(x), step_state.dt, &mut state.clone());
+                        });
+                        let failure = format!(
+                            "transient step failed to converge at t={:e} (dt={:e}): {}",
+                            step_state.time,
+                            step_state.dt,
+                            report.summary()
+                        );
+                        note = Some(note.map_or(failure.clone(), |note| format!("{}; {}", failure, note)));
+                        convergence = Some(report);
+                    }
                     break;
                 }
+                step_state.dt = retry_dt;
                 continue;
             }
 
             let ErrorEstimate { accept, .. } =
                 estimate_error_weighted(&x, &x_iter, config.abs_tol, config.rel_tol);
+            let step_end = event.unwrap_or(step_state.time + step_state.dt);
+            // 步长已缩到下限时无法再缩, 接受这一步 (否则快速边沿上会反复以 min_dt 重试);
+            // 不足两倍下限的步也一样: 减半后会被 `min_dt` 范围内的断点重新拉回原长
+            let accept = accept
+                || skip_error_check
+                || step_state.dt <= 2.0 * config.min_dt
+                || resume_at.is_some_and(|end| step_end <= end);
+            skip_error_check = false;
             step_state.accepted = accept;
 
+            // 开关或 B 源的比较在这一步内发生切换: 二分出切换时刻, 改为刚好越过它的一步;
+            // 原步已被接受, 其余部分直到原步终点都不再做误差比较
+            let mut switched = false;
+            if accept {
+                let instances = &self.circuit.instances.instances;
+                if switching_conditions(instances, &x, &state) != switching_conditions(instances, &x_iter, &state) {
+                    let tol = (config.tstep * 1e-4).max(config.min_dt);
+                    let (dt, x_event) =
+                        self.bisect_switching(&x, x_iter, &mut state, &mut junctions, step_state.dt, tol);
+                    if dt < step_state.dt {
+                        resume_at = Some(resume_at.map_or(step_end, |end: f64| end.max(step_end)));
+                        event = None;
+                    }
+                    step_state.dt = dt;
+                    x_iter = x_event;
+                    switched = true;
+                }
+            }
+
             if accept {
                 x = x_iter;
-                update_transient_state(&self.circuit.instances.instances, &x, &mut state);
-                step_state.time += step_state.dt;
+                step_state.time = event.unwrap_or(step_state.time + step_state.dt);
+                if resume_at.is_some_and(|end| step_state.time >= end) {
+                    resume_at = None;
+                }
+                self.accept_tran_step(&x, &mut state, step_state.time, step_state.dt);
+                // 数字线网刚发生跳变、噪声换到下一个采样、源波形转折或开关切换, 下一步与当前解不连续
+                skip_error_check = state.digital.take_fired()
+                    || switched
+                    || (event.is_some() && (event == noise_sample || event == breakpoint));
                 step_state.step += 1;
                 step_state.last_dt = step_state.dt;
 
                 // Store accepted time point and solution
-                tran_times.push(step_state.time);
-                tran_solutions.push(x.clone());
+                if step_state.time >= config.tstart {
+                    tran_times.push(step_state.time);
+                    tran_solutions.push(x.clone());
+                    let settled = |steady: &mut SteadyStateDetector| {
+                        steady_after_sources(steady, &state, step_state.time, &x[..node_count])
+                    };
+                    if steady.as_mut().is_some_and(settled) {
+                        let stopped = format!("steady state reached at t={:e}; transient stopped early", step_state.time);
+                        note = Some(note.map_or(stopped.clone(), |note| format!("{}; {}", stopped, note)));
+                        break;
+                    }
+                }
 
                 // Increase time step for next iteration (adaptive stepping)
                 if step_state.dt < config.max_dt {
                     step_state.dt = (step_state.dt * 1.5).min(config.max_dt);
                 }
+                // 事件之后的模拟响应要尽快被数字器件看到, 步长不超过 tstep
+                if skip_error_check {
+                    step_state.dt = step_state.dt.min(config.tstep);
+                }
             } else {
                 // Reduce time step and retry
                 step_state.dt = (step_state.dt * 0.5).max(config.min_dt);
             }
         }
+        self.record_bypass(state.bypass.take());
 
         RunResult {
             id: RunId(0),
@@ -291,144 +1286,765 @@ impl Engine {
             iterations: step_state.step,
             node_names: self.circuit.nodes.id_to_name.clone(),
             solution: x,  // Final solution
-            message: None,
+            message: note,
             sweep_var: None,
             sweep_values: Vec::new(),
             sweep_solutions: Vec::new(),
+            sweep_outer_var: None,
+            sweep_outer_values: Vec::new(),
             tran_times,
             tran_solutions,
             ac_frequencies: Vec::new(),
             ac_solutions: Vec::new(),
+            ac_phasors: Vec::new(),
+            output_params: Vec::new(),
+            measurements: Vec::new(),
+            branch_names: Vec::new(),
+            device_op: Vec::new(),
+            homotopy,
+            convergence,
+            sparams: None,
+            noise: None,
+            pac: None,
+            sampled_params: Vec::new(),
         }
     }
 
-    /// Run DC sweep analysis
-    /// Sweeps the specified source from start to stop with given step size
-    fn run_dc_sweep_result(&mut self, source: &str, start: f64, stop: f64, step: f64) -> RunResult {
-        let config = NewtonConfig::default();
+    /// 按 `.options method=` 选好积分器的瞬态状态
+    fn new_transient_state(&self) -> Result<TransientState, String> {
+        let mut state = TransientState::default();
+        // .options method=trap / gear 选择二阶积分, 缺省为后向欧拉
+        if let Some(method) = self.circuit.options.get("method") {
+            state.integrator =
+                integrator_from_option(method).ok_or_else(|| format!("unknown integration method '{}'", method))?;
+        }
+        state.bypass = BypassConfig::from_options(&self.circuit.options).map(BypassCache::new);
+        Ok(state)
+    }
+
+    /// 瞬态起点的直流工作点 (带 `.options` 中的同伦回退); `junctions` 留给之后的时间步
+    fn initial_operating_point(&mut self, x: &mut Vec<f64>, junctions: &mut JunctionState) -> NewtonResult {
         let node_count = self.circuit.nodes.id_to_name.len();
         let gnd = self.circuit.nodes.gnd_id.0;
+        let config = self.newton_config();
+        let mut bypass = BypassConfig::from_options(&self.circuit.options).map(BypassCache::new);
+        if let Some(op) = &self.initial_op {
+            x.clone_from(&op.solution);
+            junctions.clone_from(&op.junctions);
+        }
+        self.compile_instances();
+        let result = run_newton_with_limiting(&config, x, junctions, |x, gmin, source_scale, junctions| {
+            let mna = self.workspace.begin(node_count);
+            for (inst, device) in self.circuit.instances.instances.iter().zip(&self.compiled) {
+                let mut ctx = mna.context_with_junctions(gmin, source_scale, junctions);
+                stamp_with_bypass(bypass.as_mut(), inst, &mut ctx, x, 0.0, |ctx| {
+                    let _ = device.stamp_dc(inst, ctx, Some(x));
+                });
+            }
+            mna.builder.insert(gnd, gnd, 1.0);
+            self.workspace.finish()
+        }, self.solver.as_mut());
+        self.record_bypass(bypass);
+        result
+    }
 
-        // Find the source instance index
-        let source_lower = source.to_ascii_lowercase();
-        let source_idx = self.circuit.instances.instances.iter()
-            .position(|inst| inst.name.to_ascii_lowercase() == source_lower);
-
-        if source_idx.is_none() {
-            return RunResult {
-                id: RunId(0),
-                analysis: AnalysisType::Dc,
-                status: RunStatus::Failed,
-                iterations: 0,
-                node_names: self.circuit.nodes.id_to_name.clone(),
-                solution: Vec::new(),
-                message: Some(format!("DC sweep source '{}' not found", source)),
-                sweep_var: Some(source.to_string()),
-                sweep_values: Vec::new(),
-                sweep_solutions: Vec::new(),
-                tran_times: Vec::new(),
-                tran_solutions: Vec::new(),
-                ac_frequencies: Vec::new(),
-                ac_solutions: Vec::new(),
-            };
+    /// 从解 `x` 前进 `dt` 得到的 `x_dt` 中有器件切换时, 二分步长直到切换时刻落在宽度不超过 `tol`
+    /// 的区间内, 返回区间右端 (刚越过切换) 的步长及该步的解。某次求解不收敛时停在当前区间
+    fn bisect_switching(
+        &mut self,
+        x: &[f64],
+        x_dt: Vec<f64>,
+        state: &mut TransientState,
+        junctions: &mut JunctionState,
+        dt: f64,
+        tol: f64,
+    ) -> (f64, Vec<f64>) {
+        let before = switching_conditions(&self.circuit.instances.instances, x, state);
+        let (mut lo, mut hi, mut x_hi) = (0.0, dt, x_dt);
+        while hi - lo > tol {
+            let mid = 0.5 * (lo + hi);
+            let mut x_mid = x.to_vec();
+            if !self.tran_newton(&mut x_mid, state, junctions, mid).converged {
+                break;
+            }
+            if switching_conditions(&self.circuit.instances.instances, &x_mid, state) != before {
+                hi = mid;
+                x_hi = x_mid;
+            } else {
+                lo = mid;
+            }
         }
-        let source_idx = source_idx.unwrap();
+        (hi, x_hi)
+    }
 
-        // Calculate sweep points
-        let mut sweep_values = Vec::new();
-        let step_size = if stop >= start { step.abs() } else { -step.abs() };
+    /// 从 `state` 所在时刻前进 `dt` 的一个瞬态时间步; `x` 以上一时间点的解为初值, 返回时为新解
+    fn tran_newton(
+        &mut self,
+        x: &mut Vec<f64>,
+        state: &mut TransientState,
+        junctions: &mut JunctionState,
+        dt: f64,
+    ) -> NewtonResult {
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let gnd = self.circuit.nodes.gnd_id.0;
+        let mut bypass = state.bypass.take();
+        let result = run_newton_with_limiting(&self.transient_newton_config(), x, junctions, |x, gmin, source_scale, junctions| {
+            let mna = self.workspace.begin(node_count);
+            for (inst, device) in self.circuit.instances.instances.iter().zip(&self.compiled) {
+                let mut ctx = mna.context_with_junctions(gmin, source_scale, junctions);
+                stamp_with_bypass(bypass.as_mut(), inst, &mut ctx, x, dt, |ctx| {
+                    let _ = device.stamp_tran(inst, ctx, Some(x), dt, state);
+                });
+            }
+            mna.builder.insert(gnd, gnd, 1.0);
+            self.workspace.finish()
+        }, self.solver.as_mut());
+        state.bypass = bypass;
+        result
+    }
 
-        if step_size.abs() < 1e-15 {
-            // Zero step - just do single point at start
-            sweep_values.push(start);
-        } else {
-            // Calculate number of points to avoid floating point accumulation errors
-            let range = stop - start;
-            let n_points = ((range / step_size).abs().floor() as usize) + 1;
+    /// 工作点与 DC 扫描的 Newton 配置: `.options` 覆盖缺省值, 节点电压按 vntol 判断收敛
+    fn newton_config(&self) -> NewtonConfig {
+        NewtonConfig {
+            voltage_unknowns: self.circuit.nodes.id_to_name.len(),
+            ..NewtonConfig::from_options(&self.circuit.options)
+        }
+    }
+
+    /// 瞬态时间步的 Newton 配置, 迭代上限取 itl4
+    fn transient_newton_config(&self) -> NewtonConfig {
+        NewtonConfig {
+            voltage_unknowns: self.circuit.nodes.id_to_name.len(),
+            ..NewtonConfig::transient_from_options(&self.circuit.options)
+        }
+    }
+
+    /// 累计一次求解的器件旁路统计
+    fn record_bypass(&mut self, bypass: Option<BypassCache>) {
+        if let Some(bypass) = bypass {
+            self.bypass_stats.evaluated += bypass.stats.evaluated;
+            self.bypass_stats.bypassed += bypass.stats.bypassed;
+        }
+    }
+
+    /// 接受到达 `time` 的时间步 `dt`: 记录状态量并推进积分历史
+    fn accept_tran_step(&self, x: &[f64], state: &mut TransientState, time: f64, dt: f64) {
+        state.time = time;
+        update_transient_state(&self.circuit.instances.instances, x, state);
+        state.advance_history(dt);
+        if let Some(bypass) = state.bypass.as_mut() {
+            bypass.accept(&self.circuit.instances.instances, x);
+        }
+    }
+
+    /// 瞬态分析在开始时间步进之前失败的结果
+    fn tran_failure(&self, message: String) -> RunResult {
+        RunResult {
+            id: RunId(0),
+            analysis: AnalysisType::Tran,
+            status: RunStatus::Failed,
+            iterations: 0,
+            node_names: self.circuit.nodes.id_to_name.clone(),
+            solution: Vec::new(),
+            message: Some(message),
+            sweep_var: None,
+            sweep_values: Vec::new(),
+            sweep_solutions: Vec::new(),
+            sweep_outer_var: None,
+            sweep_outer_values: Vec::new(),
+            tran_times: Vec::new(),
+            tran_solutions: Vec::new(),
+            ac_frequencies: Vec::new(),
+            ac_solutions: Vec::new(),
+            ac_phasors: Vec::new(),
+            output_params: Vec::new(),
+            measurements: Vec::new(),
+            branch_names: Vec::new(),
+            device_op: Vec::new(),
+            homotopy: Vec::new(),
+            convergence: None,
+            sparams: None,
+            noise: None,
+            pac: None,
+            sampled_params: Vec::new(),
+        }
+    }
 
-            for i in 0..n_points {
-                let value = start + (i as f64) * step_size;
-                sweep_values.push(value);
+    /// 周期稳态 (打靶法): 先从直流工作点做瞬态到 `tstab` 之后的第一个网格点 t0, 再对 t0 处的
+    /// 解向量 x0 求 Φ(x0) = x0, 其中 Φ 是以 T/points 的固定步长积分一个周期 T = 1/freq 的映射。
+    /// 打靶 Newton 的 Jacobian I - ∂Φ/∂x0 用逐列扰动 x0 的差分求得。
+    /// 结果的 `tran_times` / `tran_solutions` 是 [t0, t0 + T] 上的稳态波形, `solution` 为 x0
+    fn run_pss_result(&mut self, freq: f64, tstab: f64, points: usize, max_iters: usize, tol: f64) -> RunResult {
+        if !(freq > 0.0 && tstab >= 0.0 && points >= 2) {
+            let message = format!("invalid .pss parameters: freq={} tstab={} points={}", freq, tstab, points);
+            return self.pss_result(RunStatus::Failed, 0, Some(message));
+        }
+        let period = 1.0 / freq;
+        let dt = period / points as f64;
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let gnd = self.circuit.nodes.gnd_id.0;
+        self.compile_instances();
+        self.solver.prepare(node_count);
+        let mut state = match self.new_transient_state() {
+            Ok(state) => state,
+            Err(message) => return self.pss_result(RunStatus::Failed, 0, Some(message)),
+        };
+
+        let mut x = vec![0.0; node_count];
+        let mut junctions = JunctionState::default();
+        let dc_result = self.initial_operating_point(&mut x, &mut junctions);
+        debug_dump_newton_with_tag("pss_dc_op", &dc_result);
+        if !dc_result.converged {
+            let summary = self
+                .dc_convergence_report(&dc_result)
+                .as_ref()
+                .map(ConvergenceReport::summary)
+                .unwrap_or_default();
+            let message = format!("DC operating point failed to converge: {}", summary);
+            return self.pss_result(RunStatus::Failed, 0, Some(message));
+        }
+        update_transient_state(&self.circuit.instances.instances, &x, &mut state);
+        state.start_history();
+
+        // 稳定段: 至少一步, 使解向量带上瞬态的辅助变量 (电感电流等)
+        let stab_steps = ((tstab / dt).ceil() as usize).max(1);
+        for step in 0..stab_steps {
+            if let Err(message) = self.pss_step(&mut x, &mut state, &mut junctions, step as f64 * dt, dt) {
+                return self.pss_result(RunStatus::Failed, 0, Some(message));
+            }
+        }
+        let t0 = stab_steps as f64 * dt;
+
+        let mut iterations = 0;
+        loop {
+            let (end, waveform) = match self.pss_shoot(&x, &state, &junctions, t0, dt, points) {
+                Ok(shot) => shot,
+                Err(message) => return self.pss_result(RunStatus::Failed, iterations, Some(message)),
+            };
+            let ErrorEstimate { accept, error_norm } = estimate_error_weighted(&x, &end, 1e-6, tol);
+            if accept || iterations >= max_iters {
+                let mut result = if accept {
+                    self.pss_result(RunStatus::Converged, iterations, None)
+                } else {
+                    let message = format!(
+                        "PSS shooting did not converge after {} iterations (period mismatch {:.3e} x tolerance)",
+                        iterations, error_norm
+                    );
+                    self.pss_result(RunStatus::MaxIters, iterations, Some(message))
+                };
+                result.tran_times = (0..=points).map(|k| t0 + k as f64 * dt).collect();
+                result.tran_solutions = waveform;
+                result.solution = x;
+                return result;
             }
+            iterations += 1;
 
-            // Ensure we include the exact stop value if close enough
-            if let Some(&last) = sweep_values.last() {
-                if (last - stop).abs() > 1e-12 && sweep_values.len() < 10000 {
-                    // Don't add if we're very close to stop already
-                    if (last - stop).abs() / step_size.abs() > 0.5 {
-                        sweep_values.push(stop);
-                    }
+            // 打靶 Newton: (I - ∂Φ/∂x0) Δ = Φ(x0) - x0, 地节点不参与
+            let n = x.len();
+            let mut jacobian = vec![vec![0.0; n]; n];
+            for (row, values) in jacobian.iter_mut().enumerate() {
+                values[row] = 1.0;
+            }
+            for col in (0..n).filter(|col| *col != gnd) {
+                let h = 1e-6 * (1.0 + x[col].abs());
+                let mut perturbed = x.clone();
+                perturbed[col] += h;
+                let (end_perturbed, _) = match self.pss_shoot(&perturbed, &state, &junctions, t0, dt, points) {
+                    Ok(shot) => shot,
+                    Err(message) => return self.pss_result(RunStatus::Failed, iterations, Some(message)),
+                };
+                for row in (0..n).filter(|row| *row != gnd) {
+                    jacobian[row][col] -= (end_perturbed[row] - end[row]) / h;
                 }
             }
+            let mut delta: Vec<f64> = end.iter().zip(&x).map(|(e, x0)| e - x0).collect();
+            delta[gnd] = 0.0;
+            if solve_dense(&jacobian, &mut delta).is_err() {
+                let message = "PSS shooting Jacobian is singular".to_string();
+                return self.pss_result(RunStatus::Failed, iterations, Some(message));
+            }
+            for (value, d) in x.iter_mut().zip(&delta) {
+                *value += d;
+            }
+            x[gnd] = 0.0;
+        }
+    }
+
+    /// 打靶映射 Φ: 以 `x0` 为 t0 时刻的解 (状态量按 `x0` 重新记录) 积分一个周期,
+    /// 返回周期末的解与沿途各网格点的解 (含首末两点)
+    fn pss_shoot(
+        &mut self,
+        x0: &[f64],
+        base: &TransientState,
+        junctions: &JunctionState,
+        t0: f64,
+        dt: f64,
+        points: usize,
+    ) -> Result<(Vec<f64>, Vec<Vec<f64>>), String> {
+        let mut state = base.clone();
+        let mut junctions = junctions.clone();
+        state.time = t0;
+        update_transient_state(&self.circuit.instances.instances, x0, &mut state);
+        state.start_history();
+        let mut x = x0.to_vec();
+        let mut waveform = vec![x.clone()];
+        for step in 0..points {
+            self.pss_step(&mut x, &mut state, &mut junctions, t0 + step as f64 * dt, dt)?;
+            waveform.push(x.clone());
+        }
+        Ok((x, waveform))
+    }
+
+    /// 从 `time` 前进 `dt` 到下一个网格点; 不收敛时对半细分 (最多 10 层)
+    fn pss_step(
+        &mut self,
+        x: &mut Vec<f64>,
+        state: &mut TransientState,
+        junctions: &mut JunctionState,
+        time: f64,
+        dt: f64,
+    ) -> Result<(), String> {
+        let mut pending = vec![(dt, 0usize)];
+        let mut now = time;
+        while let Some((dt, depth)) = pending.pop() {
+            let mut x_iter = x.clone();
+            let result = self.tran_newton(&mut x_iter, state, junctions, dt);
+            debug_dump_newton_with_tag("pss", &result);
+            if result.converged {
+                *x = x_iter;
+                now += dt;
+                self.accept_tran_step(x, state, now, dt);
+            } else if depth < 10 {
+                pending.push((dt / 2.0, depth + 1));
+                pending.push((dt / 2.0, depth + 1));
+            } else {
+                return Err(format!("PSS transient step failed to converge at t={:e} (dt={:e})", now, dt));
+            }
         }
+        Ok(())
+    }
 
-        let mut sweep_solutions = Vec::new();
-        let mut total_iterations = 0;
-        let mut final_status = RunStatus::Converged;
-        let mut final_message = None;
+    /// 不带波形的 PSS 结果
+    fn pss_result(&self, status: RunStatus, iterations: usize, message: Option<String>) -> RunResult {
+        let mut result = self.tran_failure(String::new());
+        result.analysis = AnalysisType::Pss;
+        result.status = status;
+        result.iterations = iterations;
+        result.message = message;
+        result
+    }
+
+    /// Run DC sweep analysis
+    /// Sweeps the specified source from start to stop with given step size.
+    /// The reserved name `temp` sweeps the circuit temperature (°C).
+    fn run_dc_sweep_result(&mut self, source: &str, start: f64, stop: f64, step: f64) -> RunResult {
+        let kind = if source.eq_ignore_ascii_case("temp") {
+            DcSweepKind::Temp
+        } else {
+            DcSweepKind::Source
+        };
+        let spec = DcSweepSpec {
+            kind,
+            source: source.to_string(),
+            start,
+            stop,
+            step,
+        };
+        self.run_dc_family_result(&spec, None)
+    }
+
+    /// Run a DC sweep, optionally nested inside an outer sweep (family of curves).
+    /// Points are stored outer-major: for each outer value the full inner sweep.
+    fn run_dc_family_result(&mut self, inner: &DcSweepSpec, outer: Option<&DcSweepSpec>) -> RunResult {
+        let config = self.newton_config();
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let gnd = self.circuit.nodes.gnd_id.0;
+
+        let mut result = RunResult {
+            id: RunId(0),
+            analysis: AnalysisType::Dc,
+            status: RunStatus::Converged,
+            iterations: 0,
+            node_names: self.circuit.nodes.id_to_name.clone(),
+            solution: Vec::new(),
+            message: None,
+            sweep_var: Some(inner.source.clone()),
+            sweep_values: Vec::new(),
+            sweep_solutions: Vec::new(),
+            sweep_outer_var: outer.map(|spec| spec.source.clone()),
+            sweep_outer_values: Vec::new(),
+            tran_times: Vec::new(),
+            tran_solutions: Vec::new(),
+            ac_frequencies: Vec::new(),
+            ac_solutions: Vec::new(),
+            ac_phasors: Vec::new(),
+            output_params: Vec::new(),
+            measurements: Vec::new(),
+            branch_names: Vec::new(),
+            device_op: Vec::new(),
+            homotopy: Vec::new(),
+            convergence: None,
+            sparams: None,
+            noise: None,
+            pac: None,
+            sampled_params: Vec::new(),
+        };
+
+        let inner_target = match self.resolve_sweep_target(inner) {
+            Ok(target) => target,
+            Err(message) => {
+                result.status = RunStatus::Failed;
+                result.message = Some(message);
+                return result;
+            }
+        };
+        let outer_target = match outer.map(|spec| self.resolve_sweep_target(spec)) {
+            Some(Ok(target)) => Some(target),
+            Some(Err(message)) => {
+                result.status = RunStatus::Failed;
+                result.message = Some(message);
+                return result;
+            }
+            None => None,
+        };
+
+        let inner_values = dc_sweep_values(inner.start, inner.stop, inner.step);
+        let outer_values = match outer {
+            Some(spec) => dc_sweep_values(spec.start, spec.stop, spec.step),
+            None => vec![f64::NAN],
+        };
+
+        // 扫描结束后恢复器件的原始取值
+        let saved_instances = self.circuit.instances.instances.clone();
 
         // Use previous solution as initial guess for next point (continuation)
         let mut x = vec![0.0; node_count];
+        let mut junctions = JunctionState::default();
         self.solver.prepare(node_count);
 
-        for &sweep_val in &sweep_values {
-            // Update source value
-            self.circuit.instances.instances[source_idx].value = Some(sweep_val.to_string());
+        'outer: for &outer_val in &outer_values {
+            for &sweep_val in &inner_values {
+                // 参数扫描会重新展开网表并覆盖器件取值, 因此先于源/温度应用
+                let mut targets = vec![(&inner_target, sweep_val)];
+                if let Some(target) = &outer_target {
+                    targets.insert(0, (target, outer_val));
+                }
+                targets.sort_by_key(|(target, _)| !matches!(target, SweepTarget::Param(_)));
+                for (target, value) in targets {
+                    if let Err(message) = self.apply_sweep_value(target, value) {
+                        result.status = RunStatus::Failed;
+                        result.message = Some(message);
+                        break 'outer;
+                    }
+                }
 
-            // Run Newton iteration at this sweep point
-            let result = run_newton_with_stepping(&config, &mut x, |x, gmin, source_scale| {
-                let mut mna = MnaBuilder::new(node_count);
-                for inst in &self.circuit.instances.instances {
-                    let stamp = InstanceStamp {
-                        instance: inst.clone(),
-                    };
-                    let mut ctx = mna.context_with(gmin, source_scale);
-                    let _ = stamp.stamp_dc(&mut ctx, Some(x));
+                // Run Newton iteration at this sweep point
+                self.compile_instances();
+                let newton = run_newton_with_limiting(&config, &mut x, &mut junctions, |x, gmin, source_scale, junctions| {
+                    let mna = self.workspace.begin(node_count);
+                    for (inst, device) in self.circuit.instances.instances.iter().zip(&self.compiled) {
+                        let mut ctx = mna.context_with_junctions(gmin, source_scale, junctions);
+                        let _ = device.stamp_dc(inst, &mut ctx, Some(x));
+                    }
+                    // Ground node constraint
+                    mna.builder.insert(gnd, gnd, 1.0);
+                    self.workspace.finish()
+                }, self.solver.as_mut());
+
+                result.iterations += newton.iterations;
+
+                match newton.reason {
+                    crate::newton::NewtonExitReason::Converged => {
+                        result.sweep_values.push(sweep_val);
+                        result.sweep_solutions.push(x.clone());
+                        if outer_target.is_some() {
+                            result.sweep_outer_values.push(outer_val);
+                        }
+                    }
+                    crate::newton::NewtonExitReason::MaxIters => {
+                        result.status = RunStatus::MaxIters;
+                        result.convergence = self.dc_convergence_report(&newton);
+                        result.message = Some(failure_message(
+                            format!("Failed to converge at sweep point {}", sweep_val),
+                            &result.convergence,
+                        ));
+                        break 'outer;
+                    }
+                    crate::newton::NewtonExitReason::SolverFailure => {
+                        result.status = RunStatus::Failed;
+                        result.convergence = self.dc_convergence_report(&newton);
+                        result.message = Some(failure_message(
+                            format!("Solver failure at sweep point {}", sweep_val),
+                            &result.convergence,
+                        ));
+                        break 'outer;
+                    }
                 }
-                // Ground node constraint
-                mna.builder.insert(gnd, gnd, 1.0);
-                let (ap, ai, ax) = mna.builder.finalize();
-                (ap, ai, ax, mna.rhs, mna.builder.n)
-            }, self.solver.as_mut());
+            }
+        }
 
-            total_iterations += result.iterations;
+        self.circuit.instances.instances = saved_instances;
 
-            match result.reason {
-                crate::newton::NewtonExitReason::Converged => {
-                    sweep_solutions.push(x.clone());
+        // For compatibility, set solution to the last sweep point solution
+        result.solution = result.sweep_solutions.last().cloned().unwrap_or_default();
+        result
+    }
+
+    /// 源扫描的名字依次匹配 `@<器件>[<参数>]` (器件参数)、器件名 (源或 R/C/L 等的取值)
+    /// 与 `.param` 参数名
+    fn resolve_sweep_target(&self, spec: &DcSweepSpec) -> Result<SweepTarget, String> {
+        let find_instance = |name: &str| {
+            self.circuit
+                .instances
+                .instances
+                .iter()
+                .position(|inst| inst.name.eq_ignore_ascii_case(name))
+        };
+        match spec.kind {
+            DcSweepKind::Source => {
+                if let Some((device, param)) = parse_instance_param(&spec.source) {
+                    return find_instance(device)
+                        .map(|idx| SweepTarget::InstanceParam(idx, param.to_ascii_lowercase()))
+                        .ok_or_else(|| format!("DC sweep device '{}' not found", device));
                 }
-                crate::newton::NewtonExitReason::MaxIters => {
-                    final_status = RunStatus::MaxIters;
-                    final_message = Some(format!("Failed to converge at sweep point {}", sweep_val));
-                    break;
+                if let Some(idx) = find_instance(&spec.source) {
+                    return Ok(SweepTarget::Source(idx));
                 }
-                crate::newton::NewtonExitReason::SolverFailure => {
-                    final_status = RunStatus::Failed;
-                    final_message = Some(format!("Solver failure at sweep point {}", sweep_val));
-                    break;
+                match &self.circuit.netlist {
+                    Some(ast) if has_param(ast, &spec.source) => Ok(SweepTarget::Param(spec.source.clone())),
+                    _ => Err(format!("DC sweep source '{}' not found", spec.source)),
+                }
+            }
+            DcSweepKind::Temp => Ok(SweepTarget::Temp),
+            DcSweepKind::Param => {
+                if self.circuit.netlist.is_none() {
+                    return Err(format!(
+                        "DC sweep of param '{}' requires a circuit built from a netlist",
+                        spec.source
+                    ));
                 }
+                Ok(SweepTarget::Param(spec.source.clone()))
             }
         }
+    }
 
-        // For compatibility, set solution to the last sweep point solution
-        let solution = sweep_solutions.last().cloned().unwrap_or_default();
+    fn apply_sweep_value(&mut self, target: &SweepTarget, value: f64) -> Result<(), String> {
+        match target {
+            SweepTarget::Source(idx) => {
+                self.circuit.instances.instances[*idx].value = Some(value.to_string());
+            }
+            SweepTarget::InstanceParam(idx, param) => {
+                self.circuit.instances.instances[*idx]
+                    .params
+                    .insert(param.clone(), value.to_string());
+            }
+            SweepTarget::Temp => self.set_temperature(value),
+            SweepTarget::Param(name) => self.apply_param_overrides(&[(name.as_str(), value)])?,
+        }
+        Ok(())
+    }
 
-        RunResult {
+    /// 扫描对象的当前取值 (温度为 ℃, 未设置时为 27); 取值不是数值时为 None
+    fn sweep_target_value(&self, target: &SweepTarget) -> Option<f64> {
+        let instances = &self.circuit.instances.instances;
+        match target {
+            SweepTarget::Source(idx) => instances[*idx].value.as_deref().and_then(parse_number_with_suffix),
+            SweepTarget::InstanceParam(idx, param) => {
+                instances[*idx].params.get(param).and_then(|value| parse_number_with_suffix(value))
+            }
+            SweepTarget::Temp => Some(
+                instances
+                    .iter()
+                    .find_map(|inst| inst.params.get("temp").and_then(|value| value.parse::<f64>().ok()))
+                    .map_or(27.0, |kelvin| kelvin - 273.15),
+            ),
+            SweepTarget::Param(name) => self.circuit.netlist.as_ref().and_then(|ast| param_number(ast, name)),
+        }
+    }
+
+    /// 把所有器件的工作温度设为 `celsius` ℃
+    pub fn set_temperature(&mut self, celsius: f64) {
+        // 器件内部温度以开尔文保存在 "temp" 参数中
+        let kelvin = (celsius + 273.15).to_string();
+        for inst in &mut self.circuit.instances.instances {
+            inst.params.insert("temp".to_string(), kelvin.clone());
+        }
+    }
+
+    /// 以给定的 `.param` 取值重新展开网表, 并把器件取值拷回当前电路 (保留扫描温度)
+    fn apply_param_overrides(&mut self, overrides: &[(&str, f64)]) -> Result<(), String> {
+        let mut ast = self.circuit.netlist.clone().unwrap_or_else(|| NetlistAst {
+            title: None,
+            statements: Vec::new(),
+            errors: Vec::new(),
+            line_origins: Vec::new(),
+            control_script: Vec::new(),
+        });
+        for (name, value) in overrides {
+            override_param(&mut ast, name, &value.to_string());
+        }
+        let elab = elaborate_netlist(&ast);
+        let rebuilt =
+            build_circuit_with_nodes(&ast, &elab, self.circuit.nodes.normalization.clone());
+        let instances = &mut self.circuit.instances.instances;
+        if rebuilt.instances.instances.len() != instances.len() {
+            let names: Vec<&str> = overrides.iter().map(|(name, _)| *name).collect();
+            return Err(format!("param '{}' changes the circuit topology", names.join(", ")));
+        }
+        for (inst, fresh) in instances.iter_mut().zip(rebuilt.instances.instances) {
+            let temp = inst.params.get("temp").cloned();
+            inst.value = fresh.value;
+            inst.params = fresh.params;
+            if let Some(temp) = temp {
+                inst.params.insert("temp".to_string(), temp);
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a `.dc data=<table>` sweep: each row sets all table columns at once.
+    /// The first column is reported as the sweep variable.
+    fn run_dc_data_result(&mut self, table: &DataTable) -> RunResult {
+        let config = self.newton_config();
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let gnd = self.circuit.nodes.gnd_id.0;
+
+        let mut result = RunResult {
             id: RunId(0),
             analysis: AnalysisType::Dc,
-            status: final_status,
-            iterations: total_iterations,
+            status: RunStatus::Converged,
+            iterations: 0,
             node_names: self.circuit.nodes.id_to_name.clone(),
-            solution,
-            message: final_message,
-            sweep_var: Some(source.to_string()),
-            sweep_values,
-            sweep_solutions,
+            solution: Vec::new(),
+            message: None,
+            sweep_var: table.columns.first().cloned(),
+            sweep_values: Vec::new(),
+            sweep_solutions: Vec::new(),
+            sweep_outer_var: None,
+            sweep_outer_values: Vec::new(),
             tran_times: Vec::new(),
             tran_solutions: Vec::new(),
             ac_frequencies: Vec::new(),
             ac_solutions: Vec::new(),
+            ac_phasors: Vec::new(),
+            output_params: Vec::new(),
+            measurements: Vec::new(),
+            branch_names: Vec::new(),
+            device_op: Vec::new(),
+            homotopy: Vec::new(),
+            convergence: None,
+            sparams: None,
+            noise: None,
+            pac: None,
+            sampled_params: Vec::new(),
+        };
+
+        // 列名依次按 temp、器件源名、.param 参数解析
+        let mut targets = Vec::new();
+        for column in &table.columns {
+            let kind = if column.eq_ignore_ascii_case("temp") {
+                DcSweepKind::Temp
+            } else if self
+                .circuit
+                .instances
+                .instances
+                .iter()
+                .any(|inst| inst.name.eq_ignore_ascii_case(column))
+            {
+                DcSweepKind::Source
+            } else {
+                DcSweepKind::Param
+            };
+            let spec = DcSweepSpec {
+                kind,
+                source: column.clone(),
+                start: 0.0,
+                stop: 0.0,
+                step: 0.0,
+            };
+            match self.resolve_sweep_target(&spec) {
+                Ok(target) => targets.push(target),
+                Err(message) => {
+                    result.status = RunStatus::Failed;
+                    result.message = Some(message);
+                    return result;
+                }
+            }
         }
+
+        let saved_instances = self.circuit.instances.instances.clone();
+        let mut x = vec![0.0; node_count];
+        let mut junctions = JunctionState::default();
+        self.solver.prepare(node_count);
+
+        for (row_index, row) in table.rows.iter().enumerate() {
+            // 同一行的参数一起覆盖后只重新展开一次, 再应用源与温度
+            let params: Vec<(&str, f64)> = targets
+                .iter()
+                .zip(row)
+                .filter_map(|(target, value)| match target {
+                    SweepTarget::Param(name) => Some((name.as_str(), *value)),
+                    _ => None,
+                })
+                .collect();
+            let mut applied = if params.is_empty() {
+                Ok(())
+            } else {
+                self.apply_param_overrides(&params)
+            };
+            for (target, value) in targets.iter().zip(row) {
+                if applied.is_ok() && !matches!(target, SweepTarget::Param(_)) {
+                    applied = self.apply_sweep_value(target, *value);
+                }
+            }
+            if let Err(message) = applied {
+                result.status = RunStatus::Failed;
+                result.message = Some(message);
+                break;
+            }
+
+            self.compile_instances();
+            let newton = run_newton_with_limiting(&config, &mut x, &mut junctions, |x, gmin, source_scale, junctions| {
+                let mna = self.workspace.begin(node_count);
+                for (inst, device) in self.circuit.instances.instances.iter().zip(&self.compiled) {
+                    let mut ctx = mna.context_with_junctions(gmin, source_scale, junctions);
+                    let _ = device.stamp_dc(inst, &mut ctx, Some(x));
+                }
+                mna.builder.insert(gnd, gnd, 1.0);
+                self.workspace.finish()
+            }, self.solver.as_mut());
+
+            result.iterations += newton.iterations;
+
+            match newton.reason {
+                crate::newton::NewtonExitReason::Converged => {
+                    result.sweep_values.push(row[0]);
+                    result.sweep_solutions.push(x.clone());
+                }
+                crate::newton::NewtonExitReason::MaxIters => {
+                    result.status = RunStatus::MaxIters;
+                    result.convergence = self.dc_convergence_report(&newton);
+                    result.message = Some(failure_message(
+                        format!("Failed to converge at data row {}", row_index + 1),
+                        &result.convergence,
+                    ));
+                    break;
+                }
+                crate::newton::NewtonExitReason::SolverFailure => {
+                    result.status = RunStatus::Failed;
+                    result.convergence = self.dc_convergence_report(&newton);
+                    result.message = Some(failure_message(
+                        format!("Solver failure at data row {}", row_index + 1),
+                        &result.convergence,
+                    ));
+                    break;
+                }
+            }
+        }
+
+        self.circuit.instances.instances = saved_instances;
+        result.solution = result.sweep_solutions.last().cloned().unwrap_or_default();
+        result
     }
 
     /// Run AC (small-signal frequency-domain) analysis
@@ -445,9 +2061,6 @@ impl Engine {
         fstart: f64,
         fstop: f64,
     ) -> RunResult {
-        let node_count = self.circuit.nodes.id_to_name.len();
-        let gnd = self.circuit.nodes.gnd_id.0;
-
         // Step 1: Run DC operating point for linearization of nonlinear devices
         let dc_result = self.run_dc_result(AnalysisType::Op);
         if !matches!(dc_result.status, RunStatus::Converged) {
@@ -462,10 +2075,23 @@ impl Engine {
                 sweep_var: None,
                 sweep_values: Vec::new(),
                 sweep_solutions: Vec::new(),
+                sweep_outer_var: None,
+                sweep_outer_values: Vec::new(),
                 tran_times: Vec::new(),
                 tran_solutions: Vec::new(),
                 ac_frequencies: Vec::new(),
                 ac_solutions: Vec::new(),
+                ac_phasors: Vec::new(),
+                output_params: Vec::new(),
+                measurements: Vec::new(),
+                branch_names: Vec::new(),
+                device_op: Vec::new(),
+                homotopy: Vec::new(),
+                convergence: None,
+                sparams: None,
+                noise: None,
+                pac: None,
+                sampled_params: Vec::new(),
             };
         }
 
@@ -474,49 +2100,26 @@ impl Engine {
         // Step 2: Generate frequency sweep
         let frequencies = generate_frequency_sweep(sweep_type, points, fstart, fstop);
 
-        // Step 3: Create complex solver
-        let mut complex_solver = create_complex_solver();
+        // Step 3: 各频率点互相独立, 多线程时与 `.step` 共用线程数设置在线程池中并行求解
+        // (每个工作线程一个复数求解器), 结果按频率顺序合并
+        let circuit = &self.circuit;
+        let solve = |solver: &mut Box<dyn ComplexLinearSolver>, freq: &f64| {
+            solve_ac_point(circuit, &dc_solution, *freq, solver.as_mut())
+        };
+        let points: Vec<Option<Vec<Complex64>>> = match self.thread_pool(frequencies.len()) {
+            Some(pool) => pool.install(|| frequencies.par_iter().map_init(create_complex_solver, solve).collect()),
+            None => {
+                let mut solver = create_complex_solver();
+                frequencies.iter().map(|freq| solve(&mut solver, freq)).collect()
+            }
+        };
 
-        // Step 4: For each frequency, build and solve the complex MNA system
         let mut ac_frequencies = Vec::with_capacity(frequencies.len());
         let mut ac_solutions = Vec::with_capacity(frequencies.len());
+        let mut ac_phasors = Vec::with_capacity(frequencies.len());
         let mut total_iterations = 0;
-
-        for freq in frequencies {
-            let omega = 2.0 * std::f64::consts::PI * freq;
-
-            // Build complex MNA matrix
-            let mut mna = ComplexMnaBuilder::new(node_count);
-
-            // Stamp all devices
-            for inst in &self.circuit.instances.instances {
-                let stamp = InstanceStamp {
-                    instance: inst.clone(),
-                };
-                let mut ctx = mna.context(omega);
-                let _ = stamp.stamp_ac(&mut ctx, &dc_solution);
-            }
-
-            // Fix ground node: clear the row and set diagonal to 1
-            // First, clear any entries in the ground row (which is stored in columns)
-            for col in 0..mna.builder.n {
-                mna.builder.col_entries[col].retain(|(row, _)| *row != gnd);
-            }
-            // Set diagonal to 1
-            mna.builder.insert(gnd, gnd, Complex64::new(1.0, 0.0));
-            // Clear ground node RHS
-            mna.rhs[gnd] = Complex64::new(0.0, 0.0);
-
-            // Finalize matrix
-            let (ap, ai, ax) = mna.builder.finalize();
-            let n = mna.builder.n;
-
-            // Prepare solver and solve
-            complex_solver.prepare(n);
-            let mut x = vec![Complex64::new(0.0, 0.0); n];
-
-            let solved = complex_solver.solve(&ap, &ai, &ax, &mna.rhs, &mut x);
-            if !solved {
+        for (freq, point) in frequencies.into_iter().zip(points) {
+            let Some(phasors) = point else {
                 return RunResult {
                     id: RunId(0),
                     analysis: AnalysisType::Ac,
@@ -528,29 +2131,28 @@ impl Engine {
                     sweep_var: None,
                     sweep_values: Vec::new(),
                     sweep_solutions: Vec::new(),
+                    sweep_outer_var: None,
+                    sweep_outer_values: Vec::new(),
                     tran_times: Vec::new(),
                     tran_solutions: Vec::new(),
                     ac_frequencies,
                     ac_solutions,
+                    ac_phasors,
+                    output_params: Vec::new(),
+                    measurements: Vec::new(),
+                    branch_names: Vec::new(),
+                    device_op: Vec::new(),
+                    homotopy: Vec::new(),
+                    convergence: None,
+                    sparams: None,
+                    noise: None,
+                    pac: None,
+                    sampled_params: Vec::new(),
                 };
-            }
-
-            // Convert complex solution to (magnitude_dB, phase_deg) for each node
-            let mut freq_solution = Vec::with_capacity(node_count);
-            for i in 0..node_count {
-                let v = x[i];
-                let mag = v.norm();
-                let mag_db = if mag > 1e-30 {
-                    20.0 * mag.log10()
-                } else {
-                    -600.0 // Very small magnitude
-                };
-                let phase_deg = v.arg() * 180.0 / std::f64::consts::PI;
-                freq_solution.push((mag_db, phase_deg));
-            }
-
+            };
             ac_frequencies.push(freq);
-            ac_solutions.push(freq_solution);
+            ac_solutions.push(db_phase(&phasors[..self.circuit.nodes.id_to_name.len()]));
+            ac_phasors.push(phasors);
             total_iterations += 1;
         }
 
@@ -565,11 +2167,378 @@ impl Engine {
             sweep_var: None,
             sweep_values: Vec::new(),
             sweep_solutions: Vec::new(),
+            sweep_outer_var: None,
+            sweep_outer_values: Vec::new(),
             tran_times: Vec::new(),
             tran_solutions: Vec::new(),
             ac_frequencies,
             ac_solutions,
+            ac_phasors,
+            output_params: Vec::new(),
+            measurements: Vec::new(),
+            branch_names: Vec::new(),
+            device_op: Vec::new(),
+            homotopy: Vec::new(),
+            convergence: None,
+            sparams: None,
+            noise: None,
+            pac: None,
+            sampled_params: Vec::new(),
+        }
+    }
+
+    /// S 参数: 在直流工作点线性化后, 每个频率依次以端口 j 的开路电压 E = 1 (串联 Z0) 激励,
+    /// 其余端口由 Z0 端接, 由各端口电压得 S_kj = 2 V_k - δ_kj; 其他源的 AC 激励不参与。
+    /// 端口按 PORT= 编号排列, 必须编号为 1..N 且共用同一参考阻抗
+    fn run_sp_result(&mut self, sweep_type: AcSweepType, points: usize, fstart: f64, fstop: f64) -> RunResult {
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let gnd = self.circuit.nodes.gnd_id.0;
+        let ports: Vec<(usize, usize, usize, f64)> = self
+            .circuit
+            .ports()
+            .into_iter()
+            .filter_map(|port| {
+                let nodes = &self.circuit.instances.instances[port.instance.0].nodes;
+                (nodes.len() == 2).then(|| (port.number, nodes[0].0, nodes[1].0, port.z0))
+            })
+            .collect();
+        if ports.is_empty() {
+            let message = "S-parameter analysis needs at least one port (P element)".to_string();
+            return self.sp_result(RunStatus::Failed, Some(message));
+        }
+        if ports.iter().enumerate().any(|(idx, port)| port.0 != idx + 1) {
+            let numbers: Vec<String> = ports.iter().map(|port| port.0.to_string()).collect();
+            let message = format!("ports must be numbered 1..N, found {}", numbers.join(", "));
+            return self.sp_result(RunStatus::Failed, Some(message));
+        }
+        let z0 = ports[0].3;
+        if ports.iter().any(|port| port.3 != z0) {
+            let message = "all ports must share one reference impedance Z0".to_string();
+            return self.sp_result(RunStatus::Failed, Some(message));
+        }
+
+        let dc_result = self.run_dc_result(AnalysisType::Op);
+        if !matches!(dc_result.status, RunStatus::Converged) {
+            let message = "DC operating point failed to converge".to_string();
+            return self.sp_result(RunStatus::Failed, Some(message));
+        }
+        let dc_solution = dc_result.solution;
+
+        let n = ports.len();
+        let mut complex_solver = create_complex_solver();
+        let frequencies = generate_frequency_sweep(sweep_type, points, fstart, fstop);
+        let mut s = Vec::with_capacity(frequencies.len());
+        for &freq in &frequencies {
+            let omega = 2.0 * std::f64::consts::PI * freq;
+            let mut mna = ComplexMnaBuilder::new(node_count);
+            for inst in &self.circuit.instances.instances {
+                let mut ctx = mna.context(omega);
+                let _ = inst.stamp_ac(&mut ctx, &dc_solution);
+            }
+            for col in 0..mna.builder.n {
+                mna.builder.col_entries[col].retain(|(row, _)| *row != gnd);
+            }
+            mna.builder.insert(gnd, gnd, Complex64::new(1.0, 0.0));
+            let (ap, ai, ax) = mna.builder.finalize();
+            let size = mna.builder.n;
+            complex_solver.prepare(size);
+
+            let mut matrix = vec![Complex64::new(0.0, 0.0); n * n];
+            for (j, &(_, a, b, _)) in ports.iter().enumerate() {
+                // 开路电压 1 V 串联 Z0 的诺顿等效: 电流 1/Z0
+                let mut rhs = vec![Complex64::new(0.0, 0.0); size];
+                rhs[a] += 1.0 / z0;
+                rhs[b] -= 1.0 / z0;
+                rhs[gnd] = Complex64::new(0.0, 0.0);
+                let mut x = vec![Complex64::new(0.0, 0.0); size];
+                if !complex_solver.solve(&ap, &ai, &ax, &rhs, &mut x) {
+                    let message = format!("S-parameter solve failed at frequency {} Hz", freq);
+                    return self.sp_result(RunStatus::Failed, Some(message));
+                }
+                for (k, &(_, a, b, _)) in ports.iter().enumerate() {
+                    let incident = if k == j { 1.0 } else { 0.0 };
+                    matrix[k * n + j] = 2.0 * (x[a] - x[b]) - incident;
+                }
+            }
+            s.push(matrix);
+        }
+
+        let mut result = self.sp_result(RunStatus::Converged, None);
+        result.iterations = frequencies.len();
+        result.solution = dc_solution;
+        result.ac_frequencies = frequencies.clone();
+        result.sparams = Some(crate::touchstone::Touchstone {
+            ports: n,
+            z0,
+            frequencies,
+            s,
+        });
+        result
+    }
+
+    /// 噪声分析: 在直流工作点线性化后, 每个频率求解一次伴随方程 Yᵀ·z = e_out, z 即各节点注入电流
+    /// 到输出电压的传输, 由此得每个器件噪声源对输出的贡献. 输入源按 AC 幅度 1 激励 (其他源的
+    /// AC 激励不参与), 其到输出的增益 z·u 用于把输出噪声折算为等效输入噪声
+    fn run_noise_result(&mut self, output: &str, reference: Option<&str>, source: &str, frequencies: Vec<f64>) -> RunResult {
+        let Some(out) = self.circuit.nodes.lookup(output) else {
+            return self.noise_result(RunStatus::Failed, Some(format!("unknown noise output node '{}'", output)));
+        };
+        let probe = match reference {
+            Some(name) => format!("V({},{})", output, name),
+            None => format!("V({})", output),
+        };
+        let reference = match reference.map(|name| (name, self.circuit.nodes.lookup(name))) {
+            Some((name, None)) => {
+                return self.noise_result(RunStatus::Failed, Some(format!("unknown noise reference node '{}'", name)));
+            }
+            Some((_, Some(id))) => Some(id.0),
+            None => None,
+        };
+        let input = self.circuit.instances.find(source).filter(|id| {
+            matches!(self.circuit.instances.instances[id.0].kind, DeviceKind::V | DeviceKind::I)
+        });
+        let Some(input) = input else {
+            let message = format!("noise input '{}' is not an independent source", source);
+            return self.noise_result(RunStatus::Failed, Some(message));
+        };
+
+        let dc_result = self.run_dc_result(AnalysisType::Op);
+        if !matches!(dc_result.status, RunStatus::Converged) {
+            let message = "DC operating point failed to converge".to_string();
+            return self.noise_result(RunStatus::Failed, Some(message));
+        }
+        let dc_solution = dc_result.solution;
+
+        let nodes = &self.circuit.nodes;
+        let instances = &self.circuit.instances.instances;
+        let sources: Vec<NoiseSource> = instances.iter().flat_map(|inst| noise_sources(inst, &dc_solution)).collect();
+        let excited: Vec<_> = instances
+            .iter()
+            .enumerate()
+            .map(|(idx, inst)| {
+                let mut inst = inst.clone();
+                inst.ac_mag = (idx == input.0).then_some(1.0);
+                inst.ac_phase = None;
+                inst
+            })
+            .collect();
+
+        let node_count = nodes.id_to_name.len();
+        let gnd = nodes.gnd_id.0;
+        let mut complex_solver = create_complex_solver();
+        let mut transfers = Vec::with_capacity(frequencies.len());
+        let mut gains = Vec::with_capacity(frequencies.len());
+        for &freq in &frequencies {
+            let omega = 2.0 * std::f64::consts::PI * freq;
+            let mut mna = ComplexMnaBuilder::new(node_count);
+            for inst in &excited {
+                let mut ctx = mna.context(omega);
+                let _ = inst.stamp_ac(&mut ctx, &dc_solution);
+            }
+            // 地节点与其余方程完全解耦, 伴随解在地节点上为零
+            for col in 0..mna.builder.n {
+                mna.builder.col_entries[col].retain(|(row, _)| *row != gnd);
+            }
+            mna.builder.col_entries[gnd].clear();
+            mna.builder.insert(gnd, gnd, Complex64::new(1.0, 0.0));
+            mna.rhs[gnd] = Complex64::new(0.0, 0.0);
+            let (ap, ai, ax) = mna.builder.finalize();
+            let (tp, ti, tx) = transpose_csc(&ap, &ai, &ax);
+            let size = mna.builder.n;
+
+            let mut e = vec![Complex64::new(0.0, 0.0); size];
+            e[out.0] += 1.0;
+            if let Some(reference) = reference {
+                e[reference] -= 1.0;
+            }
+            e[gnd] = Complex64::new(0.0, 0.0);
+            let mut z = vec![Complex64::new(0.0, 0.0); size];
+            complex_solver.prepare(size);
+            if !complex_solver.solve(&tp, &ti, &tx, &e, &mut z) {
+                let message = format!("noise solve failed at frequency {} Hz", freq);
+                return self.noise_result(RunStatus::Failed, Some(message));
+            }
+            transfers.push(sources.iter().map(|source| (z[source.nodes.0] - z[source.nodes.1]).norm_sqr()).collect());
+            gains.push(z.iter().zip(&mna.rhs).map(|(z, u)| z * u).sum::<Complex64>().norm_sqr());
+        }
+
+        let mut result = self.noise_result(RunStatus::Converged, None);
+        result.iterations = frequencies.len();
+        result.solution = dc_solution;
+        result.ac_frequencies = frequencies.clone();
+        result.noise = Some(NoiseResult::from_transfers(
+            probe,
+            source.to_string(),
+            frequencies,
+            &sources,
+            &transfers,
+            &gains,
+        ));
+        result
+    }
+
+    /// 不带噪声结果的噪声分析结果
+    fn noise_result(&self, status: RunStatus, message: Option<String>) -> RunResult {
+        let mut result = self.tran_failure(String::new());
+        result.analysis = AnalysisType::Noise;
+        result.status = status;
+        result.message = message;
+        result
+    }
+
+    /// 周期小信号分析的起点: 按电路中的 `.pss` 求周期稳态, 返回基频与一个周期内的 N 个网格点解
+    fn periodic_steady_state(&mut self) -> Result<(f64, Vec<Vec<f64>>), String> {
+        let pss = self.circuit.analysis.iter().find_map(|cmd| match cmd {
+            crate::circuit::AnalysisCmd::Pss { freq, tstab, points, max_iters, tol } => {
+                Some((*freq, *tstab, *points, *max_iters, *tol))
+            }
+            _ => None,
+        });
+        let Some((freq, tstab, points, max_iters, tol)) = pss else {
+            return Err("periodic small-signal analysis needs a .pss analysis".to_string());
+        };
+        let pss = self.run_pss_result(freq, tstab, points, max_iters, tol);
+        if !matches!(pss.status, RunStatus::Converged) {
+            return Err(format!("PSS failed: {}", pss.message.unwrap_or_default()));
+        }
+        let mut waveform = pss.tran_solutions;
+        waveform.pop();
+        Ok((freq, waveform))
+    }
+
+    /// 周期 AC: 在 PSS 周期稳态上线性化, 每个输入频率求解一次边带转换矩阵
+    fn run_pac_result(&mut self, frequencies: Vec<f64>, sidebands: usize) -> RunResult {
+        let (fundamental, waveform) = match self.periodic_steady_state() {
+            Ok(pss) => pss,
+            Err(message) => return self.periodic_result(AnalysisType::Pac, RunStatus::Failed, Some(message)),
+        };
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let gnd = self.circuit.nodes.gnd_id.0;
+        let system =
+            PeriodicSystem::linearize(&self.circuit.instances.instances, node_count, gnd, &waveform, fundamental, sidebands);
+        let k = system.sidebands() as i32;
+
+        let mut complex_solver = create_complex_solver();
+        let mut phasors = Vec::with_capacity(frequencies.len());
+        for &freq in &frequencies {
+            let Some(point) = system.solve(freq, complex_solver.as_mut()) else {
+                let message = format!("PAC solve failed at frequency {} Hz", freq);
+                return self.periodic_result(AnalysisType::Pac, RunStatus::Failed, Some(message));
+            };
+            phasors.push(point);
+        }
+
+        let mut result = self.periodic_result(AnalysisType::Pac, RunStatus::Converged, None);
+        result.iterations = frequencies.len();
+        result.solution = waveform.into_iter().next().unwrap_or_default();
+        result.ac_frequencies = frequencies.clone();
+        result.ac_phasors = phasors.iter().map(|point| point[k as usize].clone()).collect();
+        result.ac_solutions = result.ac_phasors.iter().map(|point| db_phase(point)).collect();
+        result.pac = Some(PacResult {
+            fundamental,
+            sidebands: (-k..=k).collect(),
+            frequencies,
+            phasors,
+        });
+        result
+    }
+
+    /// 周期噪声: 与 `.noise` 相同由伴随方程求各噪声源到输出的传输, 但在边带转换矩阵上求解,
+    /// 并计入噪声源强度随周期稳态的调制 (循环平稳噪声) 把各边带上的噪声搬移到输出频率
+    fn run_pnoise_result(
+        &mut self,
+        output: &str,
+        reference: Option<&str>,
+        source: &str,
+        frequencies: Vec<f64>,
+        sidebands: usize,
+    ) -> RunResult {
+        let failed = |engine: &Self, message: String| engine.periodic_result(AnalysisType::Pnoise, RunStatus::Failed, Some(message));
+        let Some(out) = self.circuit.nodes.lookup(output) else {
+            return failed(self, format!("unknown noise output node '{}'", output));
+        };
+        let probe = match reference {
+            Some(name) => format!("V({},{})", output, name),
+            None => format!("V({})", output),
+        };
+        let reference = match reference.map(|name| (name, self.circuit.nodes.lookup(name))) {
+            Some((name, None)) => return failed(self, format!("unknown noise reference node '{}'", name)),
+            Some((_, Some(id))) => Some(id.0),
+            None => None,
+        };
+        let input = self.circuit.instances.find(source).filter(|id| {
+            matches!(self.circuit.instances.instances[id.0].kind, DeviceKind::V | DeviceKind::I)
+        });
+        let Some(input) = input else {
+            return failed(self, format!("noise input '{}' is not an independent source", source));
+        };
+        let (fundamental, waveform) = match self.periodic_steady_state() {
+            Ok(pss) => pss,
+            Err(message) => return failed(self, message),
+        };
+
+        let excited: Vec<_> = self
+            .circuit
+            .instances
+            .instances
+            .iter()
+            .enumerate()
+            .map(|(idx, inst)| {
+                let mut inst = inst.clone();
+                inst.ac_mag = (idx == input.0).then_some(1.0);
+                inst.ac_phase = None;
+                inst
+            })
+            .collect();
+        let node_count = self.circuit.nodes.id_to_name.len();
+        let gnd = self.circuit.nodes.gnd_id.0;
+        let system = PeriodicSystem::linearize(&excited, node_count, gnd, &waveform, fundamental, sidebands);
+        let (sources, modulations) = system.noise_modulation(&excited, &waveform);
+
+        let mut complex_solver = create_complex_solver();
+        let mut densities = Vec::with_capacity(frequencies.len());
+        let mut gains = Vec::with_capacity(frequencies.len());
+        for &freq in &frequencies {
+            let Some((density, gain)) =
+                system.noise(freq, (out.0, reference), &sources, &modulations, complex_solver.as_mut())
+            else {
+                return failed(self, format!("PNOISE solve failed at frequency {} Hz", freq));
+            };
+            densities.push(density);
+            gains.push(gain);
         }
+
+        let mut result = self.periodic_result(AnalysisType::Pnoise, RunStatus::Converged, None);
+        result.iterations = frequencies.len();
+        result.solution = waveform.into_iter().next().unwrap_or_default();
+        result.ac_frequencies = frequencies.clone();
+        result.noise = Some(NoiseResult::from_densities(
+            probe,
+            source.to_string(),
+            frequencies,
+            &sources,
+            &densities,
+            &gains,
+        ));
+        result
+    }
+
+    /// 不带小信号结果的 PAC / PNOISE 结果
+    fn periodic_result(&self, analysis: AnalysisType, status: RunStatus, message: Option<String>) -> RunResult {
+        let mut result = self.tran_failure(String::new());
+        result.analysis = analysis;
+        result.status = status;
+        result.message = message;
+        result
+    }
+
+    /// 不带 S 参数的结果
+    fn sp_result(&self, status: RunStatus, message: Option<String>) -> RunResult {
+        let mut result = self.tran_failure(String::new());
+        result.analysis = AnalysisType::Sp;
+        result.status = status;
+        result.message = message;
+        result
     }
 }
 
@@ -618,6 +2587,190 @@ fn generate_frequency_sweep(
     frequencies
 }
 
+/// 解析后的 DC 扫描对象
+enum SweepTarget {
+    /// 器件表中的源索引
+    Source(usize),
+    /// 器件表中的索引与参数名 (小写)
+    InstanceParam(usize, String),
+    Temp,
+    Param(String),
+}
+
+/// 解析 `@<器件>[<参数>]`
+fn parse_instance_param(name: &str) -> Option<(&str, &str)> {
+    let (device, param) = name.strip_prefix('@')?.strip_suffix(']')?.split_once('[')?;
+    (!device.is_empty() && !param.is_empty()).then_some((device, param))
+}
+
+/// x + side·s, 扰动电路的积分历史沿它推进
+fn shifted(x: &[f64], s: &[f64], side: f64) -> Vec<f64> {
+    x.iter().zip(s).map(|(x, s)| x + side * s).collect()
+}
+
+/// 失败原因后接收敛诊断的摘要
+/// 源还在切换时不判断稳态, 并丢弃此前的历史: 逐步检测要等到没有待到的断点,
+/// 按周期检测要等到所有源波形都进入周期 (或保持不变)
+fn steady_after_sources(steady: &mut SteadyStateDetector, state: &TransientState, time: f64, nodes: &[f64]) -> bool {
+    let switching = match steady.period() {
+        None => state.next_breakpoint(time).is_some(),
+        Some(_) => time < state.sources_periodic_from(),
+    };
+    if switching {
+        steady.reset();
+        return false;
+    }
+    steady.accept(time, nodes)
+}
+
+fn failure_message(reason: String, convergence: &Option<ConvergenceReport>) -> String {
+    match convergence {
+        Some(report) => format!("{}: {}", reason, report.summary()),
+        None => reason,
+    }
+}
+
+/// 与 `aux` 中辅助变量编号相同的空 MNA 系统
+fn builder_with_aux(node_count: usize, aux: &AuxVarTable) -> MnaBuilder {
+    let mut mna = MnaBuilder::new(node_count);
+    mna.aux = aux.clone();
+    mna.builder.resize(node_count + aux.id_to_name.len());
+    mna.rhs.resize(mna.builder.n, 0.0);
+    mna
+}
+
+/// 一次盖章的矩阵项 (行, 列) 与右端项 (行, None), 重复项相加
+fn stamp_entries(mna: &MnaBuilder) -> HashMap<(usize, Option<usize>), f64> {
+    let mut entries = HashMap::new();
+    for (col, column) in mna.builder.col_entries.iter().enumerate() {
+        for (row, value) in column {
+            *entries.entry((*row, Some(col))).or_insert(0.0) += value;
+        }
+    }
+    for (row, value) in mna.rhs.iter().enumerate().filter(|(_, value)| **value != 0.0) {
+        *entries.entry((row, None)).or_insert(0.0) += value;
+    }
+    entries
+}
+
+/// 稠密矩阵 (按行存放) 的线性方程组, 解写回 `rhs`
+fn solve_dense(matrix: &[Vec<f64>], rhs: &mut [f64]) -> Result<(), crate::solver::SolverError> {
+    let n = rhs.len();
+    let mut ap = vec![0i64];
+    let mut ai = Vec::new();
+    let mut ax = Vec::new();
+    for col in 0..n {
+        for (row, values) in matrix.iter().enumerate().filter(|(_, values)| values[col] != 0.0) {
+            ai.push(row as i64);
+            ax.push(values[col]);
+        }
+        ap.push(ai.len() as i64);
+    }
+    let mut solver = crate::solver::DenseSolver::new(n);
+    solver.factor(&ap, &ai, &ax)?;
+    solver.solve(rhs)
+}
+
+/// `.step` 一维的扫描点
+fn step_values(values: &StepValues) -> Vec<f64> {
+    match values {
+        StepValues::Lin { start, stop, step } => dc_sweep_values(*start, *stop, *step),
+        StepValues::Log { sweep_type, points, start, stop } => {
+            generate_frequency_sweep(*sweep_type, *points, *start, *stop)
+        }
+        StepValues::List(values) => values.clone(),
+    }
+}
+
+/// DC 扫描点: 按点数生成以避免浮点累加误差, 必要时补上终点
+fn dc_sweep_values(start: f64, stop: f64, step: f64) -> Vec<f64> {
+    let mut sweep_values = Vec::new();
+    let step_size = if stop >= start { step.abs() } else { -step.abs() };
+
+    if step_size.abs() < 1e-15 {
+        // Zero step - just do single point at start
+        sweep_values.push(start);
+        return sweep_values;
+    }
+
+    let range = stop - start;
+    let n_points = ((range / step_size).abs().floor() as usize) + 1;
+    for i in 0..n_points {
+        sweep_values.push(start + (i as f64) * step_size);
+    }
+
+    // Ensure we include the exact stop value if close enough
+    if let Some(&last) = sweep_values.last() {
+        if (last - stop).abs() > 1e-12
+            && sweep_values.len() < 10000
+            && (last - stop).abs() / step_size.abs() > 0.5
+        {
+            sweep_values.push(stop);
+        }
+    }
+    sweep_values
+}
+
+/// 在频率 `freq` 处建立并求解复数 MNA 方程, 返回完整的复数解向量 (节点电压与支路电流);
+/// 求解失败时为 None
+fn solve_ac_point(
+    circuit: &Circuit,
+    dc_solution: &[f64],
+    freq: f64,
+    solver: &mut dyn ComplexLinearSolver,
+) -> Option<Vec<Complex64>> {
+    let node_count = circuit.nodes.id_to_name.len();
+    let gnd = circuit.nodes.gnd_id.0;
+    let omega = 2.0 * std::f64::consts::PI * freq;
+
+    // Build complex MNA matrix
+    let mut mna = ComplexMnaBuilder::new(node_count);
+
+    // Stamp all devices
+    for inst in &circuit.instances.instances {
+        let mut ctx = mna.context(omega);
+        let _ = inst.stamp_ac(&mut ctx, dc_solution);
+    }
+
+    // Fix ground node: clear the row and set diagonal to 1
+    // First, clear any entries in the ground row (which is stored in columns)
+    for col in 0..mna.builder.n {
+        mna.builder.col_entries[col].retain(|(row, _)| *row != gnd);
+    }
+    // Set diagonal to 1
+    mna.builder.insert(gnd, gnd, Complex64::new(1.0, 0.0));
+    // Clear ground node RHS
+    mna.rhs[gnd] = Complex64::new(0.0, 0.0);
+
+    // Finalize matrix
+    let (ap, ai, ax) = mna.builder.finalize();
+    let n = mna.builder.n;
+
+    // Prepare solver and solve
+    solver.prepare(n);
+    let mut x = vec![Complex64::new(0.0, 0.0); n];
+    if !solver.solve(&ap, &ai, &ax, &mna.rhs, &mut x) {
+        return None;
+    }
+    Some(x)
+}
+
+/// Convert complex node voltages to (magnitude_dB, phase_deg)
+fn db_phase(phasors: &[Complex64]) -> Vec<(f64, f64)> {
+    phasors
+        .iter()
+        .map(|v| {
+            let mag = v.norm();
+            let mag_db = if mag > 1e-30 {
+                20.0 * mag.log10()
+            } else {
+                -600.0 // Very small magnitude
+            };
+            (mag_db, v.arg() * 180.0 / std::f64::consts::PI)
+        })
+        .collect()
+}
+
 pub fn debug_dump_engine(engine: &Engine) {
     println!(
         "engine: nodes={} instances={}",
@@ -1,3 +1,4 @@
+pub mod diagnostic;
 pub mod netlist;
 pub mod topology;
 pub mod mna;
@@ -8,8 +9,25 @@ pub mod circuit;
 pub mod engine;
 pub mod analysis;
 pub mod stamp;
+pub mod state_table;
+pub mod integrator;
 pub mod newton;
 pub mod psf;
 pub mod raw;
+pub mod op_file;
 pub mod complex_mna;
 pub mod complex_solver;
+pub mod behavioral;
+pub mod check;
+pub mod spectre;
+pub mod touchstone;
+pub mod rlgc;
+pub mod digital;
+pub mod device_model;
+pub mod corner;
+pub mod measure;
+pub mod noise;
+pub mod periodic;
+pub mod sensitivity;
+pub mod bypass;
+pub mod ordering;
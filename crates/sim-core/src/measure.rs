@@ -0,0 +1,466 @@
+//! `.measure` 求值: 在已保存的波形上计算延迟、转换时间、过冲、建立时间、穿越时刻、
+//! 区间统计, 以及引用其他测量结果的表达式。语句由 [`crate::netlist::parse_measure`] 解析,
+//! 引擎在对应分析结束后求值。
+//!
+//! 波形的横轴随分析而定: 瞬态 / PSS 为 `tran_times`, AC 为 `ac_frequencies`,
+//! DC 扫描为 `sweep_values`; 两个采样点之间按线性插值。
+
+use crate::behavioral::{rewrite_references, BehavioralExpr};
+use crate::result_store::{AnalysisType, RunResult};
+
+/// 被测信号
+#[derive(Debug, Clone, PartialEq)]
+pub enum Signal {
+    /// `v(a)` / `v(a,b)`; AC 结果取线性幅度
+    Voltage(String, Option<String>),
+    /// `i(dev)`: 器件支路电流, 仅用于实数结果 (瞬态、DC 扫描)
+    Current(String),
+    /// `vdb(a)` / `vdb(a,b)`: AC 幅度 (dB)
+    Db(String, Option<String>),
+    /// `vp(a)` / `vp(a,b)`: AC 相位 (度)
+    Phase(String, Option<String>),
+}
+
+impl Signal {
+    /// 解析 `v(out)`、`v(a,b)`、`vm(...)`、`vdb(...)`、`vp(...)` 与 `i(dev)`
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let open = text.find('(')?;
+        let inner = text[open + 1..].strip_suffix(')')?;
+        let args: Vec<String> = inner.split(',').map(|arg| arg.trim().to_string()).collect();
+        if args.iter().any(String::is_empty) || args.len() > 2 {
+            return None;
+        }
+        let node = args[0].clone();
+        let reference = args.get(1).cloned();
+        match text[..open].trim().to_ascii_lowercase().as_str() {
+            "v" | "vm" => Some(Signal::Voltage(node, reference)),
+            "vdb" => Some(Signal::Db(node, reference)),
+            "vp" => Some(Signal::Phase(node, reference)),
+            "i" if reference.is_none() => Some(Signal::Current(node)),
+            _ => None,
+        }
+    }
+}
+
+/// 穿越方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Rise,
+    Fall,
+    /// 上升或下降均可
+    Cross,
+}
+
+/// 取第几次穿越
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occurrence {
+    /// 第 n 次 (从 1 开始)
+    Nth(usize),
+    Last,
+}
+
+/// `signal` 在 `td` 之后按 `edge` 方向第 `occurrence` 次穿过 `value`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crossing {
+    pub signal: Signal,
+    pub value: f64,
+    pub edge: Edge,
+    pub occurrence: Occurrence,
+    /// 早于此时刻的穿越不计
+    pub td: f64,
+}
+
+/// 区间统计量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    Avg,
+    Rms,
+    Min,
+    Max,
+    /// 峰峰值
+    Pp,
+    /// 积分 (梯形)
+    Integ,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Measure {
+    /// 穿越时刻 (WHEN)
+    When(Crossing),
+    /// `at` 处的值 (FIND ... AT=)
+    FindAt { signal: Signal, at: f64 },
+    /// 目标穿越与触发穿越的时刻差 (TRIG ... TARG)
+    Delay { trig: Crossing, targ: Crossing },
+    /// 转换时间: 从第 `occurrence` 次越过起始电平到此后首次越过终止电平的时间;
+    /// 上升沿从 `low` 到 `high`, 下降沿从 `high` 到 `low`
+    Slew {
+        signal: Signal,
+        low: f64,
+        high: f64,
+        edge: Edge,
+        occurrence: Occurrence,
+    },
+    /// 过冲百分比: 区间内越过终值的最大偏移相对于阶跃幅度 (终值 − 初值);
+    /// 初值、终值取区间两端的值
+    Overshoot { signal: Signal, from: f64, to: f64 },
+    /// 建立时间: 从 `from` 到信号最后一次进入终值 ± `tol`·|终值 − 初值| 误差带的时间
+    Settling { signal: Signal, from: f64, to: f64, tol: f64 },
+    /// 区间 [from, to] 上的统计量; 缺省为整个波形
+    Stat {
+        stat: Stat,
+        signal: Signal,
+        from: Option<f64>,
+        to: Option<f64>,
+    },
+    /// 引用之前测量结果 (按名字) 的表达式, 如 `tpd_rise + tpd_fall`
+    Expr(String),
+}
+
+/// 一条具名的测量
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasureSpec {
+    pub name: String,
+    pub measure: Measure,
+}
+
+/// `.measure` 作用的分析
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeasureAnalysis {
+    Tran,
+    Ac,
+    Dc,
+}
+
+impl MeasureAnalysis {
+    /// 该类测量是否在 `analysis` 的结果上求值
+    pub fn applies_to(self, analysis: AnalysisType) -> bool {
+        matches!(
+            (self, analysis),
+            (MeasureAnalysis::Tran, AnalysisType::Tran) | (MeasureAnalysis::Ac, AnalysisType::Ac) | (MeasureAnalysis::Dc, AnalysisType::Dc)
+        )
+    }
+}
+
+/// 网表中的一条 `.measure`: 作用的分析、测量本身与可选的规格上下限 (`min=` / `max=`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasureCmd {
+    pub analysis: MeasureAnalysis,
+    pub spec: MeasureSpec,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl MeasureCmd {
+    /// 取值是否落在规格上下限之内; 没有给出的限不检查
+    pub fn within_limits(&self, value: f64) -> bool {
+        value.is_finite() && self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+/// 一条测量的结果; 求不出值时为失败原因
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub name: String,
+    pub value: Result<f64, String>,
+}
+
+/// 按顺序对 `result` 求各条测量; 表达式只能引用排在它前面的测量
+pub fn evaluate_measures(result: &RunResult, specs: &[MeasureSpec]) -> Vec<Measurement> {
+    let mut measurements: Vec<Measurement> = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let value = match &spec.measure {
+            Measure::Expr(text) => evaluate_expr(text, &measurements),
+            measure => evaluate_measure(result, measure),
+        };
+        measurements.push(Measurement {
+            name: spec.name.to_ascii_lowercase(),
+            value,
+        });
+    }
+    measurements
+}
+
+/// 求一条不引用其他测量的测量
+pub fn evaluate_measure(result: &RunResult, measure: &Measure) -> Result<f64, String> {
+    match measure {
+        Measure::When(crossing) => find_crossing(result, crossing),
+        Measure::FindAt { signal, at } => {
+            let wave = Waveform::new(result, signal)?;
+            wave.value_at(*at)
+                .ok_or_else(|| format!("x={} is outside the waveform", at))
+        }
+        Measure::Delay { trig, targ } => Ok(find_crossing(result, targ)? - find_crossing(result, trig)?),
+        Measure::Slew {
+            signal,
+            low,
+            high,
+            edge,
+            occurrence,
+        } => {
+            let (start, end) = match edge {
+                Edge::Fall => (*high, *low),
+                _ => (*low, *high),
+            };
+            let wave = Waveform::new(result, signal)?;
+            let trig = wave
+                .crossing(start, *edge, *occurrence, f64::NEG_INFINITY)
+                .ok_or_else(|| format!("signal never crosses {}", start))?;
+            let targ = wave
+                .crossing(end, *edge, Occurrence::Nth(1), trig)
+                .ok_or_else(|| format!("signal never crosses {} after x={}", end, trig))?;
+            Ok(targ - trig)
+        }
+        Measure::Overshoot { signal, from, to } => {
+            let wave = Waveform::new(result, signal)?.window(Some(*from), Some(*to))?;
+            let (initial, last) = (wave.y[0], wave.y[wave.y.len() - 1]);
+            let step = last - initial;
+            if step == 0.0 {
+                return Err("overshoot of a signal without a step".to_string());
+            }
+            // 按阶跃方向取越过终值最远的点
+            let peak = wave
+                .y
+                .iter()
+                .map(|y| (y - last) * step.signum())
+                .fold(0.0, f64::max);
+            Ok(peak / step.abs() * 100.0)
+        }
+        Measure::Settling { signal, from, to, tol } => {
+            let wave = Waveform::new(result, signal)?.window(Some(*from), Some(*to))?;
+            let (initial, last) = (wave.y[0], wave.y[wave.y.len() - 1]);
+            let band = tol * (last - initial).abs();
+            // 从末尾往前找最后一个在误差带外的点, 在它与下一点之间插值出进入误差带的时刻
+            let outside = (0..wave.y.len()).rev().find(|idx| (wave.y[*idx] - last).abs() > band);
+            let Some(idx) = outside else { return Ok(0.0) };
+            let (x0, x1, y0, y1) = (wave.x[idx], wave.x[idx + 1], wave.y[idx], wave.y[idx + 1]);
+            let edge = if y0 > last { last + band } else { last - band };
+            Ok(interpolate_x(x0, x1, y0, y1, edge) - from)
+        }
+        Measure::Stat { stat, signal, from, to } => {
+            let wave = Waveform::new(result, signal)?.window(*from, *to)?;
+            wave.stat(*stat)
+        }
+        Measure::Expr(text) => evaluate_expr(text, &[]),
+    }
+}
+
+/// `signal` 在各 `at` 处的线性插值; 超出波形范围时返回 Err
+pub fn sample_signal(result: &RunResult, signal: &Signal, at: &[f64]) -> Result<Vec<f64>, String> {
+    let wave = Waveform::new(result, signal)?;
+    at.iter()
+        .map(|x| wave.value_at(*x).ok_or_else(|| format!("x={} is outside the waveform", x)))
+        .collect()
+}
+
+fn find_crossing(result: &RunResult, crossing: &Crossing) -> Result<f64, String> {
+    Waveform::new(result, &crossing.signal)?
+        .crossing(crossing.value, crossing.edge, crossing.occurrence, crossing.td)
+        .ok_or_else(|| format!("{:?} never crosses {} as requested", crossing.signal, crossing.value))
+}
+
+/// 把表达式中的测量名换成其值后求值
+fn evaluate_expr(text: &str, measurements: &[Measurement]) -> Result<f64, String> {
+    let failed = std::cell::RefCell::new(None);
+    let text = rewrite_references(
+        text,
+        |ident| {
+            let measurement = measurements.iter().find(|m| m.name.eq_ignore_ascii_case(ident))?;
+            match &measurement.value {
+                Ok(value) => Some(format!("({})", value)),
+                Err(_) => {
+                    failed.replace(Some(measurement.name.clone()));
+                    None
+                }
+            }
+        },
+        str::to_string,
+        str::to_string,
+    );
+    if let Some(name) = failed.into_inner() {
+        return Err(format!("measurement '{}' has no value", name));
+    }
+    let expr = BehavioralExpr::parse(&text)?;
+    if !expr.vars.is_empty() {
+        return Err(format!("expression '{}' references circuit quantities", text));
+    }
+    Ok(expr.eval(&[]).0)
+}
+
+/// 一个信号的采样波形
+struct Waveform {
+    x: Vec<f64>,
+    y: Vec<f64>,
+}
+
+impl Waveform {
+    fn new(result: &RunResult, signal: &Signal) -> Result<Self, String> {
+        let node = |name: &str| {
+            result
+                .node_names
+                .iter()
+                .position(|node| node.eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("node '{}' not found", name))
+        };
+        match result.analysis {
+            AnalysisType::Ac => {
+                let zero = num_complex::Complex64::new(0.0, 0.0);
+                let y = match signal {
+                    Signal::Voltage(name, reference) | Signal::Db(name, reference) | Signal::Phase(name, reference) => {
+                        let idx = node(name)?;
+                        let reference = reference.as_deref().map(node).transpose()?;
+                        result
+                            .ac_phasors
+                            .iter()
+                            .map(|phasors| {
+                                let phasor = |idx: usize| phasors.get(idx).copied().unwrap_or(zero);
+                                let value = phasor(idx) - reference.map_or(zero, phasor);
+                                match signal {
+                                    Signal::Db(..) => 20.0 * value.norm().log10(),
+                                    Signal::Phase(..) => value.arg().to_degrees(),
+                                    _ => value.norm(),
+                                }
+                            })
+                            .collect()
+                    }
+                    Signal::Current(device) => (0..result.ac_phasors.len())
+                        .map(|point| result.ac_branch_current(device, point).map(|current| current.norm()))
+                        .collect::<Option<Vec<f64>>>()
+                        .ok_or_else(|| format!("no branch current for '{}'", device))?,
+                };
+                Self::checked(result.ac_frequencies.clone(), y)
+            }
+            AnalysisType::Tran | AnalysisType::Pss | AnalysisType::Dc => {
+                let (x, solutions) = match result.analysis {
+                    AnalysisType::Dc => (&result.sweep_values, &result.sweep_solutions),
+                    _ => (&result.tran_times, &result.tran_solutions),
+                };
+                let y = match signal {
+                    Signal::Voltage(name, reference) => {
+                        let idx = node(name)?;
+                        let reference = reference.as_deref().map(node).transpose()?;
+                        solutions
+                            .iter()
+                            .map(|solution| solution[idx] - reference.map_or(0.0, |r| solution[r]))
+                            .collect()
+                    }
+                    Signal::Current(device) => solutions
+                        .iter()
+                        .map(|solution| result.branch_current(device, solution))
+                        .collect::<Option<Vec<f64>>>()
+                        .ok_or_else(|| format!("no branch current for '{}'", device))?,
+                    Signal::Db(..) | Signal::Phase(..) => {
+                        return Err("vdb() / vp() are only available in AC results".to_string())
+                    }
+                };
+                Self::checked(x.clone(), y)
+            }
+            _ => Err(format!("{:?} results have no waveform to measure", result.analysis)),
+        }
+    }
+
+    fn checked(x: Vec<f64>, y: Vec<f64>) -> Result<Self, String> {
+        if x.is_empty() || x.len() != y.len() {
+            return Err("empty waveform".to_string());
+        }
+        Ok(Self { x, y })
+    }
+
+    fn value_at(&self, at: f64) -> Option<f64> {
+        let last = self.x.len() - 1;
+        if at < self.x[0] || at > self.x[last] {
+            return None;
+        }
+        let idx = self.x.partition_point(|x| *x <= at).clamp(1, last.max(1));
+        if last == 0 {
+            return Some(self.y[0]);
+        }
+        let (x0, x1, y0, y1) = (self.x[idx - 1], self.x[idx], self.y[idx - 1], self.y[idx]);
+        if x1 == x0 {
+            return Some(y1);
+        }
+        Some(y0 + (y1 - y0) * (at - x0) / (x1 - x0))
+    }
+
+    /// 截取 [from, to], 两端按插值补点
+    fn window(&self, from: Option<f64>, to: Option<f64>) -> Result<Self, String> {
+        let from = from.unwrap_or(self.x[0]);
+        let to = to.unwrap_or(self.x[self.x.len() - 1]);
+        let (Some(start), Some(end)) = (self.value_at(from), self.value_at(to)) else {
+            return Err(format!("window [{}, {}] is outside the waveform", from, to));
+        };
+        if to <= from {
+            return Err(format!("empty window [{}, {}]", from, to));
+        }
+        let mut x = vec![from];
+        let mut y = vec![start];
+        for (xi, yi) in self.x.iter().zip(&self.y) {
+            if *xi > from && *xi < to {
+                x.push(*xi);
+                y.push(*yi);
+            }
+        }
+        x.push(to);
+        y.push(end);
+        Ok(Self { x, y })
+    }
+
+    fn crossing(&self, value: f64, edge: Edge, occurrence: Occurrence, td: f64) -> Option<f64> {
+        let mut found = None;
+        let mut count = 0;
+        for idx in 1..self.x.len() {
+            let (y0, y1) = (self.y[idx - 1], self.y[idx]);
+            let rise = y0 < value && y1 >= value;
+            let fall = y0 > value && y1 <= value;
+            let matched = match edge {
+                Edge::Rise => rise,
+                Edge::Fall => fall,
+                Edge::Cross => rise || fall,
+            };
+            if !matched {
+                continue;
+            }
+            let x = interpolate_x(self.x[idx - 1], self.x[idx], y0, y1, value);
+            if x < td {
+                continue;
+            }
+            count += 1;
+            found = Some(x);
+            if occurrence == Occurrence::Nth(count) {
+                return found;
+            }
+        }
+        match occurrence {
+            Occurrence::Last => found,
+            Occurrence::Nth(_) => None,
+        }
+    }
+
+    fn stat(&self, stat: Stat) -> Result<f64, String> {
+        let span = self.x[self.x.len() - 1] - self.x[0];
+        let integral = |f: &dyn Fn(f64) -> f64| {
+            (1..self.x.len())
+                .map(|idx| 0.5 * (f(self.y[idx - 1]) + f(self.y[idx])) * (self.x[idx] - self.x[idx - 1]))
+                .sum::<f64>()
+        };
+        let max = self.y.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let min = self.y.iter().copied().fold(f64::INFINITY, f64::min);
+        match stat {
+            Stat::Min => Ok(min),
+            Stat::Max => Ok(max),
+            Stat::Pp => Ok(max - min),
+            Stat::Integ => Ok(integral(&|y| y)),
+            Stat::Avg | Stat::Rms if span <= 0.0 => Err("window has zero width".to_string()),
+            Stat::Avg => Ok(integral(&|y| y) / span),
+            Stat::Rms => Ok((integral(&|y| y * y) / span).sqrt()),
+        }
+    }
+}
+
+/// 线段 (x0, y0)–(x1, y1) 上 y = `value` 处的 x
+fn interpolate_x(x0: f64, x1: f64, y0: f64, y1: f64, value: f64) -> f64 {
+    if y1 == y0 {
+        return x1;
+    }
+    x0 + (value - y0) * (x1 - x0) / (y1 - y0)
+}
@@ -75,10 +75,45 @@ impl SparseBuilder {
         if new_n <= self.n {
             return;
         }
-        self.col_entries.resize_with(new_n, Vec::new);
+        if self.col_entries.len() < new_n {
+            self.col_entries.resize_with(new_n, Vec::new);
+        }
         self.n = new_n;
     }
 
+    /// 清空所有插入并把维数退回 `n`; 各列已分配的存储保留, 供下一次 stamp 复用
+    pub fn reset(&mut self, n: usize) {
+        for col in &mut self.col_entries {
+            col.clear();
+        }
+        self.n = n;
+        if self.col_entries.len() < n {
+            self.col_entries.resize_with(n, Vec::new);
+        }
+    }
+
+    /// 按 `pattern` 记录的结构生成 CSC: 各列的插入序列 (行号与先后) 与上次相同时只刷新数值,
+    /// 否则重新建立结构。同一位置的多次插入相加
+    pub fn finalize_with(&mut self, pattern: &mut StampPattern) -> (Vec<i64>, Vec<i64>, Vec<f64>) {
+        let columns = &self.col_entries[..self.n];
+        let same = pattern.rows.len() == columns.len()
+            && pattern
+                .rows
+                .iter()
+                .zip(columns)
+                .all(|(rows, col)| rows.len() == col.len() && rows.iter().zip(col).all(|(row, (r, _))| row == r));
+        if same {
+            pattern.stats.reuses += 1;
+        } else {
+            pattern.rebuild(columns);
+        }
+        let mut ax = vec![0.0; pattern.ai.len()];
+        for (slot, (_, value)) in pattern.slots.iter().zip(columns.iter().flatten()) {
+            ax[*slot] += value;
+        }
+        (pattern.ap.clone(), pattern.ai.clone(), ax)
+    }
+
     pub fn finalize(&mut self) -> (Vec<i64>, Vec<i64>, Vec<f64>) {
         let mut ap = Vec::with_capacity(self.n + 1);
         let mut ai = Vec::new();
@@ -86,7 +121,7 @@ impl SparseBuilder {
 
         let mut nnz = 0;
         ap.push(0);
-        for col in &mut self.col_entries {
+        for col in &mut self.col_entries[..self.n] {
             col.sort_by_key(|(row, _)| *row);
             for (row, value) in col.iter() {
                 ai.push(*row as i64);
@@ -100,6 +135,95 @@ impl SparseBuilder {
     }
 }
 
+/// 稀疏矩阵的结构: 合并重复元后的 CSC 行号, 以及每次插入 (按列、按插入顺序) 落在 `ax` 中的位置
+#[derive(Debug, Clone, Default)]
+pub struct StampPattern {
+    ap: Vec<i64>,
+    ai: Vec<i64>,
+    /// 各列按插入顺序的行号, 用于判断结构是否变化
+    rows: Vec<Vec<usize>>,
+    slots: Vec<usize>,
+    pub stats: PatternStats,
+}
+
+/// 矩阵结构重新建立与直接复用的次数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PatternStats {
+    pub rebuilds: usize,
+    pub reuses: usize,
+}
+
+impl StampPattern {
+    fn rebuild(&mut self, columns: &[Vec<(usize, f64)>]) {
+        self.stats.rebuilds += 1;
+        self.ap = Vec::with_capacity(columns.len() + 1);
+        self.ai.clear();
+        self.slots.clear();
+        self.ap.push(0);
+        for col in columns {
+            let start = self.ai.len();
+            let mut rows: Vec<usize> = col.iter().map(|(row, _)| *row).collect();
+            rows.sort_unstable();
+            rows.dedup();
+            self.ai.extend(rows.iter().map(|row| *row as i64));
+            for (row, _) in col {
+                let offset = rows.binary_search(row).unwrap_or_default();
+                self.slots.push(start + offset);
+            }
+            self.ap.push(self.ai.len() as i64);
+        }
+        self.rows = columns.iter().map(|col| col.iter().map(|(row, _)| *row).collect()).collect();
+    }
+}
+
+/// 跨 Newton 迭代与时间步复用的实数 MNA 工作区: 各列存储只分配一次,
+/// 矩阵结构只在第一次或插入序列变化时建立, 其余时候只刷新数值
+#[derive(Debug)]
+pub struct MnaWorkspace {
+    pub mna: MnaBuilder,
+    pub pattern: StampPattern,
+}
+
+impl MnaWorkspace {
+    pub fn new(node_count: usize) -> Self {
+        Self {
+            mna: MnaBuilder::new(node_count),
+            pattern: StampPattern::default(),
+        }
+    }
+
+    /// 清空上一次的 stamp, 返回供本次 stamp 的构建器; 节点数变化时整体重建
+    pub fn begin(&mut self, node_count: usize) -> &mut MnaBuilder {
+        if self.mna.node_count != node_count {
+            self.mna = MnaBuilder::new(node_count);
+        }
+        self.mna.reset();
+        &mut self.mna
+    }
+
+    /// 生成本次的 (ap, ai, ax, rhs, n)
+    pub fn finish(&mut self) -> (Vec<i64>, Vec<i64>, Vec<f64>, Vec<f64>, usize) {
+        let (ap, ai, ax) = self.mna.builder.finalize_with(&mut self.pattern);
+        (ap, ai, ax, self.mna.rhs.clone(), self.mna.builder.n)
+    }
+}
+
+/// 跨 Newton 迭代保存的 PN 结电压, 键为 `实例名:结名`; 结电压限幅
+/// (pnjlim) 以上一次迭代实际使用的结电压为基准
+#[derive(Debug, Default, Clone)]
+pub struct JunctionState {
+    pub voltages: std::collections::HashMap<String, f64>,
+    /// 本次 stamp 中是否有结电压被限幅; 有则该次迭代不能算作收敛
+    pub limited: bool,
+}
+
+/// 一次 stamp 写入的矩阵元与右端项 (已乘并联倍数), 供器件旁路原样重放
+#[derive(Debug, Clone, Default)]
+pub struct StampRecord {
+    pub matrix: Vec<(usize, usize, f64)>,
+    pub rhs: Vec<(usize, f64)>,
+}
+
 #[derive(Debug)]
 pub struct StampContext<'a> {
     pub builder: &'a mut SparseBuilder,
@@ -108,19 +232,53 @@ pub struct StampContext<'a> {
     pub node_count: usize,
     pub gmin: f64,
     pub source_scale: f64,
+    /// 当前器件的并联倍数 (m=), 只作用于节点 KCL 行, 辅助变量的支路方程不变
+    pub multiplier: f64,
+    /// 结电压限幅状态; 为 None 时不做限幅
+    pub junctions: Option<&'a mut JunctionState>,
+    /// 不为 None 时同时记录写入的内容
+    pub record: Option<StampRecord>,
 }
 
 impl<'a> StampContext<'a> {
     pub fn add(&mut self, i: usize, j: usize, value: f64) {
+        let value = if i < self.node_count {
+            value * self.multiplier
+        } else {
+            value
+        };
+        if let Some(record) = self.record.as_mut() {
+            record.matrix.push((i, j, value));
+        }
         self.builder.insert(j, i, value);
     }
 
     pub fn add_rhs(&mut self, i: usize, value: f64) {
+        let value = if i < self.node_count {
+            value * self.multiplier
+        } else {
+            value
+        };
+        if let Some(record) = self.record.as_mut() {
+            record.rhs.push((i, value));
+        }
         if let Some(entry) = self.rhs.get_mut(i) {
             *entry += value;
         }
     }
 
+    /// 重放先前记录的 stamp
+    pub fn replay(&mut self, record: &StampRecord) {
+        for &(i, j, value) in &record.matrix {
+            self.builder.insert(j, i, value);
+        }
+        for &(i, value) in &record.rhs {
+            if let Some(entry) = self.rhs.get_mut(i) {
+                *entry += value;
+            }
+        }
+    }
+
     pub fn allocate_aux(&mut self, name: &str) -> usize {
         let (aux_id, is_new) = self.aux.allocate_with_flag(name);
         let index = self.node_count + aux_id;
@@ -130,6 +288,25 @@ impl<'a> StampContext<'a> {
         }
         index
     }
+
+    /// 对结电压做 pnjlim 限幅并记录本次使用的值; 首次迭代以 0 V 为基准
+    pub fn limit_junction(&mut self, key: &str, vnew: f64, nvt: f64, vcrit: f64) -> f64 {
+        self.limit_junction_with(key, vnew, |vnew, vold| {
+            sim_devices::junction::pnjlim(vnew, vold, nvt, vcrit)
+        })
+    }
+
+    /// 用器件自己的限幅规则 `limit(vnew, vold)` 限幅, 其余同 [`Self::limit_junction`]
+    pub fn limit_junction_with(&mut self, key: &str, vnew: f64, limit: impl FnOnce(f64, f64) -> f64) -> f64 {
+        let Some(junctions) = self.junctions.as_deref_mut() else {
+            return vnew;
+        };
+        let vold = junctions.voltages.get(key).copied().unwrap_or(0.0);
+        let v = limit(vnew, vold);
+        junctions.limited |= v != vnew;
+        junctions.voltages.insert(key.to_string(), v);
+        v
+    }
 }
 
 #[derive(Debug)]
@@ -153,6 +330,15 @@ impl MnaBuilder {
         }
     }
 
+    /// 清空矩阵、右端项与辅助变量, 维数退回节点数; 已分配的存储保留
+    pub fn reset(&mut self) {
+        self.builder.reset(self.node_count);
+        self.rhs.clear();
+        self.rhs.resize(self.node_count, 0.0);
+        self.aux.name_to_id.clear();
+        self.aux.id_to_name.clear();
+    }
+
     pub fn context(&mut self) -> StampContext<'_> {
         StampContext {
             builder: &mut self.builder,
@@ -161,6 +347,9 @@ impl MnaBuilder {
             node_count: self.node_count,
             gmin: 0.0,
             source_scale: 1.0,
+            multiplier: 1.0,
+            junctions: None,
+            record: None,
         }
     }
 
@@ -172,6 +361,22 @@ impl MnaBuilder {
             node_count: self.node_count,
             gmin,
             source_scale,
+            multiplier: 1.0,
+            junctions: None,
+            record: None,
+        }
+    }
+
+    /// 带结电压限幅状态的 stamp 上下文, 状态需在各次 Newton 迭代间保留
+    pub fn context_with_junctions<'a>(
+        &'a mut self,
+        gmin: f64,
+        source_scale: f64,
+        junctions: &'a mut JunctionState,
+    ) -> StampContext<'a> {
+        StampContext {
+            junctions: Some(junctions),
+            ..self.context_with(gmin, source_scale)
         }
     }
 }
@@ -1,14 +1,34 @@
+use crate::diagnostic::{codes, Diagnostic};
+
 #[derive(Debug, Clone)]
 pub struct NetlistAst {
     pub title: Option<String>,
     pub statements: Vec<Stmt>,
-    pub errors: Vec<ParseError>,
+    pub errors: Vec<Diagnostic>,
+    /// 展开 include 后每一行对应的 (源文件, 行号); 直接解析字符串时为空
+    pub line_origins: Vec<(std::path::PathBuf, usize)>,
+    /// `.control` ... `.endc` 块中的命令, 按出现顺序; 由 CLI 解释执行
+    pub control_script: Vec<ScriptCommand>,
+}
+
+impl NetlistAst {
+    /// 把基于展开后文本的行号换算成源文件位置
+    pub fn locate(&self, mut diag: Diagnostic) -> Diagnostic {
+        if diag.file.is_none() && diag.line > 0 {
+            if let Some((file, line)) = self.line_origins.get(diag.line - 1) {
+                diag.file = Some(file.clone());
+                diag.line = *line;
+            }
+        }
+        diag
+    }
 }
 
+/// `.control` 块中的一行命令 (ngspice 脚本语法, 如 `run`、`alter R1 2k`)
 #[derive(Debug, Clone)]
-pub struct ParseError {
+pub struct ScriptCommand {
+    pub text: String,
     pub line: usize,
-    pub message: String,
 }
 
 #[derive(Debug, Clone)]
@@ -75,6 +95,30 @@ pub enum ControlKind {
     Dc,
     Tran,
     Ac,
+    Noise,
+    Tf,
+    Four,
+    Sens,
+    Pz,
+    Pss,
+    Sp,
+    Pac,
+    Pnoise,
+    /// `.mc <runs> [seed=<n>]`: Monte Carlo 运行次数与种子
+    Mc,
+    /// `.corner <name> [lib=] [temp=] [<param>=...]`: 工艺角
+    Corner,
+    /// `.step ...`: 多维参数扫描的一维
+    Step,
+    Ic,
+    Data,
+    Options,
+    /// `.csparam name=expr`: 由仿真结果计算的输出参数
+    Csparam,
+    /// `.probe I(R1) I(X1.M1) ...`: 需要支路电流的器件
+    Probe,
+    /// `.measure` / `.meas`: 分析结束后在结果波形上求的测量
+    Measure,
     End,
     Other,
 }
@@ -86,13 +130,40 @@ pub enum DeviceKind {
     L,
     V,
     I,
+    /// 二极管: `D1 a k model [area]`
     D,
     M,
+    /// 双极型晶体管: `Q1 c b e [s] model [area]`
+    Q,
+    /// 结型场效应管: `J1 d g s model [area]`
+    J,
+    /// MESFET / HEMT: `Z1 d g s model [area]`
+    Z,
+    /// 电压控制开关: `S1 n+ n- nc+ nc- model`
+    S,
+    /// 电流控制开关: `W1 n+ n- vname model`
+    W,
+    /// 多导体有损传输线: `W1 i1 .. iN iref o1 .. oN oref N=2 L=0.1 RLGCMODEL=bus [NSEG=20]`,
+    /// 带 N= 的 W 语句按此解析
+    WLine,
+    /// S 参数块: `S1 p1 p2 ... [ref] FILE=amp.s2p`, 端口数取自 Touchstone 文件扩展名,
+    /// 省略参考节点时为地; 带 FILE= 的 S 语句按此解析
+    SParam,
+    /// 理想变压器: `N1 p+ p- s+ s- ratio`, ratio 为匝数比 Np/Ns
+    N,
+    /// 无损传输线: `T1 a+ a- b+ b- Z0=50 TD=1n` (或 `F=1g [NL=0.25]`)
+    T,
+    /// RF 端口: `P1 n+ n- PORT=1 [Z0=50] [DC v] [AC mag [phase]]`
+    P,
     E,
     G,
     F,
     H,
     X,
+    /// 行为源: `B1 n+ n- V={expr}` / `I={expr}`
+    B,
+    /// 数字器件 / 混合信号桥 (XSPICE 风格): `A1 in1 in2 out model`, 端口列表可以写成 `[in1 in2]`
+    A,
     Unknown,
 }
 
@@ -100,8 +171,12 @@ pub enum DeviceKind {
 pub struct ElaboratedNetlist {
     pub instances: Vec<DeviceStmt>,
     pub subckt_models: Vec<ControlStmt>,
+    /// 子电路端口的层次名 (`X1.a`) -> 实例上连接的节点名
+    pub node_aliases: Vec<(String, String)>,
     pub control_count: usize,
     pub error_count: usize,
+    /// 解析与子电路展开阶段的全部诊断
+    pub errors: Vec<Diagnostic>,
 }
 
 #[derive(Debug, Clone)]
@@ -113,20 +188,194 @@ pub struct SubcktDef {
     pub line: usize,
 }
 
+/// .include 搜索路径环境变量, 多个目录按平台分隔符 (`:` / `;`) 分隔
+pub const INCLUDE_PATH_ENV: &str = "MYSPICE_INCLUDE_PATH";
+
+/// 网表语法; 文件格式默认按扩展名判断
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetlistFormat {
+    Spice,
+    Spectre,
+}
+
+impl NetlistFormat {
+    /// `.scs` 为 Spectre, 常见 SPICE 扩展名为 SPICE, 其余无法判断
+    pub fn from_extension(path: &std::path::Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            // `model.scs.gz` 按解压后的扩展名判断
+            "gz" => Self::from_extension(std::path::Path::new(path.file_stem()?)),
+            "scs" => Some(NetlistFormat::Spectre),
+            "sp" | "spi" | "spice" | "cir" | "ckt" | "net" | "lib" | "inc" => Some(NetlistFormat::Spice),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &std::path::Path) -> Self {
+        Self::from_extension(path).unwrap_or(NetlistFormat::Spice)
+    }
+}
+
 pub fn parse_netlist_file(path: &std::path::Path) -> NetlistAst {
-    let mut errors = Vec::new();
-    let mut visited = std::collections::HashSet::new();
-    let content = read_with_includes(path, &mut visited, &mut errors);
-    let mut ast = parse_netlist(&content);
+    parse_netlist_file_with_includes(path, &[])
+}
+
+/// 解析网表文件, .include 的相对路径依次在以下位置查找:
+/// 1. 包含该语句的文件所在目录
+/// 2. `search_dirs` (例如 CLI 的 `-I` 参数), 按给定顺序
+/// 3. 环境变量 `MYSPICE_INCLUDE_PATH` 中的目录
+pub fn parse_netlist_file_with_includes(
+    path: &std::path::Path,
+    search_dirs: &[std::path::PathBuf],
+) -> NetlistAst {
+    parse_netlist_file_as(path, search_dirs, NetlistFormat::from_path(path))
+}
+
+/// 按指定语法解析网表文件; 被 include 的文件仍按扩展名判断, 无法判断时沿用上层语法
+pub fn parse_netlist_file_as(
+    path: &std::path::Path,
+    search_dirs: &[std::path::PathBuf],
+    format: NetlistFormat,
+) -> NetlistAst {
+    let (content, origins, errors) = read_netlist_file(path, search_dirs, format, None);
+    parse_expanded_deck(&content, &origins, errors, path, format)
+}
+
+/// 解析可能包含多个以 `.end` 分隔的网表 (deck) 的文件, 每个 deck 一个 AST。
+/// 只有顶层文件中的 `.end` 分隔 deck, include 文件里的 `.end` 不算;
+/// 文件读取与 include 错误归入第一个 deck。
+pub fn parse_netlist_file_decks(
+    path: &std::path::Path,
+    search_dirs: &[std::path::PathBuf],
+    format: NetlistFormat,
+) -> Vec<NetlistAst> {
+    parse_netlist_file_decks_with_lib(path, search_dirs, format, None)
+}
+
+/// 同 [`parse_netlist_file_decks`], 但 `.lib <file> <section>` 都改为读入 `lib_section` 段
+/// (工艺角切换模型库用; 库文件段内对其他段的引用不变); `None` 时按网表所写
+pub fn parse_netlist_file_decks_with_lib(
+    path: &std::path::Path,
+    search_dirs: &[std::path::PathBuf],
+    format: NetlistFormat,
+    lib_section: Option<&str>,
+) -> Vec<NetlistAst> {
+    let (content, origins, errors) = read_netlist_file(path, search_dirs, format, lib_section);
+    let decks = split_deck_lines(&content, |idx| origins.get(idx).is_some_and(|(file, _)| file == path));
+    let mut errors = Some(errors);
+    decks
+        .iter()
+        .map(|deck| {
+            parse_expanded_deck(deck, &origins, errors.take().unwrap_or_default(), path, format)
+        })
+        .collect()
+}
+
+/// 解析包含多个 deck 的网表文本
+pub fn parse_netlist_decks(input: &str) -> Vec<NetlistAst> {
+    split_decks(input).iter().map(|deck| parse_netlist(deck)).collect()
+}
+
+/// 按 `.end` 把网表文本分成多个 deck。每个 deck 保持原始行号 (其他 deck 的行
+/// 替换为空行); 最后一个 `.end` 之后只剩空行或注释时不算新的 deck。
+pub fn split_decks(input: &str) -> Vec<String> {
+    split_deck_lines(input, |_| true)
+}
+
+fn split_deck_lines(input: &str, is_top_level: impl Fn(usize) -> bool) -> Vec<String> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut decks = Vec::new();
+    let mut start = 0;
+    let mut has_content = false;
+    let mut push_deck = |start: usize, end: usize| {
+        let mut deck = "\n".repeat(start);
+        for line in &lines[start..end] {
+            deck.push_str(line);
+            deck.push('\n');
+        }
+        decks.push(deck);
+    };
+    for (idx, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.eq_ignore_ascii_case(".end") && is_top_level(idx) {
+            push_deck(start, idx + 1);
+            start = idx + 1;
+            has_content = false;
+        } else if !trimmed.is_empty() && !trimmed.starts_with('*') {
+            has_content = true;
+        }
+    }
+    if has_content || start == 0 {
+        push_deck(start, lines.len());
+    }
+    decks
+}
+
+/// 读入网表文件并展开 include, 返回展开后的文本、每行来源与读取错误
+fn read_netlist_file(
+    path: &std::path::Path,
+    search_dirs: &[std::path::PathBuf],
+    format: NetlistFormat,
+    lib_section: Option<&str>,
+) -> (String, Vec<(std::path::PathBuf, usize)>, Vec<Diagnostic>) {
+    let mut dirs = search_dirs.to_vec();
+    if let Some(env_dirs) = std::env::var_os(INCLUDE_PATH_ENV) {
+        dirs.extend(std::env::split_paths(&env_dirs).filter(|d| !d.as_os_str().is_empty()));
+    }
+    let mut state = IncludeState {
+        search_dirs: dirs,
+        lib_section: lib_section.map(str::to_string),
+        chain: Vec::new(),
+        errors: Vec::new(),
+        origins: Vec::new(),
+        string_params: std::collections::HashMap::new(),
+    };
+    let content = read_with_includes(path, format, None, &mut state);
+    (content, state.origins, state.errors)
+}
+
+fn parse_expanded_deck(
+    content: &str,
+    origins: &[(std::path::PathBuf, usize)],
+    errors: Vec<Diagnostic>,
+    path: &std::path::Path,
+    format: NetlistFormat,
+) -> NetlistAst {
+    let mut ast = parse_netlist(content);
+    ast.line_origins = origins.to_vec();
+    resolve_touchstone_paths(&mut ast);
+    let parse_errors = std::mem::take(&mut ast.errors);
+    ast.errors = parse_errors.into_iter().map(|diag| ast.locate(diag)).collect();
+    restore_spectre_sources(&mut ast.errors, path, format);
     ast.errors.extend(errors);
     ast
 }
 
+/// Spectre 文件中的解析错误指向翻译后的文本, 换回原始源码行
+fn restore_spectre_sources(errors: &mut [Diagnostic], top: &std::path::Path, format: NetlistFormat) {
+    let mut sources: std::collections::HashMap<std::path::PathBuf, String> = Default::default();
+    for diag in errors.iter_mut() {
+        let Some(file) = diag.file.clone() else { continue };
+        let is_spectre = NetlistFormat::from_extension(&file) == Some(NetlistFormat::Spectre)
+            || (file == top && format == NetlistFormat::Spectre);
+        if !is_spectre {
+            continue;
+        }
+        let source = sources
+            .entry(file.clone())
+            .or_insert_with(|| read_netlist_text(&file).unwrap_or_default());
+        crate::spectre::restore_source_lines(std::slice::from_mut(diag), source);
+    }
+}
+
 pub fn parse_netlist(input: &str) -> NetlistAst {
     let mut title = None;
     let mut statements = Vec::new();
     let mut errors = Vec::new();
-    let mut pending_line = String::new();
+    let mut pending = PendingStatement::default();
+    let mut control_script = Vec::new();
+    // 正在读取的 `.control` 块的起始行
+    let mut control_start: Option<(usize, String)> = None;
 
     for (index, raw_line) in input.lines().enumerate() {
         let line_no = index + 1;
@@ -137,41 +386,198 @@ pub fn parse_netlist(input: &str) -> NetlistAst {
             continue;
         }
 
-        if trimmed.starts_with('*') {
+        // `.control` 块中的行是脚本命令, 不作为网表语句解析
+        if control_start.is_some() {
+            if trimmed.eq_ignore_ascii_case(".endc") {
+                control_start = None;
+            } else if !trimmed.starts_with('*') && !trimmed.starts_with('#') {
+                control_script.push(ScriptCommand {
+                    text: trimmed.to_string(),
+                    line: line_no,
+                });
+            }
+            continue;
+        }
+        if trimmed.eq_ignore_ascii_case(".control") {
+            pending.flush(&mut title, &mut statements, &mut errors);
+            control_start = Some((line_no, raw_line.to_string()));
+            continue;
+        }
+
+        if trimmed.starts_with('*') || trimmed.starts_with('$') || trimmed.starts_with(';') {
             statements.push(Stmt::Comment(trimmed.to_string()));
             continue;
         }
+        let trimmed = strip_inline_comment(trimmed).trim_end();
 
         if trimmed.starts_with('+') {
-            if pending_line.is_empty() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: "续行没有对应的上一行".to_string(),
-                });
+            if pending.text.is_empty() {
+                errors.push(
+                    Diagnostic::error(
+                        codes::ORPHAN_CONTINUATION,
+                        line_no,
+                        "continuation line has no preceding statement",
+                    )
+                    .with_source_line(raw_line),
+                );
                 continue;
             }
-            pending_line.push(' ');
-            pending_line.push_str(trimmed.trim_start_matches('+').trim());
+            pending.text.push(' ');
+            pending.text.push_str(trimmed.trim_start_matches('+').trim());
             continue;
         }
 
-        if !pending_line.is_empty() {
-            parse_statement(&pending_line, line_no, &mut title, &mut statements, &mut errors);
-            pending_line.clear();
+        // `.data` 块一直读到 `.enddata`, 数据行可以不带 `+`
+        if is_data_block(&pending.text) {
+            if trimmed.eq_ignore_ascii_case(".enddata") {
+                pending.flush(&mut title, &mut statements, &mut errors);
+                continue;
+            }
+            if !trimmed.eq_ignore_ascii_case(".end") {
+                pending.text.push(' ');
+                pending.text.push_str(trimmed);
+                continue;
+            }
+            errors.push(
+                Diagnostic::error(codes::UNTERMINATED_DATA, line_no, ".data block is missing .enddata")
+                    .with_source_line(raw_line),
+            );
         }
 
-        pending_line = trimmed.to_string();
+        // `.model name type (` 的括号未闭合时, 后续行不带 `+` 也视为续行
+        if is_open_model_card(&pending.text) {
+            pending.text.push(' ');
+            pending.text.push_str(trimmed);
+            continue;
+        }
+
+        pending.flush(&mut title, &mut statements, &mut errors);
+        pending = PendingStatement {
+            text: trimmed.to_string(),
+            line: line_no,
+            source_line: raw_line.to_string(),
+        };
     }
 
-    if !pending_line.is_empty() {
-        parse_statement(&pending_line, input.lines().count(), &mut title, &mut statements, &mut errors);
+    if is_data_block(&pending.text) {
+        errors.push(
+            Diagnostic::error(codes::UNTERMINATED_DATA, pending.line, ".data block is missing .enddata")
+                .with_source_line(pending.source_line.clone()),
+        );
+    }
+    pending.flush(&mut title, &mut statements, &mut errors);
+    if let Some((line, source_line)) = control_start {
+        errors.push(
+            Diagnostic::error(codes::UNTERMINATED_CONTROL, line, ".control block is missing .endc")
+                .with_source_line(source_line),
+        );
     }
 
     NetlistAst {
         title,
         statements,
         errors,
+        line_origins: Vec::new(),
+        control_script,
+    }
+}
+
+/// 正在累积续行的逻辑语句, 记录首行位置以便诊断定位
+#[derive(Default)]
+struct PendingStatement {
+    text: String,
+    line: usize,
+    source_line: String,
+}
+
+impl PendingStatement {
+    fn flush(
+        &mut self,
+        title: &mut Option<String>,
+        statements: &mut Vec<Stmt>,
+        errors: &mut Vec<Diagnostic>,
+    ) {
+        if self.text.is_empty() {
+            return;
+        }
+        let first_new = errors.len();
+        parse_statement(&self.text, self.line, title, statements, errors);
+        // parse_statement 给出的列相对于去掉缩进的语句文本, 这里换算成源行的列
+        let indent = self.source_line.chars().take_while(|ch| ch.is_whitespace()).count();
+        for diag in &mut errors[first_new..] {
+            if let Some(columns) = diag.columns.take() {
+                diag.columns = Some(columns.start + indent..columns.end + indent);
+            }
+            diag.source_line = Some(self.source_line.clone());
+        }
+        self.text.clear();
+    }
+}
+
+fn is_model_card(line: &str) -> bool {
+    line.split_whitespace()
+        .next()
+        .is_some_and(|first| first.eq_ignore_ascii_case(".model"))
+}
+
+fn is_data_block(line: &str) -> bool {
+    line.split_whitespace()
+        .next()
+        .is_some_and(|first| first.eq_ignore_ascii_case(".data"))
+}
+
+fn is_open_model_card(line: &str) -> bool {
+    is_model_card(line) && line.matches('(').count() > line.matches(')').count()
+}
+
+/// 去掉 `.model` 参数表外层的括号: `nmos(vth0=0.45 tox=2n)` / `nmos ( ... )`
+/// 都变成以空白分隔的 key=value; 参数值内部的括号保持不变
+fn unwrap_model_parens(rest: &str) -> String {
+    let mut chars: Vec<char> = rest.chars().collect();
+    let Some(open) = chars.iter().position(|&ch| ch == '(') else {
+        return rest.to_string();
+    };
+    let mut depth = 0usize;
+    let mut close = None;
+    for (idx, &ch) in chars.iter().enumerate().skip(open) {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(idx);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    chars[open] = ' ';
+    if let Some(close) = close {
+        chars[close] = ' ';
     }
+    chars.into_iter().collect()
+}
+
+/// 去掉行尾注释: `;` 之后的内容, 以及空白后的 `$` 之后的内容 (`net$1` 这类节点名保留);
+/// 引号内的字符不作处理
+fn strip_inline_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev_space = true;
+    for (idx, ch) in line.char_indices() {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => {}
+            None => match ch {
+                '"' | '\'' => quote = Some(ch),
+                ';' => return &line[..idx],
+                '$' if prev_space => return &line[..idx],
+                _ => {}
+            },
+        }
+        prev_space = ch.is_whitespace();
+    }
+    line
 }
 
 fn parse_statement(
@@ -179,7 +585,7 @@ fn parse_statement(
     line_no: usize,
     title: &mut Option<String>,
     statements: &mut Vec<Stmt>,
-    errors: &mut Vec<ParseError>,
+    errors: &mut Vec<Diagnostic>,
 ) {
     let mut iter = line.split_whitespace();
     let first = match iter.next() {
@@ -192,7 +598,13 @@ fn parse_statement(
 
     if first.starts_with('.') {
         let command = first.to_ascii_lowercase();
-        let tokens: Vec<&str> = iter.collect();
+        let model_body;
+        let tokens: Vec<&str> = if is_model_card(line) {
+            model_body = unwrap_model_parens(&line.trim_start()[first.len()..]);
+            model_body.split_whitespace().collect()
+        } else {
+            iter.collect()
+        };
         let (args, params) = split_args_params(&tokens);
         let kind = map_control_kind(&command);
         let mut model_name = None;
@@ -212,10 +624,23 @@ fn parse_statement(
                 model_name = Some(args[0].clone());
                 model_type = Some(args[1].clone());
             } else {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: "model 语句缺少 name/type".to_string(),
-                });
+                errors.push(Diagnostic::error(
+                    codes::MODEL_MISSING_NAME,
+                    line_no,
+                    ".model requires a name and a type",
+                ));
+            }
+        }
+
+        if matches!(kind, ControlKind::Data) {
+            if let Err(message) = parse_data_table(&args) {
+                errors.push(Diagnostic::error(codes::INVALID_DATA_TABLE, line_no, message));
+            }
+        }
+
+        if matches!(kind, ControlKind::Measure) {
+            if let Err(message) = parse_measure(line) {
+                errors.push(Diagnostic::error(codes::INVALID_MEASURE, line_no, message));
             }
         }
 
@@ -226,10 +651,11 @@ fn parse_statement(
                     subckt_ports = args[1..].to_vec();
                 }
             } else {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: "subckt 语句缺少名称".to_string(),
-                });
+                errors.push(Diagnostic::error(
+                    codes::SUBCKT_MISSING_NAME,
+                    line_no,
+                    ".subckt requires a name",
+                ));
             }
         }
 
@@ -256,24 +682,105 @@ fn parse_statement(
         'I' | 'i' => DeviceKind::I,
         'D' | 'd' => DeviceKind::D,
         'M' | 'm' => DeviceKind::M,
+        'Q' | 'q' => DeviceKind::Q,
+        'J' | 'j' => DeviceKind::J,
+        'Z' | 'z' => DeviceKind::Z,
+        'S' | 's' => DeviceKind::S,
+        'W' | 'w' => DeviceKind::W,
+        'N' | 'n' => DeviceKind::N,
+        'T' | 't' => DeviceKind::T,
+        'P' | 'p' => DeviceKind::P,
         'E' | 'e' => DeviceKind::E,
         'G' | 'g' => DeviceKind::G,
         'F' | 'f' => DeviceKind::F,
         'H' | 'h' => DeviceKind::H,
         'X' | 'x' => DeviceKind::X,
+        'B' | 'b' => DeviceKind::B,
+        'A' | 'a' => DeviceKind::A,
         _ => DeviceKind::Unknown,
     };
 
     if matches!(kind, DeviceKind::Unknown) {
-        errors.push(ParseError {
-            line: line_no,
-            message: format!("未知器件类型: {}", first),
-        });
+        errors.push(
+            Diagnostic::error(
+                codes::UNKNOWN_DEVICE,
+                line_no,
+                format!("unknown device type '{}'", first),
+            )
+            .with_columns(1..first.chars().count() + 1),
+        );
     }
 
     let tokens: Vec<&str> = iter.collect();
-    let (args, params) = split_args_params(&tokens);
-    let (nodes, model, value, extras, poly) = split_device_fields(&kind, &args);
+    // E/G 的 VALUE= / TABLE 形式按行为源处理: E 对应 V=expr, G 对应 I=expr
+    let value_table = matches!(kind, DeviceKind::E | DeviceKind::G) && is_value_or_table(&tokens);
+    if matches!(kind, DeviceKind::B) || value_table {
+        let (nodes, params) = if value_table {
+            split_value_table_fields(first, &kind, &tokens, line_no, errors)
+        } else {
+            split_behavioral_fields(first, &tokens, line_no, errors)
+        };
+        statements.push(Stmt::Device(DeviceStmt {
+            name: first.to_string(),
+            kind: DeviceKind::B,
+            nodes,
+            model: None,
+            control: None,
+            value: None,
+            params,
+            extras: Vec::new(),
+            poly: None,
+            raw: line.to_string(),
+            line: line_no,
+            ac_mag: None,
+            ac_phase: None,
+        }));
+        return;
+    }
+    // C1 a b C={expr} / Q={expr}, L1 a b L={expr} / FLUX={expr}: 非线性电容/电感,
+    // 表达式与行为源一样引用 V(...) 与 I(...)
+    if is_nonlinear_storage(&kind, &tokens) {
+        let params = split_nonlinear_storage_fields(first, &tokens, line_no, errors);
+        statements.push(Stmt::Device(DeviceStmt {
+            name: first.to_string(),
+            kind,
+            nodes: tokens.iter().take(2).map(|t| t.to_string()).collect(),
+            model: None,
+            control: None,
+            value: None,
+            params,
+            extras: Vec::new(),
+            poly: None,
+            raw: line.to_string(),
+            line: line_no,
+            ac_mag: None,
+            ac_phase: None,
+        }));
+        return;
+    }
+    let (args, mut params) = split_args_params(&tokens);
+    let kind = if matches!(kind, DeviceKind::S) && touchstone_param(&params).is_some() {
+        DeviceKind::SParam
+    } else if matches!(kind, DeviceKind::W) && params.iter().any(|param| param.key.eq_ignore_ascii_case("n")) {
+        DeviceKind::WLine
+    } else {
+        kind
+    };
+    if matches!(kind, DeviceKind::SParam) {
+        for param in &mut params {
+            if param.key.eq_ignore_ascii_case("file") || param.key.eq_ignore_ascii_case("tstonefile") {
+                param.key = "file".to_string();
+                param.value = param.value.trim_matches(|c| c == '"' || c == '\'').to_string();
+            }
+        }
+    }
+    let (nodes, mut model, value, extras, poly) = split_device_fields(&kind, &args);
+    if matches!(kind, DeviceKind::WLine) {
+        // RLGCMODEL= 给出模型名, 作为普通模型引用
+        if let Some(pos) = params.iter().position(|param| param.key.eq_ignore_ascii_case("rlgcmodel")) {
+            model = Some(params.remove(pos).value);
+        }
+    }
     let control = extract_control_name(&kind, &args);
     validate_device_fields(
         first,
@@ -288,6 +795,54 @@ fn parse_statement(
         errors,
     );
 
+    if matches!(kind, DeviceKind::T) {
+        validate_tline_params(first, &params, line_no, errors);
+    }
+    if matches!(kind, DeviceKind::P) {
+        validate_port_params(first, &params, line_no, errors);
+    }
+
+    if matches!(kind, DeviceKind::V | DeviceKind::I) {
+        if let Some(spec) = extract_waveform_args(&extras, "PULSE") {
+            if sim_devices::source::parse_pulse(&spec).is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_ARGUMENTS,
+                    line_no,
+                    format!("{} PULSE requires V1, V2 and non-negative times, found ({})", first, spec),
+                ));
+            }
+            params.push(Param {
+                key: "pulse".to_string(),
+                value: spec,
+            });
+        } else if let Some(spec) = extract_waveform_args(&extras, "PWL") {
+            if sim_devices::source::parse_pwl(&spec).is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_ARGUMENTS,
+                    line_no,
+                    format!("{} PWL requires time / value pairs with non-decreasing times, found ({})", first, spec),
+                ));
+            }
+            params.push(Param {
+                key: "pwl".to_string(),
+                value: spec,
+            });
+        }
+        if let Some(spec) = extract_waveform_args(&extras, "TRNOISE") {
+            if sim_devices::trnoise::parse_trnoise(&spec, 0).is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_ARGUMENTS,
+                    line_no,
+                    format!("{} TRNOISE requires NA and a positive NT, found ({})", first, spec),
+                ));
+            }
+            params.push(Param {
+                key: "trnoise".to_string(),
+                value: spec,
+            });
+        }
+    }
+
     // Extract AC parameters for V/I sources (e.g., "AC 1 0" means ac_mag=1, ac_phase=0)
     let (ac_mag, ac_phase) = extract_ac_params(&kind, &args);
 
@@ -351,6 +906,258 @@ fn split_args_params(tokens: &[&str]) -> (Vec<String>, Vec<Param>) {
     (args, params)
 }
 
+/// 传输线必须给出特性阻抗 Z0, 以及延迟 TD 或频率 F (二者之一)
+fn validate_tline_params(name: &str, params: &[Param], line_no: usize, errors: &mut Vec<Diagnostic>) {
+    let has = |key: &str| params.iter().any(|param| param.key.eq_ignore_ascii_case(key));
+    if !has("z0") {
+        errors.push(Diagnostic::error(
+            codes::MISSING_VALUE,
+            line_no,
+            format!("{} is missing Z0", name),
+        ));
+    }
+    if !has("td") && !has("f") {
+        errors.push(Diagnostic::error(
+            codes::MISSING_VALUE,
+            line_no,
+            format!("{} requires TD or F", name),
+        ));
+    }
+}
+
+/// S 参数块的 Touchstone 文件参数 (`FILE=` 或 HSPICE 的 `TSTONEFILE=`)
+fn touchstone_param(params: &[Param]) -> Option<&Param> {
+    params
+        .iter()
+        .find(|param| param.key.eq_ignore_ascii_case("file") || param.key.eq_ignore_ascii_case("tstonefile"))
+}
+
+/// 端口号必须是正整数, Z0 (缺省 50) 必须为正
+fn validate_port_params(name: &str, params: &[Param], line_no: usize, errors: &mut Vec<Diagnostic>) {
+    let get = |key: &str| params.iter().find(|param| param.key.eq_ignore_ascii_case(key));
+    match get("port") {
+        None => errors.push(Diagnostic::error(
+            codes::MISSING_VALUE,
+            line_no,
+            format!("{} is missing PORT", name),
+        )),
+        Some(param) => {
+            let number = parse_number_with_suffix(&param.value);
+            // 参数引用在展开时才能求值
+            if number.is_some_and(|n| n < 1.0 || n.fract() != 0.0) {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_VALUE,
+                    line_no,
+                    format!("{} has invalid port number {}", name, param.value),
+                ));
+            }
+        }
+    }
+    if let Some(param) = get("z0") {
+        if parse_number_with_suffix(&param.value).is_some_and(|z0| z0 <= 0.0) {
+            errors.push(Diagnostic::error(
+                codes::MISSING_VALUE,
+                line_no,
+                format!("{} has non-positive Z0 {}", name, param.value),
+            ));
+        }
+    }
+}
+
+/// 解析行为源字段: 两个节点, 之后是 `V=expr` 或 `I=expr`。
+/// 表达式可以包含空格, 可以用 `{}` 或引号包裹。
+fn split_behavioral_fields(
+    name: &str,
+    tokens: &[&str],
+    line_no: usize,
+    errors: &mut Vec<Diagnostic>,
+) -> (Vec<String>, Vec<Param>) {
+    let nodes: Vec<String> = tokens.iter().take(2).map(|t| t.to_string()).collect();
+    if nodes.len() != 2 {
+        errors.push(Diagnostic::error(
+            codes::NODE_COUNT,
+            line_no,
+            format!("{} requires 2 nodes, found {}", name, nodes.len()),
+        ));
+        return (nodes, Vec::new());
+    }
+
+    let rest = tokens[2..].join(" ");
+    let Some((key, expr)) = rest.split_once('=') else {
+        errors.push(Diagnostic::error(
+            codes::INVALID_EXPRESSION,
+            line_no,
+            format!("{} is missing a V= or I= expression", name),
+        ));
+        return (nodes, Vec::new());
+    };
+    let key = key.trim().to_ascii_lowercase();
+    if key != "v" && key != "i" {
+        errors.push(Diagnostic::error(
+            codes::INVALID_EXPRESSION,
+            line_no,
+            format!("{} is missing a V= or I= expression", name),
+        ));
+        return (nodes, Vec::new());
+    }
+    let expr = strip_expression_delimiters(expr.trim());
+    if let Err(message) = crate::behavioral::validate_syntax(expr) {
+        errors.push(Diagnostic::error(
+            codes::INVALID_EXPRESSION,
+            line_no,
+            format!("{} has an invalid expression: {}", name, message),
+        ));
+    }
+    let params = vec![Param {
+        key,
+        value: expr.to_string(),
+    }];
+    (nodes, params)
+}
+
+/// 第三个字段 (两个节点之后) 是否为 `VALUE` / `TABLE` 关键字
+fn is_value_or_table(tokens: &[&str]) -> bool {
+    tokens.get(2).is_some_and(|token| {
+        let upper = token.to_ascii_uppercase();
+        upper == "VALUE" || upper.starts_with("VALUE=") || upper.starts_with("TABLE")
+    })
+}
+
+/// 解析 `E1 n+ n- VALUE={expr}` 与 `E1 n+ n- TABLE {expr} = (x1,y1) (x2,y2) ...`。
+/// TABLE 转换为行为表达式 `table(expr, x1, y1, x2, y2, ...)`, 表外取端点值。
+fn split_value_table_fields(
+    name: &str,
+    kind: &DeviceKind,
+    tokens: &[&str],
+    line_no: usize,
+    errors: &mut Vec<Diagnostic>,
+) -> (Vec<String>, Vec<Param>) {
+    let nodes: Vec<String> = tokens.iter().take(2).map(|t| t.to_string()).collect();
+    let rest = tokens[2..].join(" ");
+    let keyword_len = rest
+        .find(|c: char| c.is_whitespace() || c == '=' || c == '{')
+        .unwrap_or(rest.len());
+    let is_table = rest[..keyword_len].eq_ignore_ascii_case("table");
+    let body = rest[keyword_len..].trim();
+
+    let expr = if is_table {
+        let (input, points) = match body.strip_prefix('{').and_then(|b| b.split_once('}')) {
+            Some((input, points)) => (input.trim(), points),
+            None => body.split_once('=').unwrap_or((body, "")),
+        };
+        let points = points.trim_start().trim_start_matches('=');
+        let values: Vec<&str> = points
+            .split(|c: char| c == '(' || c == ')' || c == ',' || c.is_whitespace())
+            .filter(|v| !v.is_empty())
+            .collect();
+        if input.trim().is_empty() || values.len() < 2 || !values.len().is_multiple_of(2) {
+            errors.push(Diagnostic::error(
+                codes::MISSING_ARGUMENTS,
+                line_no,
+                format!("{} TABLE requires an input expression and (x,y) pairs", name),
+            ));
+            return (nodes, Vec::new());
+        }
+        format!("table({}, {})", input.trim(), values.join(", "))
+    } else {
+        strip_expression_delimiters(body.trim_start_matches('=').trim()).to_string()
+    };
+    if let Err(message) = crate::behavioral::validate_syntax(&expr) {
+        errors.push(Diagnostic::error(
+            codes::INVALID_EXPRESSION,
+            line_no,
+            format!("{} has an invalid expression: {}", name, message),
+        ));
+    }
+    let key = if matches!(kind, DeviceKind::E) { "v" } else { "i" };
+    let params = vec![Param {
+        key: key.to_string(),
+        value: expr,
+    }];
+    (nodes, params)
+}
+
+/// 两个节点之后是否为电容的 `C=expr` / `Q=expr` 或电感的 `L=expr` / `FLUX=expr`
+fn is_nonlinear_storage(kind: &DeviceKind, tokens: &[&str]) -> bool {
+    if !matches!(kind, DeviceKind::C | DeviceKind::L) {
+        return false;
+    }
+    let rest = tokens.get(2..).map(|rest| rest.join(" ")).unwrap_or_default();
+    rest.split_once('=')
+        .is_some_and(|(key, _)| is_expression_param(kind, key.trim()))
+}
+
+/// 解析非线性电容/电感字段: 两个节点, 表达式参数 (`C=` `Q=` `L=` `FLUX=`),
+/// 之后可跟 IC= 等参数。含空格的表达式需要用 `{}` 包裹。
+fn split_nonlinear_storage_fields(
+    name: &str,
+    tokens: &[&str],
+    line_no: usize,
+    errors: &mut Vec<Diagnostic>,
+) -> Vec<Param> {
+    if tokens.len() < 3 {
+        errors.push(Diagnostic::error(
+            codes::NODE_COUNT,
+            line_no,
+            format!("{} requires 2 nodes, found {}", name, tokens.len().min(2)),
+        ));
+        return Vec::new();
+    }
+    let rest = tokens[2..].join(" ");
+    let (key, body) = rest.split_once('=').unwrap_or((&rest, ""));
+    let body = body.trim_start();
+    let (expr, tail) = match body.strip_prefix('{') {
+        Some(inner) => {
+            let mut depth = 1;
+            let close = inner.find(|c: char| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            });
+            match close {
+                Some(close) => (&inner[..close], &inner[close + 1..]),
+                None => (inner, ""),
+            }
+        }
+        None => body.split_once(char::is_whitespace).unwrap_or((body, "")),
+    };
+    let expr = strip_expression_delimiters(expr.trim());
+    if let Err(message) = crate::behavioral::validate_syntax(expr) {
+        errors.push(Diagnostic::error(
+            codes::INVALID_EXPRESSION,
+            line_no,
+            format!("{} has an invalid expression: {}", name, message),
+        ));
+    }
+    let mut params = vec![Param {
+        key: key.trim().to_ascii_lowercase(),
+        value: expr.to_string(),
+    }];
+    let tail: Vec<&str> = tail.split_whitespace().collect();
+    let (extras, tail_params) = split_args_params(&tail);
+    if !extras.is_empty() {
+        errors.push(Diagnostic::error(
+            codes::EXTRA_FIELDS,
+            line_no,
+            format!("{} has extra fields {}", name, extras.join(" ")),
+        ));
+    }
+    params.extend(tail_params);
+    params
+}
+
+fn strip_expression_delimiters(expr: &str) -> &str {
+    for (open, close) in [('{', '}'), ('"', '"'), ('\'', '\'')] {
+        if let Some(inner) = expr.strip_prefix(open).and_then(|e| e.strip_suffix(close)) {
+            return inner.trim();
+        }
+    }
+    expr
+}
+
 /// 只在括号深度为 0 时按逗号分割字符串
 fn split_at_top_level_commas(s: &str) -> Vec<&str> {
     let mut result = Vec::new();
@@ -401,7 +1208,19 @@ fn split_device_fields(
                     }
                 } else if matches!(kind, DeviceKind::V | DeviceKind::I) && is_waveform_token(&args[2]) {
                     extras.extend_from_slice(&args[2..]);
+                } else if matches!(kind, DeviceKind::R | DeviceKind::C)
+                    && args.len() == 4
+                    && !args[2].starts_with(|c: char| c.is_ascii_alphabetic())
+                    && args[3].starts_with(|c: char| c.is_ascii_alphabetic())
+                {
+                    // R1 a b <value> <model> / C1 a b <value> <model>
+                    value = Some(args[2].clone());
+                    model = Some(args[3].clone());
                 } else {
+                    // R1 a b rmod L=.. W=..: 取值可能是模型名, 也可能是参数名, 建立电路时再区分
+                    if matches!(kind, DeviceKind::R | DeviceKind::C) && args[2].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                        model = Some(args[2].clone());
+                    }
                     value = Some(args[2].clone());
                     if args.len() > 3 {
                         extras.extend_from_slice(&args[3..]);
@@ -415,8 +1234,11 @@ fn split_device_fields(
             if args.len() >= 3 {
                 nodes.extend_from_slice(&args[0..2]);
                 model = Some(args[2].clone());
-                if args.len() > 3 {
-                    extras.extend_from_slice(&args[3..]);
+                if let Some(area) = args.get(3) {
+                    value = Some(area.clone());
+                }
+                if args.len() > 4 {
+                    extras.extend_from_slice(&args[4..]);
                 }
             } else {
                 nodes.extend_from_slice(args);
@@ -437,26 +1259,28 @@ fn split_device_fields(
                 nodes.extend_from_slice(args);
             }
         }
-        DeviceKind::E | DeviceKind::G => {
-            // 检查是否有 POLY 语法
-            let poly_idx = args.iter().position(|a| is_poly_token(a));
-            if let Some(idx) = poly_idx {
-                // POLY 语法: E1 out 0 POLY(n) ctrl1+ ctrl1- ... coeffs
-                // 或者: E1 out 0 in 0 POLY(n) ctrl2+ ctrl2- ... coeffs
-                // 输出节点总是前 2 个
-                if args.len() >= 2 {
-                    nodes.extend_from_slice(&args[0..2]);
+        DeviceKind::Q => {
+            // 第 5 个字段是数字时为面积因子, 否则为衬底节点之后的模型名
+            let substrate = args.len() >= 5 && parse_number_with_suffix(&args[4]).is_none();
+            let model_idx = if substrate { 4 } else { 3 };
+            if args.len() > model_idx {
+                nodes.extend_from_slice(&args[0..3]);
+                nodes.push(if substrate { args[3].clone() } else { "0".to_string() });
+                model = Some(args[model_idx].clone());
+                if let Some(area) = args.get(model_idx + 1) {
+                    value = Some(area.clone());
                 }
-                // POLY 之前的额外节点是第一组控制节点
-                if idx > 2 {
-                    extras.extend_from_slice(&args[2..idx]);
+                if args.len() > model_idx + 2 {
+                    extras.extend_from_slice(&args[model_idx + 2..]);
                 }
-                // POLY 及之后的内容
-                extras.extend_from_slice(&args[idx..]);
-            } else if args.len() >= 5 {
-                // 普通语法: E1 out 0 in 0 gain
+            } else {
+                nodes.extend_from_slice(args);
+            }
+        }
+        DeviceKind::S => {
+            if args.len() >= 5 {
                 nodes.extend_from_slice(&args[0..4]);
-                value = Some(args[4].clone());
+                model = Some(args[4].clone());
                 if args.len() > 5 {
                     extras.extend_from_slice(&args[5..]);
                 }
@@ -464,15 +1288,117 @@ fn split_device_fields(
                 nodes.extend_from_slice(args);
             }
         }
-        DeviceKind::F | DeviceKind::H => {
-            // 检查是否有 POLY 语法
-            let poly_idx = args.iter().position(|a| is_poly_token(a));
-            if let Some(idx) = poly_idx {
-                // POLY 语法: F1 out 0 POLY(n) V1 V2 ... coeffs
-                if args.len() >= 2 {
-                    nodes.extend_from_slice(&args[0..2]);
-                }
-                // POLY 及之后的内容
+        DeviceKind::W => {
+            // control 由 extract_control_name 提取
+            if args.len() >= 4 {
+                nodes.extend_from_slice(&args[0..2]);
+                model = Some(args[3].clone());
+                if args.len() > 4 {
+                    extras.extend_from_slice(&args[4..]);
+                }
+            } else {
+                nodes.extend_from_slice(args);
+            }
+        }
+        DeviceKind::N => {
+            if args.len() >= 5 {
+                nodes.extend_from_slice(&args[0..4]);
+                value = Some(args[4].clone());
+                if args.len() > 5 {
+                    extras.extend_from_slice(&args[5..]);
+                }
+            } else {
+                nodes.extend_from_slice(args);
+            }
+        }
+        DeviceKind::T => {
+            // Z0 / TD / F / NL 都是 key=value 参数
+            let count = args.len().min(4);
+            nodes.extend_from_slice(&args[..count]);
+            extras.extend_from_slice(&args[count..]);
+        }
+        DeviceKind::SParam => {
+            // 端口数要读取 Touchstone 文件才知道, 展开阶段再核对
+            nodes.extend_from_slice(args);
+        }
+        DeviceKind::WLine => {
+            // 导体数 N 可能是参数引用, 展开阶段再核对 2N+2 个节点
+            nodes.extend_from_slice(args);
+        }
+        DeviceKind::A => {
+            // XSPICE 的向量端口 `[a b]` 按普通节点处理, 最后一个字段是模型
+            let mut fields: Vec<String> = args
+                .iter()
+                .map(|arg| arg.trim_matches(|c| c == '[' || c == ']').to_string())
+                .filter(|arg| !arg.is_empty())
+                .collect();
+            if fields.len() >= 2 {
+                model = fields.pop();
+            }
+            nodes.extend(fields);
+        }
+        DeviceKind::P => {
+            // PORT / Z0 是 key=value 参数; DC 值与 AC 激励写法同电压源
+            let count = args.len().min(2);
+            nodes.extend_from_slice(&args[..count]);
+            let rest = &args[count..];
+            if rest.len() >= 2 && rest[0].eq_ignore_ascii_case("dc") {
+                value = Some(rest[1].clone());
+                extras.extend_from_slice(&rest[2..]);
+            } else {
+                extras.extend_from_slice(rest);
+            }
+        }
+        DeviceKind::J | DeviceKind::Z => {
+            if args.len() >= 4 {
+                nodes.extend_from_slice(&args[0..3]);
+                model = Some(args[3].clone());
+                if let Some(area) = args.get(4) {
+                    value = Some(area.clone());
+                }
+                if args.len() > 5 {
+                    extras.extend_from_slice(&args[5..]);
+                }
+            } else {
+                nodes.extend_from_slice(args);
+            }
+        }
+        DeviceKind::E | DeviceKind::G => {
+            // 检查是否有 POLY 语法
+            let poly_idx = args.iter().position(|a| is_poly_token(a));
+            if let Some(idx) = poly_idx {
+                // POLY 语法: E1 out 0 POLY(n) ctrl1+ ctrl1- ... coeffs
+                // 或者: E1 out 0 in 0 POLY(n) ctrl2+ ctrl2- ... coeffs
+                // 输出节点总是前 2 个
+                if args.len() >= 2 {
+                    nodes.extend_from_slice(&args[0..2]);
+                }
+                // POLY 之前的额外节点是第一组控制节点
+                if idx > 2 {
+                    extras.extend_from_slice(&args[2..idx]);
+                }
+                // POLY 及之后的内容
+                extras.extend_from_slice(&args[idx..]);
+            } else if args.len() >= 5 {
+                // 普通语法: E1 out 0 in 0 gain
+                nodes.extend_from_slice(&args[0..4]);
+                value = Some(args[4].clone());
+                if args.len() > 5 {
+                    extras.extend_from_slice(&args[5..]);
+                }
+            } else {
+                nodes.extend_from_slice(args);
+            }
+        }
+        DeviceKind::F | DeviceKind::H => {
+            // 检查是否有 POLY 语法
+            let poly_idx = args.iter().position(|a| is_poly_token(a));
+            if let Some(idx) = poly_idx {
+                // POLY 语法: F1 out 0 POLY(n) V1 V2 ... coeffs
+                if args.len() >= 2 {
+                    nodes.extend_from_slice(&args[0..2]);
+                }
+                // POLY 及之后的内容
                 extras.extend_from_slice(&args[idx..]);
             } else if args.len() >= 4 {
                 // 普通语法: F1 out 0 Vctrl gain
@@ -494,7 +1420,7 @@ fn split_device_fields(
                 nodes.extend_from_slice(args);
             }
         }
-        DeviceKind::Unknown => {
+        DeviceKind::B | DeviceKind::Unknown => {
             nodes.extend_from_slice(args);
         }
     }
@@ -523,12 +1449,14 @@ fn is_waveform_token(token: &str) -> bool {
         || upper == "EXP"
         || upper == "SFFM"
         || upper == "PWL"
+        || upper == "TRNOISE"
         || upper.starts_with("SIN(")
         || upper.starts_with("PULSE(")
         || upper.starts_with("EXP(")
         || upper.starts_with("SFFM(")
         || upper.starts_with("PWL(")
         || upper.starts_with("AC(")
+        || upper.starts_with("TRNOISE(")
 }
 
 fn map_control_kind(command: &str) -> ControlKind {
@@ -542,6 +1470,24 @@ fn map_control_kind(command: &str) -> ControlKind {
         ".dc" => ControlKind::Dc,
         ".tran" => ControlKind::Tran,
         ".ac" => ControlKind::Ac,
+        ".noise" => ControlKind::Noise,
+        ".tf" => ControlKind::Tf,
+        ".four" | ".fourier" => ControlKind::Four,
+        ".sens" => ControlKind::Sens,
+        ".pz" => ControlKind::Pz,
+        ".pss" => ControlKind::Pss,
+        ".sp" => ControlKind::Sp,
+        ".pac" => ControlKind::Pac,
+        ".pnoise" => ControlKind::Pnoise,
+        ".mc" => ControlKind::Mc,
+        ".corner" => ControlKind::Corner,
+        ".step" => ControlKind::Step,
+        ".ic" => ControlKind::Ic,
+        ".data" => ControlKind::Data,
+        ".options" | ".option" | ".opt" => ControlKind::Options,
+        ".csparam" => ControlKind::Csparam,
+        ".probe" => ControlKind::Probe,
+        ".measure" | ".meas" => ControlKind::Measure,
         ".end" => ControlKind::End,
         _ => ControlKind::Other,
     }
@@ -557,17 +1503,18 @@ fn validate_device_fields(
     extras: &[String],
     poly: &Option<PolySpec>,
     line_no: usize,
-    errors: &mut Vec<ParseError>,
+    errors: &mut Vec<Diagnostic>,
 ) {
     if matches!(kind, DeviceKind::Unknown) {
         return;
     }
 
     if nodes.is_empty() {
-        errors.push(ParseError {
-            line: line_no,
-            message: format!("器件缺少节点定义: {} {}", name, format_fields(nodes, model, control, value, extras, poly)),
-        });
+        errors.push(Diagnostic::error(
+            codes::NODE_COUNT,
+            line_no,
+            format!("{} has no nodes {}", name, format_fields(nodes, model, control, value, extras, poly)),
+        ));
         return;
     }
 
@@ -578,125 +1525,229 @@ fn validate_device_fields(
         | DeviceKind::V
         | DeviceKind::I => {
             if nodes.len() != 2 {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!(
-                        "{} 需要 2 个节点，当前={} {}",
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 2 nodes, found {} {}",
                         name,
                         nodes.len(),
                         format_fields(nodes, model, control, value, extras, poly)
                     ),
-                });
+                ));
             }
             if value.is_none() && !(matches!(kind, DeviceKind::V | DeviceKind::I) && has_waveform(extras)) {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 缺少数值 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::MISSING_VALUE,
+                    line_no,
+                    format!("{} is missing a value {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
             if matches!(kind, DeviceKind::R | DeviceKind::C | DeviceKind::L) && !extras.is_empty() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 存在多余字段 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::EXTRA_FIELDS,
+                    line_no,
+                    format!("{} has extra fields {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
             if matches!(kind, DeviceKind::V | DeviceKind::I)
                 && !extras.is_empty()
                 && !has_waveform(extras)
             {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 存在多余字段 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::EXTRA_FIELDS,
+                    line_no,
+                    format!("{} has extra fields {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
             if matches!(kind, DeviceKind::V | DeviceKind::I) {
                 if let Some(wave) = waveform_keyword(extras) {
                     if extras.len() == 1 {
-                        errors.push(ParseError {
-                            line: line_no,
-                            message: format!("{} 波形 {} 缺少参数 {}", name, wave, format_fields(nodes, model, control, value, extras, poly)),
-                        });
+                        errors.push(Diagnostic::error(
+                            codes::MISSING_ARGUMENTS,
+                            line_no,
+                            format!("{} waveform {} is missing arguments {}", name, wave, format_fields(nodes, model, control, value, extras, poly)),
+                        ));
                     }
                 }
             }
         }
         DeviceKind::D => {
             if nodes.len() != 2 {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!(
-                        "{} 需要 2 个节点，当前={} {}",
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 2 nodes, found {} {}",
                         name,
                         nodes.len(),
                         format_fields(nodes, model, control, value, extras, poly)
                     ),
-                });
+                ));
             }
             if model.is_none() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 缺少模型名 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a model name {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
         }
         DeviceKind::M => {
             if nodes.len() < 4 {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!(
-                        "{} 需要至少 4 个节点，当前={} {}",
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires at least 4 nodes, found {} {}",
                         name,
                         nodes.len(),
                         format_fields(nodes, model, control, value, extras, poly)
                     ),
-                });
+                ));
             }
             if model.is_none() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 缺少模型名 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a model name {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
+        DeviceKind::Q => {
+            if nodes.len() != 4 {
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 3 or 4 nodes, found {} {}",
+                        name,
+                        nodes.len(),
+                        format_fields(nodes, model, control, value, extras, poly)
+                    ),
+                ));
+            }
+            if model.is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a model name {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
+        DeviceKind::S => {
+            if nodes.len() != 4 {
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 4 nodes, found {} {}",
+                        name,
+                        nodes.len(),
+                        format_fields(nodes, model, control, value, extras, poly)
+                    ),
+                ));
+            }
+            if model.is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a model name {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
+        DeviceKind::W => {
+            if nodes.len() != 2 {
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 2 nodes, found {} {}",
+                        name,
+                        nodes.len(),
+                        format_fields(nodes, model, control, value, extras, poly)
+                    ),
+                ));
+            }
+            if control.is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a controlling source {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+            if model.is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a model name {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
+        DeviceKind::J | DeviceKind::Z => {
+            if nodes.len() != 3 {
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 3 nodes, found {} {}",
+                        name,
+                        nodes.len(),
+                        format_fields(nodes, model, control, value, extras, poly)
+                    ),
+                ));
+            }
+            if model.is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a model name {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
         }
         DeviceKind::E | DeviceKind::G => {
             if poly.is_some() {
                 // POLY 语法: 输出节点 2 个
                 if nodes.len() != 2 {
-                    errors.push(ParseError {
-                        line: line_no,
-                        message: format!(
-                            "{} POLY 需要 2 个输出节点，当前={} {}",
+                    errors.push(Diagnostic::error(
+                        codes::NODE_COUNT,
+                        line_no,
+                        format!(
+                            "{} POLY requires 2 output nodes, found {} {}",
                             name,
                             nodes.len(),
                             format_fields(nodes, model, control, value, extras, poly)
                         ),
-                    });
+                    ));
                 }
             } else {
                 // 普通语法: 4 个节点 (out+ out- in+ in-)
                 if nodes.len() != 4 {
-                    errors.push(ParseError {
-                        line: line_no,
-                        message: format!(
-                            "{} 需要 4 个节点，当前={} {}",
+                    errors.push(Diagnostic::error(
+                        codes::NODE_COUNT,
+                        line_no,
+                        format!(
+                            "{} requires 4 nodes, found {} {}",
                             name,
                             nodes.len(),
                             format_fields(nodes, model, control, value, extras, poly)
                         ),
-                    });
+                    ));
                 }
             }
             if value.is_none() && poly.is_none() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 缺少增益值 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::MISSING_VALUE,
+                    line_no,
+                    format!("{} is missing a gain {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
             if poly.is_none() && !extras.is_empty() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 存在多余字段 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::EXTRA_FIELDS,
+                    line_no,
+                    format!("{} has extra fields {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
             if let Some(spec) = poly {
                 // POLY 后面的内容 (coeffs): 控制节点 + 系数
@@ -704,124 +1755,245 @@ fn validate_device_fields(
                 // POLY 之前的内容（如 in 0）是可选的兼容语法，不计入控制节点
                 let expected_controls = spec.degree * 2;
                 if spec.coeffs.len() < expected_controls {
-                    errors.push(ParseError {
-                        line: line_no,
-                        message: format!(
-                            "{} POLY 控制节点数量不足，期望={} 实际={} {}",
+                    errors.push(Diagnostic::error(
+                        codes::MISSING_ARGUMENTS,
+                        line_no,
+                        format!(
+                            "{} POLY has too few control nodes: expected {}, found {} {}",
                             name,
                             expected_controls,
                             spec.coeffs.len(),
                             format_fields(nodes, model, control, value, extras, poly)
                         ),
-                    });
+                    ));
                 } else {
                     // 系数是控制节点之后的部分
                     let actual_coeffs = spec.coeffs.len() - expected_controls;
                     if actual_coeffs < spec.degree + 1 {
-                        errors.push(ParseError {
-                            line: line_no,
-                            message: format!(
-                                "{} POLY 系数数量不足，期望>={} 实际={} {}",
+                        errors.push(Diagnostic::error(
+                            codes::MISSING_ARGUMENTS,
+                            line_no,
+                            format!(
+                                "{} POLY has too few coefficients: expected at least {}, found {} {}",
                                 name,
                                 spec.degree + 1,
                                 actual_coeffs,
                                 format_fields(nodes, model, control, value, extras, poly)
                             ),
-                        });
+                        ));
                     }
                 }
             }
         }
         DeviceKind::F | DeviceKind::H => {
             if nodes.len() != 2 {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!(
-                        "{} 需要 2 个节点，当前={} {}",
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 2 nodes, found {} {}",
                         name,
                         nodes.len(),
                         format_fields(nodes, model, control, value, extras, poly)
                     ),
-                });
+                ));
             }
             if control.is_none() && poly.is_none() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 缺少控制源 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a controlling source {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
             if value.is_none() && poly.is_none() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 缺少增益值 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::MISSING_VALUE,
+                    line_no,
+                    format!("{} is missing a gain {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
             if poly.is_none() && !extras.is_empty() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 存在多余字段 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::EXTRA_FIELDS,
+                    line_no,
+                    format!("{} has extra fields {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
             if let Some(spec) = poly {
                 // POLY 后面的内容: 控制源名称 + 系数
                 // 控制源数量 = degree
                 let expected_controls = spec.degree;
                 if spec.coeffs.len() < expected_controls {
-                    errors.push(ParseError {
-                        line: line_no,
-                        message: format!(
-                            "{} POLY 控制源数量不足，期望={} 实际={} {}",
+                    errors.push(Diagnostic::error(
+                        codes::MISSING_ARGUMENTS,
+                        line_no,
+                        format!(
+                            "{} POLY has too few controlling sources: expected {}, found {} {}",
                             name,
                             expected_controls,
                             spec.coeffs.len(),
                             format_fields(nodes, model, control, value, extras, poly)
                         ),
-                    });
+                    ));
                 } else {
                     // 系数是控制源之后的部分
                     let actual_coeffs = spec.coeffs.len() - expected_controls;
                     if actual_coeffs < spec.degree + 1 {
-                        errors.push(ParseError {
-                            line: line_no,
-                            message: format!(
-                                "{} POLY 系数数量不足，期望>={} 实际={} {}",
+                        errors.push(Diagnostic::error(
+                            codes::MISSING_ARGUMENTS,
+                            line_no,
+                            format!(
+                                "{} POLY has too few coefficients: expected at least {}, found {} {}",
                                 name,
                                 spec.degree + 1,
                                 actual_coeffs,
                                 format_fields(nodes, model, control, value, extras, poly)
                             ),
-                        });
+                        ));
                     }
                 }
             }
         }
+        DeviceKind::N => {
+            if nodes.len() != 4 {
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 4 nodes, found {} {}",
+                        name,
+                        nodes.len(),
+                        format_fields(nodes, model, control, value, extras, poly)
+                    ),
+                ));
+            }
+            if value.is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_VALUE,
+                    line_no,
+                    format!("{} is missing a turns ratio {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+            if !extras.is_empty() {
+                errors.push(Diagnostic::error(
+                    codes::EXTRA_FIELDS,
+                    line_no,
+                    format!("{} has extra fields {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
+        DeviceKind::A => {
+            if nodes.len() < 2 {
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires at least 2 nodes, found {} {}",
+                        name,
+                        nodes.len(),
+                        format_fields(nodes, model, control, value, extras, poly)
+                    ),
+                ));
+            }
+            if model.is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a model name {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
+        DeviceKind::SParam => {
+            if nodes.is_empty() {
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!("{} is missing nodes {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
+        DeviceKind::WLine => {
+            if model.is_none() {
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing RLGCMODEL {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
+        DeviceKind::P => {
+            if nodes.len() != 2 {
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 2 nodes, found {} {}",
+                        name,
+                        nodes.len(),
+                        format_fields(nodes, model, control, value, extras, poly)
+                    ),
+                ));
+            }
+            let ac_spec = extras.first().is_some_and(|token| token.eq_ignore_ascii_case("ac")) && (2..=3).contains(&extras.len());
+            if !extras.is_empty() && !ac_spec {
+                errors.push(Diagnostic::error(
+                    codes::EXTRA_FIELDS,
+                    line_no,
+                    format!("{} has extra fields {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
+        DeviceKind::T => {
+            if nodes.len() != 4 {
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!(
+                        "{} requires 4 nodes, found {} {}",
+                        name,
+                        nodes.len(),
+                        format_fields(nodes, model, control, value, extras, poly)
+                    ),
+                ));
+            }
+            if !extras.is_empty() {
+                errors.push(Diagnostic::error(
+                    codes::EXTRA_FIELDS,
+                    line_no,
+                    format!("{} has extra fields {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
+            }
+        }
         DeviceKind::X => {
             if nodes.is_empty() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 缺少节点 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::NODE_COUNT,
+                    line_no,
+                    format!("{} is missing nodes {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
             if model.is_none() {
-                errors.push(ParseError {
-                    line: line_no,
-                    message: format!("{} 缺少子电路名 {}", name, format_fields(nodes, model, control, value, extras, poly)),
-                });
+                errors.push(Diagnostic::error(
+                    codes::MISSING_REFERENCE,
+                    line_no,
+                    format!("{} is missing a subcircuit name {}", name, format_fields(nodes, model, control, value, extras, poly)),
+                ));
             }
         }
-        DeviceKind::Unknown => {}
+        DeviceKind::B | DeviceKind::Unknown => {}
     }
 }
 
 pub fn elaborate_netlist(ast: &NetlistAst) -> ElaboratedNetlist {
     let mut errors = ast.errors.clone();
-    let (top_level, subckts, subckt_errors) = extract_subckts(&ast.statements);
+    let statements = expand_bus_statements(&ast.statements, &mut errors);
+    let (top_level, subckts, subckt_errors) = extract_subckts(&statements);
     errors.extend(subckt_errors);
 
-    let param_table = build_param_table(&top_level);
+    let param_table = build_param_table(&top_level, &mut errors);
     let subckt_map = build_subckt_map(&subckts);
     let mut instances = Vec::new();
-    let mut subckt_models = Vec::new();
+    let mut outputs = SubcktOutputs::default();
     let mut control_count = 0;
 
     for stmt in top_level {
@@ -838,23 +2010,32 @@ pub fn elaborate_netlist(ast: &NetlistAst) -> ElaboratedNetlist {
                                 &std::collections::HashMap::new(),
                                 &param_table,
                             );
+                            let scope = SubcktScope {
+                                params: local_params,
+                                models: std::collections::HashMap::new(),
+                                multiplier: 1.0,
+                            };
                             let expanded = expand_subckt_instance_recursive(
                                 &device,
                                 def,
                                 &subckt_map,
-                                &local_params,
+                                &scope,
                                 &param_table,
                                 &mut errors,
-                                &mut subckt_models,
+                                &mut outputs,
                             );
                             instances.extend(expanded);
                             continue;
                         }
                     }
-                    errors.push(ParseError {
-                        line: device.line,
-                        message: format!("子电路未定义: {:?}", device.model),
-                    });
+                    errors.push(
+                        Diagnostic::error(
+                            codes::UNDEFINED_SUBCKT,
+                            device.line,
+                            format!("undefined subcircuit '{}'", device.model.as_deref().unwrap_or_default()),
+                        )
+                        .with_source_line(device.raw.clone()),
+                    );
                     let mut fallback = device.clone();
                     apply_params_to_device_scoped(&param_table, &std::collections::HashMap::new(), &mut fallback);
                     instances.push(fallback);
@@ -871,29 +2052,198 @@ pub fn elaborate_netlist(ast: &NetlistAst) -> ElaboratedNetlist {
         }
     }
 
+    check_touchstone_blocks(&instances, &mut errors);
+    check_wline_nodes(&instances, &mut errors);
+    resolve_branch_references(&mut instances, &mut errors);
+
+    // 已带文件名的诊断 (解析阶段) 不会被重复换算
+    let errors: Vec<Diagnostic> = errors.into_iter().map(|diag| ast.locate(diag)).collect();
     ElaboratedNetlist {
         instances,
-        subckt_models,
+        subckt_models: outputs.models,
+        node_aliases: outputs.node_aliases,
         control_count,
         error_count: errors.len(),
+        errors,
+    }
+}
+
+/// 读取 S 参数块的 Touchstone 文件, 核对节点数为 N 或 N+1 (带参考节点)
+fn check_touchstone_blocks(instances: &[DeviceStmt], errors: &mut Vec<Diagnostic>) {
+    for device in instances.iter().filter(|device| matches!(device.kind, DeviceKind::SParam)) {
+        let Some(file) = touchstone_param(&device.params) else {
+            continue;
+        };
+        match crate::touchstone::read_touchstone(std::path::Path::new(&file.value)) {
+            Ok(data) if device.nodes.len() == data.ports || device.nodes.len() == data.ports + 1 => {}
+            Ok(data) => errors.push(
+                Diagnostic::error(
+                    codes::NODE_COUNT,
+                    device.line,
+                    format!(
+                        "{} requires {} or {} nodes for a {}-port, found {}",
+                        device.name,
+                        data.ports,
+                        data.ports + 1,
+                        data.ports,
+                        device.nodes.len()
+                    ),
+                )
+                .with_source_line(device.raw.clone()),
+            ),
+            Err(message) => errors.push(
+                Diagnostic::error(codes::FILE_UNREADABLE, device.line, message).with_source_line(device.raw.clone()),
+            ),
+        }
+    }
+}
+
+/// 多导体传输线的导体数 N 必须是正整数, 节点为 N 个输入、输入参考、N 个输出、输出参考
+fn check_wline_nodes(instances: &[DeviceStmt], errors: &mut Vec<Diagnostic>) {
+    for device in instances.iter().filter(|device| matches!(device.kind, DeviceKind::WLine)) {
+        let conductors = device
+            .params
+            .iter()
+            .find(|param| param.key.eq_ignore_ascii_case("n"))
+            .and_then(|param| parse_number_with_suffix(&param.value))
+            .filter(|n| *n >= 1.0 && n.fract() == 0.0);
+        let message = match conductors {
+            None => format!("{} requires a positive integer N", device.name),
+            Some(n) if device.nodes.len() != 2 * n as usize + 2 => format!(
+                "{} requires {} nodes for N={}, found {}",
+                device.name,
+                2 * n as usize + 2,
+                n,
+                device.nodes.len()
+            ),
+            Some(_) => continue,
+        };
+        errors.push(Diagnostic::error(codes::NODE_COUNT, device.line, message).with_source_line(device.raw.clone()));
+    }
+}
+
+/// 带有支路电流 (盖章时以实例名分配辅助变量) 的元件: 其电流可在表达式中以 `I(名字)` 引用
+fn owns_branch_current(device: &DeviceStmt) -> bool {
+    match device.kind {
+        DeviceKind::V | DeviceKind::L | DeviceKind::E | DeviceKind::H | DeviceKind::N | DeviceKind::T | DeviceKind::A => {
+            true
+        }
+        DeviceKind::B => device.params.iter().any(|param| param.key.eq_ignore_ascii_case("v")),
+        _ => false,
+    }
+}
+
+/// 表达式中的 `I(名字)` 改写为实例的实际名字 (不区分大小写, 被引用的元件可以写在后面),
+/// 盖章时按名字找到其支路电流; 名字不存在或该元件没有支路电流时报错
+fn resolve_branch_references(instances: &mut [DeviceStmt], errors: &mut Vec<Diagnostic>) {
+    let names: std::collections::HashMap<String, (String, bool)> = instances
+        .iter()
+        .map(|device| (device.name.to_ascii_lowercase(), (device.name.clone(), owns_branch_current(device))))
+        .collect();
+    for device in instances.iter_mut() {
+        let unresolved = std::cell::RefCell::new(Vec::new());
+        for param in device.params.iter_mut().filter(|param| is_expression_param(&device.kind, &param.key)) {
+            param.value = crate::behavioral::rewrite_references(
+                &param.value,
+                |_| None,
+                |node| node.to_string(),
+                |branch| match names.get(&branch.to_ascii_lowercase()) {
+                    Some((name, true)) => name.clone(),
+                    Some((name, false)) => {
+                        unresolved.borrow_mut().push((branch.to_string(), format!("{} has no branch current", name)));
+                        branch.to_string()
+                    }
+                    None => {
+                        unresolved.borrow_mut().push((branch.to_string(), format!("no element named '{}'", branch)));
+                        branch.to_string()
+                    }
+                },
+            );
+        }
+        for (branch, reason) in unresolved.into_inner() {
+            let message = format!("{} references I({}): {}", device.name, branch, reason);
+            let mut diag = Diagnostic::error(codes::UNKNOWN_BRANCH, device.line, message);
+            // 子电路展开后的名字带实例前缀, 源行中是原来的名字
+            let local = branch.rsplit('.').next().unwrap_or(&branch).to_ascii_lowercase();
+            let raw = device.raw.to_ascii_lowercase();
+            let start = raw
+                .match_indices(&local)
+                .map(|(start, _)| start)
+                .find(|start| raw[..*start].trim_end().ends_with('('));
+            if let Some(start) = start {
+                let column = device.raw[..start].chars().count() + 1;
+                diag = diag.with_columns(column..column + local.chars().count());
+            }
+            errors.push(diag.with_source_line(device.raw.clone()));
+        }
+    }
+}
+
+/// Touchstone 文件的相对路径相对于引用它的网表文件所在目录
+fn resolve_touchstone_paths(ast: &mut NetlistAst) {
+    for stmt in &mut ast.statements {
+        let Stmt::Device(device) = stmt else { continue };
+        if !matches!(device.kind, DeviceKind::SParam) {
+            continue;
+        }
+        let Some((source, _)) = ast.line_origins.get(device.line.wrapping_sub(1)) else {
+            continue;
+        };
+        let base_dir = source.parent().unwrap_or_else(|| std::path::Path::new("."));
+        for param in device.params.iter_mut().filter(|param| param.key == "file") {
+            if std::path::Path::new(&param.value).is_relative() {
+                param.value = base_dir.join(&param.value).to_string_lossy().into_owned();
+            }
+        }
     }
 }
 
 pub fn build_circuit(ast: &NetlistAst, elab: &ElaboratedNetlist) -> crate::circuit::Circuit {
+    build_circuit_with_nodes(ast, elab, crate::circuit::NodeNormalization::default())
+}
+
+/// 按给定的节点规范化规则 (大小写、地节点别名) 构建电路
+pub fn build_circuit_with_nodes(
+    ast: &NetlistAst,
+    elab: &ElaboratedNetlist,
+    normalization: crate::circuit::NodeNormalization,
+) -> crate::circuit::Circuit {
     use crate::circuit::{AnalysisCmd, Circuit, DeviceKind as CircuitDeviceKind, Instance, Model};
     use std::collections::HashMap;
 
-    let mut circuit = Circuit::new();
+    let mut circuit = Circuit::with_node_normalization(normalization);
+    circuit.netlist = Some(ast.clone());
+    // .ic 节点在器件之后登记, 以保持节点编号按器件出现顺序
+    let mut initial_conditions = Vec::new();
+
+    // .data 表可以出现在引用它的 .dc 之后
+    let data_tables: HashMap<String, crate::circuit::DataTable> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Control(ctrl) if matches!(ctrl.kind, ControlKind::Data) => {
+                parse_data_table(&ctrl.args).ok()
+            }
+            _ => None,
+        })
+        .map(|table| (table.name.clone(), table))
+        .collect();
 
-    // Process top-level statements from AST
-    for stmt in &ast.statements {
+    // 子电路内的语句 (包括局部 .model) 由展开阶段处理, 这里只看顶层
+    let (top_level, _, _) = extract_subckts(&ast.statements);
+    let global_params = build_param_table(&top_level, &mut Vec::new());
+    let no_locals = HashMap::new();
+    for stmt in &top_level {
         if let Stmt::Control(ctrl) = stmt {
             match ctrl.kind {
                 ControlKind::Model => {
                     if let (Some(name), Some(model_type)) = (&ctrl.model_name, &ctrl.model_type) {
                         let mut params = HashMap::new();
                         for param in &ctrl.params {
-                            params.insert(param.key.to_ascii_lowercase(), param.value.clone());
+                            params.insert(
+                                param.key.to_ascii_lowercase(),
+                                resolve_model_param(&no_locals, &global_params, &param.value),
+                            );
                         }
                         let name_norm = name.to_ascii_lowercase();
                         let model_type_norm = model_type.to_ascii_lowercase();
@@ -908,67 +2258,98 @@ pub fn build_circuit(ast: &NetlistAst, elab: &ElaboratedNetlist) -> crate::circu
                     circuit.analysis.push(AnalysisCmd::Op);
                 }
                 ControlKind::Dc => {
-                    if ctrl.args.len() >= 4 {
-                        let source = ctrl.args[0].clone();
-                        let start = parse_number_with_suffix(&ctrl.args[1])
-                            .or_else(|| ctrl.args[1].parse().ok())
-                            .unwrap_or(0.0);
-                        let stop = parse_number_with_suffix(&ctrl.args[2])
-                            .or_else(|| ctrl.args[2].parse().ok())
-                            .unwrap_or(0.0);
-                        let step = parse_number_with_suffix(&ctrl.args[3])
-                            .or_else(|| ctrl.args[3].parse().ok())
-                            .unwrap_or(0.0);
-                        circuit.analysis.push(AnalysisCmd::Dc {
-                            source,
-                            start,
-                            stop,
-                            step,
+                    // .dc data=<name>: 按 .data 表逐行扫描
+                    let data = ctrl
+                        .params
+                        .iter()
+                        .find(|param| param.key.eq_ignore_ascii_case("data"))
+                        .and_then(|param| data_tables.get(&param.value.to_ascii_lowercase()));
+                    if let Some(table) = data {
+                        circuit.analysis.push(AnalysisCmd::DcData {
+                            table: table.clone(),
                         });
+                    } else if let Some((inner, rest)) = parse_dc_sweep_spec(&ctrl.args) {
+                        // 第一组为内层扫描, 可选的第二组为外层扫描
+                        match parse_dc_sweep_spec(rest) {
+                            Some((outer, _)) => {
+                                circuit.analysis.push(AnalysisCmd::DcNested { inner, outer });
+                            }
+                            None if inner.kind == crate::circuit::DcSweepKind::Param => {
+                                circuit.analysis.push(AnalysisCmd::DcParam {
+                                    param: inner.source,
+                                    start: inner.start,
+                                    stop: inner.stop,
+                                    step: inner.step,
+                                });
+                            }
+                            None => {
+                                circuit.analysis.push(AnalysisCmd::Dc {
+                                    source: inner.source,
+                                    start: inner.start,
+                                    stop: inner.stop,
+                                    step: inner.step,
+                                });
+                            }
+                        }
                     }
                 }
                 ControlKind::Tran => {
-                    if ctrl.args.len() >= 2 {
-                        let tstep = parse_number_with_suffix(&ctrl.args[0])
-                            .or_else(|| ctrl.args[0].parse().ok())
-                            .unwrap_or(0.0);
-                        let tstop = parse_number_with_suffix(&ctrl.args[1])
-                            .or_else(|| ctrl.args[1].parse().ok())
-                            .unwrap_or(0.0);
+                    // UIC 关键字可出现在数值参数之后
+                    let uic = ctrl.args.iter().any(|arg| arg.eq_ignore_ascii_case("uic"));
+                    let args: Vec<String> = ctrl
+                        .args
+                        .iter()
+                        .filter(|arg| !arg.eq_ignore_ascii_case("uic"))
+                        .cloned()
+                        .collect();
+                    let ctrl = ControlStmt {
+                        args,
+                        ..ctrl.clone()
+                    };
+                    let numbers: Vec<f64> = ctrl
+                        .args
+                        .iter()
+                        .map(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()).unwrap_or(0.0))
+                        .collect();
+                    if let Some(windows) = parse_tran_windows(&numbers) {
+                        // .tran tstep1 tstop1 tstep2 tstop2 ... [start=<t>]
                         let tstart = ctrl
-                            .args
-                            .get(2)
-                            .and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()))
+                            .params
+                            .iter()
+                            .find(|param| param.key.eq_ignore_ascii_case("start"))
+                            .and_then(|param| {
+                                parse_number_with_suffix(&param.value).or_else(|| param.value.parse().ok())
+                            })
                             .unwrap_or(0.0);
-                        let tmax = ctrl
-                            .args
+                        circuit.analysis.push(AnalysisCmd::Tran {
+                            tstep: windows[0].tstep,
+                            tstop: windows[windows.len() - 1].tstop,
+                            tstart,
+                            tmax: windows.iter().map(|window| window.tstep).fold(0.0, f64::max),
+                            uic,
+                            windows,
+                        });
+                    } else if numbers.len() >= 2 {
+                        let (tstep, tstop) = (numbers[0], numbers[1]);
+                        let tstart = numbers.get(2).copied().unwrap_or(0.0);
+                        // 未给 TMAX 时同 SPICE: min(TSTEP, (TSTOP - TSTART) / 50)
+                        let tmax = numbers
                             .get(3)
-                            .and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()))
-                            .unwrap_or(tstop);
+                            .copied()
+                            .unwrap_or_else(|| tstep.min((tstop - tstart) / 50.0));
                         circuit.analysis.push(AnalysisCmd::Tran {
                             tstep,
                             tstop,
                             tstart,
                             tmax,
+                            uic,
+                            windows: Vec::new(),
                         });
                     }
                 }
                 ControlKind::Ac => {
                     // .ac dec|oct|lin <points> <fstart> <fstop>
-                    if ctrl.args.len() >= 4 {
-                        let sweep_type = match ctrl.args[0].to_ascii_lowercase().as_str() {
-                            "dec" => crate::circuit::AcSweepType::Dec,
-                            "oct" => crate::circuit::AcSweepType::Oct,
-                            "lin" => crate::circuit::AcSweepType::Lin,
-                            _ => crate::circuit::AcSweepType::Dec, // default to decade
-                        };
-                        let points = ctrl.args[1].parse().unwrap_or(10);
-                        let fstart = parse_number_with_suffix(&ctrl.args[2])
-                            .or_else(|| ctrl.args[2].parse().ok())
-                            .unwrap_or(1.0);
-                        let fstop = parse_number_with_suffix(&ctrl.args[3])
-                            .or_else(|| ctrl.args[3].parse().ok())
-                            .unwrap_or(1e6);
+                    if let Some((sweep_type, points, fstart, fstop)) = parse_ac_sweep(&ctrl.args) {
                         circuit.analysis.push(AnalysisCmd::Ac {
                             sweep_type,
                             points,
@@ -977,21 +2358,222 @@ pub fn build_circuit(ast: &NetlistAst, elab: &ElaboratedNetlist) -> crate::circu
                         });
                     }
                 }
-                _ => {}
-            }
-        }
-    }
-
-    // Process .model statements extracted from subcircuits during elaboration
-    for ctrl in &elab.subckt_models {
-        if let (Some(name), Some(model_type)) = (&ctrl.model_name, &ctrl.model_type) {
-            let mut params = HashMap::new();
-            for param in &ctrl.params {
-                params.insert(param.key.to_ascii_lowercase(), param.value.clone());
-            }
-            let name_norm = name.to_ascii_lowercase();
-            let model_type_norm = model_type.to_ascii_lowercase();
-            circuit.models.insert(Model {
+                ControlKind::Noise if ctrl.args.len() >= 6 => {
+                    // .noise V(out[,ref]) <src> dec|oct|lin <points> <fstart> <fstop>
+                    let probe = parse_output_probe(&ctrl.args[0]);
+                    if let (Some((_, output, reference)), Some((sweep_type, points, fstart, fstop))) =
+                        (probe, parse_ac_sweep(&ctrl.args[2..]))
+                    {
+                        circuit.analysis.push(AnalysisCmd::Noise {
+                            output,
+                            reference,
+                            source: ctrl.args[1].clone(),
+                            sweep_type,
+                            points,
+                            fstart,
+                            fstop,
+                        });
+                    }
+                }
+                ControlKind::Tf if ctrl.args.len() >= 2 => {
+                    // .tf V(out)|I(Vx) <src>
+                    circuit.analysis.push(AnalysisCmd::Tf {
+                        output: ctrl.args[0].to_ascii_lowercase(),
+                        source: ctrl.args[1].clone(),
+                    });
+                }
+                ControlKind::Four if ctrl.args.len() >= 2 => {
+                    // .four <freq> V(n1) V(n2) ...
+                    if let Some(freq) = parse_number_with_suffix(&ctrl.args[0])
+                        .or_else(|| ctrl.args[0].parse().ok())
+                    {
+                        circuit.analysis.push(AnalysisCmd::Four {
+                            freq,
+                            outputs: ctrl.args[1..]
+                                .iter()
+                                .map(|v| v.to_ascii_lowercase())
+                                .collect(),
+                        });
+                    }
+                }
+                ControlKind::Sens if !ctrl.args.is_empty() => {
+                    // .sens V(out) I(Vx) ...
+                    circuit.analysis.push(AnalysisCmd::Sens {
+                        outputs: ctrl.args.iter().map(|v| v.to_ascii_lowercase()).collect(),
+                    });
+                }
+                ControlKind::Pss if ctrl.args.len() >= 2 => {
+                    // .pss <gfreq> <tstab> [oscnob] [psspoints] [harms] [sciter] [steady_coeff];
+                    // oscnob 与 harms 只对自激振荡器有意义, 受驱电路不使用
+                    let number = |idx: usize| {
+                        ctrl.args
+                            .get(idx)
+                            .and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()))
+                    };
+                    if let (Some(freq), Some(tstab)) = (number(0), number(1)) {
+                        circuit.analysis.push(AnalysisCmd::Pss {
+                            freq,
+                            tstab,
+                            points: number(3).map(|v| v as usize).unwrap_or(128),
+                            max_iters: number(5).map(|v| v as usize).unwrap_or(20),
+                            tol: number(6).unwrap_or(1e-3),
+                        });
+                    }
+                }
+                ControlKind::Sp => {
+                    // .sp dec|oct|lin <points> <fstart> <fstop>
+                    if let Some((sweep_type, points, fstart, fstop)) = parse_ac_sweep(&ctrl.args) {
+                        circuit.analysis.push(AnalysisCmd::Sp {
+                            sweep_type,
+                            points,
+                            fstart,
+                            fstop,
+                        });
+                    }
+                }
+                ControlKind::Pac => {
+                    // .pac dec|oct|lin <points> <fstart> <fstop> [sidebands=K]
+                    if let Some((sweep_type, points, fstart, fstop)) = parse_ac_sweep(&ctrl.args) {
+                        circuit.analysis.push(AnalysisCmd::Pac {
+                            sweep_type,
+                            points,
+                            fstart,
+                            fstop,
+                            sidebands: sideband_count(&ctrl.params),
+                        });
+                    }
+                }
+                ControlKind::Pnoise if ctrl.args.len() >= 6 => {
+                    // .pnoise V(out[,ref]) <src> dec|oct|lin <points> <fstart> <fstop> [sidebands=K]
+                    let probe = parse_output_probe(&ctrl.args[0]);
+                    if let (Some((_, output, reference)), Some((sweep_type, points, fstart, fstop))) =
+                        (probe, parse_ac_sweep(&ctrl.args[2..]))
+                    {
+                        circuit.analysis.push(AnalysisCmd::Pnoise {
+                            output,
+                            reference,
+                            source: ctrl.args[1].clone(),
+                            sweep_type,
+                            points,
+                            fstart,
+                            fstop,
+                            sidebands: sideband_count(&ctrl.params),
+                        });
+                    }
+                }
+                ControlKind::Mc => {
+                    let runs = ctrl.args.first().and_then(|v| parse_number_with_suffix(v));
+                    // 未给种子时固定为 1, 结果可复现
+                    let seed = ctrl
+                        .params
+                        .iter()
+                        .find(|param| param.key.eq_ignore_ascii_case("seed"))
+                        .and_then(|param| param.value.parse::<u64>().ok())
+                        .unwrap_or(1);
+                    if let Some(runs) = runs.filter(|runs| *runs >= 1.0) {
+                        circuit.monte_carlo = Some(crate::circuit::MonteCarloConfig {
+                            runs: runs as usize,
+                            seed,
+                        });
+                    }
+                }
+                ControlKind::Step => {
+                    if let Some(step) = parse_step_spec(&ctrl.args) {
+                        circuit.steps.push(step);
+                    }
+                }
+                ControlKind::Corner => {
+                    let Some(name) = ctrl.args.first() else { continue };
+                    let mut corner = crate::circuit::Corner {
+                        name: name.clone(),
+                        lib: None,
+                        temp: None,
+                        params: Vec::new(),
+                    };
+                    for param in &ctrl.params {
+                        let key = param.key.to_ascii_lowercase();
+                        if key == "lib" {
+                            corner.lib = Some(param.value.trim_matches(['\'', '"']).to_string());
+                            continue;
+                        }
+                        let value = resolve_model_param(&no_locals, &global_params, &param.value);
+                        let Some(value) = parse_number_with_suffix(&value).or_else(|| value.parse().ok()) else {
+                            continue;
+                        };
+                        if key == "temp" {
+                            corner.temp = Some(value);
+                        } else {
+                            corner.params.push((key, value));
+                        }
+                    }
+                    circuit.corners.push(corner);
+                }
+                ControlKind::Measure => {
+                    if let Ok(measure) = parse_measure(&ctrl.raw) {
+                        circuit.measures.push(measure);
+                    }
+                }
+                ControlKind::Options => {
+                    for arg in &ctrl.args {
+                        circuit.options.insert(arg.to_ascii_lowercase(), "1".to_string());
+                    }
+                    for param in &ctrl.params {
+                        circuit.options.insert(
+                            param.key.to_ascii_lowercase(),
+                            resolve_model_param(&no_locals, &global_params, &param.value),
+                        );
+                    }
+                }
+                ControlKind::Ic => {
+                    // .ic V(node)=value ...
+                    for param in &ctrl.params {
+                        let probe = parse_output_probe(&param.key);
+                        let value = parse_number_with_suffix(&param.value)
+                            .or_else(|| param.value.parse().ok());
+                        if let (Some(('v', node, None)), Some(value)) = (probe, value) {
+                            initial_conditions.push((node, value));
+                        }
+                    }
+                }
+                ControlKind::Pz if ctrl.args.len() >= 6 => {
+                    // .pz <in+> <in-> <out+> <out-> vol|cur pol|zer|pz
+                    let transfer = match ctrl.args[4].to_ascii_lowercase().as_str() {
+                        "cur" => Some(crate::circuit::PzTransfer::Current),
+                        "vol" => Some(crate::circuit::PzTransfer::Voltage),
+                        _ => None,
+                    };
+                    let target = match ctrl.args[5].to_ascii_lowercase().as_str() {
+                        "pol" => Some(crate::circuit::PzTarget::Poles),
+                        "zer" => Some(crate::circuit::PzTarget::Zeros),
+                        "pz" => Some(crate::circuit::PzTarget::Both),
+                        _ => None,
+                    };
+                    if let (Some(transfer), Some(target)) = (transfer, target) {
+                        circuit.analysis.push(AnalysisCmd::Pz {
+                            input_pos: ctrl.args[0].clone(),
+                            input_neg: ctrl.args[1].clone(),
+                            output_pos: ctrl.args[2].clone(),
+                            output_neg: ctrl.args[3].clone(),
+                            transfer,
+                            target,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Process .model statements extracted from subcircuits during elaboration
+    for ctrl in &elab.subckt_models {
+        if let (Some(name), Some(model_type)) = (&ctrl.model_name, &ctrl.model_type) {
+            let mut params = HashMap::new();
+            for param in &ctrl.params {
+                params.insert(param.key.to_ascii_lowercase(), param.value.clone());
+            }
+            let name_norm = name.to_ascii_lowercase();
+            let model_type_norm = model_type.to_ascii_lowercase();
+            circuit.models.insert(Model {
                 name: name_norm,
                 model_type: model_type_norm,
                 params,
@@ -999,6 +2581,21 @@ pub fn build_circuit(ast: &NetlistAst, elab: &ElaboratedNetlist) -> crate::circu
         }
     }
 
+    // .options scale / geoshrink: 版图尺寸到实际尺寸的缩放
+    let option_value = |key: &str| {
+        circuit
+            .options
+            .get(key)
+            .and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()))
+            .unwrap_or(1.0)
+    };
+    let geometry_scale = option_value("scale") * option_value("geoshrink");
+    // .options defw / defl / defad / defas: 未给出尺寸的 MOSFET 取这些缺省值 (缩放前的版图单位)
+    let mos_defaults: Vec<(&str, String)> = [("w", "defw"), ("l", "defl"), ("ad", "defad"), ("as", "defas")]
+        .into_iter()
+        .filter_map(|(key, option)| Some((key, circuit.options.get(option)?.clone())))
+        .collect();
+
     for device in &elab.instances {
         let kind = match device.kind {
             DeviceKind::R => Some(CircuitDeviceKind::R),
@@ -1008,37 +2605,109 @@ pub fn build_circuit(ast: &NetlistAst, elab: &ElaboratedNetlist) -> crate::circu
             DeviceKind::I => Some(CircuitDeviceKind::I),
             DeviceKind::D => Some(CircuitDeviceKind::D),
             DeviceKind::M => Some(CircuitDeviceKind::M),
+            DeviceKind::Q => Some(CircuitDeviceKind::Q),
+            DeviceKind::J => Some(CircuitDeviceKind::J),
+            DeviceKind::Z => Some(CircuitDeviceKind::Z),
+            DeviceKind::S => Some(CircuitDeviceKind::S),
+            DeviceKind::W => Some(CircuitDeviceKind::W),
+            DeviceKind::WLine => Some(CircuitDeviceKind::WLine),
+            DeviceKind::N => Some(CircuitDeviceKind::N),
+            DeviceKind::T => Some(CircuitDeviceKind::T),
+            DeviceKind::P => Some(CircuitDeviceKind::P),
+            DeviceKind::SParam => Some(CircuitDeviceKind::SParam),
             DeviceKind::E => Some(CircuitDeviceKind::E),
             DeviceKind::G => Some(CircuitDeviceKind::G),
             DeviceKind::F => Some(CircuitDeviceKind::F),
             DeviceKind::H => Some(CircuitDeviceKind::H),
             DeviceKind::X => Some(CircuitDeviceKind::X),
+            DeviceKind::B => Some(CircuitDeviceKind::B),
+            DeviceKind::A => Some(CircuitDeviceKind::A),
             DeviceKind::Unknown => None,
         };
         let Some(kind) = kind else {
             continue;
         };
 
-        let nodes = device
+        let mut nodes = device
             .nodes
             .iter()
             .map(|name| circuit.nodes.ensure_node(name))
             .collect::<Vec<_>>();
 
-        let model = device.model.as_ref().and_then(|name| {
-            let key = name.to_ascii_lowercase();
-            circuit.models.name_to_id.get(&key).copied()
-        });
+        // 行为源 / 非线性电容、电感表达式引用的节点追加在两个端口之后, 顺序与表达式变量一致
+        if matches!(device.kind, DeviceKind::B | DeviceKind::C | DeviceKind::L) {
+            for param in device.params.iter().filter(|param| is_expression_param(&device.kind, &param.key)) {
+                if let Ok(expr) = crate::behavioral::BehavioralExpr::parse(&param.value) {
+                    for name in expr.node_names() {
+                        nodes.push(circuit.nodes.ensure_node(name));
+                    }
+                }
+            }
+        }
+
+        let model = device
+            .model
+            .as_ref()
+            .and_then(|name| resolve_model(&circuit.models, name, &device.params));
+        // 半导体电阻/电容 `R1 a b rmod`: 取值字段就是模型名, 取值由模型给出
+        let mut value = device.value.clone();
+        if matches!(kind, CircuitDeviceKind::R | CircuitDeviceKind::C)
+            && model.is_some()
+            && value.as_deref().and_then(parse_number_with_suffix).is_none()
+        {
+            value = None;
+        }
 
+        // 参数优先级: 实例参数 > .model 参数; 模型类型 (nmos/pmos/d ...) 记为 "type"
         let mut params = HashMap::new();
         if let Some(model_id) = model {
             if let Some(model_def) = circuit.models.models.get(model_id.0) {
-                params.extend(model_def.params.clone());
+                // 模型的梯度系数 M 与实例并联倍数 m= 同名, 改记为别名 mj
+                params.extend(model_def.params.iter().map(|(key, value)| {
+                    let key = if key == "m" { "mj".to_string() } else { key.clone() };
+                    (key, value.clone())
+                }));
+                params
+                    .entry("type".to_string())
+                    .or_insert_with(|| model_def.model_type.clone());
             }
         }
         for param in &device.params {
             params.insert(param.key.to_ascii_lowercase(), param.value.clone());
         }
+        if matches!(kind, CircuitDeviceKind::M) {
+            for (key, value) in &mos_defaults {
+                params.entry(key.to_string()).or_insert_with(|| value.clone());
+            }
+        }
+        if matches!(kind, CircuitDeviceKind::M) && geometry_scale != 1.0 {
+            scale_mos_geometry(&mut params, geometry_scale);
+        }
+        if matches!(kind, CircuitDeviceKind::D) {
+            add_diode_internal_node(&mut circuit.nodes, device, &params, &mut nodes);
+        }
+        add_param_nodes(&mut circuit.nodes, device, &mut nodes);
+        if matches!(kind, CircuitDeviceKind::M) {
+            if sim_devices::power::is_vdmos(&params) {
+                add_vdmos_internal_nodes(&mut circuit.nodes, device, &params, &mut nodes);
+            } else {
+                add_mos_thermal_node(&mut circuit.nodes, device, &params, &mut nodes);
+            }
+        }
+        if matches!(kind, CircuitDeviceKind::WLine) {
+            add_wline_internal_nodes(&mut circuit.nodes, device, &params, &mut nodes);
+        }
+        if matches!(kind, CircuitDeviceKind::Q) {
+            add_bjt_internal_nodes(&mut circuit.nodes, device, &params, &mut nodes);
+        }
+        if matches!(kind, CircuitDeviceKind::Z) && sim_devices::power::is_igbt(&params) {
+            // IGBT 的内部基区节点 (MOS 沟道的漏极)
+            if nodes.len() == 3 {
+                nodes.push(circuit.nodes.ensure_node(&format!("{}#b", device.name)));
+            }
+        } else if matches!(kind, CircuitDeviceKind::J | CircuitDeviceKind::Z) {
+            add_jfet_internal_nodes(&mut circuit.nodes, device, &params, &mut nodes);
+        }
 
         circuit.instances.insert(Instance {
             name: device.name.clone(),
@@ -1046,13 +2715,30 @@ pub fn build_circuit(ast: &NetlistAst, elab: &ElaboratedNetlist) -> crate::circu
             nodes,
             model,
             params,
-            value: device.value.clone(),
+            value,
             control: device.control.clone(),
             ac_mag: device.ac_mag,
             ac_phase: device.ac_phase,
+            state_slot: 0,
         });
     }
 
+    for name in probed_devices(ast) {
+        insert_ammeter(&mut circuit, &name);
+    }
+
+    for (node, value) in initial_conditions {
+        let id = circuit.nodes.ensure_node(&node);
+        circuit.initial_conditions.insert(id, value);
+    }
+
+    // 子电路端口的层次名指向外部节点; 内部节点的层次名由节点表自动登记
+    for (alias, node) in &elab.node_aliases {
+        if let Some(id) = circuit.nodes.lookup(node) {
+            circuit.nodes.add_alias(alias, id);
+        }
+    }
+
     if circuit.analysis.is_empty() {
         circuit.analysis.push(AnalysisCmd::Op);
     }
@@ -1060,76 +2746,1180 @@ pub fn build_circuit(ast: &NetlistAst, elab: &ElaboratedNetlist) -> crate::circu
     circuit
 }
 
+/// MOSFET 的版图尺寸乘以全局缩放: W/L/PD/PS 乘 `factor`, AD/AS 乘 `factor²`
+fn scale_mos_geometry(params: &mut std::collections::HashMap<String, String>, factor: f64) {
+    for (key, power) in [("w", 1), ("l", 1), ("pd", 1), ("ps", 1), ("ad", 2), ("as", 2)] {
+        if let Some(value) = params.get(key) {
+            if let Some(number) = parse_number_with_suffix(value).or_else(|| value.parse().ok()) {
+                params.insert(key.to_string(), (number * factor.powi(power)).to_string());
+            }
+        }
+    }
+}
+
+/// 解析 `.data <name> <col>... <value>...`: 第一个数值之前的字段是列名,
+/// 其余数值按列数分行
+fn parse_data_table(args: &[String]) -> Result<crate::circuit::DataTable, String> {
+    let Some(name) = args.first() else {
+        return Err(".data requires a table name".to_string());
+    };
+    let is_number = |token: &str| {
+        token
+            .chars()
+            .next()
+            .is_some_and(|ch| ch.is_ascii_digit() || matches!(ch, '-' | '+' | '.'))
+    };
+    let columns: Vec<String> = args[1..]
+        .iter()
+        .take_while(|token| !is_number(token))
+        .map(|token| token.to_ascii_lowercase())
+        .collect();
+    if columns.is_empty() {
+        return Err(format!(".data {} has no columns", name));
+    }
+    let mut values = Vec::new();
+    for token in &args[1 + columns.len()..] {
+        let value = parse_number_with_suffix(token)
+            .or_else(|| token.parse().ok())
+            .ok_or_else(|| format!(".data {} has an invalid value: {}", name, token))?;
+        values.push(value);
+    }
+    if values.is_empty() || values.len() % columns.len() != 0 {
+        return Err(format!(
+            ".data {} has {} values, which is not a multiple of its {} columns",
+            name,
+            values.len(),
+            columns.len()
+        ));
+    }
+    Ok(crate::circuit::DataTable {
+        name: name.to_ascii_lowercase(),
+        columns: columns.clone(),
+        rows: values.chunks(columns.len()).map(<[f64]>::to_vec).collect(),
+    })
+}
+
+/// 解析一组 `<src>|temp|param <name> <start> <stop> <step>`, 返回扫描定义与剩余参数
+fn parse_dc_sweep_spec(args: &[String]) -> Option<(crate::circuit::DcSweepSpec, &[String])> {
+    use crate::circuit::DcSweepKind;
+
+    let (kind, rest) = match args.first()?.to_ascii_lowercase().as_str() {
+        "temp" => (DcSweepKind::Temp, args),
+        "param" => (DcSweepKind::Param, &args[1..]),
+        _ => (DcSweepKind::Source, args),
+    };
+    if rest.len() < 4 {
+        return None;
+    }
+    let value = |token: &String| {
+        parse_number_with_suffix(token)
+            .or_else(|| token.parse().ok())
+            .unwrap_or(0.0)
+    };
+    let spec = crate::circuit::DcSweepSpec {
+        kind,
+        source: rest[0].clone(),
+        start: value(&rest[1]),
+        stop: value(&rest[2]),
+        step: value(&rest[3]),
+    };
+    Some((spec, &rest[4..]))
+}
+
+/// 多窗口 `.tran`: 偶数个 (至少四个) 正数, 各窗口的 tstop 严格递增。
+/// 四个数时与 `tstep tstop tstart tmax` 的区别是第四个数大于第二个 (TMAX 不会超过 TSTOP)
+fn parse_tran_windows(numbers: &[f64]) -> Option<Vec<crate::circuit::TranWindow>> {
+    if numbers.len() < 4 || !numbers.len().is_multiple_of(2) {
+        return None;
+    }
+    let windows: Vec<crate::circuit::TranWindow> = numbers
+        .chunks(2)
+        .map(|pair| crate::circuit::TranWindow {
+            tstep: pair[0],
+            tstop: pair[1],
+        })
+        .collect();
+    let valid = windows.iter().all(|window| window.tstep > 0.0)
+        && windows[0].tstop > 0.0
+        && windows.windows(2).all(|pair| pair[1].tstop > pair[0].tstop);
+    valid.then_some(windows)
+}
+
+/// `.measure <tran|ac|dc> <名字> <测量> [min=<下限>] [max=<上限>]`, 测量取以下之一:
+/// - `TRIG <信号> VAL=<v> [TD=] [RISE=|FALL=|CROSS=<n|LAST>] TARG <信号> VAL=<v> [...]`: 延迟
+/// - `WHEN <信号>=<v> [TD=] [RISE=|FALL=|CROSS=]`: 穿越时刻
+/// - `FIND <信号> AT=<x>`
+/// - `AVG|RMS|MIN|MAX|PP|INTEG <信号> [FROM=] [TO=]`
+/// - `SLEW <信号> LOW=<v> HIGH=<v> [RISE=|FALL=]`
+/// - `OVERSHOOT <信号> FROM= TO=`、`SETTLING <信号> FROM= TO= [TOL=<相对误差带, 缺省 0.02>]`
+/// - `PARAM=<表达式>`: 引用之前的测量
+///
+/// `min=` / `max=` 是规格上下限, 工艺角分析据此判断通过与否
+pub fn parse_measure(line: &str) -> Result<crate::measure::MeasureCmd, String> {
+    use crate::measure::{Measure, MeasureAnalysis, MeasureCmd, MeasureSpec, Stat};
+
+    let mut words = line.split_whitespace().skip(1);
+    let analysis = match words.next().map(str::to_ascii_lowercase).as_deref() {
+        Some("tran") => MeasureAnalysis::Tran,
+        Some("ac") => MeasureAnalysis::Ac,
+        Some("dc") => MeasureAnalysis::Dc,
+        Some(other) => return Err(format!(".measure does not support '{}' results", other)),
+        None => return Err(".measure needs an analysis type and a name".to_string()),
+    };
+    let name = words.next().ok_or(".measure needs a name")?.to_string();
+    let rest = words.collect::<Vec<_>>().join(" ");
+
+    // PARAM= 的表达式可以含空格; 加引号或花括号时其后还可以跟上下限
+    let lower = rest.to_ascii_lowercase();
+    let (measure, tokens) = match lower.strip_prefix("param").map(str::trim_start) {
+        Some(after) if after.starts_with('=') => {
+            let expr = rest[rest.len() - after.len() + 1..].trim();
+            let (expr, tail) = match expr.chars().next() {
+                Some(open @ ('\'' | '"' | '{')) => {
+                    let close = if open == '{' { '}' } else { open };
+                    let end = expr[1..].find(close).ok_or("unterminated PARAM= expression")? + 1;
+                    (&expr[1..end], &expr[end + 1..])
+                }
+                _ => (expr, ""),
+            };
+            if expr.trim().is_empty() {
+                return Err("PARAM= needs an expression".to_string());
+            }
+            (Some(Measure::Expr(expr.trim().to_string())), measure_tokens(tail))
+        }
+        _ => (None, measure_tokens(&rest)),
+    };
+
+    // 规格上下限可以出现在任何位置
+    let mut limits = (None, None);
+    let mut tokens: Vec<String> = tokens
+        .into_iter()
+        .filter_map(|token| match token.split_once('=') {
+            Some((key, value)) if key.eq_ignore_ascii_case("min") || key.eq_ignore_ascii_case("max") => {
+                let value = measure_number(value);
+                if key.eq_ignore_ascii_case("min") {
+                    limits.0 = Some(value);
+                } else {
+                    limits.1 = Some(value);
+                }
+                None
+            }
+            _ => Some(token),
+        })
+        .collect();
+    let (min, max) = (limits.0.transpose()?, limits.1.transpose()?);
+
+    let measure = match measure {
+        Some(measure) => {
+            if let Some(extra) = tokens.first() {
+                return Err(format!("unexpected '{}' after PARAM=", extra));
+            }
+            measure
+        }
+        None => {
+            if tokens.is_empty() {
+                return Err(format!(".measure {} needs a measurement", name));
+            }
+            let keyword = tokens.remove(0).to_ascii_lowercase();
+            let signal = |tokens: &[String]| -> Result<crate::measure::Signal, String> {
+                let text = tokens.first().ok_or_else(|| format!("{} needs a signal", keyword.to_ascii_uppercase()))?;
+                crate::measure::Signal::parse(text).ok_or_else(|| format!("invalid signal '{}'", text))
+            };
+            match keyword.as_str() {
+                "trig" => {
+                    let targ = tokens
+                        .iter()
+                        .position(|token| token.eq_ignore_ascii_case("targ"))
+                        .ok_or("TRIG needs a matching TARG")?;
+                    let trig = measure_crossing(signal(&tokens[..targ])?, None, &tokens[1..targ])?;
+                    let targ_tokens = &tokens[targ + 1..];
+                    let targ = measure_crossing(signal(targ_tokens)?, None, targ_tokens.get(1..).unwrap_or(&[]))?;
+                    Measure::Delay { trig, targ }
+                }
+                "when" => {
+                    let first = tokens.first().ok_or("WHEN needs <signal>=<value>")?;
+                    let (text, value) = first.rsplit_once('=').ok_or("WHEN needs <signal>=<value>")?;
+                    let signal = crate::measure::Signal::parse(text).ok_or_else(|| format!("invalid signal '{}'", text))?;
+                    Measure::When(measure_crossing(signal, Some(measure_number(value)?), &tokens[1..])?)
+                }
+                "find" => {
+                    let options = measure_options(&tokens[1..], &["at"])?;
+                    let at = options.get("at").copied().ok_or("FIND needs AT=")?;
+                    Measure::FindAt { signal: signal(&tokens)?, at }
+                }
+                "avg" | "rms" | "min" | "max" | "pp" | "integ" | "integral" => {
+                    let stat = match keyword.as_str() {
+                        "avg" => Stat::Avg,
+                        "rms" => Stat::Rms,
+                        "min" => Stat::Min,
+                        "max" => Stat::Max,
+                        "pp" => Stat::Pp,
+                        _ => Stat::Integ,
+                    };
+                    let options = measure_options(tokens.get(1..).unwrap_or(&[]), &["from", "to"])?;
+                    Measure::Stat {
+                        stat,
+                        signal: signal(&tokens)?,
+                        from: options.get("from").copied(),
+                        to: options.get("to").copied(),
+                    }
+                }
+                "slew" => {
+                    let options = measure_options(tokens.get(1..).unwrap_or(&[]), &["low", "high", "rise", "fall"])?;
+                    let (Some(&low), Some(&high)) = (options.get("low"), options.get("high")) else {
+                        return Err("SLEW needs LOW= and HIGH=".to_string());
+                    };
+                    let (edge, occurrence) = measure_edge(&options)?;
+                    Measure::Slew {
+                        signal: signal(&tokens)?,
+                        low,
+                        high,
+                        edge,
+                        occurrence,
+                    }
+                }
+                "overshoot" | "settling" => {
+                    let options = measure_options(tokens.get(1..).unwrap_or(&[]), &["from", "to", "tol"])?;
+                    let (Some(&from), Some(&to)) = (options.get("from"), options.get("to")) else {
+                        return Err(format!("{} needs FROM= and TO=", keyword.to_ascii_uppercase()));
+                    };
+                    let signal = signal(&tokens)?;
+                    if keyword == "overshoot" {
+                        Measure::Overshoot { signal, from, to }
+                    } else {
+                        let tol = options.get("tol").copied().unwrap_or(0.02);
+                        Measure::Settling { signal, from, to, tol }
+                    }
+                }
+                other => return Err(format!("unknown measurement '{}'", other)),
+            }
+        }
+    };
+    Ok(MeasureCmd {
+        analysis,
+        spec: MeasureSpec { name, measure },
+        min,
+        max,
+    })
+}
+
+/// `.measure` 的字段: 括号内的空白去掉 (`v(a, b)` 为一个字段), `key = value` 合成 `key=value`
+fn measure_tokens(text: &str) -> Vec<String> {
+    let mut compact = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    for ch in text.chars() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ch if ch.is_whitespace() && depth > 0 => continue,
+            _ => {}
+        }
+        compact.push(ch);
+    }
+    let spaced = compact.replace('=', " = ");
+    let mut tokens: Vec<String> = Vec::new();
+    let mut words = spaced.split_whitespace();
+    while let Some(word) = words.next() {
+        if word == "=" {
+            let next = words.next().unwrap_or("");
+            match tokens.last_mut() {
+                Some(last) => {
+                    last.push('=');
+                    last.push_str(next);
+                }
+                None => tokens.push(format!("={}", next)),
+            }
+        } else {
+            tokens.push(word.to_string());
+        }
+    }
+    tokens
+}
+
+fn measure_number(text: &str) -> Result<f64, String> {
+    parse_number_with_suffix(text)
+        .or_else(|| text.parse().ok())
+        .ok_or_else(|| format!("invalid number '{}'", text))
+}
+
+/// 字段中的 `key=value` 选项, 只允许 `allowed` 中的键; `rise/fall/cross=last` 记为 -1
+fn measure_options(tokens: &[String], allowed: &[&str]) -> Result<std::collections::HashMap<String, f64>, String> {
+    let mut options = std::collections::HashMap::new();
+    for token in tokens {
+        let (key, value) = token.split_once('=').ok_or_else(|| format!("unexpected '{}'", token))?;
+        let key = key.to_ascii_lowercase();
+        if !allowed.contains(&key.as_str()) {
+            return Err(format!("unexpected option '{}'", token));
+        }
+        let value = if matches!(key.as_str(), "rise" | "fall" | "cross") && value.eq_ignore_ascii_case("last") {
+            -1.0
+        } else {
+            measure_number(value)?
+        };
+        options.insert(key, value);
+    }
+    Ok(options)
+}
+
+/// 穿越条件: `VAL=` (WHEN 已给出电平时为 `value`)、`TD=` 与 `RISE=|FALL=|CROSS=<n|LAST>`
+fn measure_crossing(
+    signal: crate::measure::Signal,
+    value: Option<f64>,
+    tokens: &[String],
+) -> Result<crate::measure::Crossing, String> {
+    let allowed: &[&str] = match value {
+        Some(_) => &["td", "rise", "fall", "cross"],
+        None => &["val", "td", "rise", "fall", "cross"],
+    };
+    let options = measure_options(tokens, allowed)?;
+    let value = match value {
+        Some(value) => value,
+        None => options.get("val").copied().ok_or("TRIG/TARG needs VAL=")?,
+    };
+    let (edge, occurrence) = measure_edge(&options)?;
+    Ok(crate::measure::Crossing {
+        signal,
+        value,
+        edge,
+        occurrence,
+        td: options.get("td").copied().unwrap_or(f64::NEG_INFINITY),
+    })
+}
+
+/// `RISE=|FALL=|CROSS=<n|LAST>` 给出的方向与次数, 缺省为任意方向的第一次
+fn measure_edge(
+    options: &std::collections::HashMap<String, f64>,
+) -> Result<(crate::measure::Edge, crate::measure::Occurrence), String> {
+    use crate::measure::{Edge, Occurrence};
+
+    let (edge, count) = match (options.get("rise"), options.get("fall"), options.get("cross")) {
+        (Some(n), None, None) => (Edge::Rise, *n),
+        (None, Some(n), None) => (Edge::Fall, *n),
+        (None, None, Some(n)) => (Edge::Cross, *n),
+        (None, None, None) => (Edge::Cross, 1.0),
+        _ => return Err("only one of RISE=, FALL= and CROSS= may be given".to_string()),
+    };
+    let occurrence = if count < 0.0 {
+        Occurrence::Last
+    } else if count >= 1.0 && count.fract() == 0.0 {
+        Occurrence::Nth(count as usize)
+    } else {
+        return Err(format!("invalid crossing count {}", count));
+    };
+    Ok((edge, occurrence))
+}
+
+/// `.step [lin|dec|oct] <var> <start> <stop> <incr|points>` 或 `.step <var> list <v>...`,
+/// `<var>` 为 `param <name>`、`temp` 或源名
+fn parse_step_spec(args: &[String]) -> Option<crate::circuit::StepSpec> {
+    use crate::circuit::{AcSweepType, DcSweepKind, StepValues};
+
+    let sweep_type = match args.first()?.to_ascii_lowercase().as_str() {
+        "lin" => Some(AcSweepType::Lin),
+        "dec" => Some(AcSweepType::Dec),
+        "oct" => Some(AcSweepType::Oct),
+        _ => None,
+    };
+    let args = if sweep_type.is_some() { &args[1..] } else { args };
+    let (kind, rest) = match args.first()?.to_ascii_lowercase().as_str() {
+        "temp" => (DcSweepKind::Temp, args),
+        "param" => (DcSweepKind::Param, &args[1..]),
+        _ => (DcSweepKind::Source, args),
+    };
+    let name = rest.first()?.clone();
+    let is_list = rest.get(1).is_some_and(|arg| arg.eq_ignore_ascii_case("list"));
+    let numbers: Vec<f64> = rest[1 + usize::from(is_list)..]
+        .iter()
+        .map(|token| parse_number_with_suffix(token).or_else(|| token.parse().ok()))
+        .collect::<Option<_>>()?;
+    let values = if is_list {
+        if numbers.is_empty() || sweep_type.is_some() {
+            return None;
+        }
+        StepValues::List(numbers)
+    } else {
+        let [start, stop, third] = numbers[..] else { return None };
+        match sweep_type {
+            Some(sweep_type @ (AcSweepType::Dec | AcSweepType::Oct)) => {
+                if start <= 0.0 || stop <= 0.0 || third < 1.0 {
+                    return None;
+                }
+                StepValues::Log { sweep_type, points: third as usize, start, stop }
+            }
+            _ => StepValues::Lin { start, stop, step: third },
+        }
+    };
+    Some(crate::circuit::StepSpec { kind, name, values })
+}
+
+/// 网表中是否有名为 `name` 的 `.param`
+pub fn has_param(ast: &NetlistAst, name: &str) -> bool {
+    ast.statements.iter().any(|stmt| {
+        matches!(stmt, Stmt::Control(ctrl) if matches!(ctrl.kind, ControlKind::Param)
+            && ctrl.params.iter().any(|param| param.key.eq_ignore_ascii_case(name)))
+    })
+}
+
+/// 顶层 `.param` 为数值字面量时的取值; 没有该参数或取值是表达式时为 None
+pub fn param_number(ast: &NetlistAst, name: &str) -> Option<f64> {
+    ast.statements.iter().rev().find_map(|stmt| match stmt {
+        Stmt::Control(ctrl) if matches!(ctrl.kind, ControlKind::Param) => ctrl
+            .params
+            .iter()
+            .find(|param| param.key.eq_ignore_ascii_case(name))
+            .and_then(|param| parse_number_with_suffix(&param.value)),
+        _ => None,
+    })
+}
+
+/// 覆盖顶层 `.param` 的取值; 网表中没有该参数时在开头补一条 `.param`
+pub fn override_param(ast: &mut NetlistAst, name: &str, value: &str) {
+    let mut found = false;
+    for stmt in &mut ast.statements {
+        if let Stmt::Control(ctrl) = stmt {
+            if matches!(ctrl.kind, ControlKind::Param) {
+                for param in &mut ctrl.params {
+                    if param.key.eq_ignore_ascii_case(name) {
+                        param.value = value.to_string();
+                        found = true;
+                    }
+                }
+            }
+        }
+    }
+    if !found {
+        ast.statements.insert(
+            0,
+            Stmt::Control(ControlStmt {
+                command: ".param".to_string(),
+                kind: ControlKind::Param,
+                args: Vec::new(),
+                params: vec![Param {
+                    key: name.to_string(),
+                    value: value.to_string(),
+                }],
+                model_name: None,
+                model_type: None,
+                subckt_name: None,
+                subckt_ports: Vec::new(),
+                raw: format!(".param {}={}", name, value),
+                line: 0,
+            }),
+        );
+    }
+}
+
+/// 解析 `dec|oct|lin <points> <fstart> <fstop>`
+fn parse_ac_sweep(args: &[String]) -> Option<(crate::circuit::AcSweepType, usize, f64, f64)> {
+    if args.len() < 4 {
+        return None;
+    }
+    let sweep_type = match args[0].to_ascii_lowercase().as_str() {
+        "dec" => crate::circuit::AcSweepType::Dec,
+        "oct" => crate::circuit::AcSweepType::Oct,
+        "lin" => crate::circuit::AcSweepType::Lin,
+        _ => crate::circuit::AcSweepType::Dec, // default to decade
+    };
+    let points = args[1].parse().unwrap_or(10);
+    let fstart = parse_number_with_suffix(&args[2])
+        .or_else(|| args[2].parse().ok())
+        .unwrap_or(1.0);
+    let fstop = parse_number_with_suffix(&args[3])
+        .or_else(|| args[3].parse().ok())
+        .unwrap_or(1e6);
+    Some((sweep_type, points, fstart, fstop))
+}
+
+/// `.pac` / `.pnoise` 的 `sidebands=K` (别名 `maxsideband=`), 缺省为 3
+fn sideband_count(params: &[Param]) -> usize {
+    params
+        .iter()
+        .find(|param| param.key.eq_ignore_ascii_case("sidebands") || param.key.eq_ignore_ascii_case("maxsideband"))
+        .and_then(|param| param.value.parse::<usize>().ok())
+        .unwrap_or(3)
+}
+
+/// 解析输出探针 `V(n)` / `V(n1,n2)` / `I(Vx)`, 返回 (类型字母, 第一个名字, 可选的第二个名字)
+fn parse_output_probe(token: &str) -> Option<(char, String, Option<String>)> {
+    let lower = token.to_ascii_lowercase();
+    let kind = lower.chars().next()?;
+    if !matches!(kind, 'v' | 'i') {
+        return None;
+    }
+    let inner = lower[1..].strip_prefix('(')?.strip_suffix(')')?;
+    let mut parts = inner.split(',').map(|p| p.trim().to_string());
+    let first = parts.next().filter(|p| !p.is_empty())?;
+    let second = parts.next().filter(|p| !p.is_empty());
+    Some((kind, first, second))
+}
+
+/// include 链上的一环: 文件路径、正在读的 `.lib` 段及其 .include 语句所在行号
+struct IncludeFrame {
+    path: std::path::PathBuf,
+    section: Option<String>,
+    line: usize,
+}
+
+/// 展开 include 时在各层之间共享的设置与结果
+struct IncludeState {
+    search_dirs: Vec<std::path::PathBuf>,
+    /// 覆盖 `.lib` 段之外的 `.lib <file> <section>` 语句的段名
+    lib_section: Option<String>,
+    chain: Vec<IncludeFrame>,
+    errors: Vec<Diagnostic>,
+    origins: Vec<(std::path::PathBuf, usize)>,
+    /// 之前定义的字符串参数, 供 include 路径替换
+    string_params: std::collections::HashMap<String, String>,
+}
+
+/// 为二极管的 RS 串联电阻创建本征阳极节点 (`d1#a`), 追加在两个外部端口之后;
+/// RS 为 0 时不追加
+fn add_diode_internal_node(
+    table: &mut crate::circuit::NodeTable,
+    device: &DeviceStmt,
+    params: &std::collections::HashMap<String, String>,
+    nodes: &mut Vec<crate::circuit::NodeId>,
+) {
+    if nodes.len() != 2 {
+        return;
+    }
+    let area = device
+        .value
+        .as_deref()
+        .and_then(parse_number_with_suffix)
+        .or_else(|| params.get("area").and_then(|v| parse_number_with_suffix(v)))
+        .unwrap_or(1.0);
+    if sim_devices::diode::build_diode_params(params).with_area(area).rs > 0.0 {
+        nodes.push(table.ensure_node(&format!("{}#a", device.name)));
+    }
+}
+
+/// 参数引用的节点 (光电器件的光节点、热敏电阻的温度节点) 依次追加在端口 (含 `d1#a`) 之后
+fn add_param_nodes(
+    table: &mut crate::circuit::NodeTable,
+    device: &DeviceStmt,
+    nodes: &mut Vec<crate::circuit::NodeId>,
+) {
+    for key in node_param_keys(&device.kind) {
+        if let Some(param) = device.params.iter().find(|param| param.key.eq_ignore_ascii_case(key)) {
+            nodes.push(table.ensure_node(&param.value));
+        }
+    }
+}
+
+/// 多导体传输线分为 NSEG 节集总电路, 节间的 N 个导体节点 `w1#k_j` 追加在端口之后,
+/// 按节间位置 k (1..NSEG-1) 再按导体 j 排列
+fn add_wline_internal_nodes(
+    table: &mut crate::circuit::NodeTable,
+    device: &DeviceStmt,
+    params: &std::collections::HashMap<String, String>,
+    nodes: &mut Vec<crate::circuit::NodeId>,
+) {
+    let number = |key: &str| params.get(key).and_then(|v| parse_number_with_suffix(v)).map(|v| v as usize);
+    let Some(conductors) = number("n") else {
+        return;
+    };
+    let segments = number("nseg").filter(|n| *n > 0).unwrap_or(crate::rlgc::DEFAULT_SEGMENTS);
+    for k in 1..segments {
+        for j in 1..=conductors {
+            nodes.push(table.ensure_node(&format!("{}#{}_{}", device.name, k, j)));
+        }
+    }
+}
+
+/// MOSFET 自热: RTH > 0 时追加热节点 `m1#t` (节点电压即温升), 排在四个端口之后
+fn add_mos_thermal_node(
+    table: &mut crate::circuit::NodeTable,
+    device: &DeviceStmt,
+    params: &std::collections::HashMap<String, String>,
+    nodes: &mut Vec<crate::circuit::NodeId>,
+) {
+    let rth = params.get("rth").and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()));
+    if nodes.len() == 4 && rth.is_some_and(|rth| rth > 0.0) {
+        nodes.push(table.ensure_node(&format!("{}#t", device.name)));
+    }
+}
+
+/// VDMOS: 追加 RD/RS 内侧的本征漏、源节点 `m1#d` / `m1#s` (排在衬底之后), 电阻为零时重复外部端口
+fn add_vdmos_internal_nodes(
+    table: &mut crate::circuit::NodeTable,
+    device: &DeviceStmt,
+    params: &std::collections::HashMap<String, String>,
+    nodes: &mut Vec<crate::circuit::NodeId>,
+) {
+    if nodes.len() != 4 {
+        return;
+    }
+    let model = sim_devices::power::build_vdmos_params(params);
+    for (idx, suffix, resistance) in [(0, "d", model.rd), (2, "s", model.rs)] {
+        let node = if resistance > 0.0 {
+            table.ensure_node(&format!("{}#{}", device.name, suffix))
+        } else {
+            nodes[idx]
+        };
+        nodes.push(node);
+    }
+}
+
+/// 电流探针: 在器件第一个端口串入 0 V 电压源 `{name}#i` (正端接原节点, 负端接新节点
+/// `{name}#i`), 其支路电流即流入该端口的电流 I(器件)。电压源本身已有支路电流, 不再串入
+fn insert_ammeter(circuit: &mut crate::circuit::Circuit, name: &str) {
+    use crate::circuit::{DeviceKind as CircuitDeviceKind, Instance};
+    let Some(id) = circuit.instances.find(name) else { return };
+    let inst = &circuit.instances.instances[id.0];
+    if matches!(inst.kind, CircuitDeviceKind::V | CircuitDeviceKind::X) || inst.nodes.is_empty() {
+        return;
+    }
+    let ammeter = format!("{}#i", inst.name);
+    if circuit.instances.find(&ammeter).is_some() {
+        return;
+    }
+    let node = circuit.nodes.ensure_node(&ammeter);
+    let terminal = std::mem::replace(&mut circuit.instances.instances[id.0].nodes[0], node);
+    circuit.instances.insert(Instance {
+        name: ammeter,
+        kind: CircuitDeviceKind::V,
+        nodes: vec![terminal, node],
+        model: None,
+        params: std::collections::HashMap::new(),
+        value: Some("0".to_string()),
+        control: None,
+        ac_mag: None,
+        ac_phase: None,
+        state_slot: 0,
+    });
+}
+
+/// 需要支路电流的器件: `.probe` 中的 `I(name)` 与输出参数表达式引用的 `i(name)`
+pub fn probed_devices(ast: &NetlistAst) -> Vec<String> {
+    let (top_level, _, _) = extract_subckts(&ast.statements);
+    let mut names: Vec<String> = Vec::new();
+    for stmt in &top_level {
+        let Stmt::Control(ctrl) = stmt else { continue };
+        if matches!(ctrl.kind, ControlKind::Probe) {
+            names.extend(ctrl.args.iter().filter_map(|arg| {
+                let inner = arg.strip_suffix(')')?;
+                let (func, name) = inner.split_once('(')?;
+                func.eq_ignore_ascii_case("i").then(|| name.to_string())
+            }));
+        }
+    }
+    for param in output_params(ast) {
+        let tokens = tokenize_expr(strip_param_delimiters(&param.value));
+        names.extend(tokens.windows(4).filter_map(|window| match window {
+            [ExprToken::Ident(func), ExprToken::LParen, ExprToken::Ident(name), ExprToken::RParen]
+                if func.eq_ignore_ascii_case("i") =>
+            {
+                Some(name.clone())
+            }
+            _ => None,
+        }));
+    }
+    let mut unique: Vec<String> = Vec::new();
+    for name in names {
+        if !unique.iter().any(|seen| seen.eq_ignore_ascii_case(&name)) {
+            unique.push(name);
+        }
+    }
+    unique
+}
+
+/// 为 BJT 的 RC/RB/RE 串联电阻创建本征节点 (`q1#c` / `q1#b` / `q1#e`),
+/// 按 c, b, e 顺序追加在四个外部端口之后; 电阻为 0 的端口直接复用外部节点。
+/// VBIC 模型的 RCI 非零时再追加本征集电极节点 `q1#ci`
+fn add_bjt_internal_nodes(
+    table: &mut crate::circuit::NodeTable,
+    device: &DeviceStmt,
+    params: &std::collections::HashMap<String, String>,
+    nodes: &mut Vec<crate::circuit::NodeId>,
+) {
+    if nodes.len() != 4 {
+        return;
+    }
+    let area = device
+        .value
+        .as_deref()
+        .and_then(parse_number_with_suffix)
+        .or_else(|| params.get("area").and_then(|v| parse_number_with_suffix(v)))
+        .unwrap_or(1.0);
+    let model = sim_devices::bjt::build_bjt_model(params, area);
+    for (idx, (suffix, resistance)) in ["c", "b", "e"]
+        .into_iter()
+        .zip(model.series_resistances())
+        .enumerate()
+    {
+        let node = if resistance > 0.0 {
+            table.ensure_node(&format!("{}#{}", device.name, suffix))
+        } else {
+            nodes[idx]
+        };
+        nodes.push(node);
+    }
+    // VBIC 的 RCI (Kull 外延层模型) 再串一个本征集电极节点
+    if model.epi_resistance() > 0.0 {
+        nodes.push(table.ensure_node(&format!("{}#ci", device.name)));
+    }
+}
+
+/// 为 JFET / MESFET 的 RD/RS 串联电阻创建本征节点 (`j1#d` / `j1#s`), 按 d, s
+/// 顺序追加在三个外部端口之后; 电阻为 0 的端口直接复用外部节点
+fn add_jfet_internal_nodes(
+    table: &mut crate::circuit::NodeTable,
+    device: &DeviceStmt,
+    params: &std::collections::HashMap<String, String>,
+    nodes: &mut Vec<crate::circuit::NodeId>,
+) {
+    if nodes.len() != 3 {
+        return;
+    }
+    let area = device
+        .value
+        .as_deref()
+        .and_then(parse_number_with_suffix)
+        .or_else(|| params.get("area").and_then(|v| parse_number_with_suffix(v)))
+        .unwrap_or(1.0);
+    let model = sim_devices::jfet::build_jfet_params(params).with_area(area);
+    for (idx, suffix, resistance) in [(0, "d", model.rd), (2, "s", model.rs)] {
+        let node = if resistance > 0.0 {
+            table.ensure_node(&format!("{}#{}", device.name, suffix))
+        } else {
+            nodes[idx]
+        };
+        nodes.push(node);
+    }
+}
+
+/// 按名称查找模型 (大小写不敏感)。找不到时按 binning 规则匹配 `name.N` 模型:
+/// 实例的 l/w 需落在模型的 [lmin, lmax) / [wmin, wmax) 范围内。
+fn resolve_model(
+    models: &crate::circuit::ModelTable,
+    name: &str,
+    instance_params: &[Param],
+) -> Option<crate::circuit::ModelId> {
+    let key = name.to_ascii_lowercase();
+    if let Some(id) = models.name_to_id.get(&key) {
+        return Some(*id);
+    }
+
+    let prefix = format!("{}.", key);
+    let instance_dim = |dim: &str| {
+        instance_params
+            .iter()
+            .find(|p| p.key.eq_ignore_ascii_case(dim))
+            .and_then(|p| parse_number_with_suffix(&p.value).or_else(|| p.value.parse().ok()))
+    };
+    let l = instance_dim("l");
+    let w = instance_dim("w");
+    let model_dim = |model: &crate::circuit::Model, dim: &str| {
+        model
+            .params
+            .get(dim)
+            .and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()))
+    };
+    let in_range = |value: Option<f64>, min: Option<f64>, max: Option<f64>| match value {
+        Some(v) => min.is_none_or(|min| v >= min) && max.is_none_or(|max| v < max),
+        None => true,
+    };
+
+    models
+        .models
+        .iter()
+        .enumerate()
+        .filter(|(_, model)| {
+            model
+                .name
+                .strip_prefix(&prefix)
+                .is_some_and(|bin| bin.chars().all(|c| c.is_ascii_digit()))
+        })
+        .find(|(_, model)| {
+            in_range(l, model_dim(model, "lmin"), model_dim(model, "lmax"))
+                && in_range(w, model_dim(model, "wmin"), model_dim(model, "wmax"))
+        })
+        .map(|(idx, _)| crate::circuit::ModelId(idx))
+}
+
+/// binning 模型名 `nch.1` 的基础名 `nch`
+pub(crate) fn model_bin_base(name: &str) -> Option<&str> {
+    let (base, bin) = name.rsplit_once('.')?;
+    if !base.is_empty() && !bin.is_empty() && bin.chars().all(|c| c.is_ascii_digit()) {
+        Some(base)
+    } else {
+        None
+    }
+}
+
+/// 读入网表文本; gzip 压缩的文件 (按文件头识别) 透明解压
+pub(crate) fn read_netlist_text(path: &std::path::Path) -> std::io::Result<String> {
+    use std::io::Read;
+
+    let bytes = std::fs::read(path)?;
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        return String::from_utf8(bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err));
+    }
+    let mut text = String::new();
+    flate2::read::MultiGzDecoder::new(bytes.as_slice()).read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// 读入 `path` 并展开其中的 `.include` / `.lib`; `section` 给出时只读该文件中
+/// `.lib <section>` 与 `.endl` 之间的行, 否则跳过文件中所有 `.lib` 段定义
 fn read_with_includes(
     path: &std::path::Path,
-    visited: &mut std::collections::HashSet<std::path::PathBuf>,
-    errors: &mut Vec<ParseError>,
+    format: NetlistFormat,
+    section: Option<&str>,
+    state: &mut IncludeState,
 ) -> String {
-    if !visited.insert(path.to_path_buf()) {
-        errors.push(ParseError {
-            line: 0,
-            message: format!("include 循环引用: {}", path.display()),
-        });
+    let chain = &state.chain;
+    if chain
+        .iter()
+        .any(|frame| frame.path == path && frame.section.as_deref() == section)
+    {
+        let error = include_diagnostic(
+            codes::INCLUDE_CYCLE,
+            chain,
+            format!(
+                "include cycle: {} (include chain: {})",
+                path.display(),
+                format_include_chain(chain, path)
+            ),
+        );
+        state.errors.push(error);
         return String::new();
     }
 
-    let content = std::fs::read_to_string(path).unwrap_or_else(|_| {
-        errors.push(ParseError {
-            line: 0,
-            message: format!("无法读取文件: {}", path.display()),
-        });
-        String::new()
-    });
+    let content = match read_netlist_text(path) {
+        Ok(content) => content,
+        Err(_) => {
+            let message = if chain.is_empty() {
+                format!("cannot read file: {}", path.display())
+            } else {
+                format!(
+                    "cannot read file: {} (include chain: {})",
+                    path.display(),
+                    format_include_chain(chain, path)
+                )
+            };
+            let error = include_diagnostic(codes::FILE_UNREADABLE, chain, message);
+            state.errors.push(error);
+            return String::new();
+        }
+    };
+    // Spectre 文件先逐行翻译为 SPICE, 行号不变
+    let content = match format {
+        NetlistFormat::Spice => content,
+        NetlistFormat::Spectre => {
+            let (translated, spectre_errors) = crate::spectre::spectre_to_spice(&content);
+            state.errors.extend(spectre_errors.into_iter().map(|diag| diag.with_file(path)));
+            translated
+        }
+    };
 
     let mut out = String::new();
     let base_dir = path.parent().unwrap_or_else(|| std::path::Path::new("."));
+    // 当前所在的 `.lib` 段定义 (段名); 只有 `section` 段内的行会被读入
+    let mut current_section: Option<String> = None;
+    let mut section_found = false;
 
-    for line in content.lines() {
+    for (index, line) in content.lines().enumerate() {
         let trimmed = line.trim();
-        if trimmed.to_ascii_lowercase().starts_with(".include") {
-            let include_path = trimmed
-                .split_whitespace()
-                .nth(1)
-                .unwrap_or("")
-                .trim_matches('"');
-            if include_path.is_empty() {
-                errors.push(ParseError {
-                    line: 0,
-                    message: format!("include 语句缺少路径: {}", path.display()),
-                });
+        let lib = strip_keyword(trimmed, &[".lib"]).map(parse_lib_args);
+        if let Some((name, None)) = &lib {
+            current_section = Some(name.to_string());
+            section_found |= section.is_some_and(|wanted| name.eq_ignore_ascii_case(wanted));
+            continue;
+        }
+        if strip_keyword(trimmed, &[".endl"]).is_some() {
+            current_section = None;
+            continue;
+        }
+        let wanted = match (section, &current_section) {
+            (Some(wanted), Some(current)) => current.eq_ignore_ascii_case(wanted),
+            (None, None) => true,
+            _ => false,
+        };
+        if !wanted {
+            continue;
+        }
+        let (include_path, nested_section) = match (lib, strip_keyword(trimmed, &[".include", ".inc"])) {
+            (Some((file, Some(lib_section))), _) => {
+                // 段内对其他段的引用 (如公共模型段) 保持原样, 只替换网表中选段的语句
+                let lib_section = match (&state.lib_section, section) {
+                    (Some(wanted), None) => wanted.clone(),
+                    _ => lib_section.to_string(),
+                };
+                (file, Some(lib_section))
+            }
+            (_, Some(rest)) => (parse_include_path(rest), None),
+            _ => {
+                record_string_params(trimmed, &mut state.string_params);
+                out.push_str(line);
+                out.push('\n');
+                state.origins.push((path.to_path_buf(), index + 1));
                 continue;
             }
-            let include_file = base_dir.join(include_path);
-            let nested = read_with_includes(&include_file, visited, errors);
-            out.push_str(&nested);
-            out.push('\n');
-        } else {
-            out.push_str(line);
-            out.push('\n');
+        };
+        // 路径中的 `{name}` 取之前定义的字符串参数
+        let string_params = &state.string_params;
+        let include_path = substitute_braced(include_path, |expr| {
+            match eval_value_scoped(string_params, string_params, string_params, expr)? {
+                ExprValue::Str(text) => Some(text),
+                ExprValue::Number(_) => None,
+            }
+        });
+        let include_path = include_path.as_str();
+        if include_path.is_empty() {
+            state.errors.push(
+                Diagnostic::error(codes::INCLUDE_MISSING_PATH, index + 1, "include statement has no path")
+                    .with_file(path)
+                    .with_source_line(line),
+            );
+            continue;
         }
+        let include_file = resolve_include_path(base_dir, include_path, &state.search_dirs);
+        state.chain.push(IncludeFrame {
+            path: path.to_path_buf(),
+            section: section.map(str::to_string),
+            line: index + 1,
+        });
+        let nested_format = NetlistFormat::from_extension(&include_file).unwrap_or(format);
+        let nested = read_with_includes(&include_file, nested_format, nested_section.as_deref(), state);
+        state.chain.pop();
+        out.push_str(&nested);
+        out.push('\n');
+        state.origins.push((path.to_path_buf(), index + 1));
     }
 
+    if let Some(section) = section.filter(|_| !section_found) {
+        let error = include_diagnostic(
+            codes::LIB_SECTION_NOT_FOUND,
+            &state.chain,
+            format!("library section '{}' not found in {}", section, path.display()),
+        );
+        state.errors.push(error);
+    }
     out
 }
 
-fn build_param_table(statements: &[Stmt]) -> std::collections::HashMap<String, String> {
-    let mut params = std::collections::HashMap::new();
+/// 记录 `.param` 行中取值为字符串的参数, 供后续 include 路径替换
+fn record_string_params(line: &str, string_params: &mut std::collections::HashMap<String, String>) {
+    let mut tokens = line.split_whitespace();
+    if !tokens.next().is_some_and(|first| first.eq_ignore_ascii_case(".param")) {
+        return;
+    }
+    let tokens: Vec<&str> = tokens.collect();
+    let (_, params) = split_args_params(&tokens);
+    for param in params {
+        let value = eval_param_value(string_params, string_params, string_params, &param.value);
+        if string_literal(&value).is_some() {
+            string_params.insert(param.key.to_ascii_lowercase(), value);
+        }
+    }
+}
+
+/// include 相关错误定位到触发它的 `.include` 语句; 顶层文件本身出错时没有位置
+fn include_diagnostic(code: &'static str, chain: &[IncludeFrame], message: String) -> Diagnostic {
+    match chain.last() {
+        Some(frame) => Diagnostic::error(code, frame.line, message).with_file(&frame.path),
+        None => Diagnostic::error(code, 0, message),
+    }
+}
+
+/// 若行首关键字 (不区分大小写) 是 `keywords` 之一则返回关键字之后的部分
+fn strip_keyword<'a>(line: &'a str, keywords: &[&str]) -> Option<&'a str> {
+    let keyword_len = line
+        .find(|c: char| c.is_whitespace() || c == '"' || c == '\'')
+        .unwrap_or(line.len());
+    let keyword = line[..keyword_len].to_ascii_lowercase();
+    keywords
+        .contains(&keyword.as_str())
+        .then(|| &line[keyword_len..])
+}
+
+/// `.lib <file> <section>` 返回 (路径, Some(段名)); `.lib <section>` (段定义开始) 返回 (段名, None)
+fn parse_lib_args(rest: &str) -> (&str, Option<&str>) {
+    let rest = rest.trim();
+    let first = parse_include_path(rest);
+    let after = match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => rest[1..].find(quote).map_or("", |end| &rest[end + 2..]),
+        _ => &rest[first.len()..],
+    };
+    (first, after.split_whitespace().next())
+}
+
+/// 取出 include 路径: 引号内可以包含空格, 否则取第一个空白分隔的字段
+fn parse_include_path(rest: &str) -> &str {
+    let rest = rest.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = rest.strip_prefix(quote) {
+            return match inner.find(quote) {
+                Some(end) => &inner[..end],
+                None => inner,
+            };
+        }
+    }
+    rest.split_whitespace().next().unwrap_or("")
+}
+
+fn resolve_include_path(
+    base_dir: &std::path::Path,
+    include_path: &str,
+    search_dirs: &[std::path::PathBuf],
+) -> std::path::PathBuf {
+    let candidate = std::path::Path::new(include_path);
+    if candidate.is_absolute() {
+        return candidate.to_path_buf();
+    }
+    let local = base_dir.join(candidate);
+    if local.is_file() {
+        return local;
+    }
+    search_dirs
+        .iter()
+        .map(|dir| dir.join(candidate))
+        .find(|path| path.is_file())
+        .unwrap_or(local)
+}
+
+fn format_include_chain(chain: &[IncludeFrame], leaf: &std::path::Path) -> String {
+    let mut parts: Vec<String> = chain
+        .iter()
+        .map(|frame| format!("{}:{}", frame.path.display(), frame.line))
+        .collect();
+    parts.push(leaf.display().to_string());
+    parts.join(" -> ")
+}
+
+/// 顶层 `.param` 参数表。参数可以引用在其后定义的参数: 先按表达式中的
+/// 标识符建立依赖图, 再按拓扑序求值; 循环依赖报错, 环上的参数保持原始文本。
+/// 同名参数以最后一次定义为准。
+fn build_param_table(
+    statements: &[Stmt],
+    errors: &mut Vec<Diagnostic>,
+) -> std::collections::HashMap<String, String> {
+    struct ParamDef<'a> {
+        key: String,
+        value: &'a str,
+        line: usize,
+        raw: &'a str,
+    }
+
+    let mut defs: Vec<ParamDef> = Vec::new();
+    let mut index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
     for stmt in statements {
         if let Stmt::Control(ctrl) = stmt {
             if matches!(ctrl.kind, ControlKind::Param) {
                 for param in &ctrl.params {
-                    let key = param.key.to_ascii_lowercase();
-                    let value = eval_expression(&params, &param.value)
-                        .map(|v| v.to_string())
-                        .unwrap_or_else(|| param.value.clone());
-                    params.insert(key, value);
+                    let def = ParamDef {
+                        key: param.key.to_ascii_lowercase(),
+                        value: &param.value,
+                        line: ctrl.line,
+                        raw: &ctrl.raw,
+                    };
+                    match index.get(&def.key) {
+                        Some(&idx) => defs[idx] = def,
+                        None => {
+                            index.insert(def.key.clone(), defs.len());
+                            defs.push(def);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let deps: Vec<Vec<usize>> = defs
+        .iter()
+        .map(|def| {
+            let mut deps: Vec<usize> = expression_identifiers(def.value)
+                .iter()
+                .filter_map(|ident| index.get(ident).copied())
+                .collect();
+            deps.dedup();
+            deps
+        })
+        .collect();
+
+    // 深度优先后序即拓扑序; 遇到栈上的节点说明有环
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        New,
+        Active,
+        Done,
+    }
+    let mut marks = vec![Mark::New; defs.len()];
+    let mut order = Vec::new();
+    let mut in_cycle = vec![false; defs.len()];
+    for root in 0..defs.len() {
+        if marks[root] != Mark::New {
+            continue;
+        }
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        marks[root] = Mark::Active;
+        while let Some(&mut (node, ref mut next)) = stack.last_mut() {
+            if let Some(&dep) = deps[node].get(*next) {
+                *next += 1;
+                match marks[dep] {
+                    Mark::New => {
+                        marks[dep] = Mark::Active;
+                        stack.push((dep, 0));
+                    }
+                    Mark::Active => {
+                        let start = stack.iter().position(|&(n, _)| n == dep).unwrap_or(0);
+                        let cycle: Vec<usize> = stack[start..].iter().map(|&(n, _)| n).collect();
+                        for &n in &cycle {
+                            in_cycle[n] = true;
+                        }
+                        let mut names: Vec<&str> = cycle.iter().map(|&n| defs[n].key.as_str()).collect();
+                        names.push(defs[dep].key.as_str());
+                        let first = &defs[dep];
+                        errors.push(
+                            Diagnostic::error(
+                                codes::PARAM_CYCLE,
+                                first.line,
+                                format!("parameter dependency cycle: {}", names.join(" -> ")),
+                            )
+                            .with_source_line(first.raw),
+                        );
+                    }
+                    Mark::Done => {}
                 }
+            } else {
+                marks[node] = Mark::Done;
+                order.push(node);
+                stack.pop();
             }
         }
     }
+
+    let mut params = std::collections::HashMap::new();
+    for idx in order {
+        let def = &defs[idx];
+        let value = if in_cycle[idx] {
+            def.value.to_string()
+        } else {
+            eval_param_value(&params, &params, &params, def.value)
+        };
+        params.insert(def.key.clone(), value);
+    }
     params
 }
 
+/// 表达式中引用的标识符 (小写, 不含函数名)
+fn expression_identifiers(expr: &str) -> Vec<String> {
+    let tokens = tokenize_expr(strip_param_delimiters(expr));
+    tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, token)| match token {
+            ExprToken::Ident(name) if !matches!(tokens.get(idx + 1), Some(ExprToken::LParen)) => {
+                Some(name.to_ascii_lowercase())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 fn build_local_param_table(
     def: &SubcktDef,
     instance: &DeviceStmt,
@@ -1140,23 +3930,17 @@ fn build_local_param_table(
     let mut params = std::collections::HashMap::new();
     for param in &def.params {
         let key = param.key.to_ascii_lowercase();
-        let value = eval_expression_scoped(&params, parent, global, &param.value)
-            .map(|v| v.to_string())
-            .unwrap_or_else(|| param.value.clone());
+        let value = eval_param_value(&params, parent, global, &param.value);
         params.insert(key, value);
     }
     for param in body_params {
         let key = param.key.to_ascii_lowercase();
-        let value = eval_expression_scoped(&params, parent, global, &param.value)
-            .map(|v| v.to_string())
-            .unwrap_or_else(|| param.value.clone());
+        let value = eval_param_value(&params, parent, global, &param.value);
         params.insert(key, value);
     }
     for param in &instance.params {
         let key = param.key.to_ascii_lowercase();
-        let value = eval_expression_scoped(&params, parent, global, &param.value)
-            .map(|v| v.to_string())
-            .unwrap_or_else(|| param.value.clone());
+        let value = eval_param_value(&params, parent, global, &param.value);
         params.insert(key, value);
     }
     params
@@ -1167,23 +3951,72 @@ fn apply_params_to_device_scoped(
     local: &std::collections::HashMap<String, String>,
     device: &mut DeviceStmt,
 ) {
+    // 行为源 / 非线性电容、电感表达式中的参数按标识符替换, V()/I() 引用保持不变
+    for param in &mut device.params {
+        if is_expression_param(&device.kind, &param.key) {
+            param.value = crate::behavioral::rewrite_references(
+                &param.value,
+                |ident| {
+                    let key = ident.to_ascii_lowercase();
+                    local
+                        .get(&key)
+                        .or_else(|| global.get(&key))
+                        .map(|value| format!("({})", value))
+                },
+                |node| node.to_string(),
+                |branch| branch.to_string(),
+            );
+        }
+    }
+    if matches!(device.kind, DeviceKind::B) {
+        return;
+    }
     if let Some(value) = device.value.clone() {
         if let Some(replaced) = resolve_param_scoped(local, global, &value) {
             device.value = Some(replaced);
         }
     }
     if let Some(model) = device.model.clone() {
-        if let Some(replaced) = resolve_param_scoped(local, global, &model) {
-            device.model = Some(replaced);
-        }
+        device.model = Some(resolve_name_scoped(local, global, &model));
     }
     for param in &mut device.params {
+        // Touchstone 文件路径与光端口节点名不是表达式
+        if is_expression_param(&device.kind, &param.key)
+            || matches!(device.kind, DeviceKind::SParam) && param.key == "file"
+            || is_node_param(&device.kind, &param.key)
+        {
+            continue;
+        }
         if let Some(replaced) = resolve_param_scoped(local, global, &param.value) {
             param.value = replaced;
         }
     }
 }
 
+/// 参数值是否为引用 V()/I() 的表达式: 行为源的 V=/I=, 非线性电容的 C=/Q=, 非线性电感的 L=/FLUX=
+/// 取值为节点名的参数, 按展开后追加到端口之后的顺序: 光电二极管的光输入 light=、
+/// LED 的光输出 lightout=, 热敏电阻的温度节点 tnode=
+fn node_param_keys(kind: &DeviceKind) -> &'static [&'static str] {
+    match kind {
+        DeviceKind::D => &["light", "lightout"],
+        DeviceKind::R => &["tnode"],
+        _ => &[],
+    }
+}
+
+pub(crate) fn is_node_param(kind: &DeviceKind, key: &str) -> bool {
+    node_param_keys(kind).iter().any(|name| key.eq_ignore_ascii_case(name))
+}
+
+fn is_expression_param(kind: &DeviceKind, key: &str) -> bool {
+    match kind {
+        DeviceKind::B => true,
+        DeviceKind::C => key.eq_ignore_ascii_case("c") || key.eq_ignore_ascii_case("q"),
+        DeviceKind::L => key.eq_ignore_ascii_case("l") || key.eq_ignore_ascii_case("flux"),
+        _ => false,
+    }
+}
+
 fn resolve_param_scoped(
     local: &std::collections::HashMap<String, String>,
     global: &std::collections::HashMap<String, String>,
@@ -1197,7 +4030,56 @@ fn resolve_param_scoped(
         .or_else(|| eval_expression_scoped(local, &std::collections::HashMap::new(), global, token).map(|v| v.to_string()))
 }
 
-fn extract_subckts(statements: &[Stmt]) -> (Vec<Stmt>, Vec<SubcktDef>, Vec<ParseError>) {
+/// 模型参数中的参数引用按作用域求值; 本身是数值的保持原文
+fn resolve_model_param(
+    local: &std::collections::HashMap<String, String>,
+    global: &std::collections::HashMap<String, String>,
+    value: &str,
+) -> String {
+    if parse_number_with_suffix(value).is_some() || value.parse::<f64>().is_ok() {
+        return value.to_string();
+    }
+    resolve_param_scoped(local, global, value).unwrap_or_else(|| value.to_string())
+}
+
+/// 模型名中的参数替换: 整个名字是字符串参数时取其内容, `{expr}` 片段按表达式求值后拼接
+fn resolve_name_scoped(
+    local: &std::collections::HashMap<String, String>,
+    global: &std::collections::HashMap<String, String>,
+    name: &str,
+) -> String {
+    let key = name.to_ascii_lowercase();
+    if let Some(text) = local.get(&key).or_else(|| global.get(&key)).and_then(|v| string_literal(v)) {
+        return text.to_string();
+    }
+    substitute_braced(name, |expr| {
+        match eval_value_scoped(local, &std::collections::HashMap::new(), global, expr)? {
+            ExprValue::Number(value) => Some(value.to_string()),
+            ExprValue::Str(text) => Some(text),
+        }
+    })
+}
+
+/// 把文本中的 `{expr}` 片段替换为 `eval` 的结果; 无法求值的片段保持原样
+fn substitute_braced(text: &str, eval: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|idx| open + idx) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        match eval(&rest[open + 1..close]) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[open..=close]),
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+pub(crate) fn extract_subckts(statements: &[Stmt]) -> (Vec<Stmt>, Vec<SubcktDef>, Vec<Diagnostic>) {
     let mut top_level = Vec::new();
     let mut subckts = Vec::new();
     let mut errors = Vec::new();
@@ -1214,14 +4096,23 @@ fn extract_subckts(statements: &[Stmt]) -> (Vec<Stmt>, Vec<SubcktDef>, Vec<Parse
                 let mut body = Vec::new();
                 let mut found_ends = false;
 
+                // 嵌套的 .subckt 定义整体留在 body 中, 由展开时再提取
+                let mut depth = 0usize;
                 while idx < statements.len() {
                     match &statements[idx] {
-                        Stmt::Control(end_ctrl) if matches!(end_ctrl.kind, ControlKind::Ends) => {
+                        Stmt::Control(end_ctrl) if matches!(end_ctrl.kind, ControlKind::Ends) && depth == 0 => {
                             found_ends = true;
                             idx += 1;
                             break;
                         }
                         stmt => {
+                            if let Stmt::Control(ctrl) = stmt {
+                                match ctrl.kind {
+                                    ControlKind::Subckt => depth += 1,
+                                    ControlKind::Ends => depth -= 1,
+                                    _ => {}
+                                }
+                            }
                             body.push(stmt.clone());
                             idx += 1;
                         }
@@ -1229,10 +4120,11 @@ fn extract_subckts(statements: &[Stmt]) -> (Vec<Stmt>, Vec<SubcktDef>, Vec<Parse
                 }
 
                 if !found_ends {
-                    errors.push(ParseError {
+                    errors.push(Diagnostic::error(
+                        codes::SUBCKT_MISSING_ENDS,
                         line,
-                        message: format!("subckt {} 缺少 .ends", name),
-                    });
+                        format!(".subckt {} is missing .ends", name),
+                    ));
                 }
 
                 subckts.push(SubcktDef {
@@ -1275,6 +4167,97 @@ fn collect_params_from_body(body: &[Stmt]) -> Vec<Param> {
     params
 }
 
+/// 展开 `<m:n>` 总线记法: `d<3:0>` -> `d<3> d<2> d<1> d<0>`; 不含总线的名字原样返回
+fn expand_bus(name: &str) -> Vec<String> {
+    let range = name.find('<').and_then(|open| {
+        let close = open + name[open..].find('>')?;
+        let (from, to) = name[open + 1..close].split_once(':')?;
+        let from: i64 = from.trim().parse().ok()?;
+        let to: i64 = to.trim().parse().ok()?;
+        Some((open, close, from, to))
+    });
+    let Some((open, close, from, to)) = range else {
+        return vec![name.to_string()];
+    };
+    let (prefix, suffix) = (&name[..open], &name[close + 1..]);
+    let bits: Vec<i64> = if from <= to {
+        (from..=to).collect()
+    } else {
+        (to..=from).rev().collect()
+    };
+    bits.into_iter()
+        .flat_map(|bit| expand_bus(&format!("{}<{}>{}", prefix, bit, suffix)))
+        .collect()
+}
+
+/// 展开总线实例与节点。`Xbit<0:7> d<7:0> clk dff` 产生 8 个实例, 位宽等于实例数
+/// (或其整数倍) 的节点按位 (或按段) 分配, 单个节点由全部实例共享; 标量实例上的总线节点
+/// 与 `.subckt` 端口中的总线按位展开为多个节点
+fn expand_bus_statements(statements: &[Stmt], errors: &mut Vec<Diagnostic>) -> Vec<Stmt> {
+    let mut out = Vec::with_capacity(statements.len());
+    for stmt in statements {
+        match stmt {
+            Stmt::Control(ctrl) if matches!(ctrl.kind, ControlKind::Subckt) => {
+                let mut ctrl = ctrl.clone();
+                ctrl.subckt_ports = ctrl.subckt_ports.iter().flat_map(|port| expand_bus(port)).collect();
+                out.push(Stmt::Control(ctrl));
+            }
+            Stmt::Device(device) => {
+                let names = expand_bus(&device.name);
+                let buses: Vec<Vec<String>> = device.nodes.iter().map(|node| expand_bus(node)).collect();
+                if names.len() == 1 {
+                    let mut device = device.clone();
+                    device.nodes = buses.into_iter().flatten().collect();
+                    out.push(Stmt::Device(device));
+                    continue;
+                }
+                let count = names.len();
+                if let Some((node, bits)) = device
+                    .nodes
+                    .iter()
+                    .zip(&buses)
+                    .find(|(_, bits)| bits.len() > 1 && bits.len() % count != 0)
+                {
+                    errors.push(
+                        Diagnostic::error(
+                            codes::NODE_COUNT,
+                            device.line,
+                            format!(
+                                "bus '{}' has {} bits, which does not match {} instances of {}",
+                                node,
+                                bits.len(),
+                                count,
+                                device.name
+                            ),
+                        )
+                        .with_source_line(device.raw.clone()),
+                    );
+                    out.push(stmt.clone());
+                    continue;
+                }
+                for (idx, name) in names.into_iter().enumerate() {
+                    let mut bit = device.clone();
+                    bit.name = name;
+                    bit.nodes = buses
+                        .iter()
+                        .flat_map(|bits| {
+                            if bits.len() == 1 {
+                                bits.clone()
+                            } else {
+                                let width = bits.len() / count;
+                                bits[idx * width..(idx + 1) * width].to_vec()
+                            }
+                        })
+                        .collect();
+                    out.push(Stmt::Device(bit));
+                }
+            }
+            _ => out.push(stmt.clone()),
+        }
+    }
+    out
+}
+
 fn map_subckt_node(
     instance: &DeviceStmt,
     port_map: &std::collections::HashMap<String, String>,
@@ -1294,6 +4277,7 @@ fn extract_control_name(kind: &DeviceKind, args: &[String]) -> Option<String> {
             Some(token) => Some(token.clone()),
             None => None,
         },
+        DeviceKind::W if args.len() >= 4 => Some(args[2].clone()),
         _ => None,
     }
 }
@@ -1301,7 +4285,7 @@ fn extract_control_name(kind: &DeviceKind, args: &[String]) -> Option<String> {
 /// Extract AC magnitude and phase from V/I source arguments.
 /// Syntax: `V1 in 0 DC 1 AC <mag> [<phase>]`
 fn extract_ac_params(kind: &DeviceKind, args: &[String]) -> (Option<f64>, Option<f64>) {
-    if !matches!(kind, DeviceKind::V | DeviceKind::I) {
+    if !matches!(kind, DeviceKind::V | DeviceKind::I | DeviceKind::P) {
         return (None, None);
     }
 
@@ -1324,6 +4308,23 @@ fn extract_ac_params(kind: &DeviceKind, args: &[String]) -> (Option<f64>, Option
     (mag, phase)
 }
 
+/// `PULSE(...)`、`PWL(...)`、`TRNOISE(na nt nalpha namp)` 等波形的参数列表
+/// (去掉关键字与括号), 括号内可跨多个字段
+fn extract_waveform_args(extras: &[String], keyword: &str) -> Option<String> {
+    let start = extras
+        .iter()
+        .position(|token| token.to_ascii_uppercase().starts_with(keyword))?;
+    let mut fields = Vec::new();
+    for (idx, token) in extras[start..].iter().enumerate() {
+        let token = if idx == 0 { &token[keyword.len()..] } else { token.as_str() };
+        fields.push(token.trim_matches(|c| c == '(' || c == ')').to_string());
+        if token.ends_with(')') {
+            break;
+        }
+    }
+    Some(fields.join(" ").trim().to_string())
+}
+
 fn has_waveform(extras: &[String]) -> bool {
     extras.iter().any(|token| is_waveform_token(token))
 }
@@ -1332,7 +4333,7 @@ fn waveform_keyword(extras: &[String]) -> Option<String> {
     let token = extras.first()?;
     let upper = token.to_ascii_uppercase();
     match upper.as_str() {
-        "AC" | "SIN" | "PULSE" | "EXP" | "SFFM" | "PWL" => Some(upper),
+        "AC" | "SIN" | "PULSE" | "EXP" | "SFFM" | "PWL" | "TRNOISE" => Some(upper),
         _ => None,
     }
 }
@@ -1403,20 +4404,26 @@ fn parse_poly(tokens: &[String]) -> (Option<PolySpec>, Vec<String>) {
     (None, tokens.to_vec())
 }
 
-fn eval_expression(
-    params: &std::collections::HashMap<String, String>,
+fn eval_expression_scoped(
+    local: &std::collections::HashMap<String, String>,
+    parent: &std::collections::HashMap<String, String>,
+    global: &std::collections::HashMap<String, String>,
     expr: &str,
 ) -> Option<f64> {
-    eval_expression_scoped(params, params, params, expr)
+    match eval_value_scoped(local, parent, global, expr)? {
+        ExprValue::Number(value) => Some(value),
+        ExprValue::Str(_) => None,
+    }
 }
 
-fn eval_expression_scoped(
+/// 表达式求值, 结果可以是数值或字符串
+fn eval_value_scoped(
     local: &std::collections::HashMap<String, String>,
     parent: &std::collections::HashMap<String, String>,
     global: &std::collections::HashMap<String, String>,
     expr: &str,
-) -> Option<f64> {
-    let tokens = tokenize_expr(expr);
+) -> Option<ExprValue> {
+    let tokens = tokenize_expr(strip_param_delimiters(expr));
     if tokens.is_empty() {
         return None;
     }
@@ -1424,17 +4431,172 @@ fn eval_expression_scoped(
     eval_rpn(&rpn, local, parent, global)
 }
 
+/// 依赖仿真结果的输出参数: `.csparam` 的全部参数, 以及表达式引用 `v(node)` 或 `i(dev)` 的顶层 `.param`
+pub fn output_params(ast: &NetlistAst) -> Vec<Param> {
+    let (top_level, _, _) = extract_subckts(&ast.statements);
+    let mut params = Vec::new();
+    for stmt in &top_level {
+        let Stmt::Control(ctrl) = stmt else { continue };
+        match ctrl.kind {
+            ControlKind::Csparam => params.extend(ctrl.params.iter().cloned()),
+            ControlKind::Param => params.extend(
+                ctrl.params
+                    .iter()
+                    .filter(|param| references_solution(&param.value))
+                    .cloned(),
+            ),
+            _ => {}
+        }
+    }
+    params
+}
+
+/// 用一次仿真的节点电压与支路电流计算输出参数, 按定义顺序求值 (可引用前面的输出参数和
+/// `.param`); 无法求值的参数被跳过
+pub fn evaluate_output_params(
+    ast: &NetlistAst,
+    voltage: impl Fn(&str) -> Option<f64>,
+    current: impl Fn(&str) -> Option<f64>,
+) -> Vec<(String, f64)> {
+    let defs = output_params(ast);
+    if defs.is_empty() {
+        return Vec::new();
+    }
+    let (top_level, _, _) = extract_subckts(&ast.statements);
+    let mut table = build_param_table(&top_level, &mut Vec::new());
+    let mut values = Vec::new();
+    for def in defs {
+        let tokens = tokenize_expr(strip_param_delimiters(&def.value));
+        let value = substitute_solution(tokens, &voltage, &current)
+            .and_then(to_rpn)
+            .and_then(|rpn| eval_rpn(&rpn, &table, &table, &table));
+        if let Some(ExprValue::Number(value)) = value {
+            let key = def.key.to_ascii_lowercase();
+            table.insert(key.clone(), value.to_string());
+            values.push((key, value));
+        }
+    }
+    values
+}
+
+/// 表达式中是否出现 `v(...)` 或 `i(...)` 调用
+fn references_solution(expr: &str) -> bool {
+    let tokens = tokenize_expr(strip_param_delimiters(expr));
+    tokens.windows(2).any(|pair| {
+        matches!(&pair[0], ExprToken::Ident(name) if name.eq_ignore_ascii_case("v") || name.eq_ignore_ascii_case("i"))
+            && matches!(pair[1], ExprToken::LParen)
+    })
+}
+
+/// 把 `v(a)` / `v(a,b)` 替换为电压值、`i(dev)` 替换为支路电流; 节点或器件不存在时返回 None
+fn substitute_solution(
+    tokens: Vec<ExprToken>,
+    voltage: &impl Fn(&str) -> Option<f64>,
+    current: &impl Fn(&str) -> Option<f64>,
+) -> Option<Vec<ExprToken>> {
+    let node_name = |token: &ExprToken| match token {
+        ExprToken::Ident(name) => Some(name.clone()),
+        // 数字节点名 (`0`、`1`) 被切成数值
+        ExprToken::Number(value) => Some(value.to_string()),
+        _ => None,
+    };
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let is_current = matches!(&tokens[idx], ExprToken::Ident(name) if name.eq_ignore_ascii_case("i"))
+            && matches!(tokens.get(idx + 1), Some(ExprToken::LParen));
+        if is_current {
+            let ExprToken::Ident(device) = tokens.get(idx + 2)? else { return None };
+            if !matches!(tokens.get(idx + 3)?, ExprToken::RParen) {
+                return None;
+            }
+            out.push(ExprToken::Number(current(device)?));
+            idx += 4;
+            continue;
+        }
+        let is_call = matches!(&tokens[idx], ExprToken::Ident(name) if name.eq_ignore_ascii_case("v"))
+            && matches!(tokens.get(idx + 1), Some(ExprToken::LParen));
+        if !is_call {
+            out.push(tokens[idx].clone());
+            idx += 1;
+            continue;
+        }
+        let pos = node_name(tokens.get(idx + 2)?)?;
+        let (neg, end) = match tokens.get(idx + 3)? {
+            ExprToken::RParen => (None, idx + 4),
+            ExprToken::Comma => {
+                let neg = node_name(tokens.get(idx + 4)?)?;
+                if !matches!(tokens.get(idx + 5)?, ExprToken::RParen) {
+                    return None;
+                }
+                (Some(neg), idx + 6)
+            }
+            _ => return None,
+        };
+        let mut value = voltage(&pos)?;
+        if let Some(neg) = neg {
+            value -= voltage(&neg)?;
+        }
+        out.push(ExprToken::Number(value));
+        idx = end;
+    }
+    Some(out)
+}
+
+/// 去掉参数表达式外层的 `{}` 或单引号
+fn strip_param_delimiters(expr: &str) -> &str {
+    let expr = expr.trim();
+    expr.strip_prefix('{')
+        .and_then(|e| e.strip_suffix('}'))
+        .or_else(|| expr.strip_prefix('\'').and_then(|e| e.strip_suffix('\'')))
+        .unwrap_or(expr)
+}
+
+/// 参数表中的取值: 数值参数存为数值文本, 字符串参数保留双引号 (`"ff"`)
+fn eval_param_value(
+    local: &std::collections::HashMap<String, String>,
+    parent: &std::collections::HashMap<String, String>,
+    global: &std::collections::HashMap<String, String>,
+    expr: &str,
+) -> String {
+    match eval_value_scoped(local, parent, global, expr) {
+        Some(ExprValue::Number(value)) => value.to_string(),
+        Some(ExprValue::Str(text)) => format!("\"{}\"", text),
+        None => expr.to_string(),
+    }
+}
+
+/// 双引号字符串字面量的内容
+fn string_literal(value: &str) -> Option<&str> {
+    value.strip_prefix('"')?.strip_suffix('"')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ExprValue {
+    Number(f64),
+    Str(String),
+}
+
 #[derive(Debug, Clone)]
 enum ExprToken {
     Number(f64),
+    Str(String),
     Ident(String),
-    Op(char),
+    Op(&'static str),
     Comma,
     LParen,
     RParen,
     Func { name: String, argc: usize },
 }
 
+/// `1e` / `2.5E` 这类以指数字母结尾的数字前缀, 其后的 `+` / `-` 属于指数
+fn is_exponent_prefix(buf: &str) -> bool {
+    let Some(mantissa) = buf.strip_suffix(['e', 'E']) else {
+        return false;
+    };
+    !mantissa.is_empty() && mantissa.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
 fn tokenize_expr(expr: &str) -> Vec<ExprToken> {
     let mut tokens = Vec::new();
     let mut buf = String::new();
@@ -1458,9 +4620,37 @@ fn tokenize_expr(expr: &str) -> Vec<ExprToken> {
             continue;
         }
         match ch {
+            // 指数符号: 1e-3 / 2.5E+6
+            '+' | '-' if is_exponent_prefix(&buf) => buf.push(ch),
             '+' | '-' | '*' | '/' | '^' => {
                 push_buf(&mut buf, &mut tokens);
-                tokens.push(ExprToken::Op(ch));
+                tokens.push(ExprToken::Op(match ch {
+                    '+' => "+",
+                    '-' => "-",
+                    '*' => "*",
+                    '/' => "/",
+                    _ => "^",
+                }));
+            }
+            '=' | '!' | '<' | '>' => {
+                push_buf(&mut buf, &mut tokens);
+                let followed_by_eq = chars.next_if_eq(&'=').is_some();
+                let op = match (ch, followed_by_eq) {
+                    ('=', _) => "==",
+                    ('!', true) => "!=",
+                    ('<', true) => "<=",
+                    ('>', true) => ">=",
+                    ('<', false) => "<",
+                    ('>', false) => ">",
+                    // 单独的 `!` 不是合法运算符, 让求值失败
+                    _ => "!",
+                };
+                tokens.push(ExprToken::Op(op));
+            }
+            '"' => {
+                push_buf(&mut buf, &mut tokens);
+                let text: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                tokens.push(ExprToken::Str(text));
             }
             ',' => {
                 push_buf(&mut buf, &mut tokens);
@@ -1481,31 +4671,61 @@ fn tokenize_expr(expr: &str) -> Vec<ExprToken> {
     tokens
 }
 
-fn parse_number_with_suffix(token: &str) -> Option<f64> {
-    let lower = token.to_ascii_lowercase();
-    let trimmed = lower.trim();
-    let (num_str, multiplier) = if trimmed.ends_with("meg") {
-        (&trimmed[..trimmed.len() - 3], 1e6)
-    } else {
-        let (value_part, suffix) = trimmed.split_at(trimmed.len().saturating_sub(1));
-        match suffix {
-            "f" => (value_part, 1e-15),
-            "p" => (value_part, 1e-12),
-            "n" => (value_part, 1e-9),
-            "u" => (value_part, 1e-6),
-            "m" => (value_part, 1e-3),
-            "k" => (value_part, 1e3),
-            "g" => (value_part, 1e9),
-            "t" => (value_part, 1e12),
-            _ => (trimmed, 1.0),
+/// 解析带比例后缀的数值: `1k`、`2.5meg`、`10mil`、`1e-9`。
+/// 比例后缀之后的单位字母被忽略 (`100nF`、`4.7kOhm`、`5V`), 与 SPICE 一致
+pub fn parse_number_with_suffix(token: &str) -> Option<f64> {
+    let lower = token.trim().to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let mut end = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        end += 1;
+    }
+    let mantissa_start = end;
+    while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'.') {
+        end += 1;
+    }
+    if !bytes[mantissa_start..end].iter().any(u8::is_ascii_digit) {
+        return None;
+    }
+    // 指数部分必须带数字, 否则 `e` 视为单位字母
+    if end < bytes.len() && bytes[end] == b'e' {
+        let mut exp_end = end + 1;
+        if matches!(bytes.get(exp_end), Some(b'+' | b'-')) {
+            exp_end += 1;
         }
-    };
-
-    if let Ok(num) = num_str.parse::<f64>() {
-        Some(num * multiplier)
+        let digits_start = exp_end;
+        while exp_end < bytes.len() && bytes[exp_end].is_ascii_digit() {
+            exp_end += 1;
+        }
+        if exp_end > digits_start {
+            end = exp_end;
+        }
+    }
+    let num: f64 = lower[..end].parse().ok()?;
+    let rest = &lower[end..];
+    let (multiplier, unit) = if let Some(unit) = rest.strip_prefix("meg") {
+        (1e6, unit)
+    } else if let Some(unit) = rest.strip_prefix("mil") {
+        (25.4e-6, unit)
     } else {
-        None
+        let multiplier = match rest.chars().next() {
+            Some('f') => 1e-15,
+            Some('p') => 1e-12,
+            Some('n') => 1e-9,
+            Some('u') => 1e-6,
+            Some('m') => 1e-3,
+            Some('k') => 1e3,
+            Some('g') => 1e9,
+            Some('t') => 1e12,
+            _ => 1.0,
+        };
+        let unit = if multiplier == 1.0 { rest } else { &rest[1..] };
+        (multiplier, unit)
+    };
+    if !unit.chars().all(|ch| ch.is_ascii_alphabetic()) {
+        return None;
     }
+    Some(num * multiplier)
 }
 
 fn to_rpn(tokens: Vec<ExprToken>) -> Option<Vec<ExprToken>> {
@@ -1519,7 +4739,7 @@ fn to_rpn(tokens: Vec<ExprToken>) -> Option<Vec<ExprToken>> {
         let token = tokens[idx].clone();
         let next = tokens.get(idx + 1);
         match &token {
-            ExprToken::Number(_) => output.push(token.clone()),
+            ExprToken::Number(_) | ExprToken::Str(_) => output.push(token.clone()),
             ExprToken::Ident(name) => {
                 if matches!(next, Some(ExprToken::LParen)) {
                     ops.push(ExprToken::Func { name: name.clone(), argc: 0 });
@@ -1528,18 +4748,18 @@ fn to_rpn(tokens: Vec<ExprToken>) -> Option<Vec<ExprToken>> {
                 }
             }
             ExprToken::Op(op) => {
-                if !prev_was_value && *op == '-' {
+                if !prev_was_value && *op == "-" {
                     output.push(ExprToken::Number(0.0));
                 }
                 while let Some(top) = ops.last() {
                     match top {
-                        ExprToken::Op(top_op) if precedence(*top_op) >= precedence(*op) => {
+                        ExprToken::Op(top_op) if precedence(top_op) >= precedence(op) => {
                             output.push(ops.pop().unwrap());
                         }
                         _ => break,
                     }
                 }
-                ops.push(ExprToken::Op(*op));
+                ops.push(ExprToken::Op(op));
             }
             ExprToken::Comma => {
                 while let Some(top) = ops.last() {
@@ -1579,7 +4799,7 @@ fn to_rpn(tokens: Vec<ExprToken>) -> Option<Vec<ExprToken>> {
         }
         prev_was_value = matches!(
             token,
-            ExprToken::Number(_) | ExprToken::Ident(_) | ExprToken::RParen
+            ExprToken::Number(_) | ExprToken::Str(_) | ExprToken::Ident(_) | ExprToken::RParen
         );
         idx += 1;
     }
@@ -1594,11 +4814,12 @@ fn to_rpn(tokens: Vec<ExprToken>) -> Option<Vec<ExprToken>> {
     Some(output)
 }
 
-fn precedence(op: char) -> u8 {
+fn precedence(op: &str) -> u8 {
     match op {
-        '+' | '-' => 1,
-        '*' | '/' => 2,
-        '^' => 3,
+        "==" | "!=" | "<" | ">" | "<=" | ">=" => 1,
+        "+" | "-" => 2,
+        "*" | "/" => 3,
+        "^" => 4,
         _ => 0,
     }
 }
@@ -1608,32 +4829,28 @@ fn eval_rpn(
     local: &std::collections::HashMap<String, String>,
     parent: &std::collections::HashMap<String, String>,
     global: &std::collections::HashMap<String, String>,
-) -> Option<f64> {
-    let mut stack: Vec<f64> = Vec::new();
+) -> Option<ExprValue> {
+    let mut stack: Vec<ExprValue> = Vec::new();
     for token in rpn {
         match token {
-            ExprToken::Number(num) => stack.push(*num),
+            ExprToken::Number(num) => stack.push(ExprValue::Number(*num)),
+            ExprToken::Str(text) => stack.push(ExprValue::Str(text.clone())),
             ExprToken::Ident(name) => {
                 let key = name.to_ascii_lowercase();
-                let value = local
+                let val = local
                     .get(&key)
                     .or_else(|| parent.get(&key))
-                    .or_else(|| global.get(&key))
-                    .and_then(|val| parse_number_with_suffix(val).or_else(|| val.parse().ok()))?;
+                    .or_else(|| global.get(&key))?;
+                let value = match string_literal(val) {
+                    Some(text) => ExprValue::Str(text.to_string()),
+                    None => ExprValue::Number(parse_number_with_suffix(val).or_else(|| val.parse().ok())?),
+                };
                 stack.push(value);
             }
             ExprToken::Op(op) => {
                 let b = stack.pop()?;
                 let a = stack.pop()?;
-                let value = match op {
-                    '+' => a + b,
-                    '-' => a - b,
-                    '*' => a * b,
-                    '/' => a / b,
-                    '^' => a.powf(b),
-                    _ => return None,
-                };
-                stack.push(value);
+                stack.push(apply_operator(op, a, b)?);
             }
             ExprToken::Func { name, argc } => {
                 let mut args = Vec::new();
@@ -1641,35 +4858,224 @@ fn eval_rpn(
                     args.push(stack.pop()?);
                 }
                 args.reverse();
-                let value = eval_function(name, &args)?;
+                let value = if name.eq_ignore_ascii_case("if") && args.len() == 3 {
+                    // 条件分支可以返回字符串
+                    let ExprValue::Number(cond) = args[0] else { return None };
+                    args.swap_remove(if cond != 0.0 { 1 } else { 2 })
+                } else {
+                    let numbers = args
+                        .iter()
+                        .map(|arg| match arg {
+                            ExprValue::Number(value) => Some(*value),
+                            ExprValue::Str(_) => None,
+                        })
+                        .collect::<Option<Vec<f64>>>()?;
+                    ExprValue::Number(eval_function(name, &numbers)?)
+                };
                 stack.push(value);
             }
             ExprToken::Comma | ExprToken::LParen | ExprToken::RParen => return None,
         }
     }
     if stack.len() == 1 {
-        Some(stack[0])
+        stack.pop()
     } else {
         None
     }
 }
 
+/// 二元运算; 字符串只支持相等比较 (大小写不敏感)
+fn apply_operator(op: &str, a: ExprValue, b: ExprValue) -> Option<ExprValue> {
+    let truth = |cond: bool| Some(ExprValue::Number(if cond { 1.0 } else { 0.0 }));
+    match (a, b) {
+        (ExprValue::Number(a), ExprValue::Number(b)) => match op {
+            "+" => Some(ExprValue::Number(a + b)),
+            "-" => Some(ExprValue::Number(a - b)),
+            "*" => Some(ExprValue::Number(a * b)),
+            "/" => Some(ExprValue::Number(a / b)),
+            "^" => Some(ExprValue::Number(a.powf(b))),
+            "==" => truth(a == b),
+            "!=" => truth(a != b),
+            "<" => truth(a < b),
+            ">" => truth(a > b),
+            "<=" => truth(a <= b),
+            ">=" => truth(a >= b),
+            _ => None,
+        },
+        (ExprValue::Str(a), ExprValue::Str(b)) => match op {
+            "==" => truth(a.eq_ignore_ascii_case(&b)),
+            "!=" => truth(!a.eq_ignore_ascii_case(&b)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn eval_function(name: &str, args: &[f64]) -> Option<f64> {
     match name.to_ascii_lowercase().as_str() {
         "max" if args.len() == 2 => Some(args[0].max(args[1])),
         "min" if args.len() == 2 => Some(args[0].min(args[1])),
         "abs" if args.len() == 1 => Some(args[0].abs()),
-        "if" if args.len() == 3 => {
-            if args[0] != 0.0 {
-                Some(args[1])
-            } else {
-                Some(args[2])
-            }
+        // 统计分布函数在 Monte Carlo 之外取标称值
+        _ => statistical_distribution(name, args).map(|_| args[0]),
+    }
+}
+
+/// 统计分布函数描述的随机量
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    /// 正态分布
+    Gauss { mean: f64, sigma: f64 },
+    /// [low, high] 上的均匀分布
+    Uniform { low: f64, high: f64 },
+}
+
+/// `gauss(nom, rvar[, sigma])`: 标准差 nom·rvar/sigma; `agauss(nom, avar[, sigma])`: 标准差
+/// avar/sigma; `unif(nom, rvar)`: nom·(1 ± rvar); `aunif(nom, avar)`: nom ± avar。sigma 缺省为 1
+fn statistical_distribution(name: &str, args: &[f64]) -> Option<Distribution> {
+    let sigma = || match args.len() {
+        2 => Some(1.0),
+        3 if args[2] > 0.0 => Some(args[2]),
+        _ => None,
+    };
+    let nominal = *args.first()?;
+    match name.to_ascii_lowercase().as_str() {
+        "gauss" => Some(Distribution::Gauss {
+            mean: nominal,
+            sigma: (nominal * args[1]).abs() / sigma()?,
+        }),
+        "agauss" => Some(Distribution::Gauss {
+            mean: nominal,
+            sigma: args[1].abs() / sigma()?,
+        }),
+        "unif" if args.len() == 2 => {
+            let half = (nominal * args[1]).abs();
+            Some(Distribution::Uniform { low: nominal - half, high: nominal + half })
+        }
+        "aunif" if args.len() == 2 => {
+            let half = args[1].abs();
+            Some(Distribution::Uniform { low: nominal - half, high: nominal + half })
         }
         _ => None,
     }
 }
 
+/// 调用统计分布函数的顶层 `.param` 名 (小写), 按定义顺序
+pub fn statistical_params(ast: &NetlistAst) -> Vec<String> {
+    let (top_level, _, _) = extract_subckts(&ast.statements);
+    let mut names: Vec<String> = Vec::new();
+    for stmt in &top_level {
+        let Stmt::Control(ctrl) = stmt else { continue };
+        if !matches!(ctrl.kind, ControlKind::Param) {
+            continue;
+        }
+        for param in &ctrl.params {
+            let key = param.key.to_ascii_lowercase();
+            let tokens = tokenize_expr(strip_param_delimiters(&param.value));
+            let calls_statistical = tokens.windows(2).any(|pair| {
+                matches!(&pair[0], ExprToken::Ident(name) if is_statistical_function(name))
+                    && matches!(pair[1], ExprToken::LParen)
+            });
+            if calls_statistical && !names.contains(&key) {
+                names.push(key);
+            }
+        }
+    }
+    names
+}
+
+fn is_statistical_function(name: &str) -> bool {
+    matches!(name.to_ascii_lowercase().as_str(), "gauss" | "agauss" | "unif" | "aunif")
+}
+
+/// 为 [`statistical_params`] 中的参数抽样: 表达式中的每次分布函数调用由 `draw` 给出一个样本,
+/// 其余部分照常求值, 后面的参数看到前面参数的抽样值。返回 (参数名, 抽样值), 无法求值的参数被跳过
+pub fn sample_statistical_params(ast: &NetlistAst, mut draw: impl FnMut(Distribution) -> f64) -> Vec<(String, f64)> {
+    let names = statistical_params(ast);
+    if names.is_empty() {
+        return Vec::new();
+    }
+    let (top_level, _, _) = extract_subckts(&ast.statements);
+    let mut table = build_param_table(&top_level, &mut Vec::new());
+    let mut samples = Vec::new();
+    for name in names {
+        // 同名参数以最后一次定义为准
+        let Some(expr) = top_level
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Control(ctrl) if matches!(ctrl.kind, ControlKind::Param) => Some(&ctrl.params),
+                _ => None,
+            })
+            .flatten()
+            .rev()
+            .find(|param| param.key.eq_ignore_ascii_case(&name))
+            .map(|param| param.value.clone())
+        else {
+            continue;
+        };
+        let tokens = tokenize_expr(strip_param_delimiters(&expr));
+        let value = substitute_statistical(&tokens, &table, &mut draw)
+            .and_then(to_rpn)
+            .and_then(|rpn| eval_rpn(&rpn, &table, &table, &table));
+        if let Some(ExprValue::Number(value)) = value {
+            table.insert(name.clone(), value.to_string());
+            samples.push((name, value));
+        }
+    }
+    samples
+}
+
+/// 把分布函数调用替换为 `draw` 的样本; 参数先按 `table` 求值 (可以嵌套分布函数)
+fn substitute_statistical(
+    tokens: &[ExprToken],
+    table: &std::collections::HashMap<String, String>,
+    draw: &mut impl FnMut(Distribution) -> f64,
+) -> Option<Vec<ExprToken>> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let is_call = matches!(&tokens[idx], ExprToken::Ident(name) if is_statistical_function(name))
+            && matches!(tokens.get(idx + 1), Some(ExprToken::LParen));
+        if !is_call {
+            out.push(tokens[idx].clone());
+            idx += 1;
+            continue;
+        }
+        let ExprToken::Ident(name) = &tokens[idx] else { return None };
+        // 找到配对的右括号, 按顶层逗号切分参数
+        let mut depth = 0;
+        let mut args: Vec<Vec<ExprToken>> = vec![Vec::new()];
+        let mut end = idx + 1;
+        loop {
+            end += 1;
+            match tokens.get(end)? {
+                ExprToken::LParen => depth += 1,
+                ExprToken::RParen if depth == 0 => break,
+                ExprToken::RParen => depth -= 1,
+                ExprToken::Comma if depth == 0 => {
+                    args.push(Vec::new());
+                    continue;
+                }
+                _ => {}
+            }
+            args.last_mut()?.push(tokens[end].clone());
+        }
+        let values = args
+            .iter()
+            .map(|arg| {
+                let arg = substitute_statistical(arg, table, draw)?;
+                match eval_rpn(&to_rpn(arg)?, table, table, table)? {
+                    ExprValue::Number(value) => Some(value),
+                    ExprValue::Str(_) => None,
+                }
+            })
+            .collect::<Option<Vec<f64>>>()?;
+        out.push(ExprToken::Number(draw(statistical_distribution(name, &values)?)));
+        idx = end + 1;
+    }
+    Some(out)
+}
+
 pub fn debug_dump_ast(ast: &NetlistAst) {
     println!(
         "netlist ast: title={:?} statements={} errors={}",
@@ -1696,18 +5102,85 @@ fn build_subckt_map(subckts: &[SubcktDef]) -> std::collections::HashMap<String,
     map
 }
 
+/// 子电路实例的 m= 叠加到内部器件上
+fn scale_device_multiplier(device: &mut DeviceStmt, multiplier: f64) {
+    if let Some(param) = device
+        .params
+        .iter_mut()
+        .find(|p| p.key.eq_ignore_ascii_case("m"))
+    {
+        let own = parse_number_with_suffix(&param.value).unwrap_or(1.0);
+        param.value = (own * multiplier).to_string();
+    } else {
+        device.params.push(Param {
+            key: "m".to_string(),
+            value: multiplier.to_string(),
+        });
+    }
+}
+
+/// 子电路实例的局部作用域
+struct SubcktScope {
+    /// 局部参数表 (子电路定义参数、body .param、实例参数)
+    params: std::collections::HashMap<String, String>,
+    /// 可见的局部模型: 原模型名 (小写) -> 作用域化后的模型名
+    models: std::collections::HashMap<String, String>,
+    /// 外层子电路实例累积的 m= 倍数
+    multiplier: f64,
+}
+
+/// 子电路展开过程中收集、在展开后交给 build_circuit 的信息
+#[derive(Default)]
+struct SubcktOutputs {
+    /// 作用域化后的子电路局部 .model
+    models: Vec<ControlStmt>,
+    /// 端口层次名 -> 外部节点名
+    node_aliases: Vec<(String, String)>,
+}
+
 fn expand_subckt_instance_recursive(
     instance: &DeviceStmt,
     def: &SubcktDef,
     subckts: &std::collections::HashMap<String, SubcktDef>,
-    local_params: &std::collections::HashMap<String, String>,
+    inherited: &SubcktScope,
     global_params: &std::collections::HashMap<String, String>,
-    errors: &mut Vec<ParseError>,
-    models: &mut Vec<ControlStmt>,
+    errors: &mut Vec<Diagnostic>,
+    outputs: &mut SubcktOutputs,
 ) -> Vec<DeviceStmt> {
     let (body, nested_subckts, nested_errors) = extract_subckts(&def.body);
     errors.extend(nested_errors);
+    let local_params = &inherited.params;
+    // 实例自身的 m= 已按作用域求值后进入局部参数表
+    let own_multiplier = if instance.params.iter().any(|p| p.key.eq_ignore_ascii_case("m")) {
+        local_params
+            .get("m")
+            .and_then(|v| parse_number_with_suffix(v))
+            .unwrap_or(1.0)
+    } else {
+        1.0
+    };
+    let multiplier = inherited.multiplier * own_multiplier;
+
+    // 子电路内的 .model 以 "X1.name" 作用域化, 实例引用需要同步改名;
+    // 外层子电路的局部模型对内层同样可见
+    let mut scope = inherited.models.clone();
+    for stmt in &body {
+        if let Stmt::Control(ctrl) = stmt {
+            if let (ControlKind::Model, Some(name)) = (&ctrl.kind, &ctrl.model_name) {
+                let scoped = format!("{}.{}", instance.name, name);
+                if let Some(base) = model_bin_base(name) {
+                    scope.insert(
+                        base.to_ascii_lowercase(),
+                        format!("{}.{}", instance.name, base),
+                    );
+                }
+                scope.insert(name.to_ascii_lowercase(), scoped);
+            }
+        }
+    }
     let mut nested_map = build_subckt_map(&nested_subckts);
+    // 定义在本子电路内部的子电路可以看到本层的参数
+    let lexical: std::collections::HashSet<String> = nested_map.keys().cloned().collect();
     for (name, def) in subckts {
         nested_map.entry(name.clone()).or_insert_with(|| def.clone());
     }
@@ -1716,6 +5189,9 @@ fn expand_subckt_instance_recursive(
     let mut port_map = std::collections::HashMap::new();
     for (port, node) in def.ports.iter().zip(instance.nodes.iter()) {
         port_map.insert(port.to_ascii_lowercase(), node.clone());
+        outputs
+            .node_aliases
+            .push((format!("{}.{}", instance.name, port), node.clone()));
     }
 
     let mut expanded = Vec::new();
@@ -1729,38 +5205,79 @@ fn expand_subckt_instance_recursive(
                     .iter()
                     .map(|node| map_subckt_node(instance, &port_map, node))
                     .collect();
+                if matches!(scoped.kind, DeviceKind::B | DeviceKind::C | DeviceKind::L) {
+                    for param in scoped.params.iter_mut().filter(|param| is_expression_param(&dev.kind, &param.key)) {
+                        param.value = crate::behavioral::rewrite_references(
+                            &param.value,
+                            |_| None,
+                            |node| map_subckt_node(instance, &port_map, node),
+                            |branch| format!("{}.{}", instance.name, branch),
+                        );
+                    }
+                }
+                for param in scoped.params.iter_mut().filter(|param| is_node_param(&dev.kind, &param.key)) {
+                    param.value = map_subckt_node(instance, &port_map, &param.value);
+                }
+                if !matches!(scoped.kind, DeviceKind::X) {
+                    if let Some(model) = &scoped.model {
+                        if let Some(mapped) = scope.get(&model.to_ascii_lowercase()) {
+                            scoped.model = Some(mapped.clone());
+                        }
+                    }
+                }
                 if matches!(scoped.kind, DeviceKind::X) {
                     if let Some(subckt_name) = scoped.model.as_deref() {
                         if let Some(child_def) = nested_map.get(subckt_name) {
                             let body_params = collect_params_from_body(&child_def.body);
-                            let child_params = build_local_param_table(
+                            let own_params = build_local_param_table(
                                 child_def,
                                 &scoped,
                                 &body_params,
                                 local_params,
                                 global_params,
                             );
+                            let child_params = if lexical.contains(subckt_name) {
+                                let mut params = local_params.clone();
+                                // 实例倍数只属于本层
+                                params.remove("m");
+                                params.extend(own_params);
+                                params
+                            } else {
+                                own_params
+                            };
+                            let child_scope = SubcktScope {
+                                params: child_params,
+                                models: scope.clone(),
+                                multiplier,
+                            };
                             let child_expanded = expand_subckt_instance_recursive(
                                 &scoped,
                                 child_def,
                                 &nested_map,
-                                &child_params,
+                                &child_scope,
                                 global_params,
                                 errors,
-                                models,
+                                outputs,
                             );
                             expanded.extend(child_expanded);
                             continue;
                         }
                     }
-                    errors.push(ParseError {
-                        line: scoped.line,
-                        message: format!("子电路未定义: {:?}", scoped.model),
-                    });
+                    errors.push(
+                        Diagnostic::error(
+                            codes::UNDEFINED_SUBCKT,
+                            scoped.line,
+                            format!("undefined subcircuit '{}'", scoped.model.as_deref().unwrap_or_default()),
+                        )
+                        .with_source_line(scoped.raw.clone()),
+                    );
                 }
 
                 let mut final_inst = scoped.clone();
                 apply_params_to_device_scoped(global_params, local_params, &mut final_inst);
+                if multiplier != 1.0 {
+                    scale_device_multiplier(&mut final_inst, multiplier);
+                }
                 expanded.push(final_inst);
             }
             Stmt::Control(ctrl) if matches!(ctrl.kind, ControlKind::Model) => {
@@ -1770,7 +5287,10 @@ fn expand_subckt_instance_recursive(
                 if let Some(ref name) = scoped_model.model_name {
                     scoped_model.model_name = Some(format!("{}.{}", instance.name, name));
                 }
-                models.push(scoped_model);
+                for param in &mut scoped_model.params {
+                    param.value = resolve_model_param(local_params, global_params, &param.value);
+                }
+                outputs.models.push(scoped_model);
             }
             _ => {
                 // Comments, .param (handled separately), and other control statements are ignored
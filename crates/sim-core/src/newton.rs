@@ -1,13 +1,31 @@
+use std::collections::HashMap;
+
+use crate::mna::JunctionState;
+use crate::netlist::parse_number_with_suffix;
+
 #[derive(Debug, Clone)]
 pub struct NewtonConfig {
     pub max_iters: usize,
     pub abs_tol: f64,
     pub rel_tol: f64,
+    /// 节点电压的绝对容差; 只用于前 `voltage_unknowns` 个未知量, 其余 (支路电流) 用 `abs_tol`
+    pub vn_tol: f64,
+    pub voltage_unknowns: usize,
     pub gmin: f64,
     pub damping: f64,
     pub damping_min: f64,
+    /// gmin 步进最多做的 Newton 求解次数; 0 表示不做 gmin 步进
     pub gmin_steps: usize,
+    /// gmin 步进的起始附加电导
+    pub gmin_start: f64,
+    /// gmin 步进每步附加电导的缩小倍数
+    pub gmin_factor: f64,
+    /// 源步进把独立源从 0 升到满值分成的步数; 0 表示不做源步进
     pub source_steps: usize,
+    /// 伪瞬态延拓最多的伪时间步数; 0 表示不做
+    pub pseudo_transient_steps: usize,
+    /// 伪瞬态延拓起始的伪电导 C/Δt
+    pub pseudo_conductance: f64,
 }
 
 impl Default for NewtonConfig {
@@ -16,15 +34,124 @@ impl Default for NewtonConfig {
             max_iters: 50,
             abs_tol: 1e-9,
             rel_tol: 1e-6,
+            vn_tol: 1e-9,
+            voltage_unknowns: 0,
             gmin: 1e-12,
             damping: 1.0,
             damping_min: 0.1,
+            gmin_steps: 10,
+            gmin_start: 1e-3,
+            gmin_factor: 10.0,
+            source_steps: 10,
+            pseudo_transient_steps: 50,
+            pseudo_conductance: 1e-2,
+        }
+    }
+}
+
+impl NewtonConfig {
+    /// 工作点求解的配置: 缺省值按 `.options` 覆盖
+    /// (reltol / abstol / vntol / gmin / itl1 / gminsteps / gminstart / gminfactor / srcsteps / ptransteps)
+    pub fn from_options(options: &HashMap<String, String>) -> Self {
+        let mut config = Self::default();
+        config.apply_tolerances(options);
+        let get = |key: &str| option_value(options, key);
+        if let Some(iters) = get("itl1").filter(|v| *v >= 1.0) {
+            config.max_iters = iters as usize;
+        }
+        if let Some(steps) = get("gminsteps").filter(|v| *v >= 0.0) {
+            config.gmin_steps = steps as usize;
+        }
+        if let Some(start) = get("gminstart").filter(|v| *v > 0.0) {
+            config.gmin_start = start;
+        }
+        if let Some(factor) = get("gminfactor").filter(|v| *v > 1.0) {
+            config.gmin_factor = factor;
+        }
+        if let Some(steps) = get("srcsteps").filter(|v| *v >= 0.0) {
+            config.source_steps = steps as usize;
+        }
+        if let Some(steps) = get("ptransteps").filter(|v| *v >= 0.0) {
+            config.pseudo_transient_steps = steps as usize;
+        }
+        config
+    }
+
+    /// 瞬态时间步的配置: 不收敛时缩小步长, 不做同伦
+    pub fn transient() -> Self {
+        Self {
             gmin_steps: 0,
             source_steps: 0,
+            pseudo_transient_steps: 0,
+            ..Self::default()
+        }
+    }
+
+    /// 瞬态时间步的配置, 按 `.options` 覆盖 (reltol / abstol / vntol / gmin / itl4)
+    pub fn transient_from_options(options: &HashMap<String, String>) -> Self {
+        let mut config = Self::transient();
+        config.apply_tolerances(options);
+        if let Some(iters) = option_value(options, "itl4").filter(|v| *v >= 1.0) {
+            config.max_iters = iters as usize;
+        }
+        config
+    }
+
+    fn apply_tolerances(&mut self, options: &HashMap<String, String>) {
+        let get = |key: &str| option_value(options, key).filter(|v| *v > 0.0);
+        if let Some(tol) = get("reltol") {
+            self.rel_tol = tol;
+        }
+        if let Some(tol) = get("abstol") {
+            self.abs_tol = tol;
+        }
+        // 未给 vntol 时节点电压与电流共用 abstol
+        self.vn_tol = get("vntol").unwrap_or(self.abs_tol);
+        if let Some(gmin) = get("gmin") {
+            self.gmin = gmin;
         }
     }
 }
 
+fn option_value(options: &HashMap<String, String>, key: &str) -> Option<f64> {
+    options.get(key).and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()))
+}
+
+/// 工作点求解策略, 按尝试顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DcStrategy {
+    Newton,
+    GminStepping,
+    SourceStepping,
+    PseudoTransient,
+}
+
+/// 同伦轨迹中的一次 Newton 求解
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HomotopyStep {
+    pub strategy: DcStrategy,
+    /// 各节点对地的附加电导
+    pub gmin: f64,
+    /// 独立源的缩放系数
+    pub source_scale: f64,
+    /// 伪瞬态延拓中各未知量的伪电导 C/Δt, 其他策略为 0
+    pub pseudo_conductance: f64,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// 求得工作点的策略: 轨迹的最后一步须在原电路上 (gmin 同第一次直接 Newton、源满值、
+/// 没有伪电容) 收敛, 否则求解失败, 返回 None
+pub fn successful_strategy(homotopy: &[HomotopyStep]) -> Option<DcStrategy> {
+    let first = homotopy.first()?;
+    homotopy
+        .last()
+        .filter(|step| {
+            step.converged && step.gmin == first.gmin && step.source_scale == 1.0 && step.pseudo_conductance == 0.0
+        })
+        .map(|step| step.strategy)
+}
+
 #[derive(Debug, Clone)]
 pub struct NewtonState {
     pub iter: usize,
@@ -53,6 +180,25 @@ pub struct NewtonResult {
     pub final_norm: f64,
     pub reason: NewtonExitReason,
     pub message: Option<String>,
+    /// 带步进的求解中依次做过的 Newton 求解; 单次求解时为空
+    pub homotopy: Vec<HomotopyStep>,
+    /// 不收敛时最后一次迭代的诊断信息; 收敛时为 None
+    pub diagnostics: Option<NewtonDiagnostics>,
+}
+
+/// 不收敛的 Newton 求解最后一次迭代的状态, 供定位问题节点与器件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NewtonDiagnostics {
+    /// 最后一次线性化点处的残差 A(x)·x − b(x), 按未知量; 节点行为 KCL 电流失配
+    pub residual: Vec<f64>,
+    /// 最后一次完成的迭代中各未知量的 |Δx|
+    pub dx: Vec<f64>,
+    /// 最后一次线性化点
+    pub x: Vec<f64>,
+    /// 前一次线性化点; 只迭代了一次时与 `x` 相同
+    pub previous_x: Vec<f64>,
+    /// 线性求解失败时的错误; `FactorFailed` 表示矩阵奇异
+    pub solver_error: Option<crate::solver::SolverError>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -62,6 +208,9 @@ pub enum NewtonExitReason {
     SolverFailure,
 }
 
+/// 一次 stamp 的结果: 稀疏矩阵 (ap, ai, ax)、右端项和系统维数
+pub type MnaParts = (Vec<i64>, Vec<i64>, Vec<f64>, Vec<f64>, usize);
+
 pub fn run_newton<FBuild, S>(
     config: &NewtonConfig,
     x: &mut Vec<f64>,
@@ -69,7 +218,23 @@ pub fn run_newton<FBuild, S>(
     solver: &mut S,
 ) -> NewtonResult
 where
-    FBuild: FnMut(&[f64]) -> (Vec<i64>, Vec<i64>, Vec<f64>, Vec<f64>, usize),
+    FBuild: FnMut(&[f64]) -> MnaParts,
+    S: crate::solver::LinearSolver + ?Sized,
+{
+    run_newton_noncon(config, x, |x| (build(x), false), solver)
+}
+
+/// Newton 主循环; build 额外返回本次是否有器件做了限幅 (noncon).
+/// 限幅迭代中 x 的变化不反映器件真实工作点: 不判收敛, 也不据此收紧阻尼,
+/// 步长由器件限幅控制, 直接取整步
+fn run_newton_noncon<FBuild, S>(
+    config: &NewtonConfig,
+    x: &mut Vec<f64>,
+    mut build: FBuild,
+    solver: &mut S,
+) -> NewtonResult
+where
+    FBuild: FnMut(&[f64]) -> (MnaParts, bool),
     S: crate::solver::LinearSolver + ?Sized,
 {
     let mut state = NewtonState::new();
@@ -77,23 +242,37 @@ where
     let mut prev_dx_norm = f64::MAX;
     let mut reason = NewtonExitReason::MaxIters;
     let mut message = None;
+    let mut diagnostics = NewtonDiagnostics::default();
 
     for iter in 0..config.max_iters {
         state.iter = iter + 1;
-        let (ap, ai, ax, mut rhs, n) = build(x);
+        let ((ap, ai, ax, mut rhs, n), noncon) = build(x);
+        // 辅助变量 (支路电流) 在首次 stamp 时才分配, 需要扩展 x 以便后续迭代读取
+        if x.len() < n {
+            x.resize(n, 0.0);
+        }
+        diagnostics.previous_x = std::mem::replace(&mut diagnostics.x, x.clone());
+        diagnostics.residual = residual(&ap, &ai, &ax, &rhs, x);
         solver.prepare(n);
-        if solver.analyze(&ap, &ai).is_err()
-            || solver.factor(&ap, &ai, &ax).is_err()
-            || solver.solve(&mut rhs).is_err()
-        {
+        let solved = solver
+            .analyze(&ap, &ai)
+            .and_then(|_| solver.factor(&ap, &ai, &ax))
+            .and_then(|_| solver.solve(&mut rhs));
+        if let Err(err) = solved {
             reason = NewtonExitReason::SolverFailure;
             message = Some("linear solver failed".to_string());
+            diagnostics.solver_error = Some(err);
             break;
         }
         let x_new = rhs;
         let dx: Vec<f64> = x_new.iter().zip(x.iter()).map(|(a, b)| a - b).collect();
         state.last_dx_norm = norm2(&dx);
+        diagnostics.dx = dx.iter().map(|v| v.abs()).collect();
         state.last_norm = norm2(&x_new);
+        if noncon {
+            *x = x_new;
+            continue;
+        }
         if check_convergence(&dx, &x_new, config) {
             *x = x_new;
             state.converged = true;
@@ -107,15 +286,34 @@ where
         apply_damping(x, &x_new, state.damping);
     }
 
+    if diagnostics.previous_x.is_empty() {
+        diagnostics.previous_x.clone_from(&diagnostics.x);
+    }
     NewtonResult {
         converged: state.converged,
         iterations: state.iter,
         final_norm: state.last_norm,
         reason,
         message,
+        homotopy: Vec::new(),
+        diagnostics: (!state.converged).then_some(diagnostics),
     }
 }
 
+/// CSC 矩阵 (ap, ai, ax) 的线性系统在 x 处的残差 A·x − rhs
+pub(crate) fn residual(ap: &[i64], ai: &[i64], ax: &[f64], rhs: &[f64], x: &[f64]) -> Vec<f64> {
+    let mut r: Vec<f64> = rhs.iter().map(|b| -b).collect();
+    for col in 0..ap.len().saturating_sub(1) {
+        let xj = x.get(col).copied().unwrap_or(0.0);
+        for k in ap[col] as usize..ap[col + 1] as usize {
+            if let Some(entry) = r.get_mut(ai[k] as usize) {
+                *entry += ax[k] * xj;
+            }
+        }
+    }
+    r
+}
+
 pub fn debug_dump_newton(result: &NewtonResult) {
     println!(
         "newton: converged={} iters={} norm={} reason={:?} msg={:?}",
@@ -146,47 +344,250 @@ pub fn run_newton_with_stepping<FBuild, S>(
     solver: &mut S,
 ) -> NewtonResult
 where
-    FBuild: FnMut(&[f64], f64, f64) -> (Vec<i64>, Vec<i64>, Vec<f64>, Vec<f64>, usize),
+    FBuild: FnMut(&[f64], f64, f64) -> MnaParts,
+    S: crate::solver::LinearSolver + ?Sized,
+{
+    run_stepping_noncon(config, x, |x, gmin, source_scale| (build(x, gmin, source_scale), false), solver)
+}
+
+/// 带结电压限幅的 Newton 求解: stamp 时器件按 `junctions` 中上一次迭代的
+/// 结电压做 pnjlim 限幅, 有限幅的迭代不判收敛
+pub fn run_newton_with_limiting<FBuild, S>(
+    config: &NewtonConfig,
+    x: &mut Vec<f64>,
+    junctions: &mut JunctionState,
+    mut build: FBuild,
+    solver: &mut S,
+) -> NewtonResult
+where
+    FBuild: FnMut(&[f64], f64, f64, &mut JunctionState) -> MnaParts,
+    S: crate::solver::LinearSolver + ?Sized,
+{
+    run_stepping_noncon(
+        config,
+        x,
+        |x, gmin, source_scale| {
+            junctions.limited = false;
+            let parts = build(x, gmin, source_scale, junctions);
+            (parts, junctions.limited)
+        },
+        solver,
+    )
+}
+
+/// 一次同伦求解的参数
+#[derive(Debug, Clone, Copy)]
+struct HomotopyPoint<'a> {
+    strategy: DcStrategy,
+    gmin: f64,
+    source_scale: f64,
+    /// 伪瞬态: 伪电导与上一伪时间点的解
+    pseudo: Option<(f64, &'a [f64])>,
+}
+
+impl<'a> HomotopyPoint<'a> {
+    fn new(strategy: DcStrategy, gmin: f64, source_scale: f64) -> Self {
+        Self {
+            strategy,
+            gmin,
+            source_scale,
+            pseudo: None,
+        }
+    }
+}
+
+/// 依次尝试: 直接 Newton; gmin 步进; 源步进; 伪瞬态延拓 (步数为 0 的跳过).
+/// 每次 Newton 求解记入结果的 `homotopy`, 迭代次数为全部求解之和
+fn run_stepping_noncon<FBuild, S>(
+    config: &NewtonConfig,
+    x: &mut Vec<f64>,
+    mut build: FBuild,
+    solver: &mut S,
+) -> NewtonResult
+where
+    FBuild: FnMut(&[f64], f64, f64) -> (MnaParts, bool),
     S: crate::solver::LinearSolver + ?Sized,
 {
-    let gmin_start = (config.gmin * 1e3).max(1e-6);
-    let mut gmin_sched = GminSchedule::new(config.gmin_steps, gmin_start, config.gmin);
-    let mut last_result = NewtonResult {
-        converged: false,
-        iterations: 0,
-        final_norm: 0.0,
-        reason: NewtonExitReason::MaxIters,
-        message: None,
+    let mut homotopy = Vec::new();
+    let mut iterations = 0;
+    let mut solve = |x: &mut Vec<f64>, point: HomotopyPoint| {
+        let result = run_newton_noncon(
+            config,
+            x,
+            |x| {
+                let (mut parts, noncon) = build(x, point.gmin, point.source_scale);
+                if let Some((conductance, anchor)) = point.pseudo {
+                    add_pseudo_conductance(&mut parts, conductance, anchor);
+                }
+                (parts, noncon)
+            },
+            solver,
+        );
+        homotopy.push(HomotopyStep {
+            strategy: point.strategy,
+            gmin: point.gmin,
+            source_scale: point.source_scale,
+            pseudo_conductance: point.pseudo.map_or(0.0, |(conductance, _)| conductance),
+            iterations: result.iterations,
+            converged: result.converged,
+        });
+        iterations += result.iterations;
+        result
     };
 
-    for _ in 0..=config.gmin_steps {
-        let gmin = gmin_sched.value();
-        let mut source_sched = SourceSchedule::new(config.source_steps);
-
-        for _ in 0..=config.source_steps {
-            let source_scale = source_sched.scale();
-            let result = run_newton(
-                config,
-                x,
-                |x| build(x, gmin, source_scale),
-                solver,
-            );
-            if result.converged {
-                return result;
+    let x_start = x.clone();
+    let mut result = solve(x, HomotopyPoint::new(DcStrategy::Newton, config.gmin, 1.0));
+    type Fallback<F> = fn(&NewtonConfig, &mut Vec<f64>, &mut F) -> Option<NewtonResult>;
+    let fallbacks: [(usize, Fallback<_>); 3] = [
+        (config.gmin_steps, gmin_stepping),
+        (config.source_steps, source_stepping),
+        (config.pseudo_transient_steps, pseudo_transient),
+    ];
+    for (steps, fallback) in fallbacks {
+        if result.converged || steps == 0 {
+            continue;
+        }
+        x.clone_from(&x_start);
+        if let Some(found) = fallback(config, x, &mut solve) {
+            result = found;
+        }
+    }
+    result.iterations = iterations;
+    result.homotopy = homotopy;
+    result
+}
+
+/// gmin 步进: 从 `gmin_start` 出发, 每步收敛后把附加电导除以 `gmin_factor` 并以上一步的解为初值,
+/// 直到回到 `config.gmin`; 某一步不收敛时退回上一步的解, 把缩小倍数开方后重试 (动态 gmin).
+/// 最多 `gmin_steps` 次求解; 没有收敛到 `config.gmin` 时返回 None
+fn gmin_stepping(
+    config: &NewtonConfig,
+    x: &mut Vec<f64>,
+    solve: &mut impl FnMut(&mut Vec<f64>, HomotopyPoint) -> NewtonResult,
+) -> Option<NewtonResult> {
+    let mut factor = config.gmin_factor;
+    let mut gmin = config.gmin_start.max(config.gmin);
+    let mut last_good: Option<(f64, Vec<f64>)> = None;
+    for _ in 0..config.gmin_steps {
+        let result = solve(x, HomotopyPoint::new(DcStrategy::GminStepping, gmin, 1.0));
+        if result.converged {
+            if gmin <= config.gmin {
+                return Some(result);
             }
-            last_result = result;
-            source_sched.advance();
+            last_good = Some((gmin, x.clone()));
+            gmin = (gmin / factor).max(config.gmin);
+            continue;
         }
+        // 第一步就不收敛, 或缩小倍数已接近 1: 放弃
+        let (good_gmin, good_x) = last_good.as_ref()?;
+        factor = factor.sqrt();
+        if factor < 1.01 {
+            return None;
+        }
+        x.clone_from(good_x);
+        gmin = (good_gmin / factor).max(config.gmin);
+    }
+    None
+}
 
-        gmin_sched.advance();
+/// 源步进: 独立源从 0 起每步增加 1/`source_steps`, 以上一步的解为初值, 直到满值;
+/// 某一步不收敛时退回上一步的解并把增量减半. 最多 4·`source_steps` 次求解
+fn source_stepping(
+    config: &NewtonConfig,
+    x: &mut Vec<f64>,
+    solve: &mut impl FnMut(&mut Vec<f64>, HomotopyPoint) -> NewtonResult,
+) -> Option<NewtonResult> {
+    let mut increment = 1.0 / config.source_steps as f64;
+    let mut scale = 0.0;
+    let mut good: Option<(f64, Vec<f64>)> = None;
+    for _ in 0..4 * config.source_steps {
+        let result = solve(x, HomotopyPoint::new(DcStrategy::SourceStepping, config.gmin, scale));
+        if result.converged {
+            if scale >= 1.0 {
+                return Some(result);
+            }
+            good = Some((scale, x.clone()));
+            scale = (scale + increment).min(1.0);
+            continue;
+        }
+        // 源全关时仍不收敛: 放弃
+        let (good_scale, good_x) = good.as_ref()?;
+        increment *= 0.5;
+        x.clone_from(good_x);
+        scale = (good_scale + increment).min(1.0);
     }
+    None
+}
 
-    last_result
+/// 伪瞬态延拓: 给每个未知量加伪电容, 从初值出发按后向欧拉在伪时间上推进到稳态.
+/// 每步收敛后伪电导 C/Δt 缩小 4 倍 (伪时间步放大), 不收敛时退回并放大 4 倍;
+/// 伪电导降到 gmin 以下后去掉伪电容做一次直接 Newton. 最多 `pseudo_transient_steps` 个伪时间步
+fn pseudo_transient(
+    config: &NewtonConfig,
+    x: &mut Vec<f64>,
+    solve: &mut impl FnMut(&mut Vec<f64>, HomotopyPoint) -> NewtonResult,
+) -> Option<NewtonResult> {
+    let mut conductance = config.pseudo_conductance;
+    let mut anchor = x.clone();
+    for _ in 0..config.pseudo_transient_steps {
+        let point = HomotopyPoint {
+            pseudo: Some((conductance, &anchor)),
+            ..HomotopyPoint::new(DcStrategy::PseudoTransient, config.gmin, 1.0)
+        };
+        if !solve(x, point).converged {
+            x.clone_from(&anchor);
+            conductance *= 4.0;
+            continue;
+        }
+        anchor.clone_from(x);
+        conductance *= 0.25;
+        if conductance < config.gmin {
+            let result = solve(x, HomotopyPoint::new(DcStrategy::PseudoTransient, config.gmin, 1.0));
+            return result.converged.then_some(result);
+        }
+    }
+    None
+}
+
+/// 伪电容的后向欧拉伴随模型: 对角线加 `conductance`, 右端项加 `conductance`·anchor
+fn add_pseudo_conductance(parts: &mut MnaParts, conductance: f64, anchor: &[f64]) {
+    let (ap, ai, ax, rhs, n) = parts;
+    let mut new_ap = Vec::with_capacity(ap.len());
+    let mut new_ai = Vec::with_capacity(ai.len() + *n);
+    let mut new_ax = Vec::with_capacity(ax.len() + *n);
+    new_ap.push(0);
+    for col in 0..*n {
+        let mut placed = false;
+        for k in ap[col] as usize..ap[col + 1] as usize {
+            let row = ai[k] as usize;
+            if !placed && row > col {
+                new_ai.push(col as i64);
+                new_ax.push(conductance);
+                placed = true;
+            }
+            let diagonal = !placed && row == col;
+            new_ai.push(row as i64);
+            new_ax.push(if diagonal { ax[k] + conductance } else { ax[k] });
+            placed |= diagonal;
+        }
+        if !placed {
+            new_ai.push(col as i64);
+            new_ax.push(conductance);
+        }
+        new_ap.push(new_ai.len() as i64);
+    }
+    for (i, value) in rhs.iter_mut().enumerate().take(*n) {
+        *value += conductance * anchor.get(i).copied().unwrap_or(0.0);
+    }
+    *ap = new_ap;
+    *ai = new_ai;
+    *ax = new_ax;
 }
 
 pub fn check_convergence(dx: &[f64], x: &[f64], config: &NewtonConfig) -> bool {
-    dx.iter().zip(x.iter()).all(|(dx_i, x_i)| {
-        dx_i.abs() <= config.abs_tol + config.rel_tol * x_i.abs()
+    dx.iter().zip(x.iter()).enumerate().all(|(i, (dx_i, x_i))| {
+        let abs_tol = if i < config.voltage_unknowns { config.vn_tol } else { config.abs_tol };
+        dx_i.abs() <= abs_tol + config.rel_tol * x_i.abs()
     })
 }
 
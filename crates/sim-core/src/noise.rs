@@ -0,0 +1,229 @@
+//! 小信号噪声分析 (`.noise`) 的噪声源与结果汇总
+//!
+//! 每个器件在直流工作点给出若干并联在两节点间的噪声电流源 ([`NoiseSource`]),
+//! 其功率谱密度为 `density / f^exponent`. 引擎在每个频率点求出各噪声源到输出的
+//! 传输 |H|², 由此得到输出噪声谱、折算到输入源的等效噪声, 以及按器件汇总、
+//! 按贡献从大到小排列的噪声分解表 ([`NoiseContribution`]).
+
+use std::fmt::Write as _;
+
+/// 玻尔兹曼常数 [J/K]
+pub const BOLTZMANN: f64 = 1.380649e-23;
+/// 元电荷 [C]
+pub const CHARGE: f64 = 1.602176634e-19;
+
+/// 噪声机理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseMechanism {
+    /// 热噪声: 电阻 4kT/R, MOSFET 沟道 8kT·gm/3
+    Thermal,
+    /// 散粒噪声: 结电流 2q|I|
+    Shot,
+    /// 闪烁 (1/f) 噪声: KF·|I|^AF / f^EF
+    Flicker,
+}
+
+impl NoiseMechanism {
+    pub fn name(self) -> &'static str {
+        match self {
+            NoiseMechanism::Thermal => "thermal",
+            NoiseMechanism::Shot => "shot",
+            NoiseMechanism::Flicker => "flicker",
+        }
+    }
+}
+
+/// 器件内并联在两节点间的一个噪声电流源
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseSource {
+    pub device: String,
+    pub mechanism: NoiseMechanism,
+    /// 噪声电流所在的两个节点 (解向量下标)
+    pub nodes: (usize, usize),
+    /// 1 Hz 处的电流功率谱密度 [A²/Hz]
+    pub density: f64,
+    /// 频率指数: 白噪声为 0, 闪烁噪声为 EF
+    pub exponent: f64,
+}
+
+impl NoiseSource {
+    /// 频率 `freq` 处的电流功率谱密度 [A²/Hz]
+    pub fn density_at(&self, freq: f64) -> f64 {
+        if self.exponent == 0.0 {
+            self.density
+        } else {
+            self.density / freq.powf(self.exponent)
+        }
+    }
+}
+
+/// 单个器件对输出噪声的贡献
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseContribution {
+    pub device: String,
+    /// 各频率点该器件产生的输出噪声谱密度 [V²/Hz]
+    pub density: Vec<f64>,
+    /// 各机理在扫描频带上积分的输出噪声功率 [V²]
+    pub thermal: f64,
+    pub shot: f64,
+    pub flicker: f64,
+}
+
+impl NoiseContribution {
+    /// 积分输出噪声功率 [V²]
+    pub fn total(&self) -> f64 {
+        self.thermal + self.shot + self.flicker
+    }
+
+    /// 积分贡献最大的机理
+    pub fn dominant(&self) -> NoiseMechanism {
+        [
+            (NoiseMechanism::Thermal, self.thermal),
+            (NoiseMechanism::Shot, self.shot),
+            (NoiseMechanism::Flicker, self.flicker),
+        ]
+        .into_iter()
+        .fold((NoiseMechanism::Thermal, f64::NEG_INFINITY), |best, item| if item.1 > best.1 { item } else { best })
+        .0
+    }
+}
+
+/// `.noise` 分析结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseResult {
+    /// 输出探针, 如 `V(out)` 或 `V(out,ref)`
+    pub output: String,
+    /// 输入源名
+    pub source: String,
+    pub frequencies: Vec<f64>,
+    /// 各频率点的输出噪声谱密度 [V²/Hz]
+    pub output_density: Vec<f64>,
+    /// 折算到输入源的等效噪声谱密度 (电压源为 V²/Hz, 电流源为 A²/Hz); 增益为零处为无穷大
+    pub input_density: Vec<f64>,
+    /// 按积分输出噪声从大到小排列的器件贡献; 无噪声源的器件不出现
+    pub contributions: Vec<NoiseContribution>,
+}
+
+impl NoiseResult {
+    /// 由噪声源与各频率点的传输汇总结果: `transfers[k][s]` 为频率 k 处噪声源 s 到输出的 |H|²,
+    /// `gains[k]` 为输入源到输出的 |G|²
+    pub fn from_transfers(
+        output: String,
+        source: String,
+        frequencies: Vec<f64>,
+        sources: &[NoiseSource],
+        transfers: &[Vec<f64>],
+        gains: &[f64],
+    ) -> Self {
+        let densities: Vec<Vec<f64>> = frequencies
+            .iter()
+            .zip(transfers)
+            .map(|(&freq, transfer)| sources.iter().zip(transfer).map(|(s, h)| s.density_at(freq) * h).collect())
+            .collect();
+        Self::from_densities(output, source, frequencies, sources, &densities, gains)
+    }
+
+    /// 由各噪声源已算好的输出噪声谱汇总结果: `densities[k][s]` 为频率 k 处噪声源 s 产生的
+    /// 输出噪声谱密度 [V²/Hz], 噪声源只用到其器件名与机理; `gains` 同 [`Self::from_transfers`]
+    pub fn from_densities(
+        output: String,
+        source: String,
+        frequencies: Vec<f64>,
+        sources: &[NoiseSource],
+        densities: &[Vec<f64>],
+        gains: &[f64],
+    ) -> Self {
+        let mut contributions: Vec<NoiseContribution> = Vec::new();
+        let mut owner = Vec::with_capacity(sources.len());
+        for source in sources {
+            let idx = match contributions.iter().position(|c| c.device == source.device) {
+                Some(idx) => idx,
+                None => {
+                    contributions.push(NoiseContribution {
+                        device: source.device.clone(),
+                        density: vec![0.0; frequencies.len()],
+                        thermal: 0.0,
+                        shot: 0.0,
+                        flicker: 0.0,
+                    });
+                    contributions.len() - 1
+                }
+            };
+            owner.push(idx);
+        }
+
+        let mut output_density = vec![0.0; frequencies.len()];
+        for (s, source) in sources.iter().enumerate() {
+            let density: Vec<f64> = densities.iter().map(|point| point[s]).collect();
+            let power = integrate(&frequencies, &density);
+            let contribution = &mut contributions[owner[s]];
+            match source.mechanism {
+                NoiseMechanism::Thermal => contribution.thermal += power,
+                NoiseMechanism::Shot => contribution.shot += power,
+                NoiseMechanism::Flicker => contribution.flicker += power,
+            }
+            for (k, value) in density.into_iter().enumerate() {
+                contribution.density[k] += value;
+                output_density[k] += value;
+            }
+        }
+        contributions.sort_by(|a, b| b.total().total_cmp(&a.total()));
+
+        let input_density = output_density
+            .iter()
+            .zip(gains)
+            .map(|(&out, &gain)| if gain > 0.0 { out / gain } else { f64::INFINITY })
+            .collect();
+        Self {
+            output,
+            source,
+            frequencies,
+            output_density,
+            input_density,
+            contributions,
+        }
+    }
+
+    /// 扫描频带上的积分输出噪声功率 [V²]
+    pub fn total_output_noise(&self) -> f64 {
+        integrate(&self.frequencies, &self.output_density)
+    }
+
+    /// 扫描频带上的积分等效输入噪声功率
+    pub fn total_input_noise(&self) -> f64 {
+        integrate(&self.frequencies, &self.input_density)
+    }
+
+    /// 器件噪声贡献表: 按贡献从大到小列出积分噪声 (V rms)、所占比例与主导机理
+    pub fn contribution_table(&self, precision: usize) -> String {
+        let total = self.total_output_noise();
+        let width = self.contributions.iter().map(|c| c.device.len()).max().unwrap_or(0).max(6);
+        let mut table = String::new();
+        let _ = writeln!(table, "{:<width$} {:>14} {:>8}  dominant", "device", "noise(Vrms)", "percent");
+        for contribution in &self.contributions {
+            let percent = if total > 0.0 { 100.0 * contribution.total() / total } else { 0.0 };
+            let _ = writeln!(
+                table,
+                "{:<width$} {:>14.*e} {:>8.2}  {}",
+                contribution.device,
+                precision,
+                contribution.total().sqrt(),
+                percent,
+                contribution.dominant().name(),
+            );
+        }
+        table
+    }
+}
+
+/// 谱密度在扫描频率上的梯形积分; 单个频率点时取该点的点噪声 (1 Hz 带宽)
+fn integrate(frequencies: &[f64], density: &[f64]) -> f64 {
+    if frequencies.len() < 2 {
+        return density.first().copied().unwrap_or(0.0);
+    }
+    frequencies
+        .windows(2)
+        .zip(density.windows(2))
+        .map(|(f, d)| 0.5 * (d[0] + d[1]) * (f[1] - f[0]))
+        .sum()
+}
@@ -0,0 +1,79 @@
+//! Saved operating point files.
+//!
+//! A converged DC operating point is written as plain text, one value per
+//! line, keyed by name so that it can be loaded into a later run of the same
+//! circuit as the Newton initial guess:
+//!
+//! ```text
+//! * myspice operating point
+//! node out 1.234e0
+//! branch V1 -1.0e-3
+//! junction D1:d 6.5e-1
+//! ```
+//!
+//! `node` lines hold node voltages, `branch` lines the auxiliary unknowns
+//! (source and inductor currents) and `junction` lines the PN junction
+//! voltages used as the limiting reference. Blank lines and lines starting
+//! with `*` are ignored.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const HEADER: &str = "* myspice operating point";
+
+/// Operating point values by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SavedOp {
+    pub nodes: Vec<(String, f64)>,
+    pub branches: Vec<(String, f64)>,
+    pub junctions: Vec<(String, f64)>,
+}
+
+/// Render an operating point in the saved-OP text format
+pub fn format_op(op: &SavedOp) -> String {
+    let mut out = String::new();
+    out.push_str(HEADER);
+    out.push('\n');
+    let sections = [("node", &op.nodes), ("branch", &op.branches), ("junction", &op.junctions)];
+    for (kind, values) in sections {
+        for (name, value) in values {
+            // `{:e}` prints the shortest representation that round-trips exactly
+            out.push_str(&format!("{} {} {:e}\n", kind, name, value));
+        }
+    }
+    out
+}
+
+/// Parse the saved-OP text format
+pub fn parse_op(text: &str) -> Result<SavedOp, String> {
+    let mut op = SavedOp::default();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('*') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [kind, name, value] = fields[..] else {
+            return Err(format!("line {}: expected '<kind> <name> <value>'", idx + 1));
+        };
+        let value: f64 = value.parse().map_err(|_| format!("line {}: invalid value '{}'", idx + 1, value))?;
+        let entry = (name.to_string(), value);
+        match kind {
+            "node" => op.nodes.push(entry),
+            "branch" => op.branches.push(entry),
+            "junction" => op.junctions.push(entry),
+            _ => return Err(format!("line {}: unknown entry '{}'", idx + 1, kind)),
+        }
+    }
+    Ok(op)
+}
+
+pub fn write_op(op: &SavedOp, path: &Path) -> io::Result<()> {
+    fs::write(path, format_op(op))
+}
+
+pub fn read_op(path: &Path) -> io::Result<SavedOp> {
+    let text = fs::read_to_string(path)?;
+    parse_op(&text).map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+}
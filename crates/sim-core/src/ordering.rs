@@ -0,0 +1,180 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap};
+
+use crate::solver::{LinearSolver, SolverError};
+
+/// 对 A + Aᵀ 的非零结构做最小度排序 (消元图上每次消去度最小的节点, 度相同时取序号小者),
+/// 返回新序到原序的映射: 新序号 k 对应原序号 `perm[k]`。
+/// 候选节点放在按 (度, 序号) 排序的小顶堆里, 度变化时压入新项, 过期项在弹出时丢弃
+pub fn minimum_degree_order(n: usize, ap: &[i64], ai: &[i64]) -> Vec<usize> {
+    let mut graph = vec![BTreeSet::new(); n];
+    for col in 0..n {
+        for &row in &ai[ap[col] as usize..ap[col + 1] as usize] {
+            let row = row as usize;
+            if row != col && row < n {
+                graph[row].insert(col);
+                graph[col].insert(row);
+            }
+        }
+    }
+
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> = (0..n).map(|i| Reverse((graph[i].len(), i))).collect();
+    let mut eliminated = vec![false; n];
+    let mut perm = Vec::with_capacity(n);
+    while let Some(Reverse((degree, node))) = heap.pop() {
+        if eliminated[node] || degree != graph[node].len() {
+            continue;
+        }
+        eliminated[node] = true;
+        perm.push(node);
+        // 消去 node: 其邻居两两相连 (即消元产生的填充)
+        let neighbors: Vec<usize> = std::mem::take(&mut graph[node]).into_iter().collect();
+        for &u in &neighbors {
+            graph[u].remove(&node);
+            graph[u].extend(neighbors.iter().copied().filter(|v| *v != u));
+            heap.push(Reverse((graph[u].len(), u)));
+        }
+    }
+    perm
+}
+
+/// 按最小度排序得到的对称重排 P A Pᵀ 的结构: 只在原始结构变化时重新计算,
+/// 其余时候只把数值散到重排后的位置
+#[derive(Debug, Clone, Default)]
+pub struct SymbolicOrdering {
+    /// 上一次排序所依据的原始结构
+    ap: Vec<i64>,
+    ai: Vec<i64>,
+    perm: Vec<usize>,
+    /// 重排后的结构, 以及原始各非零元在其中的位置
+    pub permuted_ap: Vec<i64>,
+    pub permuted_ai: Vec<i64>,
+    positions: Vec<usize>,
+    /// 计算排序的次数
+    pub orderings: usize,
+}
+
+impl SymbolicOrdering {
+    /// 当前排序: 新序号 k 对应原序号 `permutation()[k]`
+    pub fn permutation(&self) -> &[usize] {
+        &self.perm
+    }
+
+    /// 结构与上次相同时沿用原排序, 否则重新排序; 行号越界时报 `AnalyzeFailed`
+    pub fn update(&mut self, ap: &[i64], ai: &[i64]) -> Result<(), SolverError> {
+        if ap.is_empty() || ai.iter().any(|row| *row < 0 || *row as usize >= ap.len() - 1) {
+            return Err(SolverError::AnalyzeFailed);
+        }
+        if self.matches(ap, ai) {
+            return Ok(());
+        }
+        let n = ap.len() - 1;
+        self.perm = minimum_degree_order(n, ap, ai);
+        let mut inverse = vec![0; n];
+        for (k, &old) in self.perm.iter().enumerate() {
+            inverse[old] = k;
+        }
+        let mut entries: Vec<(usize, usize, usize)> = Vec::with_capacity(ai.len());
+        for col in 0..n {
+            for idx in ap[col] as usize..ap[col + 1] as usize {
+                entries.push((inverse[col], inverse[ai[idx] as usize], idx));
+            }
+        }
+        entries.sort_unstable();
+        self.permuted_ap = vec![0; n + 1];
+        self.permuted_ai = Vec::with_capacity(entries.len());
+        self.positions = vec![0; entries.len()];
+        for (pos, &(col, row, idx)) in entries.iter().enumerate() {
+            self.permuted_ap[col + 1] += 1;
+            self.permuted_ai.push(row as i64);
+            self.positions[idx] = pos;
+        }
+        for col in 0..n {
+            self.permuted_ap[col + 1] += self.permuted_ap[col];
+        }
+        self.ap = ap.to_vec();
+        self.ai = ai.to_vec();
+        self.orderings += 1;
+        Ok(())
+    }
+
+    pub fn matches(&self, ap: &[i64], ai: &[i64]) -> bool {
+        self.ap == ap && self.ai == ai
+    }
+
+    /// 原始结构上的数值 `ax` 换到重排后的位置
+    pub fn permute_values<T: Copy + Default>(&self, ax: &[T]) -> Vec<T> {
+        let mut permuted = vec![T::default(); ax.len()];
+        for (value, &pos) in ax.iter().zip(&self.positions) {
+            permuted[pos] = *value;
+        }
+        permuted
+    }
+
+    /// 右端项按新序排列
+    pub fn permute_rhs<T: Copy>(&self, rhs: &[T]) -> Vec<T> {
+        self.perm.iter().map(|old| rhs[*old]).collect()
+    }
+
+    /// 新序的解写回原序
+    pub fn restore<T: Copy>(&self, permuted: &[T], x: &mut [T]) {
+        for (value, &old) in permuted.iter().zip(&self.perm) {
+            x[old] = *value;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ap.clear();
+        self.ai.clear();
+    }
+}
+
+/// 在任意后端之前做减少填充的对称重排: 按 [`minimum_degree_order`] 求 P A Pᵀ 交给内层求解器,
+/// 求解时置换右端项并还原解的顺序。排序只在矩阵结构变化时重新计算, Newton 迭代间保持不变
+pub struct OrderedSolver {
+    inner: Box<dyn LinearSolver>,
+    pub ordering: SymbolicOrdering,
+}
+
+impl OrderedSolver {
+    pub fn new(inner: Box<dyn LinearSolver>) -> Self {
+        Self {
+            inner,
+            ordering: SymbolicOrdering::default(),
+        }
+    }
+}
+
+impl LinearSolver for OrderedSolver {
+    fn prepare(&mut self, n: usize) {
+        self.inner.prepare(n);
+    }
+
+    fn analyze(&mut self, ap: &[i64], ai: &[i64]) -> Result<(), SolverError> {
+        self.ordering.update(ap, ai)?;
+        self.inner.analyze(&self.ordering.permuted_ap, &self.ordering.permuted_ai)
+    }
+
+    fn factor(&mut self, ap: &[i64], ai: &[i64], ax: &[f64]) -> Result<(), SolverError> {
+        if !self.ordering.matches(ap, ai) {
+            self.analyze(ap, ai)?;
+        }
+        let permuted_ax = self.ordering.permute_values(ax);
+        self.inner.factor(&self.ordering.permuted_ap, &self.ordering.permuted_ai, &permuted_ax)
+    }
+
+    fn solve(&mut self, rhs: &mut [f64]) -> Result<(), SolverError> {
+        if rhs.len() != self.ordering.permutation().len() {
+            return Err(SolverError::SolveFailed);
+        }
+        let mut permuted = self.ordering.permute_rhs(rhs);
+        self.inner.solve(&mut permuted)?;
+        self.ordering.restore(&permuted, rhs);
+        Ok(())
+    }
+
+    fn reset_pattern(&mut self) {
+        self.ordering.clear();
+        self.inner.reset_pattern();
+    }
+}
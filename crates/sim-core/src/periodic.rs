@@ -0,0 +1,317 @@
+//! 周期小信号分析 (`.pac` / `.pnoise`)
+//!
+//! 在 `.pss` 求得的周期稳态 x(t) 上线性化, 电路成为线性周期时变 (LPTV) 系统
+//! G(t)·v + d/dt(C(t)·v) = u. 沿一个周期的 N 个网格点用器件的 AC 盖章取
+//! G(t_k) = Y(0), C(t_k) = (Y(1) - Y(0)) / j, 其傅里叶系数 G_p / C_p 组成边带转换矩阵:
+//! 第 m 个行块 (频率 f + m·f0, |m| <= K) 为 Σ_l (G_{m-l} + j(ω + m·ω0)·C_{m-l})·V_l.
+//! 对频率非线性的分布参数器件 (传输线等) 这只是一阶近似.
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+use num_complex::Complex64;
+
+use crate::circuit::Instance;
+use crate::complex_mna::{ComplexMnaBuilder, ComplexSparseBuilder};
+use crate::complex_solver::ComplexLinearSolver;
+use crate::noise::NoiseSource;
+use crate::stamp::{noise_sources, DeviceStamp};
+
+/// 谐波系数相对同一矩阵元最大谐波低于此比例时视为零, 使矩阵结构不含舍入噪声
+const HARMONIC_FLOOR: f64 = 1e-12;
+
+/// `.pac` 分析结果: 各输入频率、各边带上的复数解
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacResult {
+    /// PSS 基频 f0 [Hz]
+    pub fundamental: f64,
+    /// 边带序号 -K..=K
+    pub sidebands: Vec<i32>,
+    /// 输入 (AC 源) 频率
+    pub frequencies: Vec<f64>,
+    /// `phasors[k][s]`: 输入频率 k 下边带 `sidebands[s]` (频率 f + m·f0) 上的复数解向量,
+    /// 节点电压在前, 支路电流按 AC 辅助变量顺序在后
+    pub phasors: Vec<Vec<Vec<Complex64>>>,
+}
+
+impl PacResult {
+    /// 输入频率点 `point` 下边带 `sideband` 的频率 f + m·f0
+    pub fn sideband_frequency(&self, point: usize, sideband: i32) -> Option<f64> {
+        let freq = self.frequencies.get(point)?;
+        Some(freq + sideband as f64 * self.fundamental)
+    }
+
+    /// 输入频率点 `point` 下节点 `node` 在边带 `sideband` 上的复电压
+    pub fn sideband_voltage(&self, node: usize, point: usize, sideband: i32) -> Option<Complex64> {
+        let s = self.sidebands.iter().position(|&m| m == sideband)?;
+        self.phasors.get(point)?.get(s)?.get(node).copied()
+    }
+}
+
+/// 线性化后的 LPTV 系统
+pub struct PeriodicSystem {
+    /// 单个边带块的规模 (节点加 AC 辅助变量)
+    size: usize,
+    gnd: usize,
+    fundamental: f64,
+    sidebands: usize,
+    /// 矩阵元 (行, 列) 的 G_p / C_p, 下标 p + 2K 对应谐波 p ∈ [-2K, 2K]
+    entries: Vec<(usize, usize, Vec<Complex64>, Vec<Complex64>)>,
+    /// AC 源在边带 0 上的激励 (与周期稳态无关)
+    excitation: Vec<Complex64>,
+}
+
+impl PeriodicSystem {
+    /// 沿周期稳态 `waveform` (一个周期内等间隔的 N 个解, 不含周期末点) 线性化 `instances`;
+    /// 边带数会收缩到 4K < N 以保证谐波 G_{±2K} 可由采样唯一确定
+    pub fn linearize(
+        instances: &[Instance],
+        node_count: usize,
+        gnd: usize,
+        waveform: &[Vec<f64>],
+        fundamental: f64,
+        sidebands: usize,
+    ) -> Self {
+        let samples = waveform.len();
+        let sidebands = sidebands.min(samples.saturating_sub(1) / 4);
+        let zeros = || (vec![Complex64::new(0.0, 0.0); samples], vec![Complex64::new(0.0, 0.0); samples]);
+        let mut series: BTreeMap<(usize, usize), (Vec<Complex64>, Vec<Complex64>)> = BTreeMap::new();
+        let mut size = node_count;
+        let mut excitation = Vec::new();
+        let j = Complex64::new(0.0, 1.0);
+        for (k, x) in waveform.iter().enumerate() {
+            let static_part = stamp_matrix(instances, node_count, x, 0.0);
+            let unit_part = stamp_matrix(instances, node_count, x, 1.0);
+            for ((row, col), value) in collect_entries(&static_part.builder) {
+                let entry = series.entry((row, col)).or_insert_with(zeros);
+                entry.0[k] += value;
+                entry.1[k] += j * value;
+            }
+            for ((row, col), value) in collect_entries(&unit_part.builder) {
+                series.entry((row, col)).or_insert_with(zeros).1[k] -= j * value;
+            }
+            size = static_part.builder.n;
+            if k == 0 {
+                excitation = static_part.rhs;
+            }
+        }
+
+        let entries = series
+            .into_iter()
+            .map(|((row, col), (g, c))| (row, col, harmonics(&g, 2 * sidebands), harmonics(&c, 2 * sidebands)))
+            .collect();
+        excitation.resize(size, Complex64::new(0.0, 0.0));
+        Self {
+            size,
+            gnd,
+            fundamental,
+            sidebands,
+            entries,
+            excitation,
+        }
+    }
+
+    /// 实际使用的边带数 K
+    pub fn sidebands(&self) -> usize {
+        self.sidebands
+    }
+
+    /// 边带 `m` 块中第 `row` 个未知量在转换矩阵中的下标
+    fn index(&self, m: i32, row: usize) -> usize {
+        (m + self.sidebands as i32) as usize * self.size + row
+    }
+
+    fn sideband_range(&self) -> std::ops::RangeInclusive<i32> {
+        -(self.sidebands as i32)..=self.sidebands as i32
+    }
+
+    /// 输入频率 `freq` 下的边带转换矩阵 (CSC); 各块的地节点行列去掉, 对角置 1
+    fn matrix(&self, freq: f64) -> (Vec<i64>, Vec<i64>, Vec<Complex64>) {
+        let blocks = 2 * self.sidebands + 1;
+        let mut builder = ComplexSparseBuilder::new(self.size * blocks);
+        let omega = 2.0 * PI * freq;
+        let omega0 = 2.0 * PI * self.fundamental;
+        for m in self.sideband_range() {
+            let s = Complex64::new(0.0, omega + m as f64 * omega0);
+            for l in self.sideband_range() {
+                let p = (m - l + 2 * self.sidebands as i32) as usize;
+                for (row, col, g, c) in &self.entries {
+                    if *row == self.gnd || *col == self.gnd || (g[p] == Complex64::default() && c[p] == Complex64::default()) {
+                        continue;
+                    }
+                    builder.insert(self.index(l, *col), self.index(m, *row), g[p] + s * c[p]);
+                }
+            }
+            let gnd = self.index(m, self.gnd);
+            builder.insert(gnd, gnd, Complex64::new(1.0, 0.0));
+        }
+        builder.finalize()
+    }
+
+    /// 周期 AC: 返回各边带 (-K..=K) 上的复数解向量; 求解失败时为 None
+    pub fn solve(&self, freq: f64, solver: &mut dyn ComplexLinearSolver) -> Option<Vec<Vec<Complex64>>> {
+        let (ap, ai, ax) = self.matrix(freq);
+        let total = ap.len() - 1;
+        let mut rhs = vec![Complex64::new(0.0, 0.0); total];
+        for (row, value) in self.excitation.iter().enumerate().filter(|(row, _)| *row != self.gnd) {
+            rhs[self.index(0, row)] = *value;
+        }
+        let mut x = vec![Complex64::new(0.0, 0.0); total];
+        solver.prepare(total);
+        if !solver.solve(&ap, &ai, &ax, &rhs, &mut x) {
+            return None;
+        }
+        Some(x.chunks(self.size).map(<[Complex64]>::to_vec).collect())
+    }
+
+    /// 周期噪声: 在频率 `freq` 处求伴随方程 Aᵀ·z = e_out (输出取边带 0), 返回每个噪声源
+    /// 在输出上的噪声谱密度与输入源 (即 `excitation`) 到输出的 |G|².
+    /// `modulations[s]` 为噪声源 s 的调制 sqrt(S(t)) 的谐波 (与 `noise_modulation` 相同排列),
+    /// 边带 l 上的平稳噪声经调制搬移到各边带 m 后再传到输出, 不同 l 之间不相关:
+    /// S_out = Σ_l |Σ_m H_m·M_{m-l}|²·S_n(|f + l·f0|)
+    pub fn noise(
+        &self,
+        freq: f64,
+        output: (usize, Option<usize>),
+        sources: &[NoiseSource],
+        modulations: &[Vec<Complex64>],
+        solver: &mut dyn ComplexLinearSolver,
+    ) -> Option<(Vec<f64>, f64)> {
+        let (ap, ai, ax) = self.matrix(freq);
+        let (tp, ti, tx) = transpose_csc(&ap, &ai, &ax);
+        let total = ap.len() - 1;
+        let mut e = vec![Complex64::new(0.0, 0.0); total];
+        e[self.index(0, output.0)] += 1.0;
+        if let Some(reference) = output.1 {
+            e[self.index(0, reference)] -= 1.0;
+        }
+        for m in self.sideband_range() {
+            e[self.index(m, self.gnd)] = Complex64::new(0.0, 0.0);
+        }
+        let mut z = vec![Complex64::new(0.0, 0.0); total];
+        solver.prepare(total);
+        if !solver.solve(&tp, &ti, &tx, &e, &mut z) {
+            return None;
+        }
+
+        let k = self.sidebands as i32;
+        let densities = sources
+            .iter()
+            .zip(modulations)
+            .map(|(source, modulation)| {
+                let (a, b) = source.nodes;
+                let transfer: Vec<Complex64> =
+                    self.sideband_range().map(|m| z[self.index(m, a)] - z[self.index(m, b)]).collect();
+                self.sideband_range()
+                    .map(|l| {
+                        let shifted = (freq + l as f64 * self.fundamental).abs();
+                        if shifted == 0.0 && source.exponent > 0.0 {
+                            return 0.0;
+                        }
+                        let gain: Complex64 = self
+                            .sideband_range()
+                            .map(|m| transfer[(m + k) as usize] * modulation[(m - l + 2 * k) as usize])
+                            .sum();
+                        let spectrum = if source.exponent == 0.0 { 1.0 } else { shifted.powf(-source.exponent) };
+                        gain.norm_sqr() * spectrum
+                    })
+                    .sum()
+            })
+            .collect();
+        let gain = self
+            .excitation
+            .iter()
+            .enumerate()
+            .map(|(row, u)| z[self.index(0, row)] * u)
+            .sum::<Complex64>()
+            .norm_sqr();
+        Some((densities, gain))
+    }
+
+    /// 沿周期稳态取各器件的噪声源: 返回 k=0 处的噪声源 (密度归一为 1) 与各源调制
+    /// sqrt(S(t_k)) 的谐波 M_p, p ∈ [-2K, 2K]
+    pub fn noise_modulation(&self, instances: &[Instance], waveform: &[Vec<f64>]) -> (Vec<NoiseSource>, Vec<Vec<Complex64>>) {
+        let mut sources: Vec<NoiseSource> = Vec::new();
+        let mut samples: Vec<Vec<Complex64>> = Vec::new();
+        for (k, x) in waveform.iter().enumerate() {
+            let point: Vec<NoiseSource> = instances.iter().flat_map(|inst| noise_sources(inst, x)).collect();
+            if k == 0 {
+                sources = point.iter().map(|s| NoiseSource { density: 1.0, ..s.clone() }).collect();
+                samples = vec![vec![Complex64::new(0.0, 0.0); waveform.len()]; sources.len()];
+            }
+            for (series, source) in samples.iter_mut().zip(&point) {
+                series[k] = Complex64::new(source.density.max(0.0).sqrt(), 0.0);
+            }
+        }
+        let modulations = samples.iter().map(|series| harmonics(series, 2 * self.sidebands)).collect();
+        (sources, modulations)
+    }
+}
+
+/// 在解 `x` 处以角频率 `omega` 盖章所有器件
+fn stamp_matrix(instances: &[Instance], node_count: usize, x: &[f64], omega: f64) -> ComplexMnaBuilder {
+    let mut mna = ComplexMnaBuilder::new(node_count);
+    for inst in instances {
+        let mut ctx = mna.context(omega);
+        let _ = inst.stamp_ac(&mut ctx, x);
+    }
+    mna
+}
+
+/// 合并重复位置后的矩阵元
+fn collect_entries(builder: &ComplexSparseBuilder) -> BTreeMap<(usize, usize), Complex64> {
+    let mut entries = BTreeMap::new();
+    for (col, column) in builder.col_entries.iter().enumerate() {
+        for (row, value) in column {
+            *entries.entry((*row, col)).or_insert(Complex64::new(0.0, 0.0)) += value;
+        }
+    }
+    entries
+}
+
+/// 周期采样的傅里叶系数 X_p = (1/N)·Σ_k x_k·e^{-j2πpk/N}, p ∈ [-max, max], 下标 p + max
+fn harmonics(samples: &[Complex64], max: usize) -> Vec<Complex64> {
+    let n = samples.len() as f64;
+    let mut coefficients: Vec<Complex64> = (-(max as i64)..=max as i64)
+        .map(|p| {
+            samples
+                .iter()
+                .enumerate()
+                .map(|(k, x)| x * Complex64::from_polar(1.0, -2.0 * PI * p as f64 * k as f64 / n))
+                .sum::<Complex64>()
+                / n
+        })
+        .collect();
+    let peak = coefficients.iter().map(|c| c.norm()).fold(0.0, f64::max);
+    for c in &mut coefficients {
+        if c.norm() <= HARMONIC_FLOOR * peak {
+            *c = Complex64::new(0.0, 0.0);
+        }
+    }
+    coefficients
+}
+
+/// CSC 矩阵的转置, 仍按 CSC 存放
+pub(crate) fn transpose_csc(ap: &[i64], ai: &[i64], ax: &[Complex64]) -> (Vec<i64>, Vec<i64>, Vec<Complex64>) {
+    let n = ap.len() - 1;
+    let mut tp = vec![0i64; n + 1];
+    for &row in ai {
+        tp[row as usize + 1] += 1;
+    }
+    for col in 0..n {
+        tp[col + 1] += tp[col];
+    }
+    let mut next = tp.clone();
+    let mut ti = vec![0i64; ai.len()];
+    let mut tx = vec![Complex64::new(0.0, 0.0); ax.len()];
+    for col in 0..n {
+        for k in ap[col] as usize..ap[col + 1] as usize {
+            let row = ai[k] as usize;
+            let dst = next[row] as usize;
+            ti[dst] = col as i64;
+            tx[dst] = ax[k];
+            next[row] += 1;
+        }
+    }
+    (tp, ti, tx)
+}
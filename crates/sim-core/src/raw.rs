@@ -3,51 +3,79 @@
 //! This module provides functions to write simulation results in the ngspice raw format,
 //! which is compatible with ngspice, ltspice, gwave, and other SPICE waveform viewers.
 
-use crate::result_store::RunResult;
+use crate::noise::NoiseResult;
+use crate::result_store::{AnalysisType, RunResult};
+use num_complex::Complex64;
 use std::f64::consts::PI;
 use std::fs;
 use std::path::Path;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Write operating point analysis result to ngspice raw format.
-pub fn write_raw_op(run: &RunResult, path: &Path, precision: usize) -> std::io::Result<()> {
-    let mut out = String::new();
-
-    // Filter out ground node
-    let filtered_names: Vec<_> = run.node_names.iter().filter(|n| *n != "0").collect();
-    let filtered_values: Vec<_> = run
-        .node_names
-        .iter()
-        .enumerate()
-        .filter(|(_, n)| *n != "0")
-        .map(|(i, _)| run.solution.get(i).copied().unwrap_or(0.0))
-        .collect();
-
-    write_raw_header(
-        &mut out,
-        "Operating Point",
-        "real",
-        filtered_names.len(),
-        1,
-    );
-
-    // Variables section
-    out.push_str("Variables:\n");
-    for (idx, name) in filtered_names.iter().enumerate() {
-        // Check for current variable names: I(source) or #branch patterns
-        let is_current = name.starts_with("I(") || name.starts_with("i(") || name.contains("#branch");
-        let var_type = if is_current { "current" } else { "voltage" };
-        out.push_str(&format!("\t{}\tv({})\t{}\n", idx, name, var_type));
-    }
+/// A vector in the raw file: name, type and index into the solution vectors.
+struct Variable {
+    name: String,
+    kind: &'static str,
+    index: usize,
+}
 
-    // Values section
-    out.push_str("Values:\n");
-    out.push_str(&format!(" 0\t{}\n", format_real(filtered_values[0], precision)));
-    for value in filtered_values.iter().skip(1) {
-        out.push_str(&format!("\t{}\n", format_real(*value, precision)));
+/// Write a run in the ngspice ASCII raw format, choosing the plot from the analysis:
+/// operating point, DC transfer characteristic, AC (complex, from the exact phasors),
+/// transient (a PSS period is written as a transient) and noise spectral density.
+/// Node voltages `v(<node>)` are followed by the branch currents `i(<device>)` that
+/// the solution vectors carry; internal auxiliary unknowns are left out.
+pub fn write_raw(run: &RunResult, path: &Path, precision: usize) -> std::io::Result<()> {
+    if let (AnalysisType::Noise | AnalysisType::Pnoise, Some(noise)) = (run.analysis, &run.noise) {
+        return fs::write(path, render_noise(noise, precision));
     }
+    let out = match run.analysis {
+        AnalysisType::Dc => {
+            let source = run.sweep_var.as_deref().unwrap_or("sweep");
+            render_real(
+                "DC transfer characteristic",
+                Some((source.to_lowercase(), sweep_kind(source))),
+                &run.sweep_values,
+                &run_variables(run, &run.sweep_solutions),
+                &run.sweep_solutions,
+                precision,
+            )
+        }
+        AnalysisType::Tran | AnalysisType::Pss => render_real(
+            "Transient Analysis",
+            Some(("time".to_string(), "time")),
+            &run.tran_times,
+            &run_variables(run, &run.tran_solutions),
+            &run.tran_solutions,
+            precision,
+        ),
+        AnalysisType::Ac | AnalysisType::Pac if !run.ac_phasors.is_empty() => {
+            let widths: Vec<Vec<f64>> = run.ac_phasors.iter().map(|row| vec![0.0; row.len()]).collect();
+            render_complex(&run.ac_frequencies, &run_variables(run, &widths), &run.ac_phasors, precision)
+        }
+        AnalysisType::Ac | AnalysisType::Pac => {
+            let rows: Vec<Vec<Complex64>> = run
+                .ac_solutions
+                .iter()
+                .map(|row| row.iter().map(|&(mag_db, phase_deg)| db_phase_to_complex(mag_db, phase_deg)).collect())
+                .collect();
+            render_complex(&run.ac_frequencies, &node_variables(&run.node_names), &rows, precision)
+        }
+        _ => render_real(
+            "Operating Point",
+            None,
+            &[],
+            &run_variables(run, std::slice::from_ref(&run.solution)),
+            std::slice::from_ref(&run.solution),
+            precision,
+        ),
+    };
+    fs::write(path, out)
+}
 
+/// Write operating point analysis result to ngspice raw format.
+pub fn write_raw_op(run: &RunResult, path: &Path, precision: usize) -> std::io::Result<()> {
+    let rows = std::slice::from_ref(&run.solution);
+    let out = render_real("Operating Point", None, &[], &run_variables(run, rows), rows, precision);
     fs::write(path, out)
 }
 
@@ -60,44 +88,14 @@ pub fn write_raw_sweep(
     path: &Path,
     precision: usize,
 ) -> std::io::Result<()> {
-    let mut out = String::new();
-
-    // Filter out ground node from node_names
-    let filtered_names: Vec<_> = node_names.iter().filter(|n| *n != "0").collect();
-    let num_vars = 1 + filtered_names.len(); // sweep variable + node voltages
-
-    write_raw_header(
-        &mut out,
+    let out = render_real(
         "DC transfer characteristic",
-        "real",
-        num_vars,
-        sweep_values.len(),
+        Some((source.to_lowercase(), sweep_kind(source))),
+        sweep_values,
+        &node_variables(node_names),
+        sweep_results,
+        precision,
     );
-
-    // Variables section
-    out.push_str("Variables:\n");
-    out.push_str(&format!("\t0\t{}\tvoltage\n", source.to_lowercase()));
-    for (idx, name) in filtered_names.iter().enumerate() {
-        out.push_str(&format!("\t{}\tv({})\tvoltage\n", idx + 1, name));
-    }
-
-    // Values section
-    out.push_str("Values:\n");
-    for (point_idx, sweep_val) in sweep_values.iter().enumerate() {
-        // First line has point index and sweep value
-        out.push_str(&format!(" {}\t{}\n", point_idx, format_real(*sweep_val, precision)));
-
-        // Subsequent lines have node values
-        if let Some(solution) = sweep_results.get(point_idx) {
-            for (node_idx, name) in node_names.iter().enumerate() {
-                if name != "0" {
-                    let val = solution.get(node_idx).copied().unwrap_or(0.0);
-                    out.push_str(&format!("\t{}\n", format_real(val, precision)));
-                }
-            }
-        }
-    }
-
     fs::write(path, out)
 }
 
@@ -109,44 +107,14 @@ pub fn write_raw_tran(
     path: &Path,
     precision: usize,
 ) -> std::io::Result<()> {
-    let mut out = String::new();
-
-    // Filter out ground node
-    let filtered_names: Vec<_> = node_names.iter().filter(|n| *n != "0").collect();
-    let num_vars = 1 + filtered_names.len(); // time + node voltages
-
-    write_raw_header(
-        &mut out,
+    let out = render_real(
         "Transient Analysis",
-        "real",
-        num_vars,
-        times.len(),
+        Some(("time".to_string(), "time")),
+        times,
+        &node_variables(node_names),
+        solutions,
+        precision,
     );
-
-    // Variables section
-    out.push_str("Variables:\n");
-    out.push_str("\t0\ttime\ttime\n");
-    for (idx, name) in filtered_names.iter().enumerate() {
-        out.push_str(&format!("\t{}\tv({})\tvoltage\n", idx + 1, name));
-    }
-
-    // Values section
-    out.push_str("Values:\n");
-    for (point_idx, time) in times.iter().enumerate() {
-        // First line has point index and time value
-        out.push_str(&format!(" {}\t{}\n", point_idx, format_real(*time, precision)));
-
-        // Subsequent lines have node values
-        if let Some(solution) = solutions.get(point_idx) {
-            for (node_idx, name) in node_names.iter().enumerate() {
-                if name != "0" {
-                    let val = solution.get(node_idx).copied().unwrap_or(0.0);
-                    out.push_str(&format!("\t{}\n", format_real(val, precision)));
-                }
-            }
-        }
-    }
-
     fs::write(path, out)
 }
 
@@ -161,51 +129,143 @@ pub fn write_raw_ac(
     path: &Path,
     precision: usize,
 ) -> std::io::Result<()> {
-    let mut out = String::new();
+    let rows: Vec<Vec<Complex64>> = ac_solutions
+        .iter()
+        .map(|row| row.iter().map(|&(mag_db, phase_deg)| db_phase_to_complex(mag_db, phase_deg)).collect())
+        .collect();
+    let out = render_complex(frequencies, &node_variables(node_names), &rows, precision);
+    fs::write(path, out)
+}
 
-    // Filter out ground node
-    let filtered_names: Vec<_> = node_names.iter().filter(|n| *n != "0").collect();
-    let num_vars = 1 + filtered_names.len(); // frequency + node voltages
+/// Render the output / equivalent input noise spectra in V/sqrt(Hz) as ngspice does
+/// (A/sqrt(Hz) for a current input source).
+fn render_noise(noise: &NoiseResult, precision: usize) -> String {
+    let input_kind = if noise.source.to_ascii_lowercase().starts_with('i') { "current" } else { "voltage" };
+    let variables = [
+        Variable { name: "onoise_spectrum".to_string(), kind: "voltage", index: 0 },
+        Variable { name: "inoise_spectrum".to_string(), kind: input_kind, index: 1 },
+    ];
+    let rows: Vec<Vec<f64>> = noise
+        .output_density
+        .iter()
+        .zip(&noise.input_density)
+        .map(|(output, input)| vec![output.sqrt(), input.sqrt()])
+        .collect();
+    render_real(
+        "Noise Spectral Density Curves",
+        Some(("frequency".to_string(), "frequency")),
+        &noise.frequencies,
+        &variables,
+        &rows,
+        precision,
+    )
+}
 
-    write_raw_header(
-        &mut out,
-        "AC Analysis",
-        "complex",
-        num_vars,
-        frequencies.len(),
-    );
+/// Node voltages, ground excluded.
+fn node_variables(node_names: &[String]) -> Vec<Variable> {
+    node_names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| *name != "0")
+        .map(|(index, name)| Variable {
+            name: format!("v({})", name),
+            kind: "voltage",
+            index,
+        })
+        .collect()
+}
 
-    // Variables section
-    out.push_str("Variables:\n");
-    out.push_str("\t0\tfrequency\tfrequency\n");
-    for (idx, name) in filtered_names.iter().enumerate() {
-        out.push_str(&format!("\t{}\tv({})\tvoltage\n", idx + 1, name));
+/// Node voltages followed by the branch currents present in every row of `rows`.
+fn run_variables<T>(run: &RunResult, rows: &[Vec<T>]) -> Vec<Variable> {
+    let width = rows.iter().map(Vec::len).min().unwrap_or(0);
+    let mut variables = node_variables(&run.node_names);
+    for (offset, branch) in run.branch_names.iter().enumerate() {
+        let index = run.node_names.len() + offset;
+        let device = branch.strip_suffix("#i").unwrap_or(branch);
+        if index < width && !device.contains('#') {
+            variables.push(Variable {
+                name: format!("i({})", device),
+                kind: "current",
+                index,
+            });
+        }
     }
+    variables
+}
+
+/// Type of a DC sweep variable.
+fn sweep_kind(source: &str) -> &'static str {
+    if source.eq_ignore_ascii_case("temp") {
+        "temperature"
+    } else if source.to_ascii_lowercase().starts_with('i') {
+        "current"
+    } else {
+        "voltage"
+    }
+}
+
+/// Render a real plot. With a `scale` each point starts with the scale value;
+/// without one (operating point) the first variable goes on the point-index line.
+fn render_real(
+    plotname: &str,
+    scale: Option<(String, &str)>,
+    scale_values: &[f64],
+    variables: &[Variable],
+    rows: &[Vec<f64>],
+    precision: usize,
+) -> String {
+    let mut out = String::new();
+    let offset = usize::from(scale.is_some());
+    write_raw_header(&mut out, plotname, "real", offset + variables.len(), rows.len());
+    write_variables(&mut out, scale.as_ref().map(|(name, kind)| (name.as_str(), *kind)), variables);
+
+    out.push_str("Values:\n");
+    for (point, row) in rows.iter().enumerate() {
+        let mut values = variables.iter().map(|var| row.get(var.index).copied().unwrap_or(0.0));
+        let first = match scale {
+            Some(_) => scale_values.get(point).copied(),
+            None => values.next(),
+        };
+        match first {
+            Some(value) => out.push_str(&format!(" {}\t{}\n", point, format_real(value, precision))),
+            None => out.push_str(&format!(" {}\n", point)),
+        }
+        for value in values {
+            out.push_str(&format!("\t{}\n", format_real(value, precision)));
+        }
+    }
+    out
+}
+
+/// Render a complex AC plot over `frequencies`.
+fn render_complex(frequencies: &[f64], variables: &[Variable], rows: &[Vec<Complex64>], precision: usize) -> String {
+    let mut out = String::new();
+    write_raw_header(&mut out, "AC Analysis", "complex", 1 + variables.len(), frequencies.len());
+    write_variables(&mut out, Some(("frequency", "frequency")), variables);
 
-    // Values section
     out.push_str("Values:\n");
-    for (point_idx, freq) in frequencies.iter().enumerate() {
-        // First line has point index and frequency (frequency is real, imag=0)
-        out.push_str(&format!(
-            " {}\t{}\n",
-            point_idx,
-            format_complex(*freq, 0.0, precision)
-        ));
-
-        // Subsequent lines have node values as complex
-        if let Some(solution) = ac_solutions.get(point_idx) {
-            for (node_idx, name) in node_names.iter().enumerate() {
-                if name != "0" {
-                    if let Some(&(mag_db, phase_deg)) = solution.get(node_idx) {
-                        let (real, imag) = db_phase_to_complex(mag_db, phase_deg);
-                        out.push_str(&format!("\t{}\n", format_complex(real, imag, precision)));
-                    }
-                }
+    for (point, freq) in frequencies.iter().enumerate() {
+        // The scale is written as a complex value with zero imaginary part
+        out.push_str(&format!(" {}\t{}\n", point, format_complex(*freq, 0.0, precision)));
+        if let Some(row) = rows.get(point) {
+            for var in variables {
+                let value = row.get(var.index).copied().unwrap_or_default();
+                out.push_str(&format!("\t{}\n", format_complex(value.re, value.im, precision)));
             }
         }
     }
+    out
+}
 
-    fs::write(path, out)
+fn write_variables(out: &mut String, scale: Option<(&str, &str)>, variables: &[Variable]) {
+    out.push_str("Variables:\n");
+    let offset = usize::from(scale.is_some());
+    if let Some((name, kind)) = scale {
+        out.push_str(&format!("\t0\t{}\t{}\n", name, kind));
+    }
+    for (idx, var) in variables.iter().enumerate() {
+        out.push_str(&format!("\t{}\t{}\t{}\n", idx + offset, var.name, var.kind));
+    }
 }
 
 /// Write the raw file header.
@@ -235,12 +295,10 @@ fn format_complex(real: f64, imag: f64, precision: usize) -> String {
 }
 
 /// Convert magnitude (dB) and phase (degrees) to complex representation.
-fn db_phase_to_complex(mag_db: f64, phase_deg: f64) -> (f64, f64) {
+fn db_phase_to_complex(mag_db: f64, phase_deg: f64) -> Complex64 {
     let magnitude = 10.0_f64.powf(mag_db / 20.0);
     let phase_rad = phase_deg * PI / 180.0;
-    let real = magnitude * phase_rad.cos();
-    let imag = magnitude * phase_rad.sin();
-    (real, imag)
+    Complex64::from_polar(magnitude, phase_rad)
 }
 
 /// Simple timestamp function without external dependency.
@@ -327,18 +385,18 @@ mod tests {
     #[test]
     fn test_db_phase_to_complex() {
         // 0 dB, 0 degrees should give (1, 0)
-        let (real, imag) = db_phase_to_complex(0.0, 0.0);
-        assert!((real - 1.0).abs() < 1e-10);
-        assert!(imag.abs() < 1e-10);
+        let value = db_phase_to_complex(0.0, 0.0);
+        assert!((value.re - 1.0).abs() < 1e-10);
+        assert!(value.im.abs() < 1e-10);
 
         // 0 dB, 90 degrees should give (0, 1)
-        let (real, imag) = db_phase_to_complex(0.0, 90.0);
-        assert!(real.abs() < 1e-10);
-        assert!((imag - 1.0).abs() < 1e-10);
+        let value = db_phase_to_complex(0.0, 90.0);
+        assert!(value.re.abs() < 1e-10);
+        assert!((value.im - 1.0).abs() < 1e-10);
 
         // -20 dB should give magnitude of 0.1
-        let (real, imag) = db_phase_to_complex(-20.0, 0.0);
-        assert!((real - 0.1).abs() < 1e-10);
-        assert!(imag.abs() < 1e-10);
+        let value = db_phase_to_complex(-20.0, 0.0);
+        assert!((value.re - 0.1).abs() < 1e-10);
+        assert!(value.im.abs() < 1e-10);
     }
 }
@@ -1,3 +1,13 @@
+use crate::measure::Measurement;
+use crate::noise::NoiseResult;
+use crate::periodic::PacResult;
+use crate::topology::SingularMatrixReport;
+use crate::touchstone::Touchstone;
+use crate::newton::{successful_strategy, DcStrategy, HomotopyStep};
+use num_complex::Complex64;
+
+pub use sim_devices::bsim::MosRegion;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RunId(pub usize);
 
@@ -7,6 +17,16 @@ pub enum AnalysisType {
     Dc,
     Tran,
     Ac,
+    /// 周期稳态: 一个周期的波形存放在 `tran_times` / `tran_solutions` 中
+    Pss,
+    /// S 参数: 结果存放在 `sparams` 中
+    Sp,
+    /// 噪声分析: 结果存放在 `noise` 中
+    Noise,
+    /// 周期 AC: 边带 0 存放在 `ac_frequencies` / `ac_phasors` 中, 全部边带在 `pac` 中
+    Pac,
+    /// 周期噪声: 结果存放在 `noise` 中
+    Pnoise,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -32,7 +52,11 @@ pub struct RunResult {
     pub sweep_values: Vec<f64>,
     /// DC sweep: solution vectors at each sweep point
     pub sweep_solutions: Vec<Vec<f64>>,
-    /// TRAN analysis: time points
+    /// Nested DC sweep: name of the outer swept variable
+    pub sweep_outer_var: Option<String>,
+    /// Nested DC sweep: outer value at each sweep point (parallel to `sweep_values`)
+    pub sweep_outer_values: Vec<f64>,
+    /// TRAN analysis: time points (PSS: one steady-state period)
     pub tran_times: Vec<f64>,
     /// TRAN analysis: solution vectors at each time point
     pub tran_solutions: Vec<Vec<f64>>,
@@ -41,16 +65,233 @@ pub struct RunResult {
     /// AC analysis: complex solutions at each frequency point
     /// Each inner Vec contains (magnitude_dB, phase_deg) pairs for each node
     pub ac_solutions: Vec<Vec<(f64, f64)>>,
+    /// AC analysis: raw complex solution vector at each frequency point, node voltages
+    /// followed by branch currents in `branch_names` order
+    pub ac_phasors: Vec<Vec<Complex64>>,
+    /// `.csparam` / 引用 `v()` 的 `.param` 按本次结果算出的输出参数, 按定义顺序
+    pub output_params: Vec<(String, f64)>,
+    /// 作用于本分析的 `.measure` 结果, 按网表顺序
+    pub measurements: Vec<Measurement>,
+    /// 解向量中排在节点电压之后的辅助变量 (支路电流) 名称; 电流探针名为 `{器件}#i`
+    pub branch_names: Vec<String>,
+    /// OP 收敛后各非线性器件的工作点, 按实例顺序
+    pub device_op: Vec<DeviceOpInfo>,
+    /// 工作点求解的同伦轨迹 (直接 Newton、gmin 步进、源步进中的每次 Newton 求解)
+    pub homotopy: Vec<HomotopyStep>,
+    /// Newton 不收敛导致失败时的诊断; 成功或其他原因失败时为 None
+    pub convergence: Option<ConvergenceReport>,
+    /// `.sp` 分析按端口号排列的 S 参数矩阵; 其他分析为 None
+    pub sparams: Option<Touchstone>,
+    /// `.noise` 分析的噪声谱与器件贡献; 其他分析为 None
+    pub noise: Option<NoiseResult>,
+    /// `.pac` 分析各边带上的复数解; 其他分析为 None
+    pub pac: Option<PacResult>,
+    /// Monte Carlo 中本次运行的统计参数抽样值 (参数名, 值); 其他运行为空
+    pub sampled_params: Vec<(String, f64)>,
+}
+
+/// 不收敛的 Newton 求解的诊断: 最后一次迭代中残差最大的节点、盖章变化最大的器件
+/// 与各节点的 |Δx|
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConvergenceReport {
+    /// 最后一次线性化点处 KCL 电流失配绝对值最大的节点及其残差 [A]
+    pub worst_residual: Option<(String, f64)>,
+    /// 最后两次线性化点之间矩阵项与右端项变化 (取绝对值最大的一项) 最大的器件及该变化量
+    pub largest_stamp_change: Option<(String, f64)>,
+    /// 最后一次迭代各节点的 |Δx|, 不含地节点
+    pub last_dx: Vec<(String, f64)>,
+    /// 矩阵分解失败 (奇异) 时对矩阵结构的诊断
+    pub singular: Option<SingularMatrixReport>,
+}
+
+impl ConvergenceReport {
+    /// 单行摘要, 用于 RunResult 的 message
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(singular) = &self.singular {
+            parts.push(format!("singular matrix: {}", singular.summary()));
+        }
+        if let Some((node, value)) = &self.worst_residual {
+            parts.push(format!("worst residual at node {} ({:.3e} A)", node, value));
+        }
+        if let Some((device, change)) = &self.largest_stamp_change {
+            parts.push(format!("largest stamp change in {} ({:.3e})", device, change));
+        }
+        if let Some((node, dx)) = self.last_dx.iter().max_by(|a, b| a.1.total_cmp(&b.1)) {
+            parts.push(format!("largest |dx| at node {} ({:.3e})", node, dx));
+        }
+        parts.join("; ")
+    }
+}
+
+/// 单个器件在工作点的电流与小信号参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceOpInfo {
+    pub name: String,
+    pub kind: DeviceOpKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceOpKind {
+    /// 漏源电流、跨导、输出电导、衬底跨导与有效阈值电压
+    Mosfet {
+        ids: f64,
+        gm: f64,
+        gds: f64,
+        gmbs: f64,
+        vth: f64,
+        region: MosRegion,
+    },
+    /// 结电流 (阳极流向阴极) 与结电导
+    Diode { id: f64, gd: f64 },
+}
+
+impl DeviceOpInfo {
+    /// 器件类别名: "mosfet" / "diode"
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            DeviceOpKind::Mosfet { .. } => "mosfet",
+            DeviceOpKind::Diode { .. } => "diode",
+        }
+    }
+
+    /// MOSFET 的工作区; 其他器件为 None
+    pub fn region(&self) -> Option<MosRegion> {
+        match self.kind {
+            DeviceOpKind::Mosfet { region, .. } => Some(region),
+            DeviceOpKind::Diode { .. } => None,
+        }
+    }
+
+    /// 数值量 (名称, 取值), 按打印顺序
+    pub fn values(&self) -> Vec<(&'static str, f64)> {
+        match self.kind {
+            DeviceOpKind::Mosfet { ids, gm, gds, gmbs, vth, .. } => {
+                vec![("ids", ids), ("gm", gm), ("gds", gds), ("gmbs", gmbs), ("vth", vth)]
+            }
+            DeviceOpKind::Diode { id, gd } => vec![("id", id), ("gd", gd)],
+        }
+    }
+}
+
+impl RunResult {
+    /// 器件的工作点信息
+    pub fn device_op(&self, device: &str) -> Option<&DeviceOpInfo> {
+        self.device_op.iter().find(|info| info.name.eq_ignore_ascii_case(device))
+    }
+
+    /// 求得工作点所用的策略 (直接 Newton、gmin 步进、源步进或伪瞬态);
+    /// 没有工作点记录或所有策略都失败时为 None
+    pub fn dc_strategy(&self) -> Option<DcStrategy> {
+        successful_strategy(&self.homotopy)
+    }
+
+    /// 器件的支路电流 (流入第一个端口): 取电流探针 `{device}#i`, 没有时取器件自身的辅助变量
+    /// (电压源、受控电压源等)。`solution` 是本结果中的任一解向量 (OP 解、扫描点或瞬态时间点)
+    pub fn branch_current(&self, device: &str, solution: &[f64]) -> Option<f64> {
+        solution.get(self.branch_index(device)?).copied()
+    }
+
+    /// AC 第 `point` 个频率点处节点电压的复数值
+    pub fn ac_voltage(&self, node: &str, point: usize) -> Option<Complex64> {
+        let idx = self.node_names.iter().position(|name| name.eq_ignore_ascii_case(node))?;
+        self.ac_phasors.get(point)?.get(idx).copied()
+    }
+
+    /// AC 第 `point` 个频率点处器件支路电流的复数值, 支路的查找同 [`Self::branch_current`]
+    pub fn ac_branch_current(&self, device: &str, point: usize) -> Option<Complex64> {
+        self.ac_phasors.get(point)?.get(self.branch_index(device)?).copied()
+    }
+
+    /// 器件支路电流在解向量中的下标
+    fn branch_index(&self, device: &str) -> Option<usize> {
+        let probe = format!("{}#i", device);
+        let find = |name: &str| self.branch_names.iter().position(|branch| branch.eq_ignore_ascii_case(name));
+        let idx = find(&probe).or_else(|| find(device))?;
+        Some(self.node_names.len() + idx)
+    }
+
+    /// 嵌套 DC 扫描按外层取值分组, 返回每条曲线的 (外层值, 点索引范围)。
+    /// 单层扫描返回一条曲线, 外层值为 NaN。
+    pub fn sweep_curves(&self) -> Vec<(f64, std::ops::Range<usize>)> {
+        if self.sweep_outer_values.is_empty() {
+            if self.sweep_values.is_empty() {
+                return Vec::new();
+            }
+            return vec![(f64::NAN, 0..self.sweep_values.len())];
+        }
+        let mut curves: Vec<(f64, std::ops::Range<usize>)> = Vec::new();
+        for (idx, outer) in self.sweep_outer_values.iter().enumerate() {
+            match curves.last_mut() {
+                Some((value, range)) if *value == *outer && range.end == idx => range.end = idx + 1,
+                _ => curves.push((*outer, idx..idx + 1)),
+            }
+        }
+        curves
+    }
+}
+
+/// `.step` 多维扫描: 每个取值组合对应一次内层分析。
+/// 第一维为最外层, `runs` 按行优先排列 (最后一维变化最快)
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepSweep {
+    /// 各维的扫描变量名 (源名、参数名或 "temp")
+    pub vars: Vec<String>,
+    /// 各维的取值
+    pub values: Vec<Vec<f64>>,
+    pub runs: Vec<RunId>,
+}
+
+impl StepSweep {
+    /// 各维的点数
+    pub fn shape(&self) -> Vec<usize> {
+        self.values.iter().map(Vec::len).collect()
+    }
+
+    /// 按各维下标取结果
+    pub fn run_at(&self, indices: &[usize]) -> Option<RunId> {
+        if indices.len() != self.values.len() {
+            return None;
+        }
+        let mut flat = 0;
+        for (idx, values) in indices.iter().zip(&self.values) {
+            if *idx >= values.len() {
+                return None;
+            }
+            flat = flat * values.len() + idx;
+        }
+        self.runs.get(flat).copied()
+    }
+
+    /// 第 `flat` 次运行对应的各维取值
+    pub fn point(&self, mut flat: usize) -> Vec<f64> {
+        let mut point = vec![0.0; self.values.len()];
+        for (dim, values) in self.values.iter().enumerate().rev() {
+            point[dim] = values[flat % values.len()];
+            flat /= values.len();
+        }
+        point
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ResultStore {
     pub runs: Vec<RunResult>,
+    pub step_sweeps: Vec<StepSweep>,
 }
 
 impl ResultStore {
     pub fn new() -> Self {
-        Self { runs: Vec::new() }
+        Self {
+            runs: Vec::new(),
+            step_sweeps: Vec::new(),
+        }
+    }
+
+    /// 登记一次 `.step` 扫描, 返回其在 `step_sweeps` 中的下标
+    pub fn add_step_sweep(&mut self, sweep: StepSweep) -> usize {
+        self.step_sweeps.push(sweep);
+        self.step_sweeps.len() - 1
     }
 
     pub fn add_run(&mut self, mut run: RunResult) -> RunId {
@@ -67,6 +308,16 @@ impl ResultStore {
             .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "run not found"))?;
         crate::psf::write_psf_text(run, path, precision)
     }
+
+    /// 把 `.sp` 结果写成 Touchstone 文件
+    pub fn write_touchstone(&self, id: RunId, path: &std::path::Path, precision: usize) -> std::io::Result<()> {
+        let sparams = self
+            .runs
+            .get(id.0)
+            .and_then(|run| run.sparams.as_ref())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no S-parameter run"))?;
+        crate::touchstone::write_touchstone(sparams, path, precision)
+    }
 }
 
 pub fn debug_dump_result_store(store: &ResultStore) {
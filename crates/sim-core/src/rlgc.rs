@@ -0,0 +1,103 @@
+//! Coupled multi-conductor lossy line (W element) described by
+//! per-unit-length RLGC matrices.
+//!
+//! The `.model name w` card gives the N×N matrices entry by entry as
+//! `rIJ`, `lIJ`, `gIJ` and `cIJ` (1-based conductor indices, symmetric, so
+//! either `l12` or `l21` may be used; missing entries are zero). G and C are
+//! Maxwell matrices as in HSPICE: the diagonal holds the total capacitance of
+//! a conductor, off-diagonal entries are the negated mutual terms. The
+//! reference conductor is ideal.
+//!
+//! The simulator realises the line as `NSEG` identical lumped sections, each
+//! a coupled series R + L branch between two sets of conductor nodes, with
+//! the shunt G + C of a section split half and half onto its two ends.
+
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+
+use crate::netlist::parse_number_with_suffix;
+use crate::touchstone::invert;
+
+/// Default number of lumped sections
+pub const DEFAULT_SEGMENTS: usize = 20;
+
+/// Resistance added to each series branch so that a lossless line stays a
+/// finite conductance at DC [Ohm]
+const SHORT_RESISTANCE: f64 = 1e-9;
+
+/// RLGC matrices, row-major N×N
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rlgc {
+    pub conductors: usize,
+    /// Series resistance [Ohm/m]
+    pub r: Vec<f64>,
+    /// Series inductance [H/m]
+    pub l: Vec<f64>,
+    /// Shunt conductance [S/m]
+    pub g: Vec<f64>,
+    /// Shunt capacitance [F/m]
+    pub c: Vec<f64>,
+}
+
+impl Rlgc {
+    /// Read the matrices from merged instance / model parameters
+    pub fn from_params(params: &HashMap<String, String>, conductors: usize) -> Self {
+        let matrix = |prefix: char| {
+            let mut out = vec![0.0; conductors * conductors];
+            for i in 0..conductors {
+                for j in 0..conductors {
+                    let value = [(i, j), (j, i)].iter().find_map(|(a, b)| {
+                        params
+                            .get(&format!("{}{}{}", prefix, a + 1, b + 1))
+                            .and_then(|v| parse_number_with_suffix(v).or_else(|| v.parse().ok()))
+                    });
+                    out[i * conductors + j] = value.unwrap_or(0.0);
+                }
+            }
+            out
+        };
+        Self {
+            conductors,
+            r: matrix('r'),
+            l: matrix('l'),
+            g: matrix('g'),
+            c: matrix('c'),
+        }
+    }
+
+    /// Lumped values of one section for a line of `length` metres cut into `segments`
+    pub fn section(&self, length: f64, segments: usize) -> Self {
+        let scale = |m: &[f64]| m.iter().map(|v| v * length / segments as f64).collect();
+        Self {
+            conductors: self.conductors,
+            r: scale(&self.r),
+            l: scale(&self.l),
+            g: scale(&self.g),
+            c: scale(&self.c),
+        }
+    }
+
+    /// Admittance of the series branch, (R + sL)^-1
+    pub fn series_admittance(&self, s: Complex64) -> Option<Vec<Complex64>> {
+        let n = self.conductors;
+        let z: Vec<Complex64> = (0..n * n)
+            .map(|idx| {
+                let short = if idx % (n + 1) == 0 { SHORT_RESISTANCE } else { 0.0 };
+                Complex64::new(self.r[idx] + short, 0.0) + s * self.l[idx]
+            })
+            .collect();
+        invert(&z, n)
+    }
+
+    /// Admittance of the shunt element, G + sC
+    pub fn shunt_admittance(&self, s: Complex64) -> Vec<Complex64> {
+        self.g.iter().zip(&self.c).map(|(g, c)| Complex64::new(*g, 0.0) + s * c).collect()
+    }
+}
+
+/// Matrix-vector product for a row-major N×N matrix
+pub fn mat_vec(matrix: &[f64], v: &[f64]) -> Vec<f64> {
+    let n = v.len();
+    (0..n).map(|i| (0..n).map(|j| matrix[i * n + j] * v[j]).sum()).collect()
+}
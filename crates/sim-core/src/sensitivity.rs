@@ -0,0 +1,73 @@
+//! 瞬态灵敏度分析
+//!
+//! 直接法: 只跑一次标称瞬态, 沿它接受的每个时间步在收敛解处分解 Jacobian J, 解
+//! J·s = −dF/dp 得解向量灵敏度 s = ∂x/∂p, 输出灵敏度取 s 的相应分量. 残差对参数的
+//! 导数 dF/dp 由参数取 p ± h (h = delta·|p|, p 为 0 时 h = delta) 的电路在同一解处的
+//! 残差中心差分得到, 两侧电路的积分历史沿 x ± h·s 推进, 因此含历史项随参数的变化.
+
+use std::fmt::Write as _;
+
+/// 求 dF/dp 时缺省的相对扰动步长
+pub const DEFAULT_DELTA: f64 = 1e-3;
+
+/// 瞬态输出对参数的灵敏度波形
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranSensitivity {
+    /// 标称瞬态运行的时间点
+    pub times: Vec<f64>,
+    /// 输出信号, 如 `v(out)` / `i(V1)`
+    pub outputs: Vec<String>,
+    /// 参数名, 写法与 DC 扫描相同
+    pub params: Vec<String>,
+    /// 各参数的标称值
+    pub nominal: Vec<f64>,
+    /// `values[o][p][k]`: 时刻 `times[k]` 处输出 o 对参数 p 的偏导数 ∂y/∂p
+    pub values: Vec<Vec<Vec<f64>>>,
+}
+
+impl TranSensitivity {
+    fn position(&self, output: &str, param: &str) -> Option<(usize, usize)> {
+        let o = self.outputs.iter().position(|name| name.eq_ignore_ascii_case(output))?;
+        let p = self.params.iter().position(|name| name.eq_ignore_ascii_case(param))?;
+        Some((o, p))
+    }
+
+    /// 输出 `output` 对参数 `param` 的偏导数波形 ∂y/∂p
+    pub fn sensitivity(&self, output: &str, param: &str) -> Option<&[f64]> {
+        let (o, p) = self.position(output, param)?;
+        Some(&self.values[o][p])
+    }
+
+    /// 归一化灵敏度 p·∂y/∂p: 参数相对变化 100% 时输出的一阶变化量, 便于比较不同量纲的参数
+    pub fn normalized(&self, output: &str, param: &str) -> Option<Vec<f64>> {
+        let (o, p) = self.position(output, param)?;
+        Some(self.values[o][p].iter().map(|value| value * self.nominal[p]).collect())
+    }
+
+    /// 每个 (输出, 参数) 一行: 归一化灵敏度绝对值最大处的时刻与取值
+    pub fn peak_table(&self, precision: usize) -> String {
+        let mut table = String::new();
+        let _ = writeln!(table, "{:<12} {:<12} {:>14} {:>14}", "output", "param", "time", "p*dy/dp");
+        for (o, output) in self.outputs.iter().enumerate() {
+            for (p, param) in self.params.iter().enumerate() {
+                let peak = self.values[o][p]
+                    .iter()
+                    .enumerate()
+                    .map(|(k, value)| (k, value * self.nominal[p]))
+                    .fold((0, 0.0_f64), |best, item| if item.1.abs() > best.1.abs() { item } else { best });
+                let time = self.times.get(peak.0).copied().unwrap_or(0.0);
+                let _ = writeln!(table, "{:<12} {:<12} {:>14.*e} {:>14.*e}", output, param, precision, time, precision, peak.1);
+            }
+        }
+        table
+    }
+}
+
+/// 参数 `nominal` 的扰动步长
+pub fn perturbation(nominal: f64, delta: f64) -> f64 {
+    if nominal == 0.0 {
+        delta
+    } else {
+        delta * nominal.abs()
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Debug)]
+use num_complex::Complex64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SolverError {
     AnalyzeFailed,
     FactorFailed,
@@ -8,9 +10,13 @@ pub enum SolverError {
 /// 求解器类型选择
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum SolverType {
+    /// 按矩阵规模选择: 小于 [`SPARSE_THRESHOLD`] 用稠密 LU, 否则用稀疏 LU
     #[default]
+    Auto,
     Dense,
     Klu,
+    /// 纯 Rust 稀疏 LU, 不依赖 SuiteSparse
+    SparseLu,
 }
 
 pub trait LinearSolver {
@@ -24,6 +30,7 @@ pub trait LinearSolver {
 /// 根据 SolverType 创建对应的求解器
 pub fn create_solver(solver_type: SolverType, n: usize) -> Box<dyn LinearSolver> {
     match solver_type {
+        SolverType::Auto => Box::new(AutoSolver::new(n)),
         SolverType::Dense => Box::new(DenseSolver::new(n)),
         SolverType::Klu => {
             #[cfg(feature = "klu")]
@@ -32,10 +39,70 @@ pub fn create_solver(solver_type: SolverType, n: usize) -> Box<dyn LinearSolver>
             }
             #[cfg(not(feature = "klu"))]
             {
-                eprintln!("Warning: KLU not available, falling back to Dense solver");
-                Box::new(DenseSolver::new(n))
+                eprintln!("Warning: KLU not available, falling back to native sparse LU solver");
+                Box::new(SparseLuSolver::new(n))
             }
         }
+        SolverType::SparseLu => Box::new(SparseLuSolver::new(n)),
+    }
+}
+
+/// 自动选择时改用稀疏 LU 的矩阵维数; 更小的矩阵稠密 LU 更快
+pub const SPARSE_THRESHOLD: usize = 64;
+
+/// [`SolverType::Auto`] 的求解器: 每次 `prepare` 按维数在稠密与稀疏 LU 之间切换
+pub struct AutoSolver {
+    inner: Box<dyn LinearSolver>,
+    backend: SolverType,
+}
+
+impl AutoSolver {
+    pub fn new(n: usize) -> Self {
+        let backend = Self::backend_for(n);
+        Self {
+            inner: create_solver(backend, n),
+            backend,
+        }
+    }
+
+    fn backend_for(n: usize) -> SolverType {
+        if n < SPARSE_THRESHOLD {
+            SolverType::Dense
+        } else {
+            SolverType::SparseLu
+        }
+    }
+
+    /// 当前使用的后端 (`Dense` 或 `SparseLu`)
+    pub fn backend(&self) -> SolverType {
+        self.backend
+    }
+}
+
+impl LinearSolver for AutoSolver {
+    fn prepare(&mut self, n: usize) {
+        let backend = Self::backend_for(n);
+        if backend != self.backend {
+            self.inner = create_solver(backend, n);
+            self.backend = backend;
+        }
+        self.inner.prepare(n);
+    }
+
+    fn analyze(&mut self, ap: &[i64], ai: &[i64]) -> Result<(), SolverError> {
+        self.inner.analyze(ap, ai)
+    }
+
+    fn factor(&mut self, ap: &[i64], ai: &[i64], ax: &[f64]) -> Result<(), SolverError> {
+        self.inner.factor(ap, ai, ax)
+    }
+
+    fn solve(&mut self, rhs: &mut [f64]) -> Result<(), SolverError> {
+        self.inner.solve(rhs)
+    }
+
+    fn reset_pattern(&mut self) {
+        self.inner.reset_pattern();
     }
 }
 
@@ -166,6 +233,232 @@ impl LinearSolver for DenseSolver {
     fn reset_pattern(&mut self) {}
 }
 
+/// 对角元至少为本列最大候选值的这一比例时优先取对角元作主元, 以保持稀疏性
+const DIAGONAL_PIVOT_THRESHOLD: f64 = 0.1;
+
+/// 稀疏 LU 的元素类型: 实数 (直流/瞬态) 或复数 (AC)
+pub trait LuScalar:
+    Copy
+    + Default
+    + PartialEq
+    + std::fmt::Debug
+    + std::ops::AddAssign
+    + std::ops::SubAssign
+    + std::ops::Mul<Output = Self>
+    + std::ops::Div<Output = Self>
+{
+    /// 选主元用的模
+    fn magnitude(self) -> f64;
+}
+
+impl LuScalar for f64 {
+    fn magnitude(self) -> f64 {
+        self.abs()
+    }
+}
+
+impl LuScalar for Complex64 {
+    fn magnitude(self) -> f64 {
+        self.norm()
+    }
+}
+
+/// 纯 Rust 稀疏 LU: Gilbert–Peierls 左视算法, 逐列按 L 的图求出非零结构
+/// (深度优先的拓扑序) 后做稀疏三角求解, 并按阈值部分选主元 (优先对角元)。
+/// 分解为 PA = LU, 不做列重排; L 按原行号存放, U 按主元步存放
+#[derive(Debug)]
+pub struct SparseLu<T> {
+    pub n: usize,
+    /// L 的各列 (原行号, 值), 不含单位对角元
+    l_cols: Vec<Vec<(usize, T)>>,
+    /// U 的各列 (主元步, 值), 不含对角元
+    u_cols: Vec<Vec<(usize, T)>>,
+    u_diag: Vec<T>,
+    /// 第 k 步的主元行
+    pivot_row: Vec<usize>,
+}
+
+/// 实数稀疏 LU 求解器
+pub type SparseLuSolver = SparseLu<f64>;
+
+impl<T> Default for SparseLu<T> {
+    fn default() -> Self {
+        Self {
+            n: 0,
+            l_cols: Vec::new(),
+            u_cols: Vec::new(),
+            u_diag: Vec::new(),
+            pivot_row: Vec::new(),
+        }
+    }
+}
+
+impl<T: LuScalar> SparseLu<T> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            ..Self::default()
+        }
+    }
+
+    /// L 与 U 的非零元个数 (不含对角元)
+    pub fn factor_nnz(&self) -> usize {
+        self.l_cols.iter().chain(&self.u_cols).map(Vec::len).sum()
+    }
+
+    /// 在 L 的图中从 `rows` 出发深度优先搜索, 返回按拓扑序排列的可达行
+    fn reach(&self, rows: &[usize], pinv: &[Option<usize>], marked: &mut [bool]) -> Vec<usize> {
+        let mut postorder = Vec::new();
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        for &start in rows {
+            if marked[start] {
+                continue;
+            }
+            marked[start] = true;
+            stack.push((start, 0));
+            while let Some((row, next)) = stack.last_mut() {
+                let children = pinv[*row].map_or(&[][..], |step| &self.l_cols[step][..]);
+                if let Some(&(child, _)) = children.get(*next) {
+                    *next += 1;
+                    if !marked[child] {
+                        marked[child] = true;
+                        stack.push((child, 0));
+                    }
+                } else {
+                    postorder.push(*row);
+                    stack.pop();
+                }
+            }
+        }
+        postorder.reverse();
+        postorder
+    }
+
+    pub fn factorize(&mut self, ap: &[i64], ai: &[i64], ax: &[T]) -> Result<(), SolverError> {
+        let n = self.n;
+        if ap.len() != n + 1 {
+            return Err(SolverError::AnalyzeFailed);
+        }
+        self.l_cols = vec![Vec::new(); n];
+        self.u_cols = vec![Vec::new(); n];
+        self.u_diag = vec![T::default(); n];
+        self.pivot_row = vec![0; n];
+        let mut pinv: Vec<Option<usize>> = vec![None; n];
+        let mut x = vec![T::default(); n];
+        let mut marked = vec![false; n];
+        for k in 0..n {
+            // 散列 A 的第 k 列 (重复元相加)
+            let (start, end) = (ap[k] as usize, ap[k + 1] as usize);
+            let mut rows = Vec::with_capacity(end - start);
+            for idx in start..end {
+                let row = ai[idx] as usize;
+                if row < n {
+                    x[row] += ax[idx];
+                    rows.push(row);
+                }
+            }
+            let pattern = self.reach(&rows, &pinv, &mut marked);
+
+            // 稀疏三角求解 L x = A[:, k], 已选主元的行给出 U 的第 k 列
+            for &row in &pattern {
+                if let Some(step) = pinv[row] {
+                    let value = x[row];
+                    for &(i, l) in &self.l_cols[step] {
+                        x[i] -= l * value;
+                    }
+                }
+            }
+
+            let mut pivot: Option<usize> = None;
+            let mut max_abs = 0.0;
+            for &row in &pattern {
+                if pinv[row].is_none() && x[row].magnitude() > max_abs {
+                    max_abs = x[row].magnitude();
+                    pivot = Some(row);
+                }
+            }
+            let diagonal = x[k].magnitude();
+            if pinv[k].is_none() && marked[k] && diagonal != 0.0 && diagonal >= DIAGONAL_PIVOT_THRESHOLD * max_abs {
+                pivot = Some(k);
+            }
+            let Some(pivot) = pivot else {
+                for &row in &pattern {
+                    x[row] = T::default();
+                    marked[row] = false;
+                }
+                return Err(SolverError::FactorFailed);
+            };
+            let diag = x[pivot];
+            for &row in &pattern {
+                let value = x[row];
+                let nonzero = value != T::default();
+                match pinv[row] {
+                    Some(step) if nonzero => self.u_cols[k].push((step, value)),
+                    None if row != pivot && nonzero => self.l_cols[k].push((row, value / diag)),
+                    _ => {}
+                }
+                x[row] = T::default();
+                marked[row] = false;
+            }
+            self.u_diag[k] = diag;
+            self.pivot_row[k] = pivot;
+            pinv[pivot] = Some(k);
+        }
+        Ok(())
+    }
+
+    /// 用已有的分解求解, `rhs` 返回时为解
+    pub fn solve_in_place(&self, rhs: &mut [T]) -> Result<(), SolverError> {
+        let n = self.n;
+        if rhs.len() != n || self.pivot_row.len() != n {
+            return Err(SolverError::SolveFailed);
+        }
+        // 前代: L y = P b, 按原行号在 rhs 上原地消去
+        let mut y = vec![T::default(); n];
+        for k in 0..n {
+            let value = rhs[self.pivot_row[k]];
+            y[k] = value;
+            for &(i, l) in &self.l_cols[k] {
+                rhs[i] -= l * value;
+            }
+        }
+        // 回代: U x = y
+        for k in (0..n).rev() {
+            let diag = self.u_diag[k];
+            if diag == T::default() {
+                return Err(SolverError::SolveFailed);
+            }
+            let value = y[k] / diag;
+            y[k] = value;
+            for &(step, u) in &self.u_cols[k] {
+                y[step] -= u * value;
+            }
+        }
+        rhs.copy_from_slice(&y);
+        Ok(())
+    }
+}
+
+impl LinearSolver for SparseLuSolver {
+    fn prepare(&mut self, n: usize) {
+        self.n = n;
+    }
+
+    fn analyze(&mut self, _ap: &[i64], _ai: &[i64]) -> Result<(), SolverError> {
+        Ok(())
+    }
+
+    fn factor(&mut self, ap: &[i64], ai: &[i64], ax: &[f64]) -> Result<(), SolverError> {
+        self.factorize(ap, ai, ax)
+    }
+
+    fn solve(&mut self, rhs: &mut [f64]) -> Result<(), SolverError> {
+        self.solve_in_place(rhs)
+    }
+
+    fn reset_pattern(&mut self) {}
+}
+
 pub struct KluSolver {
     pub n: usize,
     pub enabled: bool,
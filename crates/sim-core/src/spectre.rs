@@ -0,0 +1,530 @@
+//! Spectre 网表前端: 把 Spectre 语法逐条翻译成等价的 SPICE 语句, 再交给
+//! SPICE 解析器生成同样的 [`NetlistAst`]。翻译保持行号不变 (续行翻译为空行),
+//! 因此诊断仍指向原始 Spectre 源码。
+//!
+//! 支持的语句:
+//! - 实例 `name (n1 n2 ...) master key=value ...`, 括号可省略
+//! - `parameters`、`include`、`model`、`subckt` / `ends`、`ic`
+//! - 分析 `name dc|tran|ac ...`
+//! - `simulator lang=spice|spectre` 切换语言
+//! - `//` 注释与行尾 `\` 续行
+
+use std::collections::HashMap;
+
+use crate::diagnostic::{codes, Diagnostic};
+use crate::netlist::{parse_netlist, parse_number_with_suffix, NetlistAst};
+
+/// 解析 Spectre 格式的网表文本
+pub fn parse_spectre(input: &str) -> NetlistAst {
+    let (translated, errors) = spectre_to_spice(input);
+    let mut ast = parse_netlist(&translated);
+    restore_source_lines(&mut ast.errors, input);
+    ast.errors.extend(errors);
+    ast
+}
+
+/// 翻译后的诊断列号对应 SPICE 文本, 这里换回原始源码行并去掉列范围
+pub(crate) fn restore_source_lines(errors: &mut [Diagnostic], original: &str) {
+    let lines: Vec<&str> = original.lines().collect();
+    for diag in errors {
+        if let Some(line) = diag.line.checked_sub(1).and_then(|idx| lines.get(idx)) {
+            diag.source_line = Some(line.to_string());
+            diag.columns = None;
+        }
+    }
+}
+
+/// 逐行翻译为 SPICE 文本, 输出行数与输入相同
+pub fn spectre_to_spice(input: &str) -> (String, Vec<Diagnostic>) {
+    let raw_lines: Vec<&str> = input.lines().collect();
+    let statements = join_statements(&raw_lines);
+    let symbols = collect_symbols(&statements);
+
+    let mut out = vec![String::new(); raw_lines.len()];
+    let mut errors = Vec::new();
+    let mut spice_mode = false;
+
+    for stmt in &statements {
+        let text = stmt.text.trim();
+        if let Some(lang) = simulator_lang(text) {
+            spice_mode = lang.eq_ignore_ascii_case("spice");
+            out[stmt.line - 1] = format!("* {}", text);
+            continue;
+        }
+        if spice_mode {
+            // SPICE 段原样保留 (包括续行)
+            for (offset, raw) in raw_lines[stmt.line - 1..stmt.line - 1 + stmt.span].iter().enumerate() {
+                out[stmt.line - 1 + offset] = raw.to_string();
+            }
+            continue;
+        }
+        if text.is_empty() {
+            continue;
+        }
+        match translate_statement(text, &symbols) {
+            Ok(line) => out[stmt.line - 1] = line,
+            Err(message) => {
+                out[stmt.line - 1] = format!("* {}", text);
+                errors.push(
+                    Diagnostic::error(codes::SPECTRE_SYNTAX, stmt.line, message)
+                        .with_source_line(raw_lines[stmt.line - 1]),
+                );
+            }
+        }
+    }
+
+    let mut text = out.join("\n");
+    text.push('\n');
+    (text, errors)
+}
+
+/// 合并续行后的一条 Spectre 语句
+struct SpectreStatement {
+    text: String,
+    /// 首行行号 (1 起始)
+    line: usize,
+    /// 占用的物理行数
+    span: usize,
+}
+
+fn join_statements(lines: &[&str]) -> Vec<SpectreStatement> {
+    let mut statements = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let start = idx;
+        let mut text = String::new();
+        loop {
+            let line = strip_comment(lines[idx]);
+            let trimmed = line.trim_end();
+            idx += 1;
+            match trimmed.strip_suffix('\\') {
+                Some(head) if idx < lines.len() => {
+                    text.push_str(head);
+                    text.push(' ');
+                }
+                _ => {
+                    text.push_str(trimmed);
+                    break;
+                }
+            }
+        }
+        statements.push(SpectreStatement {
+            text,
+            line: start + 1,
+            span: idx - start,
+        });
+    }
+    statements
+}
+
+/// 去掉 `//` 与行首 `*` 注释; 引号内不处理
+fn strip_comment(line: &str) -> &str {
+    if line.trim_start().starts_with('*') {
+        return "";
+    }
+    let mut in_quote = false;
+    let bytes = line.as_bytes();
+    for idx in 0..bytes.len() {
+        match bytes[idx] {
+            b'"' => in_quote = !in_quote,
+            b'/' if !in_quote && bytes.get(idx + 1) == Some(&b'/') => return &line[..idx],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn simulator_lang(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix("simulator")?;
+    rest.split_whitespace()
+        .find_map(|token| token.strip_prefix("lang="))
+}
+
+/// 模型名 -> 模型类型, 以及子电路名; 决定实例翻译成哪种 SPICE 器件
+struct Symbols {
+    models: HashMap<String, String>,
+}
+
+fn collect_symbols(statements: &[SpectreStatement]) -> Symbols {
+    let mut models = HashMap::new();
+    for stmt in statements {
+        let tokens = tokenize(&stmt.text);
+        if tokens.len() >= 3 && tokens[0] == "model" {
+            models.insert(tokens[1].to_ascii_lowercase(), tokens[2].to_ascii_lowercase());
+        }
+    }
+    Symbols { models }
+}
+
+/// 按空白切分, 圆括号单独成词; `[...]` 与引号内的空白不切分
+fn tokenize(text: &str) -> Vec<String> {
+    let text = normalize_assignments(text);
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut bracket = 0usize;
+    let mut in_quote = false;
+    for ch in text.chars() {
+        match ch {
+            '"' => {
+                in_quote = !in_quote;
+                current.push(ch);
+            }
+            '[' if !in_quote => {
+                bracket += 1;
+                current.push(ch);
+            }
+            ']' if !in_quote => {
+                bracket = bracket.saturating_sub(1);
+                current.push(ch);
+            }
+            '(' | ')' if !in_quote && bracket == 0 && !current.contains('=') => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(ch.to_string());
+            }
+            ch if ch.is_whitespace() && !in_quote && bracket == 0 => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(ch),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// `r = 1k` -> `r=1k`
+fn normalize_assignments(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '=' {
+            while out.ends_with(' ') || out.ends_with('\t') {
+                out.pop();
+            }
+            out.push('=');
+            while chars.peek().is_some_and(|next| next.is_whitespace()) {
+                chars.next();
+            }
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+fn translate_statement(text: &str, symbols: &Symbols) -> Result<String, String> {
+    let tokens = tokenize(text);
+    let Some(first) = tokens.first() else {
+        return Ok(String::new());
+    };
+    let rest = &tokens[1..];
+    match first.as_str() {
+        "parameters" => Ok(format!(".param {}", convert_assignments(rest).join(" "))),
+        "include" | "ahdl_include" => {
+            let path = rest.first().ok_or_else(|| "include requires a path".to_string())?;
+            Ok(format!(".include {}", path))
+        }
+        "model" => translate_model(rest),
+        "subckt" | "inline" => {
+            let rest = if first == "inline" { &rest[1..] } else { rest };
+            let name = rest.first().ok_or_else(|| "subckt requires a name".to_string())?;
+            let ports: Vec<&str> = rest[1..]
+                .iter()
+                .map(String::as_str)
+                .filter(|token| *token != "(" && *token != ")")
+                .collect();
+            Ok(format!(".subckt {} {}", name, ports.join(" ")).trim_end().to_string())
+        }
+        "ends" => Ok(".ends".to_string()),
+        "ic" => {
+            let probes: Vec<String> = convert_assignments(rest)
+                .iter()
+                .filter_map(|assignment| assignment.split_once('='))
+                .map(|(node, value)| format!("V({})={}", node, value))
+                .collect();
+            Ok(format!(".ic {}", probes.join(" ")))
+        }
+        // 只影响输出或统计的语句不参与仿真
+        "global" | "save" | "nodeset" | "statistics" | "section" | "endsection" | "library"
+        | "endlibrary" | "real" => Ok(format!("* {}", text)),
+        _ => translate_instance(first, rest, text, symbols),
+    }
+}
+
+/// 参数值中的 Spectre 单位换成 SPICE 写法 (`M` 在 Spectre 中是兆)
+fn convert_value(value: &str) -> String {
+    match value.strip_suffix('M') {
+        Some(number)
+            if !number.is_empty()
+                && number
+                    .chars()
+                    .all(|ch| ch.is_ascii_digit() || matches!(ch, '.' | '-' | '+' | 'e' | 'E')) =>
+        {
+            format!("{}meg", number)
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn convert_assignments(tokens: &[String]) -> Vec<String> {
+    tokens
+        .iter()
+        .map(|token| match token.split_once('=') {
+            Some((key, value)) => format!("{}={}", key, convert_value(value)),
+            None => token.clone(),
+        })
+        .collect()
+}
+
+fn translate_model(rest: &[String]) -> Result<String, String> {
+    if rest.len() < 2 {
+        return Err("model requires a name and a type".to_string());
+    }
+    let name = &rest[0];
+    let master = rest[1].to_ascii_lowercase();
+    let mut params: Vec<(String, String)> = rest[2..]
+        .iter()
+        .filter(|token| *token != "(" && *token != ")")
+        .filter_map(|token| token.split_once('='))
+        .map(|(key, value)| (key.to_ascii_lowercase(), convert_value(value)))
+        .collect();
+    let polarity = params
+        .iter()
+        .position(|(key, _)| key == "type")
+        .map(|idx| params.remove(idx).1.to_ascii_lowercase());
+    let level = match master.as_str() {
+        "bsim4" => Some("54"),
+        "bsim3v3" | "bsim3" => Some("49"),
+        "mos1" => Some("1"),
+        _ => None,
+    };
+    let spice_type = match (master.as_str(), level) {
+        ("diode", _) => "d".to_string(),
+        (_, Some(_)) => match polarity.as_deref() {
+            Some("p") | Some("pmos") => "pmos".to_string(),
+            _ => "nmos".to_string(),
+        },
+        (other, None) => other.to_string(),
+    };
+    let mut line = format!(".model {} {}", name, spice_type);
+    if let Some(level) = level {
+        if !params.iter().any(|(key, _)| key == "level") {
+            line.push_str(&format!(" level={}", level));
+        }
+    }
+    for (key, value) in params {
+        line.push_str(&format!(" {}={}", key, value));
+    }
+    Ok(line)
+}
+
+/// 分析语句: `name dc|tran|ac [key=value ...]`
+fn translate_analysis(keyword: &str, params: &HashMap<String, String>) -> Result<String, String> {
+    let get = |key: &str| params.get(key).map(|value| convert_value(value));
+    match keyword {
+        "dc" => {
+            let (Some(start), Some(stop)) = (get("start"), get("stop")) else {
+                // 没有扫描范围即工作点分析
+                return Ok(".op".to_string());
+            };
+            let step = get("step").unwrap_or_else(|| "0".to_string());
+            match (params.get("dev"), params.get("param")) {
+                (Some(dev), _) => Ok(format!(".dc {} {} {} {}", dev, start, stop, step)),
+                (None, Some(param)) if param == "temp" => {
+                    Ok(format!(".dc temp {} {} {}", start, stop, step))
+                }
+                (None, Some(param)) => Ok(format!(".dc param {} {} {} {}", param, start, stop, step)),
+                (None, None) => Err("dc sweep requires dev= or param=".to_string()),
+            }
+        }
+        "tran" => {
+            let stop = get("stop").ok_or_else(|| "tran requires stop=".to_string())?;
+            let step = get("step")
+                .or_else(|| get("maxstep"))
+                .or_else(|| {
+                    parse_number_with_suffix(&stop).map(|value| format!("{:e}", value / 1000.0))
+                })
+                .ok_or_else(|| "tran requires step= when stop= is not a number".to_string())?;
+            let mut line = format!(".tran {} {}", step, stop);
+            if let Some(maxstep) = get("maxstep") {
+                line.push_str(&format!(" 0 {}", maxstep));
+            }
+            Ok(line)
+        }
+        "ac" => {
+            let start = get("start").ok_or_else(|| "ac requires start=".to_string())?;
+            let stop = get("stop").ok_or_else(|| "ac requires stop=".to_string())?;
+            let (sweep, points) = if let Some(points) = get("dec") {
+                ("dec", points)
+            } else if let Some(points) = get("oct") {
+                ("oct", points)
+            } else {
+                ("lin", get("lin").or_else(|| get("points")).unwrap_or_else(|| "50".to_string()))
+            };
+            Ok(format!(".ac {} {} {} {}", sweep, points, start, stop))
+        }
+        // 选项与尚未支持的分析保持为注释
+        _ => Ok(String::new()),
+    }
+}
+
+const ANALYSIS_KEYWORDS: &[&str] = &[
+    "dc", "tran", "ac", "noise", "xf", "sp", "stb", "pss", "pac", "pnoise", "pxf", "hb", "sweep",
+    "montecarlo", "info", "options", "set", "alter", "altergroup", "check",
+];
+
+fn translate_instance(
+    name: &str,
+    rest: &[String],
+    text: &str,
+    symbols: &Symbols,
+) -> Result<String, String> {
+    // 节点: 括号内全部词, 或 (无括号时) 第一个 key=value 之前除 master 以外的词
+    let (nodes, master, params): (Vec<String>, String, Vec<String>) =
+        if rest.first().map(String::as_str) == Some("(") {
+            let close = rest
+                .iter()
+                .position(|token| token == ")")
+                .ok_or_else(|| format!("unterminated node list in '{}'", text))?;
+            let nodes = rest[1..close].to_vec();
+            let master = rest
+                .get(close + 1)
+                .cloned()
+                .ok_or_else(|| format!("instance '{}' has no master", name))?;
+            (nodes, master, rest[close + 2..].to_vec())
+        } else {
+            let positional: Vec<String> =
+                rest.iter().take_while(|token| !token.contains('=')).cloned().collect();
+            let Some((master, nodes)) = positional.split_last() else {
+                return Err(format!("unrecognized statement '{}'", text));
+            };
+            if nodes.is_empty() && ANALYSIS_KEYWORDS.contains(&master.as_str()) {
+                let params = rest[1..]
+                    .iter()
+                    .filter_map(|token| token.split_once('='))
+                    .map(|(key, value)| (key.to_ascii_lowercase(), value.to_string()))
+                    .collect();
+                let line = translate_analysis(master, &params)?;
+                return Ok(if line.is_empty() { format!("* {}", text) } else { line });
+            }
+            (nodes.to_vec(), master.clone(), rest[positional.len()..].to_vec())
+        };
+
+    let mut params: Vec<(String, String)> = params
+        .iter()
+        .filter_map(|token| token.split_once('='))
+        .map(|(key, value)| (key.to_ascii_lowercase(), convert_value(value)))
+        .collect();
+    let mut take = |key: &str| {
+        params
+            .iter()
+            .position(|(k, _)| k == key)
+            .map(|idx| params.remove(idx).1)
+    };
+    let spice_name = |letter: char| {
+        if name.chars().next().is_some_and(|ch| ch.eq_ignore_ascii_case(&letter)) {
+            name.to_string()
+        } else {
+            format!("{}{}", letter, name)
+        }
+    };
+    let master_lower = master.to_ascii_lowercase();
+    let nodes = nodes.join(" ");
+
+    let mut line = match master_lower.as_str() {
+        "resistor" => format!("{} {} {}", spice_name('R'), nodes, take("r").unwrap_or_default()),
+        "capacitor" => format!("{} {} {}", spice_name('C'), nodes, take("c").unwrap_or_default()),
+        "inductor" => format!("{} {} {}", spice_name('L'), nodes, take("l").unwrap_or_default()),
+        "vsource" | "isource" => {
+            let letter = if master_lower == "vsource" { 'V' } else { 'I' };
+            let mut line = format!(
+                "{} {} DC {}",
+                spice_name(letter),
+                nodes,
+                take("dc").unwrap_or_else(|| "0".to_string())
+            );
+            if let Some(mag) = take("mag") {
+                line.push_str(&format!(" AC {} {}", mag, take("phase").unwrap_or_else(|| "0".to_string())));
+            }
+            let kind = take("type").map(|kind| kind.to_ascii_lowercase());
+            let waveform = match kind.as_deref() {
+                Some("pulse") => Some(format!(
+                    "PULSE({})",
+                    ["val0", "val1", "delay", "rise", "fall", "width", "period"]
+                        .iter()
+                        .map(|key| take(key).unwrap_or_else(|| "0".to_string()))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )),
+                Some("sine") => Some(format!(
+                    "SIN({})",
+                    ["sinedc", "ampl", "freq"]
+                        .iter()
+                        .map(|key| take(key).unwrap_or_else(|| "0".to_string()))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )),
+                Some("pwl") => take("wave").map(|wave| {
+                    format!("PWL({})", wave.trim_start_matches('[').trim_end_matches(']').trim())
+                }),
+                _ => None,
+            };
+            if let Some(waveform) = waveform {
+                line.push(' ');
+                line.push_str(&waveform);
+            }
+            line
+        }
+        "port" => {
+            let mut line = format!(
+                "{} {} PORT={} Z0={}",
+                spice_name('P'),
+                nodes,
+                take("num").unwrap_or_else(|| "1".to_string()),
+                take("r").unwrap_or_else(|| "50".to_string())
+            );
+            if let Some(dc) = take("dc") {
+                line.push_str(&format!(" DC {}", dc));
+            }
+            if let Some(mag) = take("mag") {
+                line.push_str(&format!(" AC {} {}", mag, take("phase").unwrap_or_else(|| "0".to_string())));
+            }
+            line
+        }
+        "vcvs" => format!("{} {} {}", spice_name('E'), nodes, take("gain").unwrap_or_default()),
+        "vccs" => format!("{} {} {}", spice_name('G'), nodes, take("gm").unwrap_or_default()),
+        "cccs" | "ccvs" => {
+            let (letter, gain_key) = if master_lower == "cccs" { ('F', "gain") } else { ('H', "rm") };
+            let probe = take("probe").ok_or_else(|| format!("{} requires probe=", master_lower))?;
+            format!(
+                "{} {} {} {}",
+                spice_name(letter),
+                nodes,
+                probe,
+                take(gain_key).unwrap_or_default()
+            )
+        }
+        _ => {
+            // 模型实例按模型类型决定器件; 模型未知 (如在 include 中) 时按实例名首字母
+            let letter = match symbols.models.get(&master_lower).map(String::as_str) {
+                Some("diode") => 'D',
+                Some(_) => 'M',
+                None => match name.chars().next().map(|ch| ch.to_ascii_uppercase()) {
+                    Some('D') if nodes.split_whitespace().count() == 2 => 'D',
+                    Some('M') if nodes.split_whitespace().count() == 4 => 'M',
+                    _ => 'X',
+                },
+            };
+            format!("{} {} {}", spice_name(letter), nodes, master)
+        }
+    };
+    for (key, value) in params {
+        line.push_str(&format!(" {}={}", key, value));
+    }
+    Ok(line)
+}
@@ -1,7 +1,17 @@
-use crate::circuit::{DeviceKind, Instance};
+use crate::behavioral::{BehavioralExpr, ExprVar};
+use crate::circuit::{DeviceKind, Instance, NodeId};
 use crate::complex_mna::ComplexStampContext;
+use crate::device_model::{instance_device_model, prepare_stamp};
+use crate::integrator::{Companion, History, IntegratorRef};
 use crate::mna::StampContext;
+use crate::netlist::parse_number_with_suffix;
+use crate::noise::{NoiseMechanism, NoiseSource, BOLTZMANN, CHARGE};
+use crate::result_store::DeviceOpKind;
+use crate::state_table::{StateBranch, StateKey, StateTable};
 use num_complex::Complex64;
+use sim_devices::digital::DigitalKind;
+use sim_devices::source::{build_source_waveform, SourceWaveform};
+use sim_devices::trnoise::{build_trnoise_params, TrNoise};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -33,20 +43,62 @@ pub struct InstanceStamp {
 
 impl DeviceStamp for InstanceStamp {
     fn stamp_dc(&self, ctx: &mut StampContext, x: Option<&[f64]>) -> Result<(), StampError> {
-        match self.instance.kind {
-            DeviceKind::R => stamp_resistor(ctx, &self.instance),
-            DeviceKind::I => stamp_current(ctx, &self.instance),
-            DeviceKind::V => stamp_voltage(ctx, &self.instance),
-            DeviceKind::D => stamp_diode(ctx, &self.instance, x),
-            DeviceKind::M => stamp_mos(ctx, &self.instance, x),
-            DeviceKind::L => stamp_inductor_dc(ctx, &self.instance),
+        self.instance.stamp_dc(ctx, x)
+    }
+
+    fn stamp_tran(
+        &self,
+        ctx: &mut StampContext,
+        x: Option<&[f64]>,
+        dt: f64,
+        state: &mut TransientState,
+    ) -> Result<(), StampError> {
+        self.instance.stamp_tran(ctx, x, dt, state)
+    }
+
+    fn stamp_ac(
+        &self,
+        ctx: &mut ComplexStampContext,
+        dc_solution: &[f64],
+    ) -> Result<(), StampError> {
+        self.instance.stamp_ac(ctx, dc_solution)
+    }
+}
+
+/// 实例直接盖章, 不必先克隆成 [`InstanceStamp`]
+impl DeviceStamp for Instance {
+    fn stamp_dc(&self, ctx: &mut StampContext, x: Option<&[f64]>) -> Result<(), StampError> {
+        ctx.multiplier = instance_multiplier(self);
+        let result = match self.kind {
+            DeviceKind::R => stamp_resistor(ctx, self, x),
+            DeviceKind::I => stamp_current(ctx, self),
+            DeviceKind::V => stamp_voltage(ctx, self),
+            DeviceKind::D => stamp_diode(ctx, self, x),
+            DeviceKind::M => stamp_mos(ctx, self, x),
+            DeviceKind::Q => stamp_bjt(ctx, self, x),
+            DeviceKind::J | DeviceKind::Z => stamp_jfet(ctx, self, x),
+            DeviceKind::S | DeviceKind::W => stamp_switch(ctx, self, x, None),
+            DeviceKind::L => stamp_inductor_dc(ctx, self),
             DeviceKind::C => Ok(()), // Capacitor is open circuit in DC
-            DeviceKind::E => stamp_vcvs(ctx, &self.instance),
-            DeviceKind::G => stamp_vccs(ctx, &self.instance),
-            DeviceKind::F => stamp_cccs(ctx, &self.instance),
-            DeviceKind::H => stamp_ccvs(ctx, &self.instance),
+            DeviceKind::E => stamp_vcvs(ctx, self),
+            DeviceKind::N => stamp_transformer(ctx, self),
+            DeviceKind::T => stamp_tline(ctx, self, TlineIncident::DC),
+            DeviceKind::WLine => stamp_wline(ctx, self, None, None),
+            DeviceKind::P => stamp_port(ctx, self),
+            DeviceKind::SParam => stamp_sparam(ctx, self),
+            DeviceKind::A => match instance_device_model(self) {
+                Some(model) => prepare_stamp(model.as_ref(), self, |name| ctx.allocate_aux(name))
+                    .and_then(|_| model.stamp_dc(self, ctx, x)),
+                None => stamp_digital(ctx, self, x, None),
+            },
+            DeviceKind::G => stamp_vccs(ctx, self),
+            DeviceKind::F => stamp_cccs(ctx, self),
+            DeviceKind::H => stamp_ccvs(ctx, self),
+            DeviceKind::B => stamp_behavioral(ctx, self, x),
             DeviceKind::X => Ok(()), // Subcircuit instances are already expanded
-        }
+        };
+        ctx.multiplier = 1.0;
+        result
     }
 
     fn stamp_tran(
@@ -56,9 +108,69 @@ impl DeviceStamp for InstanceStamp {
         dt: f64,
         state: &mut TransientState,
     ) -> Result<(), StampError> {
-        match self.instance.kind {
-            DeviceKind::C => stamp_capacitor_tran(ctx, &self.instance, x, dt, state),
-            DeviceKind::L => stamp_inductor_tran(ctx, &self.instance, x, dt, state),
+        match self.kind {
+            DeviceKind::R
+            | DeviceKind::C
+            | DeviceKind::L
+            | DeviceKind::D
+            | DeviceKind::M
+            | DeviceKind::J
+            | DeviceKind::Z
+            | DeviceKind::S
+            | DeviceKind::W
+            | DeviceKind::T
+            | DeviceKind::WLine => {
+                ctx.multiplier = instance_multiplier(self);
+                let result = match self.kind {
+                    DeviceKind::R => stamp_resistor_tran(ctx, self, x, dt, state),
+                    DeviceKind::C => stamp_capacitor_tran(ctx, self, x, dt, state),
+                    DeviceKind::L => stamp_inductor_tran(ctx, self, x, dt, state),
+                    DeviceKind::D => stamp_diode_tran(ctx, self, x, dt, state),
+                    DeviceKind::M => stamp_mos_tran(ctx, self, x, dt, state),
+                    DeviceKind::S | DeviceKind::W => {
+                        if let Some(column) = switch_control_column(ctx, self) {
+                            state.switch_aux.insert(StateKey::of(self), column);
+                        }
+                        let was_on = state.switch_on.get(StateKey::of(self)).copied();
+                        stamp_switch(ctx, self, x, was_on)
+                    }
+                    DeviceKind::T => stamp_tline_tran(ctx, self, x, dt, state),
+                    DeviceKind::WLine => stamp_wline_tran(ctx, self, x, dt, state),
+                    _ => stamp_jfet_tran(ctx, self, x, dt, state),
+                };
+                ctx.multiplier = 1.0;
+                result
+            }
+            DeviceKind::A => match instance_device_model(self) {
+                Some(model) => {
+                    ctx.multiplier = instance_multiplier(self);
+                    let result = prepare_stamp(model.as_ref(), self, |name| ctx.allocate_aux(name))
+                        .and_then(|_| model.stamp_tran(self, ctx, x, dt, state));
+                    ctx.multiplier = 1.0;
+                    result
+                }
+                None => stamp_digital(ctx, self, x, Some(&state.digital)),
+            },
+            DeviceKind::V | DeviceKind::I
+                if state.trnoise.contains_key(StateKey::of(self)) || state.waveforms.contains_key(StateKey::of(self)) =>
+            {
+                ctx.multiplier = instance_multiplier(self);
+                let result = stamp_transient_source(ctx, self, dt, state);
+                ctx.multiplier = 1.0;
+                result
+            }
+            DeviceKind::B => {
+                let result = self.stamp_dc(ctx, x);
+                if let Ok((expr, _)) = behavioral_expr(self) {
+                    let aux_ids = &ctx.aux.name_to_id;
+                    if let Ok(columns) =
+                        behavioral_columns(&expr, self, |name| existing_branch_column(aux_ids, ctx.node_count, name))
+                    {
+                        state.behavioral_columns.insert(StateKey::of(self), columns);
+                    }
+                }
+                result
+            }
             _ => self.stamp_dc(ctx, x),
         }
     }
@@ -68,51 +180,425 @@ impl DeviceStamp for InstanceStamp {
         ctx: &mut ComplexStampContext,
         dc_solution: &[f64],
     ) -> Result<(), StampError> {
-        match self.instance.kind {
-            DeviceKind::R => stamp_resistor_ac(ctx, &self.instance),
-            DeviceKind::C => stamp_capacitor_ac(ctx, &self.instance),
-            DeviceKind::L => stamp_inductor_ac(ctx, &self.instance),
-            DeviceKind::V => stamp_voltage_ac(ctx, &self.instance),
-            DeviceKind::I => stamp_current_ac(ctx, &self.instance),
-            DeviceKind::D => stamp_diode_ac(ctx, &self.instance, dc_solution),
-            DeviceKind::M => stamp_mos_ac(ctx, &self.instance, dc_solution),
-            DeviceKind::E => stamp_vcvs_ac(ctx, &self.instance),
-            DeviceKind::G => stamp_vccs_ac(ctx, &self.instance),
-            DeviceKind::F => stamp_cccs_ac(ctx, &self.instance),
-            DeviceKind::H => stamp_ccvs_ac(ctx, &self.instance),
+        ctx.multiplier = instance_multiplier(self);
+        let result = match self.kind {
+            DeviceKind::R => stamp_resistor_ac(ctx, self, dc_solution),
+            DeviceKind::C => stamp_capacitor_ac(ctx, self, dc_solution),
+            DeviceKind::L => stamp_inductor_ac(ctx, self, dc_solution),
+            DeviceKind::V => stamp_voltage_ac(ctx, self),
+            DeviceKind::I => stamp_current_ac(ctx, self),
+            DeviceKind::D => stamp_diode_ac(ctx, self, dc_solution),
+            DeviceKind::M => stamp_mos_ac(ctx, self, dc_solution),
+            DeviceKind::Q => stamp_bjt_ac(ctx, self, dc_solution),
+            DeviceKind::J | DeviceKind::Z => stamp_jfet_ac(ctx, self, dc_solution),
+            DeviceKind::S | DeviceKind::W => stamp_switch_ac(ctx, self, dc_solution),
+            DeviceKind::E => stamp_vcvs_ac(ctx, self),
+            DeviceKind::N => stamp_transformer_ac(ctx, self),
+            DeviceKind::T => stamp_tline_ac(ctx, self),
+            DeviceKind::WLine => stamp_wline_ac(ctx, self),
+            DeviceKind::P => stamp_port_ac(ctx, self),
+            DeviceKind::SParam => stamp_sparam_ac(ctx, self),
+            DeviceKind::A => match instance_device_model(self) {
+                Some(model) => prepare_stamp(model.as_ref(), self, |name| ctx.allocate_aux(name))
+                    .and_then(|_| model.stamp_ac(self, ctx, dc_solution)),
+                None => stamp_digital_ac(ctx, self),
+            },
+            DeviceKind::G => stamp_vccs_ac(ctx, self),
+            DeviceKind::F => stamp_cccs_ac(ctx, self),
+            DeviceKind::H => stamp_ccvs_ac(ctx, self),
+            DeviceKind::B => stamp_behavioral_ac(ctx, self, dc_solution),
             DeviceKind::X => Ok(()), // Subcircuit instances are already expanded
+        };
+        ctx.multiplier = 1.0;
+        result
+    }
+}
+
+/// 预编译的实例: 分析开始前把取值字符串、并联倍数与模型参数一次解析成数值,
+/// Newton 迭代中直接盖章, 不再克隆实例、逐次解析字符串。
+/// 线性 R/C/L、直流独立源、二极管与 MOSFET 走类型化的快速路径, 其余器件按 [`Instance`] 的规则盖章
+#[derive(Clone)]
+pub struct CompiledInstance {
+    multiplier: f64,
+    device: CompiledDevice,
+}
+
+#[derive(Clone)]
+enum CompiledDevice {
+    Resistor { a: usize, b: usize, g: f64 },
+    Capacitor { c: f64 },
+    Inductor { l: f64 },
+    /// 没有 PULSE / PWL 波形与 TRNOISE 的独立源
+    Source { value: f64 },
+    Diode { model: sim_devices::diode::DiodeParams, vt: f64 },
+    Mos { model: Box<MosModel>, thermal: Option<MosThermal> },
+    Generic,
+}
+
+impl CompiledInstance {
+    pub fn compile(inst: &Instance) -> Self {
+        let two_terminal = inst.nodes.len() == 2;
+        let device = match inst.kind {
+            DeviceKind::R if two_terminal && memristor_params(inst).is_none() && thermistor_params(inst).is_none() => {
+                match resistance(inst) {
+                    Ok(r) => CompiledDevice::Resistor {
+                        a: inst.nodes[0].0,
+                        b: inst.nodes[1].0,
+                        g: 1.0 / r,
+                    },
+                    Err(_) => CompiledDevice::Generic,
+                }
+            }
+            DeviceKind::C if two_terminal && matches!(nonlinear_capacitor(inst), Ok(None)) => {
+                capacitance(inst).map_or(CompiledDevice::Generic, |c| CompiledDevice::Capacitor { c })
+            }
+            DeviceKind::L if two_terminal && matches!(nonlinear_inductor(inst), Ok(None)) => {
+                inductance(inst).map_or(CompiledDevice::Generic, |l| CompiledDevice::Inductor { l })
+            }
+            DeviceKind::V | DeviceKind::I
+                if two_terminal && !inst.params.contains_key("trnoise") && build_source_waveform(&inst.params).is_none() =>
+            {
+                source_value(inst).map_or(CompiledDevice::Generic, |value| CompiledDevice::Source { value })
+            }
+            DeviceKind::D => CompiledDevice::Diode {
+                model: diode_model(inst),
+                vt: diode_thermal_voltage(inst),
+            },
+            DeviceKind::M if inst.nodes.len() >= 4 && !sim_devices::power::is_vdmos(&inst.params) => CompiledDevice::Mos {
+                model: Box::new(mos_model(inst)),
+                thermal: mos_thermal(inst),
+            },
+            _ => CompiledDevice::Generic,
+        };
+        Self {
+            multiplier: instance_multiplier(inst),
+            device,
         }
     }
+
+    /// 直流盖章; `inst` 须是编译时的实例
+    pub fn stamp_dc(&self, inst: &Instance, ctx: &mut StampContext, x: Option<&[f64]>) -> Result<(), StampError> {
+        if matches!(self.device, CompiledDevice::Generic) {
+            return inst.stamp_dc(ctx, x);
+        }
+        ctx.multiplier = self.multiplier;
+        let result = match &self.device {
+            CompiledDevice::Resistor { a, b, g } => {
+                stamp_conductance(ctx, *a, *b, *g);
+                Ok(())
+            }
+            CompiledDevice::Capacitor { .. } => Ok(()),
+            CompiledDevice::Inductor { .. } => stamp_inductor_dc(ctx, inst),
+            CompiledDevice::Source { value } => match inst.kind {
+                DeviceKind::I => stamp_current_value(ctx, inst, *value),
+                _ => stamp_voltage_value(ctx, inst, *value),
+            },
+            CompiledDevice::Diode { model, vt } => stamp_diode_with(ctx, inst, model, *vt, x),
+            CompiledDevice::Mos { model, thermal } => stamp_mos_with(ctx, inst, model, thermal.as_ref(), x),
+            CompiledDevice::Generic => unreachable!(),
+        };
+        ctx.multiplier = 1.0;
+        result
+    }
+
+    /// 瞬态盖章; `inst` 须是编译时的实例
+    pub fn stamp_tran(
+        &self,
+        inst: &Instance,
+        ctx: &mut StampContext,
+        x: Option<&[f64]>,
+        dt: f64,
+        state: &mut TransientState,
+    ) -> Result<(), StampError> {
+        ctx.multiplier = self.multiplier;
+        let result = match &self.device {
+            CompiledDevice::Capacitor { c } => {
+                stamp_linear_capacitor_tran(ctx, inst, *c, dt, state);
+                Ok(())
+            }
+            CompiledDevice::Inductor { l } => {
+                let k = inductor_branch(ctx, inst, state);
+                stamp_linear_inductor_tran(ctx, inst, k, *l, dt, state);
+                Ok(())
+            }
+            CompiledDevice::Diode { model, vt } => stamp_diode_tran_with(ctx, inst, model, *vt, x, dt, state),
+            CompiledDevice::Mos { model, thermal } => {
+                stamp_mos_tran_with(ctx, inst, model, thermal.as_ref(), x, dt, state)
+            }
+            CompiledDevice::Resistor { .. } | CompiledDevice::Source { .. } => {
+                ctx.multiplier = 1.0;
+                return self.stamp_dc(inst, ctx, x);
+            }
+            CompiledDevice::Generic => {
+                ctx.multiplier = 1.0;
+                return inst.stamp_tran(ctx, x, dt, state);
+            }
+        };
+        ctx.multiplier = 1.0;
+        result
+    }
+}
+
+/// 按实例顺序编译全部实例, 与 `instances` 一一对应
+pub fn compile_instances(instances: &[Instance]) -> Vec<CompiledInstance> {
+    instances.iter().map(CompiledInstance::compile).collect()
+}
+
+/// 并联倍数 m=, 缺省为 1
+fn instance_multiplier(inst: &Instance) -> f64 {
+    param_value(&inst.params, &["m"])
+        .filter(|m| *m > 0.0)
+        .unwrap_or(1.0)
+}
+
+/// 电阻值: 实例取值, 或 `.model R` 的 RSH*(L-SHORT)/(W-NARROW); 再按 TC1/TC2 换算到器件温度
+fn resistance(inst: &Instance) -> Result<f64, StampError> {
+    // 忆阻器在直流与 AC 下按初始状态 X0 的电阻处理
+    if let Some(params) = memristor_params(inst) {
+        return Ok(params.resistance(params.x0));
+    }
+    let value = inst.value.as_deref().and_then(parse_number_with_suffix);
+    let model = sim_devices::passive::build_resistor_params(&inst.params);
+    let r = model.nominal(value).ok_or(StampError::MissingValue)?;
+    Ok(match param_value(&inst.params, &["temp"]) {
+        Some(temp) => model.at_temperature(r, temp),
+        None => r,
+    })
 }
 
-fn stamp_resistor(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError> {
+/// `.model xxx memristor` 的电阻实例
+fn memristor_params(inst: &Instance) -> Option<sim_devices::memristor::MemristorParams> {
+    inst.params
+        .get("type")
+        .is_some_and(|t| t.eq_ignore_ascii_case("memristor"))
+        .then(|| sim_devices::memristor::build_memristor_params(&inst.params))
+}
+
+/// 忆阻器瞬态: 内部状态与端电压一起按后向欧拉隐式求解, 电流对端电压线性化
+fn stamp_resistor_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &TransientState,
+) -> Result<(), StampError> {
+    let Some(params) = memristor_params(inst) else {
+        return stamp_resistor(ctx, inst, x);
+    };
     if inst.nodes.len() != 2 {
         return Err(StampError::InvalidNodes);
     }
-    let value = inst
-        .value
-        .as_deref()
-        .and_then(parse_number_with_suffix)
-        .ok_or(StampError::MissingValue)?;
-    let g = 1.0 / value;
     let a = inst.nodes[0].0;
     let b = inst.nodes[1].0;
+    let voltage = |node: usize| x.and_then(|x| x.get(node)).copied().unwrap_or(0.0);
+    let v = voltage(a) - voltage(b);
+    let x_prev = state.memristor_state.get(StateKey::of(inst)).map_or(params.x0, |(x, _)| *x);
+    let out = params.step(x_prev, v, dt);
+    let g = out.conductance;
+    let ieq = out.current - g * v;
     ctx.add(a, a, g);
     ctx.add(b, b, g);
     ctx.add(a, b, -g);
     ctx.add(b, a, -g);
+    ctx.add_rhs(a, -ieq);
+    ctx.add_rhs(b, ieq);
     Ok(())
 }
 
-fn stamp_current(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError> {
+/// `.model xxx thermistor` 的电阻实例
+fn thermistor_params(inst: &Instance) -> Option<sim_devices::thermistor::ThermistorParams> {
+    inst.params
+        .get("type")
+        .is_some_and(|t| t.eq_ignore_ascii_case("thermistor"))
+        .then(|| sim_devices::thermistor::build_thermistor_params(&inst.params))
+}
+
+/// 热敏电阻在解 x 处的线性化: i = g (V(a) - V(b)) + gt T + ieq
+struct ThermistorLinearization {
+    a: usize,
+    b: usize,
+    /// tnode= 给出的温度节点
+    tnode: Option<usize>,
+    g: f64,
+    /// ∂i/∂T
+    gt: f64,
+    ieq: f64,
+}
+
+/// 给出 tnode= 时温度取该节点电压 [°C], 否则取器件温度 ("temp" 参数, 开尔文)
+fn thermistor_linearization(
+    inst: &Instance,
+    params: &sim_devices::thermistor::ThermistorParams,
+    x: Option<&[f64]>,
+) -> Result<ThermistorLinearization, StampError> {
+    let (a, b, tnode) = match inst.nodes.as_slice() {
+        [a, b] => (a.0, b.0, None),
+        [a, b, t] if inst.params.contains_key("tnode") => (a.0, b.0, Some(t.0)),
+        _ => return Err(StampError::InvalidNodes),
+    };
+    let v = |node: usize| x.and_then(|x| x.get(node)).copied().unwrap_or(0.0);
+    let temp = match tnode {
+        Some(t) => v(t) + 273.15,
+        None => param_value(&inst.params, &["temp"]).unwrap_or(300.15),
+    };
+    let (r, dr_dt) = params.evaluate(temp);
+    let g = 1.0 / r;
+    // i = v / R(T): ∂i/∂T = -v R' / R²
+    let gt = match tnode {
+        Some(_) => -(v(a) - v(b)) * dr_dt * g * g,
+        None => 0.0,
+    };
+    Ok(ThermistorLinearization {
+        a,
+        b,
+        tnode,
+        g,
+        gt,
+        ieq: -gt * tnode.map_or(0.0, v),
+    })
+}
+
+fn stamp_resistor(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Result<(), StampError> {
+    if let Some(params) = thermistor_params(inst) {
+        let ThermistorLinearization { a, b, tnode, g, gt, ieq } = thermistor_linearization(inst, &params, x)?;
+        ctx.add(a, a, g);
+        ctx.add(b, b, g);
+        ctx.add(a, b, -g);
+        ctx.add(b, a, -g);
+        if let Some(t) = tnode {
+            ctx.add(a, t, gt);
+            ctx.add(b, t, -gt);
+        }
+        ctx.add_rhs(a, -ieq);
+        ctx.add_rhs(b, ieq);
+        return Ok(());
+    }
     if inst.nodes.len() != 2 {
         return Err(StampError::InvalidNodes);
     }
-    let value = inst
-        .value
+    let g = 1.0 / resistance(inst)?;
+    let a = inst.nodes[0].0;
+    let b = inst.nodes[1].0;
+    ctx.add(a, a, g);
+    ctx.add(b, b, g);
+    ctx.add(a, b, -g);
+    ctx.add(b, a, -g);
+    Ok(())
+}
+
+/// 电容值: 实例取值, 或 `.model C` 的 CJ*L*W + 2*CJSW*(L+W) (L/W 扣除 SHORT/NARROW)
+fn capacitance(inst: &Instance) -> Result<f64, StampError> {
+    let value = inst.value.as_deref().and_then(parse_number_with_suffix);
+    sim_devices::passive::build_capacitor_params(&inst.params)
+        .nominal(value)
+        .ok_or(StampError::MissingValue)
+}
+
+/// 非线性电容的表达式与形式: `Q=` 给出电荷 (true), 非数值的 `C=` 给出电容 (false)。
+/// 线性电容返回 None; 表达式只能引用节点电压。
+fn nonlinear_capacitor(inst: &Instance) -> Result<Option<(BehavioralExpr, bool)>, StampError> {
+    let (text, is_charge) = if let Some(text) = inst.params.get("q") {
+        (text, true)
+    } else if let Some(text) = inst.params.get("c").filter(|text| parse_number_with_suffix(text).is_none()) {
+        (text, false)
+    } else {
+        return Ok(None);
+    };
+    let expr = BehavioralExpr::parse(text).map_err(|_| StampError::MissingValue)?;
+    if expr.vars.iter().any(|var| matches!(var, ExprVar::Branch(_))) {
+        return Err(StampError::MissingValue);
+    }
+    Ok(Some((expr, is_charge)))
+}
+
+/// 直流下电感按大电导短路
+const INDUCTOR_DC_CONDUCTANCE: f64 = 1e9;
+
+fn inductance(inst: &Instance) -> Result<f64, StampError> {
+    inst.value
         .as_deref()
         .and_then(parse_number_with_suffix)
-        .ok_or(StampError::MissingValue)?;
+        .or_else(|| param_value(&inst.params, &["l"]))
+        .ok_or(StampError::MissingValue)
+}
+
+/// 非线性电感的表达式与形式: `FLUX=` 给出磁链 (true), 非数值的 `L=` 给出电感 (false)。
+/// 线性电感返回 None; 表达式可引用节点电压与电感自身的电流 I(name)。
+fn nonlinear_inductor(inst: &Instance) -> Result<Option<(BehavioralExpr, bool)>, StampError> {
+    let (text, is_flux) = if let Some(text) = inst.params.get("flux") {
+        (text, true)
+    } else if let Some(text) = inst.params.get("l").filter(|text| parse_number_with_suffix(text).is_none()) {
+        (text, false)
+    } else {
+        return Ok(None);
+    };
+    let expr = BehavioralExpr::parse(text).map_err(|_| StampError::MissingValue)?;
+    let foreign_branch = expr.vars.iter().any(|var| match var {
+        ExprVar::Branch(name) => !name.eq_ignore_ascii_case(&inst.name),
+        ExprVar::Node(_) => false,
+    });
+    if foreign_branch {
+        return Err(StampError::MissingValue);
+    }
+    Ok(Some((expr, is_flux)))
+}
+
+/// 非线性电感表达式各变量所在的列: 节点依次排在两个端口之后, I(自身) 为支路电流列 k
+fn nonlinear_inductor_columns(expr: &BehavioralExpr, inst: &Instance, k: usize) -> Vec<usize> {
+    let mut nodes = inst.nodes[2..].iter();
+    expr.vars
+        .iter()
+        .map(|var| match var {
+            ExprVar::Node(_) => nodes.next().map_or(0, |node| node.0),
+            ExprVar::Branch(_) => k,
+        })
+        .collect()
+}
+
+/// 非线性电感表达式的变量值, I(自身) 取 `current`
+fn nonlinear_inductor_values(expr: &BehavioralExpr, inst: &Instance, x: Option<&[f64]>, current: f64) -> Vec<f64> {
+    let mut nodes = inst.nodes[2..].iter();
+    expr.vars
+        .iter()
+        .map(|var| match var {
+            ExprVar::Node(_) => nodes
+                .next()
+                .and_then(|node| x.and_then(|x| x.get(node.0)))
+                .copied()
+                .unwrap_or(0.0),
+            ExprVar::Branch(_) => current,
+        })
+        .collect()
+}
+
+/// 非线性电容表达式引用的节点电压; 这些节点依次排在两个端口之后
+fn nonlinear_capacitor_values(inst: &Instance, x: Option<&[f64]>) -> Vec<f64> {
+    inst.nodes[2..]
+        .iter()
+        .map(|node| x.and_then(|x| x.get(node.0)).copied().unwrap_or(0.0))
+        .collect()
+}
+
+/// 独立源的直流值; 没有 DC 值时取 PULSE / PWL 波形在 t=0 的值, 只给了 TRNOISE 的源为 0
+fn source_value(inst: &Instance) -> Result<f64, StampError> {
+    match inst.value.as_deref().and_then(parse_number_with_suffix) {
+        Some(value) => Ok(value),
+        None => match build_source_waveform(&inst.params) {
+            Some(waveform) => Ok(waveform.value_at(0.0)),
+            None if inst.params.contains_key("trnoise") => Ok(0.0),
+            None => Err(StampError::MissingValue),
+        },
+    }
+}
+
+fn stamp_current(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError> {
+    stamp_current_value(ctx, inst, source_value(inst)?)
+}
+
+fn stamp_current_value(ctx: &mut StampContext, inst: &Instance, value: f64) -> Result<(), StampError> {
+    if inst.nodes.len() != 2 {
+        return Err(StampError::InvalidNodes);
+    }
     let value = value * ctx.source_scale;
     let a = inst.nodes[0].0;
     let b = inst.nodes[1].0;
@@ -122,14 +608,13 @@ fn stamp_current(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampErr
 }
 
 fn stamp_voltage(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError> {
+    stamp_voltage_value(ctx, inst, source_value(inst)?)
+}
+
+fn stamp_voltage_value(ctx: &mut StampContext, inst: &Instance, value: f64) -> Result<(), StampError> {
     if inst.nodes.len() != 2 {
         return Err(StampError::InvalidNodes);
     }
-    let value = inst
-        .value
-        .as_deref()
-        .and_then(parse_number_with_suffix)
-        .ok_or(StampError::MissingValue)?;
     let value = value * ctx.source_scale;
     let a = inst.nodes[0].0;
     let b = inst.nodes[1].0;
@@ -142,54 +627,260 @@ fn stamp_voltage(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampErr
     Ok(())
 }
 
+/// 瞬态独立源: 求解时刻 (time + dt) 的 PULSE / PWL 波形值 (没有波形时为直流值),
+/// 加上该时刻所在区间的噪声采样
+fn stamp_transient_source(ctx: &mut StampContext, inst: &Instance, dt: f64, state: &mut TransientState) -> Result<(), StampError> {
+    let time = state.time + dt;
+    let noise = state.trnoise.get_mut(StateKey::of(inst)).map_or(0.0, |noise| noise.value_at(time));
+    let value = match state.waveforms.get(StateKey::of(inst)) {
+        Some(waveform) => waveform.value_at(time),
+        None => source_value(inst)?,
+    } + noise;
+    match inst.kind {
+        DeviceKind::I => stamp_current_value(ctx, inst, value),
+        _ => stamp_voltage_value(ctx, inst, value),
+    }
+}
+
+/// 端口的两个节点与参考阻抗
+fn port_terminals(inst: &Instance) -> Result<(usize, usize, f64), StampError> {
+    if inst.nodes.len() != 2 {
+        return Err(StampError::InvalidNodes);
+    }
+    let params = sim_devices::source::build_port_params(&inst.params);
+    Ok((inst.nodes[0].0, inst.nodes[1].0, params.z0))
+}
+
+/// 端口: 源电压 V 串联 Z0, 按诺顿等效写成电导 1/Z0 并联 V/Z0 的电流源;
+/// 未给 DC 值时只是 Z0 端接
+fn stamp_port(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError> {
+    let (a, b, z0) = port_terminals(inst)?;
+    let value = match inst.value.as_deref() {
+        Some(value) => parse_number_with_suffix(value).ok_or(StampError::MissingValue)?,
+        None => 0.0,
+    };
+    let g = 1.0 / z0;
+    ctx.add(a, a, g);
+    ctx.add(b, b, g);
+    ctx.add(a, b, -g);
+    ctx.add(b, a, -g);
+    let current = value * ctx.source_scale * g;
+    ctx.add_rhs(a, current);
+    ctx.add_rhs(b, -current);
+    Ok(())
+}
+
+/// 二极管模型: `.model d` 参数按实例面积因子 (第四个字段或 area=) 缩放, 再换算到器件温度
+fn diode_model(inst: &Instance) -> sim_devices::diode::DiodeParams {
+    let area = inst
+        .value
+        .as_deref()
+        .and_then(parse_number_with_suffix)
+        .or_else(|| param_value(&inst.params, &["area"]))
+        .unwrap_or(1.0);
+    let model = sim_devices::diode::build_diode_params(&inst.params).with_area(area);
+    match param_value(&inst.params, &["temp"]) {
+        Some(temp) => model.at_temperature(temp),
+        None => model,
+    }
+}
+
+/// 二极管节点: (外部阳极, 本征阳极, 阴极); RS 非零时本征阳极为展开阶段追加的 `d1#a`
+fn diode_nodes(inst: &Instance) -> Result<(usize, usize, usize), StampError> {
+    match diode_ports(inst).0 {
+        [a, k] => Ok((a.0, a.0, k.0)),
+        [a, k, ai] => Ok((a.0, ai.0, k.0)),
+        _ => Err(StampError::InvalidNodes),
+    }
+}
+
+/// 光电器件的光端口: 展开阶段把 light= (光电二极管的光输入) 与 lightout= (LED 的光输出)
+/// 节点依次追加在电端口之后, 节点电压即光功率 [W]; 返回 (电端口, 光输入, 光输出)
+fn diode_ports(inst: &Instance) -> (&[crate::circuit::NodeId], Option<usize>, Option<usize>) {
+    let nodes = inst.nodes.as_slice();
+    let (nodes, lightout) = match nodes.split_last() {
+        Some((out, rest)) if inst.params.contains_key("lightout") => (rest, Some(out.0)),
+        _ => (nodes, None),
+    };
+    let (nodes, light) = match nodes.split_last() {
+        Some((input, rest)) if inst.params.contains_key("light") => (rest, Some(input.0)),
+        _ => (nodes, None),
+    };
+    (nodes, light, lightout)
+}
+
+/// LED 光功率对结电压的斜率; 反向电流不发光
+fn led_slope(model: &sim_devices::diode::DiodeParams, out: &sim_devices::diode::DiodeOutput) -> f64 {
+    if out.id > 0.0 {
+        model.eta * out.gd
+    } else {
+        0.0
+    }
+}
+
+/// 光电二极管的光电流与 LED 的光输出。光电流 IPH + RESP * P(light) 在器件内由阴极流向阳极;
+/// LED 光功率 ETA * Id 由理想源钉在光输出节点上 (相对地), 对电路没有反作用。
+/// `junction` 为本次线性化的 (结电压, 结电流), 初始猜测时为 None
+fn stamp_diode_optical(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    model: &sim_devices::diode::DiodeParams,
+    (a, b): (usize, usize),
+    junction: Option<(f64, sim_devices::diode::DiodeOutput)>,
+) {
+    let (_, light, lightout) = diode_ports(inst);
+    let iph = model.photocurrent(0.0);
+    if iph != 0.0 {
+        ctx.add_rhs(a, iph);
+        ctx.add_rhs(b, -iph);
+    }
+    if let Some(light) = light {
+        ctx.add(a, light, -model.resp);
+        ctx.add(b, light, model.resp);
+    }
+    let Some(out) = lightout else {
+        return;
+    };
+    let k = ctx.allocate_aux(&format!("{}#light", inst.name));
+    ctx.add(out, k, 1.0);
+    ctx.add(k, out, 1.0);
+    if let Some((vd, junction)) = junction {
+        let slope = led_slope(model, &junction);
+        ctx.add(k, a, -slope);
+        ctx.add(k, b, slope);
+        ctx.add_rhs(k, model.optical_power(junction.id) - slope * vd);
+    }
+}
+
 fn stamp_diode(
     ctx: &mut StampContext,
     inst: &Instance,
     x: Option<&[f64]>,
 ) -> Result<(), StampError> {
-    if inst.nodes.len() != 2 {
-        return Err(StampError::InvalidNodes);
-    }
-    let a = inst.nodes[0].0;
-    let b = inst.nodes[1].0;
+    stamp_diode_with(ctx, inst, &diode_model(inst), diode_thermal_voltage(inst), x)
+}
+
+/// 按已解析的模型与热电压给二极管盖章
+fn stamp_diode_with(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    model: &sim_devices::diode::DiodeParams,
+    vt: f64,
+    x: Option<&[f64]>,
+) -> Result<(), StampError> {
+    let (anode, a, b) = diode_nodes(inst)?;
     let gmin = if ctx.gmin > 0.0 { ctx.gmin } else { 1e-12 };
-    let isat = param_value(&inst.params, &["is"]).unwrap_or(1e-14);
-    let emission = param_value(&inst.params, &["n", "nj"]).unwrap_or(1.0);
-    let vt = 0.02585 * emission;
+    if anode != a && model.rs > 0.0 {
+        let g = 1.0 / model.rs;
+        ctx.add(anode, anode, g);
+        ctx.add(a, a, g);
+        ctx.add(anode, a, -g);
+        ctx.add(a, anode, -g);
+    }
     if let Some(x) = x {
         let va = x.get(a).copied().unwrap_or(0.0);
         let vb = x.get(b).copied().unwrap_or(0.0);
-        let vd = va - vb;
-        let exp_vd = (vd / vt).exp();
-        let id = isat * (exp_vd - 1.0);
-        let gd = (isat / vt) * exp_vd;
-        let g = gd.max(gmin);
-        let ieq = id - gd * vd;
+        let vd = ctx.limit_junction_with(&inst.name, va - vb, |vnew, vold| model.limit_voltage(vnew, vold, vt));
+        let out = sim_devices::diode::evaluate_diode(model, vd, vt);
+        let g = out.gd.max(gmin);
+        let ieq = out.id - out.gd * vd;
         ctx.add(a, a, g);
         ctx.add(b, b, g);
         ctx.add(a, b, -g);
         ctx.add(b, a, -g);
         ctx.add_rhs(a, -ieq);
         ctx.add_rhs(b, ieq);
+        stamp_diode_optical(ctx, inst, model, (a, b), Some((vd, out)));
         return Ok(());
     }
     ctx.add(a, a, gmin);
     ctx.add(b, b, gmin);
     ctx.add(a, b, -gmin);
     ctx.add(b, a, -gmin);
+    stamp_diode_optical(ctx, inst, model, (a, b), None);
     Ok(())
 }
 
-fn stamp_mos(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Result<(), StampError> {
-    if inst.nodes.len() < 4 {
-        return Err(StampError::InvalidNodes);
+/// 二极管瞬态: 在直流模型上叠加结电荷 (耗尽电荷 + TT * Id) 的伴随模型,
+/// 上一接受时间点的结电荷保存在 cap_charge 中 (没有时由 cap_voltage 中的结电压计算)
+fn stamp_diode_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &mut TransientState,
+) -> Result<(), StampError> {
+    stamp_diode_tran_with(ctx, inst, &diode_model(inst), diode_thermal_voltage(inst), x, dt, state)
+}
+
+fn stamp_diode_tran_with(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    model: &sim_devices::diode::DiodeParams,
+    vt: f64,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &mut TransientState,
+) -> Result<(), StampError> {
+    stamp_diode_with(ctx, inst, model, vt, x)?;
+    let Some(x) = x else {
+        return Ok(());
+    };
+    if model.cjo <= 0.0 && model.tt <= 0.0 {
+        return Ok(());
     }
-    let drain = inst.nodes[0].0;
-    let gate = inst.nodes[1].0;
-    let source = inst.nodes[2].0;
-    let bulk = inst.nodes[3].0;
-    let gmin = if ctx.gmin > 0.0 { ctx.gmin } else { 1e-12 };
+    let (_, a, b) = diode_nodes(inst)?;
+    let charge = |vd: f64| model.charge(vd, &sim_devices::diode::evaluate_diode(model, vd, vt));
+    let vd = x.get(a).copied().unwrap_or(0.0) - x.get(b).copied().unwrap_or(0.0);
+    let (q, c) = charge(vd);
+    let q_prev = match state.cap_charge.get(StateKey::of(inst)) {
+        Some(q_prev) => *q_prev,
+        None => charge(state.cap_voltage.get(StateKey::of(inst)).copied().unwrap_or(vd)).0,
+    };
+    let companion = state.companion(&state.history.cap_charge, StateKey::of(inst), dt, q_prev);
+    let geq = c / companion.step;
+    let ieq = companion.derivative(q) - geq * vd;
+    ctx.add(a, a, geq);
+    ctx.add(b, b, geq);
+    ctx.add(a, b, -geq);
+    ctx.add(b, a, -geq);
+    ctx.add_rhs(a, -ieq);
+    ctx.add_rhs(b, ieq);
+    Ok(())
+}
+
+/// 记录二极管结电压与结电荷, 作为下一步电荷的起点
+fn record_diode_voltage(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    let Ok((_, a, b)) = diode_nodes(inst) else {
+        return;
+    };
+    let v = |n: usize| x.get(n).copied().unwrap_or(0.0);
+    let vd = v(a) - v(b);
+    state.cap_voltage.insert(StateKey::of(inst), vd);
+    let model = diode_model(inst);
+    if model.cjo > 0.0 || model.tt > 0.0 {
+        let out = sim_devices::diode::evaluate_diode(&model, vd, diode_thermal_voltage(inst));
+        state.cap_charge.insert(StateKey::of(inst), model.charge(vd, &out).0);
+    }
+}
+
+/// MOSFET 模型参数与器件尺寸, 由实例参数 (已合并模型参数) 构造
+#[derive(Clone)]
+struct MosModel {
+    params: sim_devices::bsim::BsimParams,
+    w: f64,
+    l: f64,
+    temp: f64,
+    /// BSIM4 应力参数: 到 STI 的距离
+    sa: f64,
+    sb: f64,
+    /// 源漏结的 (面积, 周长): [AD/PD, AS/PS]
+    junction_geometry: [(f64, f64); 2],
+    /// 源漏结二极管的热电压
+    junction_vt: f64,
+}
 
+fn mos_model(inst: &Instance) -> MosModel {
     // Parse model level (default to 49 for BSIM3)
     let level = param_value(&inst.params, &["level"]).unwrap_or(49.0) as u32;
 
@@ -197,43 +888,247 @@ fn stamp_mos(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Resu
     let is_pmos = if let Some(t) = inst.params.get("type") {
         let t_lower = t.to_ascii_lowercase();
         t_lower.contains("pmos") || t_lower == "p"
-    } else if inst.params.contains_key("pmos") {
-        true
     } else {
-        false
+        inst.params.contains_key("pmos")
     };
 
-    // Build BSIM parameters from instance params
-    let params = sim_devices::bsim::build_bsim_params(&inst.params, level, is_pmos);
-
-    // Get device dimensions
-    let w = param_value(&inst.params, &["w"]).unwrap_or(1e-6);
-    let l = param_value(&inst.params, &["l"]).unwrap_or(1e-6);
+    MosModel {
+        params: sim_devices::bsim::build_bsim_params(&inst.params, level, is_pmos),
+        w: param_value(&inst.params, &["w"]).unwrap_or(1e-6),
+        l: param_value(&inst.params, &["l"]).unwrap_or(1e-6),
+        // Temperature (default 27C = 300.15K)
+        temp: param_value(&inst.params, &["temp"]).unwrap_or(300.15),
+        sa: param_value(&inst.params, &["sa"]).unwrap_or(0.0),
+        sb: param_value(&inst.params, &["sb"]).unwrap_or(0.0),
+        junction_geometry: [("ad", "pd"), ("as", "ps")].map(|(area, perimeter)| {
+            (
+                param_value(&inst.params, &[area]).unwrap_or(0.0),
+                param_value(&inst.params, &[perimeter]).unwrap_or(0.0),
+            )
+        }),
+        junction_vt: diode_thermal_voltage(inst),
+    }
+}
 
-    // Temperature (default 27C = 300.15K)
-    let temp = param_value(&inst.params, &["temp"]).unwrap_or(300.15);
+impl MosModel {
+    /// 按模型等级求值; Level 54 计入 SA/SB 应力
+    fn evaluate(&self, vd: f64, vg: f64, vs: f64, vb: f64) -> sim_devices::bsim::BsimOutput {
+        if self.params.level == 54 {
+            self.evaluate_bsim4(vd, vg, vs, vb).base
+        } else {
+            sim_devices::bsim::evaluate_mos(&self.params, self.w, self.l, vd, vg, vs, vb, self.temp)
+        }
+    }
 
-    // BSIM4: Stress parameters (SA/SB distance to STI)
-    let sa = param_value(&inst.params, &["sa"]).unwrap_or(0.0);
-    let sb = param_value(&inst.params, &["sb"]).unwrap_or(0.0);
+    fn evaluate_bsim4(&self, vd: f64, vg: f64, vs: f64, vb: f64) -> sim_devices::bsim::Bsim4Output {
+        sim_devices::bsim::evaluate_mos_bsim4(
+            &self.params, self.w, self.l, vd, vg, vs, vb, self.temp, self.sa, self.sb,
+        )
+    }
 
-    if let Some(x) = x {
-        let vd = x.get(drain).copied().unwrap_or(0.0);
-        let vg = x.get(gate).copied().unwrap_or(0.0);
-        let vs = x.get(source).copied().unwrap_or(0.0);
-        let vb = x.get(bulk).copied().unwrap_or(0.0);
+    /// 端电荷 [qd, qg, qs, qb], 端电压按 [vd, vg, vs, vb] 给出
+    fn charges(&self, v: [f64; 4]) -> [f64; 4] {
+        let out = self.evaluate(v[0], v[1], v[2], v[3]);
+        [out.qd, out.qg, out.qs, out.qb]
+    }
 
-        // Use BSIM4 evaluator for Level 54, BSIM3 for others
-        if level == 54 {
-            // BSIM4: Full evaluation with stress and additional currents
-            let output = sim_devices::bsim::evaluate_mos_bsim4(
-                &params, w, l, vd, vg, vs, vb, temp, sa, sb
-            );
+    /// 电容矩阵 C[i][j] = dQi/dVj, 由端电荷中心差分得到 (一般不对称)
+    fn charge_jacobian(&self, v: [f64; 4]) -> [[f64; 4]; 4] {
+        terminal_jacobian(|v| self.charges(v), v)
+    }
 
-            let gm = output.base.gm;
-            let gds = output.base.gds.max(gmin);
-            let gmbs = output.base.gmbs;
-            let ieq = output.base.ieq;
+    /// BSIM4 是否启用 Igc 分配或 GIDL/GISL
+    fn has_leakage(&self) -> bool {
+        let p = &self.params;
+        p.level == 54 && (p.igcmod > 0 || p.agidl > 0.0 || p.binning.contains_key("agidl"))
+    }
+
+    /// 从各端流入器件的漏电流 [d, g, s, b]: Igc 分配与 GIDL/GISL
+    fn leakage(&self, v: [f64; 4]) -> [f64; 4] {
+        let out = self.evaluate_bsim4(v[0], v[1], v[2], v[3]);
+        [
+            out.igidl - out.igcd,
+            out.igcs + out.igcd,
+            out.igisl - out.igcs,
+            -(out.igidl + out.igisl),
+        ]
+    }
+}
+
+/// 多端量 f(v) 对端电压的雅可比 J[i][j] = dfi/dVj, 中心差分
+fn terminal_jacobian<const N: usize>(f: impl Fn([f64; N]) -> [f64; N], v: [f64; N]) -> [[f64; N]; N] {
+    const H: f64 = 1e-6;
+    let mut jac = [[0.0; N]; N];
+    for j in 0..N {
+        let (mut plus, mut minus) = (v, v);
+        plus[j] += H;
+        minus[j] -= H;
+        let (fp, fm) = (f(plus), f(minus));
+        for (row, (p, m)) in jac.iter_mut().zip(fp.iter().zip(fm)) {
+            row[j] = (p - m) / (2.0 * H);
+        }
+    }
+    jac
+}
+
+/// BSIM4 漏电流 (Igc 分配, GIDL/GISL) 的牛顿线性化: 各端电流依赖全部四个端电压
+fn stamp_mos_leakage(ctx: &mut StampContext, nodes: [usize; 4], model: &MosModel, v: [f64; 4]) {
+    stamp_terminal_currents(ctx, nodes, |v| model.leakage(v), v);
+}
+
+/// 端电流 I(v) (从各端流入器件) 在端电压 v 处的牛顿线性化
+fn stamp_terminal_currents<const N: usize>(
+    ctx: &mut StampContext,
+    nodes: [usize; N],
+    currents: impl Fn([f64; N]) -> [f64; N],
+    v: [f64; N],
+) {
+    let i = currents(v);
+    let g = terminal_jacobian(currents, v);
+    for (k, &row) in nodes.iter().enumerate() {
+        let mut ieq = i[k];
+        for (&col, (&g_kj, &v_j)) in nodes.iter().zip(g[k].iter().zip(&v)) {
+            ctx.add(row, col, g_kj);
+            ieq -= g_kj * v_j;
+        }
+        ctx.add_rhs(row, -ieq);
+    }
+}
+
+/// MOSFET 自热: RTH > 0 时展开阶段追加热节点 `m1#t`, 节点"电压"是器件相对环境的温升 [K]。
+/// 热网络为 RTH 与 CTH 并联到地, 沟道功耗 Ids·Vds 作为电流注入热节点
+#[derive(Clone)]
+struct MosThermal {
+    node: usize,
+    rth: f64,
+    cth: f64,
+}
+
+fn mos_thermal(inst: &Instance) -> Option<MosThermal> {
+    let rth = param_value(&inst.params, &["rth"]).filter(|rth| *rth > 0.0)?;
+    Some(MosThermal {
+        node: inst.nodes.get(4)?.0,
+        rth,
+        cth: param_value(&inst.params, &["cth"]).unwrap_or(0.0).max(0.0),
+    })
+}
+
+/// 按解 `x` 中的温升加热后的模型; 没有热节点时就是 [`mos_model`]
+fn heated_mos_model(inst: &Instance, x: &[f64]) -> MosModel {
+    let model = mos_model(inst);
+    match mos_thermal(inst) {
+        Some(thermal) => model.heated(&thermal, x),
+        None => model,
+    }
+}
+
+impl MosModel {
+    /// 热节点温升为 `x` 中的值时的模型
+    fn heated(&self, thermal: &MosThermal, x: &[f64]) -> MosModel {
+        let mut model = self.clone();
+        model.temp += x.get(thermal.node).copied().unwrap_or(0.0);
+        model
+    }
+}
+
+/// 自热的牛顿线性化: 沟道电流对温升的导数耦合到漏/源行, 热节点行为
+/// ΔT/RTH - P(V, ΔT) = 0, P = Ids·Vds 对四个端电压与温升线性化 (中心差分)
+fn stamp_mos_thermal(ctx: &mut StampContext, inst: &Instance, model: &MosModel, thermal: &MosThermal, x: Option<&[f64]>) {
+    let t = thermal.node;
+    ctx.add(t, t, 1.0 / thermal.rth);
+    let Some(x) = x else { return };
+    let nodes = mos_terminals(inst);
+    let v = nodes.map(|n| x.get(n).copied().unwrap_or(0.0));
+    let rise = x.get(t).copied().unwrap_or(0.0);
+    let ambient = model.temp - rise;
+    let mut probe = model.clone();
+    let mut ids = |v: [f64; 4], rise: f64| {
+        probe.temp = ambient + rise;
+        probe.evaluate(v[0], v[1], v[2], v[3]).ids
+    };
+    const H_T: f64 = 1e-3;
+    const H_V: f64 = 1e-6;
+    let power = |ids: f64, v: [f64; 4]| ids * (v[0] - v[2]);
+
+    // 电流随温度: I_d ≈ I_d0 + gT·(ΔT - ΔT0)
+    let g_t = (ids(v, rise + H_T) - ids(v, rise - H_T)) / (2.0 * H_T);
+    let (drain, source) = (nodes[0], nodes[2]);
+    ctx.add(drain, t, g_t);
+    ctx.add(source, t, -g_t);
+    ctx.add_rhs(drain, g_t * rise);
+    ctx.add_rhs(source, -g_t * rise);
+
+    let p0 = power(ids(v, rise), v);
+    let dp_dt = (power(ids(v, rise + H_T), v) - power(ids(v, rise - H_T), v)) / (2.0 * H_T);
+    let mut rhs = p0 - dp_dt * rise;
+    ctx.add(t, t, -dp_dt);
+    for (j, &col) in nodes.iter().enumerate() {
+        let (mut plus, mut minus) = (v, v);
+        plus[j] += H_V;
+        minus[j] -= H_V;
+        let dp_dv = (power(ids(plus, rise), plus) - power(ids(minus, rise), minus)) / (2.0 * H_V);
+        ctx.add(t, col, -dp_dv);
+        rhs -= dp_dv * v[j];
+    }
+    ctx.add_rhs(t, rhs);
+}
+
+fn stamp_mos(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Result<(), StampError> {
+    if sim_devices::power::is_vdmos(&inst.params) {
+        return stamp_vdmos(ctx, inst, x);
+    }
+    if inst.nodes.len() < 4 {
+        return Err(StampError::InvalidNodes);
+    }
+    stamp_mos_with(ctx, inst, &mos_model(inst), mos_thermal(inst).as_ref(), x)
+}
+
+/// 按已解析的模型与热网络给 (非 VDMOS 的四端) MOSFET 盖章; 有热节点时模型先按温升加热
+fn stamp_mos_with(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    model: &MosModel,
+    thermal: Option<&MosThermal>,
+    x: Option<&[f64]>,
+) -> Result<(), StampError> {
+    let heated;
+    let model = match (x, thermal) {
+        (Some(x), Some(thermal)) => {
+            heated = model.heated(thermal, x);
+            &heated
+        }
+        _ => model,
+    };
+    if let Some(thermal) = thermal {
+        stamp_mos_thermal(ctx, inst, model, thermal, x);
+    }
+    stamp_mos_channel(ctx, inst, model, x)
+}
+
+/// MOSFET 沟道与源漏结的直流盖章, 器件温度取 `model.temp`
+fn stamp_mos_channel(ctx: &mut StampContext, inst: &Instance, model: &MosModel, x: Option<&[f64]>) -> Result<(), StampError> {
+    let drain = inst.nodes[0].0;
+    let gate = inst.nodes[1].0;
+    let source = inst.nodes[2].0;
+    let bulk = inst.nodes[3].0;
+    let gmin = if ctx.gmin > 0.0 { ctx.gmin } else { 1e-12 };
+    stamp_mos_junctions(ctx, inst, model, x);
+
+    if let Some(x) = x {
+        let vd = x.get(drain).copied().unwrap_or(0.0);
+        let vg = x.get(gate).copied().unwrap_or(0.0);
+        let vs = x.get(source).copied().unwrap_or(0.0);
+        let vb = x.get(bulk).copied().unwrap_or(0.0);
+
+        // Use BSIM4 evaluator for Level 54, BSIM3 for others
+        if model.params.level == 54 {
+            // BSIM4: Full evaluation with stress and additional currents
+            let output = model.evaluate_bsim4(vd, vg, vs, vb);
+
+            let gm = output.base.gm;
+            let gds = output.base.gds.max(gmin);
+            let gmbs = output.base.gmbs;
+            let ieq = output.base.ieq;
 
             // Stamp gds (output conductance between drain and source)
             ctx.add(drain, drain, gds);
@@ -299,13 +1194,16 @@ fn stamp_mos(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Resu
                 ctx.add_rhs(drain, igd_eq);
             }
 
+            // BSIM4: Igc 分配与 GIDL/GISL
+            if model.has_leakage() {
+                stamp_mos_leakage(ctx, [drain, gate, source, bulk], model, [vd, vg, vs, vb]);
+            }
+
             return Ok(());
         }
 
         // BSIM3 or Level 1: Use standard evaluator
-        let output = sim_devices::bsim::evaluate_mos(
-            &params, w, l, vd, vg, vs, vb, temp
-        );
+        let output = model.evaluate(vd, vg, vs, vb);
 
         let gm = output.gm;
         let gds = output.gds.max(gmin);
@@ -346,154 +1244,1787 @@ fn stamp_mos(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Resu
     Ok(())
 }
 
-pub fn debug_dump_stamp(instance: &Instance) {
-    println!(
-        "stamp: name={} kind={:?} nodes={} value={:?}",
-        instance.name,
-        instance.kind,
-        instance.nodes.len(),
-        instance.value
-    );
+/// 器件在解 `x` 处的工作点 (MOSFET 与二极管); 其他器件为 None
+pub fn device_op_info(inst: &Instance, x: &[f64]) -> Option<DeviceOpKind> {
+    let v = |node: usize| x.get(node).copied().unwrap_or(0.0);
+    match inst.kind {
+        DeviceKind::M if sim_devices::power::is_vdmos(&inst.params) => {
+            let (_, internal) = vdmos_nodes(inst).ok()?;
+            let out = sim_devices::power::build_vdmos_params(&inst.params).channel_output(internal.map(v));
+            Some(DeviceOpKind::Mosfet {
+                ids: out.id,
+                gm: out.gm,
+                gds: out.gds,
+                gmbs: 0.0,
+                vth: param_value(&inst.params, &["vto", "vth"]).unwrap_or(0.0),
+                region: out.region,
+            })
+        }
+        DeviceKind::M if inst.nodes.len() >= 4 => {
+            let [drain, gate, source, bulk] = mos_terminals(inst);
+            let out = heated_mos_model(inst, x).evaluate(v(drain), v(gate), v(source), v(bulk));
+            Some(DeviceOpKind::Mosfet {
+                ids: out.ids,
+                gm: out.gm,
+                gds: out.gds,
+                gmbs: out.gmbs,
+                vth: out.vth_eff,
+                region: out.region,
+            })
+        }
+        DeviceKind::D => {
+            let (_, a, b) = diode_nodes(inst).ok()?;
+            let model = diode_model(inst);
+            let out = sim_devices::diode::evaluate_diode(&model, v(a) - v(b), diode_thermal_voltage(inst));
+            let light = diode_ports(inst).1.map(v).unwrap_or(0.0);
+            Some(DeviceOpKind::Diode { id: out.id - model.photocurrent(light), gd: out.gd })
+        }
+        _ => None,
+    }
 }
 
-pub fn update_transient_state(instances: &[Instance], x: &[f64], state: &mut TransientState) {
-    for inst in instances {
-        match inst.kind {
-            DeviceKind::C => {
-                if inst.nodes.len() == 2 {
-                    let a = inst.nodes[0].0;
-                    let b = inst.nodes[1].0;
-                    let va = x.get(a).copied().unwrap_or(0.0);
-                    let vb = x.get(b).copied().unwrap_or(0.0);
-                    state.cap_voltage.insert(inst.name.clone(), va - vb);
-                }
+/// 器件在工作点 `x` 处的噪声电流源: 电阻热噪声, 二极管散粒与闪烁噪声 (及 RS 热噪声),
+/// MOSFET 沟道热噪声与闪烁噪声; 闪烁噪声取模型的 KF / AF / EF, KF 为零时不产生.
+/// 其他器件视为无噪声
+pub fn noise_sources(inst: &Instance, x: &[f64]) -> Vec<NoiseSource> {
+    let v = |node: usize| x.get(node).copied().unwrap_or(0.0);
+    let kt = |inst: &Instance| BOLTZMANN * param_value(&inst.params, &["temp"]).unwrap_or(300.15);
+    let source = |mechanism, nodes, density, exponent| NoiseSource {
+        device: inst.name.clone(),
+        mechanism,
+        nodes,
+        density: density * instance_multiplier(inst),
+        exponent,
+    };
+    let flicker = |current: f64, scale: f64, nodes| {
+        let kf = param_value(&inst.params, &["kf"]).unwrap_or(0.0);
+        let af = param_value(&inst.params, &["af"]).unwrap_or(1.0);
+        let ef = param_value(&inst.params, &["ef"]).unwrap_or(1.0);
+        (kf > 0.0).then(|| source(NoiseMechanism::Flicker, nodes, kf * current.abs().powf(af) / scale, ef))
+    };
+    match inst.kind {
+        DeviceKind::R if inst.nodes.len() == 2 => match resistance(inst) {
+            Ok(r) if r > 0.0 => {
+                let nodes = (inst.nodes[0].0, inst.nodes[1].0);
+                vec![source(NoiseMechanism::Thermal, nodes, 4.0 * kt(inst) / r, 0.0)]
             }
-            DeviceKind::L => {
-                if let Some(aux) = state.ind_aux.get(&inst.name) {
-                    if let Some(current) = x.get(*aux).copied() {
-                        state.ind_current.insert(inst.name.clone(), current);
-                    }
-                }
+            _ => Vec::new(),
+        },
+        DeviceKind::D => {
+            let Ok((anode, a, k)) = diode_nodes(inst) else { return Vec::new() };
+            let model = diode_model(inst);
+            let id = sim_devices::diode::evaluate_diode(&model, v(a) - v(k), diode_thermal_voltage(inst)).id;
+            let mut sources = vec![source(NoiseMechanism::Shot, (a, k), 2.0 * CHARGE * id.abs(), 0.0)];
+            sources.extend(flicker(id, 1.0, (a, k)));
+            if anode != a && model.rs > 0.0 {
+                sources.push(source(NoiseMechanism::Thermal, (anode, a), 4.0 * kt(inst) / model.rs, 0.0));
             }
-            _ => {}
+            sources
         }
+        DeviceKind::M if inst.nodes.len() >= 4 && !sim_devices::power::is_vdmos(&inst.params) => {
+            let [drain, gate, source_node, bulk] = mos_terminals(inst);
+            let model = heated_mos_model(inst, x);
+            let out = model.evaluate(v(drain), v(gate), v(source_node), v(bulk));
+            let nodes = (drain, source_node);
+            let leff = model.params.leff(model.l);
+            let mut sources = vec![source(NoiseMechanism::Thermal, nodes, 8.0 * kt(inst) * out.gm.abs() / 3.0, 0.0)];
+            sources.extend(flicker(out.ids, model.params.cox() * leff * leff, nodes));
+            sources
+        }
+        _ => Vec::new(),
     }
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct TransientState {
-    pub cap_voltage: HashMap<String, f64>,
-    pub ind_current: HashMap<String, f64>,
-    pub ind_aux: HashMap<String, usize>,
+/// MOSFET 源漏结二极管 (状态支路, 阳极, 阴极, 结):
+/// NMOS 阳极为衬底, PMOS 阳极为漏/源区; 尺寸取实例参数 AD/PD 与 AS/PS
+fn mos_junctions(inst: &Instance, model: &MosModel) -> [(StateBranch, usize, usize, sim_devices::bsim::MosJunction); 2] {
+    use sim_devices::bsim::{MosJunction, MosType};
+    let [drain, _, source, bulk] = mos_terminals(inst);
+    let params = model.params.at_temperature(model.temp);
+    let [drain_junction, source_junction] = model.junction_geometry.map(|(area, perimeter)| MosJunction::new(&params, area, perimeter));
+    let orient = |diffusion: usize| match model.params.mos_type {
+        MosType::Nmos => (bulk, diffusion),
+        MosType::Pmos => (diffusion, bulk),
+    };
+    let (bd_a, bd_k) = orient(drain);
+    let (bs_a, bs_k) = orient(source);
+    [
+        (StateBranch::Bd, bd_a, bd_k, drain_junction),
+        (StateBranch::Bs, bs_a, bs_k, source_junction),
+    ]
 }
 
-fn parse_number_with_suffix(token: &str) -> Option<f64> {
-    let lower = token.to_ascii_lowercase();
-    let trimmed = lower.trim();
-    let (num_str, multiplier) = if trimmed.ends_with("meg") {
-        (&trimmed[..trimmed.len() - 3], 1e6)
-    } else {
-        let (value_part, suffix) = trimmed.split_at(trimmed.len().saturating_sub(1));
-        match suffix {
-            "f" => (value_part, 1e-15),
-            "p" => (value_part, 1e-12),
-            "n" => (value_part, 1e-9),
-            "u" => (value_part, 1e-6),
-            "m" => (value_part, 1e-3),
-            "k" => (value_part, 1e3),
-            "g" => (value_part, 1e9),
-            "t" => (value_part, 1e12),
-            _ => (trimmed, 1.0),
-        }
-    };
-
-    if let Ok(num) = num_str.parse::<f64>() {
-        Some(num * multiplier)
-    } else {
-        None
+/// 源漏结二极管的直流部分; 结电压按 pnjlim 限幅
+fn stamp_mos_junctions(ctx: &mut StampContext, inst: &Instance, model: &MosModel, x: Option<&[f64]>) {
+    let gmin = if ctx.gmin > 0.0 { ctx.gmin } else { 1e-12 };
+    let vt = model.junction_vt;
+    for (branch, a, k, junction) in mos_junctions(inst, model) {
+        let Some(x) = x else {
+            ctx.add(a, a, gmin);
+            ctx.add(k, k, gmin);
+            ctx.add(a, k, -gmin);
+            ctx.add(k, a, -gmin);
+            continue;
+        };
+        let nvt = junction.n * vt;
+        let vcrit = sim_devices::junction::vcrit(junction.is, nvt);
+        let vnew = x.get(a).copied().unwrap_or(0.0) - x.get(k).copied().unwrap_or(0.0);
+        let v = ctx.limit_junction(&format!("{}:{}", inst.name, branch.suffix()), vnew, nvt, vcrit);
+        let (i, g) = junction.current(v, vt);
+        let g = g + gmin;
+        let ieq = i + gmin * v - g * v;
+        ctx.add(a, a, g);
+        ctx.add(k, k, g);
+        ctx.add(a, k, -g);
+        ctx.add(k, a, -g);
+        ctx.add_rhs(a, -ieq);
+        ctx.add_rhs(k, ieq);
     }
 }
 
-fn param_value(params: &HashMap<String, String>, keys: &[&str]) -> Option<f64> {
-    for key in keys {
-        let key = key.to_ascii_lowercase();
-        if let Some(value) = params.get(&key) {
-            if let Some(num) = parse_number_with_suffix(value).or_else(|| value.parse().ok()) {
-                return Some(num);
-            }
+/// 源漏结耗尽电容的瞬态部分: 按结电荷做后向欧拉
+fn stamp_mos_junction_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    model: &MosModel,
+    x: &[f64],
+    dt: f64,
+    state: &TransientState,
+) {
+    for (branch, a, k, junction) in mos_junctions(inst, model) {
+        if junction.cj <= 0.0 && junction.cjsw <= 0.0 {
+            continue;
         }
+        let v = x.get(a).copied().unwrap_or(0.0) - x.get(k).copied().unwrap_or(0.0);
+        let v_prev = state.cap_voltage.get(StateKey::branch(inst, branch)).copied().unwrap_or(v);
+        let (q_prev, _) = junction.charge(v_prev);
+        let (q, c) = junction.charge(v);
+        let geq = c / dt;
+        let ieq = (q - q_prev) / dt - geq * v;
+        ctx.add(a, a, geq);
+        ctx.add(k, k, geq);
+        ctx.add(a, k, -geq);
+        ctx.add(k, a, -geq);
+        ctx.add_rhs(a, -ieq);
+        ctx.add_rhs(k, ieq);
     }
-    None
 }
 
-fn stamp_capacitor_tran(
+/// MOSFET 四个端口 [d, g, s, b]
+fn mos_terminals(inst: &Instance) -> [usize; 4] {
+    [0, 1, 2, 3].map(|i| inst.nodes[i].0)
+}
+
+/// MOSFET 栅电容支路 (状态支路, 栅极, 另一端), 顺序与 BsimOutput 的 cgs / cgd / cgb 对应
+fn mos_capacitors(inst: &Instance) -> [(StateBranch, usize, usize); 3] {
+    let [drain, gate, source, bulk] = mos_terminals(inst);
+    [
+        (StateBranch::Gs, gate, source),
+        (StateBranch::Gd, gate, drain),
+        (StateBranch::Gb, gate, bulk),
+    ]
+}
+
+/// MOSFET 瞬态: 直流部分加上栅电容 (后向欧拉)。
+/// capmod=0 用 Meyer 电容, 按上一时间点的 Vgs / Vgd / Vgb 求值, 本步内为线性电容;
+/// capmod>0 对端电荷做差分 i = (Q(v) - Q_prev) / dt, 电荷守恒
+fn stamp_mos_tran(
     ctx: &mut StampContext,
     inst: &Instance,
     x: Option<&[f64]>,
     dt: f64,
     state: &mut TransientState,
 ) -> Result<(), StampError> {
-    if inst.nodes.len() != 2 {
+    if sim_devices::power::is_vdmos(&inst.params) {
+        return stamp_vdmos_tran(ctx, inst, x, dt, state);
+    }
+    if inst.nodes.len() < 4 {
         return Err(StampError::InvalidNodes);
     }
-    let c = inst
-        .value
-        .as_deref()
-        .and_then(parse_number_with_suffix)
-        .ok_or(StampError::MissingValue)?;
-    let g = c / dt;
-    let a = inst.nodes[0].0;
-    let b = inst.nodes[1].0;
-    let v_prev = *state.cap_voltage.get(&inst.name).unwrap_or(&0.0);
-    let ieq = g * v_prev;
-    ctx.add(a, a, g);
-    ctx.add(b, b, g);
-    ctx.add(a, b, -g);
-    ctx.add(b, a, -g);
-    ctx.add_rhs(a, -ieq);
-    ctx.add_rhs(b, ieq);
-    let _ = x;
-    Ok(())
+    stamp_mos_tran_with(ctx, inst, &mos_model(inst), mos_thermal(inst).as_ref(), x, dt, state)
 }
 
-fn stamp_inductor_tran(
+fn stamp_mos_tran_with(
     ctx: &mut StampContext,
     inst: &Instance,
+    model: &MosModel,
+    thermal: Option<&MosThermal>,
     x: Option<&[f64]>,
     dt: f64,
     state: &mut TransientState,
 ) -> Result<(), StampError> {
-    if inst.nodes.len() != 2 {
+    stamp_mos_with(ctx, inst, model, thermal, x)?;
+    if let Some(x) = x {
+        stamp_mos_junction_tran(ctx, inst, model, x, dt, state);
+    }
+    // 热容: 上一接受时间点的温升记在 cap_voltage 的 Thermal 支路
+    if let Some(thermal) = thermal.filter(|thermal| thermal.cth > 0.0) {
+        let g = thermal.cth / dt;
+        let rise_prev = state.cap_voltage.get(StateKey::branch(inst, StateBranch::Thermal)).copied().unwrap_or(0.0);
+        ctx.add(thermal.node, thermal.node, g);
+        ctx.add_rhs(thermal.node, g * rise_prev);
+    }
+    if model.params.capmod > 0 {
+        let Some(x) = x else {
+            return Ok(());
+        };
+        let nodes = mos_terminals(inst);
+        let v = nodes.map(|n| x.get(n).copied().unwrap_or(0.0));
+        let q = model.charges(v);
+        let c = model.charge_jacobian(v);
+        let q_prev = state.mos_charge.get(StateKey::of(inst)).copied().unwrap_or(q);
+        for (i, &row) in nodes.iter().enumerate() {
+            let mut ieq = (q[i] - q_prev[i]) / dt;
+            for (&col, (&c_ij, &v_j)) in nodes.iter().zip(c[i].iter().zip(&v)) {
+                let g = c_ij / dt;
+                ctx.add(row, col, g);
+                ieq -= g * v_j;
+            }
+            ctx.add_rhs(row, -ieq);
+        }
+        return Ok(());
+    }
+    let branches = mos_capacitors(inst);
+    let [vgs, vgd, vgb] = branches
+        .each_ref()
+        .map(|(branch, _, _)| state.cap_voltage.get(StateKey::branch(inst, *branch)).copied().unwrap_or(0.0));
+    // 只有端电压差有意义, 以源极为参考重建四端电压
+    let out = model.evaluate(vgs - vgd, vgs, 0.0, vgs - vgb);
+    for ((_, a, b), (c, v_prev)) in branches.into_iter().zip([(out.cgs, vgs), (out.cgd, vgd), (out.cgb, vgb)]) {
+        if c <= 0.0 {
+            continue;
+        }
+        let g = c / dt;
+        ctx.add(a, a, g);
+        ctx.add(b, b, g);
+        ctx.add(a, b, -g);
+        ctx.add(b, a, -g);
+        ctx.add_rhs(a, g * v_prev);
+        ctx.add_rhs(b, -g * v_prev);
+    }
+    Ok(())
+}
+
+/// 记录 MOSFET 的电容状态: Meyer 模型记三个栅电容上的电压, 电荷模型记端电荷
+fn record_mos_state(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    if sim_devices::power::is_vdmos(&inst.params) {
+        return record_vdmos_state(inst, x, state);
+    }
+    if inst.nodes.len() < 4 {
+        return;
+    }
+    let v = |n: usize| x.get(n).copied().unwrap_or(0.0);
+    let model = mos_model(inst);
+    for (branch, a, k, _) in mos_junctions(inst, &model) {
+        state.cap_voltage.insert(StateKey::branch(inst, branch), v(a) - v(k));
+    }
+    if let Some(thermal) = mos_thermal(inst) {
+        state.cap_voltage.insert(StateKey::branch(inst, StateBranch::Thermal), v(thermal.node));
+    }
+    if model.params.capmod > 0 {
+        let charges = model.charges(mos_terminals(inst).map(v));
+        state.mos_charge.insert(StateKey::of(inst), charges);
+        return;
+    }
+    for (branch, a, b) in mos_capacitors(inst) {
+        state.cap_voltage.insert(StateKey::branch(inst, branch), v(a) - v(b));
+    }
+}
+
+/// BJT 的端口 [c, b, e] 与串联电阻后的节点 [c', b', e']; 没有串联电阻节点时两者相同。
+/// 第三项是本征集电极: VBIC 带 RCI 时为 `q1#ci`, 否则就是 c'
+fn bjt_nodes(inst: &Instance) -> Result<([usize; 3], [usize; 3], usize), StampError> {
+    let node = |idx: usize| inst.nodes.get(idx).map(|n| n.0);
+    let (Some(c), Some(b), Some(e)) = (node(0), node(1), node(2)) else {
         return Err(StampError::InvalidNodes);
+    };
+    let internal = [
+        node(4).unwrap_or(c),
+        node(5).unwrap_or(b),
+        node(6).unwrap_or(e),
+    ];
+    Ok(([c, b, e], internal, node(7).unwrap_or(internal[0])))
+}
+
+/// BJT 模型 (level=4 为 VBIC, 否则 Gummel-Poon), 已乘面积因子 (位置参数或 area=)
+fn bjt_model(inst: &Instance) -> sim_devices::bjt::BjtModel {
+    let area = inst
+        .value
+        .as_deref()
+        .and_then(parse_number_with_suffix)
+        .or_else(|| param_value(&inst.params, &["area"]))
+        .unwrap_or(1.0);
+    sim_devices::bjt::build_bjt_model(&inst.params, area)
+}
+
+/// VBIC 外延层电流 (cx 流向 ci) 在实际结电压 (vbci, vbcx) 处的线性化:
+/// (dI/dVbci, dI/dVbcx, 等效电流), 以流入 cx 端为正
+fn bjt_epi_linearize(
+    inst: &Instance,
+    model: &sim_devices::bjt::BjtModel,
+    vbci: f64,
+    vbcx: f64,
+) -> (f64, f64, f64) {
+    let sim_devices::bjt::BjtModel::Vbic(params) = model else {
+        return (0.0, 0.0, 0.0);
+    };
+    let sign = params.bjt_type.sign();
+    let vt = diode_thermal_voltage(inst);
+    let out = sim_devices::vbic::evaluate_kull(params, sign * vbci, sign * vbcx, vt);
+    (
+        out.dirci_dvbci,
+        out.dirci_dvbcx,
+        sign * out.irci - out.dirci_dvbci * vbci - out.dirci_dvbcx * vbcx,
+    )
+}
+
+/// 本征 BJT 在结电压 (vbe, vbc) 处的线性化: 集电极、基极、发射极三行各自的
+/// (dI/dVbe, dI/dVbc, 等效电流), 电流以流入器件为正; 两个结各并一个 gmin
+fn bjt_linearize(
+    inst: &Instance,
+    model: &sim_devices::bjt::BjtModel,
+    vbe: f64,
+    vbc: f64,
+    gmin: f64,
+) -> [(f64, f64, f64); 3] {
+    let sign = model.bjt_type().sign();
+    let out = model.evaluate(sign * vbe, sign * vbc, diode_thermal_voltage(inst));
+    // PNP: 电流反号, 对实际结电压的导数不变
+    let collector = (
+        out.dic_dvbe,
+        out.dic_dvbc - gmin,
+        sign * out.ic - out.dic_dvbe * vbe - out.dic_dvbc * vbc,
+    );
+    let base = (
+        out.dib_dvbe + gmin,
+        out.dib_dvbc + gmin,
+        sign * out.ib - out.dib_dvbe * vbe - out.dib_dvbc * vbc,
+    );
+    let emitter = (
+        -(collector.0 + base.0),
+        -(collector.1 + base.1),
+        -(collector.2 + base.2),
+    );
+    [collector, base, emitter]
+}
+
+fn stamp_bjt(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Result<(), StampError> {
+    let (external, internal, ci) = bjt_nodes(inst)?;
+    let model = bjt_model(inst);
+    let gmin = if ctx.gmin > 0.0 { ctx.gmin } else { 1e-12 };
+    for ((ext, int), r) in external.into_iter().zip(internal).zip(model.series_resistances()) {
+        if ext != int && r > 0.0 {
+            let g = 1.0 / r;
+            ctx.add(ext, ext, g);
+            ctx.add(int, int, g);
+            ctx.add(ext, int, -g);
+            ctx.add(int, ext, -g);
+        }
+    }
+    let [cx, bi, ei] = internal;
+    let Some(x) = x else {
+        for other in [ei, ci] {
+            ctx.add(bi, bi, gmin);
+            ctx.add(other, other, gmin);
+            ctx.add(bi, other, -gmin);
+            ctx.add(other, bi, -gmin);
+        }
+        if ci != cx {
+            let g = 1.0 / model.epi_resistance();
+            ctx.add(cx, cx, g);
+            ctx.add(ci, ci, g);
+            ctx.add(cx, ci, -g);
+            ctx.add(ci, cx, -g);
+        }
+        return Ok(());
+    };
+    let v = |n: usize| x.get(n).copied().unwrap_or(0.0);
+    let sign = model.bjt_type().sign();
+    let vt = diode_thermal_voltage(inst);
+    // 结电压限幅在 NPN 坐标系下进行
+    let (is, nf, nr) = model.junction_params();
+    let (nf_vt, nr_vt) = (nf * vt, nr * vt);
+    let vbe = ctx.limit_junction(
+        &format!("{}:be", inst.name),
+        sign * (v(bi) - v(ei)),
+        nf_vt,
+        sim_devices::junction::vcrit(is, nf_vt),
+    );
+    let vbc = ctx.limit_junction(
+        &format!("{}:bc", inst.name),
+        sign * (v(bi) - v(ci)),
+        nr_vt,
+        sim_devices::junction::vcrit(is, nr_vt),
+    );
+    if ci != cx {
+        let (g_ci, g_cx, ieq) = bjt_epi_linearize(inst, &model, sign * vbc, v(bi) - v(cx));
+        for (row, dir) in [(cx, 1.0), (ci, -1.0)] {
+            ctx.add(row, bi, dir * (g_ci + g_cx));
+            ctx.add(row, ci, -dir * g_ci);
+            ctx.add(row, cx, -dir * g_cx);
+            ctx.add_rhs(row, -dir * ieq);
+        }
     }
-    let l = inst
+    let rows = bjt_linearize(inst, &model, sign * vbe, sign * vbc, gmin);
+    for (row, (g_be, g_bc, ieq)) in [ci, bi, ei].into_iter().zip(rows) {
+        ctx.add(row, bi, g_be + g_bc);
+        ctx.add(row, ei, -g_be);
+        ctx.add(row, ci, -g_bc);
+        ctx.add_rhs(row, -ieq);
+    }
+    Ok(())
+}
+
+/// JFET / MESFET 的端口 [d, g, s] 与本征端口 [d', g, s']; 没有 RD/RS 节点时两者相同
+fn jfet_nodes(inst: &Instance) -> Result<([usize; 3], [usize; 3]), StampError> {
+    let node = |idx: usize| inst.nodes.get(idx).map(|n| n.0);
+    let (Some(d), Some(g), Some(s)) = (node(0), node(1), node(2)) else {
+        return Err(StampError::InvalidNodes);
+    };
+    Ok(([d, g, s], [node(3).unwrap_or(d), g, node(4).unwrap_or(s)]))
+}
+
+/// J 为 Shichman-Hodges 模型, Z 为 Statz/Curtice MESFET 模型; 已乘面积因子 (位置参数或 area=)
+fn jfet_model(inst: &Instance) -> sim_devices::mesfet::FetModel {
+    use sim_devices::mesfet::FetModel;
+    let area = inst
         .value
         .as_deref()
         .and_then(parse_number_with_suffix)
-        .ok_or(StampError::MissingValue)?;
+        .or_else(|| param_value(&inst.params, &["area"]))
+        .unwrap_or(1.0);
+    if matches!(inst.kind, DeviceKind::Z) {
+        FetModel::Mesfet(sim_devices::mesfet::build_mesfet_params(&inst.params).with_area(area))
+    } else {
+        FetModel::Jfet(sim_devices::jfet::build_jfet_params(&inst.params).with_area(area))
+    }
+}
+
+/// 本征 JFET / MESFET 在结电压 (vgs, vgd) 处的线性化: 漏极、栅极、源极三行各自的
+/// (dI/dVgs, dI/dVgd, 等效电流), 电流以流入器件为正; 两个栅结各并一个 gmin
+fn jfet_linearize(
+    inst: &Instance,
+    model: &sim_devices::mesfet::FetModel,
+    vgs: f64,
+    vgd: f64,
+    gmin: f64,
+) -> [(f64, f64, f64); 3] {
+    let sign = model.common().jfet_type.sign();
+    let out = model.evaluate(sign * vgs, sign * vgd, diode_thermal_voltage(inst));
+    let d_gd = out.did_dvgd - out.ggd - gmin;
+    let drain = (
+        out.did_dvgs,
+        d_gd,
+        sign * (out.id - out.igd) - out.did_dvgs * vgs - d_gd * vgd,
+    );
+    let (g_gs, g_gd) = (out.ggs + gmin, out.ggd + gmin);
+    let gate = (g_gs, g_gd, sign * (out.igs + out.igd) - g_gs * vgs - g_gd * vgd);
+    let source = (
+        -(drain.0 + gate.0),
+        -(drain.1 + gate.1),
+        -(drain.2 + gate.2),
+    );
+    [drain, gate, source]
+}
+
+fn stamp_jfet(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Result<(), StampError> {
+    if sim_devices::power::is_igbt(&inst.params) {
+        return stamp_igbt(ctx, inst, x);
+    }
+    let (external, internal) = jfet_nodes(inst)?;
+    let fet = jfet_model(inst);
+    let model = fet.common();
+    let gmin = if ctx.gmin > 0.0 { ctx.gmin } else { 1e-12 };
+    for ((ext, int), r) in external.into_iter().zip(internal).zip([model.rd, 0.0, model.rs]) {
+        if ext != int && r > 0.0 {
+            let g = 1.0 / r;
+            ctx.add(ext, ext, g);
+            ctx.add(int, int, g);
+            ctx.add(ext, int, -g);
+            ctx.add(int, ext, -g);
+        }
+    }
+    let [di, g, si] = internal;
+    let Some(x) = x else {
+        for other in [di, si] {
+            ctx.add(g, g, gmin);
+            ctx.add(other, other, gmin);
+            ctx.add(g, other, -gmin);
+            ctx.add(other, g, -gmin);
+        }
+        return Ok(());
+    };
+    let v = |n: usize| x.get(n).copied().unwrap_or(0.0);
+    let sign = model.jfet_type.sign();
+    // 栅结电压限幅在 N 沟道坐标系下进行
+    let nvt = model.n * diode_thermal_voltage(inst);
+    let vcrit = sim_devices::junction::vcrit(model.is, nvt);
+    let vgs = ctx.limit_junction(&format!("{}:gs", inst.name), sign * (v(g) - v(si)), nvt, vcrit);
+    let vgd = ctx.limit_junction(&format!("{}:gd", inst.name), sign * (v(g) - v(di)), nvt, vcrit);
+    let rows = jfet_linearize(inst, &fet, sign * vgs, sign * vgd, gmin);
+    for (row, (g_gs, g_gd, ieq)) in internal.into_iter().zip(rows) {
+        ctx.add(row, g, g_gs + g_gd);
+        ctx.add(row, si, -g_gs);
+        ctx.add(row, di, -g_gd);
+        ctx.add_rhs(row, -ieq);
+    }
+    Ok(())
+}
+
+/// JFET 栅结电容 (CGS 接 g-s', CGD 接 g-d'), 耗尽电容按 m=0.5 随结电压变化;
+/// 返回 (状态支路, 栅极, 另一端, 零偏电容)
+fn jfet_capacitors(model: &sim_devices::jfet::JfetParams, internal: [usize; 3]) -> [(StateBranch, usize, usize, f64); 2] {
+    let [di, g, si] = internal;
+    [
+        (StateBranch::Gs, g, si, model.cgs),
+        (StateBranch::Gd, g, di, model.cgd),
+    ]
+}
+
+/// JFET 瞬态: 直流部分加上以上一时间点结电压计算的栅结电容 (后向欧拉)
+fn stamp_jfet_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &mut TransientState,
+) -> Result<(), StampError> {
+    if sim_devices::power::is_igbt(&inst.params) {
+        return stamp_igbt_tran(ctx, inst, x, dt, state);
+    }
+    stamp_jfet(ctx, inst, x)?;
+    let (_, internal) = jfet_nodes(inst)?;
+    let fet = jfet_model(inst);
+    let model = fet.common();
+    let sign = model.jfet_type.sign();
+    for (branch, a, b, cj0) in jfet_capacitors(model, internal) {
+        let v_prev = *state.cap_voltage.get(StateKey::branch(inst, branch)).unwrap_or(&0.0);
+        let c = sim_devices::junction::depletion_capacitance(cj0, sign * v_prev, model.pb, 0.5, model.fc);
+        if c <= 0.0 {
+            continue;
+        }
+        let g = c / dt;
+        ctx.add(a, a, g);
+        ctx.add(b, b, g);
+        ctx.add(a, b, -g);
+        ctx.add(b, a, -g);
+        ctx.add_rhs(a, g * v_prev);
+        ctx.add_rhs(b, -g * v_prev);
+    }
+    Ok(())
+}
+
+/// 记录 JFET 两个栅结电容上的电压
+fn record_jfet_voltages(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    if sim_devices::power::is_igbt(&inst.params) {
+        return record_igbt_state(inst, x, state);
+    }
+    let Ok((_, internal)) = jfet_nodes(inst) else {
+        return;
+    };
+    let fet = jfet_model(inst);
+    let model = fet.common();
+    let v = |n: usize| x.get(n).copied().unwrap_or(0.0);
+    for (branch, a, b, _) in jfet_capacitors(model, internal) {
+        state.cap_voltage.insert(StateKey::branch(inst, branch), v(a) - v(b));
+    }
+}
+
+/// 串联电阻: 外部端口与本征端口不同且电阻为正时在两者间接入电导
+fn stamp_series_resistances<const N: usize>(ctx: &mut StampContext, external: [usize; N], internal: [usize; N], r: [f64; N]) {
+    for ((ext, int), r) in external.into_iter().zip(internal).zip(r) {
+        if ext != int && r > 0.0 {
+            let g = 1.0 / r;
+            ctx.add(ext, ext, g);
+            ctx.add(int, int, g);
+            ctx.add(ext, int, -g);
+            ctx.add(int, ext, -g);
+        }
+    }
+}
+
+/// 在 a-b 之间并一个电导
+fn stamp_conductance(ctx: &mut StampContext, a: usize, b: usize, g: f64) {
+    ctx.add(a, a, g);
+    ctx.add(b, b, g);
+    ctx.add(a, b, -g);
+    ctx.add(b, a, -g);
+}
+
+/// 端电荷 Q(v) 的后向欧拉: i = (Q(v) - Q_prev) / dt, 电荷守恒
+fn stamp_terminal_charges<const N: usize>(
+    ctx: &mut StampContext,
+    nodes: [usize; N],
+    charges: impl Fn([f64; N]) -> [f64; N],
+    v: [f64; N],
+    q_prev: &[f64],
+    dt: f64,
+) {
+    let q = charges(v);
+    let c = terminal_jacobian(charges, v);
+    for (i, &row) in nodes.iter().enumerate() {
+        let mut ieq = (q[i] - q_prev.get(i).copied().unwrap_or(q[i])) / dt;
+        for (&col, (&c_ij, &v_j)) in nodes.iter().zip(c[i].iter().zip(&v)) {
+            let g = c_ij / dt;
+            ctx.add(row, col, g);
+            ieq -= g * v_j;
+        }
+        ctx.add_rhs(row, -ieq);
+    }
+}
+
+/// 端电流与端电荷在工作点 v 处的小信号导纳 Y = dI/dV + jω dQ/dV
+fn stamp_terminal_admittance<const N: usize>(
+    ctx: &mut ComplexStampContext,
+    nodes: [usize; N],
+    currents: impl Fn([f64; N]) -> [f64; N],
+    charges: impl Fn([f64; N]) -> [f64; N],
+    v: [f64; N],
+) {
+    let g = terminal_jacobian(currents, v);
+    let c = terminal_jacobian(charges, v);
+    for (i, &row) in nodes.iter().enumerate() {
+        for (j, &col) in nodes.iter().enumerate() {
+            ctx.add(row, col, Complex64::new(g[i][j], ctx.omega * c[i][j]));
+        }
+    }
+}
+
+/// VDMOS (`.model x vdmos`) 的端口 [d, g, s] 与 RD/RS 内侧的本征端口 [d', g, s'];
+/// 本征节点排在衬底之后, 衬底端不参与 (体二极管接在源漏之间)
+fn vdmos_nodes(inst: &Instance) -> Result<([usize; 3], [usize; 3]), StampError> {
+    let node = |idx: usize| inst.nodes.get(idx).map(|n| n.0);
+    let (Some(d), Some(g), Some(s)) = (node(0), node(1), node(2)) else {
+        return Err(StampError::InvalidNodes);
+    };
+    Ok(([d, g, s], [node(4).unwrap_or(d), g, node(5).unwrap_or(s)]))
+}
+
+fn stamp_vdmos(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Result<(), StampError> {
+    let (external, internal) = vdmos_nodes(inst)?;
+    let model = sim_devices::power::build_vdmos_params(&inst.params);
+    let gmin = if ctx.gmin > 0.0 { ctx.gmin } else { 1e-12 };
+    stamp_series_resistances(ctx, external, internal, [model.rd, 0.0, model.rs]);
+    let [di, g, si] = internal;
+    stamp_conductance(ctx, di, si, gmin);
+    let Some(x) = x else {
+        stamp_conductance(ctx, g, si, gmin);
+        return Ok(());
+    };
+    // 沟道 Vds 与体二极管结电压依次限幅, 再据此重建漏极电压
+    let mut v = internal.map(|n| x.get(n).copied().unwrap_or(0.0));
+    let vt = diode_thermal_voltage(inst);
+    let sign = model.sign();
+    let vds = ctx.limit_junction_with(&format!("{}:ds", inst.name), sign * (v[0] - v[2]), sim_devices::power::limit_vds);
+    v[0] = v[2] + sign * vds;
+    let vbody = ctx.limit_junction_with(&format!("{}:body", inst.name), sign * (v[2] - v[0]), |vnew, vold| {
+        model.body.limit_voltage(vnew, vold, vt)
+    });
+    v[0] = v[2] - sign * vbody;
+    stamp_terminal_currents(ctx, internal, |v| model.currents(v, vt), v);
+    Ok(())
+}
+
+/// VDMOS 瞬态: 直流部分加上 CGS、Miller 电容与体二极管电荷 (后向欧拉)
+fn stamp_vdmos_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &TransientState,
+) -> Result<(), StampError> {
+    stamp_vdmos(ctx, inst, x)?;
+    let (Some(x), Some(q_prev)) = (x, state.device_state.get(StateKey::of(inst))) else {
+        return Ok(());
+    };
+    let (_, internal) = vdmos_nodes(inst)?;
+    let model = sim_devices::power::build_vdmos_params(&inst.params);
+    let vt = diode_thermal_voltage(inst);
+    let v = internal.map(|n| x.get(n).copied().unwrap_or(0.0));
+    stamp_terminal_charges(ctx, internal, |v| model.charges(v, vt), v, q_prev, dt);
+    Ok(())
+}
+
+/// 记录 VDMOS 本征端电荷
+fn record_vdmos_state(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    let Ok((_, internal)) = vdmos_nodes(inst) else {
+        return;
+    };
+    let model = sim_devices::power::build_vdmos_params(&inst.params);
+    let v = internal.map(|n| x.get(n).copied().unwrap_or(0.0));
+    let charges = model.charges(v, diode_thermal_voltage(inst));
+    state.device_state.insert(StateKey::of(inst), charges.to_vec());
+}
+
+fn stamp_vdmos_ac(ctx: &mut ComplexStampContext, inst: &Instance, dc_solution: &[f64]) -> Result<(), StampError> {
+    let (external, internal) = vdmos_nodes(inst)?;
+    let model = sim_devices::power::build_vdmos_params(&inst.params);
+    for ((ext, int), r) in external.into_iter().zip(internal).zip([model.rd, 0.0, model.rs]) {
+        if ext != int && r > 0.0 {
+            let g = 1.0 / r;
+            ctx.add_real(ext, ext, g);
+            ctx.add_real(int, int, g);
+            ctx.add_real(ext, int, -g);
+            ctx.add_real(int, ext, -g);
+        }
+    }
+    let vt = diode_thermal_voltage(inst);
+    let v = internal.map(|n| dc_solution.get(n).copied().unwrap_or(0.0));
+    stamp_terminal_admittance(ctx, internal, |v| model.currents(v, vt), |v| model.charges(v, vt), v);
+    Ok(())
+}
+
+/// IGBT (`Z c g e model`, `.model x nigbt`) 的本征端口 [c, g, e, b], b 为展开时追加的内部基区节点
+fn igbt_nodes(inst: &Instance) -> Result<[usize; 4], StampError> {
+    if inst.nodes.len() < 4 {
+        return Err(StampError::InvalidNodes);
+    }
+    Ok([0, 1, 2, 3].map(|i| inst.nodes[i].0))
+}
+
+fn stamp_igbt(ctx: &mut StampContext, inst: &Instance, x: Option<&[f64]>) -> Result<(), StampError> {
+    let nodes = igbt_nodes(inst)?;
+    let model = sim_devices::power::build_igbt_params(&inst.params);
+    let gmin = if ctx.gmin > 0.0 { ctx.gmin } else { 1e-12 };
+    let [c, g, e, b] = nodes;
+    stamp_conductance(ctx, c, b, gmin);
+    stamp_conductance(ctx, b, e, gmin);
+    let Some(x) = x else {
+        stamp_conductance(ctx, g, e, gmin);
+        return Ok(());
+    };
+    // 沟道 Vds 与集电结电压依次限幅, 再重建基区与集电极电压
+    let mut v = nodes.map(|n| x.get(n).copied().unwrap_or(0.0));
+    let vt = diode_thermal_voltage(inst);
+    let vds = ctx.limit_junction_with(&format!("{}:ds", inst.name), v[3] - v[2], sim_devices::power::limit_vds);
+    v[3] = v[2] + vds;
+    let vj = ctx.limit_junction_with(&format!("{}:cb", inst.name), v[0] - v[3], |vnew, vold| {
+        model.junction.limit_voltage(vnew, vold, vt)
+    });
+    v[0] = v[3] + vj;
+    stamp_terminal_currents(ctx, nodes, |v| model.currents(v, vt), v);
+    Ok(())
+}
+
+/// IGBT 瞬态: 直流部分加上 CGE、栅-基区 Miller 电容与集电结电荷 (拖尾电流)
+fn stamp_igbt_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &TransientState,
+) -> Result<(), StampError> {
+    stamp_igbt(ctx, inst, x)?;
+    let (Some(x), Some(q_prev)) = (x, state.device_state.get(StateKey::of(inst))) else {
+        return Ok(());
+    };
+    let nodes = igbt_nodes(inst)?;
+    let model = sim_devices::power::build_igbt_params(&inst.params);
+    let vt = diode_thermal_voltage(inst);
+    let v = nodes.map(|n| x.get(n).copied().unwrap_or(0.0));
+    stamp_terminal_charges(ctx, nodes, |v| model.charges(v, vt), v, q_prev, dt);
+    Ok(())
+}
+
+/// 记录 IGBT 本征端电荷
+fn record_igbt_state(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    let Ok(nodes) = igbt_nodes(inst) else {
+        return;
+    };
+    let model = sim_devices::power::build_igbt_params(&inst.params);
+    let v = nodes.map(|n| x.get(n).copied().unwrap_or(0.0));
+    let charges = model.charges(v, diode_thermal_voltage(inst));
+    state.device_state.insert(StateKey::of(inst), charges.to_vec());
+}
+
+fn stamp_igbt_ac(ctx: &mut ComplexStampContext, inst: &Instance, dc_solution: &[f64]) -> Result<(), StampError> {
+    let nodes = igbt_nodes(inst)?;
+    let model = sim_devices::power::build_igbt_params(&inst.params);
+    let vt = diode_thermal_voltage(inst);
+    let v = nodes.map(|n| dc_solution.get(n).copied().unwrap_or(0.0));
+    stamp_terminal_admittance(ctx, nodes, |v| model.currents(v, vt), |v| model.charges(v, vt), v);
+    Ok(())
+}
+
+/// 开关模型; S 读取 `.model sw` 的 VT/VH, W 读取 `.model csw` 的 IT/IH
+fn switch_model(inst: &Instance) -> sim_devices::switch::SwitchParams {
+    use sim_devices::switch::SwitchControl;
+    let control = match inst.kind {
+        DeviceKind::W => SwitchControl::Current,
+        _ => SwitchControl::Voltage,
+    };
+    sim_devices::switch::build_switch_params(&inst.params, control)
+}
+
+/// W 的控制电流所在的列 (控制电压源的辅助变量); S 返回 None
+fn switch_control_column(ctx: &StampContext, inst: &Instance) -> Option<usize> {
+    if !matches!(inst.kind, DeviceKind::W) {
+        return None;
+    }
+    let control_name = inst.control.as_ref()?;
+    let control_aux = ctx.aux.name_to_id.get(control_name).copied()?;
+    Some(ctx.node_count + control_aux)
+}
+
+/// 开关的两个端口, 以及控制量对应的 (列, 系数): 控制量 = Σ 系数 * x[列]
+struct SwitchPorts {
+    a: usize,
+    b: usize,
+    control: Vec<(usize, f64)>,
+}
+
+impl SwitchPorts {
+    fn control_value(&self, v: impl Fn(usize) -> f64) -> f64 {
+        self.control.iter().map(|&(col, coeff)| coeff * v(col)).sum()
+    }
+}
+
+/// `control_column` 为 W 控制电流的列, S 忽略该参数
+fn switch_ports(inst: &Instance, control_column: Option<usize>) -> Result<SwitchPorts, StampError> {
+    let node = |idx: usize| inst.nodes[idx].0;
+    match inst.kind {
+        DeviceKind::W => {
+            if inst.nodes.len() != 2 {
+                return Err(StampError::InvalidNodes);
+            }
+            let column = control_column.ok_or(StampError::MissingValue)?;
+            Ok(SwitchPorts {
+                a: node(0),
+                b: node(1),
+                control: vec![(column, 1.0)],
+            })
+        }
+        _ => {
+            if inst.nodes.len() != 4 {
+                return Err(StampError::InvalidNodes);
+            }
+            Ok(SwitchPorts {
+                a: node(0),
+                b: node(1),
+                control: vec![(node(2), 1.0), (node(3), -1.0)],
+            })
+        }
+    }
+}
+
+/// 开关在 (控制量, 端电压) 处的线性化: (电导 g, 对控制量的跨导 gm, 等效电流)
+fn switch_linearize(
+    model: &sim_devices::switch::SwitchParams,
+    control: f64,
+    vs: f64,
+    was_on: Option<bool>,
+) -> (f64, f64, f64) {
+    let (g, dg) = model.conductance(control, was_on);
+    let gm = dg * vs;
+    (g, gm, -gm * control)
+}
+
+/// 开关: 端口间电导随控制量在 1/ROFF 与 1/RON 之间平滑过渡; 瞬态中 was_on 为上一
+/// 接受时间点的状态, 用于滞回
+fn stamp_switch(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    was_on: Option<bool>,
+) -> Result<(), StampError> {
+    let ports = switch_ports(inst, switch_control_column(ctx, inst))?;
+    let (a, b) = (ports.a, ports.b);
+    let model = switch_model(inst);
+    let v = |n: usize| x.and_then(|x| x.get(n).copied()).unwrap_or(0.0);
+    let vc = ports.control_value(v);
+    let (g, gm, ieq) = switch_linearize(&model, vc, v(a) - v(b), was_on);
+    ctx.add(a, a, g);
+    ctx.add(b, b, g);
+    ctx.add(a, b, -g);
+    ctx.add(b, a, -g);
+    if gm != 0.0 {
+        for &(col, coeff) in &ports.control {
+            ctx.add(a, col, gm * coeff);
+            ctx.add(b, col, -gm * coeff);
+        }
+        ctx.add_rhs(a, -ieq);
+        ctx.add_rhs(b, ieq);
+    }
+    Ok(())
+}
+
+/// 解 `x` 处各不连续器件所处的分支: 开关是否越过当前滞回窗口的中心、B 源表达式中各比较的结果。
+/// 两个解的结果不同, 说明其间有器件发生了切换; 滞回状态取自 `state` (上一个接受时间点)
+pub fn switching_conditions(instances: &[Instance], x: &[f64], state: &TransientState) -> Vec<bool> {
+    let value = |col: usize| x.get(col).copied().unwrap_or(0.0);
+    let mut conditions = Vec::new();
+    for inst in instances {
+        match inst.kind {
+            DeviceKind::S | DeviceKind::W => {
+                if let Ok(ports) = switch_ports(inst, state.switch_aux.get(StateKey::of(inst)).copied()) {
+                    let was_on = state.switch_on.get(StateKey::of(inst)).copied();
+                    conditions.push(switch_model(inst).next_state(ports.control_value(value), was_on));
+                }
+            }
+            DeviceKind::B => {
+                if let (Some(columns), Ok((expr, _))) = (state.behavioral_columns.get(StateKey::of(inst)), behavioral_expr(inst)) {
+                    let values: Vec<f64> = columns.iter().map(|col| value(*col)).collect();
+                    conditions.extend(expr.conditions(&values));
+                }
+            }
+            _ => {}
+        }
+    }
+    conditions
+}
+
+/// 在接受的时间点更新开关的滞回状态
+fn record_switch_state(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    let column = state.switch_aux.get(StateKey::of(inst)).copied();
+    let Ok(ports) = switch_ports(inst, column) else {
+        return;
+    };
+    let vc = ports.control_value(|n| x.get(n).copied().unwrap_or(0.0));
+    let was_on = state.switch_on.get(StateKey::of(inst)).copied();
+    let on = switch_model(inst).next_state(vc, was_on);
+    state.switch_on.insert(StateKey::of(inst), on);
+}
+
+/// 传输线参数 (Z0, TD); 未给 TD 时 TD = NL / F, NL 默认 0.25
+fn tline_params(inst: &Instance) -> Result<(f64, f64), StampError> {
+    let z0 = param_value(&inst.params, &["z0"])
+        .filter(|z0| *z0 > 0.0)
+        .ok_or(StampError::MissingValue)?;
+    let td = match param_value(&inst.params, &["td"]) {
+        Some(td) => td,
+        None => {
+            let freq = param_value(&inst.params, &["f"]).ok_or(StampError::MissingValue)?;
+            param_value(&inst.params, &["nl"]).unwrap_or(0.25) / freq
+        }
+    };
+    if td < 0.0 || !td.is_finite() {
+        return Err(StampError::MissingValue);
+    }
+    Ok((z0, td))
+}
+
+fn tline_ports(inst: &Instance) -> Result<[usize; 4], StampError> {
+    if inst.nodes.len() != 4 {
+        return Err(StampError::InvalidNodes);
+    }
+    Ok([inst.nodes[0].0, inst.nodes[1].0, inst.nodes[2].0, inst.nodes[3].0])
+}
+
+/// 到达传输线端口的入射波: 端口 1 的方程为 V1 - Z0 I1 = alpha (V2 + Z0 I2) + e1,
+/// 端口 2 对称。alpha 是延迟时刻落在当前步内时对当前解的插值权重, e 为历史给出的已知部分
+#[derive(Debug, Clone, Copy)]
+struct TlineIncident {
+    alpha: f64,
+    e1: f64,
+    e2: f64,
+}
+
+impl TlineIncident {
+    /// 直流: 入射波就是对端当前的波, 线等效为直通
+    const DC: Self = Self {
+        alpha: 1.0,
+        e1: 0.0,
+        e2: 0.0,
+    };
+}
+
+/// 传输线两个端口电流的辅助变量列
+fn tline_aux(ctx: &mut StampContext, inst: &Instance) -> (usize, usize) {
+    let k1 = ctx.allocate_aux(&inst.name);
+    let k2 = ctx.allocate_aux(&format!("{}:2", inst.name));
+    (k1, k2)
+}
+
+/// 传输线 (Branin 特征线模型): 每个端口一个辅助电流变量
+fn stamp_tline(ctx: &mut StampContext, inst: &Instance, incident: TlineIncident) -> Result<(), StampError> {
+    let [a_p, a_n, b_p, b_n] = tline_ports(inst)?;
+    let (z0, _) = tline_params(inst)?;
+    let (k1, k2) = tline_aux(ctx, inst);
+    let alpha = incident.alpha;
+
+    // KCL: 端口电流从 + 端流入线
+    ctx.add(a_p, k1, 1.0);
+    ctx.add(a_n, k1, -1.0);
+    ctx.add(b_p, k2, 1.0);
+    ctx.add(b_n, k2, -1.0);
+
+    // V1 - Z0 I1 - alpha (V2 + Z0 I2) = e1
+    ctx.add(k1, a_p, 1.0);
+    ctx.add(k1, a_n, -1.0);
+    ctx.add(k1, k1, -z0);
+    ctx.add(k1, b_p, -alpha);
+    ctx.add(k1, b_n, alpha);
+    ctx.add(k1, k2, -alpha * z0);
+    ctx.add_rhs(k1, incident.e1);
+
+    // V2 - Z0 I2 - alpha (V1 + Z0 I1) = e2
+    ctx.add(k2, b_p, 1.0);
+    ctx.add(k2, b_n, -1.0);
+    ctx.add(k2, k2, -z0);
+    ctx.add(k2, a_p, -alpha);
+    ctx.add(k2, a_n, alpha);
+    ctx.add(k2, k1, -alpha * z0);
+    ctx.add_rhs(k2, incident.e2);
+
+    Ok(())
+}
+
+/// 从解向量读取传输线端口的电压与电流
+fn tline_sample(inst: &Instance, x: &[f64], aux: (usize, usize), time: f64) -> Option<TlineSample> {
+    let [a_p, a_n, b_p, b_n] = tline_ports(inst).ok()?;
+    let v = |n: usize| x.get(n).copied().unwrap_or(0.0);
+    Some(TlineSample {
+        time,
+        v1: v(a_p) - v(a_n),
+        i1: v(aux.0),
+        v2: v(b_p) - v(b_n),
+        i2: v(aux.1),
+    })
+}
+
+/// 在历史波形中按线性插值取 time 时刻离开两个端口的波 (V1 + Z0 I1, V2 + Z0 I2);
+/// 早于第一个点时取第一个点 (初始工作点)
+fn tline_waves(history: &[TlineSample], time: f64, z0: f64) -> (f64, f64) {
+    let waves = |s: &TlineSample| (s.v1 + z0 * s.i1, s.v2 + z0 * s.i2);
+    let Some(first) = history.first() else {
+        return (0.0, 0.0);
+    };
+    if time <= first.time {
+        return waves(first);
+    }
+    for pair in history.windows(2) {
+        let (s0, s1) = (&pair[0], &pair[1]);
+        if time <= s1.time {
+            let t = (time - s0.time) / (s1.time - s0.time);
+            let (w01, w02) = waves(s0);
+            let (w11, w12) = waves(s1);
+            return (w01 + t * (w11 - w01), w02 + t * (w12 - w02));
+        }
+    }
+    waves(history.last().unwrap_or(first))
+}
+
+/// 传输线瞬态: 入射波取自 t - TD 时刻的历史; TD 小于步长时延迟时刻落在当前步内,
+/// 在上一接受点与当前解之间线性插值 (隐式部分进入矩阵)
+fn stamp_tline_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &mut TransientState,
+) -> Result<(), StampError> {
+    let (z0, td) = tline_params(inst)?;
+    let aux = tline_aux(ctx, inst);
+    state.tline_aux.insert(StateKey::of(inst), aux);
+    let history = state.tline_history.get_or_insert_with(StateKey::of(inst), Vec::new);
+    if history.is_empty() {
+        // 首次调用时 x 即初始工作点 (或 UIC 初值), 作为 t <= time 的历史
+        if let Some(sample) = x.and_then(|x| tline_sample(inst, x, aux, state.time)) {
+            history.push(sample);
+        }
+    }
+    let time = state.time + dt;
+    let delayed = time - td;
+    let last = history.last().map_or(state.time, |s| s.time);
+    let incident = if delayed <= last {
+        let (w1, w2) = tline_waves(history, delayed, z0);
+        TlineIncident {
+            alpha: 0.0,
+            e1: w2,
+            e2: w1,
+        }
+    } else {
+        let alpha = (delayed - last) / (time - last);
+        let (w1, w2) = tline_waves(history, last, z0);
+        TlineIncident {
+            alpha,
+            e1: (1.0 - alpha) * w2,
+            e2: (1.0 - alpha) * w1,
+        }
+    };
+    stamp_tline(ctx, inst, incident)
+}
+
+/// 在接受的时间点记录传输线端口波形, 并丢弃早于 time - TD 的历史
+fn record_tline_sample(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    let Some(&aux) = state.tline_aux.get(StateKey::of(inst)) else {
+        return;
+    };
+    let Ok((_, td)) = tline_params(inst) else {
+        return;
+    };
+    let Some(sample) = tline_sample(inst, x, aux, state.time) else {
+        return;
+    };
+    let history = state.tline_history.get_or_insert_with(StateKey::of(inst), Vec::new);
+    history.retain(|s| s.time < sample.time);
+    history.push(sample);
+    // 保留插值 time - TD 所需的最后一个更早的点
+    let horizon = sample.time - td;
+    let keep_from = history.iter().rposition(|s| s.time <= horizon).unwrap_or(0);
+    history.drain(..keep_from);
+}
+
+/// 多导体传输线 (W) 的分段集总模型
+struct WLine {
+    conductors: usize,
+    /// NSEG+1 个界面上的导体节点: 输入端口、各节之间的内部节点、输出端口
+    boundaries: Vec<Vec<usize>>,
+    reference: usize,
+    out_reference: usize,
+    /// 每节的集总 RLGC
+    section: crate::rlgc::Rlgc,
+}
+
+fn wline(inst: &Instance) -> Result<WLine, StampError> {
+    let conductors = param_value(&inst.params, &["n"])
+        .filter(|n| *n >= 1.0)
+        .ok_or(StampError::MissingValue)? as usize;
+    let length = param_value(&inst.params, &["l"])
+        .filter(|l| *l > 0.0)
+        .ok_or(StampError::MissingValue)?;
+    let segments = param_value(&inst.params, &["nseg"])
+        .filter(|n| *n >= 1.0)
+        .map_or(crate::rlgc::DEFAULT_SEGMENTS, |n| n as usize);
+    let nodes: Vec<usize> = inst.nodes.iter().map(|node| node.0).collect();
+    let ports = 2 * conductors + 2;
+    if nodes.len() != ports + (segments - 1) * conductors {
+        return Err(StampError::InvalidNodes);
+    }
+    let mut boundaries = vec![nodes[..conductors].to_vec()];
+    boundaries.extend(nodes[ports..].chunks(conductors).map(<[usize]>::to_vec));
+    boundaries.push(nodes[conductors + 1..ports - 1].to_vec());
+    Ok(WLine {
+        conductors,
+        boundaries,
+        reference: nodes[conductors],
+        out_reference: nodes[ports - 1],
+        section: crate::rlgc::Rlgc::from_params(&inst.params, conductors).section(length, segments),
+    })
+}
+
+/// 界面 k 上并联元件的份额: 每节的 G、C 平分到两端
+fn wline_shunt_weight(line: &WLine, k: usize) -> f64 {
+    if k == 0 || k + 1 == line.boundaries.len() {
+        0.5
+    } else {
+        1.0
+    }
+}
+
+/// 一步的伴随模型, 每个元件为 (Y, J): 从 a_j 流向 b_j 的电流为 Σ_m Y_jm (V(a_m) - V(b_m)) + J_j。
+/// 串联支路按节排列, 并联元件按界面排列 (b 为参考导体); `dt` 为 None 时是直流
+struct WLineCompanion {
+    series: Vec<(Vec<f64>, Vec<f64>)>,
+    shunt: Vec<(Vec<f64>, Vec<f64>)>,
+}
+
+/// 后向欧拉伴随模型: 串联支路 I = (R + L/dt)^-1 (ΔV + L/dt I_prev),
+/// 并联元件 I = (G + C/dt) V - C/dt V_prev; 历史量见 [`record_wline_state`]
+fn wline_companion(line: &WLine, dt: Option<f64>, history: Option<&[f64]>) -> Result<WLineCompanion, StampError> {
+    let n = line.conductors;
+    let segments = line.boundaries.len() - 1;
+    let rate = dt.map_or(0.0, |dt| 1.0 / dt);
+    let y_series: Vec<f64> = line
+        .section
+        .series_admittance(Complex64::new(rate, 0.0))
+        .ok_or(StampError::MissingValue)?
+        .iter()
+        .map(|y| y.re)
+        .collect();
+    let previous = |offset: usize, k: usize| match history {
+        Some(history) if dt.is_some() => history[offset + k * n..offset + (k + 1) * n].to_vec(),
+        _ => vec![0.0; n],
+    };
+    let l_over_dt: Vec<f64> = line.section.l.iter().map(|l| l * rate).collect();
+    let series = (0..segments)
+        .map(|k| {
+            let flux = crate::rlgc::mat_vec(&l_over_dt, &previous(1, k));
+            (y_series.clone(), crate::rlgc::mat_vec(&y_series, &flux))
+        })
+        .collect();
+    let c_over_dt: Vec<f64> = line.section.c.iter().map(|c| c * rate).collect();
+    let shunt = (0..=segments)
+        .map(|k| {
+            let weight = wline_shunt_weight(line, k);
+            let y = line.section.g.iter().zip(&c_over_dt).map(|(g, c)| weight * (g + c)).collect();
+            let charge = crate::rlgc::mat_vec(&c_over_dt, &previous(1 + segments * n, k));
+            (y, charge.iter().map(|q| -weight * q).collect())
+        })
+        .collect();
+    Ok(WLineCompanion { series, shunt })
+}
+
+/// 耦合元件的矩阵元 (行, 列, 符号, Y 的下标), 元件接在节点组 a 与 b 之间
+fn coupled_pattern(a: &[usize], b: &[usize]) -> Vec<(usize, usize, f64, usize)> {
+    let n = a.len();
+    let mut out = Vec::with_capacity(4 * n * n);
+    for j in 0..n {
+        for m in 0..n {
+            let idx = j * n + m;
+            out.extend([(a[j], a[m], 1.0, idx), (a[j], b[m], -1.0, idx), (b[j], a[m], -1.0, idx), (b[j], b[m], 1.0, idx)]);
+        }
+    }
+    out
+}
+
+fn stamp_coupled(ctx: &mut StampContext, a: &[usize], b: &[usize], (y, j): &(Vec<f64>, Vec<f64>)) {
+    for (row, col, sign, idx) in coupled_pattern(a, b) {
+        ctx.add(row, col, sign * y[idx]);
+    }
+    for (k, current) in j.iter().enumerate() {
+        ctx.add_rhs(a[k], -current);
+        ctx.add_rhs(b[k], *current);
+    }
+}
+
+/// 理想参考导体: 输入与输出参考节点不同时用零伏源连通
+fn wline_reference_aux(inst: &Instance, line: &WLine, allocate_aux: impl FnOnce(&str) -> usize) -> Option<usize> {
+    (line.reference != line.out_reference).then(|| allocate_aux(&format!("{}#ref", inst.name)))
+}
+
+/// 多导体传输线: 直流 (dt 为 None) 或瞬态一步的分段伴随模型
+fn stamp_wline(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    dt: Option<f64>,
+    history: Option<&[f64]>,
+) -> Result<(), StampError> {
+    let line = wline(inst)?;
+    let companion = wline_companion(&line, dt, history)?;
+    for (pair, element) in line.boundaries.windows(2).zip(&companion.series) {
+        stamp_coupled(ctx, &pair[0], &pair[1], element);
+    }
+    let reference = vec![line.reference; line.conductors];
+    for (nodes, element) in line.boundaries.iter().zip(&companion.shunt) {
+        stamp_coupled(ctx, nodes, &reference, element);
+    }
+    if let Some(k) = wline_reference_aux(inst, &line, |name| ctx.allocate_aux(name)) {
+        ctx.add(line.reference, k, 1.0);
+        ctx.add(line.out_reference, k, -1.0);
+        ctx.add(k, line.reference, 1.0);
+        ctx.add(k, line.out_reference, -1.0);
+    }
+    Ok(())
+}
+
+/// 多导体传输线瞬态; 首次调用时 x 即初始工作点 (或 UIC 初值), 按直流记录历史
+fn stamp_wline_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &mut TransientState,
+) -> Result<(), StampError> {
+    if let (false, Some(x)) = (state.device_state.contains_key(StateKey::of(inst)), x) {
+        record_wline_state(inst, x, state);
+    }
+    stamp_wline(ctx, inst, Some(dt), state.device_state.get(StateKey::of(inst)).map(Vec::as_slice))
+}
+
+/// 在接受的时间点记录历史量: [时刻, 各节串联电流 (NSEG×N), 各界面导体对参考的电压 ((NSEG+1)×N)]。
+/// 串联电流按本步的伴随模型由节点电压求出, 与盖章时的方程一致
+fn record_wline_state(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    let Ok(line) = wline(inst) else {
+        return;
+    };
+    let history = state.device_state.get(StateKey::of(inst));
+    let dt = match history {
+        Some(history) if state.time > history[0] => Some(state.time - history[0]),
+        Some(_) => return,
+        None => None,
+    };
+    let Ok(companion) = wline_companion(&line, dt, history.map(Vec::as_slice)) else {
+        return;
+    };
+    let v = |n: usize| x.get(n).copied().unwrap_or(0.0);
+    let mut next = vec![state.time];
+    for (pair, (y, j)) in line.boundaries.windows(2).zip(&companion.series) {
+        let dv: Vec<f64> = pair[0].iter().zip(&pair[1]).map(|(a, b)| v(*a) - v(*b)).collect();
+        next.extend(crate::rlgc::mat_vec(y, &dv).iter().zip(j).map(|(i, j)| i + j));
+    }
+    for nodes in &line.boundaries {
+        next.extend(nodes.iter().map(|node| v(*node) - v(line.reference)));
+    }
+    state.device_state.insert(StateKey::of(inst), next);
+}
+
+pub fn debug_dump_stamp(instance: &Instance) {
+    println!(
+        "stamp: name={} kind={:?} nodes={} value={:?}",
+        instance.name,
+        instance.kind,
+        instance.nodes.len(),
+        instance.value
+    );
+}
+
+pub fn update_transient_state(instances: &[Instance], x: &[f64], state: &mut TransientState) {
+    for inst in instances {
+        match inst.kind {
+            DeviceKind::C => {
+                if inst.nodes.len() >= 2 {
+                    let a = inst.nodes[0].0;
+                    let b = inst.nodes[1].0;
+                    let va = x.get(a).copied().unwrap_or(0.0);
+                    let vb = x.get(b).copied().unwrap_or(0.0);
+                    state.cap_voltage.insert(StateKey::of(inst), va - vb);
+                    record_capacitor_charge(inst, x, state);
+                }
+            }
+            DeviceKind::D => record_diode_voltage(inst, x, state),
+            DeviceKind::M => record_mos_state(inst, x, state),
+            DeviceKind::J | DeviceKind::Z => record_jfet_voltages(inst, x, state),
+            DeviceKind::S | DeviceKind::W => record_switch_state(inst, x, state),
+            DeviceKind::T => record_tline_sample(inst, x, state),
+            DeviceKind::WLine => record_wline_state(inst, x, state),
+            DeviceKind::R => record_memristor_state(inst, x, state),
+            DeviceKind::A => {
+                if let Some(model) = instance_device_model(inst) {
+                    model.accept_step(inst, x, state);
+                }
+            }
+            DeviceKind::L if inst.nodes.len() >= 2 => {
+                // 直流工作点上电感没有支路电流列, 电流由短路电导上的压降给出
+                let current = match state.ind_aux.get(StateKey::of(inst)) {
+                    Some(aux) => x.get(*aux).copied(),
+                    None => {
+                        let va = x.get(inst.nodes[0].0).copied().unwrap_or(0.0);
+                        let vb = x.get(inst.nodes[1].0).copied().unwrap_or(0.0);
+                        Some(INDUCTOR_DC_CONDUCTANCE * (va - vb))
+                    }
+                };
+                if let Some(current) = current {
+                    state.ind_current.insert(StateKey::of(inst), current);
+                    record_inductor_flux(inst, x, current, state);
+                }
+            }
+            _ => {}
+        }
+    }
+    register_sources(instances, state);
+    // 数字线网在工作点之后建立, 之后每个接受时间点执行到期事件
+    if state.digital.ready {
+        state.digital.advance(instances, x, state.time);
+    } else {
+        state.digital.initialize(instances, x, state.time);
+    }
+}
+
+/// 记录电荷形式 (Q=) 非线性电容在接受点 x 上的电荷
+fn record_capacitor_charge(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    if let Ok(Some((expr, true))) = nonlinear_capacitor(inst) {
+        let (q, _) = expr.eval(&nonlinear_capacitor_values(inst, Some(x)));
+        state.cap_charge.insert(StateKey::of(inst), q);
+    }
+}
+
+/// 忆阻器在接受时间点按后向欧拉推进内部状态; 首次记录 (工作点或 UIC 起点) 取 X0
+fn record_memristor_state(inst: &Instance, x: &[f64], state: &mut TransientState) {
+    let Some(params) = memristor_params(inst) else {
+        return;
+    };
+    let next = match state.memristor_state.get(StateKey::of(inst)) {
+        Some(&(x_prev, t_prev)) if state.time > t_prev && inst.nodes.len() == 2 => {
+            let v = x.get(inst.nodes[0].0).copied().unwrap_or(0.0) - x.get(inst.nodes[1].0).copied().unwrap_or(0.0);
+            (params.step(x_prev, v, state.time - t_prev).x, state.time)
+        }
+        Some(&recorded) => recorded,
+        None => (params.x0, state.time),
+    };
+    state.memristor_state.insert(StateKey::of(inst), next);
+}
+
+/// 记录磁链形式 (FLUX=) 非线性电感在接受点 x、支路电流 `current` 上的磁链
+fn record_inductor_flux(inst: &Instance, x: &[f64], current: f64, state: &mut TransientState) {
+    if let Ok(Some((expr, true))) = nonlinear_inductor(inst) {
+        let (flux, _) = expr.eval(&nonlinear_inductor_values(&expr, inst, Some(x), current));
+        state.ind_flux.insert(StateKey::of(inst), flux);
+    }
+}
+
+/// UIC 模式下的储能元件初值: 电容取 IC= (否则取两端节点电压差), 电感取 IC= (否则为 0)
+pub fn apply_initial_conditions(instances: &[Instance], x: &[f64], state: &mut TransientState) {
+    for inst in instances {
+        let ic = param_value(&inst.params, &["ic"]);
+        match inst.kind {
+            DeviceKind::C if inst.nodes.len() >= 2 => {
+                let a = inst.nodes[0].0;
+                let b = inst.nodes[1].0;
+                let va = x.get(a).copied().unwrap_or(0.0);
+                let vb = x.get(b).copied().unwrap_or(0.0);
+                state.cap_voltage.insert(StateKey::of(inst), ic.unwrap_or(va - vb));
+                // 电荷形式按 IC= 调整正端电压 (正端接地时调整负端) 后求初始电荷
+                let mut x_ic = x.to_vec();
+                match ic {
+                    Some(ic) if a != 0 && a < x_ic.len() => x_ic[a] = vb + ic,
+                    Some(ic) if b < x_ic.len() => x_ic[b] = va - ic,
+                    _ => {}
+                }
+                record_capacitor_charge(inst, &x_ic, state);
+            }
+            DeviceKind::D => record_diode_voltage(inst, x, state),
+            DeviceKind::M => record_mos_state(inst, x, state),
+            DeviceKind::J | DeviceKind::Z => record_jfet_voltages(inst, x, state),
+            DeviceKind::S | DeviceKind::W => record_switch_state(inst, x, state),
+            DeviceKind::R => record_memristor_state(inst, x, state),
+            DeviceKind::L if inst.nodes.len() >= 2 => {
+                let current = ic.unwrap_or(0.0);
+                state.ind_current.insert(StateKey::of(inst), current);
+                record_inductor_flux(inst, x, current, state);
+            }
+            _ => {}
+        }
+    }
+    register_sources(instances, state);
+    state.digital.initialize(instances, x, state.time);
+}
+
+/// UIC 下没有给定初值、从 0 开始的状态量所属的器件: 没有 IC= 且端点不全由 `.ic`
+/// 给定的电容 (电压), 没有 IC= 的电感 (电流); 按网表顺序
+pub fn defaulted_initial_states(instances: &[Instance], ic_nodes: &HashMap<NodeId, f64>) -> Vec<String> {
+    let given = |node: &NodeId| node.0 == 0 || ic_nodes.contains_key(node);
+    instances
+        .iter()
+        .filter(|inst| param_value(&inst.params, &["ic"]).is_none())
+        .filter(|inst| match inst.kind {
+            DeviceKind::C if inst.nodes.len() >= 2 => !inst.nodes[..2].iter().all(given),
+            DeviceKind::L => inst.nodes.len() >= 2,
+            _ => false,
+        })
+        .map(|inst| inst.name.clone())
+        .collect()
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TransientState {
+    pub cap_voltage: StateTable<f64>,
+    pub ind_current: StateTable<f64>,
+    pub ind_aux: StateTable<usize>,
+    /// 带滞回开关在上一个接受时间点的通断状态
+    pub switch_on: StateTable<bool>,
+    /// 电流控制开关 (W) 的控制电流列, 在 stamp_tran 中记录
+    pub switch_aux: StateTable<usize>,
+    /// B 源表达式中各变量所在的列, 在 stamp_tran 中记录
+    pub behavioral_columns: StateTable<Vec<usize>>,
+    /// 上一个接受时间点的时刻; 当前求解的时刻为 time + dt
+    pub time: f64,
+    /// 传输线两个端口电流所在的列, 在 stamp_tran 中记录
+    pub tline_aux: StateTable<(usize, usize)>,
+    /// 传输线端口的历史波形, 按时间递增, 只保留最近一个延迟 TD 内的点
+    pub tline_history: StateTable<Vec<TlineSample>>,
+    /// 电荷型 C-V 模型 (capmod > 0) 的 MOSFET 在上一个接受时间点的端电荷 [qd, qg, qs, qb]
+    pub mos_charge: StateTable<[f64; 4]>,
+    /// 电荷形式 (Q=) 的非线性电容在上一个接受时间点的电荷
+    pub cap_charge: StateTable<f64>,
+    /// 磁链形式 (FLUX=) 的非线性电感在上一个接受时间点的磁链
+    pub ind_flux: StateTable<f64>,
+    /// 忆阻器的内部状态 x 及其所在的接受时刻
+    pub memristor_state: StateTable<(f64, f64)>,
+    /// 数字线网与事件队列
+    pub digital: crate::digital::DigitalState,
+    /// TRNOISE 源的采样序列
+    pub trnoise: StateTable<TrNoise>,
+    /// PULSE / PWL 源的波形
+    pub waveforms: StateTable<SourceWaveform>,
+    /// 插件器件 (按状态槽位) 保存的历史量, 内容由插件自己定义
+    pub device_state: StateTable<Vec<f64>>,
+    /// 积分方法, 由 `.options method=` 选择
+    pub integrator: IntegratorRef,
+    /// 由积分器维护的状态量历史
+    pub history: StateHistory,
+    /// `.options bypass` 的器件旁路缓存, 跨时间步保留
+    pub bypass: Option<crate::bypass::BypassCache>,
+}
+
+/// 电容电压、电感电流、电荷与磁链的历史 (键同 TransientState 中的同名表)
+#[derive(Debug, Default, Clone)]
+pub struct StateHistory {
+    pub cap_voltage: StateTable<History>,
+    pub ind_current: StateTable<History>,
+    pub cap_charge: StateTable<History>,
+    pub ind_flux: StateTable<History>,
+}
+
+impl TransientState {
+    /// `histories` 中 `key` 的伴随模型; 还没有历史的状态量以 `prev` 为起点
+    pub fn companion(&self, histories: &StateTable<History>, key: StateKey, dt: f64, prev: f64) -> Companion {
+        let history = histories.get(key).copied().unwrap_or_else(|| History::start(prev));
+        self.integrator.companion(dt, &history)
+    }
+
+    /// 以当前状态量为起点重建历史, 在工作点或 UIC 初值之后调用
+    pub fn start_history(&mut self) {
+        let start = |values: &StateTable<f64>| values.iter().map(|(key, value)| (key, History::start(*value))).collect();
+        self.history = StateHistory {
+            cap_voltage: start(&self.cap_voltage),
+            ind_current: start(&self.ind_current),
+            cap_charge: start(&self.cap_charge),
+            ind_flux: start(&self.ind_flux),
+        };
+    }
+
+    /// 步长 `dt` 被接受、新状态已记录之后调用: 积分器把新值推入历史
+    pub fn advance_history(&mut self, dt: f64) {
+        let integrator = &self.integrator;
+        let advance = |histories: &mut StateTable<History>, values: &StateTable<f64>| {
+            for (key, &x) in values.iter() {
+                let history = histories.get(key).copied().unwrap_or_else(|| History::start(x));
+                histories.insert(key, integrator.advance(dt, &history, x));
+            }
+        };
+        advance(&mut self.history.cap_voltage, &self.cap_voltage);
+        advance(&mut self.history.ind_current, &self.ind_current);
+        advance(&mut self.history.cap_charge, &self.cap_charge);
+        advance(&mut self.history.ind_flux, &self.ind_flux);
+    }
+
+    /// 晚于 `time` 的最早噪声采样时刻
+    pub fn next_noise_sample(&self, time: f64) -> Option<f64> {
+        self.trnoise
+            .values()
+            .map(|noise| noise.next_sample_time(time))
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// 晚于 `time` 的最早波形转折点 (PULSE 的边沿起止、PWL 的各个点)
+    pub fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        self.waveforms
+            .values()
+            .filter_map(|waveform| waveform.next_breakpoint(time))
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// 所有源波形都已按周期重复或保持不变的时刻 (没有波形时为 0)
+    pub fn sources_periodic_from(&self) -> f64 {
+        self.waveforms.values().map(SourceWaveform::periodic_from).fold(0.0, f64::max)
+    }
+}
+
+/// 为带 TRNOISE 的独立源建立采样序列 (已有的保留, 以便同一时刻取到同一采样),
+/// 并解析 PULSE / PWL 波形
+fn register_sources(instances: &[Instance], state: &mut TransientState) {
+    for inst in instances.iter().filter(|inst| matches!(inst.kind, DeviceKind::V | DeviceKind::I)) {
+        if !state.waveforms.contains_key(StateKey::of(inst)) {
+            if let Some(waveform) = build_source_waveform(&inst.params) {
+                state.waveforms.insert(StateKey::of(inst), waveform);
+            }
+        }
+        if !state.trnoise.contains_key(StateKey::of(inst)) {
+            if let Some(params) = build_trnoise_params(&inst.name, &inst.params) {
+                state.trnoise.insert(StateKey::of(inst), TrNoise::new(params));
+            }
+        }
+    }
+}
+
+/// 传输线端口在某个接受时间点的电压与流入电流
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TlineSample {
+    pub time: f64,
+    pub v1: f64,
+    pub i1: f64,
+    pub v2: f64,
+    pub i2: f64,
+}
+
+/// 热电压 kT/q; 未指定温度 (开尔文, "temp" 参数) 时沿用室温常数
+fn diode_thermal_voltage(inst: &Instance) -> f64 {
+    const BOLTZMANN_OVER_Q: f64 = 8.617333262e-5;
+    param_value(&inst.params, &["temp"])
+        .map(|kelvin| BOLTZMANN_OVER_Q * kelvin)
+        .unwrap_or(0.02585)
+}
+
+fn param_value(params: &HashMap<String, String>, keys: &[&str]) -> Option<f64> {
+    for key in keys {
+        let key = key.to_ascii_lowercase();
+        if let Some(value) = params.get(&key) {
+            if let Some(num) = parse_number_with_suffix(value).or_else(|| value.parse().ok()) {
+                return Some(num);
+            }
+        }
+    }
+    None
+}
+
+fn stamp_capacitor_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &mut TransientState,
+) -> Result<(), StampError> {
+    if inst.nodes.len() < 2 {
+        return Err(StampError::InvalidNodes);
+    }
+    if let Some(nonlinear) = nonlinear_capacitor(inst)? {
+        return stamp_nonlinear_capacitor_tran(ctx, inst, &nonlinear, x, dt, state);
+    }
+    stamp_linear_capacitor_tran(ctx, inst, capacitance(inst)?, dt, state);
+    let _ = x;
+    Ok(())
+}
+
+/// 线性电容的伴随模型, 电容值 `c` 已解析
+fn stamp_linear_capacitor_tran(ctx: &mut StampContext, inst: &Instance, c: f64, dt: f64, state: &TransientState) {
+    let a = inst.nodes[0].0;
+    let b = inst.nodes[1].0;
+    let v_prev = *state.cap_voltage.get(StateKey::of(inst)).unwrap_or(&0.0);
+    let companion = state.companion(&state.history.cap_voltage, StateKey::of(inst), dt, v_prev);
+    let g = c / companion.step;
+    let ieq = g * companion.history;
+    ctx.add(a, a, g);
+    ctx.add(b, b, g);
+    ctx.add(a, b, -g);
+    ctx.add(b, a, -g);
+    // i = g*(v - v_hist), 历史项作为等效电流源注入 a 端
+    ctx.add_rhs(a, ieq);
+    ctx.add_rhs(b, -ieq);
+}
+
+/// 非线性电容的伴随模型 (等效步长 h 与历史量见 [`crate::integrator`]), 在当前牛顿迭代点线性化:
+/// C= 形式 i = C(x)·(v - v_hist)/h, Q= 形式 i = (Q(x) - q_hist)/h
+fn stamp_nonlinear_capacitor_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    (expr, is_charge): &(BehavioralExpr, bool),
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &TransientState,
+) -> Result<(), StampError> {
+    let a = inst.nodes[0].0;
+    let b = inst.nodes[1].0;
+    let values = nonlinear_capacitor_values(inst, x);
+    let (f, grad) = expr.eval(&values);
+    let columns = inst.nodes[2..].iter().map(|node| node.0);
+    // (列, ∂i/∂x) 与当前电流 i0
+    let (i0, jacobian): (f64, Vec<(usize, f64)>) = if *is_charge {
+        let q_prev = match state.cap_charge.get(StateKey::of(inst)) {
+            Some(q) => *q,
+            None => expr.eval(&vec![0.0; values.len()]).0,
+        };
+        let Companion { step: h, history: q_hist } = state.companion(&state.history.cap_charge, StateKey::of(inst), dt, q_prev);
+        let jacobian = columns.zip(grad.iter()).map(|(col, g)| (col, g / h)).collect();
+        ((f - q_hist) / h, jacobian)
+    } else {
+        let voltage = |node: usize| x.and_then(|x| x.get(node)).copied().unwrap_or(0.0);
+        let v_prev = state.cap_voltage.get(StateKey::of(inst)).copied().unwrap_or(0.0);
+        let Companion { step: h, history: v_hist } = state.companion(&state.history.cap_voltage, StateKey::of(inst), dt, v_prev);
+        let dv = voltage(a) - voltage(b) - v_hist;
+        let mut jacobian: Vec<(usize, f64)> = vec![(a, f / h), (b, -f / h)];
+        jacobian.extend(columns.zip(grad.iter()).map(|(col, g)| (col, g * dv / h)));
+        (f * dv / h, jacobian)
+    };
+    if !i0.is_finite() {
+        return Err(StampError::MissingValue);
+    }
+    let mut ieq = i0;
+    for (col, g) in jacobian {
+        ctx.add(a, col, g);
+        ctx.add(b, col, -g);
+        ieq -= g * x.and_then(|x| x.get(col)).copied().unwrap_or(0.0);
+    }
+    ctx.add_rhs(a, -ieq);
+    ctx.add_rhs(b, ieq);
+    Ok(())
+}
+
+fn stamp_inductor_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &mut TransientState,
+) -> Result<(), StampError> {
+    if inst.nodes.len() < 2 {
+        return Err(StampError::InvalidNodes);
+    }
+    let k = inductor_branch(ctx, inst, state);
+    if let Some(nonlinear) = nonlinear_inductor(inst)? {
+        return stamp_nonlinear_inductor_tran(ctx, inst, &nonlinear, k, x, dt, state);
+    }
+    stamp_linear_inductor_tran(ctx, inst, k, inductance(inst)?, dt, state);
+    Ok(())
+}
+
+/// 每次组装矩阵都要分配支路电流列, 记录下来供接受时间点读取电流
+fn inductor_branch(ctx: &mut StampContext, inst: &Instance, state: &mut TransientState) -> usize {
+    let k = ctx.allocate_aux(&inst.name);
+    state.ind_aux.insert(StateKey::of(inst), k);
+    k
+}
+
+/// 线性电感的伴随模型, 支路电流列 `k` 已分配, 电感值 `l` 已解析
+fn stamp_linear_inductor_tran(ctx: &mut StampContext, inst: &Instance, k: usize, l: f64, dt: f64, state: &TransientState) {
+    let a = inst.nodes[0].0;
+    let b = inst.nodes[1].0;
+    let i_prev = *state.ind_current.get(StateKey::of(inst)).unwrap_or(&0.0);
+    let companion = state.companion(&state.history.ind_current, StateKey::of(inst), dt, i_prev);
+    let g = -(l / companion.step);
+    ctx.add(a, k, 1.0);
+    ctx.add(b, k, -1.0);
+    ctx.add(k, a, 1.0);
+    ctx.add(k, b, -1.0);
+    ctx.add(k, k, g);
+    ctx.add_rhs(k, g * companion.history);
+}
+
+/// 非线性电感的伴随模型 (等效步长 h 与历史量见 [`crate::integrator`]), 支路方程在当前牛顿迭代点线性化:
+/// L= 形式 v = L(x)·(i - i_hist)/h, FLUX= 形式 v = (Φ(x) - Φ_hist)/h
+fn stamp_nonlinear_inductor_tran(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    (expr, is_flux): &(BehavioralExpr, bool),
+    k: usize,
+    x: Option<&[f64]>,
+    dt: f64,
+    state: &TransientState,
+) -> Result<(), StampError> {
     let a = inst.nodes[0].0;
     let b = inst.nodes[1].0;
-    let k = *state
-        .ind_aux
-        .entry(inst.name.clone())
-        .or_insert_with(|| ctx.allocate_aux(&inst.name));
-    let g = -(l / dt);
-    let i_prev = *state.ind_current.get(&inst.name).unwrap_or(&0.0);
+    let current = x.and_then(|x| x.get(k)).copied().unwrap_or(0.0);
+    let values = nonlinear_inductor_values(expr, inst, x, current);
+    let columns = nonlinear_inductor_columns(expr, inst, k);
+    let (f, grad) = expr.eval(&values);
+    // 支路压降 v0 与 (列, ∂v/∂x)
+    let (drop, jacobian): (f64, Vec<(usize, f64)>) = if *is_flux {
+        let flux_prev = match state.ind_flux.get(StateKey::of(inst)) {
+            Some(flux) => *flux,
+            None => expr.eval(&vec![0.0; values.len()]).0,
+        };
+        let Companion { step: h, history: flux_hist } = state.companion(&state.history.ind_flux, StateKey::of(inst), dt, flux_prev);
+        let jacobian = columns.into_iter().zip(grad.iter()).map(|(col, g)| (col, g / h)).collect();
+        ((f - flux_hist) / h, jacobian)
+    } else {
+        let i_prev = state.ind_current.get(StateKey::of(inst)).copied().unwrap_or(0.0);
+        let Companion { step: h, history: i_hist } = state.companion(&state.history.ind_current, StateKey::of(inst), dt, i_prev);
+        let di = current - i_hist;
+        let mut jacobian = vec![(k, f / h)];
+        jacobian.extend(columns.into_iter().zip(grad.iter()).map(|(col, g)| (col, g * di / h)));
+        (f * di / h, jacobian)
+    };
+    if !drop.is_finite() {
+        return Err(StampError::MissingValue);
+    }
     ctx.add(a, k, 1.0);
     ctx.add(b, k, -1.0);
     ctx.add(k, a, 1.0);
     ctx.add(k, b, -1.0);
-    ctx.add(k, k, g);
-    ctx.add_rhs(k, g * i_prev);
-    let _ = x;
+    let mut rhs = drop;
+    for (col, g) in jacobian {
+        ctx.add(k, col, -g);
+        rhs -= g * x.and_then(|x| x.get(col)).copied().unwrap_or(0.0);
+    }
+    ctx.add_rhs(k, rhs);
     Ok(())
 }
 
 fn stamp_inductor_dc(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError> {
-    if inst.nodes.len() != 2 {
+    if inst.nodes.len() < 2 {
         return Err(StampError::InvalidNodes);
     }
-    let gshort = 1e9;
+    let gshort = INDUCTOR_DC_CONDUCTANCE;
     let a = inst.nodes[0].0;
     let b = inst.nodes[1].0;
     ctx.add(a, a, gshort);
@@ -537,6 +3068,140 @@ fn stamp_vcvs(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError>
     Ok(())
 }
 
+/// 理想变压器的端口与匝数比: (p+, p-, s+, s-, n)
+fn transformer_ports(inst: &Instance) -> Result<([usize; 4], f64), StampError> {
+    if inst.nodes.len() != 4 {
+        return Err(StampError::InvalidNodes);
+    }
+    let ratio = inst
+        .value
+        .as_deref()
+        .and_then(parse_number_with_suffix)
+        .ok_or(StampError::MissingValue)?;
+    let nodes = [inst.nodes[0].0, inst.nodes[1].0, inst.nodes[2].0, inst.nodes[3].0];
+    Ok((nodes, ratio))
+}
+
+/// Ideal transformer
+/// Vp = n * Vs, Is = -n * Ip, where n = Np/Ns
+/// nodes: [p+, p-, s+, s-]; one aux variable carries the primary current
+/// (p+ -> p- through the winding), so no magnetising inductance is needed
+fn stamp_transformer(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError> {
+    let ([p_p, p_n, s_p, s_n], n) = transformer_ports(inst)?;
+
+    let k = ctx.allocate_aux(&inst.name);
+
+    // KCL: Ip flows from p+ to p-, the secondary carries n * Ip from s- to s+
+    ctx.add(p_p, k, 1.0);
+    ctx.add(p_n, k, -1.0);
+    ctx.add(s_p, k, -n);
+    ctx.add(s_n, k, n);
+
+    // Constitutive relation: V(p+) - V(p-) = n * (V(s+) - V(s-))
+    ctx.add(k, p_p, 1.0);
+    ctx.add(k, p_n, -1.0);
+    ctx.add(k, s_p, -n);
+    ctx.add(k, s_n, n);
+
+    Ok(())
+}
+
+/// S 参数块的端口节点、参考节点与 Touchstone 数据; 节点数为 N 时参考节点为地
+fn sparam_block(inst: &Instance) -> Result<(Vec<usize>, usize, crate::touchstone::Touchstone), StampError> {
+    let path = inst.params.get("file").ok_or(StampError::MissingValue)?;
+    let data = crate::touchstone::read_touchstone(std::path::Path::new(path)).map_err(|_| StampError::MissingValue)?;
+    let nodes: Vec<usize> = inst.nodes.iter().map(|node| node.0).collect();
+    if nodes.len() == data.ports {
+        Ok((nodes, 0, data))
+    } else if nodes.len() == data.ports + 1 {
+        Ok((nodes[..data.ports].to_vec(), nodes[data.ports], data))
+    } else {
+        Err(StampError::InvalidNodes)
+    }
+}
+
+/// S 参数块每个端口电流的辅助变量列; 第一个端口沿用实例名
+fn sparam_aux(inst: &Instance, ports: usize, mut allocate: impl FnMut(&str) -> usize) -> Vec<usize> {
+    (1..=ports)
+        .map(|port| if port == 1 { allocate(&inst.name) } else { allocate(&format!("{}:{}", inst.name, port)) })
+        .collect()
+}
+
+/// N-port S-parameter block
+/// With the port voltages V (to the reference node) and the currents I into
+/// the ports, the waves a = V + Z0 I and b = V - Z0 I satisfy b = S a, i.e.
+/// (1 - S) V - Z0 (1 + S) I = 0. One aux current per port keeps the stamp
+/// valid for blocks without an admittance matrix (e.g. a through line)
+fn sparam_entries(
+    ports: &[usize],
+    reference: usize,
+    aux: &[usize],
+    s: &[Complex64],
+    z0: f64,
+) -> Vec<(usize, usize, Complex64)> {
+    let n = ports.len();
+    let mut entries = Vec::new();
+    for (i, (&port, &ki)) in ports.iter().zip(aux).enumerate() {
+        entries.push((port, ki, Complex64::new(1.0, 0.0)));
+        entries.push((reference, ki, Complex64::new(-1.0, 0.0)));
+        for (j, (&pj, &kj)) in ports.iter().zip(aux).enumerate() {
+            let delta = if i == j { 1.0 } else { 0.0 };
+            let sij = s[i * n + j];
+            entries.push((ki, pj, delta - sij));
+            entries.push((ki, reference, sij - delta));
+            entries.push((ki, kj, -(delta + sij) * z0));
+        }
+    }
+    entries
+}
+
+/// S 参数块的直流 (及瞬态) 盖章: 取最低频点 S 矩阵的实部
+fn stamp_sparam(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError> {
+    let (ports, reference, data) = sparam_block(inst)?;
+    let aux = sparam_aux(inst, ports.len(), |name| ctx.allocate_aux(name));
+    for (i, j, value) in sparam_entries(&ports, reference, &aux, &data.s_at(0.0), data.z0) {
+        ctx.add(i, j, value.re);
+    }
+    Ok(())
+}
+
+/// 数字器件: 输出是对地的理想电压源, 逻辑输出取 0 / 1 V, DAC 取输出电平。
+/// 直流中线网值由当前迭代解组合求出, 瞬态中取事件驱动的 `digital` 状态
+fn stamp_digital(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+    digital: Option<&crate::digital::DigitalState>,
+) -> Result<(), StampError> {
+    let params = crate::digital::digital_params(inst).ok_or(StampError::InvalidNodes)?;
+    let x = x.unwrap_or(&[]);
+    let level = |node: usize| match digital {
+        Some(state) => state.level(node, x),
+        None => x.get(node).copied().unwrap_or(0.0) >= crate::digital::LOGIC_THRESHOLD,
+    };
+    let nodes: Vec<usize> = inst.nodes.iter().map(|node| node.0).collect();
+    let out = nodes[nodes.len() - 1];
+    let output = match (digital, params.kind) {
+        (_, DigitalKind::DacBridge) => level(nodes[0]),
+        (Some(_), _) => level(out),
+        (None, DigitalKind::Dff) => params.ic,
+        (None, DigitalKind::AdcBridge) => params.adc_level(x.get(nodes[0]).copied().unwrap_or(0.0), level(out)),
+        (None, _) => params.gate_output(&nodes[..nodes.len() - 1].iter().map(|node| level(*node)).collect::<Vec<_>>()),
+    };
+    let value = match params.kind {
+        DigitalKind::DacBridge => params.dac_voltage(output),
+        _ if output => 1.0,
+        _ => 0.0,
+    };
+    let k = ctx.allocate_aux(&inst.name);
+    ctx.add(out, k, 1.0);
+    ctx.add(0, k, -1.0);
+    ctx.add(k, out, 1.0);
+    ctx.add(k, 0, -1.0);
+    ctx.add_rhs(k, value);
+    Ok(())
+}
+
 /// Voltage Controlled Current Source (VCCS)
 /// Iout = G * Vin where G is the transconductance
 /// nodes: [out+, out-, in+, in-]
@@ -640,21 +3305,155 @@ fn stamp_ccvs(ctx: &mut StampContext, inst: &Instance) -> Result<(), StampError>
     Ok(())
 }
 
+/// Behavioral source expression and output form (V= or I=)
+fn behavioral_expr(inst: &Instance) -> Result<(BehavioralExpr, bool), StampError> {
+    let (text, is_voltage) = if let Some(text) = inst.params.get("v") {
+        (text, true)
+    } else if let Some(text) = inst.params.get("i") {
+        (text, false)
+    } else {
+        return Err(StampError::MissingValue);
+    };
+    let expr = BehavioralExpr::parse(text).map_err(|_| StampError::MissingValue)?;
+    Ok((expr, is_voltage))
+}
+
+/// Map each expression variable to its MNA unknown index.
+/// Node references follow the two output terminals in `inst.nodes`;
+/// `branch` maps the element named in `I(...)` to its branch-current column.
+fn behavioral_columns(
+    expr: &BehavioralExpr,
+    inst: &Instance,
+    mut branch: impl FnMut(&str) -> Result<usize, StampError>,
+) -> Result<Vec<usize>, StampError> {
+    let mut next_node = 2;
+    let mut columns = Vec::with_capacity(expr.vars.len());
+    for var in &expr.vars {
+        match var {
+            ExprVar::Node(_) => {
+                let node = inst.nodes.get(next_node).ok_or(StampError::InvalidNodes)?;
+                columns.push(node.0);
+                next_node += 1;
+            }
+            ExprVar::Branch(name) => columns.push(branch(name)?),
+        }
+    }
+    Ok(columns)
+}
+
+/// Column of an already allocated branch current, matched case-insensitively
+fn existing_branch_column(
+    aux_ids: &HashMap<String, usize>,
+    node_count: usize,
+    name: &str,
+) -> Result<usize, StampError> {
+    aux_ids
+        .get(name)
+        .or_else(|| {
+            aux_ids
+                .iter()
+                .find(|(aux_name, _)| aux_name.eq_ignore_ascii_case(name))
+                .map(|(_, id)| id)
+        })
+        .map(|id| node_count + id)
+        .ok_or(StampError::MissingValue)
+}
+
+/// Behavioral source (B element)
+/// nodes: [out+, out-, referenced nodes...], params: "v" or "i" expression
+///
+/// The expression f(x) is linearized around the current Newton iterate:
+/// f(x) ≈ f(x0) + Σ ∂f/∂x_k · (x_k - x0_k)
+fn stamp_behavioral(
+    ctx: &mut StampContext,
+    inst: &Instance,
+    x: Option<&[f64]>,
+) -> Result<(), StampError> {
+    if inst.nodes.len() < 2 {
+        return Err(StampError::InvalidNodes);
+    }
+    let (expr, is_voltage) = behavioral_expr(inst)?;
+    let out_p = inst.nodes[0].0;
+    let out_n = inst.nodes[1].0;
+
+    // Allocate the output branch first so I(self) resolves; an element stamped
+    // later gets its branch current allocated here under the same name (the
+    // names were checked against branch-owning elements during elaboration)
+    let k = if is_voltage {
+        Some(ctx.allocate_aux(&inst.name))
+    } else {
+        None
+    };
+    let columns = behavioral_columns(&expr, inst, |name| {
+        existing_branch_column(&ctx.aux.name_to_id, ctx.node_count, name).or_else(|_| Ok(ctx.allocate_aux(name)))
+    })?;
+    let values: Vec<f64> = columns
+        .iter()
+        .map(|col| x.and_then(|x| x.get(*col)).copied().unwrap_or(0.0))
+        .collect();
+    let (value, grad) = expr.eval(&values);
+    if !value.is_finite() {
+        return Err(StampError::MissingValue);
+    }
+    let value = value * ctx.source_scale;
+    let linear: f64 = grad
+        .iter()
+        .zip(values.iter())
+        .map(|(g, v)| g * ctx.source_scale * v)
+        .sum();
+    let offset = value - linear;
+
+    if let Some(k) = k {
+        // V(out+) - V(out-) - Σ g_k x_k = f(x0) - Σ g_k x0_k
+        ctx.add(out_p, k, 1.0);
+        ctx.add(out_n, k, -1.0);
+        ctx.add(k, out_p, 1.0);
+        ctx.add(k, out_n, -1.0);
+        for (col, g) in columns.iter().zip(grad.iter()) {
+            if *g != 0.0 {
+                ctx.add(k, *col, -g * ctx.source_scale);
+            }
+        }
+        ctx.add_rhs(k, offset);
+    } else {
+        // Current flows from out+ to out- through the source
+        for (col, g) in columns.iter().zip(grad.iter()) {
+            if *g != 0.0 {
+                let g = g * ctx.source_scale;
+                ctx.add(out_p, *col, g);
+                ctx.add(out_n, *col, -g);
+            }
+        }
+        ctx.add_rhs(out_p, -offset);
+        ctx.add_rhs(out_n, offset);
+    }
+    Ok(())
+}
+
 // ============================================================================
 // AC Small-Signal Stamping Functions
 // ============================================================================
 
 /// Resistor AC stamping: Y = G = 1/R (real admittance)
-fn stamp_resistor_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
+fn stamp_resistor_ac(ctx: &mut ComplexStampContext, inst: &Instance, dc_solution: &[f64]) -> Result<(), StampError> {
+    // Thermistor: conductance at the operating-point temperature, plus the
+    // small-signal current driven by the temperature node
+    if let Some(params) = thermistor_params(inst) {
+        let ThermistorLinearization { a, b, tnode, g, gt, .. } = thermistor_linearization(inst, &params, Some(dc_solution))?;
+        ctx.add_real(a, a, g);
+        ctx.add_real(b, b, g);
+        ctx.add_real(a, b, -g);
+        ctx.add_real(b, a, -g);
+        if let Some(t) = tnode {
+            ctx.add_real(a, t, gt);
+            ctx.add_real(b, t, -gt);
+        }
+        return Ok(());
+    }
     if inst.nodes.len() != 2 {
         return Err(StampError::InvalidNodes);
     }
-    let value = inst
-        .value
-        .as_deref()
-        .and_then(parse_number_with_suffix)
-        .ok_or(StampError::MissingValue)?;
-    let g = 1.0 / value;
+    let g = 1.0 / resistance(inst)?;
     let a = inst.nodes[0].0;
     let b = inst.nodes[1].0;
     ctx.add_real(a, a, g);
@@ -665,18 +3464,31 @@ fn stamp_resistor_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(
 }
 
 /// Capacitor AC stamping: Y = jωC (imaginary admittance)
-fn stamp_capacitor_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
-    if inst.nodes.len() != 2 {
+fn stamp_capacitor_ac(
+    ctx: &mut ComplexStampContext,
+    inst: &Instance,
+    dc_solution: &[f64],
+) -> Result<(), StampError> {
+    if inst.nodes.len() < 2 {
         return Err(StampError::InvalidNodes);
     }
-    let c = inst
-        .value
-        .as_deref()
-        .and_then(parse_number_with_suffix)
-        .ok_or(StampError::MissingValue)?;
-    let y = ctx.omega * c; // jωC has imaginary part ωC
     let a = inst.nodes[0].0;
     let b = inst.nodes[1].0;
+    let c = match nonlinear_capacitor(inst)? {
+        // Q= 形式: 小信号电流 jω Σ ∂Q/∂x_k · x_k
+        Some((expr, true)) => {
+            let (_, grad) = expr.eval(&nonlinear_capacitor_values(inst, Some(dc_solution)));
+            for (node, g) in inst.nodes[2..].iter().zip(grad.iter()) {
+                ctx.add_imag(a, node.0, ctx.omega * g);
+                ctx.add_imag(b, node.0, -ctx.omega * g);
+            }
+            return Ok(());
+        }
+        // C= 形式: 取工作点上的电容
+        Some((expr, false)) => expr.eval(&nonlinear_capacitor_values(inst, Some(dc_solution))).0,
+        None => capacitance(inst)?,
+    };
+    let y = ctx.omega * c; // jωC has imaginary part ωC
     ctx.add_imag(a, a, y);
     ctx.add_imag(b, b, y);
     ctx.add_imag(a, b, -y);
@@ -686,21 +3498,42 @@ fn stamp_capacitor_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<
 
 /// Inductor AC stamping: Y = 1/(jωL) = -j/(ωL)
 /// Uses auxiliary variable for inductor current
-fn stamp_inductor_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
-    if inst.nodes.len() != 2 {
+fn stamp_inductor_ac(
+    ctx: &mut ComplexStampContext,
+    inst: &Instance,
+    dc_solution: &[f64],
+) -> Result<(), StampError> {
+    if inst.nodes.len() < 2 {
         return Err(StampError::InvalidNodes);
     }
-    let l = inst
-        .value
-        .as_deref()
-        .and_then(parse_number_with_suffix)
-        .ok_or(StampError::MissingValue)?;
     let a = inst.nodes[0].0;
     let b = inst.nodes[1].0;
 
     // Allocate auxiliary variable for inductor current
     let k = ctx.allocate_aux(&inst.name);
 
+    let l = match nonlinear_inductor(inst)? {
+        Some((expr, is_flux)) => {
+            // 工作点电流由直流短路电导上的压降给出
+            let voltage = |node: usize| dc_solution.get(node).copied().unwrap_or(0.0);
+            let current = INDUCTOR_DC_CONDUCTANCE * (voltage(a) - voltage(b));
+            let (f, grad) = expr.eval(&nonlinear_inductor_values(&expr, inst, Some(dc_solution), current));
+            if is_flux {
+                // FLUX= 形式: 小信号压降 jω Σ ∂Φ/∂x_k · x_k
+                ctx.add_real(a, k, 1.0);
+                ctx.add_real(b, k, -1.0);
+                ctx.add_real(k, a, 1.0);
+                ctx.add_real(k, b, -1.0);
+                for (col, g) in nonlinear_inductor_columns(&expr, inst, k).into_iter().zip(grad.iter()) {
+                    ctx.add_imag(k, col, -ctx.omega * g);
+                }
+                return Ok(());
+            }
+            f
+        }
+        None => inductance(inst)?,
+    };
+
     // KCL at nodes: current flows from a to b
     ctx.add_real(a, k, 1.0);
     ctx.add_real(b, k, -1.0);
@@ -742,6 +3575,47 @@ fn stamp_voltage_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<()
     Ok(())
 }
 
+/// Port AC stamping: Z0 termination driven by the AC source voltage
+fn stamp_port_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
+    let (a, b, z0) = port_terminals(inst)?;
+    let g = 1.0 / z0;
+    ctx.add_real(a, a, g);
+    ctx.add_real(b, b, g);
+    ctx.add_real(a, b, -g);
+    ctx.add_real(b, a, -g);
+
+    let ac_mag = inst.ac_mag.unwrap_or(0.0);
+    let ac_phase_rad = inst.ac_phase.unwrap_or(0.0) * std::f64::consts::PI / 180.0;
+    let current = Complex64::from_polar(ac_mag * g, ac_phase_rad);
+    ctx.add_rhs(a, current);
+    ctx.add_rhs(b, -current);
+
+    Ok(())
+}
+
+/// S-parameter block AC stamping with the S-matrix interpolated at ω
+fn stamp_sparam_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
+    let (ports, reference, data) = sparam_block(inst)?;
+    let aux = sparam_aux(inst, ports.len(), |name| ctx.allocate_aux(name));
+    let freq = ctx.omega / (2.0 * std::f64::consts::PI);
+    for (i, j, value) in sparam_entries(&ports, reference, &aux, &data.s_at(freq), data.z0) {
+        ctx.add(i, j, value);
+    }
+    Ok(())
+}
+
+/// Digital element AC stamping: outputs are held, so they carry no signal
+fn stamp_digital_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
+    crate::digital::digital_params(inst).ok_or(StampError::InvalidNodes)?;
+    let out = inst.nodes[inst.nodes.len() - 1].0;
+    let k = ctx.allocate_aux(&inst.name);
+    ctx.add_real(out, k, 1.0);
+    ctx.add_real(0, k, -1.0);
+    ctx.add_real(k, out, 1.0);
+    ctx.add_real(k, 0, -1.0);
+    Ok(())
+}
+
 /// Current source AC stamping with AC magnitude and phase
 fn stamp_current_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
     if inst.nodes.len() != 2 {
@@ -763,36 +3637,59 @@ fn stamp_current_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<()
     Ok(())
 }
 
-/// Diode AC stamping: linearized small-signal conductance from DC operating point
+/// Diode AC stamping: small-signal conductance and junction capacitance at the DC operating point
 fn stamp_diode_ac(
     ctx: &mut ComplexStampContext,
     inst: &Instance,
     dc_solution: &[f64],
 ) -> Result<(), StampError> {
-    if inst.nodes.len() != 2 {
-        return Err(StampError::InvalidNodes);
-    }
-    let a = inst.nodes[0].0;
-    let b = inst.nodes[1].0;
-
-    let gmin = 1e-12;
-    let isat = param_value(&inst.params, &["is"]).unwrap_or(1e-14);
-    let emission = param_value(&inst.params, &["n", "nj"]).unwrap_or(1.0);
-    let vt = 0.02585 * emission;
+    let (anode, a, b) = diode_nodes(inst)?;
 
+    let model = diode_model(inst);
+    if anode != a && model.rs > 0.0 {
+        let g = 1.0 / model.rs;
+        ctx.add_real(anode, anode, g);
+        ctx.add_real(a, a, g);
+        ctx.add_real(anode, a, -g);
+        ctx.add_real(a, anode, -g);
+    }
+    let vt = diode_thermal_voltage(inst);
     let va = dc_solution.get(a).copied().unwrap_or(0.0);
     let vb = dc_solution.get(b).copied().unwrap_or(0.0);
     let vd = va - vb;
 
-    // Small-signal conductance gd = dId/dVd = (Is/Vt) * exp(Vd/Vt)
-    let exp_vd = (vd / vt).exp();
-    let gd = (isat / vt) * exp_vd;
-    let g = gd.max(gmin);
+    // Small-signal conductance gd = dId/dVd, capacitance = depletion + TT * gd
+    let out = sim_devices::diode::evaluate_diode(&model, vd, vt);
+    let g = out.gd.max(1e-12);
+    let (_, c) = model.charge(vd, &out);
+    let y = ctx.omega * c;
 
     ctx.add_real(a, a, g);
     ctx.add_real(b, b, g);
     ctx.add_real(a, b, -g);
     ctx.add_real(b, a, -g);
+    if y != 0.0 {
+        ctx.add_imag(a, a, y);
+        ctx.add_imag(b, b, y);
+        ctx.add_imag(a, b, -y);
+        ctx.add_imag(b, a, -y);
+    }
+
+    // Optical ports: photocurrent follows the small-signal input power,
+    // the LED output power follows the junction voltage
+    let (_, light, lightout) = diode_ports(inst);
+    if let Some(light) = light {
+        ctx.add_real(a, light, -model.resp);
+        ctx.add_real(b, light, model.resp);
+    }
+    if let Some(out_node) = lightout {
+        let slope = led_slope(&model, &out);
+        let k = ctx.allocate_aux(&format!("{}#light", inst.name));
+        ctx.add_real(out_node, k, 1.0);
+        ctx.add_real(k, out_node, 1.0);
+        ctx.add_real(k, a, -slope);
+        ctx.add_real(k, b, slope);
+    }
 
     Ok(())
 }
@@ -803,6 +3700,9 @@ fn stamp_mos_ac(
     inst: &Instance,
     dc_solution: &[f64],
 ) -> Result<(), StampError> {
+    if sim_devices::power::is_vdmos(&inst.params) {
+        return stamp_vdmos_ac(ctx, inst, dc_solution);
+    }
     if inst.nodes.len() < 4 {
         return Err(StampError::InvalidNodes);
     }
@@ -812,33 +3712,20 @@ fn stamp_mos_ac(
     let bulk = inst.nodes[3].0;
     let gmin = 1e-12;
 
-    // Parse model level
-    let level = param_value(&inst.params, &["level"]).unwrap_or(49.0) as u32;
-
-    // Determine NMOS/PMOS
-    let is_pmos = if let Some(t) = inst.params.get("type") {
-        let t_lower = t.to_ascii_lowercase();
-        t_lower.contains("pmos") || t_lower == "p"
-    } else if inst.params.contains_key("pmos") {
-        true
-    } else {
-        false
-    };
-
-    // Build BSIM parameters
-    let params = sim_devices::bsim::build_bsim_params(&inst.params, level, is_pmos);
-
-    let w = param_value(&inst.params, &["w"]).unwrap_or(1e-6);
-    let l = param_value(&inst.params, &["l"]).unwrap_or(1e-6);
-    let temp = param_value(&inst.params, &["temp"]).unwrap_or(300.15);
-
     let vd = dc_solution.get(drain).copied().unwrap_or(0.0);
     let vg = dc_solution.get(gate).copied().unwrap_or(0.0);
     let vs = dc_solution.get(source).copied().unwrap_or(0.0);
     let vb = dc_solution.get(bulk).copied().unwrap_or(0.0);
 
     // Get small-signal parameters from DC operating point
-    let output = sim_devices::bsim::evaluate_mos(&params, w, l, vd, vg, vs, vb, temp);
+    let model = heated_mos_model(inst, dc_solution);
+    let output = model.evaluate(vd, vg, vs, vb);
+
+    // 自热: 小信号下器件温度固定在工作点, 热节点只接 RTH 与 CTH
+    if let Some(thermal) = mos_thermal(inst) {
+        let y = Complex64::new(1.0 / thermal.rth, ctx.omega * thermal.cth);
+        ctx.add(thermal.node, thermal.node, y);
+    }
 
     let gm = output.gm;
     let gds = output.gds.max(gmin);
@@ -864,6 +3751,146 @@ fn stamp_mos_ac(
         ctx.add_real(source, source, gmbs);
     }
 
+    // Source/drain junctions: conductance and depletion capacitance
+    let vt = diode_thermal_voltage(inst);
+    let v_at = |n: usize| dc_solution.get(n).copied().unwrap_or(0.0);
+    for (_, a, k, junction) in mos_junctions(inst, &model) {
+        let vj = v_at(a) - v_at(k);
+        let g = junction.current(vj, vt).1 + gmin;
+        let y = Complex64::new(g, ctx.omega * junction.charge(vj).1);
+        ctx.add(a, a, y);
+        ctx.add(k, k, y);
+        ctx.add(a, k, -y);
+        ctx.add(k, a, -y);
+    }
+
+    // Charge model: full (non-reciprocal) capacitance matrix at the operating point
+    if model.params.capmod > 0 {
+        let nodes = mos_terminals(inst);
+        let c = model.charge_jacobian([vd, vg, vs, vb]);
+        for (&row, c_row) in nodes.iter().zip(c) {
+            for (&col, c_ij) in nodes.iter().zip(c_row) {
+                ctx.add_imag(row, col, ctx.omega * c_ij);
+            }
+        }
+        return Ok(());
+    }
+
+    // Meyer gate capacitances at the operating point
+    for ((_, a, b), c) in mos_capacitors(inst).into_iter().zip([output.cgs, output.cgd, output.cgb]) {
+        let y = ctx.omega * c;
+        ctx.add_imag(a, a, y);
+        ctx.add_imag(b, b, y);
+        ctx.add_imag(a, b, -y);
+        ctx.add_imag(b, a, -y);
+    }
+
+    Ok(())
+}
+
+/// BJT AC stamping: Gummel-Poon / VBIC small-signal conductances at the DC operating point
+fn stamp_bjt_ac(
+    ctx: &mut ComplexStampContext,
+    inst: &Instance,
+    dc_solution: &[f64],
+) -> Result<(), StampError> {
+    let (external, internal, ci) = bjt_nodes(inst)?;
+    let model = bjt_model(inst);
+    for ((ext, int), r) in external.into_iter().zip(internal).zip(model.series_resistances()) {
+        if ext != int && r > 0.0 {
+            let g = 1.0 / r;
+            ctx.add_real(ext, ext, g);
+            ctx.add_real(int, int, g);
+            ctx.add_real(ext, int, -g);
+            ctx.add_real(int, ext, -g);
+        }
+    }
+    let [cx, bi, ei] = internal;
+    let v = |n: usize| dc_solution.get(n).copied().unwrap_or(0.0);
+    if ci != cx {
+        let (g_ci, g_cx, _) = bjt_epi_linearize(inst, &model, v(bi) - v(ci), v(bi) - v(cx));
+        for (row, dir) in [(cx, 1.0), (ci, -1.0)] {
+            ctx.add_real(row, bi, dir * (g_ci + g_cx));
+            ctx.add_real(row, ci, -dir * g_ci);
+            ctx.add_real(row, cx, -dir * g_cx);
+        }
+    }
+    let rows = bjt_linearize(inst, &model, v(bi) - v(ei), v(bi) - v(ci), 1e-12);
+    for (row, (g_be, g_bc, _)) in [ci, bi, ei].into_iter().zip(rows) {
+        ctx.add_real(row, bi, g_be + g_bc);
+        ctx.add_real(row, ei, -g_be);
+        ctx.add_real(row, ci, -g_bc);
+    }
+    Ok(())
+}
+
+/// JFET AC stamping: small-signal conductances and gate junction capacitances
+/// at the DC operating point
+fn stamp_jfet_ac(
+    ctx: &mut ComplexStampContext,
+    inst: &Instance,
+    dc_solution: &[f64],
+) -> Result<(), StampError> {
+    if sim_devices::power::is_igbt(&inst.params) {
+        return stamp_igbt_ac(ctx, inst, dc_solution);
+    }
+    let (external, internal) = jfet_nodes(inst)?;
+    let fet = jfet_model(inst);
+    let model = fet.common();
+    for ((ext, int), r) in external.into_iter().zip(internal).zip([model.rd, 0.0, model.rs]) {
+        if ext != int && r > 0.0 {
+            let g = 1.0 / r;
+            ctx.add_real(ext, ext, g);
+            ctx.add_real(int, int, g);
+            ctx.add_real(ext, int, -g);
+            ctx.add_real(int, ext, -g);
+        }
+    }
+    let [di, g, si] = internal;
+    let v = |n: usize| dc_solution.get(n).copied().unwrap_or(0.0);
+    let rows = jfet_linearize(inst, &fet, v(g) - v(si), v(g) - v(di), 1e-12);
+    for (row, (g_gs, g_gd, _)) in internal.into_iter().zip(rows) {
+        ctx.add_real(row, g, g_gs + g_gd);
+        ctx.add_real(row, si, -g_gs);
+        ctx.add_real(row, di, -g_gd);
+    }
+    let sign = model.jfet_type.sign();
+    for (_, a, b, cj0) in jfet_capacitors(model, internal) {
+        let c = sim_devices::junction::depletion_capacitance(cj0, sign * (v(a) - v(b)), model.pb, 0.5, model.fc);
+        let y = ctx.omega * c;
+        ctx.add_imag(a, a, y);
+        ctx.add_imag(b, b, y);
+        ctx.add_imag(a, b, -y);
+        ctx.add_imag(b, a, -y);
+    }
+    Ok(())
+}
+
+/// Switch AC stamping: conductance and control transconductance at the DC operating point
+fn stamp_switch_ac(
+    ctx: &mut ComplexStampContext,
+    inst: &Instance,
+    dc_solution: &[f64],
+) -> Result<(), StampError> {
+    let column = inst
+        .control
+        .as_ref()
+        .and_then(|name| ctx.aux.name_to_id.get(name))
+        .map(|id| ctx.node_count + id);
+    let ports = switch_ports(inst, column)?;
+    let (a, b) = (ports.a, ports.b);
+    let model = switch_model(inst);
+    let v = |n: usize| dc_solution.get(n).copied().unwrap_or(0.0);
+    let vc = ports.control_value(v);
+    let (g, gm, _) = switch_linearize(&model, vc, v(a) - v(b), None);
+    ctx.add_real(a, a, g);
+    ctx.add_real(b, b, g);
+    ctx.add_real(a, b, -g);
+    ctx.add_real(b, a, -g);
+    for &(col, coeff) in &ports.control {
+        ctx.add_real(a, col, gm * coeff);
+        ctx.add_real(b, col, -gm * coeff);
+    }
     Ok(())
 }
 
@@ -895,6 +3922,78 @@ fn stamp_vcvs_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), S
     Ok(())
 }
 
+/// Multi-conductor line AC stamping: the same lumped sections with
+/// series (R + jωL)^-1 and shunt G + jωC
+fn stamp_wline_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
+    let line = wline(inst)?;
+    let s = Complex64::new(0.0, ctx.omega);
+    let y_series = line.section.series_admittance(s).ok_or(StampError::MissingValue)?;
+    let y_shunt = line.section.shunt_admittance(s);
+    for pair in line.boundaries.windows(2) {
+        for (row, col, sign, idx) in coupled_pattern(&pair[0], &pair[1]) {
+            ctx.add(row, col, y_series[idx] * sign);
+        }
+    }
+    let reference = vec![line.reference; line.conductors];
+    for (k, nodes) in line.boundaries.iter().enumerate() {
+        let weight = wline_shunt_weight(&line, k);
+        for (row, col, sign, idx) in coupled_pattern(nodes, &reference) {
+            ctx.add(row, col, y_shunt[idx] * (sign * weight));
+        }
+    }
+    if let Some(k) = wline_reference_aux(inst, &line, |name| ctx.allocate_aux(name)) {
+        ctx.add_real(line.reference, k, 1.0);
+        ctx.add_real(line.out_reference, k, -1.0);
+        ctx.add_real(k, line.reference, 1.0);
+        ctx.add_real(k, line.out_reference, -1.0);
+    }
+    Ok(())
+}
+
+/// Ideal transformer AC stamping (frequency-independent)
+fn stamp_transformer_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
+    let ([p_p, p_n, s_p, s_n], n) = transformer_ports(inst)?;
+
+    let k = ctx.allocate_aux(&inst.name);
+
+    ctx.add_real(p_p, k, 1.0);
+    ctx.add_real(p_n, k, -1.0);
+    ctx.add_real(s_p, k, -n);
+    ctx.add_real(s_n, k, n);
+    ctx.add_real(k, p_p, 1.0);
+    ctx.add_real(k, p_n, -1.0);
+    ctx.add_real(k, s_p, -n);
+    ctx.add_real(k, s_n, n);
+
+    Ok(())
+}
+
+/// Lossless transmission line AC stamping: exact two-port with the
+/// delay as a phase factor, V1 - Z0 I1 = exp(-jwTD) (V2 + Z0 I2)
+fn stamp_tline_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
+    let [a_p, a_n, b_p, b_n] = tline_ports(inst)?;
+    let (z0, td) = tline_params(inst)?;
+    let k1 = ctx.allocate_aux(&inst.name);
+    let k2 = ctx.allocate_aux(&format!("{}:2", inst.name));
+    let delay = Complex64::from_polar(1.0, -ctx.omega * td);
+
+    ctx.add_real(a_p, k1, 1.0);
+    ctx.add_real(a_n, k1, -1.0);
+    ctx.add_real(b_p, k2, 1.0);
+    ctx.add_real(b_n, k2, -1.0);
+
+    for (k, own, other, (p, n), (q, m)) in [(k1, k1, k2, (a_p, a_n), (b_p, b_n)), (k2, k2, k1, (b_p, b_n), (a_p, a_n))] {
+        ctx.add_real(k, p, 1.0);
+        ctx.add_real(k, n, -1.0);
+        ctx.add_real(k, own, -z0);
+        ctx.add(k, q, -delay);
+        ctx.add(k, m, delay);
+        ctx.add(k, other, -delay * z0);
+    }
+
+    Ok(())
+}
+
 /// VCCS AC stamping (frequency-independent)
 fn stamp_vccs_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), StampError> {
     if inst.nodes.len() != 4 {
@@ -981,3 +4080,51 @@ fn stamp_ccvs_ac(ctx: &mut ComplexStampContext, inst: &Instance) -> Result<(), S
 
     Ok(())
 }
+
+/// Behavioral source AC stamping: partial derivatives at the DC operating point
+fn stamp_behavioral_ac(
+    ctx: &mut ComplexStampContext,
+    inst: &Instance,
+    dc_solution: &[f64],
+) -> Result<(), StampError> {
+    if inst.nodes.len() < 2 {
+        return Err(StampError::InvalidNodes);
+    }
+    let (expr, is_voltage) = behavioral_expr(inst)?;
+    let out_p = inst.nodes[0].0;
+    let out_n = inst.nodes[1].0;
+
+    let k = if is_voltage {
+        Some(ctx.allocate_aux(&inst.name))
+    } else {
+        None
+    };
+    let columns = behavioral_columns(&expr, inst, |name| {
+        existing_branch_column(&ctx.aux.name_to_id, ctx.node_count, name).or_else(|_| Ok(ctx.allocate_aux(name)))
+    })?;
+    let values: Vec<f64> = columns
+        .iter()
+        .map(|col| dc_solution.get(*col).copied().unwrap_or(0.0))
+        .collect();
+    let (_, grad) = expr.eval(&values);
+
+    if let Some(k) = k {
+        ctx.add_real(out_p, k, 1.0);
+        ctx.add_real(out_n, k, -1.0);
+        ctx.add_real(k, out_p, 1.0);
+        ctx.add_real(k, out_n, -1.0);
+        for (col, g) in columns.iter().zip(grad.iter()) {
+            if *g != 0.0 {
+                ctx.add_real(k, *col, -g);
+            }
+        }
+    } else {
+        for (col, g) in columns.iter().zip(grad.iter()) {
+            if *g != 0.0 {
+                ctx.add_real(out_p, *col, *g);
+                ctx.add_real(out_n, *col, -g);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use std::ops::Index;
+
+use crate::circuit::Instance;
+
+/// 一个实例内的状态支路: 多数器件只有一个状态量, MOSFET / JFET 的各个结电容与热节点各占一条
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateBranch {
+    Main,
+    Gs,
+    Gd,
+    Gb,
+    Bd,
+    Bs,
+    Thermal,
+}
+
+impl StateBranch {
+    const ALL: [StateBranch; 7] = [
+        StateBranch::Main,
+        StateBranch::Gs,
+        StateBranch::Gd,
+        StateBranch::Gb,
+        StateBranch::Bd,
+        StateBranch::Bs,
+        StateBranch::Thermal,
+    ];
+
+    /// 支路名, 如 `gs`; 与实例名拼成结电压限幅等仍按名字区分的键
+    pub fn suffix(self) -> &'static str {
+        match self {
+            StateBranch::Main => "",
+            StateBranch::Gs => "gs",
+            StateBranch::Gd => "gd",
+            StateBranch::Gb => "gb",
+            StateBranch::Bd => "bd",
+            StateBranch::Bs => "bs",
+            StateBranch::Thermal => "th",
+        }
+    }
+}
+
+/// 状态量的位置: 实例的状态槽位 (建电路时按实例顺序分配) 与实例内的支路
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StateKey {
+    pub slot: usize,
+    pub branch: StateBranch,
+}
+
+impl StateKey {
+    /// 实例的主状态量
+    pub fn of(inst: &Instance) -> Self {
+        Self::branch(inst, StateBranch::Main)
+    }
+
+    pub fn branch(inst: &Instance, branch: StateBranch) -> Self {
+        Self {
+            slot: inst.state_slot,
+            branch,
+        }
+    }
+
+    fn index(self) -> usize {
+        self.slot * StateBranch::ALL.len() + self.branch as usize
+    }
+
+    fn from_index(index: usize) -> Self {
+        let branches = StateBranch::ALL.len();
+        Self {
+            slot: index / branches,
+            branch: StateBranch::ALL[index % branches],
+        }
+    }
+}
+
+/// 按状态槽位直接索引的表, 取代以实例名为键的 HashMap: 每个器件每步的存取不再对名字做哈希
+#[derive(Debug, Clone)]
+pub struct StateTable<T> {
+    values: Vec<Option<T>>,
+    len: usize,
+}
+
+impl<T> Default for StateTable<T> {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> StateTable<T> {
+    pub fn get(&self, key: StateKey) -> Option<&T> {
+        self.values.get(key.index()).and_then(Option::as_ref)
+    }
+
+    pub fn get_mut(&mut self, key: StateKey) -> Option<&mut T> {
+        self.values.get_mut(key.index()).and_then(Option::as_mut)
+    }
+
+    pub fn contains_key(&self, key: StateKey) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// 写入 `key` 处的值, 返回原来的值
+    pub fn insert(&mut self, key: StateKey, value: T) -> Option<T> {
+        let index = key.index();
+        if index >= self.values.len() {
+            self.values.resize_with(index + 1, || None);
+        }
+        let old = self.values[index].replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// `key` 处的值, 没有时先以 `default()` 写入
+    pub fn get_or_insert_with(&mut self, key: StateKey, default: impl FnOnce() -> T) -> &mut T {
+        let index = key.index();
+        if index >= self.values.len() {
+            self.values.resize_with(index + 1, || None);
+        }
+        if self.values[index].is_none() {
+            self.len += 1;
+        }
+        self.values[index].get_or_insert_with(default)
+    }
+
+    pub fn remove(&mut self, key: StateKey) -> Option<T> {
+        let old = self.values.get_mut(key.index()).and_then(Option::take);
+        if old.is_some() {
+            self.len -= 1;
+        }
+        old
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.len = 0;
+    }
+
+    /// 按槽位顺序遍历已有的状态量
+    pub fn iter(&self) -> impl Iterator<Item = (StateKey, &T)> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| value.as_ref().map(|value| (StateKey::from_index(index), value)))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.values.iter().flatten()
+    }
+}
+
+impl<T> Index<StateKey> for StateTable<T> {
+    type Output = T;
+
+    fn index(&self, key: StateKey) -> &T {
+        self.get(key).expect("no state at this key")
+    }
+}
+
+impl<T> FromIterator<(StateKey, T)> for StateTable<T> {
+    fn from_iter<I: IntoIterator<Item = (StateKey, T)>>(iter: I) -> Self {
+        let mut table = Self::default();
+        for (key, value) in iter {
+            table.insert(key, value);
+        }
+        table
+    }
+}
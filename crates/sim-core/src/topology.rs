@@ -10,3 +10,161 @@ pub fn debug_dump_topology(graph: &TopologyGraph) {
         graph.node_count, graph.device_count
     );
 }
+
+/// 奇异 MNA 矩阵的结构诊断: 矩阵分解失败时据此指出出问题的节点与器件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SingularMatrixReport {
+    /// 对角元为零且不与任何辅助变量 (支路电流) 相连的节点, 其 KCL 方程不约束节点电压
+    pub zero_diagonal_nodes: Vec<String>,
+    /// 与地之间没有任何矩阵项相连的节点组 (浮空子电路), 每组按节点编号排列
+    pub floating_groups: Vec<Vec<String>>,
+    /// 电压定义的支路 (电压源、受控电压源) 构成的回路, 每条回路沿回路依次列出其上的器件
+    pub voltage_loops: Vec<Vec<String>>,
+}
+
+impl SingularMatrixReport {
+    pub fn is_empty(&self) -> bool {
+        self.zero_diagonal_nodes.is_empty() && self.floating_groups.is_empty() && self.voltage_loops.is_empty()
+    }
+
+    /// 单行摘要, 用于 RunResult 的 message
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.zero_diagonal_nodes.is_empty() {
+            parts.push(format!("zero diagonal at node(s) {}", self.zero_diagonal_nodes.join(", ")));
+        }
+        for group in &self.floating_groups {
+            parts.push(format!("floating subcircuit (no path to ground) at node(s) {}", group.join(", ")));
+        }
+        for devices in &self.voltage_loops {
+            parts.push(format!("voltage source loop {}", devices.join(" -> ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// 分析 CSC 矩阵 (ap, ai, ax) 的结构。前 `node_names.len()` 个未知量为节点 (`gnd` 为地),
+/// 其后为辅助变量。`branches` 为带支路电流的两端支路 (器件名, 支路电流的未知量编号, 两端节点);
+/// 支路电流在自身方程中的对角元为零时, 该支路方程只约束两端电压 (电压源、受控电压源等)
+pub fn analyze_singular_matrix(
+    ap: &[i64],
+    ai: &[i64],
+    ax: &[f64],
+    node_names: &[String],
+    gnd: usize,
+    branches: &[(String, usize, usize, usize)],
+) -> SingularMatrixReport {
+    let n = ap.len().saturating_sub(1);
+    let node_count = node_names.len().min(n);
+    let mut diagonal = vec![0.0; n];
+    let mut aux_coupled = vec![false; n];
+    let mut groups = UnionFind::new(n);
+    for col in 0..n {
+        for k in ap[col] as usize..ap[col + 1] as usize {
+            let row = ai[k] as usize;
+            if ax[k] == 0.0 || row >= n {
+                continue;
+            }
+            if row == col {
+                diagonal[row] += ax[k];
+                continue;
+            }
+            groups.union(row, col);
+            if row >= node_count {
+                aux_coupled[col] = true;
+            }
+            if col >= node_count {
+                aux_coupled[row] = true;
+            }
+        }
+    }
+
+    let mut report = SingularMatrixReport::default();
+    let zero_diagonal: Vec<usize> =
+        (0..node_count).filter(|&node| node != gnd && diagonal[node] == 0.0 && !aux_coupled[node]).collect();
+    report.zero_diagonal_nodes = zero_diagonal.iter().map(|&node| node_names[node].clone()).collect();
+
+    let ground = (gnd < n).then(|| groups.find(gnd));
+    let mut floating: Vec<(usize, Vec<String>)> = Vec::new();
+    for node in (0..node_count).filter(|node| *node != gnd && !zero_diagonal.contains(node)) {
+        let root = groups.find(node);
+        if Some(root) == ground {
+            continue;
+        }
+        match floating.iter_mut().find(|(group, _)| *group == root) {
+            Some((_, names)) => names.push(node_names[node].clone()),
+            None => floating.push((root, vec![node_names[node].clone()])),
+        }
+    }
+    report.floating_groups = floating.into_iter().map(|(_, names)| names).collect();
+
+    // 电压支路构成的生成森林; 新支路两端已连通时, 森林中两端间的路径加上它即为一条回路
+    let mut forest = UnionFind::new(node_count);
+    let mut edges: Vec<(usize, usize, &str)> = Vec::new();
+    for (name, current, a, b) in branches {
+        let (a, b) = (*a, *b);
+        if a >= node_count || b >= node_count || diagonal.get(*current).is_none_or(|value| *value != 0.0) {
+            continue;
+        }
+        if forest.find(a) == forest.find(b) {
+            let mut devices = forest_path(&edges, a, b);
+            devices.push(name.clone());
+            report.voltage_loops.push(devices);
+        } else {
+            forest.union(a, b);
+            edges.push((a, b, name));
+        }
+    }
+    report
+}
+
+/// 森林中从 `from` 到 `to` 的路径上的支路名; 两点相同时为空
+fn forest_path(edges: &[(usize, usize, &str)], from: usize, to: usize) -> Vec<String> {
+    let mut previous: std::collections::HashMap<usize, (usize, &str)> = std::collections::HashMap::new();
+    let mut queue = std::collections::VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            break;
+        }
+        for &(a, b, name) in edges {
+            let next = if a == node { b } else if b == node { a } else { continue };
+            if next != from && !previous.contains_key(&next) {
+                previous.insert(next, (node, name));
+                queue.push_back(next);
+            }
+        }
+    }
+    let mut path = Vec::new();
+    let mut node = to;
+    while let Some(&(prev, name)) = previous.get(&node) {
+        path.push(name.to_string());
+        node = prev;
+    }
+    path.reverse();
+    path
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        Self { parent: (0..n).collect() }
+    }
+
+    fn find(&mut self, mut idx: usize) -> usize {
+        while self.parent[idx] != idx {
+            self.parent[idx] = self.parent[self.parent[idx]];
+            idx = self.parent[idx];
+        }
+        idx
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Touchstone (`.snp`) file support for N-port S-parameter blocks.
+//!
+//! Version 1 files are read: `!` starts a comment, the option line
+//! `# <unit> <param> <format> R <z0>` (default `# GHz S MA R 50`) selects the
+//! frequency unit, the parameter type (S, Y or Z) and the number format (MA,
+//! DB or RI). Each data record is a frequency followed by N² value pairs; for
+//! two-port files the order is S11 S21 S12 S22, otherwise row by row. Y and Z
+//! data, which version 1 stores normalized to R, are converted to S on load,
+//! so the block always holds S-parameters referenced to `z0`.
+//!
+//! Results of the `.sp` analysis are written in the same version 1 layout,
+//! as `# Hz S RI R <z0>` with one line per matrix row.
+
+use num_complex::Complex64;
+use std::path::Path;
+
+/// N-port network data, S-parameters referenced to `z0`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Touchstone {
+    pub ports: usize,
+    /// Reference impedance [Ohm]
+    pub z0: f64,
+    /// Frequencies in ascending order [Hz]
+    pub frequencies: Vec<f64>,
+    /// S-matrix per frequency, row-major N×N
+    pub s: Vec<Vec<Complex64>>,
+}
+
+/// Port count from a `.sNp` extension
+pub fn ports_from_extension(path: &Path) -> Option<usize> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let digits = ext.strip_prefix('s')?.strip_suffix('p')?;
+    digits.parse().ok().filter(|n| *n > 0)
+}
+
+/// Read a Touchstone file; the port count comes from the extension
+pub fn read_touchstone(path: &Path) -> Result<Touchstone, String> {
+    let ports = ports_from_extension(path)
+        .ok_or_else(|| format!("{}: cannot infer the port count, expected a .sNp extension", path.display()))?;
+    let text = std::fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+    parse_touchstone(&text, ports).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ParamType {
+    S,
+    Y,
+    Z,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum NumberFormat {
+    MagnitudeAngle,
+    DecibelAngle,
+    RealImaginary,
+}
+
+/// Format an N-port block as Touchstone text (`# Hz S RI`); a record is
+/// the frequency followed by the matrix rows, one row per line, except for
+/// two-port data which uses the single-line S11 S21 S12 S22 order
+pub fn format_touchstone(data: &Touchstone, precision: usize) -> String {
+    let n = data.ports;
+    let mut out = format!("! {}-port S-parameters\n# Hz S RI R {}\n", n, data.z0);
+    let pair = |value: &Complex64| format!(" {:.*e} {:.*e}", precision, value.re, precision, value.im);
+    for (freq, matrix) in data.frequencies.iter().zip(&data.s) {
+        out.push_str(&format!("{:.*e}", precision, freq));
+        if n == 2 {
+            for idx in [0, 2, 1, 3] {
+                out.push_str(&pair(&matrix[idx]));
+            }
+            out.push('\n');
+            continue;
+        }
+        for (row, values) in matrix.chunks(n).enumerate() {
+            if row > 0 {
+                out.push(' ');
+            }
+            for value in values {
+                out.push_str(&pair(value));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Write an N-port block to a Touchstone file
+pub fn write_touchstone(data: &Touchstone, path: &Path, precision: usize) -> std::io::Result<()> {
+    std::fs::write(path, format_touchstone(data, precision))
+}
+
+/// Parse Touchstone text for an N-port
+pub fn parse_touchstone(text: &str, ports: usize) -> Result<Touchstone, String> {
+    let mut unit = 1e9;
+    let mut param = ParamType::S;
+    let mut format = NumberFormat::MagnitudeAngle;
+    let mut z0 = 50.0;
+    let mut numbers = Vec::new();
+    let mut seen_options = false;
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('!').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(options) = line.strip_prefix('#') {
+            // 只有第一行选项有效
+            if seen_options {
+                continue;
+            }
+            seen_options = true;
+            let mut tokens = options.split_whitespace();
+            while let Some(token) = tokens.next() {
+                match token.to_ascii_uppercase().as_str() {
+                    "HZ" => unit = 1.0,
+                    "KHZ" => unit = 1e3,
+                    "MHZ" => unit = 1e6,
+                    "GHZ" => unit = 1e9,
+                    "S" => param = ParamType::S,
+                    "Y" => param = ParamType::Y,
+                    "Z" => param = ParamType::Z,
+                    "MA" => format = NumberFormat::MagnitudeAngle,
+                    "DB" => format = NumberFormat::DecibelAngle,
+                    "RI" => format = NumberFormat::RealImaginary,
+                    "R" => {
+                        z0 = tokens
+                            .next()
+                            .and_then(|value| value.parse().ok())
+                            .filter(|value: &f64| *value > 0.0)
+                            .ok_or_else(|| format!("line {}: invalid reference impedance", index + 1))?;
+                    }
+                    other => return Err(format!("line {}: unsupported option '{}'", index + 1, other)),
+                }
+            }
+            continue;
+        }
+        for token in line.split_whitespace() {
+            let value: f64 = token
+                .parse()
+                .map_err(|_| format!("line {}: invalid number '{}'", index + 1, token))?;
+            numbers.push(value);
+        }
+    }
+
+    let record = 1 + 2 * ports * ports;
+    if numbers.is_empty() || numbers.len() % record != 0 {
+        return Err(format!(
+            "expected records of {} numbers for a {}-port, found {} numbers",
+            record,
+            ports,
+            numbers.len()
+        ));
+    }
+
+    let mut frequencies = Vec::new();
+    let mut s = Vec::new();
+    for chunk in numbers.chunks(record) {
+        let freq = chunk[0] * unit;
+        if frequencies.last().is_some_and(|last| freq <= *last) {
+            return Err(format!("frequencies must increase, found {} after {}", freq, frequencies.last().unwrap()));
+        }
+        let mut matrix = vec![Complex64::new(0.0, 0.0); ports * ports];
+        for (idx, pair) in chunk[1..].chunks(2).enumerate() {
+            let value = match format {
+                NumberFormat::RealImaginary => Complex64::new(pair[0], pair[1]),
+                NumberFormat::MagnitudeAngle => Complex64::from_polar(pair[0], pair[1].to_radians()),
+                NumberFormat::DecibelAngle => Complex64::from_polar(10f64.powf(pair[0] / 20.0), pair[1].to_radians()),
+            };
+            // 二端口按列排列: S11 S21 S12 S22
+            let (row, col) = if ports == 2 { (idx % 2, idx / 2) } else { (idx / ports, idx % ports) };
+            matrix[row * ports + col] = value;
+        }
+        let matrix = match param {
+            ParamType::S => matrix,
+            ParamType::Z => normalized_to_s(&matrix, ports, false)?,
+            ParamType::Y => normalized_to_s(&matrix, ports, true)?,
+        };
+        frequencies.push(freq);
+        s.push(matrix);
+    }
+
+    Ok(Touchstone {
+        ports,
+        z0,
+        frequencies,
+        s,
+    })
+}
+
+/// Normalized z → S: S = (z - I)(z + I)^-1; normalized y → S: S = (I - y)(I + y)^-1
+fn normalized_to_s(matrix: &[Complex64], n: usize, admittance: bool) -> Result<Vec<Complex64>, String> {
+    let mut numerator = vec![Complex64::new(0.0, 0.0); n * n];
+    let mut denominator = vec![Complex64::new(0.0, 0.0); n * n];
+    for i in 0..n {
+        for j in 0..n {
+            let value = matrix[i * n + j];
+            let identity = if i == j { 1.0 } else { 0.0 };
+            numerator[i * n + j] = if admittance { identity - value } else { value - identity };
+            denominator[i * n + j] = value + identity;
+        }
+    }
+    let inverse = invert(&denominator, n).ok_or_else(|| "singular network matrix".to_string())?;
+    Ok(multiply(&numerator, &inverse, n))
+}
+
+fn multiply(a: &[Complex64], b: &[Complex64], n: usize) -> Vec<Complex64> {
+    let mut out = vec![Complex64::new(0.0, 0.0); n * n];
+    for i in 0..n {
+        for k in 0..n {
+            for j in 0..n {
+                out[i * n + j] += a[i * n + k] * b[k * n + j];
+            }
+        }
+    }
+    out
+}
+
+/// Gauss-Jordan inverse with partial pivoting
+pub(crate) fn invert(matrix: &[Complex64], n: usize) -> Option<Vec<Complex64>> {
+    let mut a = matrix.to_vec();
+    let mut inv = vec![Complex64::new(0.0, 0.0); n * n];
+    for i in 0..n {
+        inv[i * n + i] = Complex64::new(1.0, 0.0);
+    }
+    for col in 0..n {
+        let pivot = (col..n).max_by(|x, y| a[x * n + col].norm().total_cmp(&a[y * n + col].norm()))?;
+        if a[pivot * n + col].norm() < 1e-300 {
+            return None;
+        }
+        for j in 0..n {
+            a.swap(col * n + j, pivot * n + j);
+            inv.swap(col * n + j, pivot * n + j);
+        }
+        let scale = a[col * n + col].inv();
+        for j in 0..n {
+            a[col * n + j] *= scale;
+            inv[col * n + j] *= scale;
+        }
+        for row in (0..n).filter(|row| *row != col) {
+            let factor = a[row * n + col];
+            for j in 0..n {
+                let (pa, pi) = (a[col * n + j], inv[col * n + j]);
+                a[row * n + j] -= factor * pa;
+                inv[row * n + j] -= factor * pi;
+            }
+        }
+    }
+    Some(inv)
+}
+
+impl Touchstone {
+    /// S-matrix at `freq`, linearly interpolated in real / imaginary parts;
+    /// outside the data range the nearest end point is used
+    pub fn s_at(&self, freq: f64) -> Vec<Complex64> {
+        let last = self.frequencies.len() - 1;
+        if freq <= self.frequencies[0] {
+            return self.s[0].clone();
+        }
+        if freq >= self.frequencies[last] {
+            return self.s[last].clone();
+        }
+        let upper = self.frequencies.partition_point(|f| *f < freq);
+        let (f0, f1) = (self.frequencies[upper - 1], self.frequencies[upper]);
+        let t = (freq - f0) / (f1 - f0);
+        self.s[upper - 1]
+            .iter()
+            .zip(&self.s[upper])
+            .map(|(a, b)| a + (b - a) * t)
+            .collect()
+    }
+}
@@ -0,0 +1,146 @@
+use num_complex::Complex64;
+use sim_core::analysis::AnalysisPlan;
+use sim_core::complex_solver::{ComplexDenseSolver, ComplexLinearSolver, ComplexSparseSolver};
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::result_store::{ResultStore, RunResult, RunStatus};
+
+fn run_ac(netlist: &str) -> RunResult {
+    run_ac_with_threads(netlist, 1)
+}
+
+fn run_ac_with_threads(netlist: &str, threads: usize) -> RunResult {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    engine.set_threads(threads);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    store.runs[run_id.0].clone()
+}
+
+#[test]
+fn ac_points_are_merged_in_frequency_order() {
+    // 频率点按线程数设置并行求解; 结果须按扫描顺序排列, 与串行逐位一致, 且逐点与 RC 低通的解析解一致
+    let netlist = "* rc lowpass\nV1 in 0 DC 0 AC 1\nR1 in out 1k\nC1 out 0 1u\n.ac dec 200 1 1g\n.end\n";
+    let run = run_ac(netlist);
+    let parallel = run_ac_with_threads(netlist, 4);
+    assert_eq!(parallel.ac_frequencies, run.ac_frequencies);
+    assert_eq!(parallel.ac_solutions, run.ac_solutions);
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    assert_eq!(run.ac_frequencies.len(), 1801);
+    assert_eq!(run.ac_solutions.len(), run.ac_frequencies.len());
+    assert_eq!(run.iterations, run.ac_frequencies.len());
+    assert!(run.ac_frequencies.windows(2).all(|pair| pair[1] > pair[0]));
+
+    let out = run.node_names.iter().position(|name| name == "out").unwrap();
+    for (freq, point) in run.ac_frequencies.iter().zip(&run.ac_solutions) {
+        let wrc = 2.0 * std::f64::consts::PI * freq * 1e-3;
+        let mag_db = -10.0 * (1.0 + wrc * wrc).log10();
+        let phase = -wrc.atan().to_degrees();
+        let (db, deg) = point[out];
+        assert!((db - mag_db).abs() < 1e-6, "{} Hz: {} dB vs {} dB", freq, db, mag_db);
+        assert!((deg - phase).abs() < 1e-6, "{} Hz: {} vs {}", freq, deg, phase);
+    }
+}
+
+#[test]
+fn ac_keeps_complex_node_voltages_and_branch_currents() {
+    let run = run_ac("* rc lowpass\nV1 in 0 DC 0 AC 1\nR1 in out 1k\nC1 out 0 1u\n.ac dec 5 10 10k\n.end\n");
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    assert_eq!(run.ac_phasors.len(), run.ac_frequencies.len());
+    assert_eq!(run.ac_phasors[0].len(), run.node_names.len() + run.branch_names.len());
+    assert!(run.branch_names.iter().any(|name| name == "V1"));
+
+    let out = run.node_names.iter().position(|name| name == "out").unwrap();
+    for (point, freq) in run.ac_frequencies.iter().enumerate() {
+        let jwrc = Complex64::new(0.0, 2.0 * std::f64::consts::PI * freq * 1e-3);
+        let expected = 1.0 / (1.0 + jwrc);
+        let vout = run.ac_voltage("out", point).unwrap();
+        assert!((vout - expected).norm() < 1e-9, "{} Hz: {} vs {}", freq, vout, expected);
+        // dB / 相位由同一复数解得到
+        let (db, deg) = run.ac_solutions[point][out];
+        assert!((db - 20.0 * vout.norm().log10()).abs() < 1e-9);
+        assert!((deg - vout.arg().to_degrees()).abs() < 1e-9);
+        // 电压源支路电流即流过 R1 的电流 (流出正端为负)
+        let current = run.ac_branch_current("V1", point).unwrap();
+        let through_r1 = (run.ac_voltage("in", point).unwrap() - vout) / 1e3;
+        assert!((current + through_r1).norm() < 1e-12, "{} vs {}", current, through_r1);
+    }
+}
+
+fn complex_csc(dense: &[Vec<Complex64>]) -> (Vec<i64>, Vec<i64>, Vec<Complex64>) {
+    let n = dense.len();
+    let (mut ap, mut ai, mut ax) = (vec![0], Vec::new(), Vec::new());
+    for col in 0..n {
+        for (row, values) in dense.iter().enumerate() {
+            if values[col] != Complex64::new(0.0, 0.0) {
+                ai.push(row as i64);
+                ax.push(values[col]);
+            }
+        }
+        ap.push(ai.len() as i64);
+    }
+    (ap, ai, ax)
+}
+
+/// RC 梯形网络在 ω 处的 MNA 矩阵: 节点 0 接电压源 (辅助变量为最后一行/列)
+fn rc_ladder(stages: usize, omega: f64) -> Vec<Vec<Complex64>> {
+    let n = stages + 2;
+    let mut y = vec![vec![Complex64::new(0.0, 0.0); n]; n];
+    let g = Complex64::new(1e-3, 0.0);
+    for k in 0..stages {
+        let (a, b) = (k, k + 1);
+        y[a][a] += g;
+        y[b][b] += g;
+        y[a][b] -= g;
+        y[b][a] -= g;
+        y[b][b] += Complex64::new(0.0, omega * 1e-9);
+    }
+    let aux = n - 1;
+    y[0][aux] = Complex64::new(1.0, 0.0);
+    y[aux][0] = Complex64::new(1.0, 0.0);
+    y
+}
+
+#[test]
+fn complex_sparse_solver_matches_dense_and_keeps_analysis_across_frequencies() {
+    let stages = 25;
+    let n = stages + 2;
+    let mut b = vec![Complex64::new(0.0, 0.0); n];
+    b[n - 1] = Complex64::new(1.0, 0.0);
+    let mut sparse = ComplexSparseSolver::new();
+    let mut dense = ComplexDenseSolver::new();
+    for freq in [1.0, 1e3, 1e5, 1e7] {
+        let (ap, ai, ax) = complex_csc(&rc_ladder(stages, 2.0 * std::f64::consts::PI * freq));
+        let mut expected = vec![Complex64::new(0.0, 0.0); n];
+        dense.prepare(n);
+        assert!(dense.solve(&ap, &ai, &ax, &b, &mut expected));
+        let mut x = vec![Complex64::new(0.0, 0.0); n];
+        sparse.prepare(n);
+        assert!(sparse.solve(&ap, &ai, &ax, &b, &mut x));
+        for (x, y) in x.iter().zip(&expected) {
+            assert!((x - y).norm() < 1e-9 * (1.0 + y.norm()), "{} vs {}", x, y);
+        }
+        // 梯形网络排序后没有填充
+        assert!(sparse.factor_nnz() <= 2 * n, "{}", sparse.factor_nnz());
+    }
+    // 各频率点结构相同, 符号分析只做一次
+    assert_eq!(sparse.ordering.orderings, 1);
+}
+
+#[test]
+fn complex_sparse_solver_reports_singular_matrix() {
+    // 悬空节点: 第 1 列全零
+    let y = vec![
+        vec![Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)],
+        vec![Complex64::new(0.0, 1.0), Complex64::new(0.0, 0.0)],
+    ];
+    let (ap, ai, ax) = complex_csc(&y);
+    let mut solver = ComplexSparseSolver::new();
+    solver.prepare(2);
+    let mut x = vec![Complex64::new(0.0, 0.0); 2];
+    assert!(!solver.solve(&ap, &ai, &ax, &[Complex64::new(1.0, 0.0); 2], &mut x));
+}
@@ -0,0 +1,222 @@
+mod common;
+
+use common::{run_first_analysis, run_op};
+use sim_core::behavioral::{BehavioralExpr, ExprVar};
+use sim_core::netlist::parse_netlist;
+use sim_core::result_store::RunResult;
+
+fn node_voltage(run: &RunResult, name: &str) -> f64 {
+    let idx = run
+        .node_names
+        .iter()
+        .position(|n| n == name)
+        .unwrap_or_else(|| panic!("node {} not found", name));
+    run.solution[idx]
+}
+
+#[test]
+fn behavioral_expr_derivatives() {
+    let expr = BehavioralExpr::parse("2*V(a)^2 + V(a,b)*exp(V(b))").unwrap();
+    assert_eq!(
+        expr.vars,
+        vec![ExprVar::Node("a".to_string()), ExprVar::Node("b".to_string())]
+    );
+    let (value, grad) = expr.eval(&[1.5, 0.5]);
+    let expected = 2.0 * 1.5 * 1.5 + 1.0 * 0.5_f64.exp();
+    assert!((value - expected).abs() < 1e-12);
+    // d/da = 4a + exp(b), d/db = -exp(b) + (a-b)*exp(b)
+    assert!((grad[0] - (6.0 + 0.5_f64.exp())).abs() < 1e-12);
+    assert!((grad[1] - (-0.5_f64.exp() + 0.5_f64.exp())).abs() < 1e-12);
+}
+
+#[test]
+fn behavioral_expr_rejects_unknown_identifier() {
+    assert!(BehavioralExpr::parse("V(a)*gain").is_err());
+    assert!(BehavioralExpr::parse("foo(V(a))").is_err());
+}
+
+#[test]
+fn behavioral_voltage_source_nonlinear_dc() {
+    let run = run_op("* bsrc\nV1 in 0 1.5\nB1 out 0 V={2*V(in)^2}\nR1 out 0 1k\n.op\n.end\n");
+    assert!((node_voltage(&run, "out") - 4.5).abs() < 1e-6);
+}
+
+#[test]
+fn behavioral_current_source_with_params_and_branch_current() {
+    // I(V1) = -1mA (SPICE sign: current into the positive terminal), so B1 pushes 2mA into out
+    let netlist = "* bsrc\n.param k=2\nV1 in 0 1\nR1 in 0 1k\nB1 0 out I = -k*I(V1)\nR2 out 0 1k\n.end\n";
+    let run = run_op(netlist);
+    let vout = node_voltage(&run, "out");
+    assert!((vout - 2.0).abs() < 1e-6, "vout={}", vout);
+}
+
+#[test]
+fn behavioral_branch_current_of_later_source() {
+    // Vs 在 B1 之后声明, 引用大小写也与实例名不同
+    let netlist = "B1 out 0 V={1000*I(vs)}\nR2 out 0 1k\nVs in 0 DC 1\nR1 in 0 1k\n.end\n";
+    let run = run_op(netlist);
+    let vout = node_voltage(&run, "out");
+    assert!((vout + 1.0).abs() < 1e-6, "vout={}", vout);
+}
+
+#[test]
+fn behavioral_source_in_subckt_maps_nodes() {
+    let netlist = "* bsrc\n.subckt sq a y ref\nB1 y ref V={V(a,ref)*V(a,ref)}\n.ends\nV1 in 0 3\nX1 in out 0 sq\nR1 out 0 1k\n.end\n";
+    let run = run_op(netlist);
+    assert!((node_voltage(&run, "out") - 9.0).abs() < 1e-6);
+}
+
+#[test]
+fn behavioral_source_parse_errors() {
+    let ast = parse_netlist("B1 out 0 {V(in)}\n.end\n");
+    assert_eq!(ast.errors.len(), 1);
+    let ast = parse_netlist("B1 out 0 V={V(in)*(2}\n.end\n");
+    assert_eq!(ast.errors.len(), 1);
+}
+
+#[test]
+fn controlled_sources_accept_value_and_table_forms() {
+    let netlist = "\
+* E/G VALUE and TABLE
+.param vhi=3
+V1 in 0 1.5
+E1 e1 0 VALUE = {V(in)*V(in)}
+R1 e1 0 1k
+E2 e2 0 TABLE {V(in)} = (0,0) (1,2) (2,vhi)
+R2 e2 0 1k
+G1 0 g1 TABLE {V(in)}=(0,0)(1,1m)(2,1m)
+R3 g1 0 1k
+.op
+.end
+";
+    let run = run_op(netlist);
+    assert!((node_voltage(&run, "e1") - 2.25).abs() < 1e-6);
+    assert!((node_voltage(&run, "e2") - 2.5).abs() < 1e-6);
+    assert!((node_voltage(&run, "g1") - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn behavioral_table_interpolates_with_segment_slope() {
+    let expr = BehavioralExpr::parse("table(V(a), 0, 0, 1, 2, 2, 3)").unwrap();
+    let (value, grad) = expr.eval(&[0.5]);
+    assert!((value - 1.0).abs() < 1e-12);
+    assert!((grad[0] - 2.0).abs() < 1e-12);
+    let (value, grad) = expr.eval(&[5.0]);
+    assert!((value - 3.0).abs() < 1e-12);
+    assert_eq!(grad[0], 0.0);
+}
+
+/// RC 放电 (R = 1k, IC = 1V), 返回 10ns 时的电容电压
+fn discharge(cap: &str) -> f64 {
+    let netlist = format!("* rc\n.param c0=1n\nR1 out 0 1k\nC1 out 0 {} IC=1\n.tran 1n 10n uic\n.end\n", cap);
+    let run = run_first_analysis(&netlist);
+    let out = run.node_names.iter().position(|n| n == "out").unwrap();
+    run.tran_solutions.last().unwrap()[out]
+}
+
+#[test]
+fn nonlinear_capacitor_forms_reduce_to_linear_capacitor() {
+    let linear = discharge("1n");
+    assert!((linear - (-0.01_f64).exp()).abs() < 1e-4, "v = {}", linear);
+    let charge = discharge("Q={c0*V(out)}");
+    let capacitance = discharge("C={c0 + 0*V(out)}");
+    assert!((charge - linear).abs() < 1e-6, "Q form v = {}", charge);
+    assert!((capacitance - linear).abs() < 1e-6, "C form v = {}", capacitance);
+}
+
+#[test]
+fn nonlinear_capacitor_charge_and_capacitance_forms_agree() {
+    // C(V) = 1n * (1 + V), Q(V) = 1n * (V + V^2/2): 电容随电压升高, 放电比线性电容慢
+    let capacitance = discharge("C={1n*(1 + V(out))}");
+    let charge = discharge("Q = {1n*(V(out) + V(out)^2/2)}");
+    assert!(capacitance > discharge("1n") + 0.003, "v = {}", capacitance);
+    assert!((capacitance - charge).abs() < 1e-4, "C form {} vs Q form {}", capacitance, charge);
+}
+
+#[test]
+fn nonlinear_capacitor_ac_uses_operating_point_capacitance() {
+    // 工作点 V(out) = 1: dQ/dV = C(1) = 1n, 在 1/(2*pi*RC) 处衰减 3dB
+    for cap in ["Q={0.5n*V(out)^2}", "C={1n*V(out)}"] {
+        let netlist = format!(
+            "* rc\nV1 in 0 DC 1 AC 1\nR1 in out 1k\nC1 out 0 {}\n.ac lin 1 159154.943 159154.943\n.end\n",
+            cap
+        );
+        let run = run_first_analysis(&netlist);
+        let out = run.node_names.iter().position(|n| n == "out").unwrap();
+        let (db, phase) = run.ac_solutions[0][out];
+        assert!((db + 3.0103).abs() < 1e-3, "{}: |Vout| = {} dB", cap, db);
+        assert!((phase + 45.0).abs() < 0.01, "{}: phase = {}", cap, phase);
+    }
+}
+
+#[test]
+fn nonlinear_capacitor_in_subckt_maps_nodes() {
+    let netlist = "\
+* varactor
+.subckt var a b c0=1n
+C1 a b C={c0*(1 + V(a,b))} IC=1
+.ends
+R1 out 0 1k
+X1 out 0 var c0=1n
+.tran 1n 10n uic
+.end
+";
+    let run = run_first_analysis(netlist);
+    let out = run.node_names.iter().position(|n| n == "out").unwrap();
+    let v = run.tran_solutions.last().unwrap()[out];
+    assert!((v - discharge("C={1n*(1 + V(out))}")).abs() < 1e-9, "v = {}", v);
+    assert_eq!(parse_netlist("C1 a 0 C={V(a)*(2}\n.end\n").errors.len(), 1);
+}
+
+/// RL 放电 (R = 1k, IC = 1mA), 返回 10ns 时电阻上的电压 (= -R·i)
+fn inductor_discharge(ind: &str) -> f64 {
+    let netlist = format!("* rl\n.param l0=1m\nR1 a 0 1k\nL1 a 0 {} IC=1m\n.tran 1n 10n uic\n.end\n", ind);
+    let run = run_first_analysis(&netlist);
+    let a = run.node_names.iter().position(|n| n == "a").unwrap();
+    -run.tran_solutions.last().unwrap()[a]
+}
+
+#[test]
+fn nonlinear_inductor_forms_reduce_to_linear_inductor() {
+    let linear = inductor_discharge("1m");
+    assert!((linear - (-0.01_f64).exp()).abs() < 1e-4, "v = {}", linear);
+    let flux = inductor_discharge("FLUX={l0*I(L1)}");
+    let inductance = inductor_discharge("L={l0 + 0*I(L1)}");
+    assert!((flux - linear).abs() < 1e-6, "FLUX form v = {}", flux);
+    assert!((inductance - linear).abs() < 1e-6, "L form v = {}", inductance);
+}
+
+#[test]
+fn saturating_inductor_discharges_faster() {
+    // L(i) = 1m / (1 + (i/1m)^2), Φ(i) = 1m * 1m * atan(i/1m): IC=1mA 处电感减半
+    let inductance = inductor_discharge("L={1m/(1 + (I(L1)/1m)^2)}");
+    let flux = inductor_discharge("FLUX={1m*1m*atan(I(L1)/1m)}");
+    assert!(inductance < inductor_discharge("1m") - 0.005, "v = {}", inductance);
+    assert!((inductance - flux).abs() < 1e-4, "L form {} vs FLUX form {}", inductance, flux);
+}
+
+#[test]
+fn nonlinear_inductor_ac_uses_operating_point_inductance() {
+    // 工作点电流 1mA: L = dΦ/di = 0.5m, 在 R/(2*pi*L) 处 |V(a)| 为 -3dB, 相位 +45°
+    for ind in ["L={1m/(1 + (I(L1)/1m)^2)}", "FLUX={1m*1m*atan(I(L1)/1m)}"] {
+        let netlist = format!(
+            "* rl\nV1 in 0 DC 1 AC 1\nR1 in a 1k\nL1 a 0 {}\n.ac lin 1 318309.886 318309.886\n.end\n",
+            ind
+        );
+        let run = run_first_analysis(&netlist);
+        let a = run.node_names.iter().position(|n| n == "a").unwrap();
+        let (db, phase) = run.ac_solutions[0][a];
+        assert!((db + 3.0103).abs() < 1e-3, "{}: |Va| = {} dB", ind, db);
+        assert!((phase - 45.0).abs() < 0.01, "{}: phase = {}", ind, phase);
+    }
+}
+
+#[test]
+fn behavioral_expr_conditions_track_comparisons() {
+    let expr = BehavioralExpr::parse("V(a) > 0.3 ? u(V(b) - 1) : min(V(a), 2)").unwrap();
+    // 三元条件, 比较的两个方向 (>, <), u() 参数的符号 (> 0, < 0); min 连续, 不计
+    assert_eq!(expr.conditions(&[0.0, 0.0]), vec![false, false, true, false, true]);
+    assert_eq!(expr.conditions(&[0.5, 0.0]), vec![true, true, false, false, true]);
+    assert_eq!(expr.conditions(&[0.5, 2.0]), vec![true, true, false, true, false]);
+    assert!(BehavioralExpr::parse("2 * V(a) + min(V(a), 1)").unwrap().conditions(&[1.0]).is_empty());
+}
@@ -0,0 +1,173 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::AnalysisCmd;
+use sim_core::diagnostic::codes;
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist, DeviceKind, Stmt};
+use sim_core::result_store::{ResultStore, RunStatus};
+
+fn op_solution(netlist: &str) -> (Vec<String>, Vec<f64>) {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "ast errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "elab errors: {:?}", elab.errors);
+    let circuit = build_circuit(&ast, &elab);
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, RunStatus::Converged), "run failed: {:?}", run);
+    (run.node_names.clone(), run.solution.clone())
+}
+
+fn voltage(names: &[String], solution: &[f64], node: &str) -> f64 {
+    let idx = names
+        .iter()
+        .position(|name| name == node)
+        .unwrap_or_else(|| panic!("node {} not found in {:?}", node, names));
+    solution[idx]
+}
+
+#[test]
+fn bjt_element_parses_optional_substrate_and_area() {
+    let ast = parse_netlist(
+        "\
+Q1 c b e qn
+Q2 c b e sub qn 2
+Q3 c b e qn 3 area=4
+Q4 c b qn
+.model qn npn is=1e-15
+",
+    );
+    let devices: Vec<_> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Device(device) => Some(device),
+            _ => None,
+        })
+        .collect();
+    assert!(devices.iter().all(|d| matches!(d.kind, DeviceKind::Q)));
+    assert_eq!(devices[0].nodes, vec!["c", "b", "e", "0"]);
+    assert_eq!(devices[0].model.as_deref(), Some("qn"));
+    assert_eq!(devices[1].nodes, vec!["c", "b", "e", "sub"]);
+    assert_eq!(devices[1].value.as_deref(), Some("2"));
+    assert_eq!(devices[2].nodes, vec!["c", "b", "e", "0"]);
+    assert_eq!(devices[2].value.as_deref(), Some("3"));
+    // Q4 字段不足, 只有它报错
+    assert!(!ast.errors.is_empty());
+    assert!(ast.errors.iter().all(|diag| diag.line == 4), "errors: {:?}", ast.errors);
+    assert!(ast.errors.iter().any(|diag| diag.code == codes::NODE_COUNT));
+}
+
+#[test]
+fn bjt_common_emitter_bias_point() {
+    let (names, x) = op_solution(
+        "\
+* common-emitter bias
+V1 vcc 0 5
+RC vcc c 1k
+RB vcc b 430k
+Q1 c b 0 qn
+.model qn npn is=1e-14 bf=100
+.op
+.end
+",
+    );
+    let vb = voltage(&names, &x, "b");
+    let vc = voltage(&names, &x, "c");
+    let ib = (5.0 - vb) / 430e3;
+    let ic = (5.0 - vc) / 1e3;
+    assert!(vb > 0.6 && vb < 0.75, "vb = {}", vb);
+    assert!((ic / ib - 100.0).abs() < 0.5, "beta = {}", ic / ib);
+    let expected_ic = 1e-14 * ((vb / 0.02585).exp() - 1.0);
+    assert!((ic - expected_ic).abs() / ic < 1e-3, "ic = {}, expected {}", ic, expected_ic);
+}
+
+#[test]
+fn bjt_pnp_with_series_resistances_uses_internal_nodes() {
+    let (names, x) = op_solution(
+        "\
+* pnp emitter follower with RE/RC/RB
+V1 vee 0 5
+VB b 0 2
+RE vee e 2k
+RC c 0 1k
+Q1 c b e qp
+.model qp pnp is=1e-14 bf=50 vaf=50 re=10 rc=5 rb=100
+.op
+.end
+",
+    );
+    let ve = voltage(&names, &x, "e");
+    let vc = voltage(&names, &x, "c");
+    let vei = voltage(&names, &x, "q1#e");
+    let vci = voltage(&names, &x, "q1#c");
+    let vbi = voltage(&names, &x, "q1#b");
+    let ie = (5.0 - ve) / 2e3;
+    let ic = vc / 1e3;
+    assert!(ve > 2.5 && ve < 2.8, "ve = {}", ve);
+    assert!(ic > 0.9 * ie && ic < ie, "ic = {}, ie = {}", ic, ie);
+    // 串联电阻上的压降与端电流一致
+    assert!(((ve - vei) - ie * 10.0).abs() < 1e-6, "ve - ve' = {}", ve - vei);
+    assert!(((vci - vc) - ic * 5.0).abs() < 1e-6, "vc' - vc = {}", vci - vc);
+    assert!(((vbi - 2.0) - (ie - ic) * 100.0).abs() < 1e-6, "vb' = {}", vbi);
+}
+
+fn vbic_collector_current(vce: f64, model: &str) -> f64 {
+    let (names, x) = op_solution(&format!(
+        "\
+* vbic output characteristic point, collector current sensed by RS
+VC vcc 0 {}
+RS vcc c 1
+VB b 0 0.8
+Q1 c b 0 qv
+.model qv npn level=4 {}
+.op
+.end
+",
+        vce, model
+    ));
+    voltage(&names, &x, "vcc") - voltage(&names, &x, "c")
+}
+
+#[test]
+fn vbic_level4_early_effect_raises_collector_current() {
+    let model = "is=1e-16 vef=20 ver=5 ibei=1e-18";
+    let ic_low = vbic_collector_current(1.0, model);
+    let ic_high = vbic_collector_current(5.0, model);
+    assert!(ic_low > 1e-5 && ic_low < 1e-1, "ic = {}", ic_low);
+    assert!(ic_high > ic_low * 1.05, "ic(1V) = {}, ic(5V) = {}", ic_low, ic_high);
+    // beta = IS / IBEI
+    let ideal = vbic_collector_current(3.0, "is=1e-16 ibei=1e-18");
+    let expected = 1e-16 * ((0.8 / 0.02585f64).exp() - 1.0);
+    assert!((ideal - expected).abs() / expected < 1e-3, "ic = {}, expected {}", ideal, expected);
+}
+
+#[test]
+fn vbic_quasi_saturation_limits_current_at_low_vce() {
+    let (names, _) = op_solution(
+        "\
+VC c 0 1
+VB b 0 0.8
+Q1 c b 0 qv
+.model qv npn level=4 is=1e-16 rci=200
+.op
+.end
+",
+    );
+    assert!(names.iter().any(|name| name == "q1#ci"), "nodes: {:?}", names);
+    let plain = "is=1e-16";
+    let kull = "is=1e-16 rci=200 gamm=1e-13 vo=1";
+    // 低 Vce 时外延层压降使本征 B-C 结正偏, 电流被压低
+    let ic_plain = vbic_collector_current(0.4, plain);
+    let ic_kull = vbic_collector_current(0.4, kull);
+    assert!(ic_kull < 0.9 * ic_plain, "plain {} vs kull {}", ic_plain, ic_kull);
+    // 电导调制 (GAMM) 使准饱和区电流高于纯欧姆 RCI
+    let ic_ohmic = vbic_collector_current(0.4, "is=1e-16 rci=200");
+    let ic_modulated = vbic_collector_current(0.4, "is=1e-16 rci=200 gamm=1e-13");
+    assert!(ic_modulated > ic_ohmic, "ohmic {} vs modulated {}", ic_ohmic, ic_modulated);
+    // 远离饱和时外延层的影响很小
+    let far_plain = vbic_collector_current(5.0, plain);
+    let far_kull = vbic_collector_current(5.0, kull);
+    assert!((far_kull - far_plain).abs() / far_plain < 0.05, "{} vs {}", far_plain, far_kull);
+}
@@ -3,11 +3,22 @@
 //! Tests that verify the BSIM model integrates correctly with
 //! the simulation engine.
 
+mod common;
+
+use common::run_first_analysis;
+use sim_core::analysis::AnalysisPlan;
 use sim_core::circuit::{DeviceKind, Instance, NodeId};
-use sim_core::stamp::{InstanceStamp, DeviceStamp};
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::result_store::{ResultStore, RunStatus};
+use sim_core::stamp::{update_transient_state, InstanceStamp, DeviceStamp, TransientState};
+use sim_core::state_table::{StateBranch, StateKey};
 use sim_core::mna::MnaBuilder;
 use std::collections::HashMap;
 
+/// 氧化层电容 Cox * W * L (TOX 缺省 15nm)
+const COX_10U_BY_10U: f64 = 3.9 * 8.854e-12 / 15e-9 * 10e-6 * 10e-6;
+
 fn make_mos_instance(name: &str, params: HashMap<String, String>) -> Instance {
     Instance {
         name: name.to_string(),
@@ -24,6 +35,7 @@ fn make_mos_instance(name: &str, params: HashMap<String, String>) -> Instance {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     }
 }
 
@@ -101,3 +113,306 @@ fn mos_initial_stamp_uses_gmin() {
     let result = stamp.stamp_dc(&mut ctx, None);
     assert!(result.is_ok(), "Initial stamp should succeed");
 }
+
+#[test]
+fn mos_gate_capacitance_in_accumulation_sets_ac_pole() {
+    // Vgs = -1V 深度积累: 本征电容全部接到衬底 Cgb = Cox*W*L, 再加上两侧交叠电容
+    let c = COX_10U_BY_10U + 2.0 * 1e-9 * 10e-6;
+    let pole = 1.0 / (2.0 * std::f64::consts::PI * 1e6 * c);
+    let run = run_first_analysis(&format!(
+        "\
+* gate driven through RG, all other terminals grounded
+V1 in 0 DC -1 AC 1
+RG in g 1meg
+M1 0 g 0 0 nm w=10u l=10u
+.model nm nmos level=49 cgso=1n cgdo=1n
+.ac lin 1 {} {}
+.end
+",
+        pole, pole
+    ));
+    let g = run.node_names.iter().position(|name| name == "g").unwrap();
+    let (db, _) = run.ac_solutions[0][g];
+    assert!((db + 3.0103).abs() < 1e-3, "|Vg| = {} dB", db);
+}
+
+#[test]
+fn mos_tran_stamp_adds_meyer_capacitance_in_saturation() {
+    let mut params = HashMap::new();
+    for (key, value) in [("w", "10u"), ("l", "10u"), ("level", "49"), ("cgdo", "1n")] {
+        params.insert(key.to_string(), value.to_string());
+    }
+    let stamp = InstanceStamp { instance: make_mos_instance("M1", params) };
+    let x = vec![0.0, 3.0, 1.5, 0.0, 0.0];
+    let dt = 1e-9;
+
+    let mut dc = MnaBuilder::new(5);
+    stamp.stamp_dc(&mut dc.context(), Some(&x)).unwrap();
+
+    let mut tran = MnaBuilder::new(5);
+    let mut state = TransientState::default();
+    for (branch, v) in [(StateBranch::Gs, 1.5), (StateBranch::Gd, -1.5), (StateBranch::Gb, 1.5)] {
+        state.cap_voltage.insert(StateKey::branch(&stamp.instance, branch), v);
+    }
+    stamp.stamp_tran(&mut tran.context(), Some(&x), dt, &mut state).unwrap();
+
+    // 饱和区 Cgs = 2/3 Cox*W*L, Cgd 只剩交叠电容, Cgb = 0; 后向欧拉历史电流 C/dt * V_prev
+    let cgs = 2.0 / 3.0 * COX_10U_BY_10U;
+    let cgd = 1e-9 * 10e-6;
+    let expected = (cgs * 1.5 - cgd * 1.5) / dt;
+    let delta = tran.rhs[2] - dc.rhs[2];
+    assert!((delta - expected).abs() < 1e-9 * expected.abs(), "delta = {}, expected {}", delta, expected);
+    assert!(((tran.rhs[3] - dc.rhs[3]) + cgs * 1.5 / dt).abs() < 1e-9 * expected.abs(), "source history current");
+    assert!(((tran.rhs[1] - dc.rhs[1]) - cgd * 1.5 / dt).abs() < 1e-9 * expected.abs(), "drain history current");
+    assert_eq!(tran.rhs[4], dc.rhs[4], "no gate-bulk capacitance in saturation");
+}
+
+/// 栅极经 1meg 电阻驱动的饱和 NMOS, 返回 AC 扫描点处的栅极幅度 (dB)
+fn saturated_gate_response(capmod: u32, freq: f64) -> f64 {
+    let run = run_first_analysis(&format!(
+        "\
+* saturated nmos, gate driven through RG
+V1 in 0 DC 1.5 AC 1
+RG in g 1meg
+VD d 0 5
+M1 d g 0 0 nm w=10u l=10u
+.model nm nmos level=49 capmod={}
+.ac lin 1 {} {}
+.end
+",
+        capmod, freq, freq
+    ));
+    let g = run.node_names.iter().position(|name| name == "g").unwrap();
+    run.ac_solutions[0][g].0
+}
+
+#[test]
+fn charge_model_gate_capacitance_in_saturation() {
+    // 饱和区 Qinv = -2/3 Cox*W*L*Vgst, 体电荷与 Vg 无关: Cgg = 2/3 Cox*W*L
+    let cgg = 2.0 / 3.0 * COX_10U_BY_10U;
+    let pole = 1.0 / (2.0 * std::f64::consts::PI * 1e6 * cgg);
+    let db = saturated_gate_response(1, pole);
+    assert!((db + 3.0103).abs() < 1e-3, "|Vg| = {} dB", db);
+    // capmod=3 的反型层厚度使有效 Cox 变小, 极点外移
+    let db = saturated_gate_response(3, pole);
+    assert!(db > -3.0, "|Vg| = {} dB", db);
+}
+
+#[test]
+fn charge_model_tran_stamp_conserves_charge() {
+    let mut params = HashMap::new();
+    for (key, value) in [("w", "10u"), ("l", "10u"), ("level", "49"), ("capmod", "1"), ("cgdo", "1n")] {
+        params.insert(key.to_string(), value.to_string());
+    }
+    let inst = make_mos_instance("M1", params);
+    let stamp = InstanceStamp { instance: inst.clone() };
+    let dt = 1e-9;
+
+    // 上一时间点的端电荷按实际偏置记录, 四端之和为零
+    let mut state = TransientState::default();
+    update_transient_state(&[inst], &[0.0, 0.2, 1.2, 0.0, -0.5], &mut state);
+    let q_prev = state.mos_charge[StateKey::of(&stamp.instance)];
+    assert!(q_prev[1] > 0.0, "gate charge = {}", q_prev[1]);
+    assert!(q_prev.iter().sum::<f64>().abs() < 1e-12 * q_prev[1]);
+
+    let x = vec![0.0, 3.0, 1.8, 0.0, -0.5];
+    let mut dc = MnaBuilder::new(5);
+    stamp.stamp_dc(&mut dc.context(), Some(&x)).unwrap();
+    let mut tran = MnaBuilder::new(5);
+    stamp.stamp_tran(&mut tran.context(), Some(&x), dt, &mut state).unwrap();
+
+    // 各端口的位移电流之和为零: 电荷只在端口之间转移
+    let delta: Vec<f64> = (1..5).map(|i| tran.rhs[i] - dc.rhs[i]).collect();
+    let scale = delta.iter().map(|d| d.abs()).fold(0.0, f64::max);
+    assert!(scale > 0.0);
+    assert!(delta.iter().sum::<f64>().abs() < 1e-6 * scale, "delta = {:?}", delta);
+}
+
+#[test]
+fn mos_drain_junction_conducts_when_forward_biased() {
+    // 把 1mA 从漏极抽走, 漏-衬底结 (阳极为衬底) 正偏: Vbd = Vt * ln(I/IS + 1)
+    let run = run_first_analysis(
+        "\
+* nmos held off, drain pulled below the grounded bulk
+I1 d 0 1m
+M1 d 0 0 0 nm w=10u l=10u ad=100p pd=40u
+.model nm nmos level=49 vth0=2 is=1e-14
+.op
+.end
+",
+    );
+    let d = run.node_names.iter().position(|name| name == "d").unwrap();
+    let expected = -0.02585 * (1e-3 / 1e-14 + 1.0f64).ln();
+    assert!((run.solution[d] - expected).abs() < 1e-6, "vd = {}, expected {}", run.solution[d], expected);
+}
+
+#[test]
+fn mos_junction_capacitance_loads_the_drain() {
+    // 反偏 2V: 底面 CJ*AD 与侧壁 CJSW*PD 分别按各自的 PB / MJ 减小
+    let c = 1e-3 * 100e-12 / (1.0 + 2.0 / 0.8f64).powf(0.5) + 1e-9 * 40e-6 / (1.0 + 2.0 / 0.6f64).powf(0.33);
+    let pole = 1.0 / (2.0 * std::f64::consts::PI * 1e3 * c);
+    let run = run_first_analysis(&format!(
+        "\
+* nmos held off, drain reverse biased through R1
+V1 in 0 DC 2 AC 1
+R1 in d 1k
+M1 d 0 0 0 nm w=10u l=10u ad=100p pd=40u
+.model nm nmos level=49 vth0=2 cj=1m cjsw=1n mj=0.5 mjsw=0.33 pb=0.8 pbsw=0.6
+.ac lin 1 {} {}
+.end
+",
+        pole, pole
+    ));
+    let d = run.node_names.iter().position(|name| name == "d").unwrap();
+    let (db, _) = run.ac_solutions[0][d];
+    assert!((db + 3.0103).abs() < 1e-3, "|Vd| = {} dB", db);
+}
+
+#[test]
+fn bsim4_gidl_current_flows_into_the_bulk() {
+    // 关断的 NMOS, 栅极 -1V、漏极 2V: GIDL 从漏极流入衬底, 经 RB 产生压降
+    let run = run_first_analysis(
+        "\
+* bsim4 gidl measured through a bulk resistor
+VD d 0 2
+VG g 0 -1
+RB b 0 10k
+M1 d g 0 b nm w=1u l=100n
+.model nm nmos level=54 vth0=0.35 tox=1.5n agidl=1e-7
+.op
+.end
+",
+    );
+    let node = |name: &str| run.node_names.iter().position(|n| n == name).unwrap();
+    let vb = run.solution[node("b")];
+    let mut params = HashMap::new();
+    for (k, v) in [("vth0", "0.35"), ("tox", "1.5n"), ("agidl", "1e-7")] {
+        params.insert(k.to_string(), v.to_string());
+    }
+    let model = sim_devices::bsim::build_bsim_params(&params, 54, false);
+    let expected = sim_devices::bsim::calculate_gidl(&model, 1e-6, 2.0, -1.0, 2.0 - vb);
+    assert!(expected > 1e-8, "igidl = {}", expected);
+    assert!((vb / 10e3 - expected).abs() < 1e-3 * expected, "vb = {}, igidl = {}", vb, expected);
+}
+
+#[test]
+fn bsim4_gate_tunneling_loads_the_gate_driver() {
+    // IGCMOD=1: 导通时栅极经 RG 向沟道漏电, Vds = 0 时 Igcs = Igcd
+    let run = run_first_analysis(
+        "\
+* bsim4 gate-to-channel tunneling through a gate resistor
+VG in 0 1.2
+RG in g 1meg
+M1 0 g 0 0 nm w=10u l=1u
+.model nm nmos level=54 vth0=0.35 tox=1.2n toxref=1.2n igcmod=1
+.op
+.end
+",
+    );
+    let node = |name: &str| run.node_names.iter().position(|n| n == name).unwrap();
+    let vg = run.solution[node("g")];
+    let mut params = HashMap::new();
+    for (k, v) in [("level", "54"), ("vth0", "0.35"), ("tox", "1.2n"), ("toxref", "1.2n"), ("igcmod", "1")] {
+        params.insert(k.to_string(), v.to_string());
+    }
+    let model = sim_devices::bsim::build_bsim_params(&params, 54, false);
+    let out = sim_devices::bsim::evaluate_mos_bsim4(&model, 10e-6, 1e-6, 0.0, vg, 0.0, 0.0, 300.15, 0.0, 0.0);
+    let ig = (1.2 - vg) / 1e6;
+    assert!(ig > 1e-9, "ig = {}", ig);
+    assert!((out.igcs - out.igcd).abs() < 1e-9 * out.igcs);
+    assert!((ig - (out.igcs + out.igcd)).abs() < 1e-4 * ig, "ig = {}, igc = {}", ig, out.igcs + out.igcd);
+}
+
+/// 按 `.dc temp` 扫描运行, 返回 (温度, 节点电压) 序列
+fn temp_sweep(netlist: &str, node: &str) -> Vec<(f64, f64)> {
+    let ast = parse_netlist(netlist);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    let index = run.node_names.iter().position(|n| n == node).unwrap();
+    run.sweep_values.iter().zip(&run.sweep_solutions).map(|(t, x)| (*t, x[index])).collect()
+}
+
+#[test]
+fn mos_current_follows_vsat_and_rdsw_temperature_laws() {
+    let card = "vth0=0.5 u0=400 tox=4n vsat=8e4 rdsw=200 prt=400 at=3e4 ua1=1e-9";
+    let sweep = temp_sweep(
+        &format!(
+            "\
+* saturated nmos sensed through a small drain resistor
+VDD vdd 0 1.8
+VG g 0 1.5
+RD vdd d 10
+M1 d g 0 0 nm w=10u l=200n
+.model nm nmos level=49 {}
+.dc temp 27 127 100
+.end
+",
+            card
+        ),
+        "d",
+    );
+    let mut params = HashMap::new();
+    for (k, v) in card.split_whitespace().filter_map(|kv| kv.split_once('=')) {
+        params.insert(k.to_string(), v.to_string());
+    }
+    let model = sim_devices::bsim::build_bsim_params(&params, 49, false);
+    let mut currents = Vec::new();
+    for (celsius, vd) in sweep {
+        let id = (1.8 - vd) / 10.0;
+        let expected = sim_devices::bsim::evaluate_mos(&model, 10e-6, 200e-9, vd, 1.5, 0.0, 0.0, celsius + 273.15).ids;
+        assert!((id - expected).abs() < 1e-6 * expected, "T = {}: {} vs {}", celsius, id, expected);
+        currents.push(id);
+    }
+    // VSAT 下降, RDSW 与 UA 增大: 高温下电流明显减小
+    assert!(currents[1] < 0.9 * currents[0], "{:?}", currents);
+}
+
+#[test]
+fn mos_junction_voltage_drops_with_temperature() {
+    // 恒流正偏的漏结: IS 随温度急剧增大, 结压降约 -2mV/K
+    let sweep = temp_sweep(
+        "\
+* nmos held off, drain junction forward biased by a current source
+I1 d 0 1m
+M1 d 0 0 0 nm w=10u l=10u ad=100p pd=40u
+.model nm nmos level=49 vth0=2 is=1e-14
+.dc temp 27 127 100
+.end
+",
+        "d",
+    );
+    let (cold, hot) = (-sweep[0].1, -sweep[1].1);
+    let slope = (hot - cold) / 100.0;
+    assert!(slope < -1.5e-3 && slope > -2.5e-3, "dV/dT = {} V/K", slope);
+}
+
+#[test]
+fn ekv_diode_connected_mos_biased_in_weak_inversion() {
+    // 100nA 偏置的二极管接法 EKV 管: 工作在弱反型, 牛顿迭代收敛到 Id = I1
+    let run = run_first_analysis(
+        "\
+* ekv diode-connected nmos at low current
+I1 0 d 100n
+M1 d d 0 0 nm w=10u l=2u
+.model nm nmos level=44 vto=0.5 gamma=0.6 kp=150u ucrit=3meg
+.op
+.end
+",
+    );
+    let node = |name: &str| run.node_names.iter().position(|n| n == name).unwrap();
+    let vd = run.solution[node("d")];
+    assert!(vd > 0.2 && vd < 0.5, "vgs = {}", vd);
+    let mut params = HashMap::new();
+    for (k, v) in [("vto", "0.5"), ("gamma", "0.6"), ("kp", "150u"), ("ucrit", "3meg")] {
+        params.insert(k.to_string(), v.to_string());
+    }
+    let model = sim_devices::bsim::build_bsim_params(&params, 44, false);
+    let out = sim_devices::bsim::evaluate_mos(&model, 10e-6, 2e-6, vd, vd, 0.0, 0.0, 300.15);
+    assert!((out.ids - 100e-9).abs() < 1e-3 * 100e-9, "ids = {}", out.ids);
+}
@@ -0,0 +1,68 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::bypass::{BypassConfig, BypassStats};
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::result_store::{ResultStore, RunResult, RunStatus};
+
+// 只有电阻分压由脉冲驱动, 二极管与 MOS 反相器的偏置恒定, 一直处于空闲
+const CIRCUIT: &str = "* mostly idle\nV1 in 0 PULSE(0 2 2u 1n 1n 3u 10u)\nR3 in p 1k\nR4 p 0 1k\n\
+                       V2 bias 0 2\nR1 bias a 1k\nD1 a 0 dmod\n\
+                       VDD vdd 0 3\nVG g 0 1\nM1 out g 0 0 nmod w=1u l=1u\nR2 vdd out 10k\n\
+                       .model dmod d is=1e-14\n.model nmod nmos level=1 vto=0.5 kp=1e-4\n";
+
+fn run(options: &str, analysis: &str) -> (RunResult, BypassStats) {
+    let ast = parse_netlist(&format!("{}{}\n{}\n.end\n", CIRCUIT, options, analysis));
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let result = store.runs[run_id.0].clone();
+    assert!(matches!(result.status, RunStatus::Converged), "{:?}", result.message);
+    (result, engine.bypass_stats())
+}
+
+fn node(result: &RunResult, name: &str) -> usize {
+    result.node_names.iter().position(|n| n == name).unwrap()
+}
+
+#[test]
+fn bypass_options_are_opt_in() {
+    let options = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    assert_eq!(BypassConfig::from_options(&options(&[])), None);
+    assert_eq!(BypassConfig::from_options(&options(&[("bypass", "0")])), None);
+    assert_eq!(BypassConfig::from_options(&options(&[("bypass", "1")])), Some(BypassConfig { tol: 1e-6 }));
+    assert_eq!(
+        BypassConfig::from_options(&options(&[("bypass", "1"), ("bypasstol", "1u")])),
+        Some(BypassConfig { tol: 1e-6 })
+    );
+}
+
+#[test]
+fn bypass_reuses_stamps_without_changing_the_operating_point() {
+    let (reference, stats) = run("", ".op");
+    assert_eq!(stats, BypassStats::default());
+    let (bypassed, stats) = run(".options bypass", ".op");
+    assert!(stats.bypassed > 0, "{:?}", stats);
+    assert!(stats.evaluated > 0, "{:?}", stats);
+    for name in ["a", "out"] {
+        let (x, y) = (reference.solution[node(&reference, name)], bypassed.solution[node(&bypassed, name)]);
+        assert!((x - y).abs() < 1e-5, "{}: {} vs {}", name, x, y);
+    }
+}
+
+#[test]
+fn bypass_skips_idle_devices_in_transient() {
+    let (reference, _) = run("", ".tran 1u 20u");
+    let (bypassed, stats) = run(".options bypass", ".tran 1u 20u");
+    // 空闲的器件几乎每次都被旁路
+    assert!(stats.bypassed > stats.evaluated / 2, "{:?}", stats);
+    assert_eq!(reference.tran_times.len(), bypassed.tran_times.len());
+    for name in ["p", "a", "out"] {
+        let idx = node(&reference, name);
+        for (x, y) in reference.tran_solutions.iter().zip(&bypassed.tran_solutions) {
+            assert!((x[idx] - y[idx]).abs() < 1e-5, "{}: {} vs {}", name, x[idx], y[idx]);
+        }
+    }
+}
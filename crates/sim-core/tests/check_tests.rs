@@ -0,0 +1,76 @@
+use sim_core::check::check_netlist;
+use sim_core::diagnostic::{codes, Diagnostic, Severity};
+use sim_core::netlist::{elaborate_netlist, parse_netlist};
+
+fn check(netlist: &str) -> Vec<Diagnostic> {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0);
+    check_netlist(&ast, &elab)
+}
+
+fn codes_of(findings: &[Diagnostic]) -> Vec<&'static str> {
+    findings.iter().map(|diag| diag.code).collect()
+}
+
+#[test]
+fn check_clean_netlist_has_no_findings() {
+    let netlist = "* clean\n.param rval=2k\n.model dmod d is=1e-14\nV1 in 0 1\nR1 in out rval\nD1 out 0 dmod\nC1 out 0 1p\n.op\n.end\n";
+    let findings = check(netlist);
+    assert!(findings.is_empty(), "findings: {:?}", findings);
+}
+
+#[test]
+fn check_reports_connectivity_problems() {
+    // mid 只经过电容连到电路; tail 只有一个连接
+    let netlist = "* lint\nV1 in 0 1\nR1 in out 1k\nR2 out 0 1k\nC1 out mid 1p\nR3 mid tail 1k\n.end\n";
+    let findings = check(netlist);
+    assert!(findings.iter().all(|diag| diag.severity == Severity::Warning));
+
+    let floating: Vec<&str> = findings
+        .iter()
+        .filter(|diag| diag.code == codes::FLOATING_NODE)
+        .map(|diag| diag.message.as_str())
+        .collect();
+    assert_eq!(
+        floating,
+        vec!["node 'mid' has no DC path to ground", "node 'tail' has no DC path to ground"]
+    );
+    let single: Vec<&Diagnostic> = findings
+        .iter()
+        .filter(|diag| diag.code == codes::SINGLE_CONNECTION)
+        .collect();
+    assert_eq!(single.len(), 1);
+    assert_eq!(single[0].line, 6);
+    assert!(single[0].message.contains("'tail'"));
+}
+
+#[test]
+fn check_reports_values_names_and_unused_definitions() {
+    let netlist = "* lint\n.param used=1k unused=3\n.model dunused d is=1e-14\n.model nch.1 nmos vth0=0.4\n.subckt buf a y nc\nR1 a y used\n.ends\nV1 in 0 1\nX1 in out 0 buf\nR1 out 0 0\nR1 out 0 1k\nM1 out in 0 0 nch\n.end\n";
+    let findings = check(netlist);
+    let codes = codes_of(&findings);
+    assert!(codes.contains(&codes::ZERO_VALUE), "{:?}", findings);
+    assert!(codes.contains(&codes::DUPLICATE_INSTANCE), "{:?}", findings);
+
+    let messages: Vec<&str> = findings.iter().map(|diag| diag.message.as_str()).collect();
+    assert!(messages.contains(&"param 'unused' is never used"), "{:?}", messages);
+    assert!(!messages.iter().any(|m| m.contains("'used'")), "{:?}", messages);
+    assert!(messages.contains(&"model 'dunused' is never used"), "{:?}", messages);
+    // binning 模型通过基础名引用
+    assert!(!messages.iter().any(|m| m.contains("nch")), "{:?}", messages);
+    assert!(
+        messages.contains(&"port 'nc' of subckt 'buf' is not connected inside it"),
+        "{:?}",
+        messages
+    );
+}
+
+#[test]
+fn check_treats_led_optical_output_as_driven() {
+    // 光节点只出现在 lightout= / light= 参数中: LED 把它钉在相对地的光功率上
+    let netlist = "* opto\n.model led d eta=0.1\n.model pd d resp=0.5\nV1 a 0 5\nR1 a b 1k\nD1 b 0 led lightout=opt\nD2 0 c pd light=opt\nR2 c 0 1k\n.op\n.end\n";
+    let findings = check(netlist);
+    assert!(findings.is_empty(), "findings: {:?}", findings);
+}
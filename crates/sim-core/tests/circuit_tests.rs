@@ -1,4 +1,5 @@
-use sim_core::circuit::{Circuit, DeviceKind, Instance, Model};
+use sim_core::circuit::{Circuit, DeviceKind, Instance, Model, NodeNormalization, Signal};
+use sim_core::netlist::{build_circuit, build_circuit_with_nodes, elaborate_netlist, parse_netlist};
 use std::collections::HashMap;
 
 #[test]
@@ -26,9 +27,83 @@ fn circuit_tables_accept_entries() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     });
 
     assert_eq!(circuit.nodes.id_to_name.len(), 3);
     assert_eq!(circuit.models.models.len(), 1);
     assert_eq!(circuit.instances.instances.len(), 1);
 }
+
+#[test]
+fn node_table_normalizes_case_and_ground_aliases() {
+    let mut circuit = Circuit::new();
+    let out = circuit.nodes.ensure_node("OUT");
+    assert_eq!(circuit.nodes.ensure_node("out"), out);
+    assert_eq!(circuit.nodes.lookup("Out"), Some(out));
+    assert_eq!(circuit.nodes.ensure_node("GND"), circuit.nodes.gnd_id);
+    assert_eq!(circuit.nodes.ensure_node("vss!"), circuit.nodes.gnd_id);
+    assert_eq!(circuit.nodes.ensure_node("X1:0"), circuit.nodes.gnd_id);
+    assert_eq!(circuit.nodes.id_to_name, vec!["0".to_string(), "out".to_string()]);
+}
+
+#[test]
+fn build_circuit_maps_subckt_ground_to_global() {
+    let input = ".subckt load a\nR1 a 0 1k\nR2 a gnd 1k\n.ends\nV1 IN 0 1\nX1 in load\n.end\n";
+    let ast = parse_netlist(input);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    assert_eq!(circuit.nodes.id_to_name, vec!["0".to_string(), "in".to_string()]);
+}
+
+#[test]
+fn build_circuit_uses_custom_ground_aliases() {
+    let input = "V1 in VSS 1\nR1 in vss 1k\n.end\n";
+    let ast = parse_netlist(input);
+    let elab = elaborate_netlist(&ast);
+    let normalization = NodeNormalization {
+        case_insensitive: true,
+        ground_aliases: vec!["vss".to_string()],
+    };
+    let circuit = build_circuit_with_nodes(&ast, &elab, normalization);
+    assert_eq!(circuit.nodes.id_to_name, vec!["0".to_string(), "in".to_string()]);
+
+    let strict = NodeNormalization {
+        case_insensitive: false,
+        ground_aliases: Vec::new(),
+    };
+    let circuit = build_circuit_with_nodes(&ast, &elab, strict);
+    assert_eq!(circuit.nodes.id_to_name.len(), 4);
+}
+
+#[test]
+fn build_circuit_registers_hierarchical_node_names() {
+    let ast = parse_netlist(
+        "V1 in 0 1\nX1 in out stage\n.subckt inner p q\nR1 p n 1k\nR2 n q 1k\n.ends\n\
+         .subckt stage a b\nR1 a mid 1k\nX2 mid b inner\n.ends\nR3 out 0 1k\n",
+    );
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "errors: {:?}", elab.errors);
+    let circuit = build_circuit(&ast, &elab);
+    let nodes = &circuit.nodes;
+
+    // 内部节点: 层次名与展开后的名字指向同一节点
+    let mid = nodes.lookup("x1:mid").unwrap();
+    assert_eq!(nodes.lookup("x1.mid"), Some(mid));
+    assert_eq!(nodes.lookup("X1.MID"), Some(mid));
+    assert_eq!(nodes.lookup("x1.x2.n"), nodes.lookup("x1.x2:n"));
+    assert!(nodes.lookup("x1.x2.n").is_some());
+    assert_eq!(nodes.hierarchical_name(nodes.lookup("x1.x2.n").unwrap()).as_deref(), Some("x1.x2.n"));
+    // 端口: 层次名指向外部连接的节点
+    assert_eq!(nodes.lookup("x1.a"), nodes.lookup("in"));
+    assert_eq!(nodes.lookup("x1.x2.p"), Some(mid));
+    assert_eq!(nodes.lookup("x1.x2.q"), nodes.lookup("out"));
+
+    assert_eq!(circuit.resolve_signal("x1.mid"), Some(Signal::Node(mid)));
+    let Some(Signal::Device { instance, quantity }) = circuit.resolve_signal("x1.x2.r1:i") else {
+        panic!("x1.x2.r1:i should resolve to a device");
+    };
+    assert_eq!(circuit.instances.instances[instance.0].name, "X1.X2.R1");
+    assert_eq!(quantity, "i");
+    assert_eq!(circuit.resolve_signal("x9.r1:i"), None);
+}
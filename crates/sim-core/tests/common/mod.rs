@@ -0,0 +1,60 @@
+//! 集成测试共用的辅助函数; 每个测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::AnalysisCmd;
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist, NetlistAst};
+use sim_core::result_store::{ResultStore, RunResult, RunStatus};
+
+/// 解析并展开 `netlist` (不允许有错误) 后求工作点, 要求收敛
+pub fn run_op(netlist: &str) -> RunResult {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0);
+    let circuit = build_circuit(&ast, &elab);
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    let run = store.runs[run_id.0].clone();
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    run
+}
+
+/// 解析并展开 `netlist` (不允许有错误) 后跑其中的第一个分析, 要求收敛
+pub fn run_first_analysis(netlist: &str) -> RunResult {
+    run_first_analysis_ast(&parse_netlist(netlist))
+}
+
+/// 同 [`run_first_analysis`], 网表已经解析好
+pub fn run_first_analysis_ast(ast: &NetlistAst) -> RunResult {
+    let run = first_analysis(ast);
+    assert!(matches!(run.status, RunStatus::Converged), "run failed: {:?}", run);
+    run
+}
+
+/// 同 [`run_first_analysis`], 但不检查运行状态, 由用例自己判断
+pub fn run_first_analysis_unchecked(netlist: &str) -> RunResult {
+    first_analysis(&parse_netlist(netlist))
+}
+
+fn first_analysis(ast: &NetlistAst) -> RunResult {
+    assert!(ast.errors.is_empty(), "ast errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(ast);
+    assert_eq!(elab.error_count, 0, "elab errors: {:?}", elab.errors);
+    let circuit = build_circuit(ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    store.runs[run_id.0].clone()
+}
+
+/// 节点 `node` 在解向量中的下标
+pub fn node_index(run: &RunResult, node: &str) -> usize {
+    run.node_names
+        .iter()
+        .position(|name| name == node)
+        .unwrap_or_else(|| panic!("node {} not found in {:?}", node, run.node_names))
+}
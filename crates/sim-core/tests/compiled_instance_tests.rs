@@ -0,0 +1,70 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::Circuit;
+use sim_core::engine::Engine;
+use sim_core::mna::MnaBuilder;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::result_store::{ResultStore, RunStatus};
+use sim_core::stamp::{compile_instances, DeviceStamp, TransientState};
+
+fn circuit(netlist: &str) -> Circuit {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    build_circuit(&ast, &elaborate_netlist(&ast))
+}
+
+type Stamped = (Vec<i64>, Vec<i64>, Vec<f64>, Vec<f64>);
+
+fn finish(mut mna: MnaBuilder) -> Stamped {
+    let (ap, ai, ax) = mna.builder.finalize();
+    (ap, ai, ax, mna.rhs)
+}
+
+const DEVICES: &str = "* every compiled kind\n\
+    V1 in 0 2.5\nI1 0 b 1m m=2\nR1 in a 1k tc1=1e-3 temp=350\nR2 a 0 2k m=3\nC1 a 0 1p\nL1 a b 1u\n\
+    D1 b 0 dmod 2\nM1 d g 0 0 nm w=2u l=1u ad=1p pd=4u\nM2 d g 0 0 nm w=2u l=1u rth=100 cth=1n\n\
+    M3 d g 0 0 pm w=2u l=1u capmod=1\nVD d 0 1.8\nVG g 0 1.2\nE1 e 0 a 0 2\n\
+    .model dmod d is=1e-14 cjo=1p tt=1n\n.model nm nmos level=49 cj=1e-3\n.model pm pmos level=49\n.tran 1n 10n\n.end\n";
+
+#[test]
+fn compiled_stamps_match_instance_stamps() {
+    let circuit = circuit(DEVICES);
+    let instances = &circuit.instances.instances;
+    let compiled = compile_instances(instances);
+    let n = circuit.nodes.id_to_name.len();
+    let x: Vec<f64> = (0..n).map(|i| 0.1 * i as f64 + 0.05).collect();
+    let state = {
+        let mut state = TransientState::default();
+        sim_core::stamp::update_transient_state(instances, &x, &mut state);
+        state.start_history();
+        state
+    };
+
+    for (inst, device) in instances.iter().zip(&compiled) {
+        let (mut expected, mut actual) = (MnaBuilder::new(n), MnaBuilder::new(n));
+        inst.stamp_dc(&mut expected.context(), Some(&x)).unwrap();
+        device.stamp_dc(inst, &mut actual.context(), Some(&x)).unwrap();
+        assert_eq!(finish(actual), finish(expected), "dc stamp of {}", inst.name);
+
+        let (mut expected, mut actual) = (MnaBuilder::new(n), MnaBuilder::new(n));
+        let (mut state_expected, mut state_actual) = (state.clone(), state.clone());
+        inst.stamp_tran(&mut expected.context(), Some(&x), 1e-10, &mut state_expected).unwrap();
+        device.stamp_tran(inst, &mut actual.context(), Some(&x), 1e-10, &mut state_actual).unwrap();
+        assert_eq!(finish(actual), finish(expected), "tran stamp of {}", inst.name);
+    }
+}
+
+#[test]
+fn dc_sweep_recompiles_swept_values() {
+    let circuit = circuit("* divider sweep\nV1 in 0 1\nR1 in out 1k\nR2 out 0 3k\n.dc V1 0 4 1\n.end\n");
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let result = &store.runs[run_id.0];
+    assert!(matches!(result.status, RunStatus::Converged), "{:?}", result.message);
+    let out = result.node_names.iter().position(|n| n == "out").unwrap();
+    for (v, x) in result.sweep_values.iter().zip(&result.sweep_solutions) {
+        assert!((x[out] - 0.75 * v).abs() < 1e-9, "V1={} out={}", v, x[out]);
+    }
+    assert_eq!(result.sweep_values.len(), 5);
+}
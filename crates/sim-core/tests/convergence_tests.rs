@@ -0,0 +1,177 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::run_first_analysis_unchecked;
+use sim_core::circuit::Instance;
+use sim_core::complex_mna::ComplexStampContext;
+use sim_core::device_model::{register_device_model, DeviceModel};
+use sim_core::mna::StampContext;
+use sim_core::newton::{run_newton, NewtonConfig};
+use sim_core::result_store::{RunResult, RunStatus};
+use sim_core::solver::{create_solver, SolverType};
+use sim_core::stamp::{StampError, TransientState};
+
+/// 注入节点的电流随节点电压跳变: v < 0.5 时 1 mA, 否则为 0, 雅可比为 0.
+/// 与 1k 电阻并联时 Newton 在 0 V 与 1 V 之间来回跳 (|Δx| 不增大, 不触发阻尼), 永不收敛.
+/// `tran_only` 为真时直流下不注入电流, 只在瞬态步中翻转
+struct Flip {
+    name: &'static str,
+    tran_only: bool,
+}
+
+fn inject(inst: &Instance, ctx: &mut StampContext, x: Option<&[f64]>) {
+    let node = inst.nodes[0].0;
+    let v = x.and_then(|x| x.get(node)).copied().unwrap_or(0.0);
+    ctx.add_rhs(node, if v < 0.5 { 1e-3 } else { 0.0 });
+}
+
+impl DeviceModel for Flip {
+    fn model_type(&self) -> &str {
+        self.name
+    }
+
+    fn stamp_dc(&self, inst: &Instance, ctx: &mut StampContext, x: Option<&[f64]>) -> Result<(), StampError> {
+        if !self.tran_only {
+            inject(inst, ctx, x);
+        }
+        Ok(())
+    }
+
+    fn stamp_tran(
+        &self,
+        inst: &Instance,
+        ctx: &mut StampContext,
+        x: Option<&[f64]>,
+        _dt: f64,
+        _state: &mut TransientState,
+    ) -> Result<(), StampError> {
+        inject(inst, ctx, x);
+        Ok(())
+    }
+
+    fn stamp_ac(&self, _inst: &Instance, _ctx: &mut ComplexStampContext, _dc: &[f64]) -> Result<(), StampError> {
+        Ok(())
+    }
+}
+
+/// 注册翻转模型后跑网表中的第一个分析, 不检查运行状态
+fn run_with_flips(netlist: &str) -> RunResult {
+    register_device_model(Arc::new(Flip {
+        name: "test_flip",
+        tran_only: false,
+    }));
+    register_device_model(Arc::new(Flip {
+        name: "test_flip_tran",
+        tran_only: true,
+    }));
+    run_first_analysis_unchecked(netlist)
+}
+
+const FLIP: &str = "\
+V1 in 0 1
+R1 in 0 1k
+R2 n 0 1k
+A1 n 0 flip
+.options gminsteps=0 srcsteps=0 ptransteps=0
+";
+
+#[test]
+fn operating_point_failure_names_node_device_and_dx() {
+    let run = run_with_flips(&format!("* flip\n{}.model flip test_flip\n.op\n.end\n", FLIP));
+    assert!(matches!(run.status, RunStatus::MaxIters));
+    let report = run.convergence.as_ref().expect("convergence report");
+
+    // 最后线性化点 v(n) 为 0 V 或 1 V, 两种情况 KCL 失配都是 1 mA
+    let (node, residual) = report.worst_residual.clone().unwrap();
+    assert_eq!(node, "n");
+    assert!((residual - 1e-3).abs() < 1e-12, "residual={}", residual);
+    // 只有 A1 的右端项在两次迭代间跳变
+    let (device, change) = report.largest_stamp_change.clone().unwrap();
+    assert_eq!(device, "A1");
+    assert!((change - 1e-3).abs() < 1e-12, "change={}", change);
+
+    // 地节点不列出; 节点 n 每次跳 1 V, in 由 V1 固定
+    let dx: Vec<(&str, f64)> = report.last_dx.iter().map(|(name, dx)| (name.as_str(), *dx)).collect();
+    assert_eq!(dx.iter().map(|(name, _)| *name).collect::<Vec<_>>(), vec!["in", "n"]);
+    assert!(dx[0].1 < 1e-2 && (dx[1].1 - 1.0).abs() < 1e-2, "{:?}", dx);
+
+    let message = run.message.as_deref().unwrap();
+    assert!(message.contains("worst residual at node n"), "{}", message);
+    assert!(message.contains("largest stamp change in A1"), "{}", message);
+    assert!(message.contains("largest |dx| at node n"), "{}", message);
+}
+
+#[test]
+fn transient_failures_report_diagnostics() {
+    let run = run_with_flips(&format!("* flip\n{}.model flip test_flip\n.tran 1n 10n\n.end\n", FLIP));
+    assert!(matches!(run.status, RunStatus::Failed));
+    let message = run.message.as_deref().unwrap();
+    assert!(message.starts_with("DC operating point failed to converge: worst residual at node n"), "{}", message);
+    assert!(run.convergence.is_some());
+
+    // 工作点收敛, 第一个时间步在最小步长下仍不收敛
+    let run = run_with_flips(&format!("* flip\n{}.model flip test_flip_tran\n.tran 1n 10n\n.end\n", FLIP));
+    assert!(matches!(run.status, RunStatus::Failed));
+    let message = run.message.as_deref().unwrap();
+    assert!(message.starts_with("transient step failed to converge at t=0e0"), "{}", message);
+    let report = run.convergence.as_ref().unwrap();
+    assert_eq!(report.largest_stamp_change.as_ref().map(|(name, _)| name.as_str()), Some("A1"));
+}
+
+#[test]
+fn converged_runs_have_no_report() {
+    let run = run_with_flips("* ok\nV1 in 0 1\nR1 in out 1k\nR2 out 0 1k\n.op\n.end\n");
+    assert!(matches!(run.status, RunStatus::Converged));
+    assert!(run.convergence.is_none());
+    assert_eq!(run.message, None);
+}
+
+#[test]
+fn newton_diagnostics_track_residual_and_last_step() {
+    // 单个未知量: 电导 1, 右端项在 x = 0.5 处跳变
+    let build = |x: &[f64]| {
+        let b = if x[0] < 0.5 { 1.0 } else { 0.0 };
+        (vec![0, 1], vec![0], vec![1.0], vec![b], 1)
+    };
+    let config = NewtonConfig {
+        max_iters: 5,
+        ..NewtonConfig::default()
+    };
+    let mut solver = create_solver(SolverType::Dense, 1);
+    let mut x = vec![0.0];
+    let result = run_newton(&config, &mut x, build, solver.as_mut());
+    assert!(!result.converged);
+    let diagnostics = result.diagnostics.unwrap();
+    // 线性化点依次为 0, 1, 0, 1, 0
+    assert_eq!(diagnostics.x, vec![0.0]);
+    assert_eq!(diagnostics.previous_x, vec![1.0]);
+    assert_eq!(diagnostics.residual, vec![-1.0]);
+    assert_eq!(diagnostics.dx, vec![1.0]);
+
+    let mut x = vec![0.0];
+    let result = run_newton(&config, &mut x, |_x: &[f64]| (vec![0, 1], vec![0], vec![2.0], vec![1.0], 1), solver.as_mut());
+    assert!(result.converged);
+    assert!(result.diagnostics.is_none());
+}
+
+#[test]
+fn singular_matrix_names_floating_nodes_and_source_loops() {
+    let run = run_with_flips(
+        "* singular\nV1 in 0 1\nV2 in x 0\nE1 x 0 in 0 1\nR1 in 0 1k\nR2 a b 1k\nI1 c 0 1m\nC1 c 0 1p\n.op\n.end\n",
+    );
+    assert!(matches!(run.status, RunStatus::Failed));
+    let singular = run.convergence.as_ref().and_then(|report| report.singular.clone()).expect("singular report");
+    assert_eq!(singular.zero_diagonal_nodes, vec!["c".to_string()]);
+    assert_eq!(singular.floating_groups, vec![vec!["a".to_string(), "b".to_string()]]);
+    // 回路沿支路依次列出: x → in (V2) → 0 (V1), 由 E1 闭合
+    assert_eq!(singular.voltage_loops, vec![vec!["V2".to_string(), "V1".to_string(), "E1".to_string()]]);
+    let message = run.message.as_deref().unwrap();
+    assert!(message.contains("singular matrix: zero diagonal at node(s) c"), "{}", message);
+    assert!(message.contains("floating subcircuit (no path to ground) at node(s) a, b"), "{}", message);
+    assert!(message.contains("voltage source loop V2 -> V1 -> E1"), "{}", message);
+
+    // 不收敛但矩阵并不奇异时没有结构诊断
+    let run = run_with_flips(&format!("* flip\n{}.model flip test_flip\n.op\n.end\n", FLIP));
+    assert!(run.convergence.as_ref().unwrap().singular.is_none());
+}
@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+
+use sim_core::corner::run_corners;
+use sim_core::diagnostic::codes;
+use sim_core::netlist::{
+    build_circuit, elaborate_netlist, parse_netlist, parse_netlist_file, parse_netlist_file_decks_with_lib,
+    NetlistAst, NetlistFormat,
+};
+use sim_core::result_store::ResultStore;
+
+const MODELS: &str = "* process corners\n\
+                      .lib tt\n.param rbot=1k\n.lib 'models.lib' common\n.endl tt\n\
+                      .lib ff\n.param rbot=500\n.lib 'models.lib' common\n.endl ff\n\
+                      .lib ss\n.param rbot=2k\n.lib 'models.lib' common\n.endl\n\
+                      .lib common\n.model rmod r tc1=0.01\n.endl common\n";
+
+fn corner_test_dir(name: &str) -> PathBuf {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_corner_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    std::fs::write(dir.join("models.lib"), MODELS).unwrap();
+    dir
+}
+
+fn load(path: &Path, lib: Option<&str>) -> NetlistAst {
+    parse_netlist_file_decks_with_lib(path, &[], NetlistFormat::Spice, lib).swap_remove(0)
+}
+
+fn resistor_value(ast: &NetlistAst, name: &str) -> f64 {
+    let circuit = build_circuit(ast, &elaborate_netlist(ast));
+    let inst = circuit.instances.instances.iter().find(|inst| inst.name.eq_ignore_ascii_case(name)).unwrap();
+    inst.value.as_deref().unwrap().parse().unwrap()
+}
+
+#[test]
+fn lib_includes_only_the_selected_section() {
+    let dir = corner_test_dir("section");
+    let top = dir.join("top.cir");
+    std::fs::write(&top, ".lib 'models.lib' tt\nV1 in 0 1\nR1 in 0 {rbot} rmod\n.op\n.end\n").unwrap();
+
+    let ast = parse_netlist_file(&top);
+    assert!(ast.errors.is_empty(), "{:?}", ast.errors);
+    assert_eq!(resistor_value(&ast, "R1"), 1000.0);
+    // 段内再引用同一文件的另一段不算循环
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    assert!(circuit.models.name_to_id.contains_key("rmod"));
+
+    assert_eq!(resistor_value(&load(&top, Some("ff")), "R1"), 500.0);
+    assert_eq!(resistor_value(&load(&top, Some("SS")), "R1"), 2000.0);
+}
+
+#[test]
+fn lib_reports_missing_section() {
+    let dir = corner_test_dir("missing");
+    let top = dir.join("top.cir");
+    std::fs::write(&top, "V1 in 0 1\n.lib models.lib fs\nR1 in 0 1k\n.end\n").unwrap();
+    let ast = parse_netlist_file(&top);
+    assert_eq!(ast.errors.len(), 1, "{:?}", ast.errors);
+    assert_eq!(ast.errors[0].code, codes::LIB_SECTION_NOT_FOUND);
+    assert_eq!(ast.errors[0].line, 2);
+    assert!(ast.errors[0].message.contains("'fs'"), "{}", ast.errors[0].message);
+}
+
+#[test]
+fn corner_statements_are_parsed() {
+    let ast = parse_netlist(
+        ".param vnom=1.8\n.corner tt\n.corner ff lib=ff temp=-40 vdd='vnom*1.1'\nV1 a 0 1\n.end\n",
+    );
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    assert_eq!(circuit.corners.len(), 2);
+    assert_eq!(circuit.corners[0].name, "tt");
+    assert_eq!(circuit.corners[0].lib, None);
+    let ff = &circuit.corners[1];
+    assert_eq!(ff.lib.as_deref(), Some("ff"));
+    assert_eq!(ff.temp, Some(-40.0));
+    assert_eq!(ff.params.len(), 1);
+    assert_eq!(ff.params[0].0, "vdd");
+    assert!((ff.params[0].1 - 1.98).abs() < 1e-12);
+}
+
+#[test]
+fn corners_run_every_analysis_and_aggregate_output_params() {
+    let dir = corner_test_dir("run");
+    let top = dir.join("top.cir");
+    std::fs::write(
+        &top,
+        ".lib 'models.lib' tt\n.param vdd=1\nV1 in 0 vdd\nR1 in out 1k\nR2 out 0 {rbot} rmod\n\
+         .param vout=v(out)\n.op\n.dc V1 0 1 0.5\n\
+         .corner tt\n.corner ff lib=ff vdd=2\n.corner hot lib=tt temp=127\n.corner bad lib=fs\n.end\n",
+    )
+    .unwrap();
+    let ast = load(&top, None);
+    let corners = build_circuit(&ast, &elaborate_netlist(&ast)).corners;
+
+    let mut store = ResultStore::new();
+    let report = run_corners(&corners, |lib| load(&top, lib), &mut store);
+    assert_eq!(report.runs.len(), 4);
+    assert!(!report.passed());
+
+    let vout = |idx: usize| report.runs[idx].measurements[0].1.unwrap();
+    let tt = &report.runs[0];
+    assert!(tt.passed(), "{:?}", tt.failures);
+    assert_eq!(tt.run_ids.len(), 2);
+    // 输出参数取最后一个分析 (DC 扫描终点 V1 = 1)
+    assert!((vout(0) - 0.5).abs() < 1e-9, "{}", vout(0));
+    // ff: rbot = 500, DC 扫描终点仍为 1 V; 覆盖的 vdd 作用于 .op
+    assert!(report.runs[1].passed(), "{:?}", report.runs[1].failures);
+    let ff_op = &store.runs[report.runs[1].run_ids[0].0];
+    assert!((ff_op.output_params[0].1 - 2.0 / 3.0).abs() < 1e-9, "{:?}", ff_op.output_params);
+    assert!((vout(1) - 1.0 / 3.0).abs() < 1e-9, "{}", vout(1));
+    // 127 ℃: rbot = 1k·(1 + 0.01·100)
+    assert!((vout(2) - 2.0 / 3.0).abs() < 1e-6, "{}", vout(2));
+
+    let bad = &report.runs[3];
+    assert!(!bad.passed());
+    assert!(bad.run_ids.is_empty());
+    assert!(bad.failures[0].contains("'fs'"), "{:?}", bad.failures);
+    assert_eq!(bad.measurements, vec![("vout".to_string(), None)]);
+
+    let summary = report.measurements();
+    assert_eq!(summary.len(), 1);
+    assert_eq!(summary[0].name, "vout");
+    assert!((summary[0].min.unwrap() - 1.0 / 3.0).abs() < 1e-9);
+    assert!((summary[0].max.unwrap() - 2.0 / 3.0).abs() < 1e-6);
+    assert_eq!(summary[0].failed_corners, vec!["bad".to_string()]);
+}
+
+#[test]
+fn corner_fails_when_an_output_param_cannot_be_evaluated() {
+    let ast = parse_netlist("V1 in 0 1\nR1 in 0 1k\n.param vx=v(nowhere)\n.op\n.corner tt\n.end\n");
+    let corners = build_circuit(&ast, &elaborate_netlist(&ast)).corners;
+    let mut store = ResultStore::new();
+    let report = run_corners(&corners, |_| ast.clone(), &mut store);
+    let run = &report.runs[0];
+    assert!(!run.passed());
+    assert_eq!(run.measurements, vec![("vx".to_string(), None)]);
+    assert!(run.failures[0].contains("'vx'"), "{:?}", run.failures);
+    assert_eq!(report.measurements()[0].failed_corners, vec!["tt".to_string()]);
+}
+
+#[test]
+fn corner_checks_measurements_against_limits() {
+    let ast = parse_netlist(
+        "V1 in 0 1\nR1 in out 1k\nR2 out 0 {rbot}\n.param rbot=1k\n.dc V1 0 1 0.5\n\
+         .meas dc vmid FIND v(out) AT=1 min=0.4 max=0.6\n.meas dc vtop FIND v(out) AT=1\n\
+         .meas tran tpd TRIG v(in) VAL=0.5 RISE=1 TARG v(out) VAL=0.5 RISE=1\n\
+         .corner tt\n.corner weak rbot=3k\n.end\n",
+    );
+    let corners = build_circuit(&ast, &elaborate_netlist(&ast)).corners;
+    let mut store = ResultStore::new();
+    let report = run_corners(&corners, |_| ast.clone(), &mut store);
+
+    let tt = &report.runs[0];
+    let names: Vec<&str> = tt.checks.iter().map(|check| check.name.as_str()).collect();
+    assert_eq!(names, vec!["vmid", "vtop", "tpd"]);
+    assert!((tt.checks[0].value.unwrap() - 0.5).abs() < 1e-9);
+    assert!(tt.checks[0].passed() && tt.checks[1].passed());
+    // 网表里没有 .tran, tpd 求不出值
+    assert_eq!(tt.checks[2].value, None);
+    assert_eq!(tt.failures, vec!["measurement 'tpd' could not be evaluated".to_string()]);
+
+    let weak = &report.runs[1];
+    assert!((weak.checks[0].value.unwrap() - 0.75).abs() < 1e-9);
+    assert!(!weak.checks[0].passed());
+    assert!(weak.failures[0].contains("'vmid' = 7.5"), "{:?}", weak.failures);
+
+    let summary = report.measurements();
+    let vmid = summary.iter().find(|summary| summary.name == "vmid").unwrap();
+    assert!((vmid.max.unwrap() - 0.75).abs() < 1e-9);
+    let tpd = summary.iter().find(|summary| summary.name == "tpd").unwrap();
+    assert_eq!(tpd.failed_corners, vec!["tt".to_string(), "weak".to_string()]);
+}
@@ -4,6 +4,7 @@ use sim_core::analysis::AnalysisPlan;
 use sim_core::circuit::AnalysisCmd;
 use sim_core::engine::Engine;
 use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist_file};
+use sim_core::newton::DcStrategy;
 use sim_core::result_store::{ResultStore, RunStatus};
 
 #[test]
@@ -49,4 +50,9 @@ fn dc_basic_end_to_end() {
     let vout = run.solution[out_idx];
     assert!((vin - 1.0).abs() < 1e-6, "vin={}", vin);
     assert!((vout - (2.0 / 3.0)).abs() < 1e-6, "vout={}", vout);
+    // 直接 Newton 即收敛, 同伦轨迹只有一步
+    assert_eq!(run.homotopy.len(), 1);
+    assert!(run.homotopy[0].converged && run.homotopy[0].source_scale == 1.0);
+    assert_eq!(run.homotopy[0].iterations, run.iterations);
+    assert_eq!(run.dc_strategy(), Some(DcStrategy::Newton));
 }
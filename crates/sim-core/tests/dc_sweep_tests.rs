@@ -200,3 +200,301 @@ R1 in 0 1k
     assert_eq!(run.sweep_values.len(), 1, "single point sweep should have 1 point");
     assert!((run.sweep_values[0] - 1.0).abs() < 1e-9);
 }
+
+/// Test nested DC sweep: `.dc VDS ... VGS ...` produces one curve per VGS value
+#[test]
+fn dc_sweep_nested_family_of_curves() {
+    let netlist = r#"
+* NMOS output characteristics
+.model nch nmos level=1 vto=0.5 kp=100u
+VDS d 0 0
+VGS g 0 0
+M1 d g 0 0 nch w=1u l=1u
+.dc VDS 0 2 0.5 VGS 1 2 0.5
+.end
+"#;
+
+    let ast = sim_core::netlist::parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+
+    let cmd = circuit.analysis[0].clone();
+    match &cmd {
+        AnalysisCmd::DcNested { inner, outer } => {
+            assert_eq!(inner.source, "VDS");
+            assert_eq!(outer.source, "VGS");
+            assert!((inner.stop - 2.0).abs() < 1e-9);
+            assert!((outer.step - 0.5).abs() < 1e-9);
+        }
+        other => panic!("expected nested DC sweep, got {:?}", other),
+    }
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    assert_eq!(run.sweep_var.as_deref(), Some("VDS"));
+    assert_eq!(run.sweep_outer_var.as_deref(), Some("VGS"));
+    assert_eq!(run.sweep_values.len(), 15);
+    assert_eq!(run.sweep_outer_values.len(), 15);
+
+    let curves = run.sweep_curves();
+    assert_eq!(curves.len(), 3);
+    let outer: Vec<f64> = curves.iter().map(|(v, _)| *v).collect();
+    assert_eq!(outer, vec![1.0, 1.5, 2.0]);
+    for (_, range) in &curves {
+        assert_eq!(range.len(), 5);
+        assert!((run.sweep_values[range.start] - 0.0).abs() < 1e-12);
+        assert!((run.sweep_values[range.end - 1] - 2.0).abs() < 1e-12);
+    }
+
+    // Drain node follows VDS on every curve
+    let d = run.node_names.iter().position(|n| n == "d").unwrap();
+    for (point, solution) in run.sweep_solutions.iter().enumerate() {
+        assert!((solution[d] - run.sweep_values[point]).abs() < 1e-9);
+    }
+}
+
+/// Test `.dc param`: the swept `.param` is re-evaluated in dependent device values
+#[test]
+fn dc_sweep_over_param() {
+    let netlist = r#"
+* Param sweep
+.param rload=1k
+V1 in 0 1
+R1 in out 1k
+R2 out 0 rload
+.dc param rload 1k 3k 1k
+.end
+"#;
+
+    let ast = sim_core::netlist::parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    assert!(matches!(&cmd, AnalysisCmd::DcParam { param, .. } if param == "rload"));
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    assert_eq!(run.sweep_var.as_deref(), Some("rload"));
+    assert_eq!(run.sweep_values, vec![1e3, 2e3, 3e3]);
+    let out = run.node_names.iter().position(|n| n == "out").unwrap();
+    for (rload, solution) in run.sweep_values.iter().zip(&run.sweep_solutions) {
+        let expected = rload / (1e3 + rload);
+        assert!((solution[out] - expected).abs() < 1e-9);
+    }
+    // 扫描结束后器件恢复原值
+    let r2 = engine
+        .circuit
+        .instances
+        .instances
+        .iter()
+        .find(|inst| inst.name == "R2")
+        .unwrap();
+    assert_eq!(r2.value.as_deref(), Some("1000"));
+}
+
+/// Test sweeping by name: `@dev[param]` instance parameters, element values and `.param` names
+#[test]
+fn dc_sweep_over_instance_param_element_value_and_param_name() {
+    let netlist = r#"
+* Generic sweep targets
+.param rtop=1k
+V1 in 0 1
+R1 in out rtop
+R2 out 0 rpoly l=10u w=1u
+.model rpoly r rsh=100
+.dc @R2[l] 10u 30u 10u
+.end
+"#;
+
+    let ast = sim_core::netlist::parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    assert!(matches!(&cmd, AnalysisCmd::Dc { source, .. } if source == "@R2[l]"));
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let sweep = |source: &str, start: f64, stop: f64, step: f64| AnalysisPlan {
+        cmd: AnalysisCmd::Dc { source: source.to_string(), start, stop, step },
+    };
+    let out_of = |run: &sim_core::result_store::RunResult| {
+        let out = run.node_names.iter().position(|n| n == "out").unwrap();
+        run.sweep_solutions.iter().map(|solution| solution[out]).collect::<Vec<_>>()
+    };
+    let check = |actual: Vec<f64>, expected: &[f64]| {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-9, "{} vs {}", actual, expected);
+        }
+    };
+
+    // R2 = 100 * L / 1u
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    check(out_of(run), &[0.5, 2.0 / 3.0, 0.75]);
+
+    // 器件名扫描器件取值, 不是源的器件也可以
+    let run_id = engine.run_with_store(&sweep("R1", 1e3, 3e3, 1e3), &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    check(out_of(run), &[0.5, 1.0 / 3.0, 0.25]);
+
+    // 不是器件名时按 `.param` 扫描
+    let run_id = engine.run_with_store(&sweep("rtop", 3e3, 1e3, 2e3), &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    check(out_of(run), &[0.25, 0.5]);
+
+    for (source, message) in [("@R9[l]", "'R9'"), ("nothing", "'nothing'")] {
+        let run_id = engine.run_with_store(&sweep(source, 0.0, 1.0, 1.0), &mut store);
+        let run = &store.runs[run_id.0];
+        assert!(matches!(run.status, RunStatus::Failed));
+        assert!(run.message.as_deref().unwrap().contains(message), "{:?}", run.message);
+    }
+
+    // 扫描结束后器件恢复原值
+    let r2 = engine.circuit.instances.instances.iter().find(|inst| inst.name == "R2").unwrap();
+    let l: f64 = r2.params["l"].parse().unwrap();
+    assert!((l - 10e-6).abs() < 1e-15, "{}", l);
+}
+
+/// Test `.dc temp`: diode thermal voltage follows the swept temperature
+#[test]
+fn dc_sweep_over_temperature() {
+    let netlist = r#"
+* Temperature sweep
+V1 in 0 0.6
+R1 in a 1k
+D1 a 0 dmod
+.model dmod d is=1e-14 eg=0 xti=0
+.dc temp -40 125 55
+.end
+"#;
+
+    let ast = sim_core::netlist::parse_netlist(netlist);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    assert!(matches!(&cmd, AnalysisCmd::Dc { source, .. } if source == "temp"));
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    assert_eq!(run.sweep_values, vec![-40.0, 15.0, 70.0, 125.0]);
+    let a = run.node_names.iter().position(|n| n == "a").unwrap();
+    let mut previous = 0.0;
+    for (temp, solution) in run.sweep_values.iter().zip(&run.sweep_solutions) {
+        let va = solution[a];
+        // KCL: 电阻电流等于二极管电流 Is*(exp(Va/Vt)-1), Vt = kT/q (EG=0 XTI=0 使 Is 不随温度变化)
+        let vt = 8.617333262e-5 * (temp + 273.15);
+        let i_r = (0.6 - va) / 1e3;
+        let i_d = 1e-14 * ((va / vt).exp() - 1.0);
+        assert!((i_r - i_d).abs() < 1e-9, "T={} i_r={} i_d={}", temp, i_r, i_d);
+        // 固定 Is 时温度越高, 同样电压下二极管电流越小
+        assert!(va > previous);
+        previous = va;
+    }
+}
+
+/// Test `.dc temp` on resistors: TC1/TC2 and the RSH * L / W form
+#[test]
+fn dc_sweep_resistor_temperature_coefficients() {
+    let netlist = r#"
+* Resistor tempco divider
+V1 in 0 1
+R1 in out rpoly l=20u w=2u
+R2 out 0 1k tc1=-2e-3 tc2=1e-5
+.model rpoly r rsh=100 tc1=3e-3 tnom=27
+.dc temp 27 127 50
+.end
+"#;
+
+    let ast = sim_core::netlist::parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    let out = run.node_names.iter().position(|n| n == "out").unwrap();
+    for (temp, solution) in run.sweep_values.iter().zip(&run.sweep_solutions) {
+        // R1 = 100 * 20u / 2u = 1k, 两个电阻按各自的温度系数变化
+        let dt = temp - 27.0;
+        let r1 = 1e3 * (1.0 + 3e-3 * dt);
+        let r2 = 1e3 * (1.0 - 2e-3 * dt + 1e-5 * dt * dt);
+        let expected = r2 / (r1 + r2);
+        assert!((solution[out] - expected).abs() < 1e-9, "T={} out={} expected={}", temp, solution[out], expected);
+    }
+    assert!((run.sweep_solutions[0][out] - 0.5).abs() < 1e-9);
+}
+
+/// Test `.dc data=`: every row applies a source value and a `.param` together
+#[test]
+fn dc_sweep_over_data_table() {
+    let netlist = r#"
+* Data sweep
+.param rload=1k
+V1 in 0 1
+R1 in out 1k
+R2 out 0 rload
+.dc data=bias
+.data bias v1 rload
++ 1 1k
+2 3k
++ 3 1k
+.enddata
+.end
+"#;
+
+    let ast = sim_core::netlist::parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    let AnalysisCmd::DcData { table } = &cmd else {
+        panic!("expected DcData, got {:?}", cmd);
+    };
+    assert_eq!(table.columns, vec!["v1", "rload"]);
+    assert_eq!(table.rows.len(), 3);
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    assert_eq!(run.sweep_var.as_deref(), Some("v1"));
+    assert_eq!(run.sweep_values, vec![1.0, 2.0, 3.0]);
+    let out = run.node_names.iter().position(|n| n == "out").unwrap();
+    let expected = [0.5, 1.5, 1.5];
+    for (solution, expected) in run.sweep_solutions.iter().zip(expected) {
+        assert!((solution[out] - expected).abs() < 1e-9, "{} vs {}", solution[out], expected);
+    }
+}
+
+#[test]
+fn dc_data_table_parse_errors() {
+    let ast = sim_core::netlist::parse_netlist(".data bias v1 rload\n+ 1 1k 2\n.enddata\n.end\n");
+    assert_eq!(ast.errors.len(), 1);
+    let ast = sim_core::netlist::parse_netlist(".data bias v1\n+ 1\n.end\n");
+    assert_eq!(ast.errors.len(), 1);
+}
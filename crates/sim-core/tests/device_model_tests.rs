@@ -0,0 +1,204 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{node_index, run_first_analysis};
+use num_complex::Complex64;
+use sim_core::circuit::Instance;
+use sim_core::complex_mna::ComplexStampContext;
+use sim_core::device_model::{lookup_device_model, register_device_model, DeviceModel};
+use sim_core::mna::{MnaBuilder, StampContext};
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist, parse_number_with_suffix};
+use sim_core::stamp::{DeviceStamp, InstanceStamp, StampError, TransientState};
+use sim_core::state_table::StateKey;
+
+fn param(inst: &Instance, key: &str) -> Result<f64, StampError> {
+    inst.params
+        .get(key)
+        .and_then(|value| parse_number_with_suffix(value))
+        .ok_or(StampError::MissingValue)
+}
+
+/// 两端线性电导 g=
+struct Conductance;
+
+impl DeviceModel for Conductance {
+    fn model_type(&self) -> &str {
+        "test_conductance"
+    }
+
+    fn accepts_terminals(&self, count: usize) -> bool {
+        count == 2
+    }
+
+    fn stamp_dc(&self, inst: &Instance, ctx: &mut StampContext, _x: Option<&[f64]>) -> Result<(), StampError> {
+        let g = param(inst, "g")?;
+        let (a, b) = (inst.nodes[0].0, inst.nodes[1].0);
+        ctx.add(a, a, g);
+        ctx.add(b, b, g);
+        ctx.add(a, b, -g);
+        ctx.add(b, a, -g);
+        Ok(())
+    }
+
+    fn stamp_ac(&self, inst: &Instance, ctx: &mut ComplexStampContext, _dc: &[f64]) -> Result<(), StampError> {
+        let g = param(inst, "g")?;
+        let (a, b) = (inst.nodes[0].0, inst.nodes[1].0);
+        ctx.add_real(a, a, g);
+        ctx.add_real(b, b, g);
+        ctx.add_real(a, b, -g);
+        ctx.add_real(b, a, -g);
+        Ok(())
+    }
+}
+
+/// 理想电压源 v=, 支路电流是以实例名命名的辅助变量
+struct Source;
+
+impl DeviceModel for Source {
+    fn model_type(&self) -> &str {
+        "Test_Source"
+    }
+
+    fn aux_names(&self, inst: &Instance) -> Vec<String> {
+        vec![inst.name.clone()]
+    }
+
+    fn stamp_dc(&self, inst: &Instance, ctx: &mut StampContext, _x: Option<&[f64]>) -> Result<(), StampError> {
+        let (a, b) = (inst.nodes[0].0, inst.nodes[1].0);
+        let k = ctx.allocate_aux(&inst.name);
+        ctx.add(a, k, 1.0);
+        ctx.add(b, k, -1.0);
+        ctx.add(k, a, 1.0);
+        ctx.add(k, b, -1.0);
+        ctx.add_rhs(k, param(inst, "v")?);
+        Ok(())
+    }
+
+    fn stamp_ac(&self, inst: &Instance, ctx: &mut ComplexStampContext, _dc: &[f64]) -> Result<(), StampError> {
+        let (a, b) = (inst.nodes[0].0, inst.nodes[1].0);
+        let k = ctx.allocate_aux(&inst.name);
+        ctx.add_real(a, k, 1.0);
+        ctx.add_real(b, k, -1.0);
+        ctx.add_real(k, a, 1.0);
+        ctx.add_real(k, b, -1.0);
+        Ok(())
+    }
+}
+
+/// 后向欧拉电容 c=, 上一接受时间点的电压保存在 device_state
+struct Capacitor;
+
+impl DeviceModel for Capacitor {
+    fn model_type(&self) -> &str {
+        "test_capacitor"
+    }
+
+    fn stamp_dc(&self, _inst: &Instance, _ctx: &mut StampContext, _x: Option<&[f64]>) -> Result<(), StampError> {
+        Ok(())
+    }
+
+    fn stamp_tran(
+        &self,
+        inst: &Instance,
+        ctx: &mut StampContext,
+        _x: Option<&[f64]>,
+        dt: f64,
+        state: &mut TransientState,
+    ) -> Result<(), StampError> {
+        let (a, b) = (inst.nodes[0].0, inst.nodes[1].0);
+        let geq = param(inst, "c")? / dt;
+        let v_prev = state.device_state.get(StateKey::of(inst)).map_or(0.0, |history| history[0]);
+        ctx.add(a, a, geq);
+        ctx.add(b, b, geq);
+        ctx.add(a, b, -geq);
+        ctx.add(b, a, -geq);
+        ctx.add_rhs(a, geq * v_prev);
+        ctx.add_rhs(b, -geq * v_prev);
+        Ok(())
+    }
+
+    fn accept_step(&self, inst: &Instance, x: &[f64], state: &mut TransientState) {
+        let v = x[inst.nodes[0].0] - x[inst.nodes[1].0];
+        state.device_state.insert(StateKey::of(inst), vec![v]);
+    }
+
+    fn stamp_ac(&self, inst: &Instance, ctx: &mut ComplexStampContext, _dc: &[f64]) -> Result<(), StampError> {
+        let y = Complex64::new(0.0, ctx.omega * param(inst, "c")?);
+        let (a, b) = (inst.nodes[0].0, inst.nodes[1].0);
+        ctx.add(a, a, y);
+        ctx.add(b, b, y);
+        ctx.add(a, b, -y);
+        ctx.add(b, a, -y);
+        Ok(())
+    }
+}
+
+fn register_test_models() {
+    register_device_model(Arc::new(Conductance));
+    register_device_model(Arc::new(Source));
+    register_device_model(Arc::new(Capacitor));
+}
+
+const DIVIDER: &str = "\
+A1 in 0 src
+A2 in out g1
+R1 out 0 1k
+A3 out 0 cap
+.model src test_source v=2
+.model g1 test_conductance g=1m
+.model cap test_capacitor c=1n
+";
+
+#[test]
+fn registered_models_are_found_case_insensitively() {
+    register_test_models();
+    assert!(lookup_device_model("TEST_CONDUCTANCE").is_some());
+    assert_eq!(lookup_device_model("test_source").unwrap().model_type(), "Test_Source");
+    assert!(lookup_device_model("test_missing").is_none());
+}
+
+#[test]
+fn plugin_devices_stamp_operating_point_and_transient() {
+    register_test_models();
+    let run = run_first_analysis(&format!("{}.op\n.end\n", DIVIDER));
+    assert!((run.solution[node_index(&run, "out")] - 1.0).abs() < 1e-9);
+    // 插件声明的辅助变量即支路电流
+    assert_eq!(run.branch_names, vec!["A1"]);
+    let current = run.branch_current("A1", &run.solution).unwrap();
+    assert!((current + 1e-3).abs() < 1e-12, "I(A1) = {}", current);
+
+    // 从工作点出发, 电容保持稳态
+    let run = run_first_analysis(&format!("{}.tran 1n 10n\n.end\n", DIVIDER));
+    let out = node_index(&run, "out");
+    assert!(run.tran_solutions.len() > 1);
+    for x in &run.tran_solutions {
+        assert!((x[out] - 1.0).abs() < 1e-9, "v(out) = {}", x[out]);
+    }
+}
+
+#[test]
+fn plugin_devices_stamp_small_signal() {
+    register_test_models();
+    // 1k 与 1n 的低通: 转折频率处 -3 dB, -45 度
+    let fc = 1.0 / (2.0 * std::f64::consts::PI * 1e-6);
+    let netlist = format!(
+        "V1 in 0 DC 0 AC 1\nA2 in out g1\nA3 out 0 cap\n.model g1 test_conductance g=1m\n.model cap test_capacitor c=1n\n.ac lin 1 {} {}\n.end\n",
+        fc, fc
+    );
+    let run = run_first_analysis(&netlist);
+    let (mag_db, phase) = run.ac_solutions[0][node_index(&run, "out")];
+    assert!((mag_db + 10.0 * 2f64.log10()).abs() < 1e-6, "|v(out)| = {} dB", mag_db);
+    assert!((phase + 45.0).abs() < 1e-6, "phase = {}", phase);
+}
+
+#[test]
+fn plugin_rejects_wrong_terminal_count() {
+    register_test_models();
+    let ast = parse_netlist("V1 a 0 1\nA1 a b 0 g1\n.model g1 test_conductance g=1m\n.op\n.end\n");
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let instance = circuit.instances.instances.iter().find(|inst| inst.name == "A1").unwrap().clone();
+    let mut mna = MnaBuilder::new(circuit.nodes.id_to_name.len());
+    let result = InstanceStamp { instance }.stamp_dc(&mut mna.context(), None);
+    assert!(matches!(result, Err(StampError::InvalidNodes)), "{:?}", result);
+}
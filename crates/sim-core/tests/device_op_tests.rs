@@ -0,0 +1,73 @@
+mod common;
+
+use common::run_first_analysis;
+use sim_core::result_store::{DeviceOpKind, MosRegion};
+
+fn mos_netlist(vg: f64, vd: f64) -> String {
+    format!(
+        "\
+VG g 0 {}
+VD d 0 {}
+M1 d g 0 0 nch w=1u l=1u
+.model nch nmos level=1 vto=0.5 kp=100u
+.op
+.end
+",
+        vg, vd
+    )
+}
+
+#[test]
+fn mosfet_operating_point_reports_current_conductances_and_region() {
+    let run = run_first_analysis(&mos_netlist(1.0, 1.8));
+    let info = run.device_op("m1").expect("M1 op info");
+    assert_eq!(info.kind_name(), "mosfet");
+    assert_eq!(info.region(), Some(MosRegion::Saturation));
+    let DeviceOpKind::Mosfet { ids, gm, gds, vth, .. } = info.kind else { unreachable!() };
+    // 漏极电流由 VD 提供 (另有 gmin 量级的结漏电)
+    let i_vd = run.branch_current("VD", &run.solution).unwrap();
+    assert!((ids + i_vd).abs() < 1e-10, "ids = {}, I(VD) = {}", ids, i_vd);
+    assert!((vth - 0.5).abs() < 1e-9, "vth = {}", vth);
+    // 平方律: gm = 2 Ids / (Vgs - Vth)
+    assert!((gm - 2.0 * ids / 0.5).abs() < 1e-3 * gm, "gm = {}, ids = {}", gm, ids);
+    assert!(gds >= 0.0 && gds < gm);
+
+    let linear = run_first_analysis(&mos_netlist(1.0, 0.1));
+    assert_eq!(linear.device_op("M1").unwrap().region(), Some(MosRegion::Linear));
+    let cutoff = run_first_analysis(&mos_netlist(0.0, 1.8));
+    assert_eq!(cutoff.device_op("M1").unwrap().region(), Some(MosRegion::Cutoff));
+}
+
+#[test]
+fn diode_operating_point_matches_series_current() {
+    let run = run_first_analysis(
+        "\
+V1 a 0 1
+R1 a d 1k
+D1 d 0 dmod
+R2 a 0 1k
+.model dmod d is=1e-14
+.op
+.end
+",
+    );
+    // 只列出非线性器件
+    let names: Vec<&str> = run.device_op.iter().map(|info| info.name.as_str()).collect();
+    assert_eq!(names, vec!["D1"]);
+    let info = run.device_op("D1").unwrap();
+    assert_eq!(info.region(), None);
+    let values = info.values();
+    assert_eq!(values.iter().map(|(key, _)| *key).collect::<Vec<_>>(), vec!["id", "gd"]);
+    let (id, gd) = (values[0].1, values[1].1);
+    let vd = run.solution[run.node_names.iter().position(|name| name == "d").unwrap()];
+    assert!((id - (1.0 - vd) / 1e3).abs() < 1e-9, "id = {}, vd = {}", id, vd);
+    // 指数律: gd ≈ Id / Vt
+    let vt = id / gd;
+    assert!(vt > 0.025 && vt < 0.027, "Id / gd = {}", vt);
+}
+
+#[test]
+fn device_op_is_only_filled_for_operating_point() {
+    let run = run_first_analysis("V1 a 0 0\nR1 a d 1k\nD1 d 0 dmod\n.model dmod d\n.dc V1 0 1 0.5\n.end\n");
+    assert!(run.device_op.is_empty());
+}
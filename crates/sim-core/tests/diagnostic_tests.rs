@@ -0,0 +1,97 @@
+use sim_core::diagnostic::{codes, Severity};
+use sim_core::netlist::{elaborate_netlist, parse_netlist, parse_netlist_file};
+
+#[test]
+fn diagnostic_marks_unknown_device_token() {
+    let ast = parse_netlist("* diag\nV1 in 0 1\n  Y1 c b e ymod\n.end\n");
+    assert_eq!(ast.errors.len(), 1, "errors: {:?}", ast.errors);
+    let diag = &ast.errors[0];
+    assert_eq!(diag.code, codes::UNKNOWN_DEVICE);
+    assert_eq!(diag.severity, Severity::Error);
+    assert_eq!(diag.line, 3);
+    // 列号计入行首缩进
+    assert_eq!(diag.columns, Some(3..5));
+    assert_eq!(diag.message, "unknown device type 'Y1'");
+
+    let rendered = diag.render();
+    assert_eq!(
+        rendered,
+        "error[E0101]: unknown device type 'Y1'\n --> <netlist>:3:3\n  |\n3 |   Y1 c b e ymod\n  |   ^^"
+    );
+}
+
+#[test]
+fn diagnostic_reports_first_line_of_continued_statement() {
+    let ast = parse_netlist("* diag\nR1 in\n+ out\n+ extra 1k\nV1 in 0 1\n.end\n");
+    assert_eq!(ast.errors.len(), 1, "errors: {:?}", ast.errors);
+    let diag = &ast.errors[0];
+    assert_eq!(diag.line, 2);
+    assert_eq!(diag.source_line.as_deref(), Some("R1 in"));
+    assert!(diag.to_string().starts_with("<netlist>:2: error[E0105]: R1 has extra fields"), "{}", diag);
+}
+
+#[test]
+fn diagnostic_locates_errors_inside_included_file() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_diag_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("lib.cir"), "* lib\nR2 out 0 2k\nD1 out\nX9 out 0 missing\n").unwrap();
+    let top = dir.join("top.cir");
+    std::fs::write(&top, "* top\nV1 in 0 1\n.include lib.cir\nR1 in out 1k\n.end\n").unwrap();
+
+    let ast = parse_netlist_file(&top);
+    assert_eq!(ast.errors.len(), 2, "errors: {:?}", ast.errors);
+    let parse = &ast.errors[0];
+    assert_eq!(parse.file.as_deref(), Some(dir.join("lib.cir").as_path()));
+    assert_eq!(parse.line, 3);
+    assert_eq!(parse.code, codes::NODE_COUNT);
+
+    let elab = elaborate_netlist(&ast);
+    let undefined = elab
+        .errors
+        .iter()
+        .find(|diag| diag.code == codes::UNDEFINED_SUBCKT)
+        .expect("undefined subckt diagnostic");
+    assert_eq!(undefined.file.as_deref(), Some(dir.join("lib.cir").as_path()));
+    assert_eq!(undefined.line, 4);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn diagnostic_renders_expression_errors_in_english() {
+    let ast = parse_netlist("* diag\nV1 in 0 1\nB1 out 0 V={foo(V(in))}\nB2 out 0 I=2*(V(in)\n.end\n");
+    assert_eq!(ast.errors.len(), 2, "errors: {:?}", ast.errors);
+    assert!(ast.errors.iter().all(|diag| diag.code == codes::INVALID_EXPRESSION));
+
+    let first = ast.errors[0].render();
+    assert_eq!(
+        first.lines().next(),
+        Some("error[E0107]: B1 has an invalid expression: unknown function `foo`")
+    );
+    let second = ast.errors[1].render();
+    assert_eq!(
+        second.lines().next(),
+        Some("error[E0107]: B2 has an invalid expression: expected `)`")
+    );
+}
+
+#[test]
+fn diagnostic_locates_unresolved_branch_references() {
+    let netlist = "* diag\nV1 in 0 1\nR1 in out 1k\nB1 out 0 V={I(R1)*2}\nB2 x 0 I={i( vmissing )}\nR2 x 0 1k\n.end\n";
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.errors.len(), 2, "errors: {:?}", elab.errors);
+    assert!(elab.errors.iter().all(|diag| diag.code == codes::UNKNOWN_BRANCH));
+
+    let resistor = &elab.errors[0];
+    assert_eq!(resistor.line, 4);
+    assert_eq!(resistor.message, "B1 references I(R1): R1 has no branch current");
+    assert_eq!(resistor.columns, Some(15..17));
+    let missing = &elab.errors[1];
+    assert_eq!(missing.line, 5);
+    assert_eq!(missing.message, "B2 references I(vmissing): no element named 'vmissing'");
+    assert_eq!(missing.columns, Some(14..22));
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use common::{node_index, run_first_analysis};
+use sim_core::diagnostic::codes;
+use sim_core::netlist::{parse_netlist, DeviceKind, Stmt};
+use sim_core::result_store::RunResult;
+
+/// 节点上升沿时刻 (跳变前最后一个时间点即事件时刻)
+fn rising_edges(run: &RunResult, node: &str) -> Vec<f64> {
+    let idx = node_index(run, node);
+    run.tran_solutions
+        .windows(2)
+        .zip(&run.tran_times)
+        .filter(|(pair, _)| pair[0][idx] < 0.5 && pair[1][idx] >= 0.5)
+        .map(|(_, time)| *time)
+        .collect()
+}
+
+#[test]
+fn digital_instance_parses_bracketed_inputs() {
+    let ast = parse_netlist("A1 [a b] y nand2\nA2 y\n.model nand2 d_nand\n");
+    let device = ast
+        .statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Device(device) if device.name == "A1" => Some(device),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(device.kind, DeviceKind::A));
+    assert_eq!(device.nodes, vec!["a", "b", "y"]);
+    assert_eq!(device.model.as_deref(), Some("nand2"));
+
+    let errors: Vec<(usize, &str)> = ast.errors.iter().map(|diag| (diag.line, diag.code)).collect();
+    assert_eq!(errors, vec![(2, codes::NODE_COUNT), (2, codes::MISSING_REFERENCE)]);
+}
+
+#[test]
+fn nand_gate_evaluates_at_operating_point() {
+    let run = run_first_analysis(
+        "\
+V1 a 0 1
+V2 b 0 0
+A1 [a b] y nand2
+A2 [a a] z nand2
+R1 y 0 1k
+.model nand2 d_nand
+.op
+.end
+",
+    );
+    assert_eq!(run.solution[node_index(&run, "y")], 1.0);
+    assert_eq!(run.solution[node_index(&run, "z")], 0.0);
+}
+
+#[test]
+fn inverter_ring_oscillates_with_six_gate_delays() {
+    let run = run_first_analysis(
+        "\
+* 三级反相器环振
+A1 a b inv
+A2 b c inv
+A3 c a inv
+.model inv d_inverter rise_delay=1n fall_delay=1n
+.ic v(a)=1
+.tran 0.1n 30n uic
+.end
+",
+    );
+    let edges = rising_edges(&run, "a");
+    assert!(edges.len() >= 4, "edges = {:?}", edges);
+    for pair in edges.windows(2) {
+        assert!((pair[1] - pair[0] - 6e-9).abs() < 1e-15, "edges = {:?}", edges);
+    }
+}
+
+#[test]
+fn dff_divides_clock_by_two() {
+    let run = run_first_analysis(
+        "\
+* 环振作时钟, 触发器 D 接反相输出
+A1 clk b inv
+A2 b c inv
+A3 c clk inv
+A4 d clk q dff
+A5 q d inv
+.model inv d_inverter rise_delay=1n fall_delay=1n
+.model dff d_dff rise_delay=0.5n fall_delay=0.5n ic=0
+.ic v(clk)=1
+.tran 0.1n 60n uic
+.end
+",
+    );
+    let clk = rising_edges(&run, "clk");
+    let q = rising_edges(&run, "q");
+    assert!(clk.len() >= 6, "clk = {:?}", clk);
+    assert_eq!(q.len(), clk.len().div_ceil(2), "q = {:?}, clk = {:?}", q, clk);
+    for pair in q.windows(2) {
+        assert!((pair[1] - pair[0] - 12e-9).abs() < 1e-15, "q = {:?}", q);
+    }
+}
+
+#[test]
+fn adc_and_dac_bridges_close_a_mixed_signal_loop() {
+    // DAC 经分压送回 ADC, 反相后再驱动 DAC: 半周期为 ADC 与反相器延迟之和
+    let run = run_first_analysis(
+        "\
+A1 mid level adc
+A2 level nlevel inv
+A3 nlevel drive dac
+R1 drive mid 1k
+R2 mid 0 1k
+.model adc adc_bridge in_low=0.5 in_high=1 rise_delay=1n fall_delay=1n
+.model inv d_inverter rise_delay=1n fall_delay=1n
+.model dac dac_bridge out_low=0 out_high=3
+.tran 0.1n 30n uic
+.end
+",
+    );
+    let mid = node_index(&run, "mid");
+    for x in &run.tran_solutions {
+        assert!(x[mid] == 0.0 || (x[mid] - 1.5).abs() < 1e-12, "v(mid) = {}", x[mid]);
+    }
+    // 模拟跳变要到事件后的下一个时间点才被 ADC 看到, 每次最多晚一步
+    let edges = rising_edges(&run, "level");
+    assert!(edges.len() >= 4, "edges = {:?}", edges);
+    for pair in edges.windows(2) {
+        let period = pair[1] - pair[0];
+        assert!(period > 4e-9 && period < 4.25e-9, "edges = {:?}", edges);
+    }
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{node_index, run_first_analysis};
+use sim_core::circuit::{DeviceKind as CircuitDeviceKind, Instance, NodeId};
+use sim_core::mna::MnaBuilder;
+use sim_core::netlist::{parse_netlist, Stmt};
+use sim_core::stamp::{DeviceStamp, InstanceStamp, TransientState};
+use sim_core::state_table::StateKey;
+
+#[test]
+fn diode_area_scales_saturation_current() {
+    let ast = parse_netlist("D1 a 0 dmod 2\n.model dmod d\n");
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let area = ast.statements.iter().find_map(|stmt| match stmt {
+        Stmt::Device(device) => device.value.clone(),
+        _ => None,
+    });
+    assert_eq!(area.as_deref(), Some("2"));
+
+    let run = run_first_analysis(
+        "\
+* forward-biased diode with area factor 2
+V1 in 0 5
+R1 in a 1k
+D1 a 0 dmod 2
+.model dmod d is=1e-14
+.op
+.end
+",
+    );
+    let va = run.solution[node_index(&run, "a")];
+    let i_r = (5.0 - va) / 1e3;
+    let i_d = 2e-14 * ((va / 0.02585).exp() - 1.0);
+    assert!((i_r - i_d).abs() < 1e-9 * i_r, "i_r = {}, i_d = {}", i_r, i_d);
+}
+
+#[test]
+fn diode_series_resistance_uses_internal_anode() {
+    let run = run_first_analysis(
+        "\
+* high-current diode: RS drops part of the applied voltage
+V1 in 0 5
+R1 in a 100
+D1 a 0 dmod
+.model dmod d is=1e-14 rs=20
+.op
+.end
+",
+    );
+    let va = run.solution[node_index(&run, "a")];
+    let vai = run.solution[node_index(&run, "d1#a")];
+    let i = (5.0 - va) / 100.0;
+    assert!(((va - vai) - 20.0 * i).abs() < 1e-9, "va - va' = {}", va - vai);
+    let i_d = 1e-14 * ((vai / 0.02585).exp() - 1.0);
+    assert!((i - i_d).abs() < 1e-9 * i, "i = {}, i_d = {}", i, i_d);
+    // RS=0 时不创建本征节点
+    let run = run_first_analysis("V1 a 0 0.6\nD1 a 0 dmod\n.model dmod d\n.op\n.end\n");
+    assert!(!run.node_names.iter().any(|name| name.contains('#')), "{:?}", run.node_names);
+}
+
+#[test]
+fn zener_regulator_converges_in_breakdown() {
+    let netlist = |analysis: &str| {
+        format!(
+            "\
+* zener shunt regulator: 15V supply, 1k feed resistor, 5.1V zener
+V1 in 0 15
+R1 in k 1k
+RL k 0 10k
+D1 0 k dz
+.model dz d bv=5.1 ibv=1m
+{}
+.end
+",
+            analysis
+        )
+    };
+    let run = run_first_analysis(&netlist(".op"));
+    let vk = run.solution[node_index(&run, "k")];
+    let iz = (15.0 - vk) / 1e3 - vk / 1e4;
+    // 齐纳电流约 9mA, 击穿电压只比 BV 高约 Vt * ln(IZ / IBV)
+    assert!(iz > 8e-3, "iz = {}", iz);
+    let expected = 5.1 + 0.02585 * (iz / 1e-3).ln();
+    assert!((vk - expected).abs() < 1e-3, "vk = {}, expected {}", vk, expected);
+
+    // 瞬态从同一工作点出发并保持稳压
+    let run = run_first_analysis(&netlist(".tran 1u 5u"));
+    let k = node_index(&run, "k");
+    assert!(run.tran_solutions.iter().all(|x| (x[k] - vk).abs() < 1e-6));
+}
+
+#[test]
+fn diode_ac_includes_junction_capacitance() {
+    let run = run_first_analysis(
+        "\
+* zero-biased diode: CJO forms an RC pole with R1
+V1 in 0 DC 0 AC 1
+R1 in a 1k
+D1 a 0 dmod
+.model dmod d cjo=1n
+.ac lin 1 159154.943 159154.943
+.end
+",
+    );
+    let (db, phase) = run.ac_solutions[0][node_index(&run, "a")];
+    assert!((db + 3.0103).abs() < 1e-3, "|Va| = {} dB", db);
+    assert!((phase + 45.0).abs() < 0.01, "phase = {}", phase);
+}
+
+#[test]
+fn diode_tran_stamp_adds_diffusion_charge() {
+    let mut params = HashMap::new();
+    params.insert("tt".to_string(), "1n".to_string());
+    let stamp = InstanceStamp {
+        instance: Instance {
+            name: "D1".to_string(),
+            kind: CircuitDeviceKind::D,
+            nodes: vec![NodeId(1), NodeId(0)],
+            model: None,
+            params,
+            value: None,
+            control: None,
+            ac_mag: None,
+            ac_phase: None,
+            state_slot: 0,
+        },
+    };
+    let x = vec![0.0, 0.6];
+    let dt = 1e-9;
+    let vt = 0.02585;
+
+    let mut dc = MnaBuilder::new(2);
+    stamp.stamp_dc(&mut dc.context(), Some(&x)).unwrap();
+
+    let mut tran = MnaBuilder::new(2);
+    let mut state = TransientState::default();
+    state.cap_voltage.insert(StateKey::of(&stamp.instance), 0.5);
+    stamp.stamp_tran(&mut tran.context(), Some(&x), dt, &mut state).unwrap();
+
+    // 扩散电荷 Q = TT * Id; 伴随模型电流 (Q - Q_prev)/dt - C/dt * Vd 注入阳极的负向
+    let id = |v: f64| 1e-14 * ((v / vt).exp() - 1.0);
+    let gd = 1e-14 / vt * (0.6f64 / vt).exp();
+    let ieq = 1e-9 * (id(0.6) - id(0.5)) / dt - 1e-9 * gd / dt * 0.6;
+    let delta = tran.rhs[1] - dc.rhs[1];
+    assert!((delta + ieq).abs() < 1e-9 * ieq.abs(), "delta = {}, expected {}", delta, -ieq);
+}
+
+#[test]
+fn varactor_capacitance_tracks_reverse_bias() {
+    // 反偏 VR 下 C = CJO / sqrt(1 + VR/VJ), 与 R1 形成 -3dB 极点
+    let pole = |vr: f64| {
+        let c = 10e-12 / (1.0 + vr / 0.5).sqrt();
+        1.0 / (2.0 * std::f64::consts::PI * 1e3 * c)
+    };
+    for vr in [0.0, 1.5, 4.5] {
+        let run = run_first_analysis(&format!(
+            "\
+* varactor biased through the same resistor that sets the pole
+V1 in 0 DC {} AC 1
+R1 in k 1k
+D1 0 k cv
+.model cv varactor cjo=10p vj=0.5 m=0.5
+.ac lin 1 {} {}
+.end
+",
+            vr,
+            pole(vr),
+            pole(vr)
+        ));
+        let (db, _) = run.ac_solutions[0][node_index(&run, "k")];
+        assert!((db + 3.0103).abs() < 1e-3, "vr = {}: |Vk| = {} dB", vr, db);
+    }
+}
@@ -0,0 +1,64 @@
+use sim_core::integrator::{integrator_from_option, BackwardEuler, Gear2, History, Integrator, Trapezoidal};
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-12, "actual = {}, expected = {}", actual, expected);
+}
+
+/// x_n = 2, 由 x_{n-1} = 1 经步长 h_n 得到
+fn history_after_step(integrator: &dyn Integrator, h_prev: f64) -> History {
+    integrator.advance(h_prev, &History::start(1.0), 2.0)
+}
+
+#[test]
+fn first_step_falls_back_to_backward_euler() {
+    let start = History::start(3.0);
+    for integrator in [&BackwardEuler as &dyn Integrator, &Trapezoidal, &Gear2] {
+        let companion = integrator.companion(0.1, &start);
+        assert_close(companion.step, 0.1);
+        assert_close(companion.history, 3.0);
+    }
+}
+
+#[test]
+fn advance_records_derivative_and_previous_value() {
+    let history = history_after_step(&BackwardEuler, 0.5);
+    assert_close(history.value, 2.0);
+    assert_close(history.derivative, 2.0);
+    assert_eq!(history.older, Some(1.0));
+    assert_eq!(history.last_dt, Some(0.5));
+}
+
+#[test]
+fn trapezoidal_uses_half_step_and_derivative() {
+    let history = history_after_step(&Trapezoidal, 0.5);
+    let companion = Trapezoidal.companion(0.2, &history);
+    assert_close(companion.step, 0.1);
+    // x_n + h/2·ẋ_n, 首步按后向欧拉得到 ẋ_n = (2 - 1) / 0.5
+    assert_close(companion.history, 2.0 + 0.1 * 2.0);
+}
+
+#[test]
+fn gear2_constant_step_coefficients() {
+    let history = history_after_step(&Gear2, 0.3);
+    let companion = Gear2.companion(0.3, &history);
+    // 等步长 BDF2: dx/dt ≈ (3x_{n+1} - 4x_n + x_{n-1}) / 2h
+    assert_close(companion.step, 2.0 * 0.3 / 3.0);
+    assert_close(companion.history, (4.0 * 2.0 - 1.0) / 3.0);
+}
+
+#[test]
+fn gear2_is_exact_for_quadratics_with_variable_step() {
+    let x = |t: f64| 1.0 + 2.0 * t + 3.0 * t * t;
+    let (t0, t1, t2) = (0.0, 0.4, 0.5);
+    let history = Gear2.advance(t1 - t0, &History::start(x(t0)), x(t1));
+    let companion = Gear2.companion(t2 - t1, &history);
+    assert_close(companion.derivative(x(t2)), 2.0 + 6.0 * t2);
+}
+
+#[test]
+fn integrators_are_selected_by_option_name() {
+    assert_eq!(integrator_from_option("EULER").map(|i| i.name()), Some("euler"));
+    assert_eq!(integrator_from_option("trapezoidal").map(|i| i.name()), Some("trap"));
+    assert_eq!(integrator_from_option("bdf2").map(|i| i.name()), Some("gear"));
+    assert!(integrator_from_option("rk4").is_none());
+}
@@ -0,0 +1,187 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{node_index, run_first_analysis};
+use sim_core::circuit::{DeviceKind as CircuitDeviceKind, Instance, NodeId};
+use sim_core::diagnostic::codes;
+use sim_core::mna::MnaBuilder;
+use sim_core::netlist::{parse_netlist, DeviceKind, Stmt};
+use sim_core::stamp::{DeviceStamp, InstanceStamp, TransientState};
+use sim_core::state_table::{StateBranch, StateKey};
+
+#[test]
+fn jfet_element_parses_model_and_area() {
+    let ast = parse_netlist(
+        "\
+J1 d g s jn
+J2 d g s jn 2
+J3 d g jn
+.model jn njf vto=-2
+",
+    );
+    let devices: Vec<_> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Device(device) => Some(device),
+            _ => None,
+        })
+        .collect();
+    assert!(devices.iter().all(|d| matches!(d.kind, DeviceKind::J)));
+    assert_eq!(devices[0].nodes, vec!["d", "g", "s"]);
+    assert_eq!(devices[0].model.as_deref(), Some("jn"));
+    assert_eq!(devices[1].value.as_deref(), Some("2"));
+    // J3 字段不足 (最后一个字段被当作节点), 只有它报错
+    assert!(!ast.errors.is_empty());
+    assert!(ast.errors.iter().all(|diag| diag.line == 3), "errors: {:?}", ast.errors);
+    assert!(ast.errors.iter().any(|diag| diag.code == codes::MISSING_REFERENCE));
+}
+
+#[test]
+fn jfet_saturation_follows_square_law() {
+    let run = run_first_analysis(
+        "\
+* n-channel jfet in saturation
+VDD vdd 0 10
+VG g 0 -1
+RD vdd d 1k
+J1 d g 0 jn
+.model jn njf vto=-2 beta=1m lambda=0.02
+.op
+.end
+",
+    );
+    let vd = run.solution[node_index(&run, "d")];
+    let id = (10.0 - vd) / 1e3;
+    let expected = 1e-3 * 1.0 * (1.0 + 0.02 * vd);
+    assert!((id - expected).abs() / expected < 1e-6, "id = {}, expected {}", id, expected);
+    // 栅结反偏, 栅极电流可以忽略
+    assert!(vd > 1.0, "vd = {}", vd);
+}
+
+#[test]
+fn pjf_with_source_resistance_self_biases() {
+    let run = run_first_analysis(
+        "\
+* p-channel jfet self-bias: gate tied to the supply, RS1 to the source
+VDD top 0 10
+RS1 top s 500
+J1 d top s jp
+RD d 0 2k
+.model jp pjf vto=-2 beta=1m rs=10
+.op
+.end
+",
+    );
+    let vs = run.solution[node_index(&run, "s")];
+    let vsi = run.solution[node_index(&run, "j1#s")];
+    let vd = run.solution[node_index(&run, "d")];
+    // 电流从源极流向漏极, 源极低于栅极使沟道部分夹断
+    let id = (10.0 - vs) / 500.0;
+    let vsg = vsi - 10.0;
+    let expected = 1e-3 * (vsg + 2.0).powi(2);
+    assert!(id > 0.0 && (id - expected).abs() / expected < 1e-4, "id = {}, expected {}", id, expected);
+    assert!(((vs - vsi) - id * 10.0).abs() < 1e-9, "vs - vs' = {}", vs - vsi);
+    assert!((vd / 2e3 - id).abs() < 1e-9, "vd = {}", vd);
+}
+
+#[test]
+fn jfet_common_source_ac_gain_and_gate_capacitance() {
+    let run = run_first_analysis(
+        "\
+* common-source amplifier: |Av| = gm * RD, CGS forms a pole with RG
+VDD vdd 0 10
+VG in 0 DC -1 AC 1
+RG in g 10k
+RD vdd d 1k
+J1 d g 0 jn
+.model jn njf vto=-2 beta=1m cgs=10p pb=1
+.ac dec 1 1 1e9
+.end
+",
+    );
+    let d = node_index(&run, "d");
+    // ac_solutions 按 (幅度 dB, 相位 度) 存储; gm = 2 * beta * (Vgs - Vto) = 2mS
+    let (low_db, low_phase) = run.ac_solutions[0][d];
+    assert!((low_db - 20.0 * 2.0f64.log10()).abs() < 1e-3, "|Av| at 1Hz = {} dB", low_db);
+    assert!((low_phase.abs() - 180.0).abs() < 0.01, "phase at 1Hz = {}", low_phase);
+    // Cgs(-1V) = 10p / sqrt(2), 极点约 2.25MHz, 1GHz 处增益大幅衰减
+    let (high_db, _) = run.ac_solutions.last().unwrap()[d];
+    assert!(high_db < low_db - 40.0, "|Av| at 1GHz = {} dB", high_db);
+}
+
+#[test]
+fn jfet_tran_stamp_adds_gate_junction_capacitance() {
+    let mut params = HashMap::new();
+    for (key, value) in [("cgs", "10p"), ("cgd", "2p"), ("pb", "1")] {
+        params.insert(key.to_string(), value.to_string());
+    }
+    let stamp = InstanceStamp {
+        instance: Instance {
+            name: "J1".to_string(),
+            kind: CircuitDeviceKind::J,
+            nodes: vec![NodeId(1), NodeId(2), NodeId(0)],
+            model: None,
+            params,
+            value: None,
+            control: None,
+            ac_mag: None,
+            ac_phase: None,
+            state_slot: 0,
+        },
+    };
+    let x = vec![0.0, 5.0, -1.0];
+    let dt = 1e-9;
+
+    let mut dc = MnaBuilder::new(3);
+    stamp.stamp_dc(&mut dc.context(), Some(&x)).unwrap();
+
+    let mut tran = MnaBuilder::new(3);
+    let mut state = TransientState::default();
+    state.cap_voltage.insert(StateKey::branch(&stamp.instance, StateBranch::Gs), -1.0);
+    state.cap_voltage.insert(StateKey::branch(&stamp.instance, StateBranch::Gd), -6.0);
+    stamp.stamp_tran(&mut tran.context(), Some(&x), dt, &mut state).unwrap();
+
+    // 耗尽电容 C = C0 / sqrt(1 - V/PB), 后向欧拉历史电流 C/dt * V_prev 注入栅极
+    let cgs = 10e-12 / 2.0f64.sqrt();
+    let cgd = 2e-12 / 7.0f64.sqrt();
+    let expected = -(cgs + cgd * 6.0) / dt;
+    let delta = tran.rhs[2] - dc.rhs[2];
+    assert!((delta - expected).abs() < 1e-9 * expected.abs(), "delta = {}, expected {}", delta, expected);
+    assert!(((tran.rhs[1] - dc.rhs[1]) - cgd * 6.0 / dt).abs() < 1e-9, "drain history current");
+}
+
+#[test]
+fn mesfet_statz_bias_point_and_curtice_level() {
+    let ast = parse_netlist("Z1 d g s zn 2\n.model zn nmf\n");
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    assert!(ast.statements.iter().any(|stmt| matches!(stmt, Stmt::Device(d) if matches!(d.kind, DeviceKind::Z))));
+
+    let bias = |model: &str| {
+        let run = run_first_analysis(&format!(
+            "\
+* common-source mesfet with a sense resistor
+VDD vdd 0 3
+VG g 0 -0.5
+RD vdd d 10
+Z1 d g 0 zn
+.model zn nmf vto=-1 beta=20m alpha=3 {}
+.op
+.end
+",
+            model
+        ));
+        let vd = run.solution[node_index(&run, "d")];
+        (vd, (3.0 - vd) / 10.0)
+    };
+    // Statz 饱和区: Id = beta * Vgst^2 / (1 + b Vgst)
+    let (vd, id) = bias("b=0.5");
+    assert!(vd > 1.0, "vd = {}", vd);
+    let expected = 20e-3 * 0.25 / 1.25;
+    assert!((id - expected).abs() / expected < 1e-6, "id = {}, expected {}", id, expected);
+    // Curtice: Id = beta * Vgst^2 * tanh(alpha Vds)
+    let (vd, id) = bias("level=2");
+    let expected = 20e-3 * 0.25 * (3.0 * vd).tanh();
+    assert!((id - expected).abs() / expected < 1e-6, "id = {}, expected {}", id, expected);
+}
@@ -0,0 +1,268 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::engine::Engine;
+use sim_core::measure::{
+    evaluate_measure, evaluate_measures, Crossing, Edge, Measure, MeasureAnalysis, MeasureSpec, Occurrence, Signal,
+    Stat,
+};
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_measure, parse_netlist};
+use sim_core::result_store::{ResultStore, RunResult};
+
+fn run(netlist: &str) -> RunResult {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    store.runs[run_id.0].clone()
+}
+
+/// 瞬态结果换成给定的分段线性波形 (各节点在 `times` 上的取值)
+fn tran_result(times: &[f64], waves: &[(&str, &[f64])]) -> RunResult {
+    let mut result = run("* waves\nV1 in 0 DC 1\nR1 in out 1k\nR2 out 0 1k\n.tran 1n 2n\n.end\n");
+    result.tran_times = times.to_vec();
+    result.tran_solutions = (0..times.len())
+        .map(|point| {
+            result
+                .node_names
+                .iter()
+                .map(|name| waves.iter().find(|(node, _)| node == name).map_or(0.0, |(_, wave)| wave[point]))
+                .collect()
+        })
+        .collect();
+    result
+}
+
+fn v(node: &str) -> Signal {
+    Signal::Voltage(node.to_string(), None)
+}
+
+fn crossing(node: &str, value: f64, edge: Edge, occurrence: Occurrence) -> Crossing {
+    Crossing {
+        signal: v(node),
+        value,
+        edge,
+        occurrence,
+        td: 0.0,
+    }
+}
+
+fn assert_close(actual: Result<f64, String>, expected: f64, tol: f64) {
+    let actual = actual.unwrap();
+    assert!((actual - expected).abs() <= tol, "{} vs {}", actual, expected);
+}
+
+const NS: f64 = 1e-9;
+
+/// in: 1n..2n 上升到 1, 5n..6n 下降; out 滞后 1n
+fn pulses() -> RunResult {
+    let times: Vec<f64> = [0.0, 1.0, 2.0, 3.0, 5.0, 6.0, 7.0, 8.0].iter().map(|t| t * NS).collect();
+    tran_result(
+        &times,
+        &[
+            ("in", &[0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0]),
+            ("out", &[0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.0]),
+        ],
+    )
+}
+
+#[test]
+fn measure_signal_parse() {
+    assert_eq!(Signal::parse("v(out)"), Some(v("out")));
+    assert_eq!(Signal::parse("V(a, b)"), Some(Signal::Voltage("a".into(), Some("b".into()))));
+    assert_eq!(Signal::parse("vdb(out)"), Some(Signal::Db("out".into(), None)));
+    assert_eq!(Signal::parse("vp(out)"), Some(Signal::Phase("out".into(), None)));
+    assert_eq!(Signal::parse("i(V1)"), Some(Signal::Current("V1".into())));
+    assert_eq!(Signal::parse("i(a,b)"), None);
+    assert_eq!(Signal::parse("v()"), None);
+    assert_eq!(Signal::parse("out"), None);
+}
+
+#[test]
+fn measure_crossings_delay_and_slew() {
+    let result = pulses();
+    let when = |occurrence| evaluate_measure(&result, &Measure::When(crossing("in", 0.5, Edge::Cross, occurrence)));
+    assert_close(when(Occurrence::Nth(1)), 1.5 * NS, 1e-18);
+    assert_close(when(Occurrence::Nth(2)), 5.5 * NS, 1e-18);
+    assert_close(when(Occurrence::Last), 5.5 * NS, 1e-18);
+    assert!(when(Occurrence::Nth(3)).is_err());
+
+    let mut late = crossing("in", 0.5, Edge::Rise, Occurrence::Nth(1));
+    late.td = 3.0 * NS;
+    assert!(evaluate_measure(&result, &Measure::When(late)).is_err());
+
+    let delay = |edge| Measure::Delay {
+        trig: crossing("in", 0.5, edge, Occurrence::Nth(1)),
+        targ: crossing("out", 0.5, edge, Occurrence::Nth(1)),
+    };
+    assert_close(evaluate_measure(&result, &delay(Edge::Rise)), 1.0 * NS, 1e-18);
+    assert_close(evaluate_measure(&result, &delay(Edge::Fall)), 1.0 * NS, 1e-18);
+
+    let slew = |edge| Measure::Slew {
+        signal: v("in"),
+        low: 0.1,
+        high: 0.9,
+        edge,
+        occurrence: Occurrence::Nth(1),
+    };
+    assert_close(evaluate_measure(&result, &slew(Edge::Rise)), 0.8 * NS, 1e-18);
+    assert_close(evaluate_measure(&result, &slew(Edge::Fall)), 0.8 * NS, 1e-18);
+
+    let find = Measure::FindAt { signal: v("in"), at: 1.25 * NS };
+    assert_close(evaluate_measure(&result, &find), 0.25, 1e-12);
+    let outside = Measure::FindAt { signal: v("in"), at: 9.0 * NS };
+    assert!(evaluate_measure(&result, &outside).is_err());
+    let missing = Measure::When(crossing("nowhere", 0.5, Edge::Rise, Occurrence::Nth(1)));
+    assert!(evaluate_measure(&result, &missing).unwrap_err().contains("'nowhere'"));
+}
+
+#[test]
+fn measure_overshoot_and_settling() {
+    let times: Vec<f64> = (0..8).map(|t| t as f64).collect();
+    let result = tran_result(&times, &[("out", &[0.0, 1.2, 0.9, 1.04, 0.98, 1.0, 1.0, 1.0])]);
+    let overshoot = Measure::Overshoot { signal: v("out"), from: 0.0, to: 7.0 };
+    assert_close(evaluate_measure(&result, &overshoot), 20.0, 1e-9);
+    // 最后一次在 ±5% 带外的是 t=2 的 0.9, 在 t=2..3 之间回到 0.95
+    let settling = Measure::Settling { signal: v("out"), from: 0.0, to: 7.0, tol: 0.05 };
+    assert_close(evaluate_measure(&result, &settling), 2.0 + 0.05 / 0.14, 1e-9);
+    // 误差带按窗口内的阶跃幅度计
+    let settling = Measure::Settling { signal: v("out"), from: 0.0, to: 7.0, tol: 0.25 };
+    assert_close(evaluate_measure(&result, &settling), 0.75 / 1.2, 1e-9);
+    let settling = Measure::Settling { signal: v("out"), from: 5.0, to: 7.0, tol: 0.05 };
+    assert_close(evaluate_measure(&result, &settling), 0.0, 0.0);
+
+    // 下降阶跃的过冲按阶跃方向计
+    let result = tran_result(&times, &[("out", &[1.0, -0.1, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])]);
+    assert_close(evaluate_measure(&result, &Measure::Overshoot { signal: v("out"), from: 0.0, to: 7.0 }), 10.0, 1e-9);
+}
+
+#[test]
+fn measure_window_statistics() {
+    let times = [0.0, 1.0, 2.0, 3.0, 4.0];
+    let result = tran_result(&times, &[("out", &[0.0, 2.0, 2.0, -2.0, -2.0])]);
+    let stat = |stat, from, to| {
+        evaluate_measure(&result, &Measure::Stat { stat, signal: v("out"), from, to })
+    };
+    assert_close(stat(Stat::Max, None, None), 2.0, 0.0);
+    assert_close(stat(Stat::Min, None, None), -2.0, 0.0);
+    assert_close(stat(Stat::Pp, None, None), 4.0, 0.0);
+    assert_close(stat(Stat::Integ, None, None), 1.0, 1e-12);
+    assert_close(stat(Stat::Avg, None, None), 0.25, 1e-12);
+    // 窗口两端插值: [0.5, 2] 上 1 → 2 → 2
+    assert_close(stat(Stat::Avg, Some(0.5), Some(2.0)), (0.75 + 2.0) / 1.5, 1e-12);
+    assert_close(stat(Stat::Max, Some(3.5), Some(4.0)), -2.0, 0.0);
+    assert_close(stat(Stat::Rms, Some(1.0), Some(2.0)), 2.0, 1e-12);
+    assert!(stat(Stat::Avg, Some(2.0), Some(2.0)).is_err());
+    assert!(stat(Stat::Avg, Some(-1.0), None).is_err());
+}
+
+#[test]
+fn measure_ac_bandwidth_and_phase() {
+    // RC 低通: fc = 1 / (2π·1k·1u) ≈ 159.15 Hz
+    let result = run("* rc\nV1 in 0 DC 0 AC 1\nR1 in out 1k\nC1 out 0 1u\n.ac dec 200 1 10k\n.end\n");
+    let fc = 1.0 / (2.0 * std::f64::consts::PI * 1e-3);
+    let bandwidth = Measure::When(Crossing {
+        signal: Signal::Db("out".into(), None),
+        value: -3.0103,
+        edge: Edge::Fall,
+        occurrence: Occurrence::Nth(1),
+        td: 0.0,
+    });
+    assert_close(evaluate_measure(&result, &bandwidth), fc, 0.5);
+    let phase = Measure::FindAt { signal: Signal::Phase("out".into(), None), at: fc };
+    assert_close(evaluate_measure(&result, &phase), -45.0, 0.2);
+    let gain = Measure::FindAt { signal: Signal::Voltage("out".into(), Some("in".into())), at: fc };
+    // |v(out) − v(in)| = |−jωRC / (1 + jωRC)| = 1/√2 at fc
+    assert_close(evaluate_measure(&result, &gain), std::f64::consts::FRAC_1_SQRT_2, 2e-3);
+    // AC 保留支路电流的复数解: |I(V1)| = |1 / (R (1 + jωRC))| = 1 / (√2 R) at fc
+    let current = Measure::FindAt { signal: Signal::Current("V1".into()), at: fc };
+    assert_close(evaluate_measure(&result, &current), std::f64::consts::FRAC_1_SQRT_2 * 1e-3, 2e-6);
+}
+
+#[test]
+fn measure_dc_sweep_current() {
+    let result = run("* sweep\nV1 in 0 0\nR1 in 0 1k\n.dc V1 0 2 0.5\n.end\n");
+    let current = Measure::FindAt { signal: Signal::Current("V1".into()), at: 1.25 };
+    assert_close(evaluate_measure(&result, &current), -1.25e-3, 1e-12);
+}
+
+#[test]
+fn measure_expressions_reference_earlier_measurements() {
+    // 上升沿延迟 1n, 下降沿 0.5n
+    let times: Vec<f64> = [0.0, 1.0, 2.0, 3.0, 5.0, 6.0, 7.0].iter().map(|t| t * NS).collect();
+    let result = tran_result(
+        &times,
+        &[
+            ("in", &[0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.0]),
+            ("out", &[0.0, 0.0, 0.0, 1.0, 1.0, 0.5, 0.0]),
+        ],
+    );
+    let spec = |name: &str, measure| MeasureSpec { name: name.to_string(), measure };
+    let specs = vec![
+        spec(
+            "tpd_r",
+            Measure::Delay {
+                trig: crossing("in", 0.5, Edge::Rise, Occurrence::Nth(1)),
+                targ: crossing("out", 0.5, Edge::Rise, Occurrence::Nth(1)),
+            },
+        ),
+        spec(
+            "tpd_f",
+            Measure::Delay {
+                trig: crossing("in", 0.5, Edge::Fall, Occurrence::Nth(1)),
+                targ: crossing("out", 0.5, Edge::Fall, Occurrence::Nth(1)),
+            },
+        ),
+        spec("tpd", Measure::Expr("(TPD_R + tpd_f) / 2".to_string())),
+        spec("never", Measure::When(crossing("in", 5.0, Edge::Rise, Occurrence::Nth(1)))),
+        spec("uses_never", Measure::Expr("never * 2".to_string())),
+        spec("unknown", Measure::Expr("tpd + later".to_string())),
+        spec("later", Measure::Expr("1".to_string())),
+    ];
+    let measurements = evaluate_measures(&result, &specs);
+    let names: Vec<&str> = measurements.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["tpd_r", "tpd_f", "tpd", "never", "uses_never", "unknown", "later"]);
+    assert_close(measurements[2].value.clone(), 0.75 * NS, 1e-18);
+    assert!(measurements[3].value.is_err());
+    assert!(measurements[4].value.as_ref().unwrap_err().contains("'never'"), "{:?}", measurements[4].value);
+    // 只能引用前面的测量
+    assert!(measurements[5].value.is_err());
+    assert_close(measurements[6].value.clone(), 1.0, 0.0);
+}
+
+#[test]
+fn measure_cards_run_after_analysis() {
+    let result = run("* meas\nV1 in 0 DC 0\nR1 in out 1k\nR2 out 0 1k\n.dc V1 0 2 0.1\n\
+.meas dc vmid FIND v(out) AT=1\n\
+.measure dc half WHEN v(out)=0.5\n\
+.meas dc avgv AVG v(out) FROM=0 TO=2\n\
+.meas dc twice PARAM='vmid*2' max=0.5\n\
+.meas tran skipped FIND v(out) AT=1n\n\
+.end\n");
+    let names: Vec<&str> = result.measurements.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["vmid", "half", "avgv", "twice"]);
+    assert_close(result.measurements[0].value.clone(), 0.5, 1e-9);
+    assert_close(result.measurements[1].value.clone(), 1.0, 1e-9);
+    assert_close(result.measurements[2].value.clone(), 0.5, 1e-9);
+    assert_close(result.measurements[3].value.clone(), 1.0, 1e-9);
+}
+
+#[test]
+fn measure_card_limits_and_errors() {
+    let cmd =
+        parse_measure(".meas tran tpd TRIG v(in) VAL=0.5 RISE=1 TARG v(out) VAL=0.5 RISE=1 min=1n max=5n").unwrap();
+    assert_eq!(cmd.analysis, MeasureAnalysis::Tran);
+    assert_eq!(cmd.spec.name, "tpd");
+    assert!(cmd.within_limits(2e-9));
+    assert!(!cmd.within_limits(6e-9));
+    assert!(!cmd.within_limits(f64::NAN));
+
+    assert!(parse_measure(".meas tran").is_err());
+    assert!(parse_measure(".meas noise x FIND v(out) AT=1").is_err());
+    assert!(parse_measure(".meas tran x BOGUS v(out)").is_err());
+
+    let ast = parse_netlist("* bad\nV1 in 0 DC 1\nR1 in 0 1k\n.meas tran x BOGUS v(in)\n.op\n.end\n");
+    assert_eq!(ast.errors.len(), 1);
+    assert_eq!(ast.errors[0].code, "E0008");
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::{node_index, run_first_analysis};
+use sim_devices::memristor::build_memristor_params;
+
+const MODEL: &str = ".model mr memristor ron=100 roff=10k x0=0.5";
+
+fn divider(vin: f64, analysis: &str) -> String {
+    format!("* memristor divider\nV1 in 0 DC {}\nRS in a 1k\nR1 a 0 mr\n{}\n{}\n.end\n", vin, MODEL, analysis)
+}
+
+#[test]
+fn memristor_operating_point_uses_initial_state() {
+    let run = run_first_analysis(&divider(1.0, ".op"));
+    // R(X0) = 0.5 * 100 + 0.5 * 10k
+    let expected = 5050.0 / 6050.0;
+    assert!((run.solution[node_index(&run, "a")] - expected).abs() < 1e-9);
+}
+
+#[test]
+fn memristor_state_is_integrated_during_transient() {
+    let run = run_first_analysis(&divider(1.0, ".tran 10u 1m"));
+    let a = node_index(&run, "a");
+    let va: Vec<f64> = run.tran_solutions.iter().map(|x| x[a]).collect();
+    assert!((va[0] - 5050.0 / 6050.0).abs() < 1e-9);
+    // 正向电流使掺杂区变宽, 电阻下降
+    assert!(va.windows(2).all(|w| w[1] <= w[0] + 1e-12));
+    assert!(va[0] - va[va.len() - 1] > 1e-4, "v(a) {} -> {}", va[0], va[va.len() - 1]);
+
+    // 按接受的时间点与端电压复现内部状态, 终点电阻与仿真结果一致
+    let params = build_memristor_params(
+        &[("ron", "100"), ("roff", "10k"), ("x0", "0.5")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    );
+    let mut state = params.x0;
+    for (v, t) in va.iter().skip(1).zip(run.tran_times.windows(2)) {
+        state = params.step(state, *v, t[1] - t[0]).x;
+    }
+    let v_end = va[va.len() - 1];
+    let r_sim = v_end / ((1.0 - v_end) / 1e3);
+    assert!((r_sim - params.resistance(state)).abs() < 1e-6 * r_sim, "R = {}", r_sim);
+
+    // 反向电流使电阻回升
+    let run = run_first_analysis(&divider(-1.0, ".tran 10u 1m"));
+    let a = node_index(&run, "a");
+    let first = run.tran_solutions[0][a];
+    let last = run.tran_solutions.last().unwrap()[a];
+    assert!(last < first - 1e-5, "v(a) {} -> {}", first, last);
+}
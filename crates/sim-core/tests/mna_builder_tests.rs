@@ -36,6 +36,7 @@ fn mna_builder_allocates_aux_for_voltage() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let stamp = InstanceStamp { instance };
     let mut ctx = builder.context();
@@ -58,6 +59,7 @@ fn dc_op_mna_entries_for_r_and_i() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let i1 = Instance {
         name: "I1".to_string(),
@@ -69,6 +71,7 @@ fn dc_op_mna_entries_for_r_and_i() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
 
     let mut ctx = builder.context();
@@ -97,6 +100,7 @@ fn inductor_dc_stamp_as_short() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     InstanceStamp { instance: l1 }.stamp_dc(&mut ctx, None).unwrap();
@@ -116,6 +120,7 @@ fn source_scale_applies_to_current() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context_with(0.0, 0.5);
     InstanceStamp { instance: i1 }.stamp_dc(&mut ctx, None).unwrap();
@@ -136,6 +141,7 @@ fn gmin_applies_to_diode_stamp() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context_with(1e-6, 1.0);
     InstanceStamp { instance: d1 }.stamp_dc(&mut ctx, None).unwrap();
@@ -156,6 +162,7 @@ fn diode_stamp_uses_solution_when_provided() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context_with(1e-12, 1.0);
     let x = vec![0.0, 0.7];
@@ -21,3 +21,164 @@ fn model_flow_maps_instance_to_model() {
     assert!(model.params.contains_key("is"));
     assert!(circuit.instances.instances[0].params.contains_key("is"));
 }
+
+#[test]
+fn model_flow_merges_model_card_and_type_into_mos() {
+    let input = ".model pch pmos level=54 vth0=-0.45 tox=2n\nM1 d g s b PCH w=2u l=0.1u vth0=-0.5\n.end\n";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+
+    let params = &circuit.instances.instances[0].params;
+    assert_eq!(params.get("type").map(String::as_str), Some("pmos"));
+    assert_eq!(params.get("level").map(String::as_str), Some("54"));
+    assert_eq!(params.get("tox").map(String::as_str), Some("2n"));
+    // 实例参数覆盖模型参数
+    assert_eq!(params.get("vth0").map(String::as_str), Some("-0.5"));
+}
+
+#[test]
+fn model_flow_resolves_subckt_local_model() {
+    let input = ".subckt inv in out vdd\n.model nch nmos vth0=0.3\nM1 out in 0 0 nch\n.ends\nX1 a b vdd inv\n.end\n";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+
+    let inst = &circuit.instances.instances[0];
+    assert_eq!(inst.name, "X1.M1");
+    let model_id = inst.model.expect("subckt model not resolved");
+    assert_eq!(circuit.models.models[model_id.0].name, "x1.nch");
+    assert_eq!(inst.params.get("vth0").map(String::as_str), Some("0.3"));
+}
+
+#[test]
+fn model_flow_selects_binned_model_by_geometry() {
+    let input = ".model nch.1 nmos lmin=0.1u lmax=1u vth0=0.4\n.model nch.2 nmos lmin=1u lmax=10u vth0=0.5\nM1 d g 0 0 nch l=2u w=1u\nM2 d g 0 0 nch l=0.2u w=1u\n.end\n";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+
+    let vth = |idx: usize| circuit.instances.instances[idx].params.get("vth0").cloned();
+    assert_eq!(vth(0).as_deref(), Some("0.5"));
+    assert_eq!(vth(1).as_deref(), Some("0.4"));
+}
+
+#[test]
+fn model_flow_accepts_parenthesized_model_card() {
+    let input = ".model nch nmos(vth0=0.45 tox=2n)\n.model pch pmos (\n+ vth0=-0.42\n+ tox=2.1n )\n.model nlong nmos (level=54\nu0=0.04 vsat={8e4*(1+0)})\nM1 d g 0 0 nch\nM2 d g vdd vdd pch\nM3 d g 0 0 nlong\n.end\n";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+
+    let params = |idx: usize| &circuit.instances.instances[idx].params;
+    assert_eq!(params(0).get("type").map(String::as_str), Some("nmos"));
+    assert_eq!(params(0).get("vth0").map(String::as_str), Some("0.45"));
+    assert_eq!(params(0).get("tox").map(String::as_str), Some("2n"));
+    assert_eq!(params(1).get("type").map(String::as_str), Some("pmos"));
+    assert_eq!(params(1).get("vth0").map(String::as_str), Some("-0.42"));
+    assert_eq!(params(1).get("tox").map(String::as_str), Some("2.1n"));
+    assert_eq!(params(2).get("level").map(String::as_str), Some("54"));
+    assert_eq!(params(2).get("u0").map(String::as_str), Some("0.04"));
+    assert!(params(2).contains_key("vsat"));
+}
+
+#[test]
+fn model_flow_applies_options_scale_to_mos_geometry() {
+    let input = "\
+.options scale=1u geoshrink=0.5 post
+.model nch nmos level=1
+M1 d g 0 0 nch w=10 l=2 ad=4 ps=8
+R1 d 0 1k
+.end
+";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    assert_eq!(circuit.options.get("post").map(String::as_str), Some("1"));
+
+    let number = |name: &str, key: &str| -> f64 {
+        let inst = circuit.instances.instances.iter().find(|i| i.name == name).unwrap();
+        inst.params[key].parse().unwrap()
+    };
+    let close = |a: f64, b: f64| (a - b).abs() <= 1e-12 * b.abs();
+    assert!(close(number("M1", "w"), 5e-6));
+    assert!(close(number("M1", "l"), 1e-6));
+    assert!(close(number("M1", "ps"), 4e-6));
+    assert!(close(number("M1", "ad"), 1e-12));
+    // 其他器件不受影响
+    let r1 = circuit.instances.instances.iter().find(|i| i.name == "R1").unwrap();
+    assert_eq!(r1.value.as_deref(), Some("1000"));
+}
+
+#[test]
+fn model_flow_applies_default_mos_geometry_options() {
+    let input = "\
+.options defw=4u defl=0.5u defad=2p defas=3p scale=0.5
+.model nch nmos level=1
+M1 d g 0 0 nch
+M2 d g 0 0 nch w=1u as=1p
+R1 d 0 1k
+.end
+";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+
+    let number = |name: &str, key: &str| -> Option<f64> {
+        let inst = circuit.instances.instances.iter().find(|i| i.name == name).unwrap();
+        inst.params.get(key).map(|value| value.parse().unwrap())
+    };
+    let close = |a: Option<f64>, b: f64| a.is_some_and(|a| (a - b).abs() <= 1e-12 * b.abs());
+    // 缺省尺寸与实例尺寸一样再乘 scale
+    assert!(close(number("M1", "w"), 2e-6));
+    assert!(close(number("M1", "l"), 0.25e-6));
+    assert!(close(number("M1", "ad"), 0.5e-12));
+    assert!(close(number("M1", "as"), 0.75e-12));
+    // 实例给出的尺寸优先
+    assert!(close(number("M2", "w"), 0.5e-6));
+    assert!(close(number("M2", "as"), 0.25e-12));
+    assert!(close(number("M2", "l"), 0.25e-6));
+    // 其他器件不受影响
+    assert_eq!(number("R1", "w"), None);
+}
+
+#[test]
+fn model_flow_resolves_semiconductor_resistor_model() {
+    let input = ".param rval=2k\n.model rpoly r rsh=100 tc1=1e-3\nR1 a b rpoly l=10u w=1u\nR2 b 0 1k rpoly\nR3 a 0 rval\n.end\n";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+
+    let [r1, r2, r3] = &circuit.instances.instances[..] else { panic!() };
+    // 取值字段是模型名: 阻值由模型的 RSH 与 L/W 给出
+    assert!(r1.model.is_some() && r1.value.is_none());
+    assert_eq!(r1.params.get("rsh").map(String::as_str), Some("100"));
+    // 取值后跟模型名: 保留取值, 模型只提供温度系数
+    assert!(r2.model.is_some());
+    assert_eq!(r2.value.as_deref(), Some("1000"));
+    // 参数名仍按 .param 取值
+    assert!(r3.model.is_none());
+    assert_eq!(r3.value.as_deref(), Some("2000"));
+}
+
+#[test]
+fn model_flow_resolves_layout_capacitor_model() {
+    let input = ".model cmim c cj=2e-3 cjsw=1e-10\nC1 a 0 cmim w=5u l=5u\nC2 a b 1p cmim\n.end\n";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+
+    let [c1, c2] = &circuit.instances.instances[..] else { panic!() };
+    assert!(c1.model.is_some() && c1.value.is_none());
+    assert_eq!(c1.params.get("cj").map(String::as_str), Some("2e-3"));
+    assert!(c2.model.is_some());
+    assert!(c2.value.is_some());
+}
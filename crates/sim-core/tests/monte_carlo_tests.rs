@@ -0,0 +1,132 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::MonteCarloConfig;
+use sim_core::engine::Engine;
+use sim_core::netlist::{
+    build_circuit, elaborate_netlist, parse_netlist, sample_statistical_params, statistical_params, Distribution,
+};
+use sim_core::result_store::{ResultStore, RunResult, RunStatus};
+
+const DIVIDER: &str = "V1 in 0 DC 1\n.param rtop=gauss(1k,0.1,3) rbot=unif(1k,0.2) rsum='rtop+rbot'\n\
+                       R1 in out rtop\nR2 out 0 rbot\n.param vout='v(out)'\n.op\n.mc 200 seed=7\n.end\n";
+
+fn run_mc(netlist: &str) -> Vec<RunResult> {
+    run_mc_with_threads(netlist, 1)
+}
+
+fn run_mc_with_threads(netlist: &str, threads: usize) -> Vec<RunResult> {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "elaboration errors");
+    let circuit = build_circuit(&ast, &elab);
+    let config = circuit.monte_carlo.expect(".mc");
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    engine.set_threads(threads);
+    let mut store = ResultStore::new();
+    let run_ids = engine.run_monte_carlo(&AnalysisPlan { cmd }, &config, &mut store);
+    run_ids.iter().map(|id| store.runs[id.0].clone()).collect()
+}
+
+fn sample(run: &RunResult, name: &str) -> f64 {
+    run.sampled_params.iter().find(|(key, _)| key == name).unwrap().1
+}
+
+fn output(run: &RunResult, name: &str) -> f64 {
+    run.output_params.iter().find(|(key, _)| key == name).unwrap().1
+}
+
+#[test]
+fn mc_parses_runs_and_seed() {
+    let ast = parse_netlist(DIVIDER);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    assert_eq!(circuit.monte_carlo, Some(MonteCarloConfig { runs: 200, seed: 7 }));
+    assert_eq!(statistical_params(&ast), vec!["rtop", "rbot"]);
+
+    let ast = parse_netlist("V1 a 0 1\n.mc 5\n.end\n");
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    assert_eq!(circuit.monte_carlo, Some(MonteCarloConfig { runs: 5, seed: 1 }));
+}
+
+#[test]
+fn mc_distribution_functions_evaluate_to_nominal_outside_monte_carlo() {
+    let ast = parse_netlist(
+        ".param a=gauss(2,0.1) b=agauss(3,0.5,3) c=unif(4,0.1) d=aunif(5,1)\n\
+         V1 x 0 DC 'a+b+c+d'\n.op\n.end\n",
+    );
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0);
+    let circuit = build_circuit(&ast, &elab);
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let cmd = engine.circuit.analysis[0].clone();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    let idx = run.node_names.iter().position(|name| name == "x").unwrap();
+    assert!((run.solution[idx] - 14.0).abs() < 1e-9, "{}", run.solution[idx]);
+}
+
+#[test]
+fn mc_sampling_follows_distributions() {
+    let ast = parse_netlist(".param g=gauss(10,0.3,3) u=aunif(5,2) n=agauss(0,1)\n.end\n");
+    let mut seen = Vec::new();
+    let samples = sample_statistical_params(&ast, |distribution| {
+        seen.push(distribution);
+        match distribution {
+            Distribution::Gauss { mean, sigma } => mean + sigma,
+            Distribution::Uniform { high, .. } => high,
+        }
+    });
+    assert_eq!(
+        seen,
+        vec![
+            Distribution::Gauss { mean: 10.0, sigma: 1.0 },
+            Distribution::Uniform { low: 3.0, high: 7.0 },
+            Distribution::Gauss { mean: 0.0, sigma: 1.0 },
+        ]
+    );
+    assert_eq!(samples, vec![("g".to_string(), 11.0), ("u".to_string(), 7.0), ("n".to_string(), 1.0)]);
+}
+
+#[test]
+fn mc_runs_store_samples_and_rederive_params() {
+    let runs = run_mc(DIVIDER);
+    assert_eq!(runs.len(), 200);
+    for run in &runs {
+        assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+        let (rtop, rbot) = (sample(run, "rtop"), sample(run, "rbot"));
+        assert!((800.0..=1200.0).contains(&rbot), "rbot = {}", rbot);
+        // 输出参数用本次抽样的电阻求值
+        let expected = rbot / (rtop + rbot);
+        assert!((output(run, "vout") - expected).abs() < 1e-9, "{} vs {}", output(run, "vout"), expected);
+    }
+
+    // gauss(1k, 0.1, 3): σ = 33.3
+    let values: Vec<f64> = runs.iter().map(|run| sample(run, "rtop")).collect();
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let sigma = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt();
+    assert!((mean - 1000.0).abs() < 10.0, "mean = {}", mean);
+    assert!((sigma - 33.3).abs() < 6.0, "sigma = {}", sigma);
+}
+
+#[test]
+fn mc_same_seed_reproduces_runs() {
+    let first = run_mc(DIVIDER);
+    let second = run_mc(DIVIDER);
+    let other = run_mc(&DIVIDER.replace("seed=7", "seed=8"));
+    let samples = |runs: &[RunResult]| runs.iter().map(|run| run.sampled_params.clone()).collect::<Vec<_>>();
+    assert_eq!(samples(&first), samples(&second));
+    assert_ne!(samples(&first), samples(&other));
+}
+
+#[test]
+fn mc_parallel_runs_match_serial_runs() {
+    let serial = run_mc(DIVIDER);
+    let parallel = run_mc_with_threads(DIVIDER, 4);
+    assert_eq!(parallel.len(), serial.len());
+    for (a, b) in serial.iter().zip(&parallel) {
+        assert_eq!(a.sampled_params, b.sampled_params);
+        assert_eq!(a.output_params, b.output_params);
+        assert_eq!(a.solution, b.solution);
+    }
+}
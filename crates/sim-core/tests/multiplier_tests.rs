@@ -0,0 +1,50 @@
+mod common;
+
+use common::run_op;
+use sim_core::result_store::RunResult;
+
+fn node_voltage(run: &RunResult, name: &str) -> f64 {
+    let idx = run
+        .node_names
+        .iter()
+        .position(|n| n == name)
+        .unwrap_or_else(|| panic!("node {} not found", name));
+    run.solution[idx]
+}
+
+#[test]
+fn multiplier_scales_resistor_conductance() {
+    let run = run_op("* m\nV1 in 0 1\nR1 in out 1k\nR2 out 0 1k m=2\n.end\n");
+    assert!((node_voltage(&run, "out") - 1.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn multiplier_scales_current_source() {
+    let run = run_op("* m\nI1 0 out 1m m=3\nR1 out 0 1k\n.end\n");
+    assert!((node_voltage(&run, "out") - 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn multiplier_on_subckt_instance_multiplies_children() {
+    // X1 m=2 与内部 R1 m=2 相乘, 等效 2k/4 = 500 欧
+    let netlist = "* m\n.subckt load a b\nR1 a b 2k m=2\n.ends\nV1 in 0 1\nR0 in out 1k\nX1 out 0 load m=2\n.end\n";
+    let run = run_op(netlist);
+    assert!((node_voltage(&run, "out") - 1.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn multiplier_mos_matches_parallel_devices() {
+    let header = "* m\n.model nch nmos level=1 vto=0.5 kp=100u\nVDD vdd 0 1.8\nVG g 0 1.0\nRD vdd d 5k\n";
+    let single = run_op(&format!(
+        "{}M1 d g 0 0 nch w=1u l=1u m=2\n.end\n",
+        header
+    ));
+    let parallel = run_op(&format!(
+        "{}M1 d g 0 0 nch w=1u l=1u\nM2 d g 0 0 nch w=1u l=1u\n.end\n",
+        header
+    ));
+    let vd_single = node_voltage(&single, "d");
+    let vd_parallel = node_voltage(&parallel, "d");
+    assert!(vd_single < 1.8 - 1e-3);
+    assert!((vd_single - vd_parallel).abs() < 1e-6, "{} vs {}", vd_single, vd_parallel);
+}
@@ -1,5 +1,9 @@
-use sim_core::netlist::parse_netlist;
-use sim_core::netlist::{elaborate_netlist, parse_netlist_file, ControlKind, Stmt};
+use sim_core::netlist::{
+    parse_netlist, parse_netlist_decks, parse_netlist_file_decks, parse_number_with_suffix, NetlistFormat,
+};
+use sim_core::netlist::{
+    elaborate_netlist, parse_netlist_file, parse_netlist_file_with_includes, ControlKind, Stmt,
+};
 use std::path::PathBuf;
 
 #[test]
@@ -26,6 +30,36 @@ fn netlist_parser_extracts_nodes_and_value() {
     assert_eq!(device.value, Some("10k".to_string()));
 }
 
+#[test]
+fn netlist_parser_strips_inline_comments() {
+    let input = "$ header comment\nR1 in out$1 10k $ load\n+ tc1=0 ; tempco\nC1 out$1 0 1p; cap\n.op ; dc\n.end\n";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let devices: Vec<_> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Device(dev) => Some(dev),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(devices.len(), 2);
+    assert_eq!(devices[0].nodes, vec!["in".to_string(), "out$1".to_string()]);
+    assert_eq!(devices[0].value, Some("10k".to_string()));
+    assert_eq!(devices[0].params.len(), 1);
+    assert!(devices[0].extras.is_empty());
+    assert_eq!(devices[1].value, Some("1p".to_string()));
+    let op = ast
+        .statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Control(ctrl) if matches!(ctrl.kind, ControlKind::Op) => Some(ctrl),
+            _ => None,
+        })
+        .expect("op not found");
+    assert!(op.args.is_empty());
+}
+
 #[test]
 fn netlist_parser_recognizes_model_and_subckt() {
     let input = ".model nmos bsim4 vth0=0.4\n.subckt inv in out vdd vss\n.ends\n";
@@ -127,6 +161,55 @@ fn netlist_parser_expands_include() {
     assert!(device_count >= 2);
 }
 
+fn include_test_dir(name: &str) -> PathBuf {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_include_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    dir
+}
+
+#[test]
+fn netlist_include_uses_search_dirs_and_quoted_paths() {
+    let dir = include_test_dir("search");
+    let lib_dir = dir.join("lib dir");
+    std::fs::create_dir_all(&lib_dir).unwrap();
+    std::fs::write(lib_dir.join("my models.cir"), "R2 out 0 2k\n").unwrap();
+    let top = dir.join("top.cir");
+    std::fs::write(&top, "* top\nR1 in out 1k\n.include \"my models.cir\"\n.end\n").unwrap();
+
+    let ast = parse_netlist_file(&top);
+    assert!(!ast.errors.is_empty(), "include should not resolve without search dirs");
+
+    let ast = parse_netlist_file_with_includes(&top, &[lib_dir]);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let device_count = ast
+        .statements
+        .iter()
+        .filter(|stmt| matches!(stmt, Stmt::Device(_)))
+        .count();
+    assert_eq!(device_count, 2);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn netlist_include_error_reports_chain() {
+    let dir = include_test_dir("chain");
+    std::fs::write(dir.join("mid.cir"), "R2 out 0 2k\n.inc missing.cir\n").unwrap();
+    let top = dir.join("top.cir");
+    std::fs::write(&top, "* top\n.include mid.cir\n.end\n").unwrap();
+
+    let ast = parse_netlist_file(&top);
+    assert_eq!(ast.errors.len(), 1, "errors: {:?}", ast.errors);
+    let message = &ast.errors[0].message;
+    assert!(message.contains("top.cir:2 -> "), "message: {}", message);
+    assert!(message.contains("mid.cir:2 -> "), "message: {}", message);
+    assert!(message.ends_with("missing.cir)"), "message: {}", message);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
 #[test]
 fn netlist_param_expression_evaluates() {
     let input = ".param RVAL=1k+1k\nR1 in out RVAL\n.end\n";
@@ -136,6 +219,29 @@ fn netlist_param_expression_evaluates() {
     assert_eq!(elab.instances[0].value.as_deref(), Some("2000"));
 }
 
+#[test]
+fn netlist_params_are_evaluated_in_dependency_order() {
+    let input = ".param b={2*a} c=b+1\n.param a=1k\nR1 in out c\nR2 out 0 {b}\n.end\n";
+    let ast = parse_netlist(input);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "errors: {:?}", elab.errors);
+    assert_eq!(elab.instances[0].value.as_deref(), Some("2001"));
+    assert_eq!(elab.instances[1].value.as_deref(), Some("2000"));
+}
+
+#[test]
+fn netlist_param_cycle_is_reported() {
+    let input = "* cycle\n.param a=b+1\n.param b={a*2} ok=3\nR1 in out ok\n.end\n";
+    let ast = parse_netlist(input);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.errors.len(), 1, "errors: {:?}", elab.errors);
+    let diag = &elab.errors[0];
+    assert_eq!(diag.code, sim_core::diagnostic::codes::PARAM_CYCLE);
+    assert_eq!(diag.line, 2);
+    assert_eq!(diag.message, "parameter dependency cycle: a -> b -> a");
+    assert_eq!(elab.instances[0].value.as_deref(), Some("3"));
+}
+
 #[test]
 fn netlist_controlled_source_poly_is_accepted() {
     // 标准 POLY 语法: E1 out 0 POLY(n) ctrl+ ctrl- ... coeffs
@@ -162,6 +268,46 @@ fn netlist_param_expression_if() {
     assert_eq!(elab.instances[0].value.as_deref(), Some("1000"));
 }
 
+#[test]
+fn netlist_string_params_select_values_and_model_names() {
+    let input = "\
+.param corner=\"ff\"
+.param RVAL=if(corner==\"FF\",1k,2k) mname=if(corner!=\"ss\",\"fast\",\"slow\")
+R1 in out RVAL
+M1 d g 0 0 nch_{corner} w=1u l=1u
+M2 d g 0 0 mname w=1u l=1u
+.model nch_ff nmos level=1
+.model fast nmos level=1
+.end
+";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.instances.len(), 3);
+    assert_eq!(elab.instances[0].value.as_deref(), Some("1000"));
+    assert_eq!(elab.instances[1].model.as_deref(), Some("nch_ff"));
+    assert_eq!(elab.instances[2].model.as_deref(), Some("fast"));
+}
+
+#[test]
+fn netlist_string_param_substitutes_include_path() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_string_param_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("models_ss.lib"), "R9 out 0 9k\n").unwrap();
+    let top = dir.join("top.cir");
+    std::fs::write(&top, "* top\n.param corner=\"ss\"\n.include \"models_{corner}.lib\"\n.end\n").unwrap();
+
+    let ast = parse_netlist_file(&top);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.instances.len(), 1);
+    assert_eq!(elab.instances[0].name, "R9");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
 #[test]
 fn netlist_poly_is_parsed_into_spec() {
     // 标准 POLY 语法: G1 out 0 POLY(2) ctrl1+ ctrl1- ctrl2+ ctrl2- coeffs
@@ -326,6 +472,64 @@ fn netlist_subckt_body_param_is_applied() {
     assert_eq!(elab.instances[0].value.as_deref(), Some("3000"));
 }
 
+#[test]
+fn netlist_subckt_local_params_shadow_globals_and_reach_nested_definitions() {
+    let input = "\
+.param r=1k
+.subckt outer a b
+.param r=5k rr=r*2
+.subckt inner x y
+R1 x y r
+.ends
+X1 a b inner
+R2 a b rr
+.ends
+X9 n1 n2 outer
+R4 n1 0 r
+.end
+";
+    let ast = parse_netlist(input);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "errors: {:?}", elab.errors);
+    let values: Vec<(&str, Option<&str>)> = elab
+        .instances
+        .iter()
+        .map(|inst| (inst.name.as_str(), inst.value.as_deref()))
+        .collect();
+    assert_eq!(
+        values,
+        vec![("X9.X1.R1", Some("5000")), ("X9.R2", Some("10000")), ("R4", Some("1000"))]
+    );
+}
+
+#[test]
+fn netlist_subckt_local_model_shadows_global_model() {
+    let input = "\
+.param vt=0.3
+.model nch nmos level=1 vto=vt
+.subckt cell d g
+.param vt=0.5
+.model nch nmos level=1 vto=vt
+M1 d g 0 0 nch
+.ends
+X1 d g cell
+M2 d g 0 0 nch
+.end
+";
+    let ast = parse_netlist(input);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "errors: {:?}", elab.errors);
+    let circuit = sim_core::netlist::build_circuit(&ast, &elab);
+    let vto = |name: &str| {
+        let inst = circuit.instances.instances.iter().find(|i| i.name == name).unwrap();
+        let model = &circuit.models.models[inst.model.unwrap().0];
+        model.params.get("vto").cloned()
+    };
+    assert_eq!(circuit.models.models.len(), 2);
+    assert_eq!(vto("X1.M1").as_deref(), Some("0.5"));
+    assert_eq!(vto("M2").as_deref(), Some("0.3"));
+}
+
 #[test]
 fn netlist_elaboration_expands_nested_subckt() {
     let input = ".subckt leaf a b\nR1 a b 1k\n.ends\n.subckt mid in out\nX1 in out leaf\n.ends\nXtop n1 n2 mid\n.end\n";
@@ -338,3 +542,209 @@ fn netlist_elaboration_expands_nested_subckt() {
         vec!["n1".to_string(), "n2".to_string()]
     );
 }
+
+#[test]
+fn netlist_analysis_controls_populate_circuit() {
+    use sim_core::circuit::{AcSweepType, AnalysisCmd, PzTarget, PzTransfer};
+    use sim_core::netlist::build_circuit;
+
+    let input = "* analyses\nV1 in 0 1 AC 1\nR1 in out 1k\nC1 out 0 1n\n\
+.ac dec 10 1 1meg\n.noise V(out,0) V1 oct 5 10 100k\n.tf V(out) V1\n\
+.four 1k V(out) V(in)\n.sens V(out)\n.pz in 0 out 0 vol pz\n.end\n";
+    let ast = parse_netlist(input);
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    assert_eq!(circuit.analysis.len(), 6);
+
+    assert!(matches!(circuit.analysis[0], AnalysisCmd::Ac { points: 10, .. }));
+    match &circuit.analysis[1] {
+        AnalysisCmd::Noise {
+            output,
+            reference,
+            source,
+            sweep_type,
+            points,
+            fstart,
+            fstop,
+        } => {
+            assert_eq!(output, "out");
+            assert_eq!(reference.as_deref(), Some("0"));
+            assert_eq!(source, "V1");
+            assert_eq!(*sweep_type, AcSweepType::Oct);
+            assert_eq!(*points, 5);
+            assert_eq!(*fstart, 10.0);
+            assert_eq!(*fstop, 100e3);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match &circuit.analysis[2] {
+        AnalysisCmd::Tf { output, source } => {
+            assert_eq!(output, "v(out)");
+            assert_eq!(source, "V1");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match &circuit.analysis[3] {
+        AnalysisCmd::Four { freq, outputs } => {
+            assert_eq!(*freq, 1e3);
+            assert_eq!(outputs, &vec!["v(out)".to_string(), "v(in)".to_string()]);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(matches!(&circuit.analysis[4], AnalysisCmd::Sens { outputs } if outputs.len() == 1));
+    match &circuit.analysis[5] {
+        AnalysisCmd::Pz {
+            input_pos,
+            output_pos,
+            transfer,
+            target,
+            ..
+        } => {
+            assert_eq!(input_pos, "in");
+            assert_eq!(output_pos, "out");
+            assert_eq!(*transfer, PzTransfer::Voltage);
+            assert_eq!(*target, PzTarget::Both);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn netlist_multiple_decks_are_split_at_end() {
+    let input = "* first\nV1 a 0 1\nR1 a 0 1k\n.end\n* second\nV2 b 0 2\nY9 b 0\n.end\n* trailing comment\n";
+    let decks = parse_netlist_decks(input);
+    assert_eq!(decks.len(), 2);
+    let names = |ast: &sim_core::netlist::NetlistAst| -> Vec<String> {
+        elaborate_netlist(ast).instances.iter().map(|inst| inst.name.clone()).collect()
+    };
+    assert!(decks[0].errors.is_empty());
+    assert_eq!(names(&decks[0]), vec!["V1", "R1"]);
+    // 行号仍是整个文件中的行号
+    assert_eq!(decks[1].errors.len(), 1);
+    assert_eq!(decks[1].errors[0].line, 7);
+    assert_eq!(names(&decks[1]), vec!["V2", "Y9"]);
+}
+
+#[test]
+fn netlist_end_inside_include_does_not_split_decks() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_decks_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("lib.cir"), "R2 a 0 2k\n.end\n").unwrap();
+    let top = dir.join("top.cir");
+    std::fs::write(&top, "* one\nV1 a 0 1\n.include lib.cir\n.end\n* two\nV2 b 0 1\nR3 b 0 1k\n.end\n").unwrap();
+
+    let decks = parse_netlist_file_decks(&top, &[], NetlistFormat::Spice);
+    assert_eq!(decks.len(), 2);
+    assert_eq!(elaborate_netlist(&decks[0]).instances.len(), 2);
+    assert_eq!(elaborate_netlist(&decks[1]).instances.len(), 2);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn netlist_control_block_is_collected_as_script() {
+    let ast = parse_netlist("* ctl\nV1 a 0 1\n.control\nrun\n* comment\nprint v(a)\n.endc\nR1 a 0 1k\n.end\n");
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let script: Vec<(&str, usize)> =
+        ast.control_script.iter().map(|cmd| (cmd.text.as_str(), cmd.line)).collect();
+    assert_eq!(script, vec![("run", 4), ("print v(a)", 6)]);
+    // 脚本行不作为器件或控制语句解析
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0);
+    assert_eq!(elab.instances.len(), 2);
+
+    let ast = parse_netlist("V1 a 0 1\n.control\nrun\n");
+    assert_eq!(ast.errors.len(), 1);
+    assert_eq!(ast.errors[0].code, sim_core::diagnostic::codes::UNTERMINATED_CONTROL);
+    assert_eq!(ast.errors[0].line, 2);
+}
+
+#[test]
+fn netlist_numbers_ignore_trailing_unit_letters() {
+    let close = |text: &str, expected: f64| {
+        let value = parse_number_with_suffix(text).unwrap_or_else(|| panic!("{} did not parse", text));
+        assert!((value - expected).abs() <= expected.abs() * 1e-12, "{} -> {}", text, value);
+    };
+    close("10pF", 10e-12);
+    close("100nF", 100e-9);
+    close("4.7kOhm", 4.7e3);
+    close("5V", 5.0);
+    close("2.2MegHz", 2.2e6);
+    close("1mA", 1e-3);
+    close("1e-3s", 1e-3);
+    close("2.5E+6", 2.5e6);
+    close("10mil", 254e-6);
+    assert_eq!(parse_number_with_suffix("1n4148"), None);
+    assert_eq!(parse_number_with_suffix("Ohm"), None);
+
+    // 带单位的取值不再被报告为缺少数值
+    let ast = parse_netlist("V1 in 0 5V\nR1 in out 2kOhm\nC1 out 0 10pF\n.param half={2kOhm/2}\nR2 out 0 half\n");
+    assert!(ast.errors.is_empty(), "errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "errors: {:?}", elab.errors);
+    let r2 = elab.instances.iter().find(|inst| inst.name == "R2").unwrap();
+    assert_eq!(parse_number_with_suffix(r2.value.as_deref().unwrap()), Some(1000.0));
+}
+
+#[test]
+fn netlist_gzip_include_is_decompressed() {
+    use std::io::Write;
+
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_gzip_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(b"* compressed models\n.model dmod d is=1e-14\nR2 a 0 2k\nY9 a 0\n").unwrap();
+    std::fs::write(dir.join("models.sp.gz"), encoder.finish().unwrap()).unwrap();
+    let top = dir.join("top.cir");
+    std::fs::write(&top, "V1 a 0 1\n.include models.sp.gz\nD1 a 0 dmod\n.end\n").unwrap();
+
+    let ast = parse_netlist_file(&top);
+    // 诊断指向压缩文件中的原始行
+    assert_eq!(ast.errors.len(), 1, "errors: {:?}", ast.errors);
+    assert_eq!(ast.errors[0].file.as_deref(), Some(dir.join("models.sp.gz").as_path()));
+    assert_eq!(ast.errors[0].line, 4);
+    assert_eq!(ast.errors[0].source_line.as_deref(), Some("Y9 a 0"));
+    let names: Vec<String> = elaborate_netlist(&ast).instances.iter().map(|inst| inst.name.clone()).collect();
+    assert_eq!(names, vec!["V1", "R2", "Y9", "D1"]);
+    assert!(matches!(
+        NetlistFormat::from_path(std::path::Path::new("lib.scs.gz")),
+        NetlistFormat::Spectre
+    ));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn netlist_bus_notation_expands_instances_and_nodes() {
+    let ast = parse_netlist(
+        ".subckt inv a y\nR1 a y 1k\n.ends\n.subckt reg q<1:0> clk\nR1 q<1> clk 1k\nR2 q<0> clk 2k\n.ends\n\
+         Xinv<0:3> d<3:0> o<0:3> inv\nXr r<1:0> clk reg\nXp<0:1> p<0:3> inv\n",
+    );
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "errors: {:?}", elab.errors);
+    let devices: Vec<(String, Vec<String>)> =
+        elab.instances.iter().map(|inst| (inst.name.clone(), inst.nodes.clone())).collect();
+    let expect = |name: &str, nodes: &[&str]| {
+        let found = devices.iter().find(|(n, _)| n == name).unwrap_or_else(|| panic!("{} missing", name));
+        assert_eq!(found.1, nodes, "{}", name);
+    };
+    // 实例 <0:3> 与节点 d<3:0> 按位对应
+    expect("Xinv<0>.R1", &["d<3>", "o<0>"]);
+    expect("Xinv<3>.R1", &["d<0>", "o<3>"]);
+    // 子电路端口中的总线与实例上的总线按位展开
+    expect("Xr.R1", &["r<1>", "clk"]);
+    expect("Xr.R2", &["r<0>", "clk"]);
+    // 位宽为实例数整数倍时按段分配
+    expect("Xp<0>.R1", &["p<0>", "p<1>"]);
+    expect("Xp<1>.R1", &["p<2>", "p<3>"]);
+    assert_eq!(elab.instances.len(), 8);
+
+    let elab = elaborate_netlist(&parse_netlist("R<0:1> a<0:2> 0 1k\n"));
+    assert_eq!(elab.error_count, 1);
+    assert_eq!(elab.errors[0].code, sim_core::diagnostic::codes::NODE_COUNT);
+}
@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use sim_core::newton::{
-    apply_damping, check_convergence, norm2, GminSchedule, NewtonConfig, SourceSchedule,
+    apply_damping, check_convergence, norm2, run_newton_with_stepping, successful_strategy, DcStrategy, GminSchedule,
+    MnaParts, NewtonConfig, SourceSchedule,
 };
+use sim_core::solver::{create_solver, SolverType};
 use sim_core::analysis::estimate_error;
 use sim_core::solver::{LinearSolver, SolverError};
 use sim_core::analysis::estimate_error_weighted;
@@ -108,3 +112,199 @@ fn weighted_error_rejects_large_delta() {
     let est = estimate_error_weighted(&prev, &next, 1e-6, 1e-3);
     assert!(!est.accept);
 }
+
+/// 单节点方程 atan(x - 3) + gmin·x = 0 在 x 处线性化; 从 x = 0 出发直接 Newton 发散
+fn arctan_node(x: &[f64], gmin: f64, _source_scale: f64) -> MnaParts {
+    let v = x.first().copied().unwrap_or(0.0);
+    let f = (v - 3.0).atan() + gmin * v;
+    let g = 1.0 / (1.0 + (v - 3.0).powi(2)) + gmin;
+    (vec![0, 1], vec![0], vec![g], vec![g * v - f], 1)
+}
+
+#[test]
+fn gmin_stepping_recovers_from_divergent_newton() {
+    let mut solver = create_solver(SolverType::Dense, 1);
+
+    let plain = NewtonConfig {
+        gmin_steps: 0,
+        source_steps: 0,
+        pseudo_transient_steps: 0,
+        ..NewtonConfig::default()
+    };
+    let mut x = vec![0.0];
+    let result = run_newton_with_stepping(&plain, &mut x, arctan_node, solver.as_mut());
+    assert!(!result.converged);
+    assert_eq!(result.homotopy.len(), 1);
+
+    let config = NewtonConfig {
+        gmin_steps: 20,
+        gmin_start: 10.0,
+        ..NewtonConfig::default()
+    };
+    let mut x = vec![0.0];
+    let result = run_newton_with_stepping(&config, &mut x, arctan_node, solver.as_mut());
+    assert!(result.converged, "{:?}", result.homotopy);
+    assert!((x[0] - 3.0).abs() < 1e-6, "x = {}", x[0]);
+    // 轨迹: 直接 Newton 失败, 之后 gmin 从 10 开始递减, 最后一步在 config.gmin 上收敛
+    let first = result.homotopy[0];
+    assert!(!first.converged && first.gmin == config.gmin);
+    assert_eq!(result.homotopy[1].gmin, 10.0);
+    let last = result.homotopy.last().unwrap();
+    assert!(last.converged && last.gmin == config.gmin && last.source_scale == 1.0);
+    let converged: Vec<f64> = result.homotopy.iter().skip(1).filter(|s| s.converged).map(|s| s.gmin).collect();
+    assert!(converged.windows(2).all(|w| w[1] < w[0]), "{:?}", result.homotopy);
+    assert_eq!(result.iterations, result.homotopy.iter().map(|s| s.iterations).sum::<usize>());
+    assert_eq!(successful_strategy(&result.homotopy), Some(DcStrategy::GminStepping));
+}
+
+/// 源缩放为 s 时方程为 atan(x - 3s) = 0
+fn scaled_arctan_node(x: &[f64], gmin: f64, source_scale: f64) -> MnaParts {
+    let v = x.first().copied().unwrap_or(0.0);
+    let f = (v - 3.0 * source_scale).atan() + gmin * v;
+    let g = 1.0 / (1.0 + (v - 3.0 * source_scale).powi(2)) + gmin;
+    (vec![0, 1], vec![0], vec![g], vec![g * v - f], 1)
+}
+
+#[test]
+fn source_stepping_ramps_sources_after_gmin_stepping_fails() {
+    let mut solver = create_solver(SolverType::Dense, 1);
+    let config = NewtonConfig {
+        gmin_steps: 0,
+        pseudo_transient_steps: 0,
+        ..NewtonConfig::default()
+    };
+    let mut x = vec![0.0];
+    let result = run_newton_with_stepping(&config, &mut x, scaled_arctan_node, solver.as_mut());
+    assert!(result.converged, "{:?}", result.homotopy);
+    assert!((x[0] - 3.0).abs() < 1e-6);
+    assert_eq!(successful_strategy(&result.homotopy), Some(DcStrategy::SourceStepping));
+    let scales: Vec<f64> = result.homotopy[1..].iter().map(|s| s.source_scale).collect();
+    assert_eq!(scales[0], 0.0);
+    assert_eq!(*scales.last().unwrap(), 1.0);
+    assert!(scales.windows(2).all(|w| w[1] > w[0]), "{:?}", scales);
+}
+
+#[test]
+fn pseudo_transient_is_the_last_resort() {
+    let mut solver = create_solver(SolverType::Dense, 1);
+    let config = NewtonConfig {
+        gmin_steps: 0,
+        source_steps: 0,
+        ..NewtonConfig::default()
+    };
+    let mut x = vec![0.0];
+    let result = run_newton_with_stepping(&config, &mut x, arctan_node, solver.as_mut());
+    assert!(result.converged, "{:?}", result.homotopy);
+    assert!((x[0] - 3.0).abs() < 1e-6);
+    assert_eq!(successful_strategy(&result.homotopy), Some(DcStrategy::PseudoTransient));
+    // 伪电导逐步减小, 最后一步去掉伪电容
+    let last = result.homotopy.last().unwrap();
+    assert_eq!(last.pseudo_conductance, 0.0);
+    assert!(result.homotopy[1].pseudo_conductance > 0.0);
+}
+
+#[test]
+fn failed_fallbacks_report_no_strategy() {
+    let mut solver = create_solver(SolverType::Dense, 1);
+    let config = NewtonConfig {
+        gmin_steps: 0,
+        source_steps: 0,
+        pseudo_transient_steps: 2,
+        ..NewtonConfig::default()
+    };
+    let mut x = vec![0.0];
+    let result = run_newton_with_stepping(&config, &mut x, arctan_node, solver.as_mut());
+    assert!(!result.converged);
+    assert_eq!(successful_strategy(&result.homotopy), None);
+    assert_eq!(result.homotopy.len(), 3);
+}
+
+#[test]
+fn homotopy_options() {
+    let options: HashMap<String, String> = [("gminsteps", "5"), ("gminstart", "1m"), ("gminfactor", "4")]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let config = NewtonConfig::from_options(&options);
+    assert_eq!((config.gmin_steps, config.gmin_start, config.gmin_factor), (5, 1e-3, 4.0));
+    // 不合法的值保持缺省
+    let options: HashMap<String, String> = [("gminfactor", "0.5")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    assert_eq!(NewtonConfig::from_options(&options).gmin_factor, NewtonConfig::default().gmin_factor);
+    let transient = NewtonConfig::transient();
+    assert_eq!((transient.gmin_steps, transient.source_steps, transient.pseudo_transient_steps), (0, 0, 0));
+    let options: HashMap<String, String> = [("srcsteps", "0"), ("ptransteps", "7")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    let config = NewtonConfig::from_options(&options);
+    assert_eq!((config.source_steps, config.pseudo_transient_steps), (0, 7));
+}
+
+#[test]
+fn newton_config_reads_tolerance_and_iteration_options() {
+    let options: HashMap<String, String> = [
+        ("reltol", "1e-3"),
+        ("abstol", "1p"),
+        ("vntol", "1u"),
+        ("itl1", "20"),
+        ("itl4", "7"),
+        ("gmin", "1e-10"),
+    ]
+    .iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    let dc = NewtonConfig::from_options(&options);
+    assert_eq!((dc.rel_tol, dc.abs_tol, dc.vn_tol, dc.gmin), (1e-3, 1e-12, 1e-6, 1e-10));
+    assert_eq!(dc.max_iters, 20);
+    let tran = NewtonConfig::transient_from_options(&options);
+    assert_eq!((tran.rel_tol, tran.abs_tol, tran.vn_tol, tran.gmin), (1e-3, 1e-12, 1e-6, 1e-10));
+    assert_eq!(tran.max_iters, 7);
+    assert_eq!((tran.gmin_steps, tran.source_steps), (0, 0));
+
+    // 没有 vntol 时节点电压沿用 abstol; 非法值忽略
+    let options: HashMap<String, String> = [("abstol", "1e-8"), ("itl1", "0"), ("reltol", "-1")]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let config = NewtonConfig::from_options(&options);
+    assert_eq!((config.abs_tol, config.vn_tol), (1e-8, 1e-8));
+    assert_eq!(config.max_iters, NewtonConfig::default().max_iters);
+    assert_eq!(config.rel_tol, NewtonConfig::default().rel_tol);
+}
+
+#[test]
+fn convergence_uses_vntol_for_node_voltages_only() {
+    let config = NewtonConfig {
+        abs_tol: 1e-12,
+        vn_tol: 1e-6,
+        rel_tol: 0.0,
+        voltage_unknowns: 1,
+        ..NewtonConfig::default()
+    };
+    // 节点电压变化 1e-7 在 vntol 内; 支路电流变化 1e-7 超过 abstol
+    assert!(check_convergence(&[1e-7, 1e-13], &[1.0, 1e-3], &config));
+    assert!(!check_convergence(&[1e-7, 1e-7], &[1.0, 1e-3], &config));
+}
+
+#[test]
+fn itl1_limits_operating_point_iterations() {
+    use sim_core::analysis::AnalysisPlan;
+    use sim_core::engine::Engine;
+    use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+    use sim_core::result_store::{ResultStore, RunStatus};
+
+    let run = |options: &str| {
+        let ast = parse_netlist(&format!(
+            "* diode\nV1 in 0 5\nR1 in a 1k\nD1 a 0 dmod\n.model dmod d is=1e-14\n{}\n.op\n.end\n",
+            options
+        ));
+        let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+        let cmd = circuit.analysis[0].clone();
+        let mut engine = Engine::new_default(circuit);
+        let mut store = ResultStore::new();
+        let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+        store.runs[run_id.0].status
+    };
+    assert!(matches!(run(""), RunStatus::Converged));
+    assert!(!matches!(
+        run(".options itl1=2 gminsteps=0 srcsteps=0 ptransteps=0"),
+        RunStatus::Converged
+    ));
+}
@@ -0,0 +1,97 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::noise::{NoiseMechanism, NoiseResult, BOLTZMANN};
+use sim_core::result_store::{AnalysisType, ResultStore, RunResult, RunStatus};
+
+fn run_noise(netlist: &str) -> RunResult {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "ast errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    store.runs[run_id.0].clone()
+}
+
+fn noise(netlist: &str) -> NoiseResult {
+    let run = run_noise(netlist);
+    assert!(matches!(run.analysis, AnalysisType::Noise));
+    assert!(matches!(run.status, RunStatus::Converged), "run failed: {:?}", run.message);
+    run.noise.expect("noise result")
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * b.abs()
+}
+
+#[test]
+fn divider_noise_ranks_resistors_by_contribution() {
+    let result = noise(
+        "V1 in 0 DC 0 AC 1\nR1 in out 1k\nR2 out 0 3k\n.noise v(out) V1 lin 2 1k 2k\n.end\n",
+    );
+    let four_kt = 4.0 * BOLTZMANN * 300.15;
+    assert_eq!(result.output, "V(out)");
+    assert_eq!(result.frequencies, vec![1e3, 2e3]);
+    for &density in &result.output_density {
+        assert!(close(density, four_kt * 750.0), "density {}", density);
+    }
+    for &density in &result.input_density {
+        assert!(close(density, four_kt * 750.0 / 0.5625), "density {}", density);
+    }
+
+    let devices: Vec<&str> = result.contributions.iter().map(|c| c.device.as_str()).collect();
+    assert_eq!(devices, ["R1", "R2"]);
+    assert!(close(result.contributions[0].total(), four_kt * 562.5 * 1e3));
+    assert!(close(result.contributions[1].total(), four_kt * 187.5 * 1e3));
+    assert!(result.contributions.iter().all(|c| c.dominant() == NoiseMechanism::Thermal));
+    assert!(close(result.total_output_noise(), four_kt * 750.0 * 1e3));
+
+    let table = result.contribution_table(3);
+    let rows: Vec<&str> = table.lines().collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[1].starts_with("R1") && rows[1].contains("75.00") && rows[1].ends_with("thermal"), "{}", table);
+    assert!(rows[2].starts_with("R2") && rows[2].contains("25.00"), "{}", table);
+}
+
+#[test]
+fn diode_noise_is_flicker_at_low_and_shot_at_high_frequency() {
+    let circuit = "V1 in 0 DC 1 AC 1\nR1 in a 1k\nD1 a 0 dmod\n.model dmod d is=1e-14 kf=1e-16\n";
+    let low = noise(&format!("{}.noise v(a) V1 dec 5 1 100\n.end\n", circuit));
+    let high = noise(&format!("{}.noise v(a) V1 dec 5 1meg 10meg\n.end\n", circuit));
+
+    let diode = |result: &NoiseResult| result.contributions.iter().find(|c| c.device == "D1").cloned().expect("D1");
+    assert_eq!(low.contributions[0].device, "D1");
+    assert_eq!(diode(&low).dominant(), NoiseMechanism::Flicker);
+    assert_eq!(diode(&high).dominant(), NoiseMechanism::Shot);
+    assert!(diode(&high).flicker > 0.0);
+    // 1/f: 低频段的输出谱随频率下降
+    assert!(low.output_density[0] > 10.0 * low.output_density.last().copied().unwrap());
+}
+
+#[test]
+fn noise_rejects_unknown_output_and_source() {
+    let circuit = "V1 in 0 DC 0 AC 1\nR1 in out 1k\nR2 out 0 1k\n";
+    let run = run_noise(&format!("{}.noise v(nowhere) V1 lin 2 1k 2k\n.end\n", circuit));
+    assert!(matches!(run.status, RunStatus::Failed));
+    assert!(run.message.unwrap().contains("nowhere"));
+    let run = run_noise(&format!("{}.noise v(out) R1 lin 2 1k 2k\n.end\n", circuit));
+    assert!(matches!(run.status, RunStatus::Failed));
+    assert!(run.message.unwrap().contains("independent source"));
+}
+
+#[test]
+fn mosfet_channel_noise_follows_transconductance() {
+    let result = noise(
+        "VDD vdd 0 DC 3\nVG g 0 DC 1 AC 1\nRD vdd d 10k\nM1 d g 0 0 nch w=10u l=1u\n.model nch nmos level=1 vto=0.5 kp=100u\n.noise v(d) VG lin 2 1k 2k\n.end\n",
+    );
+    let mosfet = result.contributions.iter().find(|c| c.device == "M1").expect("M1");
+    assert_eq!(mosfet.dominant(), NoiseMechanism::Thermal);
+    assert_eq!(mosfet.flicker, 0.0);
+    assert!(mosfet.thermal > 0.0);
+    // 输入折算噪声 = 输出噪声 / |gm·Rout|², 两者都为正且有限
+    assert!(result.input_density.iter().all(|d| d.is_finite() && *d > 0.0));
+    assert!(result.input_density[0] < result.output_density[0]);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::run_first_analysis;
+use sim_core::result_store::{DeviceOpKind, RunResult};
+
+fn node(run: &RunResult, name: &str) -> f64 {
+    let idx = run.node_names.iter().position(|n| n == name).unwrap_or_else(|| panic!("no node {}", name));
+    run.solution[idx]
+}
+
+fn diode_current(run: &RunResult, name: &str) -> f64 {
+    match run.device_op(name).unwrap().kind {
+        DeviceOpKind::Diode { id, .. } => id,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn photodiode_constant_photocurrent_flows_into_load() {
+    // 反偏光电二极管: 光电流由阴极流出器件外部, 在 1k 负载上产生 iph * 1k 的压降
+    let run = run_first_analysis(
+        "\
+V1 vdd 0 5
+R1 vdd k 1k
+D1 0 k pd
+.model pd d is=1e-14 iph=100u
+.op
+.end
+",
+    );
+    let vk = node(&run, "k");
+    assert!((vk - (5.0 - 0.1)).abs() < 1e-6, "V(k) = {}", vk);
+    assert!((diode_current(&run, "D1") + 100e-6).abs() < 1e-9);
+}
+
+#[test]
+fn optocoupler_transfers_led_current_through_light_node() {
+    // LED 光功率 ETA * Id 驱动光电二极管, 电流传输比 CTR = ETA * RESP
+    let netlist = "\
+.subckt opto a k c e
+D1 a k led lightout=light
+D2 e c pd light=light
+.model led d is=1e-20 n=2 eta=0.1
+.model pd d is=1e-14 resp=0.5
+.ends
+VIN in 0 5
+RIN in a 1k
+X1 a 0 c 0 opto
+VCC vcc 0 5
+RL vcc c 1k
+.op
+.end
+";
+    let run = run_first_analysis(netlist);
+    let i_led = diode_current(&run, "X1.D1");
+    assert!(i_led > 1e-3, "LED current = {}", i_led);
+    let light = node(&run, "x1:light");
+    assert!((light - 0.1 * i_led).abs() < 1e-9 * light.max(1.0), "light = {}, Iled = {}", light, i_led);
+    let i_pd = -diode_current(&run, "X1.D2");
+    assert!((i_pd - 0.05 * i_led).abs() < 1e-9, "Ipd = {}, Iled = {}", i_pd, i_led);
+    let vc = node(&run, "c");
+    assert!((vc - (5.0 - 1e3 * i_pd)).abs() < 1e-6, "V(c) = {}", vc);
+}
+
+#[test]
+fn optocoupler_ac_gain_follows_ctr() {
+    let netlist = "\
+VIN in 0 dc 5 ac 1
+RIN in a 1k
+D1 a 0 led lightout=opt
+D2 0 c pd light=opt
+.model led d is=1e-20 n=2 eta=0.1
+.model pd d is=1e-14 resp=0.5
+VCC vcc 0 5
+RL vcc c 1k
+.ac dec 1 1k 10k
+.end
+";
+    let run = run_first_analysis(netlist);
+    let c = run.node_names.iter().position(|n| n == "c").unwrap();
+    assert_eq!(run.ac_solutions.len(), 2);
+    for point in &run.ac_solutions {
+        // v(c) = -RL * CTR * i(LED), i(LED) ≈ 1 / (RIN + rd), rd 约十几欧
+        let gain = 10f64.powf(point[c].0 / 20.0);
+        assert!(gain > 0.048 && gain < 0.05, "gain = {}", gain);
+        assert!((point[c].1.abs() - 180.0).abs() < 1e-6, "phase = {}", point[c].1);
+    }
+}
@@ -0,0 +1,160 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::ordering::{minimum_degree_order, OrderedSolver};
+use sim_core::result_store::{ResultStore, RunStatus};
+use sim_core::solver::{DenseSolver, LinearSolver, SolverType, SparseLuSolver};
+
+fn to_csc(dense: &[Vec<f64>]) -> (Vec<i64>, Vec<i64>, Vec<f64>) {
+    let n = dense.len();
+    let (mut ap, mut ai, mut ax) = (vec![0], Vec::new(), Vec::new());
+    for col in 0..n {
+        for (row, values) in dense.iter().enumerate() {
+            if values[col] != 0.0 {
+                ai.push(row as i64);
+                ax.push(values[col]);
+            }
+        }
+        ap.push(ai.len() as i64);
+    }
+    (ap, ai, ax)
+}
+
+/// 节点 0 与其余所有节点相连的箭形矩阵: 按原序消元会完全填满
+fn arrow(n: usize) -> Vec<Vec<f64>> {
+    let mut dense = vec![vec![0.0; n]; n];
+    for (i, row) in dense.iter_mut().enumerate() {
+        row[i] = n as f64;
+        if i > 0 {
+            row[0] = -1.0;
+        }
+    }
+    for value in &mut dense[0][1..] {
+        *value = -1.0;
+    }
+    dense
+}
+
+#[test]
+fn minimum_degree_eliminates_the_hub_at_the_end() {
+    let n = 30;
+    let (ap, ai, _) = to_csc(&arrow(n));
+    let perm = minimum_degree_order(n, &ap, &ai);
+    assert_eq!(perm.len(), n);
+    // 最后只剩中心与一个叶子时两者度相同, 先消哪个都没有填充
+    assert!(perm[n - 2..].contains(&0), "{:?}", perm);
+    let mut sorted = perm.clone();
+    sorted.sort_unstable();
+    assert_eq!(sorted, (0..n).collect::<Vec<_>>());
+}
+
+#[test]
+fn minimum_degree_orders_large_grid_quickly() {
+    // 100×100 五点差分网格, 直接按列生成 CSC 结构
+    let side = 100;
+    let n = side * side;
+    let (mut ap, mut ai) = (vec![0_i64], Vec::new());
+    for col in 0..n {
+        let (r, c) = (col / side, col % side);
+        let mut rows = vec![col];
+        if r > 0 {
+            rows.push(col - side);
+        }
+        if r + 1 < side {
+            rows.push(col + side);
+        }
+        if c > 0 {
+            rows.push(col - 1);
+        }
+        if c + 1 < side {
+            rows.push(col + 1);
+        }
+        rows.sort_unstable();
+        ai.extend(rows.iter().map(|row| *row as i64));
+        ap.push(ai.len() as i64);
+    }
+    let start = std::time::Instant::now();
+    let perm = minimum_degree_order(n, &ap, &ai);
+    let elapsed = start.elapsed();
+    assert!(elapsed.as_secs_f64() < 10.0, "ordering took {:?}", elapsed);
+    let mut sorted = perm.clone();
+    sorted.sort_unstable();
+    assert_eq!(sorted, (0..n).collect::<Vec<_>>());
+    // 四个角的度最小, 最先消去
+    assert_eq!(perm[0], 0);
+}
+
+#[test]
+fn ordering_removes_fill_from_arrow_matrix() {
+    let n = 30;
+    let dense = arrow(n);
+    let (ap, ai, ax) = to_csc(&dense);
+    let mut natural = SparseLuSolver::new(n);
+    natural.factor(&ap, &ai, &ax).unwrap();
+    assert!(natural.factor_nnz() >= (n - 1) * (n - 1), "{}", natural.factor_nnz());
+
+    let perm = minimum_degree_order(n, &ap, &ai);
+    let permuted: Vec<Vec<f64>> = perm.iter().map(|&r| perm.iter().map(|&c| dense[r][c]).collect()).collect();
+    let (pap, pai, pax) = to_csc(&permuted);
+    let mut ordered = SparseLuSolver::new(n);
+    ordered.factor(&pap, &pai, &pax).unwrap();
+    assert_eq!(ordered.factor_nnz(), 2 * (n - 1));
+}
+
+#[test]
+fn ordered_solver_matches_unordered_solution_and_keeps_permutation() {
+    let n = 12;
+    let mut dense = arrow(n);
+    // 电压源式的零对角元与非对称项
+    dense[5][5] = 0.0;
+    dense[5][7] = 1.0;
+    dense[7][5] = 1.0;
+    dense[3][9] = 0.5;
+    let (ap, ai, ax) = to_csc(&dense);
+    let b: Vec<f64> = (0..n).map(|i| i as f64 - 3.0).collect();
+
+    let mut expected = b.clone();
+    let mut reference = DenseSolver::new(n);
+    reference.factor(&ap, &ai, &ax).unwrap();
+    reference.solve(&mut expected).unwrap();
+
+    for inner in [Box::new(DenseSolver::new(n)) as Box<dyn LinearSolver>, Box::new(SparseLuSolver::new(n))] {
+        let mut solver = OrderedSolver::new(inner);
+        for scale in [1.0, 2.0] {
+            let ax: Vec<f64> = ax.iter().map(|v| v * scale).collect();
+            let mut rhs: Vec<f64> = b.iter().map(|v| v * scale).collect();
+            solver.prepare(n);
+            solver.analyze(&ap, &ai).unwrap();
+            solver.factor(&ap, &ai, &ax).unwrap();
+            solver.solve(&mut rhs).unwrap();
+            for (x, y) in rhs.iter().zip(&expected) {
+                assert!((x - y).abs() < 1e-9 * (1.0 + y.abs()), "{} vs {}", x, y);
+            }
+        }
+        // 结构不变时排序只算一次
+        assert_eq!(solver.ordering.orderings, 1);
+        assert_ne!(solver.ordering.permutation(), (0..n).collect::<Vec<_>>().as_slice());
+    }
+}
+
+#[test]
+fn engine_results_do_not_depend_on_the_backend_under_ordering() {
+    let netlist = "* ladder\nV1 in 0 5\nR1 in n1 1k\nR2 n1 0 2k\nR3 n1 n2 1k\nR4 n2 0 2k\nR5 n2 n3 1k\n\
+                   R6 n3 0 2k\nD1 n3 0 dmod\n.model dmod d is=1e-14\n.op\n.end\n";
+    let solve = |solver_type: SolverType| {
+        let ast = parse_netlist(netlist);
+        let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+        let cmd = circuit.analysis[0].clone();
+        let mut engine = Engine::new(circuit, solver_type);
+        let mut store = ResultStore::new();
+        let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+        let result = store.runs[run_id.0].clone();
+        assert!(matches!(result.status, RunStatus::Converged), "{:?}", result.message);
+        result.solution
+    };
+    let dense = solve(SolverType::Dense);
+    let sparse = solve(SolverType::SparseLu);
+    for (x, y) in dense.iter().zip(&sparse) {
+        assert!((x - y).abs() < 1e-9, "{} vs {}", x, y);
+    }
+}
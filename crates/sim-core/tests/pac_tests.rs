@@ -0,0 +1,128 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::{AcSweepType, AnalysisCmd};
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::result_store::{AnalysisType, ResultStore, RunResult, RunStatus};
+
+/// 运行网表中第一个与 `pick` 匹配的分析
+fn run_analysis(netlist: &str, pick: fn(&AnalysisCmd) -> bool) -> RunResult {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis.iter().find(|cmd| pick(cmd)).cloned().expect("analysis");
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    store.runs[run_id.0].clone()
+}
+
+fn converged(run: &RunResult) {
+    assert!(matches!(run.status, RunStatus::Converged), "run failed: {:?}", run.message);
+}
+
+#[test]
+fn pac_and_pnoise_parse_sweep_and_sidebands() {
+    let ast = parse_netlist(
+        "V1 in 0 DC 1 AC 1\nR1 in 0 1k\n.pss 1k 0\n.pac dec 10 1k 1meg sidebands=2\n.pnoise v(in) V1 lin 3 1k 3k\n.end\n",
+    );
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    assert!(matches!(
+        circuit.analysis[1],
+        AnalysisCmd::Pac { sweep_type: AcSweepType::Dec, points: 10, sidebands: 2, .. }
+    ));
+    match &circuit.analysis[2] {
+        AnalysisCmd::Pnoise { output, reference, source, points, fstart, fstop, sidebands, .. } => {
+            assert_eq!(output, "in");
+            assert_eq!(*reference, None);
+            assert_eq!(source, "V1");
+            assert_eq!((*points, *fstart, *fstop, *sidebands), (3, 1e3, 3e3, 3));
+        }
+        other => panic!("expected .pnoise, got {:?}", other),
+    }
+}
+
+#[test]
+fn pac_of_time_invariant_circuit_matches_ac() {
+    let circuit = "V1 in 0 DC 1 AC 1\nR1 in out 1k\nC1 out 0 100n\nR2 out 0 4k\n.pss 10k 0 out 32\n";
+    let pac = run_analysis(
+        &format!("{}.pac dec 3 100 100k sidebands=2\n.end\n", circuit),
+        |cmd| matches!(cmd, AnalysisCmd::Pac { .. }),
+    );
+    let ac = run_analysis(
+        &format!("{}.ac dec 3 100 100k\n.end\n", circuit),
+        |cmd| matches!(cmd, AnalysisCmd::Ac { .. }),
+    );
+    converged(&pac);
+    converged(&ac);
+    assert!(matches!(pac.analysis, AnalysisType::Pac));
+    assert_eq!(pac.ac_frequencies, ac.ac_frequencies);
+
+    let out = pac.node_names.iter().position(|name| name == "out").unwrap();
+    let result = pac.pac.as_ref().expect("pac result");
+    assert_eq!(result.sidebands, vec![-2, -1, 0, 1, 2]);
+    assert_eq!(result.sideband_frequency(0, 1), Some(100.0 + 10e3));
+    for (point, phasors) in ac.ac_phasors.iter().enumerate() {
+        let expected = phasors[out];
+        let got = result.sideband_voltage(out, point, 0).unwrap();
+        assert!((got - expected).norm() < 1e-9, "f={} {} vs {}", ac.ac_frequencies[point], got, expected);
+        assert!((pac.ac_phasors[point][out] - expected).norm() < 1e-9);
+        for sideband in [-2, -1, 1, 2] {
+            assert!(result.sideband_voltage(out, point, sideband).unwrap().norm() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn pac_diode_mixer_converts_to_sidebands() {
+    // 大信号本振使二极管电导周期变化, 小信号在 f ± f0 上出现变频分量
+    let run = run_analysis(
+        "VLO lo 0 PULSE(0 2 0 100u 100u 400u 1m)\nVRF rf 0 DC 0 AC 1\nR1 lo a 1k\nR2 rf a 1k\nD1 a 0 dmod\n.model dmod d is=1e-14\n.pss 1k 0 a 64\n.pac lin 1 100 100 sidebands=2\n.end\n",
+        |cmd| matches!(cmd, AnalysisCmd::Pac { .. }),
+    );
+    converged(&run);
+    let a = run.node_names.iter().position(|name| name == "a").unwrap();
+    let result = run.pac.as_ref().unwrap();
+    let baseband = result.sideband_voltage(a, 0, 0).unwrap().norm();
+    let upper = result.sideband_voltage(a, 0, 1).unwrap().norm();
+    let lower = result.sideband_voltage(a, 0, -1).unwrap().norm();
+    assert!(baseband > 0.0 && baseband < 0.5, "baseband {}", baseband);
+    assert!(upper > 1e-3 * baseband, "upper sideband {}", upper);
+    assert!(lower > 1e-3 * baseband, "lower sideband {}", lower);
+}
+
+#[test]
+fn pnoise_of_time_invariant_circuit_matches_noise() {
+    let circuit = "V1 in 0 DC 0 AC 1\nR1 in out 1k\nR2 out 0 3k\nC1 out 0 10n\n.pss 10k 0 out 32\n";
+    let pnoise = run_analysis(
+        &format!("{}.pnoise v(out) V1 dec 2 100 100k sidebands=2\n.end\n", circuit),
+        |cmd| matches!(cmd, AnalysisCmd::Pnoise { .. }),
+    );
+    let noise = run_analysis(
+        &format!("{}.noise v(out) V1 dec 2 100 100k\n.end\n", circuit),
+        |cmd| matches!(cmd, AnalysisCmd::Noise { .. }),
+    );
+    converged(&pnoise);
+    assert!(matches!(pnoise.analysis, AnalysisType::Pnoise));
+    let periodic = pnoise.noise.expect("pnoise result");
+    let stationary = noise.noise.expect("noise result");
+    assert_eq!(periodic.frequencies, stationary.frequencies);
+    for (got, expected) in periodic.output_density.iter().zip(&stationary.output_density) {
+        assert!((got - expected).abs() <= 1e-6 * expected, "{} vs {}", got, expected);
+    }
+    for (got, expected) in periodic.input_density.iter().zip(&stationary.input_density) {
+        assert!((got - expected).abs() <= 1e-6 * expected, "{} vs {}", got, expected);
+    }
+    let devices: Vec<&str> = periodic.contributions.iter().map(|c| c.device.as_str()).collect();
+    assert_eq!(devices, ["R1", "R2"]);
+}
+
+#[test]
+fn periodic_analyses_need_a_pss() {
+    let run = run_analysis(
+        "V1 in 0 DC 1 AC 1\nR1 in 0 1k\n.pac lin 1 1k 1k\n.end\n",
+        |cmd| matches!(cmd, AnalysisCmd::Pac { .. }),
+    );
+    assert!(matches!(run.status, RunStatus::Failed));
+    assert!(run.message.unwrap().contains(".pss"));
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{node_index, run_first_analysis};
+use sim_core::check::check_netlist;
+use sim_core::diagnostic::codes;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist, DeviceKind, Stmt};
+use sim_core::spectre::spectre_to_spice;
+
+#[test]
+fn port_element_parses_number_impedance_and_sources() {
+    let ast = parse_netlist("P1 in 0 port=1 z0=75 DC 2 AC 1 90\nP2 out 0\nP3 a 0 port=0 z0=-5\nP4 b 0 port=4 foo\n");
+    let device = ast
+        .statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Device(device) if device.name == "P1" => Some(device),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(device.kind, DeviceKind::P));
+    assert_eq!(device.nodes, vec!["in", "0"]);
+    assert_eq!(device.value.as_deref(), Some("2"));
+    assert_eq!((device.ac_mag, device.ac_phase), (Some(1.0), Some(90.0)));
+
+    let errors: Vec<(usize, &str)> = ast.errors.iter().map(|diag| (diag.line, diag.code)).collect();
+    assert_eq!(
+        errors,
+        vec![
+            (2, codes::MISSING_VALUE),
+            (3, codes::MISSING_VALUE),
+            (3, codes::MISSING_VALUE),
+            (4, codes::EXTRA_FIELDS),
+        ]
+    );
+}
+
+#[test]
+fn port_drives_matched_load_through_reference_impedance() {
+    let run = run_first_analysis(
+        "\
+* 源电压 2V 经 50 Ohm 驱动 50 Ohm 负载
+P1 in 0 port=1 DC 2 AC 1
+RL in 0 50
+P2 out 0 port=2 z0=100
+R2 in out 100
+.op
+.end
+",
+    );
+    let vin = run.solution[node_index(&run, "in")];
+    let vout = run.solution[node_index(&run, "out")];
+    // RL 与 (R2 + 100 Ohm 端接) 并联为 40 Ohm
+    let expected = 2.0 * 40.0 / 90.0;
+    assert!((vin - expected).abs() < 1e-9, "vin = {}", vin);
+    assert!((vout - expected / 2.0).abs() < 1e-9, "vout = {}", vout);
+}
+
+#[test]
+fn port_ac_excitation_sees_matched_termination() {
+    let run = run_first_analysis(
+        "\
+P1 in 0 port=1 z0=50 AC 2 45
+P2 out 0 port=2 z0=50
+T1 in 0 out 0 z0=50 td=1n
+.ac lin 3 100meg 300meg
+.end
+",
+    );
+    let vin = node_index(&run, "in");
+    // 匹配的传输线输入端呈现 50 Ohm, 入射电压为源电压的一半
+    for point in &run.ac_solutions {
+        let (db, phase) = point[vin];
+        assert!(db.abs() < 1e-6, "|Vin| = {} dB", db);
+        assert!((phase - 45.0).abs() < 1e-6, "phase = {}", phase);
+    }
+}
+
+#[test]
+fn circuit_lists_ports_by_number() {
+    let netlist = "\
+P2 out 0 port=2 z0=75
+P1 in 0 port=1
+R1 in out 1k
+.op
+.end
+";
+    let ast = parse_netlist(netlist);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let ports = circuit.ports();
+    let summary: Vec<(String, usize, f64)> = ports
+        .iter()
+        .map(|port| (circuit.instances.instances[port.instance.0].name.clone(), port.number, port.z0))
+        .collect();
+    assert_eq!(summary, vec![("P1".to_string(), 1, 50.0), ("P2".to_string(), 2, 75.0)]);
+    assert!(check_netlist(&ast, &elab).is_empty());
+
+    let duplicate = "P1 in 0 port=1\nP2 out 0 port=1\nR1 in out 1k\n.end\n";
+    let ast = parse_netlist(duplicate);
+    let elab = elaborate_netlist(&ast);
+    let findings = check_netlist(&ast, &elab);
+    assert_eq!(findings.len(), 1, "{:?}", findings);
+    assert_eq!(findings[0].code, codes::DUPLICATE_PORT);
+    assert_eq!(findings[0].line, 2);
+}
+
+#[test]
+fn spectre_port_translates_to_port_element() {
+    let (spice, errors) = spectre_to_spice("PORT1 (in 0) port r=75 num=2 dc=1 mag=1\n");
+    assert!(errors.is_empty(), "errors: {:?}", errors);
+    assert_eq!(spice.lines().next(), Some("PORT1 in 0 PORT=2 Z0=75 DC 1 AC 1 0"));
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::{node_index, run_first_analysis};
+
+#[test]
+fn vdmos_channel_and_body_diode_operating_point() {
+    // 导通: Vgs = 10, Vov = 7, 负载 10 Ω 时处于线性区; RD 形成内部漏极节点
+    let run = run_first_analysis(
+        "\
+VDD vdd 0 20
+VG g 0 10
+RL vdd d 10
+M1 d g 0 pwr
+.model pwr vdmos vto=3 kp=2 rd=0.05
+.op
+.end
+",
+    );
+    let vd = run.solution[node_index(&run, "d")];
+    let vdi = run.solution[node_index(&run, "m1#d")];
+    let id = (20.0 - vd) / 10.0;
+    assert!((id - 2.0 * (7.0 * vdi - vdi * vdi / 2.0)).abs() < 1e-6 * id, "Id = {}, V(d') = {}", id, vdi);
+    assert!(((vd - vdi) / 0.05 - id).abs() < 1e-6 * id);
+
+    // 关断时反向电流流过体二极管
+    let run = run_first_analysis(
+        "\
+I1 d 0 1
+VG g 0 0
+M1 d g 0 pwr
+.model pwr vdmos vto=3 kp=2 is=1e-12
+.op
+.end
+",
+    );
+    let vd = run.solution[node_index(&run, "d")];
+    assert!(vd < -0.5 && vd > -0.8, "V(d) = {}", vd);
+}
+
+#[test]
+fn igbt_collector_current_includes_pnp_gain() {
+    let run = run_first_analysis(
+        "\
+VCC vcc 0 100
+VG g 0 15
+RL vcc c 10
+Z1 c g 0 sw
+.model sw nigbt vto=5 kp=0.5 bf=3 is=1e-12
+.op
+.end
+",
+    );
+    let v = |node: &str| run.solution[node_index(&run, node)];
+    let ic = (100.0 - v("c")) / 10.0;
+    // 沟道电流即 PNP 基极电流, 集电极电流为 (1 + BF) 倍
+    let vb = v("z1#b");
+    let ich = 0.5 * (10.0 * vb - vb * vb / 2.0);
+    assert!((ic - 4.0 * ich).abs() < 1e-6 * ic, "Ic = {}, Ich = {}", ic, ich);
+    // 饱和压降包含集电结的一个结压降
+    assert!(v("c") - vb > 0.5, "Vce = {}, Vb = {}", v("c"), vb);
+}
+
+#[test]
+fn vdmos_input_pole_follows_miller_capacitance() {
+    // 关断的 VDMOS 栅极经 1k 驱动, 输入电容 CGS + Cgd(Vdg): 漏极高压时 Miller 电容降到 CGDMIN
+    let netlist = |vd: f64| {
+        format!(
+            "\
+VIN in 0 dc 0 ac 1
+RG in g 1k
+VD d 0 {}
+M1 d g 0 pwr
+.model pwr vdmos vto=3 kp=2 cgs=1n cgdmax=2n cgdmin=0.1n a=1
+.ac lin 1 100k 100k
+.end
+",
+            vd
+        )
+    };
+    let w = 2.0 * std::f64::consts::PI * 100e3;
+    for (vd, c) in [(100.0, 1.1e-9), (0.0, 1.0e-9 + 1.05e-9)] {
+        let run = run_first_analysis(&netlist(vd));
+        let (db, _) = run.ac_solutions[0][node_index(&run, "g")];
+        let expected = 1.0 / (1.0 + (w * 1e3 * c).powi(2)).sqrt();
+        let gain = 10f64.powf(db / 20.0);
+        assert!((gain - expected).abs() < 1e-4, "Vd = {}: {} vs {}", vd, gain, expected);
+    }
+}
+
+#[test]
+fn igbt_transient_holds_operating_point() {
+    let run = run_first_analysis(
+        "\
+VCC vcc 0 100
+VG g 0 15
+RL vcc c 10
+Z1 c g 0 sw
+.model sw nigbt vto=5 kp=0.5 bf=3 is=1e-12 tt=1u cge=2n cgdmax=1n cgdmin=50p
+.tran 1n 10n
+.end
+",
+    );
+    let c = node_index(&run, "c");
+    let first = run.tran_solutions[0][c];
+    assert!(run.tran_solutions.len() > 2);
+    for solution in &run.tran_solutions {
+        assert!((solution[c] - first).abs() < 1e-6, "V(c) = {}", solution[c]);
+    }
+}
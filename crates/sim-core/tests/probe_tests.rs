@@ -0,0 +1,80 @@
+mod common;
+
+use common::{node_index, run_first_analysis};
+use sim_core::netlist::{parse_netlist, probed_devices};
+
+#[test]
+fn probe_inserts_ammeters_for_two_terminal_devices() {
+    let run = run_first_analysis(
+        "\
+V1 in 0 5
+R1 in mid 1k
+R2 mid 0 4k
+D1 mid 0 dmod
+.model dmod d is=1e-14
+.probe I(R1) i(R2) I(D1) I(V1)
+.op
+.end
+",
+    );
+    let current = |name: &str| run.branch_current(name, &run.solution).unwrap();
+    let mid = run.solution[node_index(&run, "mid")];
+    let i_r1 = (5.0 - mid) / 1e3;
+    assert!((current("R1") - i_r1).abs() < 1e-12, "I(R1) = {}", current("R1"));
+    assert!((current("r2") - mid / 4e3).abs() < 1e-12);
+    // 流入第一个端口为正: 二极管正向电流为正, 电源输出电流为负
+    assert!((current("D1") - (i_r1 - mid / 4e3)).abs() < 1e-9, "I(D1) = {}", current("D1"));
+    assert!((current("V1") + i_r1).abs() < 1e-12, "I(V1) = {}", current("V1"));
+    // 电压源本身已有支路电流, 不串入探针
+    assert!(!run.branch_names.iter().any(|name| name == "V1#i"));
+    assert_eq!(run.branch_current("R9", &run.solution), None);
+}
+
+#[test]
+fn output_params_referencing_currents_probe_automatically() {
+    let netlist = "\
+V1 in 0 2
+X1 in 0 load
+.subckt load a b
+R1 a b 1k
+.ends
+.csparam p=v(in)*i(x1.r1)
+.op
+.end
+";
+    let ast = parse_netlist(netlist);
+    assert_eq!(probed_devices(&ast), vec!["x1.r1".to_string()]);
+    let run = run_first_analysis(netlist);
+    let p = run.output_params.iter().find(|(name, _)| name == "p").map(|(_, value)| *value);
+    assert!(p.is_some_and(|p| (p - 4e-3).abs() < 1e-12), "output params: {:?}", run.output_params);
+}
+
+#[test]
+fn probe_currents_follow_transient_and_dc_sweep() {
+    let run = run_first_analysis(
+        "\
+V1 in 0 1
+R1 in out 1k
+R2 out 0 1k
+C1 out 0 1n
+.probe I(C1) I(R1) I(R2)
+.tran 1n 10n
+.end
+",
+    );
+    assert!(run.tran_solutions.len() > 1);
+    for x in &run.tran_solutions {
+        // 从工作点出发保持稳态: 电容无电流, 两电阻电流相同
+        assert!(run.branch_current("C1", x).unwrap().abs() < 1e-12);
+        assert!((run.branch_current("R1", x).unwrap() - 0.5e-3).abs() < 1e-12);
+        assert!((run.branch_current("R2", x).unwrap() - 0.5e-3).abs() < 1e-12);
+    }
+
+    let run = run_first_analysis("V1 in 0 0\nR1 in 0 2k\n.probe I(R1)\n.dc V1 0 4 2\n.end\n");
+    let currents: Vec<f64> = run
+        .sweep_solutions
+        .iter()
+        .map(|x| run.branch_current("R1", x).unwrap())
+        .collect();
+    assert_eq!(currents, vec![0.0, 1e-3, 2e-3]);
+}
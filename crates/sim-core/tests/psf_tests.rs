@@ -14,10 +14,23 @@ fn psf_text_writer_outputs_basic_content() {
         sweep_var: None,
         sweep_values: Vec::new(),
         sweep_solutions: Vec::new(),
+        sweep_outer_var: None,
+        sweep_outer_values: Vec::new(),
         tran_times: Vec::new(),
         tran_solutions: Vec::new(),
         ac_frequencies: Vec::new(),
         ac_solutions: Vec::new(),
+        ac_phasors: Vec::new(),
+        output_params: Vec::new(),
+        measurements: Vec::new(),
+        branch_names: Vec::new(),
+        device_op: Vec::new(),
+        homotopy: Vec::new(),
+        convergence: None,
+        sparams: None,
+        noise: None,
+        pac: None,
+        sampled_params: Vec::new(),
     };
     let run_id = store.add_run(run);
 
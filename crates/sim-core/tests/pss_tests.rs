@@ -0,0 +1,84 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::AnalysisCmd;
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::result_store::{AnalysisType, ResultStore, RunResult, RunStatus};
+
+fn run_netlist(netlist: &str) -> RunResult {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "elaboration errors");
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    store.runs[run_id.0].clone()
+}
+
+fn node_wave(run: &RunResult, node: &str) -> Vec<f64> {
+    let idx = run.node_names.iter().position(|name| name == node).unwrap();
+    run.tran_solutions.iter().map(|solution| solution[idx]).collect()
+}
+
+#[test]
+fn pss_parses_ngspice_positional_arguments() {
+    let ast = parse_netlist("V1 in 0 DC 1\n.pss 1k 2m out 256 10 30 1e-4\n.end\n");
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    match &circuit.analysis[0] {
+        AnalysisCmd::Pss { freq, tstab, points, max_iters, tol } => {
+            assert_eq!(*freq, 1e3);
+            assert!((tstab - 2e-3).abs() < 1e-15);
+            assert_eq!(*points, 256);
+            assert_eq!(*max_iters, 30);
+            assert_eq!(*tol, 1e-4);
+        }
+        other => panic!("expected .pss, got {:?}", other),
+    }
+
+    let ast = parse_netlist("V1 in 0 DC 1\n.pss 50 0\n.end\n");
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    assert!(matches!(circuit.analysis[0], AnalysisCmd::Pss { points: 128, max_iters: 20, .. }));
+}
+
+#[test]
+fn pss_rc_square_wave_reaches_periodic_steady_state() {
+    // τ = RC = 1 ms = 周期: 普通瞬态要好几个周期才稳定, 打靶法一次 Newton 即可 (线性电路)
+    let run = run_netlist(
+        "V1 in 0 PULSE(0 1 0 10u 10u 490u 1m)\nR1 in out 1k\nC1 out 0 1u\n.pss 1k 0 out 100\n.end\n",
+    );
+    assert!(matches!(run.analysis, AnalysisType::Pss));
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    assert!(run.iterations <= 2, "iterations = {}", run.iterations);
+    assert_eq!(run.tran_times.len(), 101);
+    assert!((run.tran_times[100] - run.tran_times[0] - 1e-3).abs() < 1e-12);
+
+    let out = node_wave(&run, "out");
+    assert!((out[0] - out[100]).abs() < 1e-3, "not periodic: {} vs {}", out[0], out[100]);
+    // 理想方波下的稳态极值为 1/(1+e^0.5) 与 e^0.5/(1+e^0.5)
+    let max = out.iter().cloned().fold(f64::MIN, f64::max);
+    let min = out.iter().cloned().fold(f64::MAX, f64::min);
+    assert!((max - 0.6225).abs() < 0.02, "max = {}", max);
+    assert!((min - 0.3775).abs() < 0.02, "min = {}", min);
+}
+
+#[test]
+fn pss_diode_rectifier_converges() {
+    let run = run_netlist(
+        "V1 in 0 PULSE(-2 2 0 100u 100u 400u 1m)\nD1 in out dmod\nR1 out 0 10k\nC1 out 0 1u\n\
+         .model dmod D IS=1e-14\n.pss 1k 1m out 100\n.end\n",
+    );
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    let out = node_wave(&run, "out");
+    assert!((out[0] - out[100]).abs() < 1e-2, "not periodic: {} vs {}", out[0], out[100]);
+    // 峰值检波: 输出保持在峰值减去一个二极管压降附近
+    assert!(out.iter().all(|v| *v > 0.8 && *v < 2.0), "{:?}", out);
+}
+
+#[test]
+fn pss_rejects_invalid_parameters() {
+    let run = run_netlist("V1 in 0 DC 1\nR1 in 0 1k\n.pss 0 0\n.end\n");
+    assert!(matches!(run.status, RunStatus::Failed));
+    assert!(run.message.unwrap().contains("invalid .pss"));
+}
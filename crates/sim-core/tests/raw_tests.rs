@@ -15,10 +15,23 @@ fn make_op_result() -> RunResult {
         sweep_var: None,
         sweep_values: Vec::new(),
         sweep_solutions: Vec::new(),
+        sweep_outer_var: None,
+        sweep_outer_values: Vec::new(),
         tran_times: Vec::new(),
         tran_solutions: Vec::new(),
         ac_frequencies: Vec::new(),
         ac_solutions: Vec::new(),
+        ac_phasors: Vec::new(),
+        output_params: Vec::new(),
+        measurements: Vec::new(),
+        branch_names: Vec::new(),
+        device_op: Vec::new(),
+        homotopy: Vec::new(),
+        convergence: None,
+        sparams: None,
+        noise: None,
+        pac: None,
+        sampled_params: Vec::new(),
     }
 }
 
@@ -170,3 +183,125 @@ fn raw_format_filters_ground_node() {
     assert!(content.contains("v(in)"));
     assert!(content.contains("v(out)"));
 }
+
+/// 按 ngspice ASCII raw 的结构读回: (变量名与类型, 每个点的取值字符串)
+fn read_raw(content: &str) -> (Vec<(String, String)>, Vec<Vec<String>>) {
+    let header = |key: &str| -> usize {
+        let line = content.lines().find(|line| line.starts_with(key)).expect(key);
+        line[key.len()..].trim().parse().unwrap()
+    };
+    let num_vars = header("No. Variables:");
+    let num_points = header("No. Points:");
+    let mut lines = content.lines().skip_while(|line| *line != "Variables:").skip(1);
+    let variables: Vec<(String, String)> = (&mut lines)
+        .take(num_vars)
+        .map(|line| {
+            let fields: Vec<&str> = line.split('\t').filter(|f| !f.is_empty()).collect();
+            (fields[1].to_string(), fields[2].to_string())
+        })
+        .collect();
+    assert_eq!(lines.next(), Some("Values:"));
+    let values: Vec<String> = lines.flat_map(|line| line.split_whitespace().map(str::to_string).collect::<Vec<_>>()).collect();
+    let points: Vec<Vec<String>> = values.chunks(num_vars + 1).map(|chunk| chunk[1..].to_vec()).collect();
+    assert_eq!(points.len(), num_points);
+    for (idx, chunk) in values.chunks(num_vars + 1).enumerate() {
+        assert_eq!(chunk[0], idx.to_string());
+        assert_eq!(chunk.len(), num_vars + 1);
+    }
+    (variables, points)
+}
+
+fn simulate(netlist: &str) -> RunResult {
+    use sim_core::analysis::AnalysisPlan;
+    use sim_core::engine::Engine;
+    use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+    use sim_core::result_store::ResultStore;
+
+    let ast = parse_netlist(netlist);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let cmd = circuit.analysis.first().cloned().unwrap_or(sim_core::circuit::AnalysisCmd::Op);
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = store.runs[run_id.0].clone();
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    run
+}
+
+fn write_and_read(run: &RunResult, file: &str) -> String {
+    let path = std::env::temp_dir().join(file);
+    raw::write_raw(run, &path, 12).unwrap();
+    std::fs::read_to_string(&path).unwrap()
+}
+
+#[test]
+fn write_raw_op_includes_branch_currents() {
+    let run = simulate("V1 in 0 DC 2\nR1 in out 1k\nR2 out 0 1k\n.op\n.end\n");
+    let content = write_and_read(&run, "myspice_write_raw_op.raw");
+    assert!(content.contains("Plotname: Operating Point"));
+    let (variables, points) = read_raw(&content);
+    let names: Vec<&str> = variables.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["v(in)", "v(out)", "i(V1)"]);
+    assert_eq!(variables[2].1, "current");
+    let current: f64 = points[0][2].parse().unwrap();
+    assert!((current + 1e-3).abs() < 1e-12, "{}", current);
+}
+
+#[test]
+fn write_raw_ac_uses_exact_phasors() {
+    let run = simulate("V1 in 0 DC 0 AC 1\nR1 in out 1k\nC1 out 0 1u\n.ac dec 2 10 1k\n.end\n");
+    let content = write_and_read(&run, "myspice_write_raw_ac.raw");
+    assert!(content.contains("Flags: complex"));
+    let (variables, points) = read_raw(&content);
+    assert_eq!(variables[0], ("frequency".to_string(), "frequency".to_string()));
+    assert_eq!(variables.last().unwrap(), &("i(V1)".to_string(), "current".to_string()));
+    let out = run.node_names.iter().position(|name| name == "out").unwrap();
+    let column = variables.iter().position(|(name, _)| name == "v(out)").unwrap();
+    for (point, values) in points.iter().enumerate() {
+        let (re, im) = values[column].split_once(',').unwrap();
+        let expected = run.ac_phasors[point][out];
+        assert!((re.parse::<f64>().unwrap() - expected.re).abs() < 1e-12 * expected.norm());
+        assert!((im.parse::<f64>().unwrap() - expected.im).abs() < 1e-12 * expected.norm());
+    }
+}
+
+#[test]
+fn write_raw_dc_and_tran_plots() {
+    let dc = simulate("V1 in 0 DC 0\nR1 in 0 1k\n.dc V1 0 1 0.5\n.end\n");
+    let (variables, points) = read_raw(&write_and_read(&dc, "myspice_write_raw_dc.raw"));
+    assert_eq!(variables[0], ("v1".to_string(), "voltage".to_string()));
+    assert_eq!(points.len(), 3);
+    assert_eq!(variables.last().unwrap().0, "i(V1)");
+
+    let tran = simulate("V1 in 0 DC 1\nR1 in out 1k\nR2 out 0 1k\n.tran 1u 5u\n.end\n");
+    let content = write_and_read(&tran, "myspice_write_raw_tran.raw");
+    assert!(content.contains("Plotname: Transient Analysis"));
+    let (variables, points) = read_raw(&content);
+    assert_eq!(variables[0], ("time".to_string(), "time".to_string()));
+    assert_eq!(points.len(), tran.tran_times.len());
+    let out = variables.iter().position(|(name, _)| name == "v(out)").unwrap();
+    assert!(points.iter().all(|values| (values[out].parse::<f64>().unwrap() - 0.5).abs() < 1e-9));
+}
+
+#[test]
+fn write_raw_noise_spectra() {
+    let run = simulate("V1 in 0 DC 0 AC 1\nR1 in out 1k\nR2 out 0 1k\n.noise v(out) V1 lin 2 1k 2k\n.end\n");
+    let content = write_and_read(&run, "myspice_write_raw_noise.raw");
+    assert!(content.contains("Plotname: Noise Spectral Density Curves"));
+    let (variables, points) = read_raw(&content);
+    let names: Vec<&str> = variables.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["frequency", "onoise_spectrum", "inoise_spectrum"]);
+    let noise = run.noise.as_ref().unwrap();
+    let onoise: f64 = points[0][1].parse().unwrap();
+    assert!((onoise - noise.output_density[0].sqrt()).abs() < 1e-9 * onoise);
+}
+
+#[test]
+fn write_raw_op_without_nodes_does_not_panic() {
+    let mut run = make_op_result();
+    run.node_names = vec!["0".to_string()];
+    run.solution = vec![0.0];
+    let content = write_and_read(&run, "myspice_write_raw_empty.raw");
+    assert!(content.contains("No. Variables: 0"));
+    assert!(content.ends_with("Values:\n 0\n"));
+}
@@ -1,3 +1,7 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::AnalysisCmd;
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
 use sim_core::result_store::{AnalysisType, ResultStore, RunResult, RunStatus};
 
 #[test]
@@ -14,12 +18,57 @@ fn result_store_adds_run() {
         sweep_var: None,
         sweep_values: Vec::new(),
         sweep_solutions: Vec::new(),
+        sweep_outer_var: None,
+        sweep_outer_values: Vec::new(),
         tran_times: Vec::new(),
         tran_solutions: Vec::new(),
         ac_frequencies: Vec::new(),
         ac_solutions: Vec::new(),
+        ac_phasors: Vec::new(),
+        output_params: Vec::new(),
+        measurements: Vec::new(),
+        branch_names: Vec::new(),
+        device_op: Vec::new(),
+        homotopy: Vec::new(),
+        convergence: None,
+        sparams: None,
+        noise: None,
+        pac: None,
+        sampled_params: Vec::new(),
     };
     let id = store.add_run(run);
     assert_eq!(id.0, 0);
     assert_eq!(store.runs.len(), 1);
 }
+
+#[test]
+fn result_store_records_output_params_from_simulation() {
+    let ast = parse_netlist(
+        "V1 in 0 3\nR1 in out 1k\nR2 out 0 2k\n.param k=2\n.param gain={v(out)/v(in)}\n\
+         .csparam drop={k*v(in,out)} pct={gain*100} bad={v(nosuch)}\n.op\n",
+    );
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "errors: {:?}", elab.errors);
+    let mut engine = Engine::new_default(build_circuit(&ast, &elab));
+    let mut store = ResultStore::new();
+
+    let op = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    let params = &store.runs[op.0].output_params;
+    let names: Vec<&str> = params.iter().map(|(name, _)| name.as_str()).collect();
+    // 依赖不存在节点的参数被跳过
+    assert_eq!(names, vec!["gain", "drop", "pct"]);
+    assert!((params[0].1 - 2.0 / 3.0).abs() < 1e-9);
+    assert!((params[1].1 - 2.0).abs() < 1e-9);
+    assert!((params[2].1 - 200.0 / 3.0).abs() < 1e-6);
+
+    // 扫描取最后一点
+    let cmd = AnalysisCmd::Dc {
+        source: "V1".to_string(),
+        start: 0.0,
+        stop: 6.0,
+        step: 3.0,
+    };
+    let dc = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let drop = store.runs[dc.0].output_params.iter().find(|(name, _)| name == "drop").unwrap().1;
+    assert!((drop - 4.0).abs() < 1e-9, "drop = {}", drop);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::run_first_analysis;
+use sim_core::result_store::{DeviceOpKind, RunResult};
+
+fn netlist(thermal: &str, analysis: &str) -> String {
+    format!(
+        "\
+VD d 0 1.8
+VG g 0 1.2
+M1 d g 0 0 nch w=10u l=1u {}
+.model nch nmos level=49
+{}
+.end
+",
+        thermal, analysis
+    )
+}
+
+fn drain_current(run: &RunResult) -> f64 {
+    match run.device_op("M1").unwrap().kind {
+        DeviceOpKind::Mosfet { ids, .. } => ids,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn self_heating_raises_temperature_by_power_times_rth() {
+    let cold = run_first_analysis(&netlist("", ".op"));
+    assert!(!cold.node_names.iter().any(|name| name == "m1#t"));
+
+    let hot = run_first_analysis(&netlist("rth=2k", ".op"));
+    let t = hot.node_names.iter().position(|name| name == "m1#t").expect("thermal node");
+    let rise = hot.solution[t];
+    let ids = drain_current(&hot);
+    // 稳态: ΔT = Ids · Vds · RTH
+    assert!((rise - ids * 1.8 * 2e3).abs() < 1e-6 * rise, "rise = {}, ids = {}", rise, ids);
+    assert!(rise > 1.0, "rise = {}", rise);
+    // 升温使迁移率下降, 电流变小; 工作点信息按加热后的温度给出
+    let i_vd = hot.branch_current("VD", &hot.solution).unwrap();
+    assert!((ids + i_vd).abs() < 1e-9, "ids = {}, I(VD) = {}", ids, i_vd);
+    assert!(ids < drain_current(&cold), "hot {} vs cold {}", ids, drain_current(&cold));
+}
+
+#[test]
+fn self_heating_thermal_node_in_transient_and_ac() {
+    let op = run_first_analysis(&netlist("rth=2k", ".op"));
+    let t = op.node_names.iter().position(|name| name == "m1#t").unwrap();
+
+    // 从工作点出发, 热容保持稳态温升
+    let tran = run_first_analysis(&netlist("rth=2k cth=1n", ".tran 1n 5n"));
+    assert!(tran.tran_solutions.len() > 1);
+    for x in &tran.tran_solutions {
+        assert!((x[t] - op.solution[t]).abs() < 1e-6 * op.solution[t], "rise = {}", x[t]);
+    }
+
+    // 小信号下热节点由 RTH // CTH 接地, 矩阵不奇异
+    let ac = run_first_analysis(&netlist("rth=2k cth=1n", ".ac dec 2 1k 1meg"));
+    assert!(!ac.ac_solutions.is_empty());
+    assert!((ac.solution[t] - op.solution[t]).abs() < 1e-6 * op.solution[t]);
+}
@@ -0,0 +1,164 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::AnalysisCmd;
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::result_store::{AnalysisType, ResultStore};
+use sim_core::sensitivity::{TranSensitivity, DEFAULT_DELTA};
+
+fn engine_for(netlist: &str) -> (Engine, AnalysisPlan) {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let circuit = build_circuit(&ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    (Engine::new_default(circuit), AnalysisPlan { cmd })
+}
+
+fn sensitivity(netlist: &str, outputs: &[&str], params: &[&str], delta: f64) -> (TranSensitivity, ResultStore) {
+    let (mut engine, plan) = engine_for(netlist);
+    let mut store = ResultStore::new();
+    let result = engine
+        .run_tran_sensitivity(&plan, outputs, params, delta, &mut store)
+        .expect("sensitivity");
+    (result, store)
+}
+
+#[test]
+fn rc_discharge_sensitivity_matches_analytic_derivative() {
+    // 初值取 1µV: 瞬态的误差判据近似为绝对误差, 大摆幅会把步长压得极小
+    let (result, store) = sensitivity(
+        "R1 out 0 1k\nC1 out 0 1u IC=1u\n.options method=trap\n.tran 1u 1m uic\n.end\n",
+        &["v(out)"],
+        &["R1", "C1"],
+        1e-2,
+    );
+    // 只有标称运行入库
+    assert_eq!(store.runs.len(), 1);
+    assert!(matches!(store.runs[0].analysis, AnalysisType::Tran));
+    assert_eq!(result.times, store.runs[0].tran_times);
+    assert_eq!(result.nominal, vec![1e3, 1e-6]);
+
+    // v = v0·e^(-t/RC), ∂v/∂R = v0·t/(R²C)·e^(-t/RC)
+    let tau = 1e-3;
+    let d_r = result.sensitivity("V(out)", "r1").unwrap();
+    let mut checked = 0;
+    for (k, &t) in result.times.iter().enumerate() {
+        if t < 0.2e-3 {
+            continue;
+        }
+        let expected = 1e-6 * t / (1e3 * tau) * (-t / tau).exp();
+        assert!((d_r[k] - expected).abs() < 0.05 * expected.abs(), "t={} {} vs {}", t, d_r[k], expected);
+        checked += 1;
+    }
+    assert!(checked > 10);
+
+    // v 只依赖 RC, 两个参数的归一化灵敏度相同
+    let by_r = result.normalized("v(out)", "R1").unwrap();
+    let by_c = result.normalized("v(out)", "C1").unwrap();
+    for (a, b) in by_r.iter().zip(&by_c) {
+        assert!((a - b).abs() < 1e-3 * 1e-6, "{} vs {}", a, b);
+    }
+    let table = result.peak_table(3);
+    assert_eq!(table.lines().count(), 3);
+    assert!(table.lines().nth(1).unwrap().starts_with("v(out)"));
+}
+
+#[test]
+fn divider_sensitivity_to_source_param_and_current() {
+    let (result, _) = sensitivity(
+        ".param rb=3k\nV1 in 0 DC 2\nR1 in out 1k\nR2 out 0 {rb}\n.tran 1u 5u\n.end\n",
+        &["v(out)", "i(V1)"],
+        &["V1", "rb"],
+        DEFAULT_DELTA,
+    );
+    let d_v1 = result.sensitivity("v(out)", "V1").unwrap();
+    let d_rb = result.sensitivity("v(out)", "rb").unwrap();
+    let d_i = result.sensitivity("i(V1)", "V1").unwrap();
+    // Vout = V·R2/(R1+R2): ∂/∂V = 0.75, ∂/∂R2 = V·R1/(R1+R2)² = 1.25e-4; I(V1) = -V/(R1+R2)
+    for k in 0..result.times.len() {
+        assert!((d_v1[k] - 0.75).abs() < 1e-6, "{}", d_v1[k]);
+        assert!((d_rb[k] - 1.25e-4).abs() < 1e-9, "{}", d_rb[k]);
+        assert!((d_i[k] + 2.5e-4).abs() < 1e-9, "{}", d_i[k]);
+    }
+}
+
+#[test]
+fn sensitivity_rejects_bad_requests() {
+    let netlist = "V1 in 0 DC 1\nR1 in out 1k\nR2 out 0 1k\n.tran 1u 5u\n.end\n";
+    let (mut engine, plan) = engine_for(netlist);
+    let mut store = ResultStore::new();
+
+    let err = engine.run_tran_sensitivity(&plan, &["v(out)"], &["Rx"], DEFAULT_DELTA, &mut store).unwrap_err();
+    assert!(err.contains("Rx"), "{}", err);
+    let err = engine.run_tran_sensitivity(&plan, &["v(nowhere)"], &["R1"], DEFAULT_DELTA, &mut store).unwrap_err();
+    assert!(err.contains("nowhere"), "{}", err);
+    let err = engine.run_tran_sensitivity(&plan, &["out"], &["R1"], DEFAULT_DELTA, &mut store).unwrap_err();
+    assert!(err.contains("invalid sensitivity output"), "{}", err);
+    let op = AnalysisPlan { cmd: AnalysisCmd::Op };
+    let err = engine.run_tran_sensitivity(&op, &["v(out)"], &["R1"], DEFAULT_DELTA, &mut store).unwrap_err();
+    assert!(err.contains(".tran"), "{}", err);
+}
+
+#[test]
+fn rc_charging_sensitivity_follows_the_step_history() {
+    // 直接法沿标称运行的每个时间步推进, 起点为工作点的灵敏度; 只跑一次瞬态
+    let (result, store) = sensitivity(
+        "V1 in 0 PULSE(0 1 0 1n 1n 1 2)\nR1 in out 1k\nC1 out 0 1u\n.options method=trap\n.tran 10u 3m\n.end\n",
+        &["v(out)"],
+        &["R1", "C1"],
+        DEFAULT_DELTA,
+    );
+    assert_eq!(store.runs.len(), 1);
+    assert_eq!(result.times, store.runs[0].tran_times);
+
+    // v = 1 − e^(-t/RC), ∂v/∂R = −t/(R²C)·e^(-t/RC), ∂v/∂C = −t/(RC²)·e^(-t/RC)
+    let tau = 1e-3;
+    let d_r = result.sensitivity("v(out)", "R1").unwrap();
+    let d_c = result.sensitivity("v(out)", "C1").unwrap();
+    assert_eq!(d_r[0], 0.0);
+    for (k, &t) in result.times.iter().enumerate() {
+        if t < 0.2e-3 {
+            continue;
+        }
+        let expected = -t / (1e3 * tau) * (-t / tau).exp();
+        assert!((d_r[k] - expected).abs() < 0.02 * expected.abs(), "t={} {} vs {}", t, d_r[k], expected);
+        let expected = expected * 1e3 / 1e-6;
+        assert!((d_c[k] - expected).abs() < 0.02 * expected.abs(), "t={} {} vs {}", t, d_c[k], expected);
+    }
+
+    // TSTART 之前的时间步仍参与推进, 只是不报告
+    let (late, store) = sensitivity(
+        "V1 in 0 PULSE(0 1 0 1n 1n 1 2)\nR1 in out 1k\nC1 out 0 1u\n.options method=trap\n.tran 10u 3m 1m\n.end\n",
+        &["v(out)"],
+        &["R1"],
+        DEFAULT_DELTA,
+    );
+    assert_eq!(late.times, store.runs[0].tran_times);
+    assert!(late.times[0] >= 1e-3);
+    let d_r = late.sensitivity("v(out)", "R1").unwrap();
+    for (value, &t) in d_r.iter().zip(&late.times) {
+        let expected = -t / (1e3 * tau) * (-t / tau).exp();
+        assert!((value - expected).abs() < 0.02 * expected.abs(), "t={} {} vs {}", t, value, expected);
+    }
+}
+
+#[test]
+fn diode_bias_sensitivity_matches_operating_point_differences() {
+    let netlist =
+        |r: f64| format!("V1 in 0 DC 5\nR1 in a {}\nD1 a 0 dmod\n.model dmod d is=1e-14\n.tran 1u 5u\n.end\n", r);
+    let (result, _) = sensitivity(&netlist(1e3), &["v(a)"], &["R1"], DEFAULT_DELTA);
+
+    // 工作点附近的中心差分: 两次 OP 的 v(a) 之差
+    let op = |r: f64| {
+        let (mut engine, _) = engine_for(&netlist(r));
+        let mut store = ResultStore::new();
+        let run = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+        let run = &store.runs[run.0];
+        run.solution[run.node_names.iter().position(|name| name == "a").unwrap()]
+    };
+    let expected = (op(1001.0) - op(999.0)) / 2.0;
+    assert!(expected < 0.0);
+    for value in result.sensitivity("v(a)", "R1").unwrap() {
+        assert!((value - expected).abs() < 1e-3 * expected.abs(), "{} vs {}", value, expected);
+    }
+}
@@ -41,3 +41,212 @@ fn klu_solver_solves_simple_system() {
     assert!((rhs[0] - 2.0).abs() < 1e-9);
     assert!((rhs[1] - 3.0).abs() < 1e-9);
 }
+
+/// 稠密矩阵 (按行) 转 CSC, 零元不存
+fn to_csc(dense: &[Vec<f64>]) -> (Vec<i64>, Vec<i64>, Vec<f64>) {
+    let n = dense.len();
+    let (mut ap, mut ai, mut ax) = (vec![0], Vec::new(), Vec::new());
+    for col in 0..n {
+        for (row, values) in dense.iter().enumerate() {
+            if values[col] != 0.0 {
+                ai.push(row as i64);
+                ax.push(values[col]);
+            }
+        }
+        ap.push(ai.len() as i64);
+    }
+    (ap, ai, ax)
+}
+
+#[test]
+fn sparse_lu_solver_matches_dense_solver() {
+    use sim_core::solver::{DenseSolver, LinearSolver, SparseLuSolver};
+
+    // 伪随机稀疏矩阵, 含零对角元 (需要选主元) 与电压源式的对称零块
+    let n = 40;
+    let mut seed = 12345u64;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as f64 / (1u64 << 31) as f64
+    };
+    let mut dense = vec![vec![0.0; n]; n];
+    for (i, row) in dense.iter_mut().enumerate() {
+        if i % 7 != 3 {
+            row[i] = 4.0 + next();
+        }
+        for _ in 0..3 {
+            let j = (next() * n as f64) as usize % n;
+            row[j] += next() - 0.5;
+        }
+        row[(i + 1) % n] += 1.0;
+    }
+    let (ap, ai, ax) = to_csc(&dense);
+    let b: Vec<f64> = (0..n).map(|i| i as f64 - 7.5).collect();
+
+    let mut expected = b.clone();
+    let mut solver = DenseSolver::new(n);
+    solver.factor(&ap, &ai, &ax).unwrap();
+    solver.solve(&mut expected).unwrap();
+
+    let mut rhs = b.clone();
+    let mut sparse = SparseLuSolver::new(n);
+    sparse.prepare(n);
+    sparse.analyze(&ap, &ai).unwrap();
+    sparse.factor(&ap, &ai, &ax).unwrap();
+    sparse.solve(&mut rhs).unwrap();
+    for (x, y) in rhs.iter().zip(&expected) {
+        assert!((x - y).abs() < 1e-9 * (1.0 + y.abs()), "{} vs {}", x, y);
+    }
+    // 同一分解可重复求解
+    let mut again = b;
+    sparse.solve(&mut again).unwrap();
+    assert_eq!(again, rhs);
+}
+
+#[test]
+fn sparse_lu_solver_sums_duplicates_and_keeps_tridiagonal_fill_free() {
+    use sim_core::solver::{LinearSolver, SparseLuSolver};
+
+    // 三对角 (每个对角元分两次给出): 不选主元时 LU 没有填充
+    let n = 100;
+    let (mut ap, mut ai, mut ax) = (vec![0i64], Vec::new(), Vec::new());
+    for col in 0..n {
+        if col > 0 {
+            ai.push(col as i64 - 1);
+            ax.push(-1.0);
+        }
+        ai.extend([col as i64, col as i64]);
+        ax.extend([1.5, 1.5]);
+        if col + 1 < n {
+            ai.push(col as i64 + 1);
+            ax.push(-1.0);
+        }
+        ap.push(ai.len() as i64);
+    }
+    let mut rhs = vec![1.0; n];
+    let mut solver = SparseLuSolver::new(n);
+    solver.factor(&ap, &ai, &ax).unwrap();
+    assert_eq!(solver.factor_nnz(), 2 * (n - 1));
+    solver.solve(&mut rhs).unwrap();
+    // 验证残差
+    for row in 0..n {
+        let mut sum = 3.0 * rhs[row];
+        if row > 0 {
+            sum -= rhs[row - 1];
+        }
+        if row + 1 < n {
+            sum -= rhs[row + 1];
+        }
+        assert!((sum - 1.0).abs() < 1e-9, "row {}: {}", row, sum);
+    }
+}
+
+#[test]
+fn sparse_lu_solver_reports_singular_matrix() {
+    use sim_core::solver::{LinearSolver, SolverError, SparseLuSolver};
+
+    // 第二列全零
+    let (ap, ai, ax) = to_csc(&[vec![1.0, 0.0, 2.0], vec![3.0, 0.0, 1.0], vec![0.0, 0.0, 1.0]]);
+    let mut solver = SparseLuSolver::new(3);
+    assert_eq!(solver.factor(&ap, &ai, &ax), Err(SolverError::FactorFailed));
+    // 两列成比例
+    let (ap, ai, ax) = to_csc(&[vec![1.0, 2.0], vec![2.0, 4.0]]);
+    let mut solver = SparseLuSolver::new(2);
+    assert_eq!(solver.factor(&ap, &ai, &ax), Err(SolverError::FactorFailed));
+}
+
+#[test]
+fn engine_runs_with_sparse_lu_solver() {
+    use sim_core::analysis::AnalysisPlan;
+    use sim_core::circuit::AnalysisCmd;
+    use sim_core::engine::Engine;
+    use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+    use sim_core::result_store::{ResultStore, RunStatus};
+    use sim_core::solver::SolverType;
+
+    let ast = parse_netlist(
+        "* diode ladder\nV1 in 0 5\nR1 in a 1k\nD1 a b dmod\nR2 b 0 2k\nE1 c 0 b 0 2\nR3 c 0 1k\n\
+         .model dmod d is=1e-14\n.op\n.end\n",
+    );
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let mut solutions = Vec::new();
+    for solver_type in [SolverType::Dense, SolverType::SparseLu] {
+        let mut engine = Engine::new(circuit.clone(), solver_type);
+        let mut store = ResultStore::new();
+        let run_id = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+        let run = store.runs[run_id.0].clone();
+        assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+        solutions.push(run.solution);
+    }
+    for (x, y) in solutions[0].iter().zip(&solutions[1]) {
+        assert!((x - y).abs() < 1e-9, "{} vs {}", x, y);
+    }
+}
+
+#[test]
+fn auto_solver_switches_to_sparse_lu_for_large_matrices() {
+    use sim_core::solver::{create_solver, AutoSolver, LinearSolver, SolverType, SPARSE_THRESHOLD};
+
+    assert_eq!(SolverType::default(), SolverType::Auto);
+    let mut auto = AutoSolver::new(2);
+    assert_eq!(auto.backend(), SolverType::Dense);
+    auto.prepare(SPARSE_THRESHOLD);
+    assert_eq!(auto.backend(), SolverType::SparseLu);
+    auto.prepare(SPARSE_THRESHOLD - 1);
+    assert_eq!(auto.backend(), SolverType::Dense);
+
+    // 三对角系统 2x_k - x_{k-1} - x_{k+1} = b_k, 解取 x_k = k
+    let n = 4 * SPARSE_THRESHOLD;
+    let dense: Vec<Vec<f64>> = (0..n)
+        .map(|row| {
+            (0..n)
+                .map(|col| match row.abs_diff(col) {
+                    0 => 2.0,
+                    1 => -1.0,
+                    _ => 0.0,
+                })
+                .collect()
+        })
+        .collect();
+    let (ap, ai, ax) = to_csc(&dense);
+    let mut rhs: Vec<f64> = (0..n)
+        .map(|row| (0..n).map(|col| dense[row][col] * col as f64).sum())
+        .collect();
+    let mut solver = create_solver(SolverType::default(), 1);
+    solver.prepare(n);
+    solver.analyze(&ap, &ai).unwrap();
+    solver.factor(&ap, &ai, &ax).unwrap();
+    solver.solve(&mut rhs).unwrap();
+    for (k, value) in rhs.iter().enumerate() {
+        assert!((value - k as f64).abs() < 1e-6, "x[{}] = {}", k, value);
+    }
+}
+
+#[test]
+fn default_engine_solves_large_ladder() {
+    use sim_core::analysis::AnalysisPlan;
+    use sim_core::circuit::AnalysisCmd;
+    use sim_core::engine::Engine;
+    use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+    use sim_core::result_store::{ResultStore, RunStatus};
+
+    // 200 节 1k 串联电阻链, 末端接地: 各节点电压线性下降
+    let sections = 200;
+    let mut netlist = String::from("* ladder\nV1 n0 0 DC 2\n");
+    for k in 0..sections {
+        netlist.push_str(&format!("R{} n{} n{} 1k\n", k, k, k + 1));
+    }
+    netlist.push_str(&format!("RL n{} 0 1k\n.op\n.end\n", sections));
+    let ast = parse_netlist(&netlist);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    for k in [0, 50, 200] {
+        let index = run.node_names.iter().position(|name| *name == format!("n{}", k)).unwrap();
+        let expected = 2.0 * (sections + 1 - k) as f64 / (sections + 1) as f64;
+        assert!((run.solution[index] - expected).abs() < 1e-9, "n{} = {}", k, run.solution[index]);
+    }
+}
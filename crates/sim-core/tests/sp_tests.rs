@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use num_complex::Complex64;
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::{AcSweepType, AnalysisCmd};
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist, parse_netlist_file, NetlistAst};
+use sim_core::result_store::{AnalysisType, ResultStore, RunId, RunStatus};
+use sim_core::touchstone::{format_touchstone, parse_touchstone, read_touchstone, Touchstone};
+
+fn sp_test_dir(name: &str) -> PathBuf {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_sp_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    dir
+}
+
+/// 按网表里的第一条分析语句运行, 返回结果所在的 store
+fn run_sp(ast: &NetlistAst) -> (ResultStore, RunId) {
+    assert!(ast.errors.is_empty(), "ast errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(ast);
+    assert_eq!(elab.error_count, 0, "elab errors: {:?}", elab.errors);
+    let circuit = build_circuit(ast, &elab);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    (store, run_id)
+}
+
+fn sparams(netlist: &str) -> Touchstone {
+    let (store, run_id) = run_sp(&parse_netlist(netlist));
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.analysis, AnalysisType::Sp));
+    assert!(matches!(run.status, RunStatus::Converged), "run failed: {:?}", run.message);
+    run.sparams.clone().expect("S-parameters")
+}
+
+fn close(a: Complex64, b: Complex64) -> bool {
+    (a - b).norm() < 1e-9
+}
+
+#[test]
+fn sp_parses_sweep() {
+    let ast = parse_netlist("P1 a 0 port=1\n.sp dec 10 1k 1g\n.end\n");
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    assert!(matches!(
+        circuit.analysis[0],
+        AnalysisCmd::Sp { sweep_type: AcSweepType::Dec, points: 10, .. }
+    ));
+}
+
+#[test]
+fn sp_series_resistor_between_ports() {
+    let data = sparams("P1 in 0 port=1\nR1 in out 50\nP2 out 0 port=2\n.sp lin 3 1k 1meg\n.end\n");
+    assert_eq!(data.ports, 2);
+    assert_eq!(data.z0, 50.0);
+    assert_eq!(data.frequencies.len(), 3);
+    for matrix in &data.s {
+        let third = Complex64::new(1.0 / 3.0, 0.0);
+        assert!(close(matrix[0], third), "S11 = {}", matrix[0]);
+        assert!(close(matrix[1], 2.0 * third), "S12 = {}", matrix[1]);
+        assert!(close(matrix[2], 2.0 * third), "S21 = {}", matrix[2]);
+        assert!(close(matrix[3], third), "S22 = {}", matrix[3]);
+    }
+}
+
+#[test]
+fn sp_shunt_capacitor_follows_frequency() {
+    // 两端口之间并联电容 Y = jωC: S11 = -Z0·Y/(2 + Z0·Y), S21 = 2/(2 + Z0·Y)
+    let data = sparams("P1 a 0 port=1 z0=75\nC1 a 0 1p\nP2 a 0 port=2 z0=75\n.sp lin 2 1g 2g\n.end\n");
+    assert_eq!(data.z0, 75.0);
+    for (freq, matrix) in data.frequencies.iter().zip(&data.s) {
+        let zy = Complex64::new(0.0, 2.0 * std::f64::consts::PI * freq * 1e-12 * 75.0);
+        assert!(close(matrix[0], -zy / (2.0 + zy)), "S11 = {}", matrix[0]);
+        assert!(close(matrix[2], 2.0 / (2.0 + zy)), "S21 = {}", matrix[2]);
+        assert!(close(matrix[1], matrix[2]), "not reciprocal: {} vs {}", matrix[1], matrix[2]);
+    }
+}
+
+#[test]
+fn sp_recovers_block_data_and_exports_touchstone() {
+    let dir = sp_test_dir("block");
+    std::fs::write(dir.join("amp.s2p"), "# GHz S MA R 50\n1 0.5 0 2 -90 0 0 0.25 45\n2 0.5 0 2 -90 0 0 0.25 45\n").unwrap();
+    let top = dir.join("top.cir");
+    std::fs::write(&top, "P1 a 0 port=1\nS1 a b FILE=amp.s2p\nP2 b 0 port=2\n.sp lin 3 1g 2g\n.end\n").unwrap();
+
+    let (store, run_id) = run_sp(&parse_netlist_file(&top));
+    let data = store.runs[run_id.0].sparams.clone().expect("S-parameters");
+    for matrix in &data.s {
+        assert!(close(matrix[0], Complex64::new(0.5, 0.0)), "S11 = {}", matrix[0]);
+        assert!(close(matrix[2], Complex64::from_polar(2.0, -90f64.to_radians())), "S21 = {}", matrix[2]);
+        assert!(close(matrix[1], Complex64::new(0.0, 0.0)), "S12 = {}", matrix[1]);
+        assert!(close(matrix[3], Complex64::from_polar(0.25, 45f64.to_radians())), "S22 = {}", matrix[3]);
+    }
+
+    let path = dir.join("out.s2p");
+    store.write_touchstone(run_id, &path, 12).unwrap();
+    let written = read_touchstone(&path).unwrap();
+    assert_eq!(written.frequencies, data.frequencies);
+    for (a, b) in written.s.iter().flatten().zip(data.s.iter().flatten()) {
+        assert!(close(*a, *b), "{} vs {}", a, b);
+    }
+}
+
+#[test]
+fn sp_touchstone_text_round_trips_multiport_rows() {
+    let matrix: Vec<Complex64> = (0..9).map(|idx| Complex64::new(idx as f64 * 0.1, -(idx as f64) * 0.01)).collect();
+    let data = Touchstone {
+        ports: 3,
+        z0: 50.0,
+        frequencies: vec![1e6, 2e6],
+        s: vec![matrix.clone(), matrix],
+    };
+    let text = format_touchstone(&data, 10);
+    assert!(text.contains("# Hz S RI R 50"), "{}", text);
+    let parsed = parse_touchstone(&text, 3).unwrap();
+    assert_eq!(parsed.frequencies, data.frequencies);
+    for (a, b) in parsed.s.iter().flatten().zip(data.s.iter().flatten()) {
+        assert!(close(*a, *b), "{} vs {}", a, b);
+    }
+}
+
+#[test]
+fn sp_rejects_missing_or_inconsistent_ports() {
+    let cases = [
+        ("R1 a 0 50\n", "at least one port"),
+        ("P1 a 0 port=1\nP2 b 0 port=3\nR1 a b 50\n", "numbered 1..N"),
+        ("P1 a 0 port=1\nP2 b 0 port=2 z0=75\nR1 a b 50\n", "reference impedance"),
+    ];
+    for (devices, expected) in cases {
+        let (store, run_id) = run_sp(&parse_netlist(&format!("{}.sp lin 2 1k 2k\n.end\n", devices)));
+        let run = &store.runs[run_id.0];
+        assert!(matches!(run.status, RunStatus::Failed));
+        let message = run.message.as_deref().unwrap_or("");
+        assert!(message.contains(expected), "{}", message);
+    }
+}
@@ -0,0 +1,145 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::AnalysisCmd;
+use sim_core::diagnostic::codes;
+use sim_core::engine::Engine;
+use sim_core::netlist::{
+    build_circuit, elaborate_netlist, parse_netlist, parse_netlist_file, DeviceKind, NetlistAst, Stmt,
+};
+use sim_core::result_store::{ResultStore, RunStatus};
+use sim_core::spectre::{parse_spectre, spectre_to_spice};
+
+fn op_voltages(ast: &NetlistAst) -> Vec<(String, f64)> {
+    assert!(ast.errors.is_empty(), "ast errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(ast);
+    assert_eq!(elab.error_count, 0, "elab errors: {:?}", elab.errors);
+    let circuit = build_circuit(ast, &elab);
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, RunStatus::Converged), "run failed: {:?}", run);
+    let mut voltages: Vec<(String, f64)> = run
+        .node_names
+        .iter()
+        .cloned()
+        .zip(run.solution.iter().copied())
+        .collect();
+    voltages.sort_by(|a, b| a.0.cmp(&b.0));
+    voltages
+}
+
+#[test]
+fn spectre_deck_matches_equivalent_spice_deck() {
+    let spectre = "\
+// divider with a subcircuit
+simulator lang=spectre
+parameters rval=2k vdd=3
+subckt half (a b)
+  R0 (a b) resistor r=rval
+ends half
+V1 (in 0) vsource dc=vdd
+X1 (in mid) half
+R2 (mid 0) resistor \\
+    r=2k
+op1 dc
+";
+    let spice = "\
+* divider with a subcircuit
+.param rval=2k vdd=3
+.subckt half a b
+R0 a b rval
+.ends
+V1 in 0 DC vdd
+X1 in mid half
+R2 mid 0 2k
+.op
+.end
+";
+    let from_spectre = parse_spectre(spectre);
+    let from_spice = parse_netlist(spice);
+    let a = op_voltages(&from_spectre);
+    let b = op_voltages(&from_spice);
+    assert_eq!(a.len(), b.len());
+    for ((name_a, va), (name_b, vb)) in a.iter().zip(&b) {
+        assert_eq!(name_a, name_b);
+        assert!((va - vb).abs() < 1e-9, "{}: {} vs {}", name_a, va, vb);
+    }
+    let mid = a.iter().find(|(name, _)| name == "mid").unwrap().1;
+    assert!((mid - 1.5).abs() < 1e-9, "mid = {}", mid);
+}
+
+#[test]
+fn spectre_translates_models_sources_and_analyses() {
+    let (spice, errors) = spectre_to_spice(
+        "\
+model nch bsim4 type=n vth0=0.4
+model dmod diode is=1e-14
+M1 (d g 0 0) nch w=1u l=100n
+D1 (a 0) dmod
+Vg (g 0) vsource dc=0 mag=1 type=pulse val0=0 val1=1.8 rise=1n fall=1n width=5n period=10n
+sweep1 dc dev=Vg start=0 stop=1.8 step=0.1
+tr tran stop=20n step=0.1n
+ac1 ac start=1 stop=1M dec=10
+",
+    );
+    assert!(errors.is_empty(), "errors: {:?}", errors);
+    let lines: Vec<&str> = spice.lines().collect();
+    assert_eq!(lines[0], ".model nch nmos level=54 vth0=0.4");
+    assert_eq!(lines[1], ".model dmod d is=1e-14");
+    assert_eq!(lines[2], "M1 d g 0 0 nch w=1u l=100n");
+    assert_eq!(lines[3], "D1 a 0 dmod");
+    assert_eq!(lines[4], "Vg g 0 DC 0 AC 1 0 PULSE(0 1.8 0 1n 1n 5n 10n)");
+    assert_eq!(lines[5], ".dc Vg 0 1.8 0.1");
+    assert_eq!(lines[6], ".tran 0.1n 20n");
+    assert_eq!(lines[7], ".ac dec 10 1 1meg");
+
+    let ast = parse_netlist(&spice);
+    assert!(ast.errors.is_empty(), "ast errors: {:?}", ast.errors);
+    let devices: Vec<_> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Device(device) => Some(device),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(devices.len(), 3);
+    assert!(matches!(devices[0].kind, DeviceKind::M));
+    assert!(matches!(devices[1].kind, DeviceKind::D));
+    assert!(matches!(devices[2].kind, DeviceKind::V));
+}
+
+#[test]
+fn spectre_file_selected_by_extension_reports_original_lines() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_spectre_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("parts.scs"), "R2 (out 0) resistor r=1k\n").unwrap();
+    let top = dir.join("top.scs");
+    std::fs::write(
+        &top,
+        "// top\nV1 (in 0) vsource dc=1\ninclude \"parts.scs\"\nR1 (in out) resistor r=1k\nfoo (\n",
+    )
+    .unwrap();
+
+    let ast = parse_netlist_file(&top);
+    assert_eq!(ast.errors.len(), 1, "errors: {:?}", ast.errors);
+    let diag = &ast.errors[0];
+    assert_eq!(diag.code, codes::SPECTRE_SYNTAX);
+    assert_eq!(diag.file.as_deref(), Some(top.as_path()));
+    assert_eq!(diag.line, 5);
+    assert_eq!(diag.source_line.as_deref(), Some("foo ("));
+
+    let devices: Vec<&str> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Device(device) => Some(device.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(devices, vec!["V1", "R2", "R1"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
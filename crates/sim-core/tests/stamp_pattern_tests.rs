@@ -0,0 +1,107 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::engine::Engine;
+use sim_core::mna::{MnaWorkspace, PatternStats};
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::result_store::{ResultStore, RunResult, RunStatus};
+
+fn dense(ap: &[i64], ai: &[i64], ax: &[f64], n: usize) -> Vec<Vec<f64>> {
+    let mut a = vec![vec![0.0; n]; n];
+    for col in 0..n {
+        for k in ap[col] as usize..ap[col + 1] as usize {
+            a[ai[k] as usize][col] += ax[k];
+        }
+    }
+    a
+}
+
+fn stamp(workspace: &mut MnaWorkspace, g: f64) -> (Vec<i64>, Vec<i64>, Vec<f64>, Vec<f64>, usize) {
+    let mna = workspace.begin(2);
+    let mut ctx = mna.context();
+    ctx.add(0, 0, g);
+    ctx.add(0, 1, -g);
+    ctx.add(1, 0, -g);
+    ctx.add(1, 1, g);
+    ctx.add(1, 1, 1.0);
+    ctx.add_rhs(0, g);
+    workspace.finish()
+}
+
+#[test]
+fn workspace_reuses_pattern_and_merges_duplicates() {
+    let mut workspace = MnaWorkspace::new(2);
+    let (ap, ai, ax, rhs, n) = stamp(&mut workspace, 1.0);
+    assert_eq!(n, 2);
+    assert_eq!(ap, vec![0, 2, 4]);
+    assert_eq!(ai, vec![0, 1, 0, 1]);
+    assert_eq!(dense(&ap, &ai, &ax, n), vec![vec![1.0, -1.0], vec![-1.0, 2.0]]);
+    assert_eq!(rhs, vec![1.0, 0.0]);
+
+    let (ap2, ai2, ax2, rhs2, _) = stamp(&mut workspace, 2.0);
+    assert_eq!((ap2, ai2), (ap, ai));
+    assert_eq!(ax2, vec![2.0, -2.0, -2.0, 3.0]);
+    assert_eq!(rhs2, vec![2.0, 0.0]);
+    assert_eq!(workspace.pattern.stats, PatternStats { rebuilds: 1, reuses: 1 });
+}
+
+#[test]
+fn workspace_rebuilds_pattern_when_structure_changes() {
+    let mut workspace = MnaWorkspace::new(2);
+    stamp(&mut workspace, 1.0);
+
+    // 多一个辅助变量: 维数与结构都变了
+    let mut ctx = workspace.begin(2).context();
+    let aux = ctx.allocate_aux("V1");
+    ctx.add(0, 0, 1.0);
+    ctx.add(1, 1, 1.0);
+    ctx.add(0, aux, 1.0);
+    ctx.add(aux, 0, 1.0);
+    ctx.add_rhs(aux, 5.0);
+    let (ap, ai, ax, rhs, n) = workspace.finish();
+    assert_eq!(n, 3);
+    assert_eq!(
+        dense(&ap, &ai, &ax, n),
+        vec![vec![1.0, 0.0, 1.0], vec![0.0, 1.0, 0.0], vec![1.0, 0.0, 0.0]]
+    );
+    assert_eq!(rhs, vec![0.0, 0.0, 5.0]);
+    assert_eq!(workspace.pattern.stats.rebuilds, 2);
+
+    // 回到原来的结构时辅助变量被清掉, 再建立一次
+    let (_, _, _, rhs, n) = stamp(&mut workspace, 1.0);
+    assert_eq!(n, 2);
+    assert_eq!(rhs, vec![1.0, 0.0]);
+    assert_eq!(workspace.pattern.stats, PatternStats { rebuilds: 3, reuses: 0 });
+}
+
+fn run(netlist: &str) -> (RunResult, PatternStats) {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let result = store.runs[run_id.0].clone();
+    assert!(matches!(result.status, RunStatus::Converged), "{:?}", result.message);
+    (result, engine.pattern_stats())
+}
+
+#[test]
+fn transient_reuses_pattern_across_iterations_and_steps() {
+    let (result, stats) = run(
+        "* divider with diode\nV1 in 0 PULSE(0 2 2u 1n 1n 3u 10u)\nR1 in out 1k\nR2 out 0 1k\n\
+         V2 bias 0 2\nR3 bias a 1k\nD1 a 0 dmod\n.model dmod d is=1e-14\n.tran 1u 20u\n.end\n",
+    );
+    assert!(stats.rebuilds <= 2, "{:?}", stats);
+    assert!(stats.reuses > 20 * stats.rebuilds, "{:?}", stats);
+    let out = result.node_names.iter().position(|n| n == "out").unwrap();
+    let peak = result.tran_solutions.iter().map(|x| x[out]).fold(f64::MIN, f64::max);
+    assert!((peak - 1.0).abs() < 1e-6, "peak {}", peak);
+}
+
+#[test]
+fn operating_point_is_unchanged_by_pattern_reuse() {
+    let (result, stats) = run("* divider\nV1 in 0 3\nR1 in out 1k\nR2 out 0 2k\n.op\n.end\n");
+    assert!(stats.rebuilds >= 1, "{:?}", stats);
+    let out = result.node_names.iter().position(|n| n == "out").unwrap();
+    assert!((result.solution[out] - 2.0).abs() < 1e-9);
+}
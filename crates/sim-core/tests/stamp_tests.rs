@@ -1,6 +1,7 @@
 use sim_core::circuit::{DeviceKind, Instance, NodeId};
 use sim_core::mna::MnaBuilder;
 use sim_core::stamp::{DeviceStamp, InstanceStamp, TransientState};
+use sim_core::state_table::StateKey;
 use std::collections::HashMap;
 
 #[test]
@@ -16,6 +17,7 @@ fn diode_stamp_allows_basic_nodes() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     InstanceStamp { instance: diode }.stamp_dc(&mut ctx, None).unwrap();
@@ -34,6 +36,7 @@ fn mos_stamp_allows_basic_nodes() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     InstanceStamp { instance: mos }.stamp_dc(&mut ctx, None).unwrap();
@@ -52,6 +55,7 @@ fn capacitor_tran_stamp_basic() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     let mut state = TransientState::default();
@@ -61,6 +65,36 @@ fn capacitor_tran_stamp_basic() {
     assert!(builder.rhs[1].is_finite());
 }
 
+#[test]
+fn capacitor_value_from_model_geometry() {
+    let mut builder = MnaBuilder::new(2);
+    let mut params = HashMap::new();
+    // .model cmod c cj=1e-3 cjsw=1e-9, W=L=10u: C = 1e-3*1e-10 + 2*1e-9*20e-6 = 1.4e-13
+    for (key, value) in [("cj", "1e-3"), ("cjsw", "1e-9"), ("w", "10u"), ("l", "10u")] {
+        params.insert(key.to_string(), value.to_string());
+    }
+    let cap = Instance {
+        name: "C1".to_string(),
+        kind: DeviceKind::C,
+        nodes: vec![NodeId(1), NodeId(0)],
+        model: None,
+        params,
+        value: None,
+        control: None,
+        ac_mag: None,
+        ac_phase: None,
+        state_slot: 0,
+    };
+    let mut ctx = builder.context();
+    let mut state = TransientState::default();
+    state.cap_voltage.insert(StateKey::of(&cap), 1.0);
+    InstanceStamp { instance: cap }
+        .stamp_tran(&mut ctx, Some(&[0.0, 1.0]), 1e-9, &mut state)
+        .unwrap();
+    // 后向欧拉历史项 C/dt * v_prev
+    assert!((builder.rhs[1] - 1.4e-13 / 1e-9).abs() < 1e-12, "rhs = {}", builder.rhs[1]);
+}
+
 #[test]
 fn inductor_tran_stamp_basic() {
     let mut builder = MnaBuilder::new(2);
@@ -74,6 +108,7 @@ fn inductor_tran_stamp_basic() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     let mut state = TransientState::default();
@@ -95,10 +130,12 @@ fn update_transient_state_tracks_cap_voltage() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut state = TransientState::default();
+    let key = StateKey::of(&cap);
     sim_core::stamp::update_transient_state(&[cap], &[0.0, 2.0], &mut state);
-    assert_eq!(state.cap_voltage.get("C1").copied(), Some(2.0));
+    assert_eq!(state.cap_voltage.get(key).copied(), Some(2.0));
 }
 
 #[test]
@@ -115,6 +152,7 @@ fn vcvs_stamp_basic() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     InstanceStamp { instance: vcvs }.stamp_dc(&mut ctx, None).unwrap();
@@ -136,6 +174,7 @@ fn vccs_stamp_basic() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     InstanceStamp { instance: vccs }.stamp_dc(&mut ctx, None).unwrap();
@@ -159,6 +198,7 @@ fn cccs_stamp_requires_control_source() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     InstanceStamp { instance: vsrc }.stamp_dc(&mut ctx, None).unwrap();
@@ -174,6 +214,7 @@ fn cccs_stamp_requires_control_source() {
         control: Some("Vctrl".to_string()),
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     InstanceStamp { instance: cccs }.stamp_dc(&mut ctx, None).unwrap();
@@ -195,6 +236,7 @@ fn ccvs_stamp_requires_control_source() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     InstanceStamp { instance: vsrc }.stamp_dc(&mut ctx, None).unwrap();
@@ -210,6 +252,7 @@ fn ccvs_stamp_requires_control_source() {
         control: Some("Vctrl".to_string()),
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     InstanceStamp { instance: ccvs }.stamp_dc(&mut ctx, None).unwrap();
@@ -230,6 +273,7 @@ fn subcircuit_instance_stamp_is_noop() {
         control: None,
         ac_mag: None,
         ac_phase: None,
+        state_slot: 0,
     };
     let mut ctx = builder.context();
     // Should succeed without doing anything (subcircuits are already expanded)
@@ -0,0 +1,82 @@
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::stamp::{update_transient_state, TransientState};
+use sim_core::state_table::{StateBranch, StateKey, StateTable};
+
+fn key(slot: usize, branch: StateBranch) -> StateKey {
+    StateKey { slot, branch }
+}
+
+#[test]
+fn state_table_behaves_like_a_map() {
+    let mut table = StateTable::default();
+    assert!(table.is_empty());
+    assert_eq!(table.insert(key(3, StateBranch::Gd), 1.0), None);
+    assert_eq!(table.insert(key(0, StateBranch::Main), 2.0), None);
+    assert_eq!(table.insert(key(3, StateBranch::Gd), 4.0), Some(1.0));
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.get(key(3, StateBranch::Gs)), None);
+    assert_eq!(table[key(3, StateBranch::Gd)], 4.0);
+    // 按槽位顺序遍历
+    let entries: Vec<_> = table.iter().map(|(key, value)| (key, *value)).collect();
+    assert_eq!(entries, vec![(key(0, StateBranch::Main), 2.0), (key(3, StateBranch::Gd), 4.0)]);
+    assert_eq!(table.remove(key(0, StateBranch::Main)), Some(2.0));
+    assert_eq!(table.len(), 1);
+    assert!(!table.contains_key(key(0, StateBranch::Main)));
+
+    table.get_or_insert_with(key(5, StateBranch::Main), || 7.0);
+    *table.get_or_insert_with(key(5, StateBranch::Main), || 0.0) += 1.0;
+    assert_eq!(table[key(5, StateBranch::Main)], 8.0);
+    assert_eq!(table.len(), 2);
+}
+
+#[test]
+fn elaboration_assigns_one_slot_per_instance() {
+    let netlist = "* slots\n.subckt rc a b\nR1 a m 1k\nC1 m b 1p\n.ends\nV1 in 0 1\nX1 in 0 rc\nX2 in 0 rc\nC2 in 0 2p\n.tran 1n 10n\n.end\n";
+    let ast = parse_netlist(netlist);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let slots: Vec<usize> = circuit.instances.instances.iter().map(|inst| inst.state_slot).collect();
+    assert_eq!(slots, (0..circuit.instances.instances.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn transient_state_is_kept_per_slot() {
+    let netlist = "* two caps\nV1 a 0 1\nC1 a b 1p\nC2 b 0 1p\nM1 a b 0 0 nm w=1u l=1u\n.model nm nmos level=1\n.tran 1n 10n\n.end\n";
+    let ast = parse_netlist(netlist);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let instances = &circuit.instances.instances;
+    let find = |name: &str| instances.iter().find(|inst| inst.name.eq_ignore_ascii_case(name)).unwrap();
+    let a = circuit.nodes.name_to_id["a"].0;
+    let b = circuit.nodes.name_to_id["b"].0;
+    let mut x = vec![0.0; circuit.nodes.id_to_name.len()];
+    x[a] = 3.0;
+    x[b] = 1.0;
+
+    let mut state = TransientState::default();
+    update_transient_state(instances, &x, &mut state);
+    assert_eq!(state.cap_voltage.get(StateKey::of(find("C1"))), Some(&2.0));
+    assert_eq!(state.cap_voltage.get(StateKey::of(find("C2"))), Some(&1.0));
+    // MOSFET 的三个栅电容各占一条支路: d=a, g=b, s=0, b=0
+    let mos = find("M1");
+    assert_eq!(state.cap_voltage.get(StateKey::branch(mos, StateBranch::Gs)), Some(&1.0));
+    assert_eq!(state.cap_voltage.get(StateKey::branch(mos, StateBranch::Gd)), Some(&-2.0));
+    assert_eq!(state.cap_voltage.get(StateKey::branch(mos, StateBranch::Gb)), Some(&1.0));
+
+    state.start_history();
+    assert_eq!(state.history.cap_voltage.len(), state.cap_voltage.len());
+}
+
+#[test]
+fn source_waveforms_are_kept_per_slot() {
+    let netlist = "* sources\nV1 a 0 PULSE(0 1 1n 1n 1n 5n 20n)\nV2 b 0 DC 1\nR1 a b 1k\n.tran 1n 10n\n.end\n";
+    let ast = parse_netlist(netlist);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let instances = &circuit.instances.instances;
+    let find = |name: &str| instances.iter().find(|inst| inst.name.eq_ignore_ascii_case(name)).unwrap();
+    let x = vec![0.0; circuit.nodes.id_to_name.len()];
+
+    let mut state = TransientState::default();
+    update_transient_state(instances, &x, &mut state);
+    assert!(state.waveforms.contains_key(StateKey::of(find("V1"))));
+    assert!(!state.waveforms.contains_key(StateKey::of(find("V2"))));
+    assert_eq!(state.next_breakpoint(0.0), Some(1e-9));
+}
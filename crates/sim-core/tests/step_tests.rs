@@ -0,0 +1,156 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::{AcSweepType, AnalysisCmd, DcSweepKind, StepSpec, StepValues};
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::result_store::{ResultStore, RunStatus};
+
+fn engine_for(netlist: &str) -> Engine {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "elaboration errors");
+    Engine::new_default(build_circuit(&ast, &elab))
+}
+
+fn output(store: &ResultStore, run: sim_core::result_store::RunId, name: &str) -> f64 {
+    store.runs[run.0].output_params.iter().find(|(key, _)| key == name).unwrap().1
+}
+
+#[test]
+fn step_statements_are_parsed() {
+    let ast = parse_netlist(
+        "V1 in 0 1\n.step param rtop 1k 3k 1k\n.step dec temp 1 100 2\n.step V1 list 1 2 5\n\
+         .step oct param x 1 4 1\n.step param bad list\n.end\n",
+    );
+    let steps = build_circuit(&ast, &elaborate_netlist(&ast)).steps;
+    assert_eq!(
+        steps,
+        vec![
+            StepSpec {
+                kind: DcSweepKind::Param,
+                name: "rtop".to_string(),
+                values: StepValues::Lin { start: 1e3, stop: 3e3, step: 1e3 },
+            },
+            StepSpec {
+                kind: DcSweepKind::Temp,
+                name: "temp".to_string(),
+                values: StepValues::Log { sweep_type: AcSweepType::Dec, points: 2, start: 1.0, stop: 100.0 },
+            },
+            StepSpec {
+                kind: DcSweepKind::Source,
+                name: "V1".to_string(),
+                values: StepValues::List(vec![1.0, 2.0, 5.0]),
+            },
+            StepSpec {
+                kind: DcSweepKind::Param,
+                name: "x".to_string(),
+                values: StepValues::Log { sweep_type: AcSweepType::Oct, points: 1, start: 1.0, stop: 4.0 },
+            },
+        ]
+    );
+}
+
+#[test]
+fn step_runs_every_combination_in_row_major_order() {
+    let mut engine = engine_for(
+        ".param rtop=1k\nV1 in 0 1\nR1 in out rtop\nR2 out 0 1k\n.param vout='v(out)'\n\
+         .step param rtop 1k 3k 1k\n.step V1 list 1 2\n.op\n.end\n",
+    );
+    let steps = engine.circuit.steps.clone();
+    let mut store = ResultStore::new();
+    let index = engine.run_step_sweep(&AnalysisPlan { cmd: AnalysisCmd::Op }, &steps, &mut store).unwrap();
+    let sweep = &store.step_sweeps[index];
+    assert_eq!(sweep.vars, vec!["rtop", "V1"]);
+    assert_eq!(sweep.shape(), vec![3, 2]);
+    assert_eq!(sweep.runs.len(), 6);
+    assert_eq!(sweep.point(3), vec![2000.0, 2.0]);
+
+    for (i, rtop) in [1000.0, 2000.0, 3000.0].iter().enumerate() {
+        for (j, v1) in [1.0, 2.0].iter().enumerate() {
+            let run = sweep.run_at(&[i, j]).unwrap();
+            assert!(matches!(store.runs[run.0].status, RunStatus::Converged));
+            let expected = v1 * 1000.0 / (rtop + 1000.0);
+            let vout = output(&store, run, "vout");
+            assert!((vout - expected).abs() < 1e-9, "rtop={} v1={}: {}", rtop, v1, vout);
+        }
+    }
+    assert_eq!(sweep.run_at(&[3, 0]), None);
+    assert_eq!(sweep.run_at(&[0]), None);
+
+    // 扫描结束后恢复标称电路
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    assert!((output(&store, run_id, "vout") - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn step_sweeps_temperature_inside_parameters() {
+    let mut engine = engine_for(
+        ".param rb=1k\nV1 in 0 1\nR1 in out 1k\nR2 out 0 {rb} rmod\n.model rmod r tc1=0.01\n\
+         .param vout='v(out)'\n.op\n.end\n",
+    );
+    let steps = vec![
+        StepSpec {
+            kind: DcSweepKind::Param,
+            name: "rb".to_string(),
+            values: StepValues::List(vec![1000.0, 500.0]),
+        },
+        StepSpec {
+            kind: DcSweepKind::Temp,
+            name: "temp".to_string(),
+            values: StepValues::List(vec![27.0, 127.0]),
+        },
+    ];
+    let mut store = ResultStore::new();
+    let index = engine.run_step_sweep(&AnalysisPlan { cmd: AnalysisCmd::Op }, &steps, &mut store).unwrap();
+    let sweep = &store.step_sweeps[index];
+    // 127 ℃: rb·(1 + 0.01·100)
+    let hot = output(&store, sweep.run_at(&[1, 1]).unwrap(), "vout");
+    assert!((hot - 1000.0 / 2000.0).abs() < 1e-6, "{}", hot);
+    let cold = output(&store, sweep.run_at(&[1, 0]).unwrap(), "vout");
+    assert!((cold - 500.0 / 1500.0).abs() < 1e-6, "{}", cold);
+}
+
+#[test]
+fn step_rejects_bad_specs() {
+    let mut engine = engine_for("V1 in 0 1\nR1 in 0 1k\n.op\n.end\n");
+    let mut store = ResultStore::new();
+    let plan = AnalysisPlan { cmd: AnalysisCmd::Op };
+    let step = |name: &str| StepSpec {
+        kind: DcSweepKind::Source,
+        name: name.to_string(),
+        values: StepValues::List(vec![1.0]),
+    };
+
+    let err = engine.run_step_sweep(&plan, &[step("V1"), step("V1"), step("V1"), step("V1")], &mut store);
+    assert!(err.unwrap_err().contains("1 to 3"));
+    let err = engine.run_step_sweep(&plan, &[step("V9")], &mut store);
+    assert!(err.unwrap_err().contains("'V9'"));
+    assert!(store.runs.is_empty());
+    assert!(store.step_sweeps.is_empty());
+}
+
+#[test]
+fn step_points_run_in_parallel_match_serial_order() {
+    let netlist = ".param rb=1k\nV1 in 0 1\nR1 in out 1k\nR2 out 0 {rb} rmod\n.model rmod r tc1=0.01\n\
+                   .param vout='v(out)'\n.step param rb list 1k 500 2k\n.step temp list 27 127\n\
+                   .step V1 list 1 3\n.op\n.end\n";
+    let run = |threads: usize| {
+        let mut engine = engine_for(netlist);
+        engine.set_threads(threads);
+        let steps = engine.circuit.steps.clone();
+        let mut store = ResultStore::new();
+        let index = engine.run_step_sweep(&AnalysisPlan { cmd: AnalysisCmd::Op }, &steps, &mut store).unwrap();
+        let vouts: Vec<f64> =
+            store.step_sweeps[index].runs.iter().map(|run| output(&store, *run, "vout")).collect();
+        // 扫描结束后恢复标称电路
+        let run_id = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+        assert!((output(&store, run_id, "vout") - 0.5).abs() < 1e-9);
+        vouts
+    };
+    let serial = run(1);
+    assert_eq!(serial.len(), 12);
+    // rb=2k, 127 ℃, V1=3: 3·4k/(1k + 4k)
+    assert!((serial[11] - 2.4).abs() < 1e-6, "{}", serial[11]);
+    assert_eq!(run(4), serial);
+    assert_eq!(run(0), serial);
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::{AnalysisCmd, DeviceKind as CircuitDeviceKind, Instance, NodeId};
+use sim_core::engine::Engine;
+use sim_core::mna::MnaBuilder;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist, DeviceKind, Stmt};
+use sim_core::result_store::{ResultStore, RunStatus};
+use sim_core::stamp::{update_transient_state, DeviceStamp, InstanceStamp, TransientState};
+use sim_core::state_table::StateKey;
+
+fn op_voltage(netlist: &str, node: &str) -> f64 {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "ast errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    assert_eq!(elab.error_count, 0, "elab errors: {:?}", elab.errors);
+    let circuit = build_circuit(&ast, &elab);
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, RunStatus::Converged), "run failed: {:?}", run);
+    let idx = run.node_names.iter().position(|name| name == node).unwrap();
+    run.solution[idx]
+}
+
+fn switch_divider(vctrl: f64, model: &str) -> f64 {
+    op_voltage(
+        &format!(
+            "\
+* switch in series with a 1k load
+VIN in 0 5
+VC c 0 {}
+S1 in out c 0 sw1
+RL out 0 1k
+.model sw1 sw {}
+.op
+.end
+",
+            vctrl, model
+        ),
+        "out",
+    )
+}
+
+#[test]
+fn switch_element_parses_nodes_and_model() {
+    let ast = parse_netlist("S1 a b c d sw1\nS2 a b c sw1\n.model sw1 sw ron=1\n");
+    let device = ast
+        .statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Device(device) if device.name == "S1" => Some(device),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(device.kind, DeviceKind::S));
+    assert_eq!(device.nodes, vec!["a", "b", "c", "d"]);
+    assert_eq!(device.model.as_deref(), Some("sw1"));
+    assert!(ast.errors.iter().all(|diag| diag.line == 2), "errors: {:?}", ast.errors);
+    assert!(!ast.errors.is_empty());
+}
+
+#[test]
+fn switch_dc_on_off_and_smooth_transition() {
+    let model = "ron=10 roff=1meg vt=1";
+    let on = switch_divider(3.0, model);
+    let off = switch_divider(0.0, model);
+    assert!((on - 5.0 * 1e3 / 1010.0).abs() < 1e-6, "on: {}", on);
+    assert!((off - 5.0 * 1e3 / (1e6 + 1e3)).abs() < 1e-6, "off: {}", off);
+    // VH < 0: 平滑过渡, 中点电阻为 sqrt(RON * ROFF)
+    let mid = switch_divider(1.0, "ron=10 roff=1meg vt=1 vh=-0.5");
+    let r = (10.0f64 * 1e6).sqrt();
+    assert!((mid - 5.0 * 1e3 / (r + 1e3)).abs() < 1e-6, "mid: {}", mid);
+}
+
+#[test]
+fn switch_tran_stamp_keeps_state_inside_hysteresis_band() {
+    let mut params = HashMap::new();
+    for (key, value) in [("ron", "10"), ("roff", "1k"), ("vt", "2"), ("vh", "0.5")] {
+        params.insert(key.to_string(), value.to_string());
+    }
+    let instance = Instance {
+        name: "S1".to_string(),
+        kind: CircuitDeviceKind::S,
+        nodes: vec![NodeId(1), NodeId(0), NodeId(2), NodeId(0)],
+        model: None,
+        params,
+        value: None,
+        control: None,
+        ac_mag: None,
+        ac_phase: None,
+        state_slot: 0,
+    };
+    let stamp = InstanceStamp {
+        instance: instance.clone(),
+    };
+    // 控制电压 2.2V 位于 [1.5, 2.5] 滞回区内
+    let x = vec![0.0, 1.0, 2.2];
+    let conductance = |state: &mut TransientState| {
+        let mut builder = MnaBuilder::new(3);
+        stamp.stamp_tran(&mut builder.context(), Some(&x), 1e-9, state).unwrap();
+        // 节点 1 的对角元即开关电导 (控制量在平坦区, 无跨导项)
+        let (ap, ai, ax) = builder.builder.finalize();
+        (ap[1] as usize..ap[2] as usize)
+            .find(|&k| ai[k] == 1)
+            .map(|k| ax[k])
+            .unwrap()
+    };
+    let mut state = TransientState::default();
+    state.switch_on.insert(StateKey::of(&instance), false);
+    assert!((conductance(&mut state) - 1e-3).abs() < 1e-12);
+    state.switch_on.insert(StateKey::of(&instance), true);
+    assert!((conductance(&mut state) - 0.1).abs() < 1e-12);
+
+    // 接受时间点时更新状态: 超过 VT+VH 才导通, 低于 VT-VH 才关断
+    let mut state = TransientState::default();
+    state.switch_on.insert(StateKey::of(&instance), false);
+    update_transient_state(std::slice::from_ref(&instance), &x, &mut state);
+    assert_eq!(state.switch_on.get(StateKey::of(&instance)), Some(&false));
+    update_transient_state(std::slice::from_ref(&instance), &[0.0, 1.0, 2.6], &mut state);
+    assert_eq!(state.switch_on.get(StateKey::of(&instance)), Some(&true));
+    update_transient_state(std::slice::from_ref(&instance), &x, &mut state);
+    assert_eq!(state.switch_on.get(StateKey::of(&instance)), Some(&true));
+    update_transient_state(std::slice::from_ref(&instance), &[0.0, 1.0, 1.4], &mut state);
+    assert_eq!(state.switch_on.get(StateKey::of(&instance)), Some(&false));
+}
+
+fn current_switch_divider(ictrl: f64) -> f64 {
+    op_voltage(
+        &format!(
+            "\
+* current-controlled switch: control current flows through VSENSE
+I1 0 c {}
+VSENSE c 0 0
+VIN in 0 5
+W1 in out VSENSE csw1
+RL out 0 1k
+.model csw1 csw ron=10 roff=1meg it=1m
+.op
+.end
+",
+            ictrl
+        ),
+        "out",
+    )
+}
+
+#[test]
+fn current_switch_parses_control_source() {
+    let ast = parse_netlist("W1 a b VSENSE csw1\nW2 a b csw1\n.model csw1 csw it=1m\n");
+    let device = ast
+        .statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Device(device) if device.name == "W1" => Some(device),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(device.kind, DeviceKind::W));
+    assert_eq!(device.nodes, vec!["a", "b"]);
+    assert_eq!(device.control.as_deref(), Some("VSENSE"));
+    assert_eq!(device.model.as_deref(), Some("csw1"));
+    assert!(ast.errors.iter().all(|diag| diag.line == 2), "errors: {:?}", ast.errors);
+    assert!(!ast.errors.is_empty());
+}
+
+#[test]
+fn current_switch_follows_control_current() {
+    let on = current_switch_divider(5e-3);
+    let off = current_switch_divider(0.0);
+    assert!((on - 5.0 * 1e3 / 1010.0).abs() < 1e-6, "on: {}", on);
+    assert!((off - 5.0 * 1e3 / (1e6 + 1e3)).abs() < 1e-6, "off: {}", off);
+}
+
+#[test]
+fn transient_lands_on_switching_times() {
+    // 控制电压在 5n 到 5.001n 之间线性上升到 1 V: B1 在 0.3 V (5.0003n) 翻转,
+    // S1 在 VT + VH = 0.6 V (5.0006n) 闭合; 两个时刻都落在一个时间点上
+    let netlist = "\
+* switching events inside one source edge
+V1 ctrl 0 PWL(0 0 5n 0 5.001n 1)
+B1 cmp 0 V={V(ctrl) > 0.3 ? 1 : 0}
+R3 cmp 0 1k
+V2 vdd 0 DC 1
+R2 vdd out 1k
+S1 out 0 ctrl 0 swmod
+.model swmod sw vt=0.5 vh=0.1 ron=1 roff=1meg
+.tran 1n 10n
+.end
+";
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "ast errors: {:?}", ast.errors);
+    let circuit = build_circuit(&ast, &elaborate_netlist(&ast));
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, RunStatus::Converged), "{:?}", run.message);
+    let node = |name: &str| run.node_names.iter().position(|n| n == name).unwrap();
+    let (cmp, out) = (node("cmp"), node("out"));
+
+    // 第一个越过切换的时间点: 落在切换时刻之后不超过 tstep·1e-4 处
+    let first = |flipped: &dyn Fn(&[f64]) -> bool| {
+        let idx = run.tran_solutions.iter().position(|x| flipped(x)).unwrap();
+        assert!(!flipped(&run.tran_solutions[idx - 1]));
+        run.tran_times[idx]
+    };
+    let t_cmp = first(&|x| x[cmp] > 0.5);
+    assert!(t_cmp >= 5.0003e-9 && t_cmp - 5.0003e-9 <= 1e-13, "{:e}", t_cmp);
+    let t_out = first(&|x| x[out] < 0.5);
+    assert!(t_out >= 5.0006e-9 && t_out - 5.0006e-9 <= 1e-13, "{:e}", t_out);
+    // 源波形的断点仍然保留
+    assert!(run.tran_times.iter().any(|t| (t - 5.001e-9).abs() < 1e-21));
+    assert!((run.tran_solutions.last().unwrap()[out] - 1.0 / 1001.0).abs() < 1e-6);
+}
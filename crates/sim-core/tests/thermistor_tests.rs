@@ -0,0 +1,62 @@
+mod common;
+
+use common::{node_index, run_first_analysis};
+
+fn ntc(celsius: f64) -> f64 {
+    10e3 * (3950.0 * (1.0 / (celsius + 273.15) - 1.0 / 298.15)).exp()
+}
+
+#[test]
+fn thermistor_follows_global_temperature_sweep() {
+    let run = run_first_analysis(
+        "\
+V1 in 0 1
+R1 in out 10k
+RT out 0 ntc
+.model ntc thermistor r25=10k beta=3950
+.dc temp 0 100 25
+.end
+",
+    );
+    let out = node_index(&run, "out");
+    assert_eq!(run.sweep_values.len(), 5);
+    for (celsius, solution) in run.sweep_values.iter().zip(&run.sweep_solutions) {
+        let r = ntc(*celsius);
+        let expected = r / (10e3 + r);
+        assert!((solution[out] - expected).abs() < 1e-9, "T = {}: {} vs {}", celsius, solution[out], expected);
+    }
+}
+
+#[test]
+fn thermistor_reads_temperature_from_node() {
+    // 温度节点电压即摄氏温度; 由电压源设定为 85 °C
+    let netlist = |analysis: &str| {
+        format!(
+            "\
+VT t 0 dc 85 ac 1
+V1 in 0 1
+R1 in out 10k
+RT out 0 ntc tnode=t
+.model ntc thermistor r25=10k beta=3950
+{}
+.end
+",
+            analysis
+        )
+    };
+    let run = run_first_analysis(&netlist(".op"));
+    let r = ntc(85.0);
+    let v = run.solution[node_index(&run, "out")];
+    assert!((v - r / (10e3 + r)).abs() < 1e-9, "V(out) = {}", v);
+
+    // 小信号: dV(out)/dT = d/dT [R / (10k + R)] = 10k R' / (10k + R)²
+    let h = 1e-3;
+    let slope = (ntc(85.0 + h) - ntc(85.0 - h)) / (2.0 * h);
+    let expected = (10e3 * slope / (10e3 + r).powi(2)).abs();
+    let ac = run_first_analysis(&netlist(".ac lin 1 1 1"));
+    let (db, phase) = ac.ac_solutions[0][node_index(&ac, "out")];
+    let gain = 10f64.powf(db / 20.0);
+    assert!((gain - expected).abs() < 1e-6 * expected, "gain = {}, expected = {}", gain, expected);
+    // NTC: 温度升高输出下降
+    assert!((phase.abs() - 180.0).abs() < 1e-6, "phase = {}", phase);
+}
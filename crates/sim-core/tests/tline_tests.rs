@@ -0,0 +1,144 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{node_index, run_first_analysis};
+use sim_core::circuit::{DeviceKind as CircuitDeviceKind, Instance, NodeId};
+use sim_core::diagnostic::codes;
+use sim_core::mna::MnaBuilder;
+use sim_core::netlist::{parse_netlist, DeviceKind, Stmt};
+use sim_core::stamp::{DeviceStamp, InstanceStamp, TlineSample, TransientState};
+use sim_core::state_table::StateKey;
+
+#[test]
+fn tline_element_requires_impedance_and_delay() {
+    let ast = parse_netlist("T1 a 0 b 0 z0=50 td=1n\nT2 a 0 b 0 z0=50\nT3 a 0 b 0 z0=75 f=1g nl=0.5\n");
+    let device = ast
+        .statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Device(device) if device.name == "T1" => Some(device),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(device.kind, DeviceKind::T));
+    assert_eq!(device.nodes, vec!["a", "0", "b", "0"]);
+    assert!(ast.errors.iter().all(|diag| diag.line == 2), "errors: {:?}", ast.errors);
+    assert!(ast.errors.iter().any(|diag| diag.code == codes::MISSING_VALUE));
+}
+
+#[test]
+fn tline_is_a_through_connection_at_dc() {
+    let run = run_first_analysis(
+        "\
+* matched source and load: the line passes DC unchanged
+V1 in 0 1
+RS in a 50
+T1 a 0 b 0 z0=50 td=1n
+RL b 0 50
+.op
+.end
+",
+    );
+    let va = run.solution[node_index(&run, "a")];
+    let vb = run.solution[node_index(&run, "b")];
+    assert!((va - 0.5).abs() < 1e-12, "va = {}", va);
+    assert!((vb - 0.5).abs() < 1e-12, "vb = {}", vb);
+}
+
+#[test]
+fn tline_ac_matched_line_is_a_pure_delay() {
+    let run = run_first_analysis(
+        "\
+* matched line: |Vb| = 1/2 and phase = -360 f TD
+V1 in 0 DC 0 AC 1
+RS in a 50
+T1 a 0 b 0 z0=50 td=1n
+RL b 0 50
+.ac dec 1 1meg 100meg
+.end
+",
+    );
+    let b = node_index(&run, "b");
+    for (freq, point) in run.ac_frequencies.iter().zip(&run.ac_solutions) {
+        let (db, phase) = point[b];
+        assert!((db - 20.0 * 0.5f64.log10()).abs() < 1e-9, "|Vb| = {} dB at {}", db, freq);
+        let expected = -360.0 * freq * 1e-9;
+        assert!((phase - expected).abs() < 1e-6, "phase = {} at {}, expected {}", phase, freq, expected);
+    }
+}
+
+#[test]
+fn tline_ac_open_quarter_wave_line_inverts_impedance() {
+    let run = run_first_analysis(
+        "\
+* open-ended quarter-wave stub looks like a short at its input
+V1 in 0 DC 0 AC 1
+RS in a 50
+T1 a 0 b 0 z0=50 f=10meg
+RL b 0 1e12
+.ac lin 1 10meg 10meg
+.end
+",
+    );
+    let (db, _) = run.ac_solutions[0][node_index(&run, "a")];
+    assert!(db < -100.0, "|Va| = {} dB", db);
+}
+
+fn tline_instance(params: &[(&str, &str)]) -> Instance {
+    Instance {
+        name: "T1".to_string(),
+        kind: CircuitDeviceKind::T,
+        nodes: vec![NodeId(1), NodeId(0), NodeId(2), NodeId(0)],
+        model: None,
+        params: params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>(),
+        value: None,
+        control: None,
+        ac_mag: None,
+        ac_phase: None,
+        state_slot: 0,
+    }
+}
+
+#[test]
+fn tline_tran_stamp_uses_delayed_waves() {
+    let stamp = InstanceStamp {
+        instance: tline_instance(&[("z0", "50"), ("td", "1.5n")]),
+    };
+    let mut state = TransientState {
+        time: 2e-9,
+        ..TransientState::default()
+    };
+    let sample = |time: f64, v2: f64, i2: f64| TlineSample {
+        time,
+        v2,
+        i2,
+        ..TlineSample::default()
+    };
+    state
+        .tline_history
+        .insert(StateKey::of(&stamp.instance), vec![sample(0.0, 0.0, 0.0), sample(1e-9, 1.0, 0.01), sample(2e-9, 1.0, 0.01)]);
+
+    // 延迟时刻 2.5n - 1.5n = 1n 落在历史内: 端口 1 的入射波 = V2 + Z0 I2 = 1.5
+    let mut mna = MnaBuilder::new(3);
+    stamp.stamp_tran(&mut mna.context(), None, 0.5e-9, &mut state).unwrap();
+    assert!((mna.rhs[3] - 1.5).abs() < 1e-12, "e1 = {}", mna.rhs[3]);
+    assert!(mna.rhs[4].abs() < 1e-12, "e2 = {}", mna.rhs[4]);
+    assert_eq!(state.tline_aux.get(StateKey::of(&stamp.instance)), Some(&(3, 4)));
+
+    // 延迟时刻 0.75n 落在 0 与 1n 之间时线性插值
+    let mut mna = MnaBuilder::new(3);
+    stamp.stamp_tran(&mut mna.context(), None, 0.25e-9, &mut state).unwrap();
+    assert!((mna.rhs[3] - 1.125).abs() < 1e-12, "e1 = {}", mna.rhs[3]);
+
+    // TD 短于步长: 延迟时刻 2.5n 落在当前步内, 对当前解的权重 alpha = 0.5, 已知部分取上一点的一半
+    let stamp = InstanceStamp {
+        instance: tline_instance(&[("z0", "50"), ("td", "0.5n")]),
+    };
+    let mut mna = MnaBuilder::new(3);
+    stamp.stamp_tran(&mut mna.context(), None, 1e-9, &mut state).unwrap();
+    assert!((mna.rhs[3] - 0.75).abs() < 1e-12, "e1 = {}", mna.rhs[3]);
+}
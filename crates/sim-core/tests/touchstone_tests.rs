@@ -0,0 +1,130 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::{node_index, run_first_analysis_ast};
+use num_complex::Complex64;
+use sim_core::diagnostic::codes;
+use sim_core::netlist::{elaborate_netlist, parse_netlist_file, DeviceKind, Stmt};
+use sim_core::touchstone::{parse_touchstone, ports_from_extension};
+
+fn touchstone_test_dir(name: &str) -> PathBuf {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("myspice_touchstone_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    dir
+}
+
+fn close(a: Complex64, b: Complex64) -> bool {
+    (a - b).norm() < 1e-9
+}
+
+#[test]
+fn touchstone_two_port_order_formats_and_interpolation() {
+    assert_eq!(ports_from_extension(std::path::Path::new("amp.S2P")), Some(2));
+    assert_eq!(ports_from_extension(std::path::Path::new("amp.txt")), None);
+
+    // 二端口记录顺序为 S11 S21 S12 S22
+    let data = parse_touchstone(
+        "! amplifier\n# MHz S MA R 75\n100 0.1 0 2 90 0 0 0.5 180\n200 0.1 0 4 90 0 0 0.5 180\n",
+        2,
+    )
+    .unwrap();
+    assert_eq!(data.z0, 75.0);
+    assert_eq!(data.frequencies, vec![100e6, 200e6]);
+    let s = data.s_at(150e6);
+    assert!(close(s[2], Complex64::new(0.0, 3.0)), "S21 = {}", s[2]);
+    assert!(close(s[1], Complex64::new(0.0, 0.0)), "S12 = {}", s[1]);
+    assert!(close(s[3], Complex64::new(-0.5, 0.0)));
+    // 范围外取端点
+    assert!(close(data.s_at(0.0)[2], Complex64::new(0.0, 2.0)));
+
+    let db = parse_touchstone("# Hz S DB\n1 -6.020599913 0 0 0 0 0 0 0\n", 2).unwrap();
+    assert!((db.s[0][0].re - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn touchstone_converts_normalized_z_and_y_to_s() {
+    // 100 Ohm 并联电阻, R=50 归一化 z = 2: S11 = -1/5, S21 = 4/5
+    let z = parse_touchstone("# GHz Z RI R 50\n1 2 0 2 0 2 0 2 0\n", 2).unwrap();
+    assert!(close(z.s[0][0], Complex64::new(-0.2, 0.0)), "S11 = {}", z.s[0][0]);
+    assert!(close(z.s[0][2], Complex64::new(0.8, 0.0)), "S21 = {}", z.s[0][2]);
+    // 单端口 100 Ohm: y = 0.5, S = (1 - y) / (1 + y)
+    let y = parse_touchstone("# GHz Y RI R 50\n1 0.5 0\n", 1).unwrap();
+    assert!(close(y.s[0][0], Complex64::new(1.0 / 3.0, 0.0)));
+}
+
+#[test]
+fn touchstone_multiport_rows_may_wrap() {
+    let text = "# GHz S RI\n1 0.1 0 0.2 0 0.3 0\n  0.4 0 0.5 0 0.6 0\n  0.7 0 0.8 0 0.9 0\n";
+    let data = parse_touchstone(text, 3).unwrap();
+    let row: Vec<f64> = data.s[0].iter().map(|value| value.re).collect();
+    assert_eq!(row, vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9]);
+    let err = parse_touchstone("# GHz S RI\n1 0.1 0 0.2 0\n", 3).unwrap_err();
+    assert!(err.contains("19 numbers"), "{}", err);
+}
+
+#[test]
+fn sparam_block_stamps_dc_and_ac() {
+    let dir = touchstone_test_dir("stamp");
+    // 50 Ohm 串联电阻 (S11 = 1/3, S21 = 2/3) 与 S21 = 2∠-90 的单向放大器
+    std::fs::write(dir.join("series.s2p"), "# Hz S RI R 50\n0 0.333333333333333 0 0.666666666666667 0 0.666666666666667 0 0.333333333333333 0\n").unwrap();
+    std::fs::write(dir.join("amp.s2p"), "# GHz S MA R 50\n1 0 0 2 -90 0 0 0 0\n2 0 0 2 -90 0 0 0 0\n").unwrap();
+    let top = dir.join("top.cir");
+    std::fs::write(
+        &top,
+        "\
+* S-parameter blocks
+V1 in 0 1
+S1 in mid 0 FILE=series.s2p
+RL mid 0 50
+P1 a 0 port=1 DC 0 AC 2
+S2 a b TSTONEFILE=\"amp.s2p\"
+P2 b 0 port=2
+.op
+.end
+",
+    )
+    .unwrap();
+
+    let ast = parse_netlist_file(&top);
+    let device = ast
+        .statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Device(device) if device.name == "S2" => Some(device),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(device.kind, DeviceKind::SParam));
+    assert_eq!(device.params[0].value, dir.join("amp.s2p").to_string_lossy());
+
+    let run = run_first_analysis_ast(&ast);
+    let mid = run.solution[node_index(&run, "mid")];
+    assert!((mid - 0.5).abs() < 1e-9, "mid = {}", mid);
+
+    let ac = std::fs::read_to_string(&top).unwrap().replace(".op", ".ac lin 3 1g 2g");
+    std::fs::write(&top, ac).unwrap();
+    let run = run_first_analysis_ast(&parse_netlist_file(&top));
+    let (a, b) = (node_index(&run, "a"), node_index(&run, "b"));
+    for point in &run.ac_solutions {
+        // 两端匹配: 入射 1V, 输出 2∠-90
+        assert!(point[a].0.abs() < 1e-6, "|Va| = {} dB", point[a].0);
+        assert!((point[b].0 - 20.0 * 2f64.log10()).abs() < 1e-6, "|Vb| = {} dB", point[b].0);
+        assert!((point[b].1 + 90.0).abs() < 1e-6, "phase = {}", point[b].1);
+    }
+}
+
+#[test]
+fn sparam_block_reports_missing_file_and_node_count() {
+    let dir = touchstone_test_dir("errors");
+    std::fs::write(dir.join("thru.s2p"), "# GHz S MA\n1 0 0 1 0 1 0 0 0\n").unwrap();
+    let top = dir.join("top.cir");
+    std::fs::write(&top, "* errors\nS1 a b c d FILE=thru.s2p\nS2 a b FILE=missing.s2p\nR1 a 0 1\n.end\n").unwrap();
+    let ast = parse_netlist_file(&top);
+    assert!(ast.errors.is_empty(), "ast errors: {:?}", ast.errors);
+    let elab = elaborate_netlist(&ast);
+    let errors: Vec<(usize, &str)> = elab.errors.iter().map(|diag| (diag.line, diag.code)).collect();
+    assert_eq!(errors, vec![(2, codes::NODE_COUNT), (3, codes::FILE_UNREADABLE)]);
+}
@@ -1,3 +1,6 @@
+mod common;
+
+use common::node_index;
 use sim_core::analysis::AnalysisPlan;
 use sim_core::circuit::AnalysisCmd;
 use sim_core::engine::Engine;
@@ -32,6 +35,8 @@ R2 out 0 1k
             tstop: 1e-5,
             tstart: 0.0,
             tmax: 1e-5,
+            uic: false,
+            windows: Vec::new(),
         },
     };
 
@@ -75,6 +80,8 @@ R2 out 0 1k
             tstop: 1e-5,
             tstart: 0.0,
             tmax: 1e-5,
+            uic: false,
+            windows: Vec::new(),
         },
     };
 
@@ -112,6 +119,8 @@ R2 out 0 1k
             tstop: 1e-5,
             tstart: 0.0,
             tmax: 1e-5,
+            uic: false,
+            windows: Vec::new(),
         },
     };
 
@@ -143,3 +152,418 @@ R2 out 0 1k
     // Clean up
     std::fs::remove_file(&path).ok();
 }
+
+#[test]
+fn tran_uic_starts_from_capacitor_ic() {
+    // RC = 10ms, 远大于仿真时长, 电容电压几乎保持初值
+    let netlist = "* uic\nR1 out 0 10k\nC1 out 0 1u IC=1\n.tran 1n 20n uic\n.end\n";
+    let circuit = parse_and_build(netlist);
+    let cmd = circuit.analysis[0].clone();
+    assert!(matches!(cmd, AnalysisCmd::Tran { uic: true, .. }));
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    let out = node_index(run, "out");
+
+    let last = run.tran_solutions.last().unwrap()[out];
+    assert!(last > 0.999 && last < 1.0, "v(out)={}", last);
+    assert!(*run.tran_times.last().unwrap() >= 20e-9);
+}
+
+#[test]
+fn tran_uic_uses_dot_ic_node_voltages() {
+    let netlist = "* uic\nR1 out 0 10k\nC1 out 0 1u\n.ic V(out)=2\n.tran 1n 10n UIC\n.end\n";
+    let circuit = parse_and_build(netlist);
+    assert_eq!(circuit.initial_conditions.len(), 1);
+    let cmd = circuit.analysis[0].clone();
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    let out = node_index(run, "out");
+
+    assert!((run.tran_solutions[0][out] - 2.0).abs() < 1e-12);
+    let last = run.tran_solutions.last().unwrap()[out];
+    assert!(last > 1.998 && last < 2.0, "v(out)={}", last);
+}
+
+#[test]
+fn tran_without_uic_starts_from_operating_point() {
+    let netlist = "* no uic\nR1 out 0 10k\nC1 out 0 1u IC=1\n.tran 1n 10n\n.end\n";
+    let circuit = parse_and_build(netlist);
+    let cmd = circuit.analysis[0].clone();
+    assert!(matches!(cmd, AnalysisCmd::Tran { uic: false, .. }));
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    let out = node_index(run, "out");
+    assert!(run.tran_solutions.last().unwrap()[out].abs() < 1e-9);
+}
+
+#[test]
+fn tran_uic_starts_from_inductor_ic() {
+    // L/R = 1ms, 远大于仿真时长, 电感电流几乎保持 IC=1, 电阻上压降为 -1V
+    let netlist = "* uic\nR1 a 0 1\nL1 a 0 1m IC=1\n.tran 1n 10n uic\n.end\n";
+    let circuit = parse_and_build(netlist);
+    let cmd = circuit.analysis[0].clone();
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    let a = node_index(run, "a");
+    let last = run.tran_solutions.last().unwrap()[a];
+    assert!(last < -0.9999 && last > -1.0, "v(a)={}", last);
+}
+
+#[test]
+fn tran_without_uic_starts_from_inductor_operating_current() {
+    // 工作点电流 1mA 流过电感, 瞬态应保持稳态而不是从零电流起步
+    let netlist = "* no uic\nV1 in 0 1\nR1 in a 1k\nL1 a 0 1u\n.tran 1n 10n\n.end\n";
+    let circuit = parse_and_build(netlist);
+    let cmd = circuit.analysis[0].clone();
+
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    let a = node_index(run, "a");
+    assert!(run.tran_solutions.iter().all(|x| x[a].abs() < 1e-6));
+}
+
+/// RC 放电 (τ = 1ms, 初值 1µV, 误差判据把步长限制在 τ/1000 左右), 返回末点相对解析解的误差
+fn rc_discharge_error(options: &str) -> f64 {
+    let netlist = format!("* rc\nR1 out 0 1k\nC1 out 0 1u IC=1u\n{}\n.tran 1u 1m uic\n.end\n", options);
+    let circuit = parse_and_build(&netlist);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, sim_core::result_store::RunStatus::Converged));
+    let out = node_index(run, "out");
+    let t = *run.tran_times.last().unwrap();
+    let exact = 1e-6 * (-t / 1e-3).exp();
+    (run.tran_solutions.last().unwrap()[out] - exact).abs() / exact
+}
+
+/// τ = R·C 的 RC 低通由 `source` 驱动, 按网表的 .tran 运行, 要求收敛
+fn run_rc(source: &str, rc: &str, tran: &str) -> sim_core::result_store::RunResult {
+    let netlist = format!("* rc\n{}\n{}\n{}\n.end\n", source, rc, tran);
+    let circuit = parse_and_build(&netlist);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = store.runs[run_id.0].clone();
+    assert!(matches!(run.status, sim_core::result_store::RunStatus::Converged), "{:?}", run.message);
+    run
+}
+
+#[test]
+fn tran_rc_step_with_default_tolerances_takes_few_steps() {
+    // 1V 阶跃经 τ = 1ms 的 RC 跑 3τ: 缺省 reltol 下约两万步, reltol=1e-6 时要上百万步;
+    // 1ns 的边沿以最小步长走过, 不会反复重试
+    let run = run_rc("V1 in 0 PULSE(0 1 0 1n 1n 10m 20m)", "R1 in out 1k\nC1 out 0 1u", ".tran 10u 3m");
+    assert!(run.iterations < 40_000, "{} steps", run.iterations);
+    let out = node_index(&run, "out");
+    let last = run.tran_solutions.last().unwrap()[out];
+    assert!((last - (1.0 - (-3.0f64).exp())).abs() < 5e-3, "v(out)={}", last);
+}
+
+#[test]
+fn tran_pulse_train_runs_past_every_edge() {
+    // 周期 10us 的脉冲驱动 τ = 1us 的 RC: 舍入误差会在断点前留下不足两倍最小步长的碎步,
+    // 这一步必须被接受, 否则在第二个下降沿 (16.002us) 处原地打转
+    let run = run_rc("V1 in 0 PULSE(0 1 1u 1n 1n 5u 10u)", "R1 in out 1k\nC1 out 0 1n", ".tran 10n 20u");
+    assert!((run.tran_times.last().unwrap() - 20e-6).abs() < 1e-15, "{:?}", run.tran_times.last());
+    for edge in [1e-6, 6.001e-6, 11e-6, 16.002e-6] {
+        assert!(run.tran_times.iter().any(|t| (t - edge).abs() < 1e-12), "edge {} missing", edge);
+    }
+    assert!(run.iterations < 100_000, "{} steps", run.iterations);
+    // 最后一个脉冲 16.002us 结束后放电约 4τ
+    let out = node_index(&run, "out");
+    let last = run.tran_solutions.last().unwrap()[out];
+    assert!((last - 0.0182).abs() < 1e-3, "v(out)={}", last);
+}
+
+#[test]
+fn tran_gear_integration_is_second_order() {
+    let euler = rc_discharge_error("");
+    let gear = rc_discharge_error(".options method=gear");
+    // 后向欧拉的全局误差约 t·h/(2τ²), Gear2 为 O(h²)
+    assert!(euler > 1e-4, "euler error = {}", euler);
+    assert!(gear < euler / 100.0, "gear error = {}, euler error = {}", gear, euler);
+}
+
+#[test]
+fn tran_trapezoidal_integration_is_second_order() {
+    let euler = rc_discharge_error("");
+    let trap = rc_discharge_error(".options method=trap");
+    assert!(trap < euler / 100.0, "trap error = {}, euler error = {}", trap, euler);
+}
+
+#[test]
+fn tran_rejects_unknown_integration_method() {
+    let circuit = parse_and_build("* rc\nR1 out 0 1k\nC1 out 0 1u\n.options method=rk4\n.tran 1u 10u\n.end\n");
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, sim_core::result_store::RunStatus::Failed));
+    assert_eq!(run.message.as_deref(), Some("unknown integration method 'rk4'"));
+}
+
+fn run_divider(source: &str, tran: &str) -> sim_core::result_store::RunResult {
+    let netlist = format!("* divider\n{}\nR1 in out 1k\nR2 out 0 1k\n{}\n.end\n", source, tran);
+    let circuit = parse_and_build(&netlist);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = store.runs[run_id.0].clone();
+    assert!(matches!(run.status, sim_core::result_store::RunStatus::Converged), "run failed: {:?}", run.message);
+    run
+}
+
+#[test]
+fn tran_lands_on_narrow_pulse_edges() {
+    // 5ns 宽的脉冲远小于 1us 的输出步长, 不按断点走会被整个跨过
+    let run = run_divider("V1 in 0 PULSE(0 1 10u 1n 1n 5n)", ".tran 1u 50u");
+    let out = node_index(&run, "out");
+    for corner in [10e-6, 10.001e-6, 10.006e-6, 10.007e-6] {
+        let idx = run
+            .tran_times
+            .iter()
+            .position(|t| (t - corner).abs() < 1e-15)
+            .unwrap_or_else(|| panic!("breakpoint {} missing from {:?}", corner, run.tran_times));
+        let expected = if corner == 10e-6 || corner == 10.007e-6 { 0.0 } else { 0.5 };
+        assert!((run.tran_solutions[idx][out] - expected).abs() < 1e-9, "t = {}", corner);
+    }
+}
+
+#[test]
+fn tran_follows_pwl_source() {
+    // 1ns 的上升沿与紧随的 1ns 宽的尖峰
+    let run = run_divider("V1 in 0 PWL(10u 0 10.001u 1 20u 1 20u 3 20.001u 0)", ".tran 1u 40u");
+    let out = node_index(&run, "out");
+    for corner in [10e-6, 10.001e-6, 20e-6, 20.001e-6] {
+        assert!(run.tran_times.iter().any(|t| (t - corner).abs() < 1e-15), "breakpoint {} missing", corner);
+    }
+    let vin = |t: f64| match t {
+        t if t <= 10e-6 => 0.0,
+        t if t < 10.001e-6 => (t - 10e-6) / 1e-9,
+        t if t <= 20e-6 + 1e-15 => 1.0,
+        t if t < 20.001e-6 => 3.0 * (20.001e-6 - t) / 1e-9,
+        _ => 0.0,
+    };
+    for (t, x) in run.tran_times.iter().zip(&run.tran_solutions) {
+        assert!((x[out] - vin(*t) / 2.0).abs() < 1e-6, "t = {}, out = {}", t, x[out]);
+    }
+}
+
+#[test]
+fn pulse_without_dc_value_starts_at_v1() {
+    let run = run_divider("V1 in 0 PULSE(0.4 1 1u)", ".tran 1u 3u");
+    let out = node_index(&run, "out");
+    assert!((run.tran_solutions[0][out] - 0.2).abs() < 1e-9);
+    assert!((run.tran_solutions.last().unwrap()[out] - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn malformed_pwl_is_a_parse_error() {
+    let ast = parse_netlist("V1 in 0 PWL(1u 0 0 1)\nR1 in 0 1k\n.end\n");
+    assert_eq!(ast.errors.len(), 1, "{:?}", ast.errors);
+    assert_eq!(ast.errors[0].code, sim_core::diagnostic::codes::MISSING_ARGUMENTS);
+}
+
+#[test]
+fn tran_honors_tstep_and_tstop() {
+    let run = run_divider("V1 in 0 DC 1", ".tran 1n 1u");
+    assert_eq!(run.tran_times.first(), Some(&0.0));
+    assert!((run.tran_times.last().unwrap() - 1e-6).abs() < 1e-18, "last = {:?}", run.tran_times.last());
+    // 未给 TMAX 时最大步长为 min(TSTEP, (TSTOP - TSTART) / 50)
+    let max_step = run.tran_times.windows(2).map(|w| w[1] - w[0]).fold(0.0, f64::max);
+    assert!(max_step <= 1e-9 * (1.0 + 1e-9), "max step = {}", max_step);
+    assert!(run.tran_times.len() > 1000);
+}
+
+#[test]
+fn tran_stores_results_from_tstart() {
+    let run = run_divider("V1 in 0 PULSE(0 1 100n 1n 1n 200n)", ".tran 10n 1u 500n 20n");
+    let out = node_index(&run, "out");
+    assert!((run.tran_times[0] - 500e-9).abs() < 1e-18, "first = {}", run.tran_times[0]);
+    assert!((run.tran_times.last().unwrap() - 1e-6).abs() < 1e-18);
+    // 源波形从 t=0 起算, 保存窗口内脉冲已经结束
+    assert!(run.tran_solutions.iter().all(|x| x[out].abs() < 1e-9));
+    let max_step = run.tran_times.windows(2).map(|w| w[1] - w[0]).fold(0.0, f64::max);
+    assert!(max_step > 10e-9 && max_step <= 20e-9 * (1.0 + 1e-9), "max step = {}", max_step);
+}
+
+#[test]
+fn tran_rejects_invalid_parameters() {
+    let circuit = parse_and_build("* bad\nV1 in 0 DC 1\nR1 in 0 1k\n.tran 1n 1u 2u\n.end\n");
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert!(matches!(run.status, sim_core::result_store::RunStatus::Failed));
+    assert!(run.message.as_deref().unwrap_or("").starts_with("invalid .tran parameters"), "{:?}", run.message);
+}
+
+#[test]
+fn tran_uic_reports_defaulted_states() {
+    // C1 由 IC= 给定, C2 两端由 .ic 给定, C3 与 L1 没有初值
+    let netlist = "* uic\nV1 a 0 DC 1\nR1 a b 1k\nC1 b 0 1u IC=0.5\nC2 c 0 1u\nR2 c b 1k\nC3 b d 1u\nR3 d 0 1k\nL1 d 0 1m\n.ic V(c)=0.2\n.tran 1n 10n UIC\n.end\n";
+    let circuit = parse_and_build(netlist);
+    let ic_nodes = circuit.initial_conditions.clone();
+    assert_eq!(
+        sim_core::stamp::defaulted_initial_states(&circuit.instances.instances, &ic_nodes),
+        vec!["C3".to_string(), "L1".to_string()]
+    );
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    let run = &store.runs[run_id.0];
+    assert_eq!(run.message.as_deref(), Some("UIC: no initial condition for C3, L1; started from 0"));
+}
+
+#[test]
+fn tran_uic_with_all_states_given_has_no_message() {
+    let netlist = "* uic\nR1 out 0 10k\nC1 out 0 1u\n.ic V(out)=2\n.tran 1n 10n UIC\n.end\n";
+    let circuit = parse_and_build(netlist);
+    let cmd = circuit.analysis[0].clone();
+    let mut engine = Engine::new_default(circuit);
+    let mut store = ResultStore::new();
+    let run_id = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    assert_eq!(store.runs[run_id.0].message, None);
+}
+
+#[test]
+fn tran_windows_are_parsed() {
+    let circuit = parse_and_build("V1 a 0 1\nR1 a 0 1k\n.tran 1n 10n 10p 12n 1n 30n start=5n\n.tran 1n 100n 0 1n\n.end\n");
+    match &circuit.analysis[0] {
+        AnalysisCmd::Tran { tstep, tstop, tstart, tmax, windows, .. } => {
+            assert_eq!((*tstep, *tstart, *tmax), (1e-9, 5e-9, 1e-9));
+            assert!((tstop - 30e-9).abs() < 1e-18);
+            let expected = [(1e-9, 10e-9), (10e-12, 12e-9), (1e-9, 30e-9)];
+            assert_eq!(windows.len(), expected.len());
+            for (window, (tstep, tstop)) in windows.iter().zip(expected) {
+                assert!((window.tstep - tstep).abs() < 1e-21 && (window.tstop - tstop).abs() < 1e-18, "{:?}", window);
+            }
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    // tstep tstop tstart tmax 形式不受影响
+    assert!(matches!(
+        &circuit.analysis[1],
+        AnalysisCmd::Tran { tstart, tmax, windows, .. } if *tstart == 0.0 && *tmax == 1e-9 && windows.is_empty()
+    ));
+}
+
+#[test]
+fn tran_windows_store_dense_points_only_inside_the_fine_window() {
+    let run = run_divider("V1 in 0 DC 1", ".tran 1n 10n 10p 12n 1n 30n");
+    let times = &run.tran_times;
+    assert!((times.last().unwrap() - 30e-9).abs() < 1e-18);
+    // 窗口终点落在时间点上
+    for end in [10e-9, 12e-9] {
+        assert!(times.iter().any(|t| (t - end).abs() < 1e-18), "missing {}", end);
+    }
+    let eps = 1e-15;
+    let mut fine = 0;
+    for pair in times.windows(2) {
+        let step = pair[1] - pair[0];
+        if pair[1] <= 10e-9 + eps || pair[0] >= 12e-9 - eps {
+            assert!(step <= 1e-9 + eps, "step {} at {}", step, pair[1]);
+        } else {
+            assert!(step <= 10e-12 + eps, "step {} at {}", step, pair[1]);
+            fine += 1;
+        }
+    }
+    assert!(fine >= 200, "{}", fine);
+    // 细窗口之外点数稀疏
+    assert!(times.len() < fine + 60, "{}", times.len());
+}
+
+#[test]
+fn tran_stops_once_nodes_settle_for_consecutive_steps() {
+    let full = run_divider("V1 in 0 DC 1", ".tran 1u 100u");
+    assert!((full.tran_times.last().unwrap() - 100e-6).abs() < 1e-15);
+    assert_eq!(full.message, None);
+
+    let run = run_divider("V1 in 0 DC 1", ".options ssdetect=5 sstol=1u\n.tran 1u 100u");
+    // t=0 之后连续 5 步与前一步相同
+    assert_eq!(run.tran_times.len(), 6, "{:?}", run.tran_times);
+    let stop = *run.tran_times.last().unwrap();
+    assert!(stop < 100e-6);
+    assert_eq!(run.message, Some(format!("steady state reached at t={:e}; transient stopped early", stop)));
+}
+
+#[test]
+fn tran_stops_once_a_periodic_waveform_repeats() {
+    // 10us 周期的方波: 与一个周期之前逐点比较, 连续 2 个周期一致后停止
+    let source = "V1 in 0 PULSE(0 1 0 1n 1n 4u 10u)";
+    let run = run_divider(source, ".options ssdetect=2 ssperiod=10u\n.tran 1u 100u");
+    let stop = *run.tran_times.last().unwrap();
+    assert!((30e-6 - 1e-12..31e-6).contains(&stop), "{}", stop);
+    assert!(run.message.as_deref().unwrap().starts_with("steady state reached"), "{:?}", run.message);
+
+    // 检测窗口不足时一直算到 tstop
+    let run = run_divider(source, ".options ssdetect=20 ssperiod=10u\n.tran 1u 100u");
+    assert!((run.tran_times.last().unwrap() - 100e-6).abs() < 1e-15);
+    assert_eq!(run.message, None);
+}
+
+#[test]
+fn steady_state_waits_for_delayed_sources() {
+    // 10us 之前一直平静, 但脉冲还没来: 逐步检测要等到最后一个转折点 (16.002us) 之后
+    let run = run_divider("V1 in 0 PULSE(0 1 10u 1n 1n 6u)", ".options ssdetect=5\n.tran 1u 100u");
+    let stop = *run.tran_times.last().unwrap();
+    assert!(stop > 16.002e-6 && stop < 100e-6, "{}", stop);
+    assert!(run.message.as_deref().unwrap().starts_with("steady state reached"), "{:?}", run.message);
+
+    // 按周期检测: 从 10us 进入周期后再比较, 连续 2 个周期一致
+    let run = run_divider("V1 in 0 PULSE(0 1 10u 1n 1n 4u 10u)", ".options ssdetect=2 ssperiod=10u\n.tran 1u 100u");
+    let stop = *run.tran_times.last().unwrap();
+    assert!((40e-6 - 1e-12..41e-6).contains(&stop), "{}", stop);
+}
+
+#[test]
+fn steady_state_detector_restarts_the_window_on_change() {
+    use sim_core::analysis::{SteadyStateConfig, SteadyStateDetector};
+    let options = [("ssdetect", "2"), ("sstol", "1m")]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let config = SteadyStateConfig::from_options(&options).unwrap();
+    assert_eq!(config, SteadyStateConfig { windows: 2, tol: 1e-3, period: None });
+    assert_eq!(SteadyStateConfig::from_options(&Default::default()), None);
+
+    let mut detector = SteadyStateDetector::new(config);
+    let steady: Vec<bool> = [1.0, 1.0005, 1.1, 1.1, 1.1005]
+        .iter()
+        .enumerate()
+        .map(|(i, v)| detector.accept(i as f64, &[*v]))
+        .collect();
+    assert_eq!(steady, vec![false, false, false, false, true]);
+}
+
+#[test]
+fn transient_error_tolerances_follow_options() {
+    use sim_core::analysis::TimeStepConfig;
+    let options = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    assert_eq!(TimeStepConfig::tolerances(&options(&[])), (1e-9, 1e-3));
+    assert_eq!(TimeStepConfig::tolerances(&options(&[("reltol", "1e-6")])), (1e-9, 1e-6));
+    assert_eq!(TimeStepConfig::tolerances(&options(&[("reltol", "1e-3"), ("vntol", "1e-6")])), (1e-6, 1e-3));
+    let (abs_tol, rel_tol) = TimeStepConfig::tolerances(&options(&[("reltol", "1e-3"), ("trtol", "7")]));
+    assert!((abs_tol - 7e-9).abs() < 1e-20 && (rel_tol - 7e-3).abs() < 1e-15, "{} {}", abs_tol, rel_tol);
+}
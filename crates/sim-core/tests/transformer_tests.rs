@@ -0,0 +1,66 @@
+mod common;
+
+use common::{node_index, run_first_analysis};
+use sim_core::diagnostic::codes;
+use sim_core::netlist::{parse_netlist, DeviceKind, Stmt};
+
+#[test]
+fn transformer_element_parses_ratio() {
+    let ast = parse_netlist("N1 p 0 s 0 2\nN2 p 0 s 2\n");
+    let device = ast
+        .statements
+        .iter()
+        .find_map(|stmt| match stmt {
+            Stmt::Device(device) if device.name == "N1" => Some(device),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(device.kind, DeviceKind::N));
+    assert_eq!(device.nodes, vec!["p", "0", "s", "0"]);
+    assert_eq!(device.value.as_deref(), Some("2"));
+    assert!(ast.errors.iter().all(|diag| diag.line == 2), "errors: {:?}", ast.errors);
+    assert!(ast.errors.iter().any(|diag| diag.code == codes::MISSING_VALUE));
+}
+
+#[test]
+fn transformer_reflects_load_by_ratio_squared() {
+    let run = run_first_analysis(
+        "\
+* 2:1 step-down transformer, the 1k load appears as 4k on the primary
+V1 in 0 10
+RSRC in p 1k
+N1 p 0 s 0 2
+RL s 0 1k
+.op
+.end
+",
+    );
+    let vp = run.solution[node_index(&run, "p")];
+    let vs = run.solution[node_index(&run, "s")];
+    assert!((vp - 8.0).abs() < 1e-9, "vp = {}", vp);
+    assert!((vs - 4.0).abs() < 1e-9, "vs = {}", vs);
+    // 初级电流 = 次级电流 / n
+    let ip = (10.0 - vp) / 1e3;
+    assert!((ip - vs / 1e3 / 2.0).abs() < 1e-12, "ip = {}", ip);
+}
+
+#[test]
+fn transformer_ac_isolated_secondary_with_reversed_polarity() {
+    let run = run_first_analysis(
+        "\
+* 1:3 step-up with the secondary connected in reverse
+V1 p 0 DC 0 AC 1
+N1 p 0 0 s 0.333333333333
+RL s 0 10k
+.ac dec 1 1k 1meg
+.end
+",
+    );
+    let s = node_index(&run, "s");
+    // ac_solutions 按 (幅度 dB, 相位 度) 存储
+    for point in &run.ac_solutions {
+        let (db, phase) = point[s];
+        assert!((db - 20.0 * 3.0f64.log10()).abs() < 1e-6, "|Vs| = {} dB", db);
+        assert!((phase.abs() - 180.0).abs() < 1e-6, "phase = {}", phase);
+    }
+}
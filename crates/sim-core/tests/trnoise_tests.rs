@@ -0,0 +1,60 @@
+mod common;
+
+use common::{node_index, run_first_analysis};
+use sim_core::diagnostic::codes;
+use sim_core::netlist::{parse_netlist, Stmt};
+use sim_devices::trnoise::{parse_trnoise, TrNoise};
+
+fn noise_divider(seed: u64) -> String {
+    format!("V1 in 0 DC 1 TRNOISE(10m 1n) seed={}\nR1 in out 1k\nR2 out 0 1k\n.tran 0.1n 50n\n.end\n", seed)
+}
+
+#[test]
+fn trnoise_parses_attached_and_spaced_forms() {
+    let ast = parse_netlist("V1 a 0 DC 1 TRNOISE(1m 1n 0 0) seed=3\nI1 0 b trnoise (1m, 1n, 1, 2u)\nV2 c 0 TRNOISE(1m)\n");
+    let params: Vec<Vec<(String, String)>> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Device(device) => Some(device.params.iter().map(|p| (p.key.clone(), p.value.clone())).collect()),
+            _ => None,
+        })
+        .collect();
+    assert!(params[0].contains(&("trnoise".to_string(), "1m 1n 0 0".to_string())), "{:?}", params[0]);
+    assert!(params[0].contains(&("seed".to_string(), "3".to_string())));
+    let spec = &params[1][0].1;
+    assert_eq!(parse_trnoise(spec, 0).map(|p| (p.nalpha, p.namp)), Some((1.0, 2e-6)), "spec = {}", spec);
+
+    let errors: Vec<(usize, &str)> = ast.errors.iter().map(|diag| (diag.line, diag.code)).collect();
+    assert_eq!(errors, vec![(3, codes::MISSING_ARGUMENTS)]);
+}
+
+#[test]
+fn trnoise_holds_one_sample_per_interval() {
+    let run = run_first_analysis(&noise_divider(7));
+    let out = node_index(&run, "out");
+    let mut noise = TrNoise::new(parse_trnoise("10m 1n", 7).unwrap());
+    // 工作点不含噪声
+    assert!((run.tran_solutions[0][out] - 0.5).abs() < 1e-12);
+    for (t, x) in run.tran_times.iter().zip(&run.tran_solutions).skip(1) {
+        let expected = (1.0 + noise.value_at(*t)) / 2.0;
+        assert!((x[out] - expected).abs() < 1e-9, "t = {}: {} vs {}", t, x[out], expected);
+    }
+    // 每个采样区间的结尾都是一个时间点
+    for k in 1..50 {
+        let t = k as f64 * 1e-9;
+        assert!(run.tran_times.iter().any(|time| (time - t).abs() < 1e-18), "missing sample time {}", t);
+    }
+}
+
+#[test]
+fn trnoise_is_reproducible_per_seed() {
+    let out = |seed| {
+        let run = run_first_analysis(&noise_divider(seed));
+        let idx = node_index(&run, "out");
+        run.tran_solutions.iter().map(|x| x[idx]).collect::<Vec<f64>>()
+    };
+    let first = out(1);
+    assert_eq!(first, out(1));
+    assert_ne!(first, out(2));
+}
@@ -0,0 +1,150 @@
+use sim_core::analysis::AnalysisPlan;
+use sim_core::circuit::{AnalysisCmd, DcSweepKind, StepSpec, StepValues};
+use sim_core::engine::Engine;
+use sim_core::netlist::{build_circuit, elaborate_netlist, parse_netlist};
+use sim_core::op_file::{format_op, parse_op, read_op, SavedOp};
+use sim_core::result_store::{ResultStore, RunStatus};
+
+const DIODE: &str = "* diode bias\n.param vdd=5\nV1 in 0 {vdd}\nR1 in a 1k\nD1 a 0 dmod\n\
+                     .model dmod d is=1e-14\n.param va=v(a)\n.op\n.end\n";
+
+fn build_engine(netlist: &str) -> Engine {
+    let ast = parse_netlist(netlist);
+    assert!(ast.errors.is_empty(), "parse errors: {:?}", ast.errors);
+    Engine::new_default(build_circuit(&ast, &elaborate_netlist(&ast)))
+}
+
+fn voltage(store: &ResultStore, run: usize, node: &str) -> f64 {
+    let result = &store.runs[run];
+    let idx = result.node_names.iter().position(|name| name == node).unwrap();
+    result.solution[idx]
+}
+
+#[test]
+fn tune_op_starts_from_the_last_operating_point() {
+    let mut engine = build_engine(DIODE);
+    let mut store = ResultStore::new();
+    let cold = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    assert!(matches!(store.runs[cold.0].status, RunStatus::Converged));
+    let cold_iterations = store.runs[cold.0].iterations;
+
+    let tuned = engine.tune_op(&[("R1", 1.01e3)], &mut store).unwrap();
+    assert!(tuned.warm);
+    let result = &store.runs[tuned.run_id.0];
+    assert!(matches!(result.status, RunStatus::Converged));
+    assert_eq!(result.iterations, tuned.iterations);
+    assert!(tuned.iterations < cold_iterations, "{} vs {}", tuned.iterations, cold_iterations);
+    assert_eq!(tuned.iterations_saved, cold_iterations - tuned.iterations);
+    // 输出参数随新的工作点更新
+    assert_eq!(result.output_params[0].1, voltage(&store, tuned.run_id.0, "a"));
+
+    // 与同样取值的冷启动结果一致
+    let mut reference = build_engine(&DIODE.replace("R1 in a 1k", "R1 in a 1.01k"));
+    let run = reference.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    let (warm_va, cold_va) = (voltage(&store, tuned.run_id.0, "a"), voltage(&store, run.0, "a"));
+    assert!((warm_va - cold_va).abs() < 1e-9, "{} vs {}", warm_va, cold_va);
+}
+
+#[test]
+fn tune_op_accepts_params_and_instance_params() {
+    let mut engine = build_engine(DIODE);
+    let mut store = ResultStore::new();
+    // 没有已收敛的工作点时冷启动
+    let first = engine.tune_op(&[("vdd", 3.0)], &mut store).unwrap();
+    assert!(!first.warm);
+    assert_eq!(first.iterations_saved, 0);
+    assert_eq!(voltage(&store, first.run_id.0, "in"), 3.0);
+    let i_first = (3.0 - voltage(&store, first.run_id.0, "a")) / 1e3;
+
+    // 修改保留在电路中: vdd 仍为 3
+    let second = engine.tune_op(&[("@R1[r]", 2e3)], &mut store).unwrap();
+    assert!(second.warm);
+    assert_eq!(voltage(&store, second.run_id.0, "in"), 3.0);
+    let i_second = (3.0 - voltage(&store, second.run_id.0, "a")) / 2e3;
+    assert!(i_second < i_first);
+
+    let before = store.runs.len();
+    let err = engine.tune_op(&[("vdd", 4.0), ("Rnone", 1.0)], &mut store).unwrap_err();
+    assert!(err.contains("'Rnone'"), "{}", err);
+    assert_eq!(store.runs.len(), before);
+}
+
+fn op_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("myspice_op_{}_{}.op", name, std::process::id()))
+}
+
+#[test]
+fn saved_op_round_trips_through_text() {
+    let op = SavedOp {
+        nodes: vec![("out".to_string(), 0.1 + 0.2)],
+        branches: vec![("V1".to_string(), -1.25e-3)],
+        junctions: vec![("D1".to_string(), 0.65)],
+    };
+    assert_eq!(parse_op(&format_op(&op)).unwrap(), op);
+    assert!(parse_op("node out\n").unwrap_err().contains("line 1"));
+    assert!(parse_op("* c\nstate x 1\n").unwrap_err().contains("line 2"));
+}
+
+#[test]
+fn loaded_op_is_the_initial_guess_of_later_runs() {
+    let path = op_path("diode");
+    let mut store = ResultStore::new();
+    let mut engine = build_engine(DIODE);
+    assert!(engine.save_op(&path).is_err());
+    let cold = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    engine.save_op(&path).unwrap();
+    let saved = read_op(&path).unwrap();
+    assert_eq!(saved.branches.len(), 1);
+    assert!(!saved.junctions.is_empty());
+
+    // 新建的引擎读入保存的工作点后几乎不需要迭代, 解与冷启动一致
+    let mut reloaded = build_engine(DIODE);
+    let matched = reloaded.load_op(&path).unwrap();
+    assert_eq!(matched, saved.nodes.len() + saved.branches.len() + saved.junctions.len());
+    let warm = reloaded.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    assert!(matches!(store.runs[warm.0].status, RunStatus::Converged));
+    assert!(store.runs[warm.0].iterations < store.runs[cold.0].iterations);
+    assert!((voltage(&store, warm.0, "a") - voltage(&store, cold.0, "a")).abs() < 1e-9);
+
+    // 瞬态的初始工作点同样从保存的解出发; 电路中没有的名字被忽略
+    let tran = DIODE.replace(".op", ".tran 1u 5u").replace("D1 a 0 dmod", "D1 a 0 dmod\nR2 a 0 1meg");
+    let mut engine = build_engine(&tran);
+    assert_eq!(engine.load_op(&path).unwrap(), matched);
+    let cmd = engine.circuit.analysis[0].clone();
+    let run = engine.run_with_store(&AnalysisPlan { cmd }, &mut store);
+    assert!(matches!(store.runs[run.0].status, RunStatus::Converged), "{:?}", store.runs[run.0].message);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn loaded_op_seeds_parallel_step_points() {
+    let path = op_path("step");
+    let mut store = ResultStore::new();
+    let mut engine = build_engine(DIODE);
+    let cold = engine.run_with_store(&AnalysisPlan { cmd: AnalysisCmd::Op }, &mut store);
+    engine.save_op(&path).unwrap();
+    let cold_iterations = store.runs[cold.0].iterations;
+
+    // 多线程时每个工作线程的副本同样从读入的工作点出发, 迭代次数与结果都与串行一致
+    let steps = [StepSpec {
+        kind: DcSweepKind::Param,
+        name: "vdd".to_string(),
+        values: StepValues::List(vec![5.0, 5.0, 5.0, 5.0]),
+    }];
+    let run = |threads: usize| {
+        let mut engine = build_engine(DIODE);
+        engine.load_op(&path).unwrap();
+        engine.set_threads(threads);
+        let mut store = ResultStore::new();
+        let index = engine.run_step_sweep(&AnalysisPlan { cmd: AnalysisCmd::Op }, &steps, &mut store).unwrap();
+        store.step_sweeps[index]
+            .runs
+            .iter()
+            .map(|run| (store.runs[run.0].iterations, voltage(&store, run.0, "a")))
+            .collect::<Vec<_>>()
+    };
+    let serial = run(1);
+    assert!(serial.iter().all(|(iterations, _)| *iterations < cold_iterations), "{serial:?}");
+    assert_eq!(run(4), serial);
+    let _ = std::fs::remove_file(&path);
+}
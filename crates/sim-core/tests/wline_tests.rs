@@ -0,0 +1,128 @@
+mod common;
+
+use common::{node_index, run_first_analysis};
+use sim_core::diagnostic::codes;
+use sim_core::netlist::{elaborate_netlist, parse_netlist, DeviceKind, Stmt};
+
+/// 两根耦合导体的总线: 每米 L = 250n, C = 100p (Z0 = 50, 5 ns/m), 可选互感与互容
+const BUS_MODEL: &str = "\
+.model bus w modeltype=rlgc n=2 r11=10 r22=10 l11=250n l22=250n l12=50n c11=100p c22=100p c12=-20p
+.model single w n=1 l11=250n c11=100p
+";
+
+#[test]
+fn wline_with_n_parses_as_multiconductor_line() {
+    let ast = parse_netlist("W1 a1 a2 0 b1 b2 0 N=2 L=0.1 RLGCMODEL=bus\nW2 x 0 vsense sw\n");
+    assert!(ast.errors.is_empty(), "{:?}", ast.errors);
+    let devices: Vec<_> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Device(device) => Some(device),
+            _ => None,
+        })
+        .collect();
+    assert!(matches!(devices[0].kind, DeviceKind::WLine));
+    assert_eq!(devices[0].nodes.len(), 6);
+    assert_eq!(devices[0].model.as_deref(), Some("bus"));
+    // 没有 N= 的 W 仍是电流控制开关
+    assert!(matches!(devices[1].kind, DeviceKind::W));
+
+    let missing = parse_netlist("W1 a 0 b 0 N=1 L=0.1\n");
+    assert!(missing.errors.iter().any(|diag| diag.code == codes::MISSING_REFERENCE));
+
+    let ast = parse_netlist(&format!("{}W1 a1 a2 0 b1 b2 N=2 L=0.1 RLGCMODEL=bus\n", BUS_MODEL));
+    let elab = elaborate_netlist(&ast);
+    assert!(elab.errors.iter().any(|diag| diag.code == codes::NODE_COUNT && diag.message.contains("requires 6 nodes")));
+}
+
+#[test]
+fn wline_dc_is_series_resistance_per_conductor() {
+    // 0.5 m, 每根导体 10 Ω/m 的串联电阻 -> 5 Ω; 直流下电容开路, 互感不起作用
+    let netlist = format!(
+        "{}V1 in 0 1
+W1 in 0 0 out1 out2 0 N=2 L=0.5 RLGCMODEL=bus NSEG=5
+RL1 out1 0 45
+RL2 out2 0 45
+.op
+.end
+",
+        BUS_MODEL
+    );
+    let run = run_first_analysis(&netlist);
+    let v1 = run.solution[node_index(&run, "out1")];
+    let v2 = run.solution[node_index(&run, "out2")];
+    assert!((v1 - 0.9).abs() < 1e-6, "V(out1) = {}", v1);
+    assert!(v2.abs() < 1e-9, "V(out2) = {}", v2);
+    // 分 5 节, 每根导体 4 个内部节点
+    assert!(run.node_names.iter().any(|name| name == "w1#4_2"));
+    assert!(!run.node_names.iter().any(|name| name == "w1#5_1"));
+}
+
+#[test]
+fn wline_matched_single_conductor_delays_signal() {
+    // 匹配的无损线: V(out) = V(src)/2 · e^{-jωTD}, TD = 0.5 ns, 100 MHz 时相移 18°
+    let netlist = format!(
+        "{}VS src 0 dc 0 ac 1
+RS src in 50
+W1 in 0 out 0 N=1 L=0.1 RLGCMODEL=single NSEG=40
+RL out 0 50
+.ac lin 1 100meg 100meg
+.end
+",
+        BUS_MODEL
+    );
+    let run = run_first_analysis(&netlist);
+    let (db, phase) = run.ac_solutions[0][node_index(&run, "out")];
+    let mag = 10f64.powf(db / 20.0);
+    assert!((mag - 0.5).abs() < 5e-3, "mag = {}", mag);
+    assert!((phase + 18.0).abs() < 0.5, "phase = {}", phase);
+}
+
+#[test]
+fn wline_coupling_produces_crosstalk_on_quiet_line() {
+    let netlist = |model: &str| {
+        format!(
+            "{}VS src 0 dc 0 ac 1
+RS src a1 50
+RN a2 0 50
+W1 a1 a2 0 b1 b2 0 N=2 L=0.1 RLGCMODEL={}
+RF1 b1 0 50
+RF2 b2 0 50
+.ac lin 1 200meg 200meg
+.end
+",
+            BUS_MODEL, model
+        )
+    };
+    let run = run_first_analysis(&netlist("bus"));
+    let mag = |node: &str| 10f64.powf(run.ac_solutions[0][node_index(&run, node)].0 / 20.0);
+    let (near, far, through) = (mag("a2"), mag("b2"), mag("b1"));
+    assert!(near > 1e-3 && near < 0.2 * through, "near-end = {}, through = {}", near, through);
+    assert!(far > 1e-4 && far < 0.2 * through, "far-end = {}, through = {}", far, through);
+}
+
+#[test]
+fn wline_transient_holds_operating_point() {
+    // 直流激励下瞬态从工作点出发, 各节电感电流与电容电压保持不变
+    let netlist = format!(
+        "{}V1 in 0 1
+RS in a1 50
+W1 a1 a2 0 b1 b2 0 N=2 L=0.1 RLGCMODEL=bus NSEG=4
+RN a2 0 50
+RF1 b1 0 50
+RF2 b2 0 50
+.tran 1n 20n
+.end
+",
+        BUS_MODEL
+    );
+    let run = run_first_analysis(&netlist);
+    let b1 = node_index(&run, "b1");
+    // 1 V 经 50 + 1 + 50 Ω
+    let expected = 50.0 / 101.0;
+    assert!(run.tran_solutions.len() > 2);
+    for solution in &run.tran_solutions {
+        assert!((solution[b1] - expected).abs() < 1e-6, "V(b1) = {}", solution[b1]);
+    }
+}
@@ -0,0 +1,319 @@
+//! Bipolar junction transistor: Gummel-Poon DC model
+//!
+//! Implements the SPICE Gummel-Poon transport model with Early effect
+//! (VAF/VAR), high-level injection (IKF/IKR) and low-current base
+//! recombination (ISE/NE, ISC/NC). Terminal resistances RB/RC/RE are
+//! exposed in [`BjtParams`] and realised by the caller as series resistors
+//! to internal nodes.
+//!
+//! All voltages and currents are in the NPN frame; for PNP devices the
+//! caller negates junction voltages before evaluation and negates the
+//! resulting currents afterwards (conductances are unchanged).
+
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+
+/// BJT polarity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BjtType {
+    #[default]
+    Npn,
+    Pnp,
+}
+
+impl BjtType {
+    /// +1 for NPN, -1 for PNP
+    pub fn sign(self) -> f64 {
+        match self {
+            BjtType::Npn => 1.0,
+            BjtType::Pnp => -1.0,
+        }
+    }
+}
+
+/// Gummel-Poon model parameters
+///
+/// Zero for VAF/VAR/IKF/IKR means "infinite" (effect disabled), as in SPICE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BjtParams {
+    pub bjt_type: BjtType,
+    /// Transport saturation current [A]
+    pub is: f64,
+    /// Ideal maximum forward beta
+    pub bf: f64,
+    /// Ideal maximum reverse beta
+    pub br: f64,
+    /// Forward current emission coefficient
+    pub nf: f64,
+    /// Reverse current emission coefficient
+    pub nr: f64,
+    /// Forward Early voltage [V]
+    pub vaf: f64,
+    /// Reverse Early voltage [V]
+    pub var: f64,
+    /// Forward beta high-current roll-off corner [A]
+    pub ikf: f64,
+    /// Reverse beta high-current roll-off corner [A]
+    pub ikr: f64,
+    /// B-E leakage saturation current [A]
+    pub ise: f64,
+    /// B-E leakage emission coefficient
+    pub ne: f64,
+    /// B-C leakage saturation current [A]
+    pub isc: f64,
+    /// B-C leakage emission coefficient
+    pub nc: f64,
+    /// Base resistance [Ohm]
+    pub rb: f64,
+    /// Collector resistance [Ohm]
+    pub rc: f64,
+    /// Emitter resistance [Ohm]
+    pub re: f64,
+}
+
+impl Default for BjtParams {
+    fn default() -> Self {
+        Self {
+            bjt_type: BjtType::Npn,
+            is: 1e-16,
+            bf: 100.0,
+            br: 1.0,
+            nf: 1.0,
+            nr: 1.0,
+            vaf: 0.0,
+            var: 0.0,
+            ikf: 0.0,
+            ikr: 0.0,
+            ise: 0.0,
+            ne: 1.5,
+            isc: 0.0,
+            nc: 2.0,
+            rb: 0.0,
+            rc: 0.0,
+            re: 0.0,
+        }
+    }
+}
+
+impl BjtParams {
+    /// Scale by the emitter area factor: currents multiply, resistances divide
+    pub fn with_area(mut self, area: f64) -> Self {
+        if area > 0.0 && area != 1.0 {
+            self.is *= area;
+            self.ikf *= area;
+            self.ikr *= area;
+            self.ise *= area;
+            self.isc *= area;
+            self.rb /= area;
+            self.rc /= area;
+            self.re /= area;
+        }
+        self
+    }
+}
+
+/// Build Gummel-Poon parameters from a netlist parameter map
+///
+/// The polarity comes from the model type (`npn` / `pnp`) stored under
+/// `type`; unspecified parameters keep their SPICE defaults.
+pub fn build_bjt_params(params: &HashMap<String, String>) -> BjtParams {
+    let get = |keys: &[&str]| -> Option<f64> {
+        keys.iter()
+            .find_map(|key| params.get(*key).and_then(|value| parse_number(value)))
+    };
+    let defaults = BjtParams::default();
+    let bjt_type = match params.get("type").map(|t| t.to_ascii_lowercase()) {
+        Some(t) if t == "pnp" => BjtType::Pnp,
+        _ => BjtType::Npn,
+    };
+    BjtParams {
+        bjt_type,
+        is: get(&["is"]).unwrap_or(defaults.is),
+        bf: get(&["bf"]).unwrap_or(defaults.bf),
+        br: get(&["br"]).unwrap_or(defaults.br),
+        nf: get(&["nf"]).unwrap_or(defaults.nf),
+        nr: get(&["nr"]).unwrap_or(defaults.nr),
+        vaf: get(&["vaf", "va"]).unwrap_or(defaults.vaf),
+        var: get(&["var", "vb"]).unwrap_or(defaults.var),
+        ikf: get(&["ikf", "ik"]).unwrap_or(defaults.ikf),
+        ikr: get(&["ikr"]).unwrap_or(defaults.ikr),
+        ise: get(&["ise"]).unwrap_or(defaults.ise),
+        ne: get(&["ne"]).unwrap_or(defaults.ne),
+        isc: get(&["isc"]).unwrap_or(defaults.isc),
+        nc: get(&["nc"]).unwrap_or(defaults.nc),
+        rb: get(&["rb"]).unwrap_or(defaults.rb),
+        rc: get(&["rc"]).unwrap_or(defaults.rc),
+        re: get(&["re"]).unwrap_or(defaults.re),
+    }
+}
+
+/// Output from Gummel-Poon DC evaluation (NPN frame)
+///
+/// `ic` flows into the collector and `ib` into the base; the emitter
+/// current is `-(ic + ib)`. Derivatives are with respect to the intrinsic
+/// junction voltages Vbe and Vbc.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BjtOutput {
+    pub ic: f64,
+    pub ib: f64,
+    pub dic_dvbe: f64,
+    pub dic_dvbc: f64,
+    pub dib_dvbe: f64,
+    pub dib_dvbc: f64,
+}
+
+impl BjtOutput {
+    /// Small-signal transconductance gm = dIc/dVbe
+    pub fn gm(&self) -> f64 {
+        self.dic_dvbe + self.dic_dvbc
+    }
+
+    /// Output conductance go = dIc/dVce
+    pub fn go(&self) -> f64 {
+        -self.dic_dvbc
+    }
+
+    /// Input conductance gpi = dIb/dVbe
+    pub fn gpi(&self) -> f64 {
+        self.dib_dvbe
+    }
+}
+
+/// Exponent beyond which the junction exponential is continued linearly,
+/// so that a wild Newton step cannot overflow
+const EXP_LIMIT: f64 = 40.0;
+
+/// `exp(v / nvt)` and its derivative with respect to `v`, linearised above
+/// [`EXP_LIMIT`]
+pub(crate) fn junction_exp(v: f64, nvt: f64) -> (f64, f64) {
+    let arg = v / nvt;
+    if arg > EXP_LIMIT {
+        let e = EXP_LIMIT.exp();
+        (e * (1.0 + arg - EXP_LIMIT), e / nvt)
+    } else {
+        let e = arg.exp();
+        (e, e / nvt)
+    }
+}
+
+/// Diode-like current `is * (exp(v / (n vt)) - 1)` and its derivative
+pub(crate) fn junction_current(is: f64, v: f64, n: f64, vt: f64) -> (f64, f64) {
+    if is <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let (e, de) = junction_exp(v, n * vt);
+    (is * (e - 1.0), is * de)
+}
+
+/// Evaluate the Gummel-Poon DC model
+///
+/// # Arguments
+/// * `params` - Model parameters (area already applied)
+/// * `vbe`, `vbc` - Intrinsic junction voltages in the NPN frame [V]
+/// * `vt` - Thermal voltage kT/q [V]
+pub fn evaluate_bjt(params: &BjtParams, vbe: f64, vbc: f64, vt: f64) -> BjtOutput {
+    let (i_f, g_f) = junction_current(params.is, vbe, params.nf, vt);
+    let (i_r, g_r) = junction_current(params.is, vbc, params.nr, vt);
+    let (i_le, g_le) = junction_current(params.ise, vbe, params.ne, vt);
+    let (i_lc, g_lc) = junction_current(params.isc, vbc, params.nc, vt);
+
+    // Normalized base charge qb = q1/2 * (1 + sqrt(1 + 4 q2))
+    let inv_vaf = if params.vaf > 0.0 { 1.0 / params.vaf } else { 0.0 };
+    let inv_var = if params.var > 0.0 { 1.0 / params.var } else { 0.0 };
+    let inv_ikf = if params.ikf > 0.0 { 1.0 / params.ikf } else { 0.0 };
+    let inv_ikr = if params.ikr > 0.0 { 1.0 / params.ikr } else { 0.0 };
+
+    let denom = (1.0 - vbc * inv_vaf - vbe * inv_var).max(1e-3);
+    let q1 = 1.0 / denom;
+    let dq1_dvbe = q1 * q1 * inv_var;
+    let dq1_dvbc = q1 * q1 * inv_vaf;
+
+    let q2 = i_f * inv_ikf + i_r * inv_ikr;
+    let dq2_dvbe = g_f * inv_ikf;
+    let dq2_dvbc = g_r * inv_ikr;
+
+    let root = (1.0 + 4.0 * q2).max(1e-12).sqrt();
+    let qb = 0.5 * q1 * (1.0 + root);
+    let dqb_dvbe = 0.5 * (1.0 + root) * dq1_dvbe + q1 / root * dq2_dvbe;
+    let dqb_dvbc = 0.5 * (1.0 + root) * dq1_dvbc + q1 / root * dq2_dvbc;
+
+    // Transport current and base currents
+    let ict = (i_f - i_r) / qb;
+    let dict_dvbe = (g_f - ict * dqb_dvbe) / qb;
+    let dict_dvbc = (-g_r - ict * dqb_dvbc) / qb;
+
+    let ibe = i_f / params.bf + i_le;
+    let gbe = g_f / params.bf + g_le;
+    let ibc = i_r / params.br + i_lc;
+    let gbc = g_r / params.br + g_lc;
+
+    BjtOutput {
+        ic: ict - ibc,
+        ib: ibe + ibc,
+        dic_dvbe: dict_dvbe,
+        dic_dvbc: dict_dvbc - gbc,
+        dib_dvbe: gbe,
+        dib_dvbc: gbc,
+    }
+}
+
+/// BJT model selected by the `.model` card: `level=4` picks VBIC, anything
+/// else Gummel-Poon
+#[derive(Debug, Clone, PartialEq)]
+pub enum BjtModel {
+    GummelPoon(BjtParams),
+    Vbic(crate::vbic::VbicParams),
+}
+
+/// Build the BJT model named by `level`, with the area factor applied
+pub fn build_bjt_model(params: &HashMap<String, String>, area: f64) -> BjtModel {
+    let level = params.get("level").and_then(|value| parse_number(value));
+    if level.map(|l| l.round() as i64) == Some(4) {
+        BjtModel::Vbic(crate::vbic::build_vbic_params(params).with_area(area))
+    } else {
+        BjtModel::GummelPoon(build_bjt_params(params).with_area(area))
+    }
+}
+
+impl BjtModel {
+    pub fn bjt_type(&self) -> BjtType {
+        match self {
+            BjtModel::GummelPoon(p) => p.bjt_type,
+            BjtModel::Vbic(p) => p.bjt_type,
+        }
+    }
+
+    /// Series resistances to the internal [c, b, e] nodes [Ohm]
+    pub fn series_resistances(&self) -> [f64; 3] {
+        match self {
+            BjtModel::GummelPoon(p) => [p.rc, p.rb, p.re],
+            BjtModel::Vbic(p) => [p.rcx, p.rb(), p.re],
+        }
+    }
+
+    /// Intrinsic collector (epi) resistance; nonzero only for VBIC with RCI
+    pub fn epi_resistance(&self) -> f64 {
+        match self {
+            BjtModel::GummelPoon(_) => 0.0,
+            BjtModel::Vbic(p) => p.rci,
+        }
+    }
+
+    /// Transport saturation current and forward / reverse emission
+    /// coefficients, used for junction voltage limiting
+    pub fn junction_params(&self) -> (f64, f64, f64) {
+        match self {
+            BjtModel::GummelPoon(p) => (p.is, p.nf, p.nr),
+            BjtModel::Vbic(p) => (p.is, p.nf, p.nr),
+        }
+    }
+
+    /// Evaluate the intrinsic transistor in the NPN frame
+    pub fn evaluate(&self, vbe: f64, vbc: f64, vt: f64) -> BjtOutput {
+        match self {
+            BjtModel::GummelPoon(p) => evaluate_bjt(p, vbe, vbc, vt),
+            BjtModel::Vbic(p) => crate::vbic::evaluate_vbic(p, vbe, vbc, vt),
+        }
+    }
+}
@@ -1,14 +1,26 @@
 //! BSIM4-specific physics modules
 //!
 //! This module contains physics models unique to BSIM4 (Level 54):
+//! - L/W/P binning of size-dependent parameters
+//! - Unified mobility models (MOBMOD 0-2)
+//! - Velocity overshoot and source-end velocity limit
 //! - Impact ionization (substrate current)
 //! - Layout-dependent stress effects
-//! - Gate tunneling current
+//! - Gate tunneling current, including the Igc source/drain partition
+//! - Gate-induced drain/source leakage (GIDL/GISL)
 
+pub mod binning;
+pub mod gidl;
+pub mod mobility;
 pub mod substrate;
 pub mod stress;
 pub mod tunneling;
+pub mod velocity;
 
+pub use binning::{apply_binning, BINNABLE_PARAMS};
+pub use gidl::calculate_gidl;
+pub use mobility::calculate_mobility_bsim4;
 pub use substrate::calculate_isub;
-pub use stress::calculate_stress_effects;
-pub use tunneling::calculate_gate_tunneling;
+pub use stress::{apply_layout_effects, calculate_stress_effects};
+pub use tunneling::{calculate_gate_tunneling, calculate_igc};
+pub use velocity::{source_velocity_limit, velocity_overshoot};
@@ -25,6 +25,8 @@
 //! Devices closer to STI experience more stress and
 //! therefore have modified electrical characteristics.
 
+use crate::bsim::params::BsimParams;
+
 /// Calculate stress effect factors on mobility and threshold
 ///
 /// # Arguments
@@ -138,6 +140,95 @@ pub fn calculate_stress_lod(
     (u0_mult, delta_vth)
 }
 
+/// Apply the standard BSIM4 layout-dependent effects to a parameter set:
+/// LOD stress from SA/SB/SD/NF (U0, VSAT, VTH0, K2 and ETA0) and, with
+/// WPEMOD=1, the well proximity shifts from SCA/SCB/SCC (VTH0, K2, U0).
+///
+/// ```text
+/// Inv_sa = 1/NF · Σ 1/(SA + L/2 + i·(SD + L))          (likewise Inv_sb)
+/// OD     = Inv_sa + Inv_sb - 1/(SAREF + L/2) - 1/(SBREF + L/2)
+/// ρ      = KU0 / Kstress_u0 · (Inv_sa + Inv_sb)
+/// U0    *= (1 + ρ) / (1 + ρ_ref)
+/// VSAT  *= (1 + KVSAT·ρ) / (1 + KVSAT·ρ_ref)
+/// VTH0  += KVTH0 / Kstress_vth0 · OD
+/// K2    += STK2 / Kstress_vth0^LODK2 · OD
+/// ETA0  += STETA0 / Kstress_vth0^LODETA0 · OD
+/// ```
+///
+/// `l` and `w` are the drawn length and total width; each finger has width
+/// `w / NF`. LOD applies when SA and SB are positive (and SD too for
+/// multi-finger devices). Returns `None` when nothing changes.
+pub fn apply_layout_effects(
+    params: &BsimParams,
+    l: f64,
+    w: f64,
+    temp: f64,
+    sa: f64,
+    sb: f64,
+) -> Option<BsimParams> {
+    let nf = params.nf.max(1.0).round();
+    let lod = sa > 0.0 && sb > 0.0 && (nf == 1.0 || params.sd > 0.0);
+    let wpe = params.wpemod == 1;
+    if !lod && !wpe {
+        return None;
+    }
+    let mut p = params.clone();
+    if lod {
+        let w_finger = w / nf;
+        let size = |lcoef: f64, wcoef: f64, pcoef: f64, lexp: f64, wexp: f64| {
+            let lterm = l.powf(lexp);
+            let wterm = (w_finger + params.wlod).powf(wexp);
+            1.0 + lcoef / lterm + wcoef / wterm + pcoef / (lterm * wterm)
+        };
+        let kstress_u0 = size(params.lku0, params.wku0, params.pku0, params.llodku0, params.wlodku0)
+            * (1.0 + params.tku0 * (temp / params.tnom - 1.0));
+        let kstress_vth0 = size(params.lkvth0, params.wkvth0, params.pkvth0, params.llodvth, params.wlodvth);
+
+        let inverse_sum = |s: f64| {
+            (0..nf as usize)
+                .map(|i| 1.0 / (s + 0.5 * l + i as f64 * (params.sd + l)))
+                .sum::<f64>()
+                / nf
+        };
+        let inv_od = inverse_sum(sa) + inverse_sum(sb);
+        let inv_od_ref = 1.0 / (params.saref + 0.5 * l) + 1.0 / (params.sbref + 0.5 * l);
+        let od_offset = inv_od - inv_od_ref;
+
+        let rho = params.ku0 / kstress_u0 * inv_od;
+        let rho_ref = params.ku0 / kstress_u0 * inv_od_ref;
+        p.u0 *= (1.0 + rho) / (1.0 + rho_ref);
+        p.vsat *= (1.0 + params.kvsat * rho) / (1.0 + params.kvsat * rho_ref);
+        p.vth0 += params.kvth0 / kstress_vth0 * od_offset;
+        p.k2 += params.stk2 / kstress_vth0.powf(params.lodk2) * od_offset;
+        p.eta0 += params.steta0 / kstress_vth0.powf(params.lodeta0) * od_offset;
+    }
+    if wpe {
+        let (sca, scb, scc) = well_proximity(params, w / nf);
+        let sum = sca + params.web * scb + params.wec * scc;
+        p.vth0 += params.kvth0we * sum;
+        p.k2 += params.k2we * sum;
+        p.u0 *= 1.0 + params.ku0we * sum;
+    }
+    Some(p)
+}
+
+/// SCA/SCB/SCC as given, or derived from the well-edge distance SC for a
+/// finger of width `w`
+fn well_proximity(params: &BsimParams, w: f64) -> (f64, f64, f64) {
+    let given = params.sca > 0.0 || params.scb > 0.0 || params.scc > 0.0;
+    if given || params.sc <= 0.0 || w <= 0.0 {
+        return (params.sca, params.scb, params.scc);
+    }
+    let (sc, scref) = (params.sc, params.scref);
+    let far = sc + w;
+    let sca = scref * scref / w * (1.0 / sc - 1.0 / far);
+    let tail = |k: f64| {
+        let term = |x: f64| (scref / k * x + scref * scref / (k * k)) * (-k * x / scref).exp();
+        (term(sc) - term(far)) / w
+    };
+    (sca, tail(10.0), tail(20.0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,4 +326,79 @@ mod tests {
         );
         assert!(u0_mult >= 0.1, "u0_mult should be clamped to minimum 0.1");
     }
+
+    #[test]
+    fn test_layout_effects_standard_lod_shifts() {
+        let mut params = BsimParams::nmos_default();
+        params.ku0 = 1e-7;
+        params.kvth0 = 2e-9;
+        params.stk2 = 1e-9;
+        params.steta0 = 3e-9;
+        params.kvsat = 0.5;
+        let l = 0.1e-6;
+        // At the reference distances nothing moves
+        let same = apply_layout_effects(&params, l, 1e-6, 300.15, 1e-6, 1e-6).unwrap();
+        assert!((same.u0 - params.u0).abs() < 1e-9 * params.u0);
+        assert!((same.vth0 - params.vth0).abs() < 1e-12);
+
+        let near = apply_layout_effects(&params, l, 1e-6, 300.15, 0.2e-6, 0.2e-6).unwrap();
+        let inv_od = 2.0 / (0.2e-6 + 0.5 * l);
+        let inv_ref = 2.0 / (1e-6 + 0.5 * l);
+        let od = inv_od - inv_ref;
+        assert!((near.vth0 - params.vth0 - 2e-9 * od).abs() < 1e-12);
+        assert!((near.k2 - params.k2 - 1e-9 * od).abs() < 1e-12);
+        assert!((near.eta0 - params.eta0 - 3e-9 * od).abs() < 1e-12);
+        let u0_ratio = (1.0 + 1e-7 * inv_od) / (1.0 + 1e-7 * inv_ref);
+        assert!((near.u0 / params.u0 - u0_ratio).abs() < 1e-12);
+        let vsat_ratio = (1.0 + 0.5e-7 * inv_od) / (1.0 + 0.5e-7 * inv_ref);
+        assert!((near.vsat / params.vsat - vsat_ratio).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_layout_effects_average_over_fingers() {
+        let mut params = BsimParams::nmos_default();
+        params.kvth0 = 1e-9;
+        params.nf = 2.0;
+        let l = 0.1e-6;
+        // Multi-finger LOD needs SD
+        assert!(apply_layout_effects(&params, l, 2e-6, 300.15, 0.2e-6, 0.2e-6).is_none());
+        params.sd = 0.3e-6;
+        let p = apply_layout_effects(&params, l, 2e-6, 300.15, 0.2e-6, 0.2e-6).unwrap();
+        let side = (1.0 / (0.2e-6 + 0.5 * l) + 1.0 / (0.2e-6 + 0.5 * l + 0.4e-6)) / 2.0;
+        let od = 2.0 * side - 2.0 / (1e-6 + 0.5 * l);
+        assert!((p.vth0 - params.vth0 - 1e-9 * od).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_well_proximity_shifts() {
+        let mut params = BsimParams::nmos_default();
+        params.kvth0we = 0.01;
+        params.k2we = 0.002;
+        params.ku0we = -0.01;
+        params.web = 2.0;
+        params.wec = 3.0;
+        params.sca = 5.0;
+        params.scb = 0.1;
+        params.scc = 0.01;
+        // Off unless WPEMOD=1
+        assert!(apply_layout_effects(&params, 1e-6, 1e-6, 300.15, 0.0, 0.0).is_none());
+        params.wpemod = 1;
+        let p = apply_layout_effects(&params, 1e-6, 1e-6, 300.15, 0.0, 0.0).unwrap();
+        let sum = 5.0 + 2.0 * 0.1 + 3.0 * 0.01;
+        assert!((p.vth0 - params.vth0 - 0.01 * sum).abs() < 1e-12);
+        assert!((p.k2 - params.k2 - 0.002 * sum).abs() < 1e-12);
+        assert!((p.u0 / params.u0 - (1.0 - 0.01 * sum)).abs() < 1e-12);
+
+        // SCA/SCB/SCC from SC shrink as the well edge moves away
+        params.sca = 0.0;
+        params.scb = 0.0;
+        params.scc = 0.0;
+        params.sc = 0.5e-6;
+        let near = well_proximity(&params, 1e-6);
+        params.sc = 2e-6;
+        let far = well_proximity(&params, 1e-6);
+        assert!((near.0 - (1.0 / 0.5 - 1.0 / 1.5)).abs() < 1e-9, "SCA = {}", near.0);
+        assert!(far.0 < near.0 && far.1 < near.1 && far.2 < near.2);
+        assert!(near.1 > 0.0 && near.2 > 0.0 && near.2 < near.1);
+    }
 }
@@ -31,6 +31,31 @@
 //! - Static power consumption
 //! - Input impedance of analog circuits
 //! - Gate voltage divider effects
+//!
+//! ## Gate-to-Channel Tunneling (IGCMOD = 1)
+//!
+//! In inversion, electrons tunnel from the channel to the gate through the
+//! whole gate area. BSIM4 models this current as
+//!
+//! ```text
+//! Igc = Weff * Leff * A * Toxratio * Vgs * Vaux
+//!     * exp(-B * TOX * (AIGC - BIGC * Vox) * (1 + CIGC * Vox))
+//! Toxratio = (TOXREF / TOX)^NTOX / TOX^2
+//! ```
+//!
+//! and partitions it between the source and drain ends by PIGCD:
+//!
+//! ```text
+//! Igcs = Igc * (PIGCD*Vdseff + exp(-PIGCD*Vdseff) - 1 + 1e-4) / (PIGCD^2*Vdseff^2 + 2e-4)
+//! Igcd = Igc * (1 - (PIGCD*Vdseff + 1) * exp(-PIGCD*Vdseff) + 1e-4) / (PIGCD^2*Vdseff^2 + 2e-4)
+//! ```
+//!
+//! At Vds = 0 the two halves are equal; as the channel pinches off near the
+//! drain, the current moves to the source end.
+
+use crate::bsim::params::BsimParams;
+use crate::bsim::threshold::PHI_DEFAULT;
+use crate::bsim::types::MosType;
 
 /// Calculate gate tunneling currents (source and drain sides)
 ///
@@ -116,50 +141,62 @@ pub fn calculate_gate_tunneling(
     (igs, igd, gigs, gigd)
 }
 
-/// Calculate gate-induced drain leakage (GIDL)
-///
-/// GIDL occurs at the drain-gate overlap region when
-/// the gate voltage is low and drain voltage is high,
-/// causing band-to-band tunneling.
+/// Tunneling prefactor A [A/V^2] and exponent constant B for electrons (NMOS)
+/// and holes (PMOS)
+const IGC_A_NMOS: f64 = 4.97232e-7;
+const IGC_B_NMOS: f64 = 7.45669e11;
+const IGC_A_PMOS: f64 = 3.42537e-7;
+const IGC_B_PMOS: f64 = 1.16645e12;
+
+/// Calculate the gate-to-channel tunneling current split into its source
+/// and drain parts (IGCMOD = 1)
 ///
 /// # Arguments
-/// * `vgd` - Gate-drain voltage [V]
-/// * `vds` - Drain-source voltage [V]
-/// * `weff` - Effective width [m]
-/// * `tox` - Oxide thickness [m]
-/// * `agidl` - GIDL coefficient [A/V³]
-/// * `bgidl` - GIDL voltage [V]
-/// * `cgidl` - GIDL bias dependence [1/V]
+/// * `params` - Model parameters (AIGC, BIGC, CIGC, NIGC, PIGCD, TOXREF, NTOX)
+/// * `weff`, `leff` - Effective channel dimensions [m]
+/// * `vth` - Threshold voltage in the N-channel frame [V]
+/// * `bias` - `[vgs, vdseff, vbs]` in the N-channel frame [V]
+/// * `vt` - Thermal voltage [V]
 ///
 /// # Returns
-/// * `(igidl, gigidl)` - GIDL current [A] and conductance [S]
-pub fn calculate_gidl(
-    vgd: f64,
-    vds: f64,
+/// * `(igcs, igcd)` - Currents from the gate into the source and drain ends
+///   of the channel [A]
+pub fn calculate_igc(
+    params: &BsimParams,
     weff: f64,
-    tox: f64,
-    agidl: f64,
-    bgidl: f64,
-    cgidl: f64,
+    leff: f64,
+    vth: f64,
+    bias: [f64; 3],
+    vt: f64,
 ) -> (f64, f64) {
-    // GIDL only occurs when Vgd < 0 (gate low, drain high)
-    if agidl <= 0.0 || vgd >= 0.0 || vds < 0.1 {
+    if params.igcmod == 0 {
         return (0.0, 0.0);
     }
+    let [vgs, vdseff, vbs] = bias;
+    let (a, b) = match params.mos_type {
+        MosType::Nmos => (IGC_A_NMOS, IGC_B_NMOS),
+        MosType::Pmos => (IGC_A_PMOS, IGC_B_PMOS),
+    };
 
-    let vgd_abs = (-vgd).max(0.01);
-    let eox = vgd_abs / tox;
-
-    // GIDL: I = A * W * |Vgd|^3 * exp(-B/Eox) * (1 + C*Vds)
-    let exp_arg = (-bgidl / eox).max(-40.0);
-    let exp_term = exp_arg.exp();
+    // Smoothed inversion overdrive (Vaux) and the oxide voltage in inversion
+    let nvt = params.nigc.max(0.1) * vt;
+    let x = (vgs - vth) / nvt;
+    let vaux = nvt * if x > 40.0 { x } else { x.exp().ln_1p() };
+    let vox = vaux + params.k1 * (PHI_DEFAULT - vbs).max(0.0).sqrt();
 
-    let igidl = agidl * weff * vgd_abs.powi(3) * exp_term * (1.0 + cgidl * vds);
-    let gigidl = 3.0 * igidl / vgd_abs + igidl * bgidl / (eox * tox);
+    let tox = params.tox;
+    let tox_ratio = (params.toxref / tox).powf(params.ntox) / (tox * tox);
+    let exponent = -b * tox * (params.aigc - params.bigc * vox) * (1.0 + params.cigc * vox);
+    let igc = weff * leff * a * tox_ratio * vgs * vaux * exponent.min(40.0).exp();
 
-    (igidl.max(0.0), gigidl.max(0.0))
+    // Partition: even split at Vds = 0, shifting to the source with Vdseff
+    let t = params.pigcd * vdseff.max(0.0);
+    let e = (-t).exp();
+    let denom = t * t + 2e-4;
+    let igcs = igc * (t + e - 1.0 + 1e-4) / denom;
+    let igcd = igc * (1.0 - (t + 1.0) * e + 1e-4) / denom;
+    (igcs, igcd)
 }
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,32 +278,42 @@ mod tests {
         assert!(igs_n2 < igs_n1, "Higher ideality factor should reduce tunneling");
     }
 
+    fn igc_params() -> BsimParams {
+        BsimParams {
+            igcmod: 1,
+            tox: 1.5e-9,
+            toxref: 1.5e-9,
+            ..BsimParams::nmos_default()
+        }
+    }
+
     #[test]
-    fn test_gidl_off_when_vgd_positive() {
-        let (igidl, _) = calculate_gidl(
-            0.5,   // vgd > 0 (no GIDL)
-            1.8,   // vds
-            1e-6,  // weff
-            1.5e-9, // tox
-            1e-12, // agidl
-            3.0,   // bgidl
-            0.0,   // cgidl
-        );
-        assert!(igidl.abs() < 1e-30, "No GIDL when Vgd > 0");
+    fn test_igc_disabled_by_default() {
+        let params = BsimParams::nmos_default();
+        assert_eq!(calculate_igc(&params, 1e-6, 1e-7, 0.4, [1.2, 0.0, 0.0], 0.0259), (0.0, 0.0));
     }
 
     #[test]
-    fn test_gidl_on_when_vgd_negative() {
-        let (igidl, gigidl) = calculate_gidl(
-            -1.0,  // vgd < 0 (GIDL condition)
-            1.8,   // vds
-            1e-6,  // weff
-            1.5e-9, // tox
-            1e-12, // agidl
-            3.0,   // bgidl
-            0.0,   // cgidl
-        );
-        assert!(igidl > 0.0, "GIDL should occur when Vgd < 0");
-        assert!(gigidl > 0.0);
+    fn test_igc_splits_evenly_at_zero_vds() {
+        let params = igc_params();
+        let (igcs, igcd) = calculate_igc(&params, 1e-6, 1e-7, 0.4, [1.2, 0.0, 0.0], 0.0259);
+        assert!(igcs > 0.0);
+        assert!((igcs - igcd).abs() < 1e-12 * igcs);
+    }
+
+    #[test]
+    fn test_igc_shifts_to_source_with_vds() {
+        let params = igc_params();
+        let (igcs, igcd) = calculate_igc(&params, 1e-6, 1e-7, 0.4, [1.2, 0.5, 0.0], 0.0259);
+        assert!(igcs > igcd && igcd > 0.0, "igcs = {}, igcd = {}", igcs, igcd);
+    }
+
+    #[test]
+    fn test_igc_thinner_oxide_leaks_more() {
+        let thin = igc_params();
+        let thick = BsimParams { tox: 2.5e-9, ..igc_params() };
+        let (i_thin, _) = calculate_igc(&thin, 1e-6, 1e-7, 0.4, [1.2, 0.0, 0.0], 0.0259);
+        let (i_thick, _) = calculate_igc(&thick, 1e-6, 1e-7, 0.4, [1.2, 0.0, 0.0], 0.0259);
+        assert!(i_thin > 100.0 * i_thick, "thin = {}, thick = {}", i_thin, i_thick);
     }
 }
@@ -0,0 +1,118 @@
+//! BSIM4 Velocity Overshoot and Source-End Velocity Limit
+//!
+//! In very short channels carriers cross the high-field region faster
+//! than their energy relaxes, so their velocity overshoots VSAT and the
+//! saturation current exceeds the drift-diffusion prediction. The
+//! enhancement grows with the voltage dropped beyond pinch-off relative
+//! to Esat * Leff:
+//!
+//! ```text
+//! F_ovs = 1 + LAMBDA * (Vds - Vdsat) / (Esat * Leff)      (Vds > Vdsat)
+//! ```
+//!
+//! Overshoot cannot push the current above what the source can inject at
+//! the thermal velocity VTL. With the backscattering ratio
+//! r = LC / (LC + Leff) the injection limit is
+//!
+//! ```text
+//! Ilim = Weff * Cox * Vgst * VTL * (1 - r) / (1 + r)
+//! Ids' = Ids / (1 + (Ids / Ilim)^XN)^(1/XN)
+//! ```
+//!
+//! Both are active only when LAMBDA > 0, so existing model cards that
+//! leave LAMBDA at its default are unaffected.
+//!
+//! ## References
+//! - BSIM4.8 Manual, Chapter 6 (velocity overshoot, source-end velocity limit)
+//! - M. Lundstrom, "Elementary Scattering Theory of the Si MOSFET", 1997
+
+use crate::bsim::params::BsimParams;
+
+/// Velocity overshoot current factor and its derivative w.r.t. Vds
+///
+/// # Arguments
+/// * `lambda` - Velocity overshoot coefficient LAMBDA [dimensionless]
+/// * `vds` - Drain-source voltage [V]
+/// * `vdsat` - Saturation voltage [V]
+/// * `esat_leff` - Esat * Leff [V]
+///
+/// # Returns
+/// * `(f_ovs, df_dvds)`
+pub fn velocity_overshoot(lambda: f64, vds: f64, vdsat: f64, esat_leff: f64) -> (f64, f64) {
+    if lambda <= 0.0 || vds <= vdsat || esat_leff <= 0.0 {
+        return (1.0, 0.0);
+    }
+    let slope = lambda / esat_leff;
+    (1.0 + slope * (vds - vdsat), slope)
+}
+
+/// Apply the source-end thermal velocity limit to the drain current
+///
+/// # Arguments
+/// * `params` - Model parameters (VTL, LC, XN, TOX)
+/// * `ids` - Drain current before the limit [A]
+/// * `weff`, `leff` - Effective channel dimensions [m]
+/// * `vgst` - Gate overdrive [V]
+///
+/// # Returns
+/// * `(ids_lim, d_dids, d_dvgst)` - Limited current and its partial
+///   derivatives with respect to the unlimited current and Vgst
+pub fn source_velocity_limit(params: &BsimParams, ids: f64, weff: f64, leff: f64, vgst: f64) -> (f64, f64, f64) {
+    if params.vtl <= 0.0 || vgst <= 0.0 || ids <= 0.0 {
+        return (ids, 1.0, 0.0);
+    }
+    let r = params.lc / (params.lc + leff);
+    let ilim = weff * params.cox() * vgst * params.vtl * (1.0 - r) / (1.0 + r);
+    let n = params.xn.max(1.0);
+    let xn = (ids / ilim).powf(n);
+    let base = 1.0 + xn;
+    let d_dids = base.powf(-1.0 / n - 1.0);
+    let ids_lim = ids * d_dids * base;
+    // Ilim is proportional to Vgst
+    let d_dvgst = ids * xn * d_dids / vgst;
+    (ids_lim, d_dids, d_dvgst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_overshoot_below_vdsat() {
+        assert_eq!(velocity_overshoot(0.2, 0.3, 0.5, 1.0), (1.0, 0.0));
+        assert_eq!(velocity_overshoot(0.0, 2.0, 0.5, 1.0), (1.0, 0.0));
+        let (f, df) = velocity_overshoot(0.2, 1.5, 0.5, 1.0);
+        assert!((f - 1.2).abs() < 1e-12 && (df - 0.2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_velocity_limit_caps_current() {
+        let params = BsimParams::nmos_default();
+        let (weff, leff, vgst) = (1e-6, 50e-9, 0.5);
+        let r = params.lc / (params.lc + leff);
+        let ilim = weff * params.cox() * vgst * params.vtl * (1.0 - r) / (1.0 + r);
+        // Far below the limit the current passes through
+        let (small, _, _) = source_velocity_limit(&params, 1e-3 * ilim, weff, leff, vgst);
+        assert!((small / (1e-3 * ilim) - 1.0).abs() < 1e-6);
+        // Far above it saturates at Ilim
+        let (large, _, _) = source_velocity_limit(&params, 1e3 * ilim, weff, leff, vgst);
+        assert!((large / ilim - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_velocity_limit_derivatives() {
+        let params = BsimParams::nmos_default();
+        let (weff, leff, vgst, ids) = (1e-6, 50e-9, 0.5, 2e-4);
+        let (_, d_dids, d_dvgst) = source_velocity_limit(&params, ids, weff, leff, vgst);
+        let h = 1e-9;
+        let fd_ids = (source_velocity_limit(&params, ids + h, weff, leff, vgst).0
+            - source_velocity_limit(&params, ids - h, weff, leff, vgst).0)
+            / (2.0 * h);
+        let hv = 1e-6;
+        let fd_vgst = (source_velocity_limit(&params, ids, weff, leff, vgst + hv).0
+            - source_velocity_limit(&params, ids, weff, leff, vgst - hv).0)
+            / (2.0 * hv);
+        assert!((fd_ids - d_dids).abs() < 1e-6 * d_dids);
+        assert!((fd_vgst - d_dvgst).abs() < 1e-5 * d_dvgst.abs());
+    }
+}
@@ -0,0 +1,266 @@
+//! MOSFET Gate Capacitances and Terminal Charges
+//!
+//! The C-V formulation is selected by CAPMOD:
+//!
+//! - `capmod=0` (default): Meyer capacitances Cgs / Cgd / Cgb, used as
+//!   C * dV/dt. Simple, but not charge conserving.
+//! - `capmod=1`, `2`: charge-based long-channel model. The four terminal
+//!   charges Qd, Qg, Qs, Qb sum to zero by construction, and the caller
+//!   differences charges between time steps, so no charge is lost.
+//! - `capmod=3`: as above, with the BSIM3 charge-thickness correction: the
+//!   inversion charge sits a distance Tcen below the interface, which puts
+//!   the centroid capacitance EPSSI / Tcen in series with Cox.
+//!
+//! ## Meyer Model
+//!
+//! The intrinsic gate charge is split between source, drain and bulk
+//! according to the operating region, with Cox_total = Cox * Weff * Leff:
+//!
+//! | Region | Cgs | Cgd | Cgb |
+//! |--------|-----|-----|-----|
+//! | Accumulation (Vgst <= -PHI) | 0 | 0 | Cox_total |
+//! | Depletion (-PHI < Vgst <= -PHI/2) | 0 | 0 | -Vgst/PHI * Cox_total |
+//! | Weak inversion (-PHI/2 < Vgst <= 0) | 2/3 Cox_total (1 + 2 Vgst/PHI) | 0 | -Vgst/PHI * Cox_total |
+//! | Saturation (Vds >= Vdsat) | 2/3 Cox_total | 0 | 0 |
+//! | Linear (Vds < Vdsat) | 2/3 Cox_total [1 - ((Vdsat-Vds)/(2Vdsat-Vds))^2] | 2/3 Cox_total [1 - (Vdsat/(2Vdsat-Vds))^2] | 0 |
+//!
+//! The overlap capacitances CGSO / CGDO (per unit width) and CGBO (per unit
+//! length) are added on top. The capacitances are continuous across all
+//! region boundaries; at Vds = 0 in the linear region Cgs = Cgd = Cox_total / 2.
+//!
+//! ## Charge Model
+//!
+//! With Vfb chosen so that Vth = Vfb + PHI + K1 * sqrt(PHI - Vbs), the gate
+//! charge is Cox_total (Vgb - Vfb) in accumulation and the depletion charge
+//! Cox_total K1^2/2 (sqrt(1 + 4 (Vgb - Vfb) / K1^2) - 1) below threshold,
+//! which meets the inversion expression continuously at Vgst = 0. Above
+//! threshold, with Vdseff = min(Vds, Vgst):
+//!
+//! ```text
+//! Qinv = -Cox_total * (Vgst - Vdseff/2 + Vdseff^2 / (12 (Vgst - Vdseff/2)))
+//! Qb   = -Cox_total * K1 * sqrt(PHI - Vbs)
+//! Qg   = -(Qinv + Qb)
+//! ```
+//!
+//! Qinv is split between drain and source by XPART: 0/100 (XPART > 0.5),
+//! 50/50 (XPART = 0.5) or the Ward-Dutton 40/60 partition (XPART < 0.5).
+//! Overlap charges CGSO / CGDO / CGBO are linear in the terminal voltages.
+//!
+//! ## References
+//! - J. E. Meyer, "MOS Models and Circuit Simulation", RCA Review, 1971
+//! - SPICE3 `DEVqmeyer`
+//! - BSIM3v3.3 Manual, Chapter 5 (capMod, charge partitioning)
+
+use super::params::{BsimParams, EPSILON_SI};
+use super::threshold::PHI_DEFAULT;
+use super::types::MosType;
+
+/// Calculate the gate capacitances in the external terminal frame
+///
+/// # Arguments
+/// * `params` - BSIM model parameters (TOX and overlap capacitances)
+/// * `weff`, `leff` - Effective channel dimensions [m]
+/// * `vgst` - Gate overdrive Vgs - Vth in the forward N-channel frame [V]
+/// * `vds` - Drain-source voltage in the forward frame (>= 0) [V]
+/// * `vdsat` - Saturation voltage (ignored below threshold) [V]
+/// * `swapped` - True when the forward-frame source is the external drain
+///   (reverse mode or PMOS, but not both)
+///
+/// # Returns
+/// * `(cgs, cgd, cgb)` - Capacitances [F]
+pub fn calculate_meyer_caps(
+    params: &BsimParams,
+    weff: f64,
+    leff: f64,
+    vgst: f64,
+    vds: f64,
+    vdsat: f64,
+    swapped: bool,
+) -> (f64, f64, f64) {
+    let cox = params.cox() * weff * leff;
+    let phi = PHI_DEFAULT;
+
+    let (cgs, cgd, cgb) = if vgst <= -phi {
+        (0.0, 0.0, cox)
+    } else if vgst <= -0.5 * phi {
+        (0.0, 0.0, -vgst / phi * cox)
+    } else if vgst <= 0.0 {
+        (2.0 / 3.0 * cox * (1.0 + 2.0 * vgst / phi), 0.0, -vgst / phi * cox)
+    } else {
+        let vdsat = vdsat.max(0.0);
+        if vds >= vdsat {
+            (2.0 / 3.0 * cox, 0.0, 0.0)
+        } else {
+            let vddif = 2.0 * vdsat - vds;
+            let vddif1 = vdsat - vds;
+            let vddif2 = vddif * vddif;
+            (
+                2.0 / 3.0 * cox * (1.0 - vddif1 * vddif1 / vddif2),
+                2.0 / 3.0 * cox * (1.0 - vdsat * vdsat / vddif2),
+                0.0,
+            )
+        }
+    };
+
+    let (cgs, cgd) = if swapped { (cgd, cgs) } else { (cgs, cgd) };
+    (
+        cgs + params.cgso * weff,
+        cgd + params.cgdo * weff,
+        cgb + params.cgbo * leff,
+    )
+}
+
+/// Calculate the terminal charges in the external terminal frame
+///
+/// # Arguments
+/// * `params` - BSIM model parameters (TOX, K1, CAPMOD, XPART, overlaps)
+/// * `weff`, `leff` - Effective channel dimensions [m]
+/// * `vth` - Threshold voltage in the forward N-channel frame [V]
+/// * `bias` - `[vgs, vds, vbs]` in the forward frame, Vds >= 0 [V]
+/// * `swapped` - True when the forward-frame source is the external drain
+///
+/// # Returns
+/// * `(qd, qg, qs, qb)` - Terminal charges [C], summing to zero
+pub fn calculate_charges(
+    params: &BsimParams,
+    weff: f64,
+    leff: f64,
+    vth: f64,
+    bias: [f64; 3],
+    swapped: bool,
+) -> (f64, f64, f64, f64) {
+    let [vgs, vds, vbs] = bias;
+    let cox_area = params.cox();
+    let cox = cox_area * weff * leff;
+    let phi = PHI_DEFAULT;
+    let k1 = params.k1.max(0.0);
+
+    // Bulk depletion charge per Cox at threshold, Vth - Vfb - PHI
+    let qdep = k1 * (phi - vbs).max(0.01).sqrt();
+    let vfb = vth - phi - qdep;
+    let vgb = vgs - vbs;
+    let vgst = vgs - vth;
+
+    let (mut qd, mut qg, mut qs, mut qb);
+    if vgst <= 0.0 {
+        // Accumulation / depletion: no inversion charge
+        qg = if vgb <= vfb {
+            cox * (vgb - vfb)
+        } else if k1 > 0.0 {
+            let k1sq = k1 * k1;
+            0.5 * cox * k1sq * ((1.0 + 4.0 * (vgb - vfb) / k1sq).sqrt() - 1.0)
+        } else {
+            0.0
+        };
+        qb = -qg;
+        qd = 0.0;
+        qs = 0.0;
+    } else {
+        // Capacitance seen by the inversion charge
+        let cinv = if params.capmod >= 3 {
+            let t0 = (vgst + 4.0 * qdep) / (2.0e8 * params.tox);
+            let tcen = 1.9e-9 / (1.0 + t0.powf(0.7));
+            let ccen = EPSILON_SI / tcen;
+            cox * ccen / (ccen + cox_area)
+        } else {
+            cox
+        };
+        let a = vgst;
+        let b = vds.min(a);
+        let a_half = a - 0.5 * b;
+        let qinv = -cinv * (a_half + b * b / (12.0 * a_half));
+        qb = -cox * qdep;
+        qg = -(qinv + qb);
+        qd = if params.xpart > 0.5 {
+            0.0
+        } else if params.xpart < 0.5 {
+            // Ward-Dutton: Qd = W * integral of (x/L) Qi(x) dx
+            let poly = a * a * a / 2.0 - 5.0 * a * a * b / 6.0 + a * b * b / 2.0 - b * b * b / 10.0;
+            -cinv * poly / (a_half * a_half)
+        } else {
+            0.5 * qinv
+        };
+        qs = qinv - qd;
+    }
+
+    // Overlap charges, attached to the physical source / drain
+    let (cov_s, cov_d) = if swapped {
+        (params.cgdo * weff, params.cgso * weff)
+    } else {
+        (params.cgso * weff, params.cgdo * weff)
+    };
+    let q_ov_s = cov_s * vgs;
+    let q_ov_d = cov_d * (vgs - vds);
+    let q_ov_b = params.cgbo * leff * vgb;
+    qg += q_ov_s + q_ov_d + q_ov_b;
+    qs -= q_ov_s;
+    qd -= q_ov_d;
+    qb -= q_ov_b;
+
+    if swapped {
+        std::mem::swap(&mut qd, &mut qs);
+    }
+    // PMOS voltages were negated, so are the charges
+    let sign = match params.mos_type {
+        MosType::Nmos => 1.0,
+        MosType::Pmos => -1.0,
+    };
+    (sign * qd, sign * qg, sign * qs, sign * qb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meyer_continuous_at_threshold() {
+        let params = BsimParams::nmos_default();
+        let cox = params.cox() * 1e-12;
+        let (below, _, _) = calculate_meyer_caps(&params, 1e-6, 1e-6, -1e-9, 0.5, 0.0, false);
+        let (above, _, _) = calculate_meyer_caps(&params, 1e-6, 1e-6, 1e-9, 0.5, 1e-9, false);
+        assert!((below - above).abs() < 1e-6 * cox);
+    }
+
+    #[test]
+    fn test_meyer_linear_splits_evenly_at_zero_vds() {
+        let params = BsimParams::nmos_default();
+        let cox = params.cox() * 1e-12;
+        let (cgs, cgd, cgb) = calculate_meyer_caps(&params, 1e-6, 1e-6, 1.0, 0.0, 1.0, false);
+        assert!((cgs - 0.5 * cox).abs() < 1e-9 * cox);
+        assert!((cgd - 0.5 * cox).abs() < 1e-9 * cox);
+        assert_eq!(cgb, 0.0);
+    }
+
+    #[test]
+    fn test_charges_sum_to_zero_in_every_region() {
+        let params = BsimParams {
+            cgso: 1e-10,
+            cgdo: 2e-10,
+            cgbo: 1e-10,
+            ..BsimParams::nmos_default()
+        };
+        for (vgs, vds) in [(-2.0, 0.5), (0.3, 0.5), (1.5, 0.2), (1.5, 3.0)] {
+            let (qd, qg, qs, qb) = calculate_charges(&params, 1e-6, 1e-6, 0.7, [vgs, vds, 0.0], false);
+            assert!((qd + qg + qs + qb).abs() < 1e-12 * qg.abs(), "vgs = {}, vds = {}", vgs, vds);
+        }
+    }
+
+    #[test]
+    fn test_charges_40_60_partition_in_saturation() {
+        let params = BsimParams::nmos_default();
+        let (qd, _, qs, _) = calculate_charges(&params, 1e-6, 1e-6, 0.7, [1.7, 3.0, 0.0], false);
+        assert!((qd / (qd + qs) - 0.4).abs() < 1e-12);
+        let half = BsimParams { xpart: 0.5, ..BsimParams::nmos_default() };
+        let (qd, _, qs, _) = calculate_charges(&half, 1e-6, 1e-6, 0.7, [1.7, 3.0, 0.0], false);
+        assert!((qd - qs).abs() < 1e-12 * qs.abs());
+    }
+
+    #[test]
+    fn test_charges_continuous_at_threshold() {
+        let params = BsimParams::nmos_default();
+        let below = calculate_charges(&params, 1e-6, 1e-6, 0.7, [0.7 - 1e-9, 0.5, -0.5], false);
+        let above = calculate_charges(&params, 1e-6, 1e-6, 0.7, [0.7 + 1e-9, 0.5, -0.5], false);
+        assert!((below.1 - above.1).abs() < 1e-6 * below.1.abs());
+        assert!((below.3 - above.3).abs() < 1e-6 * below.3.abs());
+    }
+}
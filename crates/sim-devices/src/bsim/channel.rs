@@ -198,16 +198,15 @@ pub fn calculate_dibl_conductance(
 /// Series resistance reduces effective Vgs and Vds at the intrinsic device
 ///
 /// # Arguments
-/// * `params` - BSIM3 model parameters
+/// * `params` - BSIM3 model parameters (RDSW already at the device
+///   temperature, see `BsimParams::at_temperature`)
 /// * `weff` - Effective width [m]
-/// * `temp` - Temperature [K]
 ///
 /// # Returns
 /// * Total S/D resistance [ohm]
 pub fn calculate_rds(
     params: &BsimParams,
     weff: f64,
-    temp: f64,
 ) -> f64 {
     if params.rdsw <= 0.0 {
         return 0.0;
@@ -215,11 +214,7 @@ pub fn calculate_rds(
 
     // RDSW is in ohm*um, so divide by W in um
     let weff_um = weff * 1e6;
-    let rds_base = params.rdsw / weff_um.max(0.1);
-
-    // Temperature correction
-    let delta_t = temp - params.tnom;
-    let rds = rds_base * (1.0 + params.prt * delta_t);
+    let rds = params.rdsw / weff_um.max(0.1);
 
     rds.max(0.0)
 }
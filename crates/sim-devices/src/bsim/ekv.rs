@@ -0,0 +1,323 @@
+//! EKV 2.6 MOSFET Model (Level 44)
+//!
+//! The EKV model is written in terms of the pinch-off voltage VP and the
+//! source / drain voltages referred to the bulk. A single interpolation
+//! function covers weak, moderate and strong inversion, so the current and
+//! all of its derivatives are continuous through threshold:
+//!
+//! ```text
+//! VG' = VG - VTO + PHI + GAMMA * sqrt(PHI)
+//! VP  = VG' - PHI - GAMMA' * (sqrt(VG' + (GAMMA'/2)^2) - GAMMA'/2)
+//! n   = 1 + GAMMA / (2 * sqrt(VP + PHI + 4 Vt))
+//! F(v) = ln^2(1 + exp(v/2))
+//! if  = F((VP - VS) / Vt),   ir = F((VP - VD) / Vt)
+//! Ids = 2 n beta Vt^2 * (if - ir),   beta = KP * Weff / Leq / (1 + THETA * VP)
+//! ```
+//!
+//! F tends to exp(v) in weak inversion and to (v/2)^2 in strong inversion,
+//! giving the exponential and square-law limits. GAMMA' carries the charge
+//! sharing corrections (LETA, WETA). Velocity saturation (UCRIT) and channel
+//! length modulation (LAMBDA) enter through the saturation voltage Vdss, the
+//! equivalent channel length Leq and the reverse current ir', following the
+//! EKV 2.6 static model.
+//!
+//! GAMMA is read from K1, VTO from VTH0 and KP defaults to U0 * COX, so a
+//! BSIM-style card with only `level=44` gives a sensible device. The gate
+//! capacitances reuse the Meyer and charge models of `capacitance.rs`.
+//!
+//! ## Temperature
+//!
+//! ```text
+//! VTO(T)   = VTO - TCV * (T - TNOM)
+//! KP(T)    = KP * (T/TNOM)^BEX
+//! UCRIT(T) = UCRIT * (T/TNOM)^UCEX
+//! PHI(T)   = PHI * T/TNOM - 3 Vt ln(T/TNOM) - Eg(TNOM) * T/TNOM + Eg(T)
+//! ```
+//!
+//! ## References
+//! - M. Bucher, C. Lallement, C. Enz, F. Theodoloz, F. Krummenacher,
+//!   "The EPFL-EKV MOSFET Model Equations for Simulation", Version 2.6, 1998
+//! - C. Enz, F. Krummenacher, E. Vittoz, "An Analytical MOS Transistor Model
+//!   Valid in All Regions of Operation", AICSP Journal, 1995
+
+use super::capacitance::{calculate_charges, calculate_meyer_caps};
+use super::params::{BsimParams, EPSILON_SI, K_BOLTZMANN, Q_ELECTRON};
+use super::temperature::band_gap;
+use super::types::{BsimOutput, MosRegion, MosType};
+
+/// Voltage step for the numerical conductances [V]
+const DV: f64 = 1e-6;
+
+/// EKV interpolation function F(v) = ln^2(1 + exp(v/2))
+pub fn ekv_interpolation(v: f64) -> f64 {
+    // ln(1 + e^x) without overflow for large x
+    let x = 0.5 * v;
+    let softplus = if x > 30.0 { x } else { x.exp().ln_1p() };
+    softplus * softplus
+}
+
+/// Temperature- and size-adjusted quantities shared by all bias points
+struct EkvDevice {
+    vto: f64,
+    gamma: f64,
+    phi: f64,
+    kp: f64,
+    theta: f64,
+    ucrit: f64,
+    lambda: f64,
+    leta: f64,
+    weta: f64,
+    /// eps_si / Cox [m]
+    es_cox: f64,
+    /// Characteristic length sqrt(eps_si / Cox * XJ) [m]
+    lc: f64,
+    weff: f64,
+    leff: f64,
+    vt: f64,
+}
+
+/// Terminal-level results of one EKV evaluation in the N-channel frame
+struct EkvPoint {
+    ids: f64,
+    vp: f64,
+    /// Saturation voltage VD - VS at which the channel pinches off [V]
+    vdsat: f64,
+}
+
+impl EkvDevice {
+    fn new(params: &BsimParams, w: f64, l: f64, temp: f64, sign: f64) -> Self {
+        let tnom = params.tnom;
+        let ratio = temp / tnom;
+        let vt = K_BOLTZMANN * temp / Q_ELECTRON;
+        let cox = params.cox();
+        let kp = if params.kp > 0.0 { params.kp } else { params.u0 * 1e-4 * cox };
+        let phi = params.phi * ratio - 3.0 * vt * ratio.ln() - band_gap(tnom) * ratio + band_gap(temp);
+        let es_cox = EPSILON_SI / cox;
+        EkvDevice {
+            vto: sign * params.vth0 - params.tcv * (temp - tnom),
+            gamma: params.k1.max(0.0),
+            phi: phi.max(0.1),
+            kp: kp * ratio.powf(params.bex),
+            theta: params.theta,
+            ucrit: params.ucrit * ratio.powf(params.ucex),
+            lambda: params.lambda.max(0.0),
+            leta: params.leta,
+            weta: params.weta,
+            es_cox,
+            lc: (es_cox * params.xj).sqrt(),
+            weff: params.weff(w),
+            leff: params.leff(l),
+            vt,
+        }
+    }
+
+    /// Drain current for VD >= VS, voltages referred to the bulk
+    fn forward(&self, vg: f64, vd: f64, vs: f64) -> EkvPoint {
+        let (phi, vt) = (self.phi, self.vt);
+        let pinch_off = |vgp: f64, gamma: f64| {
+            if vgp > 0.0 {
+                vgp - phi - gamma * ((vgp + 0.25 * gamma * gamma).sqrt() - 0.5 * gamma)
+            } else {
+                -phi
+            }
+        };
+
+        // Pinch-off voltage with charge sharing (short / narrow channel)
+        let vgp = vg - self.vto + phi + self.gamma * phi.sqrt();
+        let vp0 = pinch_off(vgp, self.gamma);
+        let smooth = |v: f64| 0.5 * (v + phi + ((v + phi).powi(2) + 16.0 * vt * vt).sqrt());
+        let gamma0 = self.gamma
+            - self.es_cox
+                * (self.leta / self.leff * (smooth(vs).sqrt() + smooth(vd).sqrt())
+                    - 3.0 * self.weta / self.weff * (vp0 + phi).max(0.0).sqrt());
+        let gamma_eff = 0.5 * (gamma0 + (gamma0 * gamma0 + 0.1 * vt).sqrt());
+        let vp = pinch_off(vgp, gamma_eff);
+        let n = 1.0 + self.gamma / (2.0 * (vp + phi + 4.0 * vt).max(vt).sqrt());
+
+        // Forward current and velocity-saturated pinch-off
+        let i_f = ekv_interpolation((vp - vs) / vt);
+        let sqrt_if = i_f.sqrt();
+        let vc = self.ucrit * self.leff;
+        let vdss = vc * ((0.25 + vt / vc * sqrt_if).sqrt() - 0.5);
+        let dv = 4.0 * vt * (self.lambda * (sqrt_if - vdss / vt) + 1.0 / 64.0).max(1.0 / 64.0).sqrt();
+
+        // Channel length modulation and velocity saturation on Leq
+        let vds = 0.5 * (vd - vs);
+        let vip = (vdss * vdss + dv * dv).sqrt() - ((vds - vdss).powi(2) + dv * dv).sqrt();
+        let delta_l = self.lambda * self.lc * (1.0 + (vds - vip) / (self.lc * self.ucrit)).ln();
+        let l_prime = self.leff - delta_l + (vds + vip) / self.ucrit;
+        let lmin = 0.1 * self.leff;
+        let leq = 0.5 * (l_prime + (l_prime * l_prime + lmin * lmin).sqrt());
+
+        // Reverse current with the drain clamped at the saturation voltage
+        let ln_if = i_f.max(1e-30).ln();
+        let vdss_r = vc * ((0.25 + vt / vc * (sqrt_if - 0.75 * ln_if)).sqrt() - 0.5)
+            + vt * ((vc / (2.0 * vt)).ln() - 0.6);
+        let v_rev = vp - vds - vs - (vdss_r * vdss_r + dv * dv).sqrt() + ((vds - vdss_r).powi(2) + dv * dv).sqrt();
+        let i_r = ekv_interpolation(v_rev / vt);
+
+        let beta = self.kp * self.weff / leq / (1.0 + self.theta * vp.max(0.0));
+        let ispec = 2.0 * n * beta * vt * vt;
+        EkvPoint {
+            ids: ispec * (i_f - i_r),
+            vp,
+            vdsat: 2.0 * vdss,
+        }
+    }
+
+    /// Drain current for any VD / VS; the device is symmetric
+    fn current(&self, vg: f64, vd: f64, vs: f64) -> f64 {
+        if vd >= vs {
+            self.forward(vg, vd, vs).ids
+        } else {
+            -self.forward(vg, vs, vd).ids
+        }
+    }
+}
+
+/// EKV 2.6 DC evaluation with gate capacitances and charges
+///
+/// # Arguments
+/// * `params` - Model parameters (VTH0, K1, PHI, KP, THETA, UCRIT, LAMBDA, ...)
+/// * `w`, `l` - Drawn device dimensions [m]
+/// * `bias` - `[vd, vg, vs, vb]` external terminal voltages [V]
+/// * `temp` - Temperature [K]
+///
+/// # Returns
+/// * `BsimOutput` in the external frame; the conductances are central
+///   differences of the (smooth) drain current
+pub fn evaluate_ekv_dc(params: &BsimParams, w: f64, l: f64, bias: [f64; 4], temp: f64) -> BsimOutput {
+    let sign = match params.mos_type {
+        MosType::Nmos => 1.0,
+        MosType::Pmos => -1.0,
+    };
+    let [vd, vg, vs, vb] = bias;
+    // PMOS: negate all voltages; the current changes sign with them
+    let (vd_int, vg_int, vs_int) = (sign * (vd - vb), sign * (vg - vb), sign * (vs - vb));
+    let dev = EkvDevice::new(params, w, l, temp, sign);
+
+    let ids_int = dev.current(vg_int, vd_int, vs_int);
+    let diff = |f: &dyn Fn(f64) -> f64, x: f64| (f(x + DV) - f(x - DV)) / (2.0 * DV);
+    let gm = diff(&|v| dev.current(v, vd_int, vs_int), vg_int);
+    let gds = diff(&|v| dev.current(vg_int, v, vs_int), vd_int);
+    let gms = diff(&|v| dev.current(vg_int, vd_int, v), vs_int);
+    // The current depends on voltage differences only
+    let gmbs = -(gm + gds + gms);
+
+    let ids = sign * ids_int;
+    let ieq = ids - gm * (vg - vs) - gds * (vd - vs) - gmbs * (vb - vs);
+
+    // Forward frame: the lower of drain / source acts as the source
+    let reversed = vd_int < vs_int;
+    let (vd_f, vs_f) = if reversed { (vs_int, vd_int) } else { (vd_int, vs_int) };
+    let point = dev.forward(vg_int, vd_f, vs_f);
+    let vds = vd_f - vs_f;
+    let vsb = vs_f.max(-dev.phi + 0.01);
+    let vth = dev.vto + dev.gamma * ((dev.phi + vsb).sqrt() - dev.phi.sqrt());
+    let region = if point.vp < vs_f {
+        MosRegion::Cutoff
+    } else if vds < point.vdsat {
+        MosRegion::Linear
+    } else {
+        MosRegion::Saturation
+    };
+
+    let (weff, leff) = (dev.weff, dev.leff);
+    let vgst = vg_int - vs_f - vth;
+    let (cgs, cgd, cgb) = calculate_meyer_caps(params, weff, leff, vgst, vds, point.vdsat, reversed);
+    let (qd, qg, qs, qb) = calculate_charges(params, weff, leff, vth, [vg_int - vs_f, vds, -vs_f], reversed);
+
+    BsimOutput {
+        ids,
+        gm,
+        gds,
+        gmbs,
+        ieq,
+        region,
+        vth_eff: sign * vth,
+        cgs,
+        cgd,
+        cgb,
+        qd,
+        qg,
+        qs,
+        qb,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ekv_params() -> BsimParams {
+        BsimParams {
+            level: 44,
+            vth0: 0.5,
+            kp: 100e-6,
+            lambda: 0.5,
+            ..BsimParams::nmos_default()
+        }
+    }
+
+    fn ids(params: &BsimParams, vgs: f64, vds: f64) -> f64 {
+        evaluate_ekv_dc(params, 10e-6, 10e-6, [vds, vgs, 0.0, 0.0], params.tnom).ids
+    }
+
+    #[test]
+    fn test_interpolation_limits() {
+        // Weak inversion: F(v) -> exp(v)
+        let v = -20.0;
+        assert!((ekv_interpolation(v) / v.exp() - 1.0).abs() < 1e-3);
+        // Strong inversion: F(v) -> (v/2)^2
+        let v = 80.0;
+        assert!((ekv_interpolation(v) / (0.25 * v * v) - 1.0).abs() < 1e-6);
+        assert!(ekv_interpolation(1e4).is_finite());
+    }
+
+    #[test]
+    fn test_zero_current_at_zero_vds() {
+        let params = ekv_params();
+        assert!(ids(&params, 1.0, 0.0).abs() < 1e-15);
+        let out = evaluate_ekv_dc(&params, 10e-6, 10e-6, [0.0, 1.0, 0.0, 0.0], params.tnom);
+        assert!(out.gds > 0.0 && out.region == MosRegion::Linear);
+    }
+
+    #[test]
+    fn test_symmetric_in_drain_and_source() {
+        let params = ekv_params();
+        let forward = evaluate_ekv_dc(&params, 10e-6, 10e-6, [0.3, 1.2, 0.0, 0.0], params.tnom);
+        let reverse = evaluate_ekv_dc(&params, 10e-6, 10e-6, [0.0, 1.2, 0.3, 0.0], params.tnom);
+        assert!(forward.ids > 0.0);
+        assert!((forward.ids + reverse.ids).abs() < 1e-12 * forward.ids);
+        // Swapping the terminals swaps the gate capacitances
+        assert!((forward.cgs - reverse.cgd).abs() < 1e-9 * forward.cgs);
+    }
+
+    #[test]
+    fn test_conductances_match_current() {
+        let params = ekv_params();
+        let (vd, vg) = (0.8, 0.9);
+        let out = evaluate_ekv_dc(&params, 10e-6, 10e-6, [vd, vg, 0.0, 0.0], params.tnom);
+        let h = 1e-4;
+        let gm = (ids(&params, vg + h, vd) - ids(&params, vg - h, vd)) / (2.0 * h);
+        let gds = (ids(&params, vg, vd + h) - ids(&params, vg, vd - h)) / (2.0 * h);
+        assert!((out.gm - gm).abs() < 1e-4 * gm);
+        assert!((out.gds - gds).abs() < 1e-3 * gds);
+        assert!(out.gmbs > 0.0);
+    }
+
+    #[test]
+    fn test_pmos_mirrors_nmos() {
+        let nmos = ekv_params();
+        let pmos = BsimParams {
+            mos_type: MosType::Pmos,
+            vth0: -0.5,
+            ..ekv_params()
+        };
+        let n = evaluate_ekv_dc(&nmos, 10e-6, 10e-6, [1.0, 1.0, 0.0, 0.0], nmos.tnom);
+        let p = evaluate_ekv_dc(&pmos, 10e-6, 10e-6, [-1.0, -1.0, 0.0, 0.0], pmos.tnom);
+        assert!((p.ids + n.ids).abs() < 1e-12 * n.ids);
+        assert!((p.gm - n.gm).abs() < 1e-9 * n.gm);
+        assert!((p.qg + n.qg).abs() < 1e-9 * n.qg.abs());
+    }
+}
@@ -23,12 +23,26 @@
 //! - gds = dIds/dVds (output conductance)
 //! - gmbs = dIds/dVbs (body transconductance)
 //!
+//! The Meyer gate capacitances (Cgs, Cgd, Cgb) and the terminal charges of
+//! the charge-based C-V model are evaluated alongside the current from the
+//! same threshold and region, see `capacitance.rs`.
+//!
+//! ## Temperature
+//!
+//! Away from TNOM the parameters are first moved to the device temperature
+//! (VSAT, UA/UB/UC, RDSW, see `temperature.rs`); U0 and Vth take the
+//! temperature directly in their own equations.
+//!
 //! ## BSIM4 Extensions
 //!
 //! BSIM4 adds:
+//! - L/W/P binning of size-dependent parameters
+//! - MOBMOD 0/1/2 mobility, velocity overshoot and the source-end
+//!   velocity limit
 //! - Layout-dependent stress effects on mobility and Vth
 //! - Substrate current from impact ionization
-//! - Gate tunneling currents (source and drain sides)
+//! - Gate tunneling currents (source and drain sides, Igc partition)
+//! - Gate-induced drain/source leakage
 //!
 //! ## MNA Stamping
 //!
@@ -44,7 +58,11 @@ use super::types::{BsimOutput, Bsim4Output, MosRegion, MosType};
 use super::threshold::calculate_vth;
 use super::mobility::calculate_mobility;
 use super::channel::{calculate_vdsat, calculate_clm_factor, calculate_rds};
-use super::bsim4::{calculate_isub, calculate_stress_effects, calculate_gate_tunneling};
+use super::bsim4::{
+    apply_binning, calculate_gate_tunneling, calculate_gidl, calculate_igc, calculate_isub,
+    apply_layout_effects, calculate_mobility_bsim4, source_velocity_limit, velocity_overshoot,
+};
+use super::capacitance::{calculate_charges, calculate_meyer_caps};
 
 /// Minimum conductance for numerical stability [S]
 const GMIN: f64 = 1e-12;
@@ -106,6 +124,15 @@ pub fn evaluate_bsim_dc(
     let leff = params.leff(l);
     let weff = params.weff(w);
 
+    // Temperature update of VSAT, UA/UB/UC and RDSW
+    let heated;
+    let params = if temp == params.tnom {
+        params
+    } else {
+        heated = params.at_temperature(temp);
+        &heated
+    };
+
     // Oxide capacitance per unit area
     let cox = EPSILON_OX / params.tox;
 
@@ -128,6 +155,7 @@ pub fn evaluate_bsim_dc(
     let mut gm: f64;
     let mut gds: f64;
     let gmbs: f64;
+    let mut vdsat_cap: f64 = 0.0;
 
     if vgst <= 0.0 {
         // ========================================
@@ -166,6 +194,7 @@ pub fn evaluate_bsim_dc(
         // Step 4: Saturation Voltage
         // ========================================
         let (vdsat, dvdsat_dvgs) = calculate_vdsat(params, vgs, vth, ueff, leff);
+        vdsat_cap = vdsat;
 
         // ========================================
         // Step 5: Drain Current Calculation
@@ -233,7 +262,7 @@ pub fn evaluate_bsim_dc(
     // ========================================
     // Source/Drain Series Resistance
     // ========================================
-    let rds = calculate_rds(params, weff, temp);
+    let rds = calculate_rds(params, weff);
     if rds > 0.0 && ids > 0.0 {
         // Simplified Rds effect: reduce effective gds
         // Full model would iterate on Vds_int
@@ -267,6 +296,15 @@ pub fn evaluate_bsim_dc(
 
     let ieq = ids - gm * vgs_orig - gds * vds_orig - gmbs * vbs_orig;
 
+    // ========================================
+    // Meyer gate capacitances
+    // ========================================
+    let swapped = reversed != (params.mos_type == MosType::Pmos);
+    let (cgs, cgd, cgb) = calculate_meyer_caps(params, weff, leff, vgst, vds, vdsat_cap, swapped);
+    // Forward-frame Vbs: in reverse mode the drain acts as the source
+    let vbs_cv = if reversed { vb_int - vd_int } else { vbs };
+    let (qd, qg, qs, qb) = calculate_charges(params, weff, leff, vth, [vgs, vds, vbs_cv], swapped);
+
     BsimOutput {
         ids,
         gm,
@@ -275,6 +313,13 @@ pub fn evaluate_bsim_dc(
         ieq,
         region,
         vth_eff: vth,
+        cgs,
+        cgd,
+        cgb,
+        qd,
+        qg,
+        qs,
+        qb,
     }
 }
 
@@ -352,15 +397,20 @@ pub fn evaluate_level1_dc(
         ieq,
         region,
         vth_eff: vth,
+        ..Default::default()
     }
 }
 
 /// BSIM4 DC evaluation function
 ///
 /// Enhanced version of BSIM DC evaluation with:
+/// - L/W/P binning (`l<name>`, `w<name>`, `p<name>`, BINUNIT)
+/// - Unified mobility models (MOBMOD)
+/// - Velocity overshoot and source-end velocity limit (LAMBDA, VTL, LC, XN)
 /// - Layout-dependent stress effects (SA/SB parameters)
 /// - Substrate current from impact ionization (ALPHA0, BETA0)
-/// - Gate tunneling currents (JTSS, JTSD)
+/// - Gate tunneling currents (JTSS, JTSD; Igc partition with IGCMOD, PIGCD)
+/// - GIDL/GISL (AGIDL, BGIDL, CGIDL, EGIDL)
 ///
 /// # Arguments
 /// * `params` - BSIM parameters (should have level=54 for full BSIM4)
@@ -418,6 +468,26 @@ pub fn evaluate_bsim4_dc(
     let leff = params.leff(l);
     let weff = params.weff(w);
 
+    // ========================================
+    // BSIM4: L/W Binning (before any physics)
+    // ========================================
+    let binned;
+    let params = if params.binning.is_empty() {
+        params
+    } else {
+        binned = apply_binning(params, leff, weff);
+        &binned
+    };
+
+    // Temperature update of the size-adjusted parameters
+    let heated;
+    let params = if temp == params.tnom {
+        params
+    } else {
+        heated = params.at_temperature(temp);
+        &heated
+    };
+
     // Oxide capacitance per unit area
     let cox = EPSILON_OX / params.tox;
 
@@ -425,29 +495,21 @@ pub fn evaluate_bsim4_dc(
     let vt = K_BOLTZMANN * temp / Q_ELECTRON;
 
     // ========================================
-    // BSIM4: Stress Effects
+    // BSIM4: Layout-Dependent Effects (LOD stress, well proximity)
     // ========================================
-    let (u0_stress_mult, vth_stress_shift) = if sa > 0.0 || sb > 0.0 {
-        calculate_stress_effects(
-            sa.max(params.saref), // Use reference if not specified
-            sb.max(params.sbref),
-            params.saref,
-            params.sbref,
-            params.ku0,
-            params.kvth0,
-            temp,
-            params.tnom,
-            params.tku0,
-        )
-    } else {
-        (1.0, 0.0)
+    let shifted;
+    let params = match apply_layout_effects(params, l, w, temp, sa, sb) {
+        Some(adjusted) => {
+            shifted = adjusted;
+            &shifted
+        }
+        None => params,
     };
 
     // ========================================
-    // Step 1: Threshold Voltage (with stress shift)
+    // Step 1: Threshold Voltage
     // ========================================
-    let (vth_base, dvth_dvbs) = calculate_vth(params, vbs, vds, leff, weff, temp);
-    let vth = vth_base + vth_stress_shift;
+    let (vth, dvth_dvbs) = calculate_vth(params, vbs, vds, leff, weff, temp);
 
     // Gate overdrive
     let vgst = vgs - vth;
@@ -459,7 +521,7 @@ pub fn evaluate_bsim4_dc(
     let mut ids: f64;
     let mut gm: f64;
     let mut gds: f64;
-    let gmbs: f64;
+    let mut gmbs: f64;
     let mut ueff: f64 = 0.0;
     let mut vdsat: f64 = 0.0;
 
@@ -470,7 +532,7 @@ pub fn evaluate_bsim4_dc(
         region = MosRegion::Cutoff;
 
         let n = params.nfactor.max(1.0);
-        let i0 = weff / leff * params.u0 * 1e-4 * u0_stress_mult * cox * vt * vt * (n - 1.0);
+        let i0 = weff / leff * params.u0 * 1e-4 * cox * vt * vt * (n - 1.0);
 
         let exp_vgst = (vgst / (n * vt)).exp();
         let exp_vds = (-vds / vt).exp();
@@ -487,9 +549,12 @@ pub fn evaluate_bsim4_dc(
 
     } else {
         // ========================================
-        // Step 3: Mobility (with stress effect)
+        // Step 3: Mobility (MOBMOD)
         // ========================================
-        ueff = calculate_mobility(params, vgs, vbs, vth, leff, temp) * u0_stress_mult;
+        ueff = match params.mobmod {
+            Some(mobmod) => calculate_mobility_bsim4(params, mobmod, vgst, vth, vbs, temp),
+            None => calculate_mobility(params, vgs, vbs, vth, leff, temp),
+        };
 
         // ========================================
         // Step 4: Saturation Voltage
@@ -528,16 +593,36 @@ pub fn evaluate_bsim4_dc(
             let gds_dibl = gm * params.eta0;
             gds += gds_dibl;
 
+            // BSIM4: velocity overshoot beyond pinch-off
+            let esat_leff = 2.0 * params.vsat / ueff_m2 * leff;
+            let (f_ovs, df_dvds) = velocity_overshoot(params.lambda, vds, vdsat, esat_leff);
+            if f_ovs > 1.0 {
+                gm = gm * f_ovs - ids * df_dvds * dvdsat_dvgs;
+                gds = gds * f_ovs + ids * df_dvds;
+                ids *= f_ovs;
+            }
+
             gmbs = -gm * dvth_dvbs;
         }
     }
 
+    // ========================================
+    // BSIM4: Source-End Velocity Limit (with LAMBDA)
+    // ========================================
+    if params.lambda > 0.0 && vgst > 0.0 {
+        let (ids_lim, d_dids, d_dvgst) = source_velocity_limit(params, ids, weff, leff, vgst);
+        gm = gm * d_dids + d_dvgst;
+        gds *= d_dids;
+        gmbs = gmbs * d_dids - d_dvgst * dvth_dvbs;
+        ids = ids_lim;
+    }
+
     ids = ids.max(0.0);
 
     // ========================================
     // Source/Drain Series Resistance
     // ========================================
-    let rds = calculate_rds(params, weff, temp);
+    let rds = calculate_rds(params, weff);
     if rds > 0.0 && ids > 0.0 {
         let v_rds = ids * rds;
         if v_rds < vds * 0.5 {
@@ -583,6 +668,24 @@ pub fn evaluate_bsim4_dc(
         (0.0, 0.0, 0.0, 0.0)
     };
 
+    // ========================================
+    // BSIM4: Gate-to-Channel Tunneling and GIDL/GISL
+    // ========================================
+    // Body bias measured from the forward-frame source
+    let vbs_cv = if reversed { vb_int - vd_int } else { vbs };
+    let vdseff = if vgst > 0.0 { vds.min(vdsat) } else { 0.0 };
+    let (igcs, igcd) = calculate_igc(params, weff, leff, vth, [vgs, vdseff, vbs_cv], vt);
+    let igidl = calculate_gidl(params, weff, vds, vgs, vds - vbs_cv);
+    let igisl = calculate_gidl(params, weff, -vds, vgd, -vbs_cv);
+
+    // Internal source/drain back to the external terminals
+    let swapped = reversed != (params.mos_type == MosType::Pmos);
+    let (igcs, igcd, igidl, igisl) = if swapped {
+        (igcd, igcs, igisl, igidl)
+    } else {
+        (igcs, igcd, igidl, igisl)
+    };
+
     // Apply sign for PMOS
     let ids_signed = ids * sign;
     let isub_signed = isub * sign.abs(); // Substrate current always flows to bulk
@@ -598,6 +701,13 @@ pub fn evaluate_bsim4_dc(
 
     let ieq = ids_signed - gm * vgs_orig - gds * vds_orig - gmbs * vbs_orig;
 
+    // ========================================
+    // Meyer gate capacitances
+    // ========================================
+    let (cgs, cgd, cgb) = calculate_meyer_caps(params, weff, leff, vgst, vds, vdsat, swapped);
+    let vbs_cv = if reversed { vb_int - vd_int } else { vbs };
+    let (qd, qg, qs, qb) = calculate_charges(params, weff, leff, vth, [vgs, vds, vbs_cv], swapped);
+
     Bsim4Output {
         base: BsimOutput {
             ids: ids_signed,
@@ -607,6 +717,13 @@ pub fn evaluate_bsim4_dc(
             ieq,
             region,
             vth_eff: vth,
+            cgs,
+            cgd,
+            cgb,
+            qd,
+            qg,
+            qs,
+            qb,
         },
         isub: isub_signed,
         gsub,
@@ -614,6 +731,10 @@ pub fn evaluate_bsim4_dc(
         igd: igd_signed,
         gigs,
         gigd,
+        igcs: igcs * sign,
+        igcd: igcd * sign,
+        igidl: igidl * sign,
+        igisl: igisl * sign,
         ueff,
         vdsat,
     }
@@ -737,8 +858,8 @@ mod tests {
     #[test]
     fn test_bsim4_with_stress() {
         let mut params = BsimParams::nmos_default();
-        params.ku0 = 0.1;      // Mobility stress coefficient
-        params.kvth0 = 0.01;   // Vth stress coefficient
+        params.ku0 = 1e-7;     // Mobility stress coefficient [m]
+        params.kvth0 = 1e-9;   // Vth stress coefficient [V·m]
         params.saref = 1e-6;
         params.sbref = 1e-6;
 
@@ -751,6 +872,7 @@ mod tests {
         // Currents should differ due to stress effects
         assert!(out_no_stress.base.ids > 0.0);
         assert!(out_stress.base.ids > 0.0);
+        assert!((out_stress.base.ids - out_no_stress.base.ids).abs() > 1e-3 * out_no_stress.base.ids);
         // The specific relationship depends on the sign of stress coefficients
     }
 
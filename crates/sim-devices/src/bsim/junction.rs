@@ -0,0 +1,101 @@
+//! MOSFET Source/Drain Junction Diodes
+//!
+//! The drain and source diffusions form PN junctions with the bulk. Each
+//! junction is an ideal diode plus a depletion capacitance made of a bottom
+//! part (CJ per unit area, PB, MJ) and a sidewall part (CJSW per unit
+//! perimeter, PBSW, MJSW), sized by the instance parameters AD/PD (drain)
+//! and AS/PS (source).
+//!
+//! The saturation current is JS * area + JSSW * perimeter when a current
+//! density is given, otherwise the absolute IS. Junction voltages are in
+//! the N-channel frame (bulk minus diffusion for NMOS); the caller applies
+//! the polarity.
+//!
+//! The parameters are taken as given; callers pass parameters already
+//! moved to the device temperature (`BsimParams::at_temperature`).
+
+use super::params::BsimParams;
+use crate::bjt::junction_current;
+use crate::junction::{depletion_capacitance, depletion_charge};
+
+/// One source/drain junction, sized for a given diffusion geometry
+#[derive(Debug, Clone, PartialEq)]
+pub struct MosJunction {
+    /// Saturation current [A]
+    pub is: f64,
+    /// Emission coefficient
+    pub n: f64,
+    /// Zero-bias bottom capacitance CJ * area [F]
+    pub cj: f64,
+    /// Zero-bias sidewall capacitance CJSW * perimeter [F]
+    pub cjsw: f64,
+    pub pb: f64,
+    pub mj: f64,
+    pub pbsw: f64,
+    pub mjsw: f64,
+    /// Forward-bias depletion capacitance coefficient
+    pub fc: f64,
+}
+
+impl MosJunction {
+    /// Size the junction from the model parameters and the diffusion
+    /// area [m^2] / perimeter [m]
+    pub fn new(params: &BsimParams, area: f64, perimeter: f64) -> Self {
+        let area = area.max(0.0);
+        let perimeter = perimeter.max(0.0);
+        let scaled = params.js * area + params.jssw * perimeter;
+        MosJunction {
+            is: if scaled > 0.0 { scaled } else { params.is },
+            n: params.nj,
+            cj: params.cj * area,
+            cjsw: params.cjsw * perimeter,
+            pb: params.pb,
+            mj: params.mj,
+            pbsw: params.pbsw,
+            mjsw: params.mjsw,
+            fc: params.fc,
+        }
+    }
+
+    /// Junction current and conductance at junction voltage `v`
+    pub fn current(&self, v: f64, vt: f64) -> (f64, f64) {
+        junction_current(self.is, v, self.n, vt)
+    }
+
+    /// Depletion charge and capacitance at junction voltage `v`
+    pub fn charge(&self, v: f64) -> (f64, f64) {
+        let q = depletion_charge(self.cj, v, self.pb, self.mj, self.fc)
+            + depletion_charge(self.cjsw, v, self.pbsw, self.mjsw, self.fc);
+        let c = depletion_capacitance(self.cj, v, self.pb, self.mj, self.fc)
+            + depletion_capacitance(self.cjsw, v, self.pbsw, self.mjsw, self.fc);
+        (q, c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_junction_sizing_prefers_current_density() {
+        let mut params = BsimParams::nmos_default();
+        let j = MosJunction::new(&params, 1e-12, 4e-6);
+        assert_eq!(j.is, params.is);
+        assert!((j.cj - params.cj * 1e-12).abs() < 1e-30);
+        params.js = 1e-4;
+        let j = MosJunction::new(&params, 1e-12, 4e-6);
+        assert!((j.is - 1e-16).abs() < 1e-28);
+    }
+
+    #[test]
+    fn test_junction_capacitance_is_charge_derivative() {
+        let params = BsimParams::nmos_default();
+        let j = MosJunction::new(&params, 1e-11, 1e-5);
+        for v in [-2.0, 0.0, 0.6] {
+            let h = 1e-6;
+            let (_, c) = j.charge(v);
+            let fd = (j.charge(v + h).0 - j.charge(v - h).0) / (2.0 * h);
+            assert!((fd - c).abs() < 1e-6 * c, "v = {}", v);
+        }
+    }
+}
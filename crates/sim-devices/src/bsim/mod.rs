@@ -1,7 +1,8 @@
 //! BSIM MOSFET Model Implementation
 //!
 //! This module implements BSIM3 (Level 49) and BSIM4 (Level 54) DC models
-//! for SPICE simulation. BSIM3/4 are industry-standard models for CMOS processes.
+//! for SPICE simulation, plus the EKV 2.6 model (Level 44). BSIM3/4 are
+//! industry-standard models for CMOS processes.
 //!
 //! ## Module Structure
 //!
@@ -10,8 +11,11 @@
 //! - `threshold`: Threshold voltage calculation with body effect, SCE, DIBL
 //! - `mobility`: Mobility degradation with field and temperature effects
 //! - `channel`: Vdsat, CLM, and output conductance calculations
+//! - `capacitance`: Meyer intrinsic and overlap gate capacitances
+//! - `junction`: Source/drain junction diodes and depletion capacitances
 //! - `evaluate`: Main DC evaluation entry point
 //! - `bsim4`: BSIM4-specific physics (substrate current, stress, tunneling)
+//! - `ekv`: EKV 2.6 model (Level 44)
 //!
 //! ## Usage
 //!
@@ -42,6 +46,7 @@
 //! | Level | Model | Status |
 //! |-------|-------|--------|
 //! | 1 | Level 1 (Shichman-Hodges) | Supported via `evaluate_level1_dc` |
+//! | 44 | EKV 2.6 | Supported via `evaluate_ekv_dc` |
 //! | 49 | BSIM3v3 | Core DC supported |
 //! | 54 | BSIM4 | Supported (substrate current, stress, tunneling) |
 //!
@@ -57,14 +62,21 @@ pub mod types;
 pub mod threshold;
 pub mod mobility;
 pub mod channel;
+pub mod capacitance;
+pub mod temperature;
+pub mod junction;
 pub mod evaluate;
 pub mod bsim4;
+pub mod ekv;
 
 // Re-export commonly used items
 pub use params::BsimParams;
 pub use types::{MosType, MosRegion, BsimOutput, BsimState, Bsim4Output, Bsim4State};
 pub use evaluate::{evaluate_bsim_dc, evaluate_level1_dc, evaluate_bsim4_dc};
-pub use bsim4::{calculate_isub, calculate_stress_effects, calculate_gate_tunneling};
+pub use bsim4::{apply_layout_effects, calculate_isub, calculate_stress_effects, calculate_gate_tunneling, calculate_gidl, calculate_igc};
+pub use capacitance::{calculate_charges, calculate_meyer_caps};
+pub use junction::MosJunction;
+pub use ekv::evaluate_ekv_dc;
 
 use std::collections::HashMap;
 
@@ -92,6 +104,10 @@ pub fn build_bsim_params(
     };
 
     p.level = level;
+    if level == 44 {
+        // EKV default channel length modulation
+        p.lambda = 0.5;
+    }
 
     // Helper to parse parameter value
     let get_param = |keys: &[&str]| -> Option<f64> {
@@ -110,7 +126,7 @@ pub fn build_bsim_params(
     if let Some(v) = get_param(&["vth0", "vto", "vth"]) {
         p.vth0 = v;
     }
-    if let Some(v) = get_param(&["k1"]) {
+    if let Some(v) = get_param(&["k1", "gamma"]) {
         p.k1 = v;
     }
     if let Some(v) = get_param(&["k2"]) {
@@ -213,8 +229,44 @@ pub fn build_bsim_params(
     if let Some(v) = get_param(&["kt2"]) {
         p.kt2 = v;
     }
+    if let Some(v) = get_param(&["at"]) {
+        p.at = v;
+    }
+    if let Some(v) = get_param(&["prt"]) {
+        p.prt = v;
+    }
+    if let Some(v) = get_param(&["ua1"]) {
+        p.ua1 = v;
+    }
+    if let Some(v) = get_param(&["ub1"]) {
+        p.ub1 = v;
+    }
+    if let Some(v) = get_param(&["uc1"]) {
+        p.uc1 = v;
+    }
+    if let Some(v) = get_param(&["xti"]) {
+        p.xti = v;
+    }
+    if let Some(v) = get_param(&["tpb"]) {
+        p.tpb = v;
+    }
+    if let Some(v) = get_param(&["tpbsw"]) {
+        p.tpbsw = v;
+    }
+    if let Some(v) = get_param(&["tcj"]) {
+        p.tcj = v;
+    }
+    if let Some(v) = get_param(&["tcjsw"]) {
+        p.tcjsw = v;
+    }
 
-    // Capacitance parameters (for future use)
+    // Capacitance parameters
+    if let Some(v) = get_param(&["capmod"]) {
+        p.capmod = v as u32;
+    }
+    if let Some(v) = get_param(&["xpart"]) {
+        p.xpart = v;
+    }
     if let Some(v) = get_param(&["cgso"]) {
         p.cgso = v;
     }
@@ -225,6 +277,41 @@ pub fn build_bsim_params(
         p.cgbo = v;
     }
 
+    // Source/drain junction parameters
+    if let Some(v) = get_param(&["cj"]) {
+        p.cj = v;
+    }
+    if let Some(v) = get_param(&["cjsw"]) {
+        p.cjsw = v;
+    }
+    if let Some(v) = get_param(&["pb"]) {
+        p.pb = v;
+    }
+    if let Some(v) = get_param(&["pbsw"]) {
+        p.pbsw = v;
+    }
+    if let Some(v) = get_param(&["mj"]) {
+        p.mj = v;
+    }
+    if let Some(v) = get_param(&["mjsw"]) {
+        p.mjsw = v;
+    }
+    if let Some(v) = get_param(&["fc"]) {
+        p.fc = v;
+    }
+    if let Some(v) = get_param(&["is"]) {
+        p.is = v;
+    }
+    if let Some(v) = get_param(&["js"]) {
+        p.js = v;
+    }
+    if let Some(v) = get_param(&["jssw"]) {
+        p.jssw = v;
+    }
+    if let Some(v) = get_param(&["nj", "n"]) {
+        p.nj = v;
+    }
+
     // ============================================================
     // BSIM4-specific parameters (Level 54)
     // ============================================================
@@ -298,6 +385,9 @@ pub fn build_bsim_params(
     if let Some(v) = get_param(&["eu"]) {
         p.eu = v;
     }
+    if let Some(v) = get_param(&["mobmod"]) {
+        p.mobmod = Some(v as u32);
+    }
 
     // Enhanced velocity saturation parameters
     if let Some(v) = get_param(&["vs"]) {
@@ -315,6 +405,9 @@ pub fn build_bsim_params(
     if let Some(v) = get_param(&["lc"]) {
         p.lc = v;
     }
+    if let Some(v) = get_param(&["xn"]) {
+        p.xn = v;
+    }
 
     // Substrate current (impact ionization) parameters
     if let Some(v) = get_param(&["alpha0"]) {
@@ -352,6 +445,94 @@ pub fn build_bsim_params(
     if let Some(v) = get_param(&["tku0"]) {
         p.tku0 = v;
     }
+    if let Some(v) = get_param(&["kvsat"]) {
+        p.kvsat = v;
+    }
+    if let Some(v) = get_param(&["stk2"]) {
+        p.stk2 = v;
+    }
+    if let Some(v) = get_param(&["lodk2"]) {
+        p.lodk2 = v;
+    }
+    if let Some(v) = get_param(&["steta0"]) {
+        p.steta0 = v;
+    }
+    if let Some(v) = get_param(&["lodeta0"]) {
+        p.lodeta0 = v;
+    }
+    if let Some(v) = get_param(&["lku0"]) {
+        p.lku0 = v;
+    }
+    if let Some(v) = get_param(&["wku0"]) {
+        p.wku0 = v;
+    }
+    if let Some(v) = get_param(&["pku0"]) {
+        p.pku0 = v;
+    }
+    if let Some(v) = get_param(&["llodku0"]) {
+        p.llodku0 = v;
+    }
+    if let Some(v) = get_param(&["wlodku0"]) {
+        p.wlodku0 = v;
+    }
+    if let Some(v) = get_param(&["lkvth0"]) {
+        p.lkvth0 = v;
+    }
+    if let Some(v) = get_param(&["wkvth0"]) {
+        p.wkvth0 = v;
+    }
+    if let Some(v) = get_param(&["pkvth0"]) {
+        p.pkvth0 = v;
+    }
+    if let Some(v) = get_param(&["llodvth"]) {
+        p.llodvth = v;
+    }
+    if let Some(v) = get_param(&["wlodvth"]) {
+        p.wlodvth = v;
+    }
+
+    // Well proximity effect parameters
+    if let Some(v) = get_param(&["wpemod"]) {
+        p.wpemod = v as u32;
+    }
+    if let Some(v) = get_param(&["scref"]) {
+        p.scref = v;
+    }
+    if let Some(v) = get_param(&["web"]) {
+        p.web = v;
+    }
+    if let Some(v) = get_param(&["wec"]) {
+        p.wec = v;
+    }
+    if let Some(v) = get_param(&["kvth0we"]) {
+        p.kvth0we = v;
+    }
+    if let Some(v) = get_param(&["k2we"]) {
+        p.k2we = v;
+    }
+    if let Some(v) = get_param(&["ku0we"]) {
+        p.ku0we = v;
+    }
+
+    // Instance layout parameters (merged into the model parameters per instance)
+    if let Some(v) = get_param(&["sd"]) {
+        p.sd = v;
+    }
+    if let Some(v) = get_param(&["nf"]) {
+        p.nf = v;
+    }
+    if let Some(v) = get_param(&["sc"]) {
+        p.sc = v;
+    }
+    if let Some(v) = get_param(&["sca"]) {
+        p.sca = v;
+    }
+    if let Some(v) = get_param(&["scb"]) {
+        p.scb = v;
+    }
+    if let Some(v) = get_param(&["scc"]) {
+        p.scc = v;
+    }
 
     // Gate tunneling parameters
     if let Some(v) = get_param(&["jtss"]) {
@@ -369,6 +550,44 @@ pub fn build_bsim_params(
     if let Some(v) = get_param(&["vtsd"]) {
         p.vtsd = v;
     }
+    if let Some(v) = get_param(&["igcmod"]) {
+        p.igcmod = v as u32;
+    }
+    if let Some(v) = get_param(&["aigc"]) {
+        p.aigc = v;
+    }
+    if let Some(v) = get_param(&["bigc"]) {
+        p.bigc = v;
+    }
+    if let Some(v) = get_param(&["cigc"]) {
+        p.cigc = v;
+    }
+    if let Some(v) = get_param(&["nigc"]) {
+        p.nigc = v;
+    }
+    if let Some(v) = get_param(&["pigcd"]) {
+        p.pigcd = v;
+    }
+    if let Some(v) = get_param(&["toxref"]) {
+        p.toxref = v;
+    }
+    if let Some(v) = get_param(&["ntox"]) {
+        p.ntox = v;
+    }
+
+    // GIDL / GISL parameters
+    if let Some(v) = get_param(&["agidl"]) {
+        p.agidl = v;
+    }
+    if let Some(v) = get_param(&["bgidl"]) {
+        p.bgidl = v;
+    }
+    if let Some(v) = get_param(&["cgidl"]) {
+        p.cgidl = v;
+    }
+    if let Some(v) = get_param(&["egidl"]) {
+        p.egidl = v;
+    }
 
     // Output conductance parameters
     if let Some(v) = get_param(&["pvag"]) {
@@ -384,36 +603,107 @@ pub fn build_bsim_params(
         p.delta = v;
     }
 
+    // EKV 2.6 parameters
+    if let Some(v) = get_param(&["kp"]) {
+        p.kp = v;
+    }
+    if let Some(v) = get_param(&["phi"]) {
+        p.phi = v;
+    }
+    if let Some(v) = get_param(&["theta"]) {
+        p.theta = v;
+    }
+    if let Some(v) = get_param(&["ucrit"]) {
+        p.ucrit = v;
+    }
+    if let Some(v) = get_param(&["xj"]) {
+        p.xj = v;
+    }
+    if let Some(v) = get_param(&["leta"]) {
+        p.leta = v;
+    }
+    if let Some(v) = get_param(&["weta"]) {
+        p.weta = v;
+    }
+    if let Some(v) = get_param(&["tcv"]) {
+        p.tcv = v;
+    }
+    if let Some(v) = get_param(&["bex"]) {
+        p.bex = v;
+    }
+    if let Some(v) = get_param(&["ucex"]) {
+        p.ucex = v;
+    }
+
+    // Binning: l<name>, w<name>, p<name> for every binnable parameter
+    if let Some(v) = get_param(&["binunit"]) {
+        p.binunit = v as u32;
+    }
+    for name in bsim4::BINNABLE_PARAMS {
+        let coeffs = ["l", "w", "p"].map(|prefix| get_param(&[&format!("{}{}", prefix, name)]));
+        if coeffs.iter().any(Option::is_some) {
+            p.binning.insert(name.to_string(), coeffs.map(|c| c.unwrap_or(0.0)));
+        }
+    }
+
     p
 }
 
-/// Parse a number with optional SI suffix
-fn parse_number(s: &str) -> Option<f64> {
-    let lower = s.to_ascii_lowercase();
-    let trimmed = lower.trim();
-
-    // Check for SI suffixes
-    let (num_str, multiplier) = if trimmed.ends_with("meg") {
-        (&trimmed[..trimmed.len() - 3], 1e6)
-    } else if trimmed.ends_with("mil") {
-        (&trimmed[..trimmed.len() - 3], 25.4e-6)
-    } else {
-        let (value_part, suffix) = trimmed.split_at(trimmed.len().saturating_sub(1));
-        match suffix {
-            "f" => (value_part, 1e-15),
-            "p" => (value_part, 1e-12),
-            "n" => (value_part, 1e-9),
-            "u" => (value_part, 1e-6),
-            "m" => (value_part, 1e-3),
-            "k" => (value_part, 1e3),
-            "g" => (value_part, 1e9),
-            "t" => (value_part, 1e12),
-            _ => (trimmed, 1.0),
+/// Parse a number with optional SI suffix; unit letters after the suffix
+/// are ignored (`100nF`, `4.7kOhm`)
+pub(crate) fn parse_number(s: &str) -> Option<f64> {
+    let lower = s.trim().to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let mut end = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        end += 1;
+    }
+    let mantissa_start = end;
+    while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'.') {
+        end += 1;
+    }
+    if !bytes[mantissa_start..end].iter().any(u8::is_ascii_digit) {
+        return None;
+    }
+    // Exponent only when followed by digits; a bare `e` is a unit letter
+    if end < bytes.len() && bytes[end] == b'e' {
+        let mut exp_end = end + 1;
+        if matches!(bytes.get(exp_end), Some(b'+' | b'-')) {
+            exp_end += 1;
         }
+        let digits_start = exp_end;
+        while exp_end < bytes.len() && bytes[exp_end].is_ascii_digit() {
+            exp_end += 1;
+        }
+        if exp_end > digits_start {
+            end = exp_end;
+        }
+    }
+    let num: f64 = lower[..end].parse().ok()?;
+    let rest = &lower[end..];
+    let (multiplier, unit) = if let Some(unit) = rest.strip_prefix("meg") {
+        (1e6, unit)
+    } else if let Some(unit) = rest.strip_prefix("mil") {
+        (25.4e-6, unit)
+    } else {
+        let multiplier = match rest.chars().next() {
+            Some('f') => 1e-15,
+            Some('p') => 1e-12,
+            Some('n') => 1e-9,
+            Some('u') => 1e-6,
+            Some('m') => 1e-3,
+            Some('k') => 1e3,
+            Some('g') => 1e9,
+            Some('t') => 1e12,
+            _ => 1.0,
+        };
+        let unit = if multiplier == 1.0 { rest } else { &rest[1..] };
+        (multiplier, unit)
     };
-
-    num_str.parse::<f64>().ok().map(|n| n * multiplier)
-        .or_else(|| trimmed.parse::<f64>().ok())
+    if !unit.chars().all(|ch| ch.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(num * multiplier)
 }
 
 /// Route to appropriate model evaluation based on level
@@ -444,14 +734,41 @@ pub fn evaluate_mos(
             let lambda = 0.02; // Default CLM for Level 1
             let beta = params.u0 * 1e-4 * params.cox();
 
-            evaluate_level1_dc(
+            let is_pmos = params.mos_type == MosType::Pmos;
+            let mut out = evaluate_level1_dc(
                 vth0,
                 beta,
                 lambda,
                 w, l,
                 vd, vg, vs, vb,
-                params.mos_type == MosType::Pmos,
-            )
+                is_pmos,
+            );
+
+            // Gate capacitances and charges in the same forward frame as the DC model
+            let (vd_int, vg_int, vs_int, vb_int) = if is_pmos { (-vs, -vg, -vd, -vb) } else { (vd, vg, vs, vb) };
+            let reversed = vd_int < vs_int;
+            let (vgs, vbs) = if reversed {
+                (vg_int - vd_int, vb_int - vd_int)
+            } else {
+                (vg_int - vs_int, vb_int - vs_int)
+            };
+            let vds = (vd_int - vs_int).abs();
+            let vgst = vgs - out.vth_eff;
+            let swapped = reversed != is_pmos;
+            let (cgs, cgd, cgb) = calculate_meyer_caps(params, w, l, vgst, vds, vgst, swapped);
+            out.cgs = cgs;
+            out.cgd = cgd;
+            out.cgb = cgb;
+            let (qd, qg, qs, qb) = calculate_charges(params, w, l, out.vth_eff, [vgs, vds, vbs], swapped);
+            out.qd = qd;
+            out.qg = qg;
+            out.qs = qs;
+            out.qb = qb;
+            out
+        }
+        44 => {
+            // EKV 2.6: continuous from weak to strong inversion
+            evaluate_ekv_dc(params, w, l, [vd, vg, vs, vb], temp)
         }
         49 => {
             // BSIM3 (Level 49)
@@ -3,6 +3,8 @@
 //! Contains the BsimParams structure with all BSIM3 model parameters
 //! and their default values for NMOS and PMOS devices.
 
+use std::collections::HashMap;
+
 use super::types::MosType;
 
 /// Physical constants
@@ -23,11 +25,11 @@ pub const T_NOMINAL: f64 = 300.15; // Nominal temperature [K] (27C)
 /// - Short-channel effects: pclm, pdiblc1, pdiblc2
 /// - Geometry: tox, lint, wint
 /// - Parasitic: rdsw
-/// - Temperature: tnom, ute, kt1
+/// - Temperature: tnom, ute, kt1, at, prt, ua1/ub1/uc1, junction coefficients
 #[derive(Debug, Clone)]
 pub struct BsimParams {
     // ============ Model Selection ============
-    /// Model level: 1=Level1, 49=BSIM3, 54=BSIM4
+    /// Model level: 1=Level1, 44=EKV, 49=BSIM3, 54=BSIM4
     pub level: u32,
     /// Device type: NMOS or PMOS
     pub mos_type: MosType,
@@ -154,12 +156,35 @@ pub struct BsimParams {
     pub kt1l: f64,
     /// Vth temperature coefficient (body bias) [V]
     pub kt2: f64,
-    /// Saturation velocity temperature coefficient [m/s/K]
+    /// Saturation velocity temperature coefficient [m/s]
+    /// VSAT(T) = VSAT - AT * (T/Tnom - 1)
     pub at: f64,
-    /// RDSW temperature coefficient [1/K]
+    /// RDSW temperature coefficient [ohm*um]
+    /// RDSW(T) = RDSW + PRT * (T/Tnom - 1)
     pub prt: f64,
-
-    // ============ Capacitance (for future AC/transient) ============
+    /// Temperature coefficient of UA [m/V]
+    pub ua1: f64,
+    /// Temperature coefficient of UB [(m/V)^2]
+    pub ub1: f64,
+    /// Temperature coefficient of UC [m/V^2]
+    pub uc1: f64,
+    /// Junction saturation current temperature exponent [dimensionless]
+    pub xti: f64,
+    /// Temperature coefficient of PB [V/K]
+    pub tpb: f64,
+    /// Temperature coefficient of PBSW [V/K]
+    pub tpbsw: f64,
+    /// Temperature coefficient of CJ [1/K]
+    pub tcj: f64,
+    /// Temperature coefficient of CJSW [1/K]
+    pub tcjsw: f64,
+
+    // ============ Capacitance ============
+    /// Gate capacitance model: 0 = Meyer, 1/2 = charge-based,
+    /// 3 = charge-based with inversion charge thickness
+    pub capmod: u32,
+    /// Channel charge partition: 0 = 40/60, 0.5 = 50/50, 1 = 0/100 (drain/source)
+    pub xpart: f64,
     /// Gate-source overlap capacitance per unit width [F/m]
     pub cgso: f64,
     /// Gate-drain overlap capacitance per unit width [F/m]
@@ -178,6 +203,16 @@ pub struct BsimParams {
     pub mj: f64,
     /// Junction sidewall grading coefficient [dimensionless]
     pub mjsw: f64,
+    /// Forward-bias junction capacitance coefficient [dimensionless]
+    pub fc: f64,
+    /// Junction saturation current when JS / JSSW are not given [A]
+    pub is: f64,
+    /// Junction saturation current density [A/m^2]
+    pub js: f64,
+    /// Sidewall junction saturation current density [A/m]
+    pub jssw: f64,
+    /// Junction emission coefficient [dimensionless]
+    pub nj: f64,
 
     // ============ Flicker Noise (for future noise analysis) ============
     /// Flicker noise coefficient A [dimensionless]
@@ -234,6 +269,9 @@ pub struct BsimParams {
     pub ud1: f64,
     /// Field effect mobility exponent [dimensionless]
     pub eu: f64,
+    /// BSIM4 mobility model selector (0-2); `None` keeps the BSIM3
+    /// universal-field expression
+    pub mobmod: Option<u32>,
 
     // ============ BSIM4 Enhanced Velocity Saturation ============
     /// Alternative saturation velocity [m/s] (0 = use VSAT)
@@ -246,6 +284,8 @@ pub struct BsimParams {
     pub vtl: f64,
     /// Characteristic length for velocity [m]
     pub lc: f64,
+    /// Smoothing exponent of the source-end velocity limit [dimensionless]
+    pub xn: f64,
 
     // ============ BSIM4 Substrate Current (Impact Ionization) ============
     /// Impact ionization drain voltage coefficient [1/V]
@@ -268,6 +308,58 @@ pub struct BsimParams {
     pub ku0mult: f64,
     /// Temperature coefficient for KU0 [1/K]
     pub tku0: f64,
+    /// Saturation velocity stress coefficient [dimensionless]
+    pub kvsat: f64,
+    /// K2 stress shift coefficient [V·m]
+    pub stk2: f64,
+    /// Size exponent of the K2 stress shift [dimensionless]
+    pub lodk2: f64,
+    /// ETA0 stress shift coefficient [m]
+    pub steta0: f64,
+    /// Size exponent of the ETA0 stress shift [dimensionless]
+    pub lodeta0: f64,
+    /// Length / width / product dependence of KU0
+    pub lku0: f64,
+    pub wku0: f64,
+    pub pku0: f64,
+    /// Length / width exponents of the KU0 size dependence
+    pub llodku0: f64,
+    pub wlodku0: f64,
+    /// Length / width / product dependence of KVTH0
+    pub lkvth0: f64,
+    pub wkvth0: f64,
+    pub pkvth0: f64,
+    /// Length / width exponents of the KVTH0 size dependence
+    pub llodvth: f64,
+    pub wlodvth: f64,
+
+    // ============ BSIM4 Well Proximity Effect ============
+    /// WPE model selector (1 = on)
+    pub wpemod: u32,
+    /// Reference distance for SCA/SCB/SCC [m]
+    pub scref: f64,
+    /// SCB / SCC weights in the WPE sum [dimensionless]
+    pub web: f64,
+    pub wec: f64,
+    /// WPE threshold voltage shift coefficient [V]
+    pub kvth0we: f64,
+    /// WPE K2 shift coefficient [V^0.5]
+    pub k2we: f64,
+    /// WPE mobility coefficient [dimensionless]
+    pub ku0we: f64,
+
+    // ============ BSIM4 Instance Layout ============
+    /// Distance between neighbouring fingers [m]
+    pub sd: f64,
+    /// Number of fingers
+    pub nf: f64,
+    /// Distance from the channel to the well edge [m]; derives SCA/SCB/SCC
+    /// when those are not given
+    pub sc: f64,
+    /// Well-edge integral distribution functions [dimensionless]
+    pub sca: f64,
+    pub scb: f64,
+    pub scc: f64,
 
     // ============ BSIM4 Gate Tunneling ============
     /// Source-side gate tunneling current density [A/m^2]
@@ -280,6 +372,39 @@ pub struct BsimParams {
     pub vtss: f64,
     /// Drain-side tunneling voltage [V]
     pub vtsd: f64,
+    /// Gate-to-channel tunneling switch (0 = off, 1 = on)
+    pub igcmod: u32,
+    /// Igc exponent coefficient [(F s^2/g)^0.5 / m]
+    pub aigc: f64,
+    /// Igc oxide-voltage coefficient [(F s^2/g)^0.5 / (m V)]
+    pub bigc: f64,
+    /// Igc oxide-voltage coefficient [1/V]
+    pub cigc: f64,
+    /// Igc inversion-charge ideality factor [dimensionless]
+    pub nigc: f64,
+    /// Igc source/drain partition coefficient [1/V]
+    pub pigcd: f64,
+    /// Reference oxide thickness for gate tunneling [m]
+    pub toxref: f64,
+    /// Oxide thickness exponent for gate tunneling [dimensionless]
+    pub ntox: f64,
+
+    // ============ BSIM4 GIDL / GISL ============
+    /// GIDL pre-exponential coefficient [S] (0 disables GIDL and GISL)
+    pub agidl: f64,
+    /// GIDL exponential field coefficient [V/m]
+    pub bgidl: f64,
+    /// GIDL body-bias parameter [V^3]
+    pub cgidl: f64,
+    /// GIDL band-bending offset [V]
+    pub egidl: f64,
+
+    // ============ BSIM4 Binning ============
+    /// Unit of Leff / Weff in the binning equations: 1 = micron, else meter
+    pub binunit: u32,
+    /// Size-dependence coefficients `[L, W, P]` by parameter name, from
+    /// the `l<name>`, `w<name>` and `p<name>` model parameters
+    pub binning: HashMap<String, [f64; 3]>,
 
     // ============ BSIM4 Enhanced Output Conductance ============
     /// Gate voltage PDIBL parameter [dimensionless]
@@ -290,6 +415,29 @@ pub struct BsimParams {
     pub pdits: f64,
     /// Effective Vds transition smoothing [V]
     pub delta: f64,
+
+    // ============ EKV 2.6 (Level 44) ============
+    // VTO, GAMMA and LAMBDA share VTH0, K1 and LAMBDA above
+    /// Transconductance parameter [A/V^2] (0 = U0 * COX)
+    pub kp: f64,
+    /// Bulk Fermi potential, twice [V]
+    pub phi: f64,
+    /// Mobility reduction coefficient [1/V]
+    pub theta: f64,
+    /// Longitudinal critical field for velocity saturation [V/m]
+    pub ucrit: f64,
+    /// Junction depth [m]
+    pub xj: f64,
+    /// Short-channel charge sharing coefficient [dimensionless]
+    pub leta: f64,
+    /// Narrow-channel effect coefficient [dimensionless]
+    pub weta: f64,
+    /// Threshold voltage temperature coefficient [V/K]
+    pub tcv: f64,
+    /// Mobility temperature exponent [dimensionless]
+    pub bex: f64,
+    /// UCRIT temperature exponent [dimensionless]
+    pub ucex: f64,
 }
 
 impl Default for BsimParams {
@@ -365,8 +513,18 @@ impl BsimParams {
             kt2: 0.022,
             at: 3.3e4,
             prt: 0.0,
+            ua1: 4.31e-9,
+            ub1: -7.61e-18,
+            uc1: -5.6e-11,
+            xti: 3.0,
+            tpb: 0.0,
+            tpbsw: 0.0,
+            tcj: 0.0,
+            tcjsw: 0.0,
 
             // Capacitance
+            capmod: 0,
+            xpart: 0.0,
             cgso: 0.0,
             cgdo: 0.0,
             cgbo: 0.0,
@@ -376,6 +534,11 @@ impl BsimParams {
             pbsw: 1.0,
             mj: 0.5,
             mjsw: 0.33,
+            fc: 0.5,
+            is: 1e-14,
+            js: 0.0,
+            jssw: 0.0,
+            nj: 1.0,
 
             // Noise
             kf: 0.0,
@@ -406,6 +569,7 @@ impl BsimParams {
             ud: 0.0,
             ud1: 0.0,
             eu: 1.67,
+            mobmod: None,
 
             // Enhanced Velocity Saturation
             vs: 0.0,
@@ -413,6 +577,7 @@ impl BsimParams {
             lambda: 0.0,
             vtl: 2.0e5,
             lc: 5e-9,
+            xn: 3.0,
 
             // Substrate Current (Impact Ionization)
             alpha1: 0.0,
@@ -426,6 +591,38 @@ impl BsimParams {
             kvth0: 0.0,
             ku0mult: 1.0,
             tku0: 0.0,
+            kvsat: 0.0,
+            stk2: 0.0,
+            lodk2: 1.0,
+            steta0: 0.0,
+            lodeta0: 1.0,
+            lku0: 0.0,
+            wku0: 0.0,
+            pku0: 0.0,
+            llodku0: 0.0,
+            wlodku0: 0.0,
+            lkvth0: 0.0,
+            wkvth0: 0.0,
+            pkvth0: 0.0,
+            llodvth: 0.0,
+            wlodvth: 0.0,
+
+            // Well Proximity Effect
+            wpemod: 0,
+            scref: 1e-6,
+            web: 0.0,
+            wec: 0.0,
+            kvth0we: 0.0,
+            k2we: 0.0,
+            ku0we: 0.0,
+
+            // Instance Layout
+            sd: 0.0,
+            nf: 1.0,
+            sc: 0.0,
+            sca: 0.0,
+            scb: 0.0,
+            scc: 0.0,
 
             // Gate Tunneling
             jtss: 0.0,
@@ -433,12 +630,42 @@ impl BsimParams {
             nsti: 1.0,
             vtss: 10.0,
             vtsd: 10.0,
+            igcmod: 0,
+            aigc: 1.36e-2,
+            bigc: 1.71e-3,
+            cigc: 0.075,
+            nigc: 1.0,
+            pigcd: 1.0,
+            toxref: 3.0e-9,
+            ntox: 1.0,
+
+            // GIDL / GISL
+            agidl: 0.0,
+            bgidl: 2.3e9,
+            cgidl: 0.5,
+            egidl: 0.8,
+
+            // Binning
+            binunit: 1,
+            binning: HashMap::new(),
 
             // Enhanced Output Conductance
             pvag: 0.0,
             fprout: 0.0,
             pdits: 0.0,
             delta: 0.01,
+
+            // EKV 2.6
+            kp: 0.0,
+            phi: 0.7,
+            theta: 0.0,
+            ucrit: 2.0e6,
+            xj: 1.0e-7,
+            leta: 0.1,
+            weta: 0.25,
+            tcv: 1.0e-3,
+            bex: -1.5,
+            ucex: 0.8,
         }
     }
 
@@ -453,6 +680,9 @@ impl BsimParams {
         // BSIM4 PMOS adjustments
         params.voff = 0.1;       // Positive for PMOS
         params.ute0 = -1.0;      // Different temp coefficient for PMOS
+        params.aigc = 9.8e-3;    // Hole tunneling (valence band)
+        params.bigc = 7.59e-4;
+        params.cigc = 0.03;
         params
     }
 
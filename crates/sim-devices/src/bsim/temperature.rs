@@ -0,0 +1,114 @@
+//! BSIM3 Temperature Dependence
+//!
+//! Model parameters are extracted at TNOM. At another device temperature T
+//! the BSIM3v3 temperature equations shift them before evaluation:
+//!
+//! ```text
+//! VSAT(T) = VSAT - AT * (T/TNOM - 1)
+//! UA(T)   = UA + UA1 * (T/TNOM - 1)         (likewise UB / UB1, UC / UC1)
+//! RDSW(T) = RDSW + PRT * (T/TNOM - 1)
+//! ```
+//!
+//! The source/drain junctions follow the diode laws: the saturation
+//! currents scale with the band gap and XTI,
+//!
+//! ```text
+//! IS(T) = IS * exp((Eg(TNOM)/Vt(TNOM) - Eg(T)/Vt(T) + XTI * ln(T/TNOM)) / NJ)
+//! Eg(T) = 1.16 - 7.02e-4 * T^2 / (T + 1108)
+//! ```
+//!
+//! while the built-in potentials drop linearly (TPB, TPBSW) and the
+//! zero-bias capacitances grow linearly (TCJ, TCJSW) with T - TNOM.
+//!
+//! U0 (UTE) and Vth (KT1, KT1L, KT2) are temperature dependent too; they
+//! are evaluated inside the mobility and threshold equations, which take
+//! the temperature directly, so [`BsimParams::at_temperature`] leaves them
+//! unchanged.
+//!
+//! ## References
+//! - BSIM3v3.3 Manual, Appendix A (temperature effects)
+
+use super::params::{BsimParams, K_BOLTZMANN, Q_ELECTRON};
+
+/// Silicon band gap [eV] at temperature `temp` [K]
+pub(crate) fn band_gap(temp: f64) -> f64 {
+    1.16 - 7.02e-4 * temp * temp / (temp + 1108.0)
+}
+
+impl BsimParams {
+    /// Return the parameters adjusted from TNOM to the device
+    /// temperature `temp` [K]
+    pub fn at_temperature(&self, temp: f64) -> BsimParams {
+        let mut p = self.clone();
+        if temp <= 0.0 || temp == self.tnom {
+            return p;
+        }
+        let ratio = temp / self.tnom - 1.0;
+        let delta_t = temp - self.tnom;
+
+        // Channel
+        p.vsat = (self.vsat - self.at * ratio).max(1e3);
+        p.ua = self.ua + self.ua1 * ratio;
+        p.ub = self.ub + self.ub1 * ratio;
+        p.uc = self.uc + self.uc1 * ratio;
+        p.rdsw = (self.rdsw + self.prt * ratio).max(0.0);
+
+        // Source/drain junctions
+        let vt_nom = K_BOLTZMANN * self.tnom / Q_ELECTRON;
+        let vt = K_BOLTZMANN * temp / Q_ELECTRON;
+        let arg = band_gap(self.tnom) / vt_nom - band_gap(temp) / vt + self.xti * (temp / self.tnom).ln();
+        let factor = (arg / self.nj.max(0.1)).exp();
+        p.is = self.is * factor;
+        p.js = self.js * factor;
+        p.jssw = self.jssw * factor;
+        p.pb = (self.pb - self.tpb * delta_t).max(0.01);
+        p.pbsw = (self.pbsw - self.tpbsw * delta_t).max(0.01);
+        p.cj = (self.cj * (1.0 + self.tcj * delta_t)).max(0.0);
+        p.cjsw = (self.cjsw * (1.0 + self.tcjsw * delta_t)).max(0.0);
+        p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nominal_temperature_is_identity() {
+        let params = BsimParams::nmos_default();
+        let same = params.at_temperature(params.tnom);
+        assert_eq!(same.vsat, params.vsat);
+        assert_eq!(same.is, params.is);
+    }
+
+    #[test]
+    fn test_vsat_and_mobility_coefficients() {
+        let params = BsimParams::nmos_default();
+        let hot = params.at_temperature(2.0 * params.tnom);
+        assert!((hot.vsat - (params.vsat - params.at)).abs() < 1e-9);
+        assert!((hot.ua - (params.ua + params.ua1)).abs() < 1e-24);
+        assert!((hot.ub - (params.ub + params.ub1)).abs() < 1e-34);
+        assert!((hot.uc - (params.uc + params.uc1)).abs() < 1e-24);
+    }
+
+    #[test]
+    fn test_junction_saturation_current_rises_steeply() {
+        let params = BsimParams::nmos_default();
+        let hot = params.at_temperature(params.tnom + 10.0);
+        // Roughly doubles every 5-6 K around room temperature
+        let ratio = hot.is / params.is;
+        assert!(ratio > 2.5 && ratio < 5.0, "ratio = {}", ratio);
+    }
+
+    #[test]
+    fn test_junction_potential_and_capacitance_coefficients() {
+        let params = BsimParams {
+            tpb: 1.5e-3,
+            tcj: 1e-3,
+            ..BsimParams::nmos_default()
+        };
+        let hot = params.at_temperature(params.tnom + 100.0);
+        assert!((hot.pb - (params.pb - 0.15)).abs() < 1e-12);
+        assert!((hot.cj - params.cj * 1.1).abs() < 1e-12 * params.cj);
+    }
+}
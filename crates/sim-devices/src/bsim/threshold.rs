@@ -24,7 +24,7 @@ use super::params::{BsimParams, EPSILON_SI, Q_ELECTRON, K_BOLTZMANN};
 
 /// Built-in potential for silicon (default PHI) [V]
 /// PHI = 2 * Vt * ln(Na / ni) ≈ 0.7V for typical doping
-const PHI_DEFAULT: f64 = 0.7;
+pub(crate) const PHI_DEFAULT: f64 = 0.7;
 
 
 /// Calculate threshold voltage with all BSIM3 effects
@@ -55,6 +55,17 @@ pub struct BsimOutput {
     pub region: MosRegion,
     /// Effective threshold voltage [V]
     pub vth_eff: f64,
+    /// Gate-source capacitance, Meyer intrinsic plus overlap [F]
+    pub cgs: f64,
+    /// Gate-drain capacitance, Meyer intrinsic plus overlap [F]
+    pub cgd: f64,
+    /// Gate-bulk capacitance, Meyer intrinsic plus overlap [F]
+    pub cgb: f64,
+    /// Terminal charges of the charge-based C-V model [C]; they sum to zero
+    pub qd: f64,
+    pub qg: f64,
+    pub qs: f64,
+    pub qb: f64,
 }
 
 /// Internal state for BSIM calculations
@@ -82,7 +93,10 @@ pub struct BsimState {
 
 /// Extended output from BSIM4 DC evaluation
 ///
-/// Includes additional currents for substrate and gate tunneling.
+/// Includes additional currents for substrate, gate tunneling and GIDL.
+/// The Igc partition and GIDL/GISL currents are given in the external
+/// terminal frame (source and drain as connected, PMOS currents negative);
+/// they have no conductances here, callers linearize them numerically.
 #[derive(Debug, Clone, Default)]
 pub struct Bsim4Output {
     /// Base BSIM output (Ids, gm, gds, gmbs, ieq, region, vth_eff)
@@ -102,6 +116,15 @@ pub struct Bsim4Output {
     /// Gate-drain tunneling conductance [S]
     pub gigd: f64,
 
+    /// Gate-to-channel tunneling into the source end [A] (IGCMOD = 1)
+    pub igcs: f64,
+    /// Gate-to-channel tunneling into the drain end [A] (IGCMOD = 1)
+    pub igcd: f64,
+    /// Gate-induced drain leakage, drain to bulk [A]
+    pub igidl: f64,
+    /// Gate-induced source leakage, source to bulk [A]
+    pub igisl: f64,
+
     /// Effective mobility [cm^2/V/s]
     pub ueff: f64,
     /// Saturation voltage [V]
@@ -0,0 +1,125 @@
+//! Digital primitives and analog/digital bridges
+//!
+//! Behavioral two-valued logic elements in the XSPICE style, selected by the
+//! model type of an `A` instance:
+//!
+//! ```text
+//! d_inverter   A1 in out model                 out = !in
+//! d_nand       A2 in1 in2 ... out model        out = !(in1 & in2 & ...)
+//! d_dff        A3 d clk q model                q = d on the rising clock edge
+//! adc_bridge   A4 ain dout model               analog -> digital, with hysteresis
+//! dac_bridge   A5 din aout model               digital -> analog level
+//! ```
+//!
+//! Output changes are delayed by RISE_DELAY / FALL_DELAY. The ADC bridge
+//! switches high above IN_HIGH and low below IN_LOW and holds its level in
+//! between; the DAC bridge drives OUT_LOW or OUT_HIGH.
+
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigitalKind {
+    Inverter,
+    Nand,
+    Dff,
+    AdcBridge,
+    DacBridge,
+}
+
+/// Digital element parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigitalParams {
+    pub kind: DigitalKind,
+    /// Delay of a low-to-high output change [s]
+    pub rise_delay: f64,
+    /// Delay of a high-to-low output change [s]
+    pub fall_delay: f64,
+    /// Initial flip-flop state
+    pub ic: bool,
+    /// ADC bridge: input below which the output goes low [V]
+    pub in_low: f64,
+    /// ADC bridge: input above which the output goes high [V]
+    pub in_high: f64,
+    /// DAC bridge: output level for logic 0 [V]
+    pub out_low: f64,
+    /// DAC bridge: output level for logic 1 [V]
+    pub out_high: f64,
+}
+
+/// Build digital element parameters; `None` if the model type (`type`)
+/// is not a digital element
+pub fn build_digital_params(params: &HashMap<String, String>) -> Option<DigitalParams> {
+    let kind = match params.get("type")?.to_ascii_lowercase().as_str() {
+        "d_inverter" => DigitalKind::Inverter,
+        "d_nand" => DigitalKind::Nand,
+        "d_dff" => DigitalKind::Dff,
+        "adc_bridge" => DigitalKind::AdcBridge,
+        "dac_bridge" => DigitalKind::DacBridge,
+        _ => return None,
+    };
+    let get = |key: &str| params.get(key).and_then(|value| parse_number(value));
+    // A zero delay would let events propagate endlessly at one instant
+    let delay = |key: &str| get(key).filter(|d| *d > 0.0).unwrap_or(1e-9);
+    Some(DigitalParams {
+        kind,
+        rise_delay: delay("rise_delay"),
+        fall_delay: delay("fall_delay"),
+        ic: get("ic").is_some_and(|ic| ic >= 0.5),
+        in_low: get("in_low").unwrap_or(1.0),
+        in_high: get("in_high").unwrap_or(2.0),
+        out_low: get("out_low").unwrap_or(0.0),
+        out_high: get("out_high").unwrap_or(1.0),
+    })
+}
+
+impl DigitalParams {
+    /// Whether `count` terminals fit the element
+    pub fn accepts_terminals(&self, count: usize) -> bool {
+        match self.kind {
+            DigitalKind::Nand => count >= 3,
+            DigitalKind::Dff => count == 3,
+            _ => count == 2,
+        }
+    }
+
+    /// Delay of an output change to `level`
+    pub fn delay(&self, level: bool) -> f64 {
+        if level {
+            self.rise_delay
+        } else {
+            self.fall_delay
+        }
+    }
+
+    /// Combinational output of a gate
+    pub fn gate_output(&self, inputs: &[bool]) -> bool {
+        match self.kind {
+            DigitalKind::Inverter => !inputs.first().copied().unwrap_or(false),
+            DigitalKind::Nand => !inputs.iter().all(|input| *input),
+            _ => false,
+        }
+    }
+
+    /// ADC bridge level for the input voltage `v`, holding `previous`
+    /// inside the hysteresis band
+    pub fn adc_level(&self, v: f64, previous: bool) -> bool {
+        if v >= self.in_high {
+            true
+        } else if v <= self.in_low {
+            false
+        } else {
+            previous
+        }
+    }
+
+    /// DAC bridge output voltage for a logic level
+    pub fn dac_voltage(&self, level: bool) -> f64 {
+        if level {
+            self.out_high
+        } else {
+            self.out_low
+        }
+    }
+}
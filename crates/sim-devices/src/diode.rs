@@ -1,5 +1,242 @@
+//! Junction diode: SPICE level-1 model
+//!
+//! The DC characteristic is the ideal exponential (IS, N) plus a second
+//! exponential for reverse breakdown (BV, IBV, NBV). The breakdown term is
+//! always present rather than switched in below -BV, so the current and its
+//! derivative are smooth everywhere, and [`DiodeParams::limit_voltage`]
+//! applies pnjlim to the breakdown junction as well, which lets Zener
+//! regulators converge from a zero initial guess. Charge storage
+//! combines the depletion charge of the junction capacitance (CJO, VJ, M,
+//! FC) with the diffusion charge TT * Id. IS follows the SPICE temperature
+//! law through EG and XTI relative to TNOM. The series resistance RS is
+//! exposed in [`DiodeParams`] and realised by the caller.
+//!
+//! A model of type `varactor` (`.model cv varactor ...` or `type=varactor`)
+//! keeps only the junction capacitance: it conducts no DC current, so it
+//! acts as a voltage-dependent capacitor C(V) for tuning circuits.
+//!
+//! Optoelectronic variants share the same junction. A photodiode adds a
+//! photocurrent flowing from cathode to anode inside the device, IPH plus
+//! RESP times the optical power at an optical input node. An LED emits an
+//! optical power ETA * Id, which the caller drives onto an optical output
+//! node so that it can feed a photodiode (optocoupler macro-models).
+
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+use crate::junction::{depletion_capacitance, depletion_charge, pnjlim, vcrit};
+
 #[derive(Debug, Clone)]
 pub struct DiodeDevice {
     pub name: String,
     pub model: String,
 }
+
+/// Boltzmann constant over electron charge [V/K]
+const BOLTZMANN_OVER_Q: f64 = 8.617333262e-5;
+
+/// Diode model parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiodeParams {
+    /// Saturation current [A]
+    pub is: f64,
+    /// Emission coefficient
+    pub n: f64,
+    /// Ohmic series resistance [Ohm]
+    pub rs: f64,
+    /// Zero-bias junction capacitance [F]
+    pub cjo: f64,
+    /// Junction potential [V]
+    pub vj: f64,
+    /// Grading coefficient
+    pub m: f64,
+    /// Forward-bias depletion capacitance coefficient
+    pub fc: f64,
+    /// Transit time [s]
+    pub tt: f64,
+    /// Reverse breakdown voltage [V]; `None` disables breakdown
+    pub bv: Option<f64>,
+    /// Current at the breakdown voltage [A]
+    pub ibv: f64,
+    /// Breakdown emission coefficient
+    pub nbv: f64,
+    /// Activation energy [eV]
+    pub eg: f64,
+    /// Saturation current temperature exponent
+    pub xti: f64,
+    /// Parameter measurement temperature [K]
+    pub tnom: f64,
+    /// Capacitance-only junction (no DC conduction)
+    pub varactor: bool,
+    /// Constant photocurrent, cathode to anode [A]
+    pub iph: f64,
+    /// Photocurrent per unit optical input power [A/W]
+    pub resp: f64,
+    /// Emitted optical power per unit forward current [W/A]
+    pub eta: f64,
+}
+
+impl Default for DiodeParams {
+    fn default() -> Self {
+        Self {
+            is: 1e-14,
+            n: 1.0,
+            rs: 0.0,
+            cjo: 0.0,
+            vj: 1.0,
+            m: 0.5,
+            fc: 0.5,
+            tt: 0.0,
+            bv: None,
+            ibv: 1e-3,
+            nbv: 1.0,
+            eg: 1.11,
+            xti: 3.0,
+            tnom: 300.15,
+            varactor: false,
+            iph: 0.0,
+            resp: 1.0,
+            eta: 1.0,
+        }
+    }
+}
+
+/// Build diode parameters from a netlist parameter map
+///
+/// Accepts the usual aliases (CJ0 / CJ for CJO, PB for VJ); the grading
+/// coefficient M is read as MJ only, because `m` is the instance multiplier
+/// in the merged parameter map. TNOM is given in degrees Celsius as in SPICE.
+pub fn build_diode_params(params: &HashMap<String, String>) -> DiodeParams {
+    let get = |keys: &[&str]| keys.iter().find_map(|key| params.get(*key).and_then(|value| parse_number(value)));
+    let defaults = DiodeParams::default();
+    DiodeParams {
+        is: get(&["is"]).unwrap_or(defaults.is),
+        n: get(&["n", "nj"]).unwrap_or(defaults.n),
+        rs: get(&["rs"]).unwrap_or(defaults.rs),
+        cjo: get(&["cjo", "cj0", "cj"]).unwrap_or(defaults.cjo),
+        vj: get(&["vj", "pb"]).unwrap_or(defaults.vj),
+        m: get(&["mj"]).unwrap_or(defaults.m),
+        fc: get(&["fc"]).unwrap_or(defaults.fc),
+        tt: get(&["tt"]).unwrap_or(defaults.tt),
+        bv: get(&["bv"]).filter(|bv| *bv > 0.0),
+        ibv: get(&["ibv"]).filter(|ibv| *ibv > 0.0).unwrap_or(defaults.ibv),
+        nbv: get(&["nbv"]).filter(|nbv| *nbv > 0.0).or(get(&["n", "nj"])).unwrap_or(defaults.nbv),
+        eg: get(&["eg"]).unwrap_or(defaults.eg),
+        xti: get(&["xti"]).unwrap_or(defaults.xti),
+        tnom: get(&["tnom"]).map(|celsius| celsius + 273.15).unwrap_or(defaults.tnom),
+        varactor: params.get("type").is_some_and(|t| t.eq_ignore_ascii_case("varactor")),
+        iph: get(&["iph"]).unwrap_or(defaults.iph),
+        resp: get(&["resp", "responsivity"]).unwrap_or(defaults.resp),
+        eta: get(&["eta"]).unwrap_or(defaults.eta),
+    }
+}
+
+impl DiodeParams {
+    /// Scale by the area factor: currents, capacitances and the optical
+    /// collection multiply, the series resistance divides
+    pub fn with_area(mut self, area: f64) -> Self {
+        if area > 0.0 && area != 1.0 {
+            self.is *= area;
+            self.ibv *= area;
+            self.cjo *= area;
+            self.iph *= area;
+            self.resp *= area;
+            self.rs /= area;
+        }
+        self
+    }
+
+    /// Adjust IS to the device temperature `temp` [K]:
+    /// IS(T) = IS * (T/TNOM)^(XTI/N) * exp(EG / (N k/q) * (1/TNOM - 1/T))
+    pub fn at_temperature(mut self, temp: f64) -> Self {
+        if temp > 0.0 && temp != self.tnom {
+            let ratio = temp / self.tnom;
+            let arg = self.eg / (self.n * BOLTZMANN_OVER_Q) * (1.0 / self.tnom - 1.0 / temp);
+            self.is *= ratio.powf(self.xti / self.n) * arg.exp();
+        }
+        self
+    }
+
+    /// Effective breakdown voltage: the knee of `IS * exp(-(V + BV') / NBV Vt)`
+    /// is shifted so that the current at V = -BV equals IBV
+    pub fn breakdown_voltage(&self, vt: f64) -> Option<f64> {
+        let nbvt = self.nbv * vt;
+        self.bv.map(|bv| {
+            if self.ibv > self.is {
+                bv - nbvt * (self.ibv / self.is).ln()
+            } else {
+                bv
+            }
+        })
+    }
+
+    /// SPICE diode voltage limiting: pnjlim on the forward junction, and on
+    /// the mirrored voltage -(V + BV') once the new voltage is in breakdown
+    pub fn limit_voltage(&self, vnew: f64, vold: f64, vt: f64) -> f64 {
+        if self.varactor {
+            return vnew;
+        }
+        if let Some(xbv) = self.breakdown_voltage(vt) {
+            let nbvt = self.nbv * vt;
+            if vnew < (-xbv + 10.0 * nbvt).min(0.0) {
+                let v = pnjlim(-(vnew + xbv), -(vold + xbv), nbvt, vcrit(self.is, nbvt));
+                return -(v + xbv);
+            }
+        }
+        let nvt = self.n * vt;
+        pnjlim(vnew, vold, nvt, vcrit(self.is, nvt))
+    }
+
+    /// Photocurrent for an optical input power `light` [W]
+    pub fn photocurrent(&self, light: f64) -> f64 {
+        self.iph + self.resp * light
+    }
+
+    /// Emitted optical power for a junction current `id` [W]; reverse current emits nothing
+    pub fn optical_power(&self, id: f64) -> f64 {
+        self.eta * id.max(0.0)
+    }
+
+    /// Junction charge and capacitance at `vd`: depletion charge plus the
+    /// diffusion charge TT * Id (`out` from [`evaluate_diode`] at the same `vd`)
+    pub fn charge(&self, vd: f64, out: &DiodeOutput) -> (f64, f64) {
+        let q = depletion_charge(self.cjo, vd, self.vj, self.m, self.fc) + self.tt * out.id;
+        let c = depletion_capacitance(self.cjo, vd, self.vj, self.m, self.fc) + self.tt * out.gd;
+        (q, c)
+    }
+}
+
+/// Output from diode DC evaluation: junction current (anode to cathode)
+/// and its derivative with respect to the junction voltage
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiodeOutput {
+    pub id: f64,
+    pub gd: f64,
+}
+
+/// Evaluate the diode junction current
+///
+/// # Arguments
+/// * `params` - Model parameters (area and temperature already applied)
+/// * `vd` - Junction voltage, anode minus cathode [V]
+/// * `vt` - Thermal voltage kT/q [V]
+pub fn evaluate_diode(params: &DiodeParams, vd: f64, vt: f64) -> DiodeOutput {
+    if params.varactor {
+        return DiodeOutput::default();
+    }
+    let nvt = params.n * vt;
+    let is = params.is;
+    let e = (vd / nvt).exp();
+    let mut out = DiodeOutput {
+        id: is * (e - 1.0),
+        gd: is * e / nvt,
+    };
+    if let Some(xbv) = params.breakdown_voltage(vt) {
+        // Offset by the zero-bias value so that Id(0) = 0
+        let nbvt = params.nbv * vt;
+        let eb = (-(xbv + vd) / nbvt).exp();
+        out.id -= is * (eb - (-xbv / nbvt).exp());
+        out.gd += is * eb / nbvt;
+    }
+    out
+}
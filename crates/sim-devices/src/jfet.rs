@@ -0,0 +1,216 @@
+//! Junction field-effect transistor: Shichman-Hodges model
+//!
+//! The channel current follows the SPICE square-law JFET model (VTO, BETA,
+//! LAMBDA) and is symmetric in drain and source: for Vds < 0 the device is
+//! evaluated in inverse mode with the roles of Vgs and Vgd exchanged. The
+//! gate-source and gate-drain junctions are ideal diodes (IS, N) with
+//! depletion capacitances CGS / CGD. Drain and source resistances RD / RS
+//! are realised by the caller as series resistors to internal nodes.
+//!
+//! All voltages and currents are in the N-channel frame; for P-channel
+//! devices the caller negates junction voltages before evaluation and
+//! negates the resulting currents afterwards.
+
+use std::collections::HashMap;
+
+use crate::bjt::junction_current;
+use crate::bsim::parse_number;
+
+/// JFET channel polarity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JfetType {
+    #[default]
+    Njf,
+    Pjf,
+}
+
+impl JfetType {
+    /// +1 for N-channel, -1 for P-channel
+    pub fn sign(self) -> f64 {
+        match self {
+            JfetType::Njf => 1.0,
+            JfetType::Pjf => -1.0,
+        }
+    }
+}
+
+/// Shichman-Hodges JFET model parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct JfetParams {
+    pub jfet_type: JfetType,
+    /// Threshold (pinch-off) voltage [V]
+    pub vto: f64,
+    /// Transconductance parameter [A/V^2]
+    pub beta: f64,
+    /// Channel-length modulation [1/V]
+    pub lambda: f64,
+    /// Gate junction saturation current [A]
+    pub is: f64,
+    /// Gate junction emission coefficient
+    pub n: f64,
+    /// Drain / source ohmic resistances [Ohm]
+    pub rd: f64,
+    pub rs: f64,
+    /// Zero-bias gate-source / gate-drain junction capacitances [F]
+    pub cgs: f64,
+    pub cgd: f64,
+    /// Gate junction potential [V]
+    pub pb: f64,
+    /// Forward-bias depletion capacitance coefficient
+    pub fc: f64,
+}
+
+impl Default for JfetParams {
+    fn default() -> Self {
+        Self {
+            jfet_type: JfetType::Njf,
+            vto: -2.0,
+            beta: 1e-4,
+            lambda: 0.0,
+            is: 1e-14,
+            n: 1.0,
+            rd: 0.0,
+            rs: 0.0,
+            cgs: 0.0,
+            cgd: 0.0,
+            pb: 1.0,
+            fc: 0.5,
+        }
+    }
+}
+
+impl JfetParams {
+    /// Scale by the area factor: currents and capacitances multiply,
+    /// resistances divide
+    pub fn with_area(mut self, area: f64) -> Self {
+        if area > 0.0 && area != 1.0 {
+            self.beta *= area;
+            self.is *= area;
+            self.cgs *= area;
+            self.cgd *= area;
+            self.rd /= area;
+            self.rs /= area;
+        }
+        self
+    }
+}
+
+/// Build JFET parameters from a netlist parameter map
+///
+/// The polarity comes from the model type (`njf` / `pjf`) stored under
+/// `type`; unspecified parameters keep their SPICE defaults.
+pub fn build_jfet_params(params: &HashMap<String, String>) -> JfetParams {
+    let get = |key: &str| params.get(key).and_then(|value| parse_number(value));
+    let defaults = JfetParams::default();
+    let jfet_type = match params.get("type").map(|t| t.to_ascii_lowercase()) {
+        Some(t) if t == "pjf" => JfetType::Pjf,
+        _ => JfetType::Njf,
+    };
+    JfetParams {
+        jfet_type,
+        vto: get("vto").unwrap_or(defaults.vto),
+        beta: get("beta").unwrap_or(defaults.beta),
+        lambda: get("lambda").unwrap_or(defaults.lambda),
+        is: get("is").unwrap_or(defaults.is),
+        n: get("n").unwrap_or(defaults.n),
+        rd: get("rd").unwrap_or(defaults.rd),
+        rs: get("rs").unwrap_or(defaults.rs),
+        cgs: get("cgs").unwrap_or(defaults.cgs),
+        cgd: get("cgd").unwrap_or(defaults.cgd),
+        pb: get("pb").unwrap_or(defaults.pb),
+        fc: get("fc").unwrap_or(defaults.fc),
+    }
+}
+
+/// Output from JFET DC evaluation (N-channel frame)
+///
+/// `id` flows from drain to source through the channel; `igs` / `igd` flow
+/// from the gate into the source / drain junction. Derivatives are with
+/// respect to the junction voltages Vgs and Vgd.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct JfetOutput {
+    pub id: f64,
+    pub did_dvgs: f64,
+    pub did_dvgd: f64,
+    pub igs: f64,
+    pub ggs: f64,
+    pub igd: f64,
+    pub ggd: f64,
+}
+
+impl JfetOutput {
+    /// Small-signal transconductance gm = dId/dVgs at constant Vds
+    pub fn gm(&self) -> f64 {
+        self.did_dvgs + self.did_dvgd
+    }
+
+    /// Output conductance gds = dId/dVds at constant Vgs
+    pub fn gds(&self) -> f64 {
+        -self.did_dvgd
+    }
+}
+
+/// Forward-mode channel current and its derivatives (gm, gds) for Vds >= 0
+fn channel_current(params: &JfetParams, vgs: f64, vds: f64) -> (f64, f64, f64) {
+    let vgst = vgs - params.vto;
+    if vgst <= 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    let clm = 1.0 + params.lambda * vds;
+    let betap = params.beta * clm;
+    if vgst <= vds {
+        // Saturation
+        let id = betap * vgst * vgst;
+        (id, 2.0 * betap * vgst, params.lambda * params.beta * vgst * vgst)
+    } else {
+        // Linear region
+        let core = vds * (2.0 * vgst - vds);
+        (
+            betap * core,
+            2.0 * betap * vds,
+            2.0 * betap * (vgst - vds) + params.lambda * params.beta * core,
+        )
+    }
+}
+
+/// Evaluate the Shichman-Hodges JFET model
+///
+/// # Arguments
+/// * `params` - Model parameters (area already applied)
+/// * `vgs`, `vgd` - Gate junction voltages in the N-channel frame [V]
+/// * `vt` - Thermal voltage kT/q [V]
+pub fn evaluate_jfet(params: &JfetParams, vgs: f64, vgd: f64, vt: f64) -> JfetOutput {
+    evaluate_gate_fet(params, vgs, vgd, vt, |vgs, vds| channel_current(params, vgs, vds))
+}
+
+/// Shared evaluation of a symmetric gate-junction FET: ideal gate diodes
+/// plus a forward-mode channel characteristic `channel(vgs, vds) -> (id, gm, gds)`
+/// that is mirrored for Vds < 0
+pub(crate) fn evaluate_gate_fet(
+    params: &JfetParams,
+    vgs: f64,
+    vgd: f64,
+    vt: f64,
+    channel: impl Fn(f64, f64) -> (f64, f64, f64),
+) -> JfetOutput {
+    let (igs, ggs) = junction_current(params.is, vgs, params.n, vt);
+    let (igd, ggd) = junction_current(params.is, vgd, params.n, vt);
+    let vds = vgs - vgd;
+    let (id, did_dvgs, did_dvgd) = if vds >= 0.0 {
+        let (id, gm, gds) = channel(vgs, vds);
+        (id, gm + gds, -gds)
+    } else {
+        // Inverse mode: the drain acts as the source
+        let (id, gm, gds) = channel(vgd, -vds);
+        (-id, gds, -(gm + gds))
+    };
+    JfetOutput {
+        id,
+        did_dvgs,
+        did_dvgd,
+        igs,
+        ggs,
+        igd,
+        ggd,
+    }
+}
@@ -3,4 +3,15 @@ pub mod passive;
 pub mod source;
 pub mod diode;
 pub mod mosfet;
+pub mod bjt;
+pub mod jfet;
+pub mod mesfet;
+pub mod switch;
+pub mod memristor;
+pub mod thermistor;
+pub mod power;
+pub mod digital;
+pub mod trnoise;
+pub mod junction;
+pub mod vbic;
 pub mod bsim;
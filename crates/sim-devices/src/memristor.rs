@@ -0,0 +1,166 @@
+//! Memristor: HP linear ion-drift model with a window function
+//!
+//! The device is a doped/undoped TiO2 stack whose boundary moves with the
+//! charge that has passed through it. The normalized width of the doped
+//! region x = w / D in [0, 1] is the internal state:
+//!
+//! ```text
+//! R(x)  = RON * x + ROFF * (1 - x)
+//! dx/dt = UV * RON / D^2 * i * f(x, i)
+//! ```
+//!
+//! The window f keeps x inside [0, 1] and models the slowing drift near the
+//! boundaries:
+//!
+//! ```text
+//! Joglekar: f(x)    = 1 - (2x - 1)^(2p)
+//! Biolek:   f(x, i) = 1 - (x - stp(-i))^(2p)      stp(i) = 1 for i >= 0
+//! ```
+//!
+//! Biolek's window depends on the current direction, so a device driven to
+//! a boundary can leave it again when the current reverses (Joglekar's
+//! window traps it there). [`MemristorParams::step`] integrates the state
+//! with backward Euler over one time step, which makes the terminal
+//! current an implicit function of the terminal voltage; the returned
+//! conductance is its total derivative, for the Newton linearization.
+//!
+//! ## References
+//! - D. B. Strukov et al., "The missing memristor found", Nature 453, 2008
+//! - Z. Biolek, D. Biolek, V. Biolkova, "SPICE model of memristor with
+//!   nonlinear dopant drift", Radioengineering 18(2), 2009
+
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+
+/// Window function limiting the state drift near the boundaries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemristorWindow {
+    /// Linear drift, f = 1 (x is only clamped to [0, 1])
+    None,
+    /// f(x) = 1 - (2x - 1)^(2p)
+    Joglekar,
+    /// f(x, i) = 1 - (x - stp(-i))^(2p)
+    Biolek,
+}
+
+/// Memristor model parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemristorParams {
+    /// Resistance of the fully doped device (x = 1) [Ohm]
+    pub ron: f64,
+    /// Resistance of the undoped device (x = 0) [Ohm]
+    pub roff: f64,
+    /// Film thickness [m]
+    pub d: f64,
+    /// Dopant mobility [m^2/(V s)]
+    pub uv: f64,
+    /// Window exponent
+    pub p: f64,
+    /// Initial state
+    pub x0: f64,
+    pub window: MemristorWindow,
+}
+
+impl Default for MemristorParams {
+    fn default() -> Self {
+        Self {
+            ron: 100.0,
+            roff: 16e3,
+            d: 10e-9,
+            uv: 1e-14,
+            p: 1.0,
+            x0: 0.1,
+            window: MemristorWindow::Biolek,
+        }
+    }
+}
+
+/// Build memristor parameters from a netlist parameter map
+///
+/// `window` is `biolek` (default), `joglekar` or `none`.
+pub fn build_memristor_params(params: &HashMap<String, String>) -> MemristorParams {
+    let get = |key: &str| params.get(key).and_then(|value| parse_number(value));
+    let defaults = MemristorParams::default();
+    let window = match params.get("window").map(|w| w.to_ascii_lowercase()).as_deref() {
+        Some("none") => MemristorWindow::None,
+        Some("joglekar") => MemristorWindow::Joglekar,
+        _ => MemristorWindow::Biolek,
+    };
+    MemristorParams {
+        ron: get("ron").filter(|r| *r > 0.0).unwrap_or(defaults.ron),
+        roff: get("roff").filter(|r| *r > 0.0).unwrap_or(defaults.roff),
+        d: get("d").filter(|d| *d > 0.0).unwrap_or(defaults.d),
+        uv: get("uv").unwrap_or(defaults.uv),
+        p: get("p").filter(|p| *p > 0.0).unwrap_or(defaults.p),
+        x0: get("x0").unwrap_or(defaults.x0).clamp(0.0, 1.0),
+        window,
+    }
+}
+
+/// Result of one backward-Euler state step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemristorStep {
+    /// State at the end of the step
+    pub x: f64,
+    /// Terminal current [A]
+    pub current: f64,
+    /// d(current)/d(voltage) including the state response [S]
+    pub conductance: f64,
+}
+
+impl MemristorParams {
+    /// Resistance at state `x`
+    pub fn resistance(&self, x: f64) -> f64 {
+        self.ron * x + self.roff * (1.0 - x)
+    }
+
+    /// Window value and its derivative with respect to x; only the sign of
+    /// the current `i` matters
+    pub fn window(&self, x: f64, i: f64) -> (f64, f64) {
+        let two_p = 2.0 * self.p;
+        // f = 1 - |u|^(2p), df/du = -2p sign(u) |u|^(2p-1)
+        let (u, du_dx) = match self.window {
+            MemristorWindow::None => return (1.0, 0.0),
+            MemristorWindow::Joglekar => (2.0 * x - 1.0, 2.0),
+            MemristorWindow::Biolek => (if i <= 0.0 { x - 1.0 } else { x }, 1.0),
+        };
+        let f = 1.0 - u.abs().powf(two_p);
+        let df = -two_p * u.signum() * u.abs().powf(two_p - 1.0) * du_dx;
+        (f, df)
+    }
+
+    /// Advance the state from `x_prev` over `dt` with the voltage `v`
+    /// applied at the end of the step (backward Euler)
+    pub fn step(&self, x_prev: f64, v: f64, dt: f64) -> MemristorStep {
+        let k = self.uv * self.ron / (self.d * self.d);
+        let dr_dx = self.ron - self.roff;
+        let mut x = x_prev.clamp(0.0, 1.0);
+        let mut dx_dv = 0.0;
+        // Solve g(x) = x - x_prev - dt k v / R(x) f(x) = 0
+        for _ in 0..50 {
+            let r = self.resistance(x);
+            let (f, df) = self.window(x, v);
+            let g = x - x_prev - dt * k * v / r * f;
+            let dg_dx = 1.0 - dt * k * v * (df / r - f * dr_dx / (r * r));
+            let dg_dv = -dt * k * f / r;
+            dx_dv = -dg_dv / dg_dx;
+            let next = (x - g / dg_dx).clamp(0.0, 1.0);
+            let converged = (next - x).abs() < 1e-12;
+            x = next;
+            if converged {
+                break;
+            }
+        }
+        // A state pinned at a boundary no longer responds to the voltage
+        if x <= 0.0 || x >= 1.0 {
+            dx_dv = 0.0;
+        }
+        let r = self.resistance(x);
+        MemristorStep {
+            x,
+            current: v / r,
+            conductance: 1.0 / r - v / (r * r) * dr_dx * dx_dv,
+        }
+    }
+}
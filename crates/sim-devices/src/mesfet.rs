@@ -0,0 +1,158 @@
+//! GaAs MESFET / HEMT: Statz and Curtice models
+//!
+//! The MESFET shares its topology with the JFET (Schottky gate diodes,
+//! RD / RS, CGS / CGD), so [`MesfetParams`] embeds a [`JfetParams`] for the
+//! common parameters and adds the channel-specific ALPHA (tanh saturation)
+//! and B (doping tail). `level=1` (default) selects the Statz model, whose
+//! cubic polynomial approximates the tanh knee; `level=2` selects the
+//! Curtice quadratic model with an exact tanh.
+//!
+//! [`FetModel`] lets the caller stamp JFETs and MESFETs through one path.
+
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+use crate::jfet::{build_jfet_params, evaluate_gate_fet, evaluate_jfet, JfetOutput, JfetParams, JfetType};
+
+/// MESFET drain current formulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MesfetLevel {
+    /// Statz et al. (SPICE3 level 1)
+    #[default]
+    Statz,
+    /// Curtice quadratic
+    Curtice,
+}
+
+/// MESFET model parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct MesfetParams {
+    /// Parameters shared with the JFET (VTO BETA LAMBDA IS N RD RS CGS CGD PB FC)
+    pub fet: JfetParams,
+    /// Saturation voltage parameter [1/V]
+    pub alpha: f64,
+    /// Doping tail extending parameter [1/V] (Statz only)
+    pub b: f64,
+    pub level: MesfetLevel,
+}
+
+impl Default for MesfetParams {
+    fn default() -> Self {
+        Self {
+            fet: JfetParams {
+                beta: 2.5e-3,
+                ..JfetParams::default()
+            },
+            alpha: 2.0,
+            b: 0.3,
+            level: MesfetLevel::Statz,
+        }
+    }
+}
+
+impl MesfetParams {
+    /// Scale by the area factor (see [`JfetParams::with_area`])
+    pub fn with_area(mut self, area: f64) -> Self {
+        self.fet = self.fet.with_area(area);
+        self
+    }
+}
+
+/// Build MESFET parameters from a netlist parameter map
+///
+/// The polarity comes from the model type (`nmf` / `pmf`) stored under
+/// `type`; the common parameters use the JFET defaults except BETA.
+pub fn build_mesfet_params(params: &HashMap<String, String>) -> MesfetParams {
+    let get = |key: &str| params.get(key).and_then(|value| parse_number(value));
+    let defaults = MesfetParams::default();
+    let mut fet = build_jfet_params(params);
+    fet.beta = get("beta").unwrap_or(defaults.fet.beta);
+    fet.jfet_type = match params.get("type").map(|t| t.to_ascii_lowercase()) {
+        Some(t) if t == "pmf" => JfetType::Pjf,
+        _ => JfetType::Njf,
+    };
+    let level = match get("level").map(|l| l.round() as i64) {
+        Some(2) => MesfetLevel::Curtice,
+        _ => MesfetLevel::Statz,
+    };
+    MesfetParams {
+        fet,
+        alpha: get("alpha").unwrap_or(defaults.alpha),
+        b: get("b").unwrap_or(defaults.b),
+        level,
+    }
+}
+
+/// Forward-mode channel current and its derivatives (gm, gds) for Vds >= 0
+fn channel_current(params: &MesfetParams, vgs: f64, vds: f64) -> (f64, f64, f64) {
+    let vgst = vgs - params.fet.vto;
+    if vgst <= 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    let beta = params.fet.beta;
+    let lambda = params.fet.lambda;
+    let clm = 1.0 + lambda * vds;
+    match params.level {
+        MesfetLevel::Statz => {
+            let denom = 1.0 + params.b * vgst;
+            let core = beta * vgst * vgst / denom;
+            let dcore = beta * vgst * (2.0 + params.b * vgst) / (denom * denom);
+            // (1 - (1 - alpha vds / 3)^3) below the knee, 1 above
+            let k = 1.0 - params.alpha * vds / 3.0;
+            let (knee, dknee) = if k > 0.0 {
+                (1.0 - k * k * k, params.alpha * k * k)
+            } else {
+                (1.0, 0.0)
+            };
+            (
+                core * knee * clm,
+                dcore * knee * clm,
+                core * (dknee * clm + knee * lambda),
+            )
+        }
+        MesfetLevel::Curtice => {
+            let core = beta * vgst * vgst;
+            let th = (params.alpha * vds).tanh();
+            (
+                core * clm * th,
+                2.0 * beta * vgst * clm * th,
+                core * (lambda * th + clm * params.alpha * (1.0 - th * th)),
+            )
+        }
+    }
+}
+
+/// Evaluate the MESFET model
+///
+/// # Arguments
+/// * `params` - Model parameters (area already applied)
+/// * `vgs`, `vgd` - Gate junction voltages in the N-channel frame [V]
+/// * `vt` - Thermal voltage kT/q [V]
+pub fn evaluate_mesfet(params: &MesfetParams, vgs: f64, vgd: f64, vt: f64) -> JfetOutput {
+    evaluate_gate_fet(&params.fet, vgs, vgd, vt, |vgs, vds| channel_current(params, vgs, vds))
+}
+
+/// Gate-junction FET model: JFET (`J`) or MESFET (`Z`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum FetModel {
+    Jfet(JfetParams),
+    Mesfet(MesfetParams),
+}
+
+impl FetModel {
+    /// Parameters common to both devices (polarity, gate diodes, RD/RS, CGS/CGD)
+    pub fn common(&self) -> &JfetParams {
+        match self {
+            FetModel::Jfet(p) => p,
+            FetModel::Mesfet(p) => &p.fet,
+        }
+    }
+
+    /// Evaluate the device in the N-channel frame
+    pub fn evaluate(&self, vgs: f64, vgd: f64, vt: f64) -> JfetOutput {
+        match self {
+            FetModel::Jfet(p) => evaluate_jfet(p, vgs, vgd, vt),
+            FetModel::Mesfet(p) => evaluate_mesfet(p, vgs, vgd, vt),
+        }
+    }
+}
@@ -1,3 +1,36 @@
+//! Passive devices: resistor and capacitor models
+//!
+//! A resistor takes its value from the instance line or, in the
+//! semiconductor form `R1 a b rmod L=10u W=1u`, from the sheet resistance
+//! of its `.model rmod R` card:
+//!
+//! ```text
+//! R = RSH * (L - SHORT) / (W - NARROW)       (W defaults to DEFW)
+//! ```
+//!
+//! A model without RSH (or an instance without L) falls back to the model
+//! parameter R. The resistance then follows the quadratic temperature law
+//!
+//! ```text
+//! R(T) = R * (1 + TC1 * (T - TNOM) + TC2 * (T - TNOM)^2)
+//! ```
+//!
+//! where TC1 / TC2 may be given on the instance or on the model (the
+//! instance wins) and TNOM is in degrees Celsius as in SPICE.
+//!
+//! A layout-style capacitor `C1 a b cmod W=5u L=5u` likewise takes its
+//! value from the area and perimeter of its `.model cmod C` card:
+//!
+//! ```text
+//! C = CJ * (L - SHORT) * (W - NARROW) + 2 * CJSW * ((L - SHORT) + (W - NARROW))
+//! ```
+//!
+//! falling back to the model parameter C when no L is given.
+
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+
 #[derive(Debug, Clone)]
 pub enum PassiveKind {
     Resistor,
@@ -10,3 +43,161 @@ pub struct PassiveDevice {
     pub name: String,
     pub kind: PassiveKind,
 }
+
+/// Resistor parameters, instance and model merged
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResistorParams {
+    /// Sheet resistance [Ohm/square] (0 = not a semiconductor resistor)
+    pub rsh: f64,
+    /// Drawn length [m]
+    pub l: Option<f64>,
+    /// Drawn width [m]; `None` uses DEFW
+    pub w: Option<f64>,
+    /// Default width [m]
+    pub defw: f64,
+    /// Width narrowing due to side etching [m]
+    pub narrow: f64,
+    /// Length shortening [m]
+    pub short: f64,
+    /// Default resistance from the model card [Ohm]
+    pub r: Option<f64>,
+    /// First-order temperature coefficient [1/K]
+    pub tc1: f64,
+    /// Second-order temperature coefficient [1/K^2]
+    pub tc2: f64,
+    /// Parameter measurement temperature [K]
+    pub tnom: f64,
+}
+
+impl Default for ResistorParams {
+    fn default() -> Self {
+        Self {
+            rsh: 0.0,
+            l: None,
+            w: None,
+            defw: 1e-5,
+            narrow: 0.0,
+            short: 0.0,
+            r: None,
+            tc1: 0.0,
+            tc2: 0.0,
+            tnom: 300.15,
+        }
+    }
+}
+
+/// Build resistor parameters from a netlist parameter map
+///
+/// The model default resistance is read from `r` or `res`.
+pub fn build_resistor_params(params: &HashMap<String, String>) -> ResistorParams {
+    let get = |keys: &[&str]| keys.iter().find_map(|key| params.get(*key).and_then(|value| parse_number(value)));
+    let defaults = ResistorParams::default();
+    ResistorParams {
+        rsh: get(&["rsh"]).unwrap_or(defaults.rsh),
+        l: get(&["l"]),
+        w: get(&["w"]),
+        defw: get(&["defw"]).unwrap_or(defaults.defw),
+        narrow: get(&["narrow"]).unwrap_or(defaults.narrow),
+        short: get(&["short"]).unwrap_or(defaults.short),
+        r: get(&["r", "res"]),
+        tc1: get(&["tc1"]).unwrap_or(defaults.tc1),
+        tc2: get(&["tc2"]).unwrap_or(defaults.tc2),
+        tnom: get(&["tnom"]).map(|celsius| celsius + 273.15).unwrap_or(defaults.tnom),
+    }
+}
+
+impl ResistorParams {
+    /// Resistance at TNOM: the instance value if given, otherwise the
+    /// geometric value from RSH, otherwise the model R
+    pub fn nominal(&self, value: Option<f64>) -> Option<f64> {
+        if value.is_some() {
+            return value;
+        }
+        match self.l {
+            Some(l) if self.rsh > 0.0 => {
+                let w = self.w.unwrap_or(self.defw) - self.narrow;
+                let l = l - self.short;
+                (w > 0.0 && l > 0.0).then(|| self.rsh * l / w)
+            }
+            _ => self.r,
+        }
+    }
+
+    /// Scale a nominal resistance to the device temperature `temp` [K]
+    pub fn at_temperature(&self, r: f64, temp: f64) -> f64 {
+        let dt = temp - self.tnom;
+        r * (1.0 + self.tc1 * dt + self.tc2 * dt * dt)
+    }
+}
+
+/// Capacitor parameters, instance and model merged
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapacitorParams {
+    /// Junction bottom capacitance per area [F/m^2]
+    pub cj: f64,
+    /// Junction sidewall capacitance per length [F/m]
+    pub cjsw: f64,
+    /// Drawn length [m]
+    pub l: Option<f64>,
+    /// Drawn width [m]; `None` uses DEFW
+    pub w: Option<f64>,
+    /// Default width [m]
+    pub defw: f64,
+    /// Width narrowing due to side etching [m]
+    pub narrow: f64,
+    /// Length shortening [m]
+    pub short: f64,
+    /// Default capacitance from the model card [F]
+    pub c: Option<f64>,
+}
+
+impl Default for CapacitorParams {
+    fn default() -> Self {
+        Self {
+            cj: 0.0,
+            cjsw: 0.0,
+            l: None,
+            w: None,
+            defw: 1e-5,
+            narrow: 0.0,
+            short: 0.0,
+            c: None,
+        }
+    }
+}
+
+/// Build capacitor parameters from a netlist parameter map
+///
+/// The model default capacitance is read from `c` or `cap`.
+pub fn build_capacitor_params(params: &HashMap<String, String>) -> CapacitorParams {
+    let get = |keys: &[&str]| keys.iter().find_map(|key| params.get(*key).and_then(|value| parse_number(value)));
+    let defaults = CapacitorParams::default();
+    CapacitorParams {
+        cj: get(&["cj"]).unwrap_or(defaults.cj),
+        cjsw: get(&["cjsw"]).unwrap_or(defaults.cjsw),
+        l: get(&["l"]),
+        w: get(&["w"]),
+        defw: get(&["defw"]).unwrap_or(defaults.defw),
+        narrow: get(&["narrow"]).unwrap_or(defaults.narrow),
+        short: get(&["short"]).unwrap_or(defaults.short),
+        c: get(&["c", "cap"]),
+    }
+}
+
+impl CapacitorParams {
+    /// Capacitance: the instance value if given, otherwise the area and
+    /// perimeter value from CJ / CJSW, otherwise the model C
+    pub fn nominal(&self, value: Option<f64>) -> Option<f64> {
+        if value.is_some() {
+            return value;
+        }
+        match self.l {
+            Some(l) if self.cj > 0.0 || self.cjsw > 0.0 => {
+                let w = self.w.unwrap_or(self.defw) - self.narrow;
+                let l = l - self.short;
+                (w > 0.0 && l > 0.0).then_some(self.cj * l * w + 2.0 * self.cjsw * (l + w))
+            }
+            _ => self.c,
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Power switch macro models: vertical DMOS (VDMOS) and IGBT
+//!
+//! Both devices share a square-law channel with a smooth threshold tail:
+//!
+//! ```text
+//! Vov = KSUBTHRES * ln(1 + exp((Vgs - VTO) / KSUBTHRES))   (max(Vgs - VTO, 0) if KSUBTHRES = 0)
+//! Id  = KP/2 * Vov^2 * (1 + LAMBDA * Vds)                  Vds >= Vov
+//! Id  = KP * (Vov * Vds - Vds^2/2) * (1 + LAMBDA * Vds)    Vds <  Vov
+//! ```
+//!
+//! and the nonlinear Miller capacitance of the gate over the drift region,
+//! large (CGDMAX) while the drain is below the gate and collapsing to CGDMIN
+//! once the drift region depletes:
+//!
+//! ```text
+//! Cgd(Vdg) = CGDMIN + (CGDMAX - CGDMIN) * (1 - tanh(A * Vdg)) / 2
+//! ```
+//!
+//! The charge is the closed-form integral, so the capacitance is charge
+//! conserving. The gate-source capacitance CGS is constant.
+//!
+//! A VDMOS (`.model x vdmos`, `pchan` for a P-channel device) adds the body
+//! diode from source to drain (IS, N, CJO, VJ, MJ, TT, BV as in the diode
+//! model) and the series resistances RD / RS, which the caller realises.
+//!
+//! An IGBT (`.model x nigbt`) is the MOS channel driving the base of a wide
+//! base PNP whose emitter is the collector terminal. The intrinsic device has
+//! the terminals collector C, gate G, emitter E and the internal base B (the
+//! channel drain). The C-B junction current splits into the base part
+//! I/(1 + BF), which flows on through the channel, and the transport part
+//! BF/(1 + BF) * I straight to the emitter. The diffusion charge TT * I of
+//! that junction gives the turn-off current tail.
+
+use std::collections::HashMap;
+
+use crate::bsim::{parse_number, MosRegion};
+use crate::diode::{build_diode_params, evaluate_diode, DiodeParams};
+
+/// Square-law channel with a softplus threshold tail
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerChannel {
+    /// Threshold voltage [V]
+    pub vto: f64,
+    /// Transconductance parameter [A/V^2]
+    pub kp: f64,
+    /// Channel-length modulation [1/V]
+    pub lambda: f64,
+    /// Width of the threshold tail [V]; 0 gives a hard threshold
+    pub ksubthres: f64,
+}
+
+/// Channel current (drain to source) and its derivatives
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelOutput {
+    pub id: f64,
+    pub gm: f64,
+    pub gds: f64,
+    pub region: MosRegion,
+}
+
+impl PowerChannel {
+    /// Effective overdrive and its derivative with respect to Vgs
+    fn overdrive(&self, vgs: f64) -> (f64, f64) {
+        let v = vgs - self.vto;
+        if self.ksubthres <= 0.0 {
+            return if v > 0.0 { (v, 1.0) } else { (0.0, 0.0) };
+        }
+        let x = v / self.ksubthres;
+        if x > 40.0 {
+            (v, 1.0)
+        } else {
+            let e = x.exp();
+            (self.ksubthres * e.ln_1p(), e / (1.0 + e))
+        }
+    }
+
+    /// Current for a drain above the source (`vds` >= 0)
+    fn forward(&self, vgs: f64, vds: f64) -> ChannelOutput {
+        let (vov, dvov) = self.overdrive(vgs);
+        let clm = 1.0 + self.lambda * vds;
+        if vov <= 0.0 {
+            return ChannelOutput { id: 0.0, gm: 0.0, gds: 0.0, region: MosRegion::Cutoff };
+        }
+        if vds >= vov {
+            let i0 = 0.5 * self.kp * vov * vov;
+            ChannelOutput {
+                id: i0 * clm,
+                gm: self.kp * vov * dvov * clm,
+                gds: i0 * self.lambda,
+                region: MosRegion::Saturation,
+            }
+        } else {
+            let i0 = self.kp * (vov * vds - 0.5 * vds * vds);
+            ChannelOutput {
+                id: i0 * clm,
+                gm: self.kp * vds * dvov * clm,
+                gds: self.kp * (vov - vds) * clm + i0 * self.lambda,
+                region: MosRegion::Linear,
+            }
+        }
+    }
+
+    /// Drain-to-source current at (`vgs`, `vds`); for `vds` < 0 drain and
+    /// source swap roles. `gm` is dId/dVgs and `gds` dId/dVds in both cases.
+    pub fn evaluate(&self, vgs: f64, vds: f64) -> ChannelOutput {
+        if vds >= 0.0 {
+            return self.forward(vgs, vds);
+        }
+        let out = self.forward(vgs - vds, -vds);
+        ChannelOutput {
+            id: -out.id,
+            gm: -out.gm,
+            gds: out.gm + out.gds,
+            region: out.region,
+        }
+    }
+}
+
+/// SPICE drain-source voltage limiting (DEVlimvds): large steps are cut so
+/// that a saturated channel, whose output conductance may be zero, does not
+/// throw its drain node far away between Newton iterations. A negative
+/// `vnew` is limited as the mirrored voltage, drain and source swapped.
+pub fn limit_vds(vnew: f64, vold: f64) -> f64 {
+    if vnew < 0.0 {
+        return -limit_forward_vds(-vnew, -vold);
+    }
+    limit_forward_vds(vnew, vold)
+}
+
+fn limit_forward_vds(vnew: f64, vold: f64) -> f64 {
+    if vold >= 3.5 {
+        if vnew > vold {
+            vnew.min(3.0 * vold + 2.0)
+        } else if vnew < 3.5 {
+            vnew.max(2.0)
+        } else {
+            vnew
+        }
+    } else if vnew > vold {
+        vnew.min(4.0)
+    } else {
+        vnew.max(-0.5)
+    }
+}
+
+/// Gate-drain (Miller) capacitance as a function of Vdg
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MillerCapacitance {
+    /// Capacitance with the drift region depleted [F]
+    pub cgdmin: f64,
+    /// Capacitance with the drain below the gate [F]
+    pub cgdmax: f64,
+    /// Sharpness of the transition [1/V]
+    pub a: f64,
+}
+
+impl MillerCapacitance {
+    /// Small-signal capacitance at `vdg`
+    pub fn capacitance(&self, vdg: f64) -> f64 {
+        self.cgdmin + (self.cgdmax - self.cgdmin) * (1.0 - (self.a * vdg).tanh()) / 2.0
+    }
+
+    /// Charge on the drain side, the integral of the capacitance from 0 to `vdg`
+    pub fn charge(&self, vdg: f64) -> f64 {
+        let delta = self.cgdmax - self.cgdmin;
+        let tail = if self.a > 0.0 { ln_cosh(self.a * vdg) / self.a } else { 0.0 };
+        self.cgdmin * vdg + delta / 2.0 * (vdg - tail)
+    }
+}
+
+/// ln(cosh(x)) without overflow for large |x|
+fn ln_cosh(x: f64) -> f64 {
+    let x = x.abs();
+    x + (-2.0 * x).exp().ln_1p() - std::f64::consts::LN_2
+}
+
+/// Vertical power MOSFET parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct VdmosParams {
+    pub channel: PowerChannel,
+    pub miller: MillerCapacitance,
+    /// Gate-source capacitance [F]
+    pub cgs: f64,
+    /// Drain series resistance [Ohm]
+    pub rd: f64,
+    /// Source series resistance [Ohm]
+    pub rs: f64,
+    /// Body diode, anode at the source
+    pub body: DiodeParams,
+    pub pchannel: bool,
+}
+
+/// IGBT parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgbtParams {
+    pub channel: PowerChannel,
+    /// Gate-collector capacitance, realised between the gate and the base
+    pub miller: MillerCapacitance,
+    /// Gate-emitter capacitance [F]
+    pub cge: f64,
+    /// Current gain of the PNP
+    pub bf: f64,
+    /// Collector-base junction; TT sets the tail
+    pub junction: DiodeParams,
+}
+
+/// Whether a merged parameter map describes a VDMOS (`.model x vdmos`)
+pub fn is_vdmos(params: &HashMap<String, String>) -> bool {
+    params.get("type").is_some_and(|t| t.eq_ignore_ascii_case("vdmos"))
+}
+
+/// Whether a merged parameter map describes an IGBT (`.model x nigbt` or `igbt`)
+pub fn is_igbt(params: &HashMap<String, String>) -> bool {
+    params
+        .get("type")
+        .is_some_and(|t| t.eq_ignore_ascii_case("nigbt") || t.eq_ignore_ascii_case("igbt"))
+}
+
+fn build_channel(get: impl Fn(&[&str]) -> Option<f64>) -> (PowerChannel, MillerCapacitance) {
+    let cgdmax = get(&["cgdmax"]).unwrap_or(0.0);
+    let channel = PowerChannel {
+        vto: get(&["vto", "vth"]).unwrap_or(0.0),
+        kp: get(&["kp"]).unwrap_or(1.0),
+        lambda: get(&["lambda"]).unwrap_or(0.0),
+        ksubthres: get(&["ksubthres"]).unwrap_or(0.0).max(0.0),
+    };
+    let miller = MillerCapacitance {
+        cgdmin: get(&["cgdmin"]).unwrap_or(cgdmax),
+        cgdmax,
+        a: get(&["a"]).unwrap_or(1.0),
+    };
+    (channel, miller)
+}
+
+/// Build VDMOS parameters from a netlist parameter map
+///
+/// The body diode takes the diode keys; its series resistance is not modelled.
+pub fn build_vdmos_params(params: &HashMap<String, String>) -> VdmosParams {
+    let get = |keys: &[&str]| keys.iter().find_map(|key| params.get(*key).and_then(|value| parse_number(value)));
+    let (channel, miller) = build_channel(get);
+    let mut body = build_diode_params(params);
+    body.rs = 0.0;
+    VdmosParams {
+        channel,
+        miller,
+        cgs: get(&["cgs"]).unwrap_or(0.0),
+        rd: get(&["rd"]).unwrap_or(0.0),
+        rs: get(&["rs"]).unwrap_or(0.0),
+        body,
+        pchannel: params.contains_key("pchan"),
+    }
+}
+
+/// Build IGBT parameters from a netlist parameter map
+pub fn build_igbt_params(params: &HashMap<String, String>) -> IgbtParams {
+    let get = |keys: &[&str]| keys.iter().find_map(|key| params.get(*key).and_then(|value| parse_number(value)));
+    let (channel, miller) = build_channel(get);
+    let mut junction = build_diode_params(params);
+    junction.rs = 0.0;
+    IgbtParams {
+        channel,
+        miller,
+        cge: get(&["cge", "cgs"]).unwrap_or(0.0),
+        bf: get(&["bf"]).filter(|bf| *bf >= 0.0).unwrap_or(1.0),
+        junction,
+    }
+}
+
+impl VdmosParams {
+    /// +1 for an N-channel device, -1 for a P-channel one
+    pub fn sign(&self) -> f64 {
+        if self.pchannel {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Channel operating point at intrinsic voltages [d, g, s]
+    pub fn channel_output(&self, v: [f64; 3]) -> ChannelOutput {
+        let sign = self.sign();
+        let out = self.channel.evaluate(sign * (v[1] - v[2]), sign * (v[0] - v[2]));
+        ChannelOutput { id: sign * out.id, ..out }
+    }
+
+    /// Currents flowing into the intrinsic terminals [d, g, s]
+    pub fn currents(&self, v: [f64; 3], vt: f64) -> [f64; 3] {
+        let sign = self.sign();
+        let id = self.channel_output(v).id;
+        let ib = sign * evaluate_diode(&self.body, sign * (v[2] - v[0]), vt).id;
+        [id - ib, 0.0, ib - id]
+    }
+
+    /// Terminal charges [d, g, s]
+    pub fn charges(&self, v: [f64; 3], vt: f64) -> [f64; 3] {
+        let sign = self.sign();
+        let qgd = sign * self.miller.charge(sign * (v[0] - v[1]));
+        let qgs = self.cgs * (v[1] - v[2]);
+        let vb = sign * (v[2] - v[0]);
+        let (qb, _) = self.body.charge(vb, &evaluate_diode(&self.body, vb, vt));
+        let qb = sign * qb;
+        [qgd - qb, qgs - qgd, qb - qgs]
+    }
+}
+
+impl IgbtParams {
+    /// Currents flowing into the intrinsic terminals [c, g, e, b]
+    pub fn currents(&self, v: [f64; 4], vt: f64) -> [f64; 4] {
+        let [c, g, e, b] = v;
+        let ich = self.channel.evaluate(g - e, b - e).id;
+        let ij = evaluate_diode(&self.junction, c - b, vt).id;
+        let base = ij / (1.0 + self.bf);
+        [ij, 0.0, -(ij - base) - ich, ich - base]
+    }
+
+    /// Terminal charges [c, g, e, b]
+    pub fn charges(&self, v: [f64; 4], vt: f64) -> [f64; 4] {
+        let [c, g, e, b] = v;
+        let qgc = self.miller.charge(b - g);
+        let qge = self.cge * (g - e);
+        let (qj, _) = self.junction.charge(c - b, &evaluate_diode(&self.junction, c - b, vt));
+        [qj, qge - qgc, -qge, qgc - qj]
+    }
+}
@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+
 #[derive(Debug, Clone)]
 pub enum SourceKind {
     Voltage,
@@ -9,3 +13,208 @@ pub struct SourceDevice {
     pub name: String,
     pub kind: SourceKind,
 }
+
+/// Port element parameters
+///
+/// A port `P1 n+ n- port=1 z0=50 [DC v] [AC mag [phase]]` terminates its
+/// two nodes in the reference impedance Z0 and, when driven, behaves as a
+/// voltage source of the given DC / AC value in series with Z0 (the
+/// open-circuit voltage). S-parameter and noise-figure analyses identify
+/// ports by their number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortParams {
+    /// Port number (1-based); 0 when PORT= is missing
+    pub number: usize,
+    /// Reference impedance [Ohm]
+    pub z0: f64,
+}
+
+impl Default for PortParams {
+    fn default() -> Self {
+        Self { number: 0, z0: 50.0 }
+    }
+}
+
+/// Build port parameters from a netlist parameter map
+pub fn build_port_params(params: &HashMap<String, String>) -> PortParams {
+    let get = |key: &str| params.get(key).and_then(|value| parse_number(value));
+    let defaults = PortParams::default();
+    PortParams {
+        number: get("port")
+            .filter(|n| *n >= 1.0 && n.fract() == 0.0)
+            .map_or(defaults.number, |n| n as usize),
+        z0: get("z0").filter(|z| *z > 0.0).unwrap_or(defaults.z0),
+    }
+}
+
+/// Transient waveform of an independent source
+///
+/// ```text
+/// PULSE(V1 V2 [TD [TR [TF [PW [PER]]]]])
+/// PWL(T1 V1 T2 V2 ...)
+/// ```
+///
+/// A PULSE without PW / PER is a single step that stays at V2; zero TR / TF
+/// give ideal edges. A PWL source holds its first value before T1 and its
+/// last value after the final point. The corners of both waveforms are
+/// breakpoints the time step has to land on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceWaveform {
+    Pulse(Pulse),
+    Pwl(Vec<(f64, f64)>),
+}
+
+/// PULSE parameters; times in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pulse {
+    pub v1: f64,
+    pub v2: f64,
+    pub td: f64,
+    pub tr: f64,
+    pub tf: f64,
+    pub pw: f64,
+    pub per: f64,
+}
+
+/// Split a waveform argument list (whitespace or comma separated)
+fn parse_values(spec: &str) -> Option<Vec<f64>> {
+    spec.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(parse_number)
+        .collect()
+}
+
+/// Parse the `PULSE(...)` argument list; `None` without V1 / V2 or with
+/// negative times
+pub fn parse_pulse(spec: &str) -> Option<Pulse> {
+    let values = parse_values(spec)?;
+    let time = |idx: usize, default: f64| values.get(idx).copied().unwrap_or(default);
+    let pulse = Pulse {
+        v1: *values.first()?,
+        v2: *values.get(1)?,
+        td: time(2, 0.0),
+        tr: time(3, 0.0),
+        tf: time(4, 0.0),
+        pw: time(5, f64::INFINITY),
+        per: time(6, f64::INFINITY),
+    };
+    let times = [pulse.td, pulse.tr, pulse.tf, pulse.pw, pulse.per];
+    times.iter().all(|t| *t >= 0.0).then_some(pulse)
+}
+
+/// Parse the `PWL(...)` argument list; `None` unless it holds at least one
+/// time / value pair with non-decreasing times
+pub fn parse_pwl(spec: &str) -> Option<Vec<(f64, f64)>> {
+    let values = parse_values(spec)?;
+    if values.is_empty() || values.len() % 2 != 0 {
+        return None;
+    }
+    let points: Vec<(f64, f64)> = values.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+    points.windows(2).all(|pair| pair[0].0 <= pair[1].0).then_some(points)
+}
+
+/// Build the waveform of a source from its parameter map (`pulse` / `pwl`
+/// hold the argument list)
+pub fn build_source_waveform(params: &HashMap<String, String>) -> Option<SourceWaveform> {
+    if let Some(spec) = params.get("pulse") {
+        return parse_pulse(spec).map(SourceWaveform::Pulse);
+    }
+    params.get("pwl").and_then(|spec| parse_pwl(spec)).map(SourceWaveform::Pwl)
+}
+
+impl Pulse {
+    /// Corners within one period, relative to its start
+    fn corners(&self) -> [f64; 4] {
+        [0.0, self.tr, self.tr + self.pw, self.tr + self.pw + self.tf]
+    }
+
+    /// Start of the period containing `time` (never before TD)
+    fn period_start(&self, time: f64) -> f64 {
+        if !self.per.is_finite() || self.per <= 0.0 || time <= self.td {
+            return self.td;
+        }
+        self.td + ((time - self.td) / self.per).floor() * self.per
+    }
+
+    /// Value at `time`; a time within rounding of an ideal edge still sees
+    /// the value before the edge
+    pub fn value_at(&self, time: f64) -> f64 {
+        let start = self.period_start(time);
+        let [_, rise_end, fall_start, fall_end] = self.corners().map(|corner| start + corner);
+        if !is_after(time, start) {
+            self.v1
+        } else if time < rise_end {
+            self.v1 + (self.v2 - self.v1) * (time - start) / self.tr
+        } else if !is_after(time, fall_start) {
+            self.v2
+        } else if time < fall_end {
+            self.v2 + (self.v1 - self.v2) * (time - fall_start) / self.tf
+        } else {
+            self.v1
+        }
+    }
+
+    pub fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        let start = self.period_start(time);
+        let mut starts = vec![start];
+        if self.per.is_finite() && self.per > 0.0 {
+            starts.push(start + self.per);
+        }
+        starts
+            .into_iter()
+            .flat_map(|start| self.corners().map(|corner| start + corner))
+            .find(|t| t.is_finite() && is_after(*t, time))
+    }
+
+    /// Time from which the waveform repeats with PER, or holds its final
+    /// value when it is not periodic
+    pub fn periodic_from(&self) -> f64 {
+        if self.per.is_finite() && self.per > 0.0 {
+            self.td
+        } else if self.pw.is_finite() {
+            self.td + self.tr + self.pw + self.tf
+        } else {
+            self.td + self.tr
+        }
+    }
+}
+
+impl SourceWaveform {
+    /// Source value at `time`; on a jump (PULSE edge with zero TR / TF, PWL
+    /// points sharing a time) the value before the jump
+    pub fn value_at(&self, time: f64) -> f64 {
+        match self {
+            Self::Pulse(pulse) => pulse.value_at(time),
+            Self::Pwl(points) => {
+                let idx = points.partition_point(|(t, _)| is_after(time, *t));
+                match (idx.checked_sub(1).map(|i| points[i]), points.get(idx).copied()) {
+                    (Some((t0, v0)), Some((t1, v1))) => v0 + (v1 - v0) * (time - t0) / (t1 - t0),
+                    (Some((_, v)), None) | (None, Some((_, v))) => v,
+                    (None, None) => 0.0,
+                }
+            }
+        }
+    }
+
+    /// Earliest corner of the waveform strictly after `time`
+    pub fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        match self {
+            Self::Pulse(pulse) => pulse.next_breakpoint(time),
+            Self::Pwl(points) => points.iter().map(|(t, _)| *t).find(|t| is_after(*t, time)),
+        }
+    }
+
+    /// Time from which the waveform is periodic (PULSE with PER) or constant
+    pub fn periodic_from(&self) -> f64 {
+        match self {
+            Self::Pulse(pulse) => pulse.periodic_from(),
+            Self::Pwl(points) => points.last().map_or(0.0, |(t, _)| *t),
+        }
+    }
+}
+
+/// `t` lies after `time` by more than floating-point noise, so that a step
+/// that landed on a breakpoint neither sees it again nor jumps past it
+fn is_after(t: f64, time: f64) -> bool {
+    t - time > 1e-12 * time.abs().max(t.abs())
+}
@@ -0,0 +1,111 @@
+//! Controlled switches: voltage-controlled `S` and current-controlled `W`
+//!
+//! The switch conductance moves between 1/ROFF and 1/RON as the control
+//! quantity crosses the threshold. A hard step makes Newton iteration
+//! chatter, so the transition is smoothed: ln(g) follows a cubic
+//! smoothstep over a window around the threshold, which keeps g and dg/dc
+//! continuous.
+//!
+//! Following the common SPICE convention, a negative hysteresis (VH / IH)
+//! requests a stateless smooth transition over `[VT - |VH|, VT + |VH|]`.
+//! A positive hysteresis makes the switch remember its state: it turns on
+//! above VT + VH and off below VT - VH. Without history (DC analysis) the
+//! hysteresis band itself is used as the transition window.
+
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+
+/// What controls the switch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchControl {
+    /// Control voltage between two nodes (`S`, `.model sw`)
+    Voltage,
+    /// Current through a voltage source (`W`, `.model csw`)
+    Current,
+}
+
+impl SwitchControl {
+    /// Narrowest transition half-width, in the units of the control quantity
+    fn min_width(self) -> f64 {
+        match self {
+            SwitchControl::Voltage => 1e-3,
+            SwitchControl::Current => 1e-6,
+        }
+    }
+}
+
+/// Switch model parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchParams {
+    pub control: SwitchControl,
+    /// On resistance [Ohm]
+    pub ron: f64,
+    /// Off resistance [Ohm]
+    pub roff: f64,
+    /// Threshold (VT [V] or IT [A])
+    pub threshold: f64,
+    /// Hysteresis (VH [V] or IH [A]); negative selects a smooth transition
+    pub hysteresis: f64,
+}
+
+/// Build switch parameters from a netlist parameter map
+///
+/// Voltage switches read VT / VH, current switches IT / IH.
+pub fn build_switch_params(params: &HashMap<String, String>, control: SwitchControl) -> SwitchParams {
+    let get = |key: &str| params.get(key).and_then(|value| parse_number(value));
+    let (threshold, hysteresis) = match control {
+        SwitchControl::Voltage => ("vt", "vh"),
+        SwitchControl::Current => ("it", "ih"),
+    };
+    SwitchParams {
+        control,
+        ron: get("ron").filter(|r| *r > 0.0).unwrap_or(1.0),
+        roff: get("roff").filter(|r| *r > 0.0).unwrap_or(1e12),
+        threshold: get(threshold).unwrap_or(0.0),
+        hysteresis: get(hysteresis).unwrap_or(0.0),
+    }
+}
+
+impl SwitchParams {
+    /// Centre and half-width of the transition window for the given
+    /// previous state (`None` when there is no history)
+    fn window(&self, was_on: Option<bool>) -> (f64, f64) {
+        let min_width = self.control.min_width();
+        let vh = self.hysteresis;
+        if vh < 0.0 {
+            return (self.threshold, (-vh).max(min_width));
+        }
+        match was_on {
+            None => (self.threshold, vh.max(min_width)),
+            Some(false) => (self.threshold + vh, min_width),
+            Some(true) => (self.threshold - vh, min_width),
+        }
+    }
+
+    /// Switch conductance and its derivative with respect to the control
+    /// quantity
+    pub fn conductance(&self, control: f64, was_on: Option<bool>) -> (f64, f64) {
+        let (centre, width) = self.window(was_on);
+        let (gon, goff) = (1.0 / self.ron, 1.0 / self.roff);
+        let t = (control - (centre - width)) / (2.0 * width);
+        if t <= 0.0 {
+            return (goff, 0.0);
+        }
+        if t >= 1.0 {
+            return (gon, 0.0);
+        }
+        let span = gon.ln() - goff.ln();
+        let s = t * t * (3.0 - 2.0 * t);
+        let ds = 6.0 * t * (1.0 - t) / (2.0 * width);
+        let g = (goff.ln() + span * s).exp();
+        (g, g * span * ds)
+    }
+
+    /// State after an accepted time point: on when the control is past the
+    /// centre of the active transition window
+    pub fn next_state(&self, control: f64, was_on: Option<bool>) -> bool {
+        let (centre, _) = self.window(was_on);
+        control > centre
+    }
+}
@@ -0,0 +1,79 @@
+//! Thermistor: temperature-dependent resistor (NTC / PTC)
+//!
+//! The resistance follows one of two laws of the absolute temperature T:
+//!
+//! ```text
+//! beta:            R(T) = R0 * exp(BETA * (1/T - 1/T0))
+//! Steinhart-Hart:  1/T  = A + B * ln(R) + C * ln(R)^3
+//! ```
+//!
+//! A positive BETA gives an NTC device, a negative one a PTC device. The
+//! Steinhart-Hart form is used when A and B are given; it is inverted in
+//! closed form for R. T0 is given in degrees Celsius as in SPICE.
+
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+
+/// Resistance law of a thermistor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThermistorLaw {
+    /// R0 [Ohm] at T0 [K], BETA [K]
+    Beta { r0: f64, t0: f64, beta: f64 },
+    /// Steinhart-Hart coefficients A [1/K], B [1/K], C [1/K]
+    SteinhartHart { a: f64, b: f64, c: f64 },
+}
+
+/// Thermistor model parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermistorParams {
+    pub law: ThermistorLaw,
+}
+
+/// Build thermistor parameters from a netlist parameter map
+///
+/// R0 may also be written R25 (resistance at 25 C), BETA also B25.
+pub fn build_thermistor_params(params: &HashMap<String, String>) -> ThermistorParams {
+    let get = |keys: &[&str]| keys.iter().find_map(|key| params.get(*key).and_then(|value| parse_number(value)));
+    let law = match (get(&["a"]), get(&["b"])) {
+        (Some(a), Some(b)) => ThermistorLaw::SteinhartHart {
+            a,
+            b,
+            c: get(&["c"]).unwrap_or(0.0),
+        },
+        _ => ThermistorLaw::Beta {
+            r0: get(&["r0", "r25"]).unwrap_or(10e3),
+            t0: get(&["t0"]).unwrap_or(25.0) + 273.15,
+            beta: get(&["beta", "b25"]).unwrap_or(3950.0),
+        },
+    };
+    ThermistorParams { law }
+}
+
+impl ThermistorParams {
+    /// Resistance and its temperature derivative dR/dT at `temp` [K]
+    pub fn evaluate(&self, temp: f64) -> (f64, f64) {
+        match self.law {
+            ThermistorLaw::Beta { r0, t0, beta } => {
+                let r = r0 * (beta * (1.0 / temp - 1.0 / t0)).exp();
+                (r, -beta / (temp * temp) * r)
+            }
+            ThermistorLaw::SteinhartHart { a, b, c } => {
+                let y = steinhart_hart_log_resistance(a, b, c, temp);
+                let r = y.exp();
+                // d(1/T) = (B + 3 C y^2) dy
+                (r, -r / (temp * temp * (b + 3.0 * c * y * y)))
+            }
+        }
+    }
+}
+
+/// Solve C y^3 + B y + (A - 1/T) = 0 for y = ln(R)
+fn steinhart_hart_log_resistance(a: f64, b: f64, c: f64, temp: f64) -> f64 {
+    if c == 0.0 {
+        return (1.0 / temp - a) / b;
+    }
+    let x = (a - 1.0 / temp) / c;
+    let root = ((b / (3.0 * c)).powi(3) + x * x / 4.0).sqrt();
+    (root - x / 2.0).cbrt() - (root + x / 2.0).cbrt()
+}
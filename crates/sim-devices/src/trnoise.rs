@@ -0,0 +1,187 @@
+//! Transient noise source
+//!
+//! `V1 n+ n- [DC v] TRNOISE(NA NT [NALPHA [NAMP]]) [seed=N]` adds a random
+//! sample sequence to the source value. A new sample is drawn every NT
+//! seconds and held until the next one; sample k applies on
+//! (k·NT, (k+1)·NT], and the source carries no noise at t <= 0 (the
+//! operating point). Each sample is
+//!
+//! ```text
+//! n_k = NA · w_k + NAMP · f_k
+//! ```
+//!
+//! where `w_k` is white Gaussian noise of unit variance and `f_k` is
+//! 1/f^NALPHA noise (0 < NALPHA <= 2) obtained by filtering a second
+//! Gaussian stream with a truncated fractional-integration filter (Kasdin).
+//! The sequence is fully determined by the seed, so runs are reproducible;
+//! without SEED= the seed is derived from the instance name.
+
+use std::collections::HashMap;
+
+use crate::bsim::parse_number;
+
+/// Length of the 1/f shaping filter; sets the lowest shaped frequency to
+/// about 1 / (FLICKER_TAPS · NT)
+pub const FLICKER_TAPS: usize = 512;
+
+/// Transient noise parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrNoiseParams {
+    /// White noise rms amplitude [V or A]
+    pub na: f64,
+    /// Time between samples [s]
+    pub nt: f64,
+    /// 1/f exponent
+    pub nalpha: f64,
+    /// 1/f noise amplitude [V or A]
+    pub namp: f64,
+    pub seed: u64,
+}
+
+/// Parse the `TRNOISE(...)` argument list (whitespace or comma separated);
+/// `None` if NA / NT are missing or NT is not positive
+pub fn parse_trnoise(spec: &str, seed: u64) -> Option<TrNoiseParams> {
+    let values: Vec<f64> = spec
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(parse_number)
+        .collect::<Option<_>>()?;
+    let na = *values.first()?;
+    let nt = values.get(1).copied().filter(|nt| *nt > 0.0)?;
+    Some(TrNoiseParams {
+        na,
+        nt,
+        nalpha: values.get(2).copied().unwrap_or(0.0).clamp(0.0, 2.0),
+        namp: values.get(3).copied().unwrap_or(0.0),
+        seed,
+    })
+}
+
+/// Build the noise parameters of source `name` from its parameter map
+/// (`trnoise` holds the argument list, `seed` the optional seed)
+pub fn build_trnoise_params(name: &str, params: &HashMap<String, String>) -> Option<TrNoiseParams> {
+    let spec = params.get("trnoise")?;
+    let seed = params
+        .get("seed")
+        .and_then(|seed| seed.parse::<u64>().ok())
+        .unwrap_or_else(|| name_seed(name));
+    parse_trnoise(spec, seed)
+}
+
+/// FNV-1a hash of the lower-case instance name
+fn name_seed(name: &str) -> u64 {
+    name.to_ascii_lowercase()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Seeded Gaussian generator: SplitMix64 uniforms through Box-Muller; also
+/// drives the Monte Carlo parameter sampling
+#[derive(Debug, Clone)]
+pub struct Gaussian {
+    state: u64,
+    spare: Option<f64>,
+}
+
+impl Gaussian {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed, spare: None }
+    }
+
+    /// Uniform sample in (0, 1]
+    pub fn uniform(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        // In (0, 1], so ln() stays finite
+        ((z >> 11) as f64 + 1.0) / (1u64 << 53) as f64
+    }
+
+    /// Standard normal sample
+    pub fn normal(&mut self) -> f64 {
+        if let Some(value) = self.spare.take() {
+            return value;
+        }
+        let radius = (-2.0 * self.uniform().ln()).sqrt();
+        let angle = 2.0 * std::f64::consts::PI * self.uniform();
+        self.spare = Some(radius * angle.sin());
+        radius * angle.cos()
+    }
+}
+
+/// Sample sequence of one noise source; samples are generated on demand
+/// and kept, so any time can be revisited (rejected steps, retries)
+#[derive(Debug, Clone)]
+pub struct TrNoise {
+    pub params: TrNoiseParams,
+    white: Gaussian,
+    pink: Gaussian,
+    /// Kasdin filter h_0..h_{FLICKER_TAPS-1}
+    filter: Vec<f64>,
+    pink_inputs: Vec<f64>,
+    samples: Vec<f64>,
+}
+
+impl TrNoise {
+    pub fn new(params: TrNoiseParams) -> Self {
+        let mut filter = Vec::with_capacity(FLICKER_TAPS);
+        let mut h = 1.0;
+        for k in 0..FLICKER_TAPS {
+            if k > 0 {
+                h *= (k as f64 - 1.0 + params.nalpha / 2.0) / k as f64;
+            }
+            filter.push(h);
+        }
+        Self {
+            white: Gaussian::new(params.seed),
+            pink: Gaussian::new(params.seed ^ 0x5851_f42d_4c95_7f2d),
+            filter,
+            pink_inputs: Vec::new(),
+            samples: Vec::new(),
+            params,
+        }
+    }
+
+    /// Sample `k`
+    pub fn sample(&mut self, k: usize) -> f64 {
+        while self.samples.len() <= k {
+            let white = self.white.normal();
+            let flicker = if self.params.namp != 0.0 {
+                self.pink_inputs.push(self.pink.normal());
+                self.filter
+                    .iter()
+                    .zip(self.pink_inputs.iter().rev())
+                    .map(|(h, w)| h * w)
+                    .sum()
+            } else {
+                0.0
+            };
+            self.samples.push(self.params.na * white + self.params.namp * flicker);
+        }
+        self.samples[k]
+    }
+
+    /// Noise value at time `t`
+    pub fn value_at(&mut self, t: f64) -> f64 {
+        if t <= 0.0 {
+            return 0.0;
+        }
+        // A point exactly on a sample time still belongs to the previous interval
+        let k = (t / self.params.nt * (1.0 - 1e-12)).floor() as usize;
+        self.sample(k)
+    }
+
+    /// The first sample time after `t`
+    pub fn next_sample_time(&self, t: f64) -> f64 {
+        let nt = self.params.nt;
+        let next = ((t / nt).floor() + 1.0) * nt;
+        // Rounding may leave t just short of a sample time
+        if next - t <= nt * 1e-9 {
+            next + nt
+        } else {
+            next
+        }
+    }
+}
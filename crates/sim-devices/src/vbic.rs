@@ -0,0 +1,311 @@
+//! VBIC bipolar model: DC core without self-heating
+//!
+//! Selected with `.model ... npn level=4`. Compared with Gummel-Poon the
+//! Early effect follows the junction depletion charges (VEF/VER with
+//! PE/ME, PC/MC), the base current has separate ideal and non-ideal
+//! components, and the intrinsic collector resistance RCI uses the Kull
+//! epi-layer model (GAMM, VO, HRCF) to capture quasi-saturation.
+//!
+//! The intrinsic transistor returns a [`BjtOutput`] so that it stamps
+//! exactly like the Gummel-Poon core; the epi current between the external
+//! collector node `cx` and the intrinsic collector `ci` is evaluated
+//! separately by [`evaluate_kull`].
+
+use std::collections::HashMap;
+
+use crate::bjt::{junction_current, junction_exp, BjtOutput, BjtType};
+use crate::bsim::parse_number;
+
+/// VBIC model parameters (DC subset)
+///
+/// Zero for VEF/VER/IKF/IKR means "infinite" (effect disabled).
+#[derive(Debug, Clone, PartialEq)]
+pub struct VbicParams {
+    pub bjt_type: BjtType,
+    /// Transport saturation current [A]
+    pub is: f64,
+    /// Forward / reverse emission coefficients
+    pub nf: f64,
+    pub nr: f64,
+    /// Forward-bias depletion capacitance linearisation coefficient
+    pub fc: f64,
+    /// B-E built-in potential [V] and grading coefficient
+    pub pe: f64,
+    pub me: f64,
+    /// B-C built-in potential [V] and grading coefficient
+    pub pc: f64,
+    pub mc: f64,
+    /// Forward / reverse Early voltages [V]
+    pub vef: f64,
+    pub ver: f64,
+    /// Forward / reverse knee currents [A]
+    pub ikf: f64,
+    pub ikr: f64,
+    /// High-current roll-off exponent
+    pub nkf: f64,
+    /// Ideal and non-ideal B-E base current
+    pub ibei: f64,
+    pub nei: f64,
+    pub iben: f64,
+    pub nen: f64,
+    /// Ideal and non-ideal B-C base current
+    pub ibci: f64,
+    pub nci: f64,
+    pub ibcn: f64,
+    pub ncn: f64,
+    /// Extrinsic collector, extrinsic/intrinsic base and emitter resistances [Ohm]
+    pub rcx: f64,
+    pub rbx: f64,
+    pub rbi: f64,
+    pub re: f64,
+    /// Intrinsic collector (epi) resistance [Ohm]; 0 disables the Kull model
+    pub rci: f64,
+    /// Epi doping parameter
+    pub gamm: f64,
+    /// Epi drift saturation voltage [V]; 0 disables velocity saturation
+    pub vo: f64,
+    /// High-current RC factor
+    pub hrcf: f64,
+}
+
+impl Default for VbicParams {
+    fn default() -> Self {
+        Self {
+            bjt_type: BjtType::Npn,
+            is: 1e-16,
+            nf: 1.0,
+            nr: 1.0,
+            fc: 0.9,
+            pe: 0.75,
+            me: 0.33,
+            pc: 0.75,
+            mc: 0.33,
+            vef: 0.0,
+            ver: 0.0,
+            ikf: 0.0,
+            ikr: 0.0,
+            nkf: 0.5,
+            ibei: 1e-18,
+            nei: 1.0,
+            iben: 0.0,
+            nen: 2.0,
+            ibci: 1e-16,
+            nci: 1.0,
+            ibcn: 0.0,
+            ncn: 2.0,
+            rcx: 0.0,
+            rbx: 0.0,
+            rbi: 0.0,
+            re: 0.0,
+            rci: 0.0,
+            gamm: 0.0,
+            vo: 0.0,
+            hrcf: 1.0,
+        }
+    }
+}
+
+impl VbicParams {
+    /// Scale by the emitter area factor: currents multiply, resistances divide
+    pub fn with_area(mut self, area: f64) -> Self {
+        if area > 0.0 && area != 1.0 {
+            for current in [
+                &mut self.is,
+                &mut self.ikf,
+                &mut self.ikr,
+                &mut self.ibei,
+                &mut self.iben,
+                &mut self.ibci,
+                &mut self.ibcn,
+            ] {
+                *current *= area;
+            }
+            for resistance in [
+                &mut self.rcx,
+                &mut self.rbx,
+                &mut self.rbi,
+                &mut self.re,
+                &mut self.rci,
+            ] {
+                *resistance /= area;
+            }
+        }
+        self
+    }
+
+    /// Total series base resistance (RBX + RBI, no conductivity modulation)
+    pub fn rb(&self) -> f64 {
+        self.rbx + self.rbi
+    }
+}
+
+/// Build VBIC parameters from a netlist parameter map
+pub fn build_vbic_params(params: &HashMap<String, String>) -> VbicParams {
+    let get = |key: &str| params.get(key).and_then(|value| parse_number(value));
+    let d = VbicParams::default();
+    let bjt_type = match params.get("type").map(|t| t.to_ascii_lowercase()) {
+        Some(t) if t == "pnp" => BjtType::Pnp,
+        _ => BjtType::Npn,
+    };
+    VbicParams {
+        bjt_type,
+        is: get("is").unwrap_or(d.is),
+        nf: get("nf").unwrap_or(d.nf),
+        nr: get("nr").unwrap_or(d.nr),
+        fc: get("fc").unwrap_or(d.fc),
+        pe: get("pe").unwrap_or(d.pe),
+        me: get("me").unwrap_or(d.me),
+        pc: get("pc").unwrap_or(d.pc),
+        mc: get("mc").unwrap_or(d.mc),
+        vef: get("vef").unwrap_or(d.vef),
+        ver: get("ver").unwrap_or(d.ver),
+        ikf: get("ikf").unwrap_or(d.ikf),
+        ikr: get("ikr").unwrap_or(d.ikr),
+        nkf: get("nkf").unwrap_or(d.nkf),
+        ibei: get("ibei").unwrap_or(d.ibei),
+        nei: get("nei").unwrap_or(d.nei),
+        iben: get("iben").unwrap_or(d.iben),
+        nen: get("nen").unwrap_or(d.nen),
+        ibci: get("ibci").unwrap_or(d.ibci),
+        nci: get("nci").unwrap_or(d.nci),
+        ibcn: get("ibcn").unwrap_or(d.ibcn),
+        ncn: get("ncn").unwrap_or(d.ncn),
+        rcx: get("rcx").unwrap_or(d.rcx),
+        rbx: get("rbx").unwrap_or(d.rbx),
+        rbi: get("rbi").unwrap_or(d.rbi),
+        re: get("re").unwrap_or(d.re),
+        rci: get("rci").unwrap_or(d.rci),
+        gamm: get("gamm").unwrap_or(d.gamm),
+        vo: get("vo").unwrap_or(d.vo),
+        hrcf: get("hrcf").unwrap_or(d.hrcf),
+    }
+}
+
+/// Normalised depletion charge of a junction and its derivative
+///
+/// `qj = P/(1-M) * (1 - (1 - v/P)^(1-M))`, continued linearly above
+/// `FC * P` where the exact expression becomes singular.
+fn depletion_charge(v: f64, p: f64, m: f64, fc: f64) -> (f64, f64) {
+    let charge = |v: f64| p / (1.0 - m) * (1.0 - (1.0 - v / p).powf(1.0 - m));
+    let vlim = fc * p;
+    if v < vlim {
+        (charge(v), (1.0 - v / p).powf(-m))
+    } else {
+        let slope = (1.0 - fc).powf(-m);
+        (charge(vlim) + slope * (v - vlim), slope)
+    }
+}
+
+/// Evaluate the VBIC intrinsic transistor (NPN frame)
+///
+/// # Arguments
+/// * `params` - Model parameters (area already applied)
+/// * `vbei`, `vbci` - Intrinsic junction voltages [V]
+/// * `vt` - Thermal voltage kT/q [V]
+pub fn evaluate_vbic(params: &VbicParams, vbei: f64, vbci: f64, vt: f64) -> BjtOutput {
+    let (itzf, g_f) = junction_current(params.is, vbei, params.nf, vt);
+    let (itzr, g_r) = junction_current(params.is, vbci, params.nr, vt);
+
+    // Early effect through the depletion charges, smoothly clamped above 0
+    let inv_vef = if params.vef > 0.0 { 1.0 / params.vef } else { 0.0 };
+    let inv_ver = if params.ver > 0.0 { 1.0 / params.ver } else { 0.0 };
+    let (qje, dqje) = depletion_charge(vbei, params.pe, params.me, params.fc);
+    let (qjc, dqjc) = depletion_charge(vbci, params.pc, params.mc, params.fc);
+    let q1z = 1.0 + qje * inv_ver + qjc * inv_vef;
+    let root = ((q1z - 1e-4).powi(2) + 1e-8).sqrt();
+    let q1 = 0.5 * (root + q1z - 1e-4) + 1e-4;
+    let dq1_dq1z = 0.5 * ((q1z - 1e-4) / root + 1.0);
+    let dq1_dvbe = dq1_dq1z * dqje * inv_ver;
+    let dq1_dvbc = dq1_dq1z * dqjc * inv_vef;
+
+    // High-level injection
+    let inv_ikf = if params.ikf > 0.0 { 1.0 / params.ikf } else { 0.0 };
+    let inv_ikr = if params.ikr > 0.0 { 1.0 / params.ikr } else { 0.0 };
+    let q2 = itzf * inv_ikf + itzr * inv_ikr;
+    let arg = (1.0 + 4.0 * q2).max(1e-12);
+    let pow = arg.powf(params.nkf);
+    let dpow_dq2 = 4.0 * params.nkf * pow / arg;
+    let qb = 0.5 * q1 * (1.0 + pow);
+    let dqb_dvbe = 0.5 * (1.0 + pow) * dq1_dvbe + 0.5 * q1 * dpow_dq2 * g_f * inv_ikf;
+    let dqb_dvbc = 0.5 * (1.0 + pow) * dq1_dvbc + 0.5 * q1 * dpow_dq2 * g_r * inv_ikr;
+
+    let itz = (itzf - itzr) / qb;
+    let ditz_dvbe = (g_f - itz * dqb_dvbe) / qb;
+    let ditz_dvbc = (-g_r - itz * dqb_dvbc) / qb;
+
+    // Ideal and non-ideal base currents
+    let (ibe_i, gbe_i) = junction_current(params.ibei, vbei, params.nei, vt);
+    let (ibe_n, gbe_n) = junction_current(params.iben, vbei, params.nen, vt);
+    let (ibc_i, gbc_i) = junction_current(params.ibci, vbci, params.nci, vt);
+    let (ibc_n, gbc_n) = junction_current(params.ibcn, vbci, params.ncn, vt);
+    let ibc = ibc_i + ibc_n;
+    let gbc = gbc_i + gbc_n;
+
+    BjtOutput {
+        ic: itz - ibc,
+        ib: ibe_i + ibe_n + ibc,
+        dic_dvbe: ditz_dvbe,
+        dic_dvbc: ditz_dvbc - gbc,
+        dib_dvbe: gbe_i + gbe_n,
+        dib_dvbc: gbc,
+    }
+}
+
+/// Epi-layer current from the extrinsic collector `cx` to the intrinsic
+/// collector `ci` (NPN frame) and its derivatives
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KullOutput {
+    pub irci: f64,
+    /// Derivative with respect to Vbci = V(bi) - V(ci)
+    pub dirci_dvbci: f64,
+    /// Derivative with respect to Vbcx = V(bi) - V(cx)
+    pub dirci_dvbcx: f64,
+}
+
+/// Evaluate the Kull quasi-saturation model of the intrinsic collector
+///
+/// Requires `params.rci > 0`. With `gamm = 0` and `vo = 0` it reduces to a
+/// plain resistor RCI between `cx` and `ci`.
+pub fn evaluate_kull(params: &VbicParams, vbci: f64, vbcx: f64, vt: f64) -> KullOutput {
+    let vrci = vbci - vbcx;
+    let inv_rci = 1.0 / params.rci;
+
+    // Kbc = sqrt(1 + GAMM exp(Vbc / vt))
+    let k = |v: f64| {
+        let (e, de) = junction_exp(v, vt);
+        let kb = (1.0 + params.gamm * e).sqrt();
+        (kb, params.gamm * de / (2.0 * kb))
+    };
+    let (kbci, dkbci) = k(vbci);
+    let (kbcx, dkbcx) = k(vbcx);
+    let iohm = (vrci + vt * (kbci - kbcx - ((kbci + 1.0) / (kbcx + 1.0)).ln())) * inv_rci;
+    let diohm_dvbci = (1.0 + vt * dkbci * kbci / (kbci + 1.0)) * inv_rci;
+    let diohm_dvbcx = -(1.0 + vt * dkbcx * kbcx / (kbcx + 1.0)) * inv_rci;
+
+    if params.vo <= 0.0 {
+        return KullOutput {
+            irci: iohm,
+            dirci_dvbci: diohm_dvbci,
+            dirci_dvbcx: diohm_dvbcx,
+        };
+    }
+
+    // Velocity saturation in the epi drift region
+    let ivo = 1.0 / params.vo;
+    let ihrcf = if params.hrcf > 0.0 { 1.0 / params.hrcf } else { 0.0 };
+    let s = (vrci * vrci + 0.01).sqrt();
+    let denom = 1.0 + 0.5 * ivo * ihrcf * s;
+    let ddenom_dvrci = 0.5 * ivo * ihrcf * vrci / s;
+    let derf = ivo * iohm / denom;
+    let scale = (1.0 + derf * derf).sqrt();
+    let irci = iohm / scale;
+    let dirci = |diohm: f64, dvrci: f64| {
+        let dderf = ivo * (diohm * denom - iohm * ddenom_dvrci * dvrci) / (denom * denom);
+        diohm / scale - iohm * derf * dderf / (scale * scale * scale)
+    };
+    KullOutput {
+        irci,
+        dirci_dvbci: dirci(diohm_dvbci, 1.0),
+        dirci_dvbcx: dirci(diohm_dvbcx, -1.0),
+    }
+}
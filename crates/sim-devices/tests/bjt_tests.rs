@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use sim_devices::bjt::{build_bjt_params, evaluate_bjt, BjtType};
+use sim_devices::junction::{pnjlim, vcrit};
+use sim_devices::vbic::{build_vbic_params, evaluate_kull, evaluate_vbic};
+
+#[test]
+fn bjt_params_read_model_card() {
+    let mut params = HashMap::new();
+    params.insert("type".to_string(), "pnp".to_string());
+    params.insert("is".to_string(), "2f".to_string());
+    params.insert("vaf".to_string(), "75".to_string());
+    params.insert("rb".to_string(), "100".to_string());
+    let p = build_bjt_params(&params).with_area(2.0);
+    assert_eq!(p.bjt_type, BjtType::Pnp);
+    assert!((p.is - 4e-15).abs() < 1e-27);
+    assert!((p.vaf - 75.0).abs() < 1e-12);
+    assert!((p.rb - 50.0).abs() < 1e-12);
+    assert!((p.bf - 100.0).abs() < 1e-12);
+}
+
+#[test]
+fn bjt_derivatives_match_finite_differences() {
+    let mut params = HashMap::new();
+    for (key, value) in [("is", "1e-15"), ("bf", "120"), ("vaf", "60"), ("var", "20"), ("ikf", "10m"), ("ise", "1e-14")] {
+        params.insert(key.to_string(), value.to_string());
+    }
+    let p = build_bjt_params(&params);
+    let vt = 0.02585;
+    let (vbe, vbc) = (0.72, -2.0);
+    let out = evaluate_bjt(&p, vbe, vbc, vt);
+    let h = 1e-7;
+    let fd = |dvbe: f64, dvbc: f64| {
+        let hi = evaluate_bjt(&p, vbe + dvbe, vbc + dvbc, vt);
+        let lo = evaluate_bjt(&p, vbe - dvbe, vbc - dvbc, vt);
+        ((hi.ic - lo.ic) / (2.0 * h), (hi.ib - lo.ib) / (2.0 * h))
+    };
+    let (dic_dvbe, dib_dvbe) = fd(h, 0.0);
+    let (dic_dvbc, dib_dvbc) = fd(0.0, h);
+    for (analytic, numeric) in [
+        (out.dic_dvbe, dic_dvbe),
+        (out.dic_dvbc, dic_dvbc),
+        (out.dib_dvbe, dib_dvbe),
+        (out.dib_dvbc, dib_dvbc),
+    ] {
+        assert!(
+            (analytic - numeric).abs() <= 1e-4 * numeric.abs().max(1e-12),
+            "analytic {} vs numeric {}",
+            analytic,
+            numeric
+        );
+    }
+    // 高注入 (IKF) 使 beta 低于 BF
+    assert!(out.ic / out.ib < 120.0);
+    assert!(out.go() > 0.0);
+}
+
+#[test]
+fn vbic_derivatives_match_finite_differences() {
+    let mut params = HashMap::new();
+    for (key, value) in [
+        ("level", "4"),
+        ("is", "1e-16"),
+        ("vef", "40"),
+        ("ver", "4"),
+        ("ikf", "5m"),
+        ("ibei", "1e-18"),
+        ("iben", "1e-15"),
+        ("rci", "50"),
+        ("gamm", "1e-11"),
+        ("vo", "2"),
+    ] {
+        params.insert(key.to_string(), value.to_string());
+    }
+    let p = build_vbic_params(&params);
+    let vt = 0.02585;
+    let h = 1e-7;
+    let close = |analytic: f64, numeric: f64| {
+        assert!(
+            (analytic - numeric).abs() <= 1e-4 * numeric.abs().max(1e-12),
+            "analytic {} vs numeric {}",
+            analytic,
+            numeric
+        );
+    };
+
+    let (vbe, vbc) = (0.8, -1.0);
+    let out = evaluate_vbic(&p, vbe, vbc, vt);
+    let fd = |dvbe: f64, dvbc: f64| {
+        let hi = evaluate_vbic(&p, vbe + dvbe, vbc + dvbc, vt);
+        let lo = evaluate_vbic(&p, vbe - dvbe, vbc - dvbc, vt);
+        ((hi.ic - lo.ic) / (2.0 * h), (hi.ib - lo.ib) / (2.0 * h))
+    };
+    let (dic_dvbe, dib_dvbe) = fd(h, 0.0);
+    let (dic_dvbc, dib_dvbc) = fd(0.0, h);
+    close(out.dic_dvbe, dic_dvbe);
+    close(out.dic_dvbc, dic_dvbc);
+    close(out.dib_dvbe, dib_dvbe);
+    close(out.dib_dvbc, dib_dvbc);
+    assert!(out.go() > 0.0);
+
+    // 准饱和区: 本征 B-C 结正偏
+    let (vbci, vbcx) = (0.75, 0.2);
+    let kull = evaluate_kull(&p, vbci, vbcx, vt);
+    assert!(kull.irci > 0.0);
+    let irci = |dci: f64, dcx: f64| evaluate_kull(&p, vbci + dci, vbcx + dcx, vt).irci;
+    let d_ci = (irci(h, 0.0) - irci(-h, 0.0)) / (2.0 * h);
+    let d_cx = (irci(0.0, h) - irci(0.0, -h)) / (2.0 * h);
+    close(kull.dirci_dvbci, d_ci);
+    close(kull.dirci_dvbcx, d_cx);
+}
+
+#[test]
+fn pnjlim_compresses_large_forward_steps() {
+    let nvt = 0.02585;
+    let crit = vcrit(1e-14, nvt);
+    assert!(crit > 0.6 && crit < 0.8, "vcrit = {}", crit);
+    // 小步长和低于临界电压的值保持不变
+    assert_eq!(pnjlim(0.5, 0.0, nvt, crit), 0.5);
+    assert_eq!(pnjlim(0.71, 0.70, nvt, crit), 0.71);
+    let limited = pnjlim(3.0, 0.0, nvt, crit);
+    assert!(limited < 0.2, "limited = {}", limited);
+    let limited = pnjlim(3.0, 0.7, nvt, crit);
+    assert!(limited > 0.7 && limited < 0.9, "limited = {}", limited);
+}
@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+
+use sim_devices::digital::{build_digital_params, DigitalKind};
+
+fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn digital_params_select_kind_and_defaults() {
+    let p = build_digital_params(&params(&[("type", "D_NAND"), ("rise_delay", "2n"), ("fall_delay", "0")])).unwrap();
+    assert_eq!(p.kind, DigitalKind::Nand);
+    assert_eq!((p.rise_delay, p.fall_delay), (2e-9, 1e-9));
+    assert!(p.accepts_terminals(4));
+    assert!(!p.accepts_terminals(2));
+    assert!(p.gate_output(&[true, false]));
+    assert!(!p.gate_output(&[true, true]));
+    assert!(build_digital_params(&params(&[("type", "nmos")])).is_none());
+}
+
+#[test]
+fn adc_bridge_holds_level_inside_hysteresis_band() {
+    let adc = build_digital_params(&params(&[("type", "adc_bridge"), ("in_low", "0.8"), ("in_high", "2")])).unwrap();
+    assert!(!adc.adc_level(1.5, false));
+    assert!(adc.adc_level(1.5, true));
+    assert!(adc.adc_level(2.0, false));
+    assert!(!adc.adc_level(0.8, true));
+
+    let dac = build_digital_params(&params(&[("type", "dac_bridge"), ("out_high", "3.3")])).unwrap();
+    assert_eq!((dac.dac_voltage(false), dac.dac_voltage(true)), (0.0, 3.3));
+}
@@ -1,4 +1,124 @@
+use std::collections::HashMap;
+
+use sim_devices::diode::{build_diode_params, evaluate_diode};
+use sim_devices::junction::{depletion_capacitance, depletion_charge};
+
+fn model(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn diode_params_read_aliases_and_area() {
+    let p = build_diode_params(&model(&[("cj0", "2p"), ("pb", "0.8"), ("rs", "10"), ("tnom", "25")])).with_area(2.0);
+    assert!((p.cjo - 4e-12).abs() < 1e-24);
+    assert!((p.vj - 0.8).abs() < 1e-12);
+    assert!((p.rs - 5.0).abs() < 1e-12);
+    assert!((p.is - 2e-14).abs() < 1e-26);
+    assert!((p.tnom - 298.15).abs() < 1e-9);
+    assert_eq!(p.bv, None);
+}
+
+#[test]
+fn breakdown_current_equals_ibv_at_bv() {
+    let p = build_diode_params(&model(&[("bv", "5.1"), ("ibv", "2m")]));
+    let vt = 0.02585;
+    let out = evaluate_diode(&p, -5.1, vt);
+    assert!((out.id + 2e-3).abs() < 1e-12, "id = {}", out.id);
+    // 击穿区导数与有限差分一致
+    let h = 1e-7;
+    let fd = (evaluate_diode(&p, -5.1 + h, vt).id - evaluate_diode(&p, -5.1 - h, vt).id) / (2.0 * h);
+    assert!((out.gd - fd).abs() < 1e-6 * fd.abs(), "gd = {}, fd = {}", out.gd, fd);
+    // 击穿电压以内只有饱和电流
+    assert!((evaluate_diode(&p, -3.0, vt).id + p.is).abs() < 1e-20);
+}
+
+#[test]
+fn saturation_current_follows_eg_xti_temperature_law() {
+    let p = build_diode_params(&model(&[("is", "1e-14"), ("eg", "1.11"), ("xti", "3")]));
+    assert_eq!(p.clone().at_temperature(p.tnom).is, 1e-14);
+    let hot = p.clone().at_temperature(400.0);
+    let k: f64 = 8.617333262e-5;
+    let expected = 1e-14 * (400.0f64 / 300.15).powi(3) * (1.11 / k * (1.0 / 300.15 - 1.0 / 400.0)).exp();
+    assert!((hot.is - expected).abs() < 1e-9 * expected, "is = {}", hot.is);
+    assert!(p.at_temperature(250.0).is < 1e-14);
+}
+
+#[test]
+fn depletion_charge_integrates_capacitance() {
+    let h = 1e-6;
+    for (m, v) in [(0.5, -2.0), (0.33, 0.2), (0.5, 0.8), (1.0, -1.0)] {
+        let fd = (depletion_charge(1e-12, v + h, 1.0, m, 0.5) - depletion_charge(1e-12, v - h, 1.0, m, 0.5)) / (2.0 * h);
+        let c = depletion_capacitance(1e-12, v, 1.0, m, 0.5);
+        assert!((fd - c).abs() < 1e-6 * c, "m = {}, v = {}: dq/dv = {}, c = {}", m, v, fd, c);
+    }
+    assert_eq!(depletion_charge(1e-12, 0.0, 1.0, 0.5, 0.5), 0.0);
+}
+
+#[test]
+fn diode_charge_adds_transit_time_term() {
+    let p = build_diode_params(&model(&[("cjo", "1p"), ("tt", "10n")]));
+    let vt = 0.02585;
+    let out = evaluate_diode(&p, 0.6, vt);
+    let (q, c) = p.charge(0.6, &out);
+    let expected_q = depletion_charge(1e-12, 0.6, 1.0, 0.5, 0.5) + 10e-9 * out.id;
+    assert!((q - expected_q).abs() < 1e-24);
+    assert!((c - depletion_capacitance(1e-12, 0.6, 1.0, 0.5, 0.5) - 10e-9 * out.gd).abs() < 1e-24);
+}
+
+#[test]
+fn breakdown_characteristic_is_smooth_through_the_knee() {
+    let p = build_diode_params(&model(&[("bv", "5.1"), ("ibv", "1m"), ("nbv", "2")]));
+    let vt = 0.02585;
+    let xbv = p.breakdown_voltage(vt).unwrap();
+    assert!((xbv - (5.1 - 2.0 * vt * (1e-3f64 / 1e-14).ln())).abs() < 1e-12);
+    assert_eq!(evaluate_diode(&p, 0.0, vt).id, 0.0);
+    let h = 1e-7;
+    for vd in [-xbv - 0.05, -xbv, -xbv + 0.05, -5.1, 0.3] {
+        let out = evaluate_diode(&p, vd, vt);
+        let fd = (evaluate_diode(&p, vd + h, vt).id - evaluate_diode(&p, vd - h, vt).id) / (2.0 * h);
+        assert!((out.gd - fd).abs() < 1e-5 * fd.abs(), "vd = {}: gd = {}, fd = {}", vd, out.gd, fd);
+    }
+}
+
+#[test]
+fn limiting_compresses_steps_into_breakdown() {
+    let p = build_diode_params(&model(&[("bv", "5.1"), ("ibv", "1m")]));
+    let vt = 0.02585;
+    let xbv = p.breakdown_voltage(vt).unwrap();
+    // 从 0V 一步跳到 -15V: 只进入击穿拐点附近
+    let v = p.limit_voltage(-15.0, 0.0, vt);
+    assert!(v < -xbv && v > -xbv - 0.5, "v = {}", v);
+    // 反向但未击穿的小步长不受影响, 正向仍按 pnjlim 限幅
+    assert_eq!(p.limit_voltage(-2.0, -1.9, vt), -2.0);
+    assert!(p.limit_voltage(5.0, 0.6, vt) < 1.0);
+}
+
+#[test]
+fn varactor_has_capacitance_but_no_dc_current() {
+    let p = build_diode_params(&model(&[("type", "varactor"), ("cjo", "10p"), ("vj", "0.7"), ("mj", "0.33")]));
+    assert!(p.varactor);
+    let vt = 0.02585;
+    for vd in [-5.0, 0.0, 0.8] {
+        let out = evaluate_diode(&p, vd, vt);
+        assert_eq!((out.id, out.gd), (0.0, 0.0));
+        assert_eq!(p.limit_voltage(vd, 0.0, vt), vd);
+    }
+    let (_, c) = p.charge(-2.1, &evaluate_diode(&p, -2.1, vt));
+    assert!((c - 10e-12 / 4.0f64.powf(0.33)).abs() < 1e-24, "c = {}", c);
+}
+
 #[test]
-fn diode_module_placeholder() {
-    assert!(true);
+fn optical_parameters_scale_with_area() {
+    let p = build_diode_params(&model(&[("iph", "1u"), ("resp", "0.5"), ("eta", "0.2")])).with_area(2.0);
+    // 光电流 = IPH + RESP * P, 面积加倍时收集的光加倍
+    assert!((p.photocurrent(1e-3) - (2e-6 + 1e-3)).abs() < 1e-15);
+    assert!((p.optical_power(10e-3) - 2e-3).abs() < 1e-15);
+    // 反向电流不发光
+    assert_eq!(p.optical_power(-1e-6), 0.0);
+
+    let plain = build_diode_params(&model(&[]));
+    assert_eq!(plain.photocurrent(0.0), 0.0);
 }
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use sim_devices::jfet::{build_jfet_params, evaluate_jfet, JfetType};
+use sim_devices::junction::depletion_capacitance;
+use sim_devices::mesfet::{build_mesfet_params, evaluate_mesfet, MesfetLevel};
+
+#[test]
+fn jfet_params_read_model_card() {
+    let mut params = HashMap::new();
+    params.insert("type".to_string(), "pjf".to_string());
+    params.insert("beta".to_string(), "2m".to_string());
+    params.insert("rs".to_string(), "20".to_string());
+    let p = build_jfet_params(&params).with_area(2.0);
+    assert_eq!(p.jfet_type, JfetType::Pjf);
+    assert!((p.beta - 4e-3).abs() < 1e-15);
+    assert!((p.rs - 10.0).abs() < 1e-12);
+    assert!((p.vto + 2.0).abs() < 1e-12);
+}
+
+#[test]
+fn jfet_derivatives_match_finite_differences_in_all_regions() {
+    let mut params = HashMap::new();
+    params.insert("beta".to_string(), "1m".to_string());
+    params.insert("lambda".to_string(), "0.05".to_string());
+    let p = build_jfet_params(&params);
+    let vt = 0.02585;
+    let h = 1e-7;
+    // 饱和区、线性区、反向工作
+    for (vgs, vgd) in [(-1.0, -6.0), (-0.5, -0.8), (-1.5, -0.2)] {
+        let out = evaluate_jfet(&p, vgs, vgd, vt);
+        let id = |dgs: f64, dgd: f64| evaluate_jfet(&p, vgs + dgs, vgd + dgd, vt).id;
+        let d_gs = (id(h, 0.0) - id(-h, 0.0)) / (2.0 * h);
+        let d_gd = (id(0.0, h) - id(0.0, -h)) / (2.0 * h);
+        for (analytic, numeric) in [(out.did_dvgs, d_gs), (out.did_dvgd, d_gd)] {
+            assert!(
+                (analytic - numeric).abs() <= 1e-5 * numeric.abs().max(1e-9),
+                "at ({}, {}): analytic {} vs numeric {}",
+                vgs,
+                vgd,
+                analytic,
+                numeric
+            );
+        }
+    }
+    // 反向工作时电流从源流向漏
+    assert!(evaluate_jfet(&p, -1.5, -0.2, vt).id < 0.0);
+    // 夹断
+    assert_eq!(evaluate_jfet(&p, -2.5, -5.0, vt).id, 0.0);
+}
+
+#[test]
+fn depletion_capacitance_is_continuous_at_forward_knee() {
+    let below = depletion_capacitance(1e-12, 0.5 - 1e-9, 1.0, 0.5, 0.5);
+    let above = depletion_capacitance(1e-12, 0.5 + 1e-9, 1.0, 0.5, 0.5);
+    assert!((below - above).abs() < 1e-20);
+    assert!((depletion_capacitance(1e-12, -3.0, 1.0, 0.5, 0.5) - 0.5e-12).abs() < 1e-24);
+}
+
+#[test]
+fn mesfet_statz_and_curtice_derivatives_match_finite_differences() {
+    let vt = 0.02585;
+    let h = 1e-7;
+    for level in ["1", "2"] {
+        let mut params = HashMap::new();
+        for (key, value) in [("level", level), ("beta", "10m"), ("alpha", "3"), ("b", "0.5"), ("lambda", "0.04")] {
+            params.insert(key.to_string(), value.to_string());
+        }
+        let p = build_mesfet_params(&params);
+        assert_eq!(p.level, if level == "1" { MesfetLevel::Statz } else { MesfetLevel::Curtice });
+        // 饱和区、膝点以下、反向工作
+        for (vgs, vgd) in [(-0.5, -3.0), (-0.5, -0.8), (-1.2, -0.9)] {
+            let out = evaluate_mesfet(&p, vgs, vgd, vt);
+            let id = |dgs: f64, dgd: f64| evaluate_mesfet(&p, vgs + dgs, vgd + dgd, vt).id;
+            let d_gs = (id(h, 0.0) - id(-h, 0.0)) / (2.0 * h);
+            let d_gd = (id(0.0, h) - id(0.0, -h)) / (2.0 * h);
+            for (analytic, numeric) in [(out.did_dvgs, d_gs), (out.did_dvgd, d_gd)] {
+                assert!(
+                    (analytic - numeric).abs() <= 1e-5 * numeric.abs().max(1e-9),
+                    "level {} at ({}, {}): analytic {} vs numeric {}",
+                    level,
+                    vgs,
+                    vgd,
+                    analytic,
+                    numeric
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn mesfet_statz_saturates_at_three_over_alpha() {
+    let mut params = HashMap::new();
+    params.insert("type".to_string(), "nmf".to_string());
+    let p = build_mesfet_params(&params);
+    assert!((p.fet.beta - 2.5e-3).abs() < 1e-15);
+    // 默认 ALPHA=2: Vds >= 1.5V 后电流不再随 Vds 变化 (LAMBDA=0)
+    let sat = evaluate_mesfet(&p, 0.0, -1.5, 0.02585).id;
+    let deep = evaluate_mesfet(&p, 0.0, -4.0, 0.02585).id;
+    let expected = 2.5e-3 * 4.0 / (1.0 + 0.3 * 2.0);
+    assert!((sat - expected).abs() < 1e-15 && (deep - expected).abs() < 1e-15, "sat {} deep {}", sat, deep);
+    assert!(evaluate_mesfet(&p, 0.0, -0.5, 0.02585).id < 0.99 * expected);
+}
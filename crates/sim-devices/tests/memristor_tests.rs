@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use sim_devices::memristor::{build_memristor_params, MemristorParams, MemristorWindow};
+
+fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+/// k = UV * RON / D^2 = 1e4 /(A s)
+fn memristor() -> MemristorParams {
+    build_memristor_params(&params(&[
+        ("ron", "100"),
+        ("roff", "10k"),
+        ("d", "10n"),
+        ("uv", "1e-14"),
+        ("x0", "0.5"),
+    ]))
+}
+
+#[test]
+fn memristor_params_and_resistance() {
+    let p = build_memristor_params(&params(&[("ron", "50"), ("window", "Joglekar"), ("x0", "2")]));
+    assert_eq!(p.window, MemristorWindow::Joglekar);
+    assert_eq!(p.x0, 1.0);
+    assert_eq!(p.resistance(1.0), 50.0);
+    assert_eq!(p.resistance(0.0), 16e3);
+    assert_eq!(build_memristor_params(&HashMap::new()).window, MemristorWindow::Biolek);
+}
+
+#[test]
+fn biolek_window_lets_the_state_leave_a_boundary() {
+    let p = memristor();
+    // 正电流推向 x = 1 并在边界处停下; 反向电流可以离开边界
+    assert_eq!(p.window(1.0, 1e-3).0, 0.0);
+    assert_eq!(p.window(1.0, -1e-3).0, 1.0);
+    assert_eq!(p.window(0.0, -1e-3).0, 0.0);
+    let joglekar = MemristorParams { window: MemristorWindow::Joglekar, ..memristor() };
+    assert_eq!(joglekar.window(1.0, -1e-3).0, 0.0);
+    assert_eq!(joglekar.window(0.5, 1e-3), (1.0, 0.0));
+}
+
+#[test]
+fn memristor_step_drifts_with_current_and_stays_bounded() {
+    let p = memristor();
+    let up = p.step(0.5, 1.0, 1e-3);
+    assert!(up.x > 0.5 && up.x < 1.0, "x = {}", up.x);
+    assert!((up.current - 1.0 / p.resistance(up.x)).abs() < 1e-15);
+    let down = p.step(0.5, -1.0, 1e-3);
+    assert!(down.x < 0.5, "x = {}", down.x);
+    // 很大的步长也不会越过边界
+    assert!(p.step(0.5, 10.0, 1.0).x <= 1.0);
+    assert!(p.step(0.5, -10.0, 1.0).x >= 0.0);
+}
+
+#[test]
+fn memristor_conductance_matches_finite_difference() {
+    let p = memristor();
+    let (v, dt, h) = (0.8, 1e-4, 1e-6);
+    let g = p.step(0.3, v, dt).conductance;
+    let numeric = (p.step(0.3, v + h, dt).current - p.step(0.3, v - h, dt).current) / (2.0 * h);
+    assert!((g - numeric).abs() < 1e-6 * numeric.abs(), "g = {}, numeric = {}", g, numeric);
+    // 状态的响应使电流随电压的增长快于 1/R
+    assert!(g > 1.0 / p.resistance(p.step(0.3, v, dt).x));
+}
+
+#[test]
+fn memristor_shows_pinched_hysteresis() {
+    let p = memristor();
+    // 半个周期内流过的电荷使 x 变化约 0.6
+    let period = 1.0;
+    let steps = 2400;
+    let dt = period / steps as f64;
+    let mut x = p.x0;
+    let mut trace = Vec::new();
+    for n in 1..=steps {
+        let v = (2.0 * std::f64::consts::PI * n as f64 / steps as f64).sin();
+        let out = p.step(x, v, dt);
+        x = out.x;
+        trace.push((v, out.current));
+    }
+    // 过零点电流为零 (回线在原点收缩)
+    let (v_half, i_half) = trace[steps / 2 - 1];
+    assert!(v_half.abs() < 1e-12 && i_half.abs() < 1e-12);
+    // 同一电压 0.5V 上升沿与下降沿电流不同: 下降沿时电阻已经变小
+    let rising = trace[steps / 12 - 1];
+    let falling = trace[5 * steps / 12 - 1];
+    assert!((rising.0 - falling.0).abs() < 1e-9);
+    assert!(falling.1 > 1.5 * rising.1, "rising {:?}, falling {:?}", rising, falling);
+}
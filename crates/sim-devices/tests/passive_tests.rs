@@ -1,4 +1,55 @@
+use std::collections::HashMap;
+
+use sim_devices::passive::{build_capacitor_params, build_resistor_params};
+
+fn params(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn resistor_instance_value_wins_over_geometry() {
+    let p = build_resistor_params(&params(&[("rsh", "100"), ("l", "10u"), ("w", "1u")]));
+    assert_eq!(p.nominal(Some(47.0)), Some(47.0));
+    assert!((p.nominal(None).unwrap() - 1000.0).abs() < 1e-9);
+}
+
+#[test]
+fn semiconductor_resistor_uses_narrow_short_and_defw() {
+    let p = build_resistor_params(&params(&[
+        ("rsh", "50"),
+        ("l", "20u"),
+        ("narrow", "0.2u"),
+        ("short", "1u"),
+        ("defw", "2u"),
+    ]));
+    // R = RSH * (L - SHORT) / (DEFW - NARROW)
+    let expected = 50.0 * 19e-6 / 1.8e-6;
+    assert!((p.nominal(None).unwrap() - expected).abs() < 1e-9 * expected);
+    // 没有 L 时退回模型 R; 都没有时无法确定阻值
+    let fallback = build_resistor_params(&params(&[("rsh", "50"), ("r", "2k")]));
+    assert_eq!(fallback.nominal(None), Some(2000.0));
+    assert_eq!(build_resistor_params(&HashMap::new()).nominal(None), None);
+}
+
+#[test]
+fn resistor_temperature_coefficients() {
+    let p = build_resistor_params(&params(&[("tc1", "1e-3"), ("tc2", "1e-5"), ("tnom", "25")]));
+    assert_eq!(p.at_temperature(1e3, 298.15), 1e3);
+    // dT = 100K: 1 + 0.1 + 0.1
+    assert!((p.at_temperature(1e3, 398.15) - 1.2e3).abs() < 1e-9);
+}
+
 #[test]
-fn passive_module_placeholder() {
-    assert!(true);
+fn layout_capacitor_from_area_and_perimeter() {
+    let p = build_capacitor_params(&params(&[("cj", "1e-3"), ("cjsw", "1e-10"), ("l", "5u"), ("w", "5u")]));
+    let expected = 1e-3 * 25e-12 + 2.0 * 1e-10 * 10e-6;
+    assert!((p.nominal(None).unwrap() - expected).abs() < 1e-9 * expected);
+    assert_eq!(p.nominal(Some(1e-12)), Some(1e-12));
+    // NARROW / SHORT 扣除, 未给 W 时取 DEFW
+    let q = build_capacitor_params(&params(&[("cj", "1e-3"), ("l", "6u"), ("short", "1u"), ("narrow", "1u"), ("defw", "6u")]));
+    assert!((q.nominal(None).unwrap() - 25e-15).abs() < 1e-24);
+    assert_eq!(build_capacitor_params(&params(&[("c", "3p")])).nominal(None), Some(3e-12));
 }
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use sim_devices::bsim::MosRegion;
+use sim_devices::power::{
+    build_igbt_params, build_vdmos_params, is_igbt, is_vdmos, limit_vds, MillerCapacitance, PowerChannel,
+};
+
+fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn channel_is_square_law_with_smooth_threshold_tail() {
+    let hard = PowerChannel { vto: 3.0, kp: 2.0, lambda: 0.0, ksubthres: 0.0 };
+    // 饱和区 KP/2 Vov², 线性区 KP (Vov Vds - Vds²/2)
+    let sat = hard.evaluate(5.0, 10.0);
+    assert_eq!(sat.region, MosRegion::Saturation);
+    assert!((sat.id - 4.0).abs() < 1e-12);
+    let lin = hard.evaluate(5.0, 1.0);
+    assert_eq!(lin.region, MosRegion::Linear);
+    assert!((lin.id - 3.0).abs() < 1e-12);
+    assert_eq!(hard.evaluate(2.5, 10.0).id, 0.0);
+
+    // 阈值以下按 exp((Vgs - VTO) / KSUBTHRES) 衰减, 远高于阈值时回到平方律
+    let soft = PowerChannel { ksubthres: 0.1, ..hard };
+    let (i1, i2) = (soft.evaluate(2.5, 10.0).id, soft.evaluate(2.4, 10.0).id);
+    assert!(i1 > 0.0 && i2 > 0.0);
+    assert!(((i1 / i2).ln() - 2.0).abs() < 0.01, "ratio = {}", i1 / i2);
+    assert!((soft.evaluate(8.0, 10.0).id - 25.0).abs() < 1e-9);
+
+    // 反向 Vds: 源漏互换, 导数与差分一致
+    let h = 1e-6;
+    for (vgs, vds) in [(5.0, -1.0), (5.0, 0.5), (2.9, -0.3)] {
+        let out = soft.evaluate(vgs, vds);
+        let gm = (soft.evaluate(vgs + h, vds).id - soft.evaluate(vgs - h, vds).id) / (2.0 * h);
+        let gds = (soft.evaluate(vgs, vds + h).id - soft.evaluate(vgs, vds - h).id) / (2.0 * h);
+        assert!((out.gm - gm).abs() < 1e-6, "gm {} vs {}", out.gm, gm);
+        assert!((out.gds - gds).abs() < 1e-6, "gds {} vs {}", out.gds, gds);
+    }
+    assert!((soft.evaluate(5.0, -1.0).id + soft.evaluate(6.0, 1.0).id).abs() < 1e-12);
+}
+
+#[test]
+fn vds_limiting_bounds_newton_steps_in_both_directions() {
+    assert_eq!(limit_vds(100.0, 0.0), 4.0);
+    assert_eq!(limit_vds(100.0, 10.0), 32.0);
+    assert_eq!(limit_vds(1.0, 10.0), 2.0);
+    assert_eq!(limit_vds(-100.0, 0.0), -4.0);
+    assert_eq!(limit_vds(0.3, 0.2), 0.3);
+}
+
+#[test]
+fn miller_capacitance_is_charge_conserving() {
+    let miller = MillerCapacitance { cgdmin: 10e-12, cgdmax: 1e-9, a: 0.5 };
+    assert!((miller.capacitance(-50.0) - 1e-9).abs() < 1e-15);
+    assert!((miller.capacitance(50.0) - 10e-12).abs() < 1e-15);
+    assert_eq!(miller.charge(0.0), 0.0);
+    let h = 1e-4;
+    for vdg in [-20.0, -1.0, 0.0, 2.0, 400.0] {
+        let c = (miller.charge(vdg + h) - miller.charge(vdg - h)) / (2.0 * h);
+        assert!((c - miller.capacitance(vdg)).abs() < 1e-6 * miller.cgdmax, "Vdg = {}", vdg);
+    }
+}
+
+#[test]
+fn vdmos_and_igbt_currents_satisfy_kcl() {
+    let vdmos_params = params(&[("type", "vdmos"), ("vto", "3"), ("kp", "2"), ("is", "1e-12"), ("rd", "0.1")]);
+    assert!(is_vdmos(&vdmos_params) && !is_igbt(&vdmos_params));
+    let vdmos = build_vdmos_params(&vdmos_params);
+    assert_eq!(vdmos.rd, 0.1);
+    assert_eq!(vdmos.body.rs, 0.0);
+    let vt = 0.025852;
+    let [d, g, s] = vdmos.currents([1.0, 5.0, 0.0], vt);
+    assert!((d - 3.0).abs() < 1e-9 && g == 0.0 && (d + s).abs() < 1e-15);
+    // 关断时反向导通的是体二极管
+    let [d, _, _] = vdmos.currents([-0.6, 0.0, 0.0], vt);
+    assert!((d + 1e-12 * ((0.6 / vt).exp() - 1.0)).abs() < 1e-9 * d.abs());
+
+    let igbt_params = params(&[("type", "nigbt"), ("vto", "4"), ("kp", "1"), ("bf", "3"), ("tt", "1u")]);
+    assert!(is_igbt(&igbt_params));
+    let igbt = build_igbt_params(&igbt_params);
+    let v = [3.0, 15.0, 0.0, 2.3];
+    let i = igbt.currents(v, vt);
+    assert!(i.iter().sum::<f64>().abs() < 1e-12 * i[0].abs());
+    assert!(igbt.charges(v, vt).iter().sum::<f64>().abs() < 1e-18);
+    // 集电结电荷含扩散电荷 TT * I, 关断时形成拖尾
+    assert!(igbt.charges(v, vt)[0] >= 1e-6 * i[0]);
+}
@@ -1,4 +1,79 @@
+use std::collections::HashMap;
+
+use sim_devices::source::{build_port_params, build_source_waveform, parse_pulse, parse_pwl, PortParams, SourceWaveform};
+
+fn params(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn port_params_from_netlist() {
+    let p = build_port_params(&params(&[("port", "2"), ("z0", "75")]));
+    assert_eq!(p, PortParams { number: 2, z0: 75.0 });
+    // 缺省 Z0 = 50; 非正整数的端口号视为缺失
+    assert_eq!(build_port_params(&params(&[("port", "1")])).z0, 50.0);
+    assert_eq!(build_port_params(&params(&[("port", "1.5"), ("z0", "-1")])), PortParams::default());
+}
+
+#[test]
+fn pulse_value_and_breakpoints() {
+    let pulse = SourceWaveform::Pulse(parse_pulse("0 1 1n 1n 2n 3n 10n").unwrap());
+    let expected = [(0.5e-9, 0.0), (1.5e-9, 0.5), (3e-9, 1.0), (6e-9, 0.5), (8e-9, 0.0), (11.5e-9, 0.5)];
+    for (t, v) in expected {
+        assert!((pulse.value_at(t) - v).abs() < 1e-9, "t = {}", t);
+    }
+    // 转折点: TD, TD+TR, TD+TR+PW, TD+TR+PW+TF, 之后按周期重复
+    let mut time = 0.0;
+    let mut corners = Vec::new();
+    while let Some(t) = pulse.next_breakpoint(time).filter(|t| *t < 15e-9) {
+        corners.push(t);
+        time = t;
+    }
+    let expected = [1e-9, 2e-9, 5e-9, 7e-9, 11e-9, 12e-9];
+    assert_eq!(corners.len(), expected.len(), "{:?}", corners);
+    for (t, e) in corners.iter().zip(expected) {
+        assert!((t - e).abs() < 1e-18, "{:?}", corners);
+    }
+}
+
+#[test]
+fn pulse_defaults_to_a_single_step() {
+    let pulse = parse_pulse("0 5").unwrap();
+    let waveform = SourceWaveform::Pulse(pulse);
+    assert_eq!(waveform.value_at(0.0), 0.0);
+    assert_eq!(waveform.value_at(1.0), 5.0);
+    assert_eq!(waveform.next_breakpoint(0.0), None);
+    assert!(parse_pulse("0").is_none());
+    assert!(parse_pulse("0 1 -1n").is_none());
+}
+
+#[test]
+fn pwl_interpolates_and_holds_end_values() {
+    let pwl = build_source_waveform(&params(&[("pwl", "1n 0 2n 1 2n 3 4n 1")])).unwrap();
+    assert_eq!(pwl.value_at(0.0), 0.0);
+    assert!((pwl.value_at(1.5e-9) - 0.5).abs() < 1e-12);
+    // 同一时刻的两个点构成跳变, 转折点上取跳变前的值
+    assert!((pwl.value_at(2e-9) - 1.0).abs() < 1e-12);
+    assert!((pwl.value_at(3e-9) - 2.0).abs() < 1e-12);
+    assert_eq!(pwl.value_at(1.0), 1.0);
+    assert_eq!(pwl.next_breakpoint(1e-9), Some(2e-9));
+    assert_eq!(pwl.next_breakpoint(2e-9), Some(4e-9));
+    assert_eq!(pwl.next_breakpoint(4e-9), None);
+    assert!(parse_pwl("1n 0 2n").is_none());
+    assert!(parse_pwl("2n 0 1n 1").is_none());
+}
+
 #[test]
-fn source_module_placeholder() {
-    assert!(true);
+fn waveforms_report_when_they_stop_switching() {
+    let periodic = SourceWaveform::Pulse(parse_pulse("0 1 10n 1n 2n 3n 10n").unwrap());
+    assert_eq!(periodic.periodic_from(), 10e-9);
+    let single = SourceWaveform::Pulse(parse_pulse("0 1 10n 1n 2n 3n").unwrap());
+    assert!((single.periodic_from() - 16e-9).abs() < 1e-18);
+    let step = SourceWaveform::Pulse(parse_pulse("0 1 10n 1n").unwrap());
+    assert!((step.periodic_from() - 11e-9).abs() < 1e-18);
+    let pwl = build_source_waveform(&params(&[("pwl", "1n 0 2n 1 4n 1")])).unwrap();
+    assert_eq!(pwl.periodic_from(), 4e-9);
 }
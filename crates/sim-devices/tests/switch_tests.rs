@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use sim_devices::switch::{build_switch_params, SwitchControl};
+
+fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn switch_conductance_is_smooth_and_matches_derivative() {
+    let p = build_switch_params(
+        &params(&[("ron", "1"), ("roff", "1meg"), ("vt", "1"), ("vh", "-0.5")]),
+        SwitchControl::Voltage,
+    );
+    assert_eq!(p.conductance(0.4, None), (1e-6, 0.0));
+    assert_eq!(p.conductance(1.6, None), (1.0, 0.0));
+    // 过渡区中点为几何平均电阻
+    let (g_mid, _) = p.conductance(1.0, None);
+    assert!((g_mid - 1e-3).abs() < 1e-12, "g = {}", g_mid);
+    let h = 1e-7;
+    for vc in [0.6, 0.9, 1.2, 1.45] {
+        let (_, dg) = p.conductance(vc, None);
+        let numeric = (p.conductance(vc + h, None).0 - p.conductance(vc - h, None).0) / (2.0 * h);
+        assert!((dg - numeric).abs() <= 1e-5 * numeric.abs(), "vc {}: {} vs {}", vc, dg, numeric);
+    }
+}
+
+#[test]
+fn switch_hysteresis_depends_on_previous_state() {
+    let p = build_switch_params(
+        &params(&[("ron", "10"), ("roff", "1k"), ("vt", "2"), ("vh", "0.5")]),
+        SwitchControl::Voltage,
+    );
+    // 滞回区内保持原状态
+    assert_eq!(p.conductance(2.2, Some(false)).0, 1e-3);
+    assert_eq!(p.conductance(2.2, Some(true)).0, 0.1);
+    assert!(!p.next_state(2.2, Some(false)));
+    assert!(p.next_state(2.2, Some(true)));
+    assert!(p.next_state(2.6, Some(false)));
+    assert!(!p.next_state(1.4, Some(true)));
+    // 电流控制开关读取 IT/IH
+    let w = build_switch_params(&params(&[("it", "1m"), ("ih", "0.2m")]), SwitchControl::Current);
+    assert!((w.threshold - 1e-3).abs() < 1e-15 && (w.hysteresis - 2e-4).abs() < 1e-15);
+}
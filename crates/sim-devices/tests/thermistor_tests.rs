@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use sim_devices::thermistor::{build_thermistor_params, ThermistorLaw};
+
+fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn beta_law_gives_r0_at_t0_and_ntc_or_ptc_slope() {
+    let ntc = build_thermistor_params(&params(&[("r25", "10k"), ("beta", "3950")]));
+    let (r, dr) = ntc.evaluate(298.15);
+    assert!((r - 10e3).abs() < 1e-9);
+    assert!(dr < 0.0);
+    // 50 °C: R = R0 exp(B (1/T - 1/T0))
+    let expected = 10e3 * (3950.0_f64 * (1.0 / 323.15 - 1.0 / 298.15)).exp();
+    assert!((ntc.evaluate(323.15).0 - expected).abs() < 1e-9 * expected);
+
+    let ptc = build_thermistor_params(&params(&[("r0", "100"), ("t0", "0"), ("beta", "-1500")]));
+    let (r, dr) = ptc.evaluate(273.15);
+    assert!((r - 100.0).abs() < 1e-12);
+    assert!(dr > 0.0);
+}
+
+#[test]
+fn steinhart_hart_inverts_the_temperature_equation() {
+    // 典型 10k NTC 的系数
+    let (a, b, c) = (1.129148e-3, 2.34125e-4, 8.76741e-8);
+    let p = build_thermistor_params(&params(&[("a", "1.129148e-3"), ("b", "2.34125e-4"), ("c", "8.76741e-8")]));
+    assert!(matches!(p.law, ThermistorLaw::SteinhartHart { .. }));
+    for temp in [253.15, 298.15, 373.15] {
+        let (r, dr) = p.evaluate(temp);
+        let y = r.ln();
+        let inv_t = a + b * y + c * y * y * y;
+        assert!((inv_t * temp - 1.0).abs() < 1e-10, "T = {}, R = {}", temp, r);
+        // 与差分比较导数
+        let h = 1e-3;
+        let numeric = (p.evaluate(temp + h).0 - p.evaluate(temp - h).0) / (2.0 * h);
+        assert!((dr - numeric).abs() < 1e-6 * dr.abs(), "dR/dT = {}, numeric = {}", dr, numeric);
+    }
+    assert!((p.evaluate(298.15).0 - 10e3).abs() < 10.0);
+}
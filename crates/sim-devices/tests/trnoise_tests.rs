@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use sim_devices::trnoise::{build_trnoise_params, parse_trnoise, TrNoise};
+
+fn variance(values: &[f64]) -> f64 {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+}
+
+#[test]
+fn trnoise_white_samples_have_requested_rms() {
+    let mut noise = TrNoise::new(parse_trnoise("2m 1n", 11).unwrap());
+    let samples: Vec<f64> = (0..20000).map(|k| noise.sample(k)).collect();
+    let rms = variance(&samples).sqrt();
+    assert!((rms - 2e-3).abs() < 0.05e-3, "rms = {}", rms);
+    // 相邻采样不相关: 差分方差约为 2σ²
+    let diffs: Vec<f64> = samples.windows(2).map(|w| w[1] - w[0]).collect();
+    assert!((variance(&diffs) / variance(&samples) - 2.0).abs() < 0.1);
+}
+
+#[test]
+fn trnoise_flicker_samples_are_correlated() {
+    let mut noise = TrNoise::new(parse_trnoise("0 1n 1 1m", 5).unwrap());
+    let samples: Vec<f64> = (0..20000).map(|k| noise.sample(k)).collect();
+    let diffs: Vec<f64> = samples.windows(2).map(|w| w[1] - w[0]).collect();
+    // 1/f 噪声集中在低频, 相邻采样之差远小于白噪声的 2σ²
+    assert!(variance(&diffs) / variance(&samples) < 0.5);
+}
+
+#[test]
+fn trnoise_holds_samples_and_finds_next_sample_time() {
+    let mut noise = TrNoise::new(parse_trnoise("1 1n", 3).unwrap());
+    assert_eq!(noise.value_at(0.0), 0.0);
+    let first = noise.sample(0);
+    assert_eq!(noise.value_at(0.5e-9), first);
+    assert_eq!(noise.value_at(1e-9), first);
+    assert_eq!(noise.value_at(1.5e-9), noise.sample(1));
+    assert_eq!(noise.next_sample_time(0.0), 1e-9);
+    assert!((noise.next_sample_time(3e-9) - 4e-9).abs() < 1e-20);
+
+    let params: HashMap<String, String> = [("trnoise".to_string(), "1m 1n".to_string())].into_iter().collect();
+    let a = build_trnoise_params("V1", &params).unwrap();
+    let b = build_trnoise_params("v1", &params).unwrap();
+    let c = build_trnoise_params("V2", &params).unwrap();
+    assert_eq!(a.seed, b.seed);
+    assert_ne!(a.seed, c.seed);
+    assert!(parse_trnoise("1m 0", 0).is_none());
+}